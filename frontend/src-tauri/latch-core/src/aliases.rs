@@ -0,0 +1,440 @@
+//! Email aliases from SimpleLogin, Firefox Relay or addy.io, so a new Login can be
+//! given an address of its own instead of the user's real one. The API key comes
+//! from the frontend, which keeps it in the OS keychain; it is never written to
+//! disk here.
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::Url;
+
+/// Pages fetched at most when listing, so a runaway account can't stall the app.
+const MAX_PAGES: u32 = 50;
+const ADDY_PAGE_SIZE: usize = 100;
+const ADDY_DOMAIN: &str = "anonaddy.me";
+/// Returned when the service refuses the API key.
+pub const KEY_REFUSED: &str = "The alias service refused the API key; check it in Settings";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasService {
+    SimpleLogin,
+    FirefoxRelay,
+    Addy,
+}
+
+impl AliasService {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            AliasService::SimpleLogin => "https://app.simplelogin.io",
+            AliasService::FirefoxRelay => "https://relay.firefox.com",
+            AliasService::Addy => "https://app.addy.io",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AliasService::SimpleLogin => "SimpleLogin",
+            AliasService::FirefoxRelay => "Firefox Relay",
+            AliasService::Addy => "addy.io",
+        }
+    }
+}
+
+/// Which service to use and how to sign in to it.
+#[derive(Clone, Deserialize, Type)]
+pub struct AliasAccount {
+    pub service: AliasService,
+    pub api_key: String,
+    /// For a self-hosted SimpleLogin or addy.io; the public service otherwise.
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct Alias {
+    pub id: String,
+    pub email: String,
+    /// Whether mail sent to the alias is still forwarded.
+    pub enabled: bool,
+    /// What the alias was made for, as the service has it.
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SimpleLoginAlias {
+    id: u64,
+    email: String,
+    enabled: bool,
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SimpleLoginPage {
+    aliases: Vec<SimpleLoginAlias>,
+}
+
+#[derive(Deserialize)]
+struct RelayAddress {
+    id: u64,
+    full_address: String,
+    enabled: bool,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct AddyAlias {
+    id: String,
+    email: String,
+    active: bool,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddyDomainOptions {
+    default_alias_domain: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddyData<T> {
+    data: T,
+}
+
+impl From<SimpleLoginAlias> for Alias {
+    fn from(alias: SimpleLoginAlias) -> Self {
+        Alias {
+            id: alias.id.to_string(),
+            email: alias.email,
+            enabled: alias.enabled,
+            note: alias.note.filter(|n| !n.is_empty()),
+        }
+    }
+}
+
+impl From<RelayAddress> for Alias {
+    fn from(address: RelayAddress) -> Self {
+        Alias {
+            id: address.id.to_string(),
+            email: address.full_address,
+            enabled: address.enabled,
+            note: Some(address.description).filter(|d| !d.is_empty()),
+        }
+    }
+}
+
+impl From<AddyAlias> for Alias {
+    fn from(alias: AddyAlias) -> Self {
+        Alias {
+            id: alias.id,
+            email: alias.email,
+            enabled: alias.active,
+            note: alias.description.filter(|d| !d.is_empty()),
+        }
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(service: AliasService, body: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(body)
+        .map_err(|e| format!("Failed to parse {} response: {}", service.name(), e))
+}
+
+/// The alias in a create (or, for SimpleLogin, fetch) response.
+fn parse_alias(service: AliasService, body: &[u8]) -> Result<Alias, String> {
+    Ok(match service {
+        AliasService::SimpleLogin => parse::<SimpleLoginAlias>(service, body)?.into(),
+        AliasService::FirefoxRelay => parse::<RelayAddress>(service, body)?.into(),
+        AliasService::Addy => parse::<AddyData<AddyAlias>>(service, body)?.data.into(),
+    })
+}
+
+/// One page of a list response.
+fn parse_aliases(service: AliasService, body: &[u8]) -> Result<Vec<Alias>, String> {
+    Ok(match service {
+        AliasService::SimpleLogin => parse::<SimpleLoginPage>(service, body)?
+            .aliases
+            .into_iter()
+            .map(Alias::from)
+            .collect(),
+        AliasService::FirefoxRelay => parse::<Vec<RelayAddress>>(service, body)?
+            .into_iter()
+            .map(Alias::from)
+            .collect(),
+        AliasService::Addy => parse::<AddyData<Vec<AddyAlias>>>(service, body)?
+            .data
+            .into_iter()
+            .map(Alias::from)
+            .collect(),
+    })
+}
+
+/// Ids end up in request paths, so only what the services hand out is let through:
+/// numbers, or addy.io's UUIDs.
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err("Invalid alias id".to_string());
+    }
+    Ok(())
+}
+
+pub struct AliasClient {
+    service: AliasService,
+    api_key: String,
+    base: Url,
+    client: reqwest::Client,
+}
+
+impl AliasClient {
+    pub fn new(account: AliasAccount) -> Result<Self, String> {
+        if account.api_key.trim().is_empty() {
+            return Err("Invalid API key: it is empty".to_string());
+        }
+        let base = account
+            .base_url
+            .as_deref()
+            .filter(|u| !u.is_empty())
+            .unwrap_or(account.service.default_base_url());
+        let base = Url::parse(base).map_err(|_| "Invalid alias service URL".to_string())?;
+        if !matches!(base.scheme(), "https" | "http") || base.host_str().is_none() {
+            return Err("Invalid alias service URL: use an http(s) URL".to_string());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to start alias client: {}", e))?;
+        Ok(Self {
+            service: account.service,
+            api_key: account.api_key.trim().to_string(),
+            base,
+            client,
+        })
+    }
+
+    fn url(&self, path: &str) -> Result<Url, String> {
+        self.base
+            .join(path)
+            .map_err(|_| "Invalid alias service URL".to_string())
+    }
+
+    fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, url)
+            .header(reqwest::header::ACCEPT, "application/json");
+        match self.service {
+            AliasService::SimpleLogin => request.header("Authentication", &self.api_key),
+            AliasService::FirefoxRelay => request.header(
+                reqwest::header::AUTHORIZATION,
+                format!("Token {}", self.api_key),
+            ),
+            AliasService::Addy => request
+                .bearer_auth(&self.api_key)
+                .header("X-Requested-With", "XMLHttpRequest"),
+        }
+    }
+
+    fn with_json(
+        request: reqwest::RequestBuilder,
+        body: serde_json::Value,
+    ) -> reqwest::RequestBuilder {
+        request
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Vec<u8>, String> {
+        let name = self.service.name();
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach {}: {}", name, e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(KEY_REFUSED.to_string());
+        }
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("Alias not found on {}", name));
+        }
+        if !status.is_success() {
+            return Err(format!("{} refused the request ({})", name, status));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read from {}: {}", name, e))?;
+        Ok(body.to_vec())
+    }
+
+    /// The domain the user picked as default on addy.io, which differs on a
+    /// self-hosted instance.
+    async fn addy_domain(&self) -> Result<String, String> {
+        let url = self.url("/api/v1/domain-options")?;
+        let body = self.send(self.request(reqwest::Method::GET, url)).await?;
+        Ok(parse::<AddyDomainOptions>(self.service, &body)?
+            .default_alias_domain
+            .unwrap_or_else(|| ADDY_DOMAIN.to_string()))
+    }
+
+    /// Mints a new random alias, noting the site it is for when given.
+    pub async fn create(&self, for_site: Option<&str>) -> Result<Alias, String> {
+        let note = for_site.unwrap_or_default();
+        let request = match self.service {
+            AliasService::SimpleLogin => {
+                let mut url = self.url("/api/alias/random/new")?;
+                if !note.is_empty() {
+                    url.query_pairs_mut().append_pair("hostname", note);
+                }
+                Self::with_json(
+                    self.request(reqwest::Method::POST, url),
+                    serde_json::json!({ "note": note }),
+                )
+            }
+            AliasService::FirefoxRelay => Self::with_json(
+                self.request(reqwest::Method::POST, self.url("/api/v1/relayaddresses/")?),
+                serde_json::json!({
+                    "enabled": true,
+                    "description": note,
+                    "generated_for": note,
+                }),
+            ),
+            AliasService::Addy => Self::with_json(
+                self.request(reqwest::Method::POST, self.url("/api/v1/aliases")?),
+                serde_json::json!({
+                    "domain": self.addy_domain().await?,
+                    "format": "random_characters",
+                    "description": note,
+                }),
+            ),
+        };
+        parse_alias(self.service, &self.send(request).await?)
+    }
+
+    pub async fn list(&self) -> Result<Vec<Alias>, String> {
+        let mut aliases = Vec::new();
+        for page in 0..MAX_PAGES {
+            let url = match self.service {
+                AliasService::FirefoxRelay => self.url("/api/v1/relayaddresses/")?,
+                AliasService::SimpleLogin => {
+                    let mut url = self.url("/api/v2/aliases")?;
+                    url.query_pairs_mut()
+                        .append_pair("page_id", &page.to_string());
+                    url
+                }
+                AliasService::Addy => {
+                    let mut url = self.url("/api/v1/aliases")?;
+                    url.query_pairs_mut()
+                        .append_pair("page[number]", &(page + 1).to_string())
+                        .append_pair("page[size]", &ADDY_PAGE_SIZE.to_string());
+                    url
+                }
+            };
+            let body = self.send(self.request(reqwest::Method::GET, url)).await?;
+            let found = parse_aliases(self.service, &body)?;
+            let last = match self.service {
+                AliasService::FirefoxRelay => true,
+                AliasService::SimpleLogin => found.is_empty(),
+                AliasService::Addy => found.len() < ADDY_PAGE_SIZE,
+            };
+            aliases.extend(found);
+            if last {
+                break;
+            }
+        }
+        Ok(aliases)
+    }
+
+    /// Stops forwarding mail sent to the alias. The address itself stays with the
+    /// account, so it can be turned back on from the service's own site.
+    pub async fn disable(&self, id: &str) -> Result<(), String> {
+        check_id(id)?;
+        match self.service {
+            AliasService::SimpleLogin => {
+                // The API only toggles, so look first to avoid turning it back on.
+                let url = self.url(&format!("/api/aliases/{}", id))?;
+                let body = self.send(self.request(reqwest::Method::GET, url)).await?;
+                if parse_alias(self.service, &body)?.enabled {
+                    let url = self.url(&format!("/api/aliases/{}/toggle", id))?;
+                    self.send(self.request(reqwest::Method::POST, url)).await?;
+                }
+            }
+            AliasService::FirefoxRelay => {
+                let url = self.url(&format!("/api/v1/relayaddresses/{}/", id))?;
+                self.send(Self::with_json(
+                    self.request(reqwest::Method::PATCH, url),
+                    serde_json::json!({ "enabled": false }),
+                ))
+                .await?;
+            }
+            AliasService::Addy => {
+                let url = self.url(&format!("/api/v1/active-aliases/{}", id))?;
+                self.send(self.request(reqwest::Method::DELETE, url))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_each_services_aliases() {
+        let simple_login = br#"{"aliases": [
+            {"id": 7, "email": "shop.x1@simplelogin.com", "enabled": true, "note": "shop.example", "nb_forward": 3}
+        ]}"#;
+        let relay = br#"[
+            {"id": 12, "full_address": "abc123@mozmail.com", "enabled": false, "description": "", "domain": 2}
+        ]"#;
+        let addy = br#"{"data": {"id": "50c9e585-e7f5-41c4-9016-9014c15454bc", "email": "q8x@anonaddy.me", "active": true, "description": null}}"#;
+
+        assert_eq!(
+            parse_aliases(AliasService::SimpleLogin, simple_login).unwrap(),
+            vec![Alias {
+                id: "7".to_string(),
+                email: "shop.x1@simplelogin.com".to_string(),
+                enabled: true,
+                note: Some("shop.example".to_string()),
+            }]
+        );
+        assert_eq!(
+            parse_aliases(AliasService::FirefoxRelay, relay).unwrap(),
+            vec![Alias {
+                id: "12".to_string(),
+                email: "abc123@mozmail.com".to_string(),
+                enabled: false,
+                note: None,
+            }]
+        );
+        let alias = parse_alias(AliasService::Addy, addy).unwrap();
+        assert_eq!(alias.id, "50c9e585-e7f5-41c4-9016-9014c15454bc");
+        assert!(alias.enabled);
+        assert!(parse_alias(AliasService::Addy, relay).is_err());
+    }
+
+    #[test]
+    fn checks_account_and_ids() {
+        let account = |base_url: Option<&str>| AliasAccount {
+            service: AliasService::Addy,
+            api_key: "key".to_string(),
+            base_url: base_url.map(str::to_string),
+        };
+        let client = AliasClient::new(account(Some("https://addy.home.example"))).unwrap();
+        assert_eq!(
+            client.url("/api/v1/aliases").unwrap().as_str(),
+            "https://addy.home.example/api/v1/aliases"
+        );
+        assert!(AliasClient::new(account(None)).is_ok());
+        assert!(AliasClient::new(account(Some("file:///etc"))).is_err());
+        assert!(AliasClient::new(AliasAccount {
+            api_key: " ".to_string(),
+            ..account(None)
+        })
+        .is_err());
+
+        assert!(check_id("50c9e585-e7f5-41c4-9016-9014c15454bc").is_ok());
+        assert!(check_id("12").is_ok());
+        assert!(check_id("../users").is_err());
+        assert!(check_id("").is_err());
+    }
+}
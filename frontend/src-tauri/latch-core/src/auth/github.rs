@@ -0,0 +1,155 @@
+//! Signing in with GitHub through the OAuth device flow: Latch shows a code, the
+//! user enters it on github.com, and Latch polls until GitHub hands over an
+//! access token. The token is only used to ask GitHub who signed in; the
+//! account's numeric id then goes through the same key derivation as a Google
+//! account's `sub`.
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::env;
+use std::time::Duration;
+
+const DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+const ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const USER_URL: &str = "https://api.github.com/user";
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// What the user needs to approve the sign-in, and what Latch polls with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// Entered by the user at `verification_uri`.
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u32,
+    /// Seconds to wait between polls.
+    pub interval: u32,
+}
+
+fn client_id() -> Result<String, String> {
+    env::var("LATCH_GITHUB_CLIENT_ID")
+        .ok()
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| "GitHub sign-in is not configured in this build".to_string())
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent("Latch")
+        .build()
+        .map_err(|e| format!("Failed to start GitHub sign-in: {}", e))
+}
+
+async fn post(url: &str, form: &[(&str, &str)]) -> Result<String, String> {
+    client()?
+        .post(url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(form)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("GitHub sign-in failed: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("GitHub sign-in failed: {}", e))
+}
+
+/// Asks GitHub for a code for the user to enter.
+pub async fn start() -> Result<DeviceCode, String> {
+    let client_id = client_id()?;
+    let body = post(
+        DEVICE_CODE_URL,
+        &[("client_id", &client_id), ("scope", "read:user")],
+    )
+    .await?;
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected reply from GitHub: {}", e))
+}
+
+/// GitHub's answer to a poll.
+#[derive(Debug, Deserialize)]
+struct TokenReply {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// The access token once the user has approved; None while GitHub is still
+/// waiting for them.
+fn token_from(body: &str) -> Result<Option<String>, String> {
+    let reply: TokenReply =
+        serde_json::from_str(body).map_err(|e| format!("Unexpected reply from GitHub: {}", e))?;
+    if let Some(token) = reply.access_token {
+        return Ok(Some(token));
+    }
+    match reply.error.as_deref() {
+        Some("authorization_pending" | "slow_down") => Ok(None),
+        Some("expired_token") => Err("The GitHub code expired; start again".to_string()),
+        Some("access_denied") => Err("GitHub sign-in was cancelled".to_string()),
+        Some(error) => Err(format!(
+            "GitHub sign-in failed: {}",
+            reply.error_description.as_deref().unwrap_or(error)
+        )),
+        None => Err("Unexpected reply from GitHub".to_string()),
+    }
+}
+
+/// Checks once whether the user has approved `device_code`.
+pub async fn poll(device_code: &str) -> Result<Option<String>, String> {
+    let client_id = client_id()?;
+    let body = post(
+        ACCESS_TOKEN_URL,
+        &[
+            ("client_id", &client_id),
+            ("device_code", device_code),
+            ("grant_type", DEVICE_GRANT),
+        ],
+    )
+    .await?;
+    token_from(&body)
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u64,
+}
+
+/// The numeric id of the account `access_token` belongs to, as GitHub reports
+/// it. Unlike the login it never changes.
+pub async fn user_id(access_token: &str) -> Result<String, String> {
+    let body = client()?
+        .get(USER_URL)
+        .bearer_auth(access_token)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to confirm the GitHub account: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to confirm the GitHub account: {}", e))?;
+    let user: User =
+        serde_json::from_str(&body).map_err(|e| format!("Unexpected reply from GitHub: {}", e))?;
+    Ok(user.id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_wait_for_approval_and_surface_refusals() {
+        assert_eq!(
+            token_from(r#"{"access_token":"gho_abc","token_type":"bearer"}"#).unwrap(),
+            Some("gho_abc".to_string())
+        );
+        assert_eq!(
+            token_from(r#"{"error":"authorization_pending"}"#).unwrap(),
+            None
+        );
+        assert_eq!(token_from(r#"{"error":"slow_down"}"#).unwrap(), None);
+        assert!(token_from(r#"{"error":"access_denied"}"#).is_err());
+        assert!(token_from(r#"{"error":"expired_token"}"#).is_err());
+        assert!(token_from("not json").is_err());
+    }
+}
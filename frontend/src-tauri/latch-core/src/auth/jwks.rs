@@ -0,0 +1,310 @@
+//! The keys ID tokens are signed with, for each OpenID provider Latch accepts
+//! them from (Google, and Apple on macOS/iOS). An OAuth Vault's key comes from
+//! the account id in the ID token, so the token's signature has to be checked
+//! against the provider's published keys, or anyone could hand over a token
+//! naming the account.
+//!
+//! Keys are fetched before an OAuth sign-in is checked (`refresh_for`) and kept
+//! in memory and on disk for as long as the provider says they are current. When
+//! they can't be fetched, keys past that point still verify for
+//! `OFFLINE_GRACE_SECS`, so an OAuth Vault opens offline for a while; after
+//! that, or with no keys at all, sign-ins are refused.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::DecodingKey;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const GOOGLE_CERTS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+pub const APPLE_CERTS_URL: &str = "https://appleid.apple.com/auth/keys";
+/// How long fetched keys are current when the provider's response doesn't say.
+const DEFAULT_MAX_AGE_SECS: u64 = 6 * 60 * 60;
+/// How long past that keys are still trusted when they can't be refetched.
+pub const OFFLINE_GRACE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// An OpenID provider whose ID tokens Latch verifies, with its cached keys.
+pub struct Issuer {
+    pub name: &'static str,
+    /// The `iss` values its tokens carry.
+    pub issuers: &'static [&'static str],
+    certs_url: &'static str,
+    /// File in the config directory the keys are cached in.
+    cache_file: &'static str,
+    keys: Mutex<Option<KeySet>>,
+}
+
+pub static GOOGLE: Issuer = Issuer {
+    name: "Google",
+    issuers: &["https://accounts.google.com", "accounts.google.com"],
+    certs_url: GOOGLE_CERTS_URL,
+    cache_file: "google-keys.json",
+    keys: Mutex::new(None),
+};
+
+pub static APPLE: Issuer = Issuer {
+    name: "Apple",
+    issuers: &["https://appleid.apple.com"],
+    certs_url: APPLE_CERTS_URL,
+    cache_file: "apple-keys.json",
+    keys: Mutex::new(None),
+};
+
+static ISSUERS: [&Issuer; 2] = [&GOOGLE, &APPLE];
+
+/// The provider that issued `id_token`, going by its (not yet verified) `iss`
+/// claim. Only picks which keys to check the signature against.
+pub fn issuer_of(id_token: &str) -> Option<&'static Issuer> {
+    #[derive(Deserialize)]
+    struct Claims {
+        iss: String,
+    }
+    let payload = id_token.split('.').nth(1)?;
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    ISSUERS
+        .iter()
+        .copied()
+        .find(|issuer| issuer.issuers.contains(&claims.iss.as_str()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SigningKey {
+    kid: String,
+    /// RSA modulus and exponent, base64url as in the JWK.
+    n: String,
+    e: String,
+}
+
+/// A provider's keys as last fetched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySet {
+    keys: Vec<SigningKey>,
+    /// Unix time the provider's response stops being current.
+    #[serde(default)]
+    expires_at: u64,
+}
+
+impl KeySet {
+    /// Parses a JWK set, keeping its RSA keys, current for `max_age_secs` from `now`.
+    pub fn parse(jwks: &str, now: u64, max_age_secs: u64) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Jwk {
+            #[serde(default)]
+            kid: String,
+            #[serde(default)]
+            kty: String,
+            #[serde(default)]
+            n: String,
+            #[serde(default)]
+            e: String,
+        }
+        #[derive(Deserialize)]
+        struct Jwks {
+            keys: Vec<Jwk>,
+        }
+        let jwks: Jwks = serde_json::from_str(jwks)
+            .map_err(|e| format!("Failed to parse signing keys: {}", e))?;
+        let keys: Vec<SigningKey> = jwks
+            .keys
+            .into_iter()
+            .filter(|k| k.kty == "RSA" && !k.kid.is_empty())
+            .map(|k| SigningKey {
+                kid: k.kid,
+                n: k.n,
+                e: k.e,
+            })
+            .collect();
+        if keys.is_empty() {
+            return Err("No usable signing keys were published".to_string());
+        }
+        Ok(Self {
+            keys,
+            expires_at: now.saturating_add(max_age_secs),
+        })
+    }
+
+    fn is_current(&self, now: u64) -> bool {
+        now < self.expires_at
+    }
+
+    fn has(&self, kid: &str) -> bool {
+        self.keys.iter().any(|k| k.kid == kid)
+    }
+
+    /// The key a token signed under `kid` verifies against, as of `now`.
+    fn key(&self, name: &str, kid: &str, now: u64) -> Result<DecodingKey, String> {
+        if now >= self.expires_at.saturating_add(OFFLINE_GRACE_SECS) {
+            return Err(format!(
+                "{}'s signing keys are out of date; connect to the internet and try again",
+                name
+            ));
+        }
+        let key =
+            self.keys.iter().find(|k| k.kid == kid).ok_or_else(|| {
+                format!("ID token was signed with a key {} doesn't publish", name)
+            })?;
+        DecodingKey::from_rsa_components(&key.n, &key.e)
+            .map_err(|e| format!("Invalid {} signing key: {}", name, e))
+    }
+}
+
+impl Issuer {
+    fn cache_path(&self) -> Result<PathBuf, String> {
+        Ok(crate::vault::storage::config_dir()?.join(self.cache_file))
+    }
+
+    /// The cached keys, loading them from disk the first time.
+    fn cached<'a>(&self, keys: &'a mut Option<KeySet>) -> Option<&'a KeySet> {
+        if keys.is_none() {
+            *keys = self
+                .cache_path()
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|json| serde_json::from_str(&json).ok());
+        }
+        keys.as_ref()
+    }
+
+    /// Makes `keys` the ones tokens are checked against, e.g. after fetching them.
+    pub fn install(&self, keys: KeySet) {
+        if let Ok(mut cached) = self.keys.lock() {
+            *cached = Some(keys);
+        }
+    }
+
+    /// The key to check a token signed under `kid` against.
+    pub fn key_for(&self, kid: &str, now: u64) -> Result<DecodingKey, String> {
+        let mut keys = self
+            .keys
+            .lock()
+            .map_err(|_| format!("{}'s signing keys are temporarily unavailable", self.name))?;
+        self.cached(&mut keys)
+            .ok_or_else(|| {
+                format!(
+                    "{}'s signing keys could not be fetched; connect to the internet and try again",
+                    self.name
+                )
+            })?
+            .key(self.name, kid, now)
+    }
+
+    async fn fetch(&self, now: u64) -> Result<KeySet, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("Failed to start key fetch: {}", e))?;
+        let response = client
+            .get(self.certs_url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch {}'s signing keys: {}", self.name, e))?;
+        let max_age_secs = response
+            .headers()
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+            .and_then(max_age)
+            .unwrap_or(DEFAULT_MAX_AGE_SECS);
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to fetch {}'s signing keys: {}", self.name, e))?;
+        KeySet::parse(&body, now, max_age_secs)
+    }
+
+    fn save(&self, keys: &KeySet) -> Result<(), String> {
+        let path = self.cache_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_string(keys)
+            .map_err(|e| format!("Failed to serialize signing keys: {}", e))?;
+        fs::write(path, json)
+            .map_err(|e| format!("Failed to cache {}'s signing keys: {}", self.name, e))
+    }
+}
+
+/// `max-age` from a Cache-Control header.
+fn max_age(cache_control: &str) -> Option<u64> {
+    cache_control
+        .split(',')
+        .find_map(|directive| directive.trim().strip_prefix("max-age="))
+        .and_then(|secs| secs.parse().ok())
+}
+
+/// Fetches the keys of `id_token`'s issuer unless the cached ones are current
+/// and hold the key it was signed with, which is how a rotation is picked up. A
+/// failed fetch leaves the cache as it was; `key_for` then decides whether it
+/// will do.
+pub async fn refresh_for(id_token: &str) {
+    let Some(kid) = jsonwebtoken::decode_header(id_token)
+        .ok()
+        .and_then(|header| header.kid)
+    else {
+        return;
+    };
+    let Some(issuer) = issuer_of(id_token) else {
+        return;
+    };
+    let now = crate::vault::unix_now();
+    let fresh = issuer.keys.lock().ok().is_some_and(|mut keys| {
+        issuer
+            .cached(&mut keys)
+            .is_some_and(|k| k.is_current(now) && k.has(&kid))
+    });
+    if fresh {
+        return;
+    }
+    match issuer.fetch(now).await {
+        Ok(keys) => {
+            if let Err(e) = issuer.save(&keys) {
+                eprintln!("{}", e);
+            }
+            issuer.install(keys);
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JWKS: &str = include_str!("../../testdata/google-jwks.json");
+
+    #[test]
+    fn keys_are_picked_by_kid_and_trusted_offline_for_a_while() {
+        let keys = KeySet::parse(JWKS, 1_000, 3_600).unwrap();
+        assert!(keys.is_current(1_000));
+        assert!(!keys.is_current(4_600));
+        assert!(keys.key("Google", "latch-test", 4_600).is_ok());
+        assert!(keys.key("Google", "rotated-away", 1_000).is_err());
+        assert!(keys
+            .key("Google", "latch-test", 4_600 + OFFLINE_GRACE_SECS)
+            .is_err());
+
+        assert!(KeySet::parse(r#"{"keys":[]}"#, 0, 0).is_err());
+        assert_eq!(
+            max_age("public, max-age=19732, must-revalidate"),
+            Some(19732)
+        );
+        assert_eq!(max_age("no-cache"), None);
+    }
+
+    #[test]
+    fn tokens_are_routed_to_their_issuers_keys() {
+        let token = |iss: &str| {
+            let claims = URL_SAFE_NO_PAD.encode(format!(r#"{{"iss":"{}","sub":"1"}}"#, iss));
+            format!("e30.{}.sig", claims)
+        };
+        let name = |iss: &str| issuer_of(&token(iss)).map(|issuer| issuer.name);
+        assert_eq!(name("https://accounts.google.com"), Some("Google"));
+        assert_eq!(name("accounts.google.com"), Some("Google"));
+        assert_eq!(name("https://appleid.apple.com"), Some("Apple"));
+        assert_eq!(name("https://evil.example"), None);
+        assert!(issuer_of("not-a-token").is_none());
+    }
+}
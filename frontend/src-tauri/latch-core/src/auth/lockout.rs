@@ -0,0 +1,332 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const MAX_FAILED_ATTEMPTS: u32 = 10;
+const BASE_LOCKOUT_DURATION: Duration = Duration::from_secs(5);
+const MAX_LOCKOUT_DURATION: Duration = Duration::from_secs(300);
+
+pub struct AuthAttemptState {
+    failed_attempts: u32,
+    last_failed_time: Option<Instant>,
+    lockout_until: Option<Instant>,
+    store: Option<LockoutStore>,
+}
+
+impl Default for AuthAttemptState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuthAttemptState {
+    pub fn new() -> Self {
+        Self {
+            failed_attempts: 0,
+            last_failed_time: None,
+            lockout_until: None,
+            store: None,
+        }
+    }
+
+    /// Restores the counter and any running Lockout from disk, and keeps the file
+    /// updated from then on so restarting the app does not reset either.
+    pub fn persisted(store: LockoutStore) -> Self {
+        let mut state = Self::new();
+        match store.load(crate::vault::unix_now()) {
+            Ok(Some((failed_attempts, remaining))) => {
+                state.failed_attempts = failed_attempts;
+                state.lockout_until = remaining.map(|r| Instant::now() + r);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Lockout state rejected, locking out: {}", e);
+                state.failed_attempts = MAX_FAILED_ATTEMPTS;
+                state.lockout_until = Some(Instant::now() + MAX_LOCKOUT_DURATION);
+            }
+        }
+        state.store = Some(store);
+        state
+    }
+
+    pub fn is_locked_out(&self) -> bool {
+        if let Some(lockout) = self.lockout_until {
+            Instant::now() < lockout
+        } else {
+            false
+        }
+    }
+
+    pub fn record_failure(&mut self) -> Result<(), String> {
+        self.failed_attempts += 1;
+        self.last_failed_time = Some(Instant::now());
+
+        if self.failed_attempts >= MAX_FAILED_ATTEMPTS {
+            self.lockout_until = Some(Instant::now() + MAX_LOCKOUT_DURATION);
+            self.save();
+            return Err(format!(
+                "Too many failed attempts. Account locked for {} minutes.",
+                MAX_LOCKOUT_DURATION.as_secs() / 60
+            ));
+        }
+
+        let lockout_duration =
+            BASE_LOCKOUT_DURATION.saturating_mul(2_u32.pow(self.failed_attempts.saturating_sub(1)));
+        let lockout_duration = std::cmp::min(lockout_duration, MAX_LOCKOUT_DURATION);
+        self.lockout_until = Some(Instant::now() + lockout_duration);
+        self.save();
+
+        Err(format!(
+            "Too many failed attempts. Please try again in {} seconds.",
+            lockout_duration.as_secs()
+        ))
+    }
+
+    pub fn reset(&mut self) {
+        self.failed_attempts = 0;
+        self.last_failed_time = None;
+        self.lockout_until = None;
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let remaining = self
+            .lockout_until
+            .map(|until| until.saturating_duration_since(Instant::now()));
+        if let Err(e) = store.save(self.failed_attempts, remaining, crate::vault::unix_now()) {
+            log::warn!("Failed to save lockout state: {}", e);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LockoutRecord {
+    failed_attempts: u32,
+    lockout_until: Option<u64>,
+    recorded_at: u64,
+    mac: String,
+}
+
+impl LockoutRecord {
+    fn message(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.failed_attempts,
+            self.lockout_until
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+            self.recorded_at
+        )
+    }
+}
+
+/// On-disk Lockout state, authenticated with HMAC-SHA256 under a per-install key
+/// kept in a separate file. A record that fails verification, or is missing while
+/// the key exists, is treated as a full Lockout rather than ignored.
+pub struct LockoutStore {
+    pub path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl LockoutStore {
+    pub fn new() -> Result<Self, String> {
+        let config_dir = crate::vault::storage::config_dir()?;
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        Ok(Self {
+            path: config_dir.join("lockout.json"),
+            key_path: config_dir.join("lockout.key"),
+        })
+    }
+
+    /// Returns the failed attempt count and the Lockout time still remaining.
+    /// Timestamps are wall-clock, so if the clock has moved backwards since the
+    /// record was written the remaining time is measured from `recorded_at`
+    /// instead, and never exceeds the longest Lockout.
+    fn load(&self, now: u64) -> Result<Option<(u32, Option<Duration>)>, String> {
+        if !self.path.exists() {
+            // The key is only written alongside a record, so a lone key means
+            // the record was deleted.
+            if self.key_path.exists() {
+                return Err("Lockout state is missing".to_string());
+            }
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read lockout state: {}", e))?;
+        let record: LockoutRecord = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse lockout state: {}", e))?;
+
+        let key = self.read_key()?.ok_or("Lockout key is missing")?;
+        let mac = hex::decode(&record.mac).map_err(|_| "Invalid lockout signature")?;
+        mac_for(&key, &record)
+            .verify_slice(&mac)
+            .map_err(|_| "Lockout state has been modified")?;
+
+        let remaining = record.lockout_until.and_then(|until| {
+            let from = now.max(record.recorded_at);
+            (until > from).then(|| Duration::from_secs(until - from).min(MAX_LOCKOUT_DURATION))
+        });
+        Ok(Some((record.failed_attempts, remaining)))
+    }
+
+    fn save(
+        &self,
+        failed_attempts: u32,
+        remaining: Option<Duration>,
+        now: u64,
+    ) -> Result<(), String> {
+        let key = match self.read_key()? {
+            Some(key) => key,
+            None => self.create_key()?,
+        };
+        let mut record = LockoutRecord {
+            failed_attempts,
+            lockout_until: remaining
+                .filter(|r| !r.is_zero())
+                .map(|r| now + r.as_secs().max(1)),
+            recorded_at: now,
+            mac: String::new(),
+        };
+        record.mac = hex::encode(mac_for(&key, &record).finalize().into_bytes());
+
+        let json = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize lockout state: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, &json).map_err(|e| format!("Failed to write lockout state: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to rename lockout state: {}", e))?;
+        Ok(())
+    }
+
+    fn read_key(&self) -> Result<Option<[u8; 32]>, String> {
+        if !self.key_path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.key_path)
+            .map_err(|e| format!("Failed to read lockout key: {}", e))?;
+        let bytes = hex::decode(content.trim()).map_err(|_| "Invalid lockout key")?;
+        let key: [u8; 32] = bytes.try_into().map_err(|_| "Invalid lockout key")?;
+        Ok(Some(key))
+    }
+
+    fn create_key(&self) -> Result<[u8; 32], String> {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        fs::write(&self.key_path, hex::encode(key))
+            .map_err(|e| format!("Failed to write lockout key: {}", e))?;
+        Ok(key)
+    }
+}
+
+fn mac_for(key: &[u8; 32], record: &LockoutRecord) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(record.message().as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_is_not_locked_out() {
+        let state = AuthAttemptState::new();
+        assert!(!state.is_locked_out());
+    }
+
+    #[test]
+    fn test_first_failure_returns_error_with_wait() {
+        let mut state = AuthAttemptState::new();
+        let result = state.record_failure();
+        assert!(result.is_err());
+        assert!(state.is_locked_out());
+    }
+
+    #[test]
+    fn test_reset_clears_lockout() {
+        let mut state = AuthAttemptState::new();
+        state.record_failure().ok();
+        state.reset();
+        assert!(!state.is_locked_out());
+        assert_eq!(state.failed_attempts, 0);
+    }
+
+    fn store(dir: &tempfile::TempDir) -> LockoutStore {
+        LockoutStore {
+            path: dir.path().join("lockout.json"),
+            key_path: dir.path().join("lockout.key"),
+        }
+    }
+
+    #[test]
+    fn lockout_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = AuthAttemptState::persisted(store(&dir));
+        state.record_failure().ok();
+        state.record_failure().ok();
+
+        let restored = AuthAttemptState::persisted(store(&dir));
+        assert_eq!(restored.failed_attempts, 2);
+        assert!(restored.is_locked_out());
+
+        let mut restored = restored;
+        restored.reset();
+        let cleared = AuthAttemptState::persisted(store(&dir));
+        assert_eq!(cleared.failed_attempts, 0);
+        assert!(!cleared.is_locked_out());
+    }
+
+    #[test]
+    fn tampered_record_locks_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        store.save(3, None, 1_000).unwrap();
+
+        let content = fs::read_to_string(&store.path).unwrap();
+        fs::write(
+            &store.path,
+            content.replace("\"failed_attempts\":3", "\"failed_attempts\":0"),
+        )
+        .unwrap();
+
+        assert!(store.load(1_000).is_err());
+        assert!(AuthAttemptState::persisted(store).is_locked_out());
+    }
+
+    #[test]
+    fn deleted_record_locks_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        assert_eq!(store.load(1_000), Ok(None));
+
+        store
+            .save(9, Some(Duration::from_secs(300)), 1_000)
+            .unwrap();
+        fs::remove_file(&store.path).unwrap();
+
+        assert!(store.load(1_000).is_err());
+        assert!(AuthAttemptState::persisted(store).is_locked_out());
+    }
+
+    #[test]
+    fn clock_rollback_does_not_shorten_or_extend_lockout() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        store.save(4, Some(Duration::from_secs(40)), 1_000).unwrap();
+
+        let (_, remaining) = store.load(500).unwrap().unwrap();
+        assert_eq!(remaining, Some(Duration::from_secs(40)));
+        let (_, remaining) = store.load(1_030).unwrap().unwrap();
+        assert_eq!(remaining, Some(Duration::from_secs(10)));
+        let (_, remaining) = store.load(2_000).unwrap().unwrap();
+        assert_eq!(remaining, None);
+    }
+}
@@ -5,6 +5,7 @@ pub enum AuthMethod {
     Password,
     OAuth,
     Biometric,
+    HardwareKey,
 }
 
 impl AuthMethod {
@@ -13,6 +14,7 @@ impl AuthMethod {
             AuthMethod::Password => "password-pbkdf2",
             AuthMethod::OAuth => "oauth-argon2id",
             AuthMethod::Biometric => "biometric-keychain",
+            AuthMethod::HardwareKey => "hardware-key",
         }
     }
 
@@ -21,6 +23,7 @@ impl AuthMethod {
             "password-pbkdf2" => Some(AuthMethod::Password),
             "oauth-argon2id" | "oauth-pbkdf2" => Some(AuthMethod::OAuth),
             "biometric-keychain" => Some(AuthMethod::Biometric),
+            "hardware-key" => Some(AuthMethod::HardwareKey),
             _ => None,
         }
     }
@@ -32,6 +35,7 @@ impl AuthMethod {
             "oauth-argon2id",
             "oauth-pbkdf2",
             "biometric-keychain",
+            "hardware-key",
         ]
     }
 }
@@ -46,6 +50,7 @@ mod tests {
             AuthMethod::Password,
             AuthMethod::OAuth,
             AuthMethod::Biometric,
+            AuthMethod::HardwareKey,
         ] {
             let tag = method.vault_tag();
             let parsed = AuthMethod::from_vault_tag(tag).unwrap();
@@ -1,4 +1,7 @@
+pub mod github;
+pub mod jwks;
 pub mod lockout;
 pub mod method;
 pub mod oauth;
 pub mod password;
+pub mod provider;
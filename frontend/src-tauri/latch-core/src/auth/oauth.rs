@@ -0,0 +1,512 @@
+//! Signing in with an outside identity provider. Each provider turns whatever
+//! its sign-in hands back (an ID token, a GitHub access token) into a verified
+//! `Identity`; the account id derived from it is an OAuth Vault's salt, and the
+//! Vault's key comes from it alone (see `derive_key`).
+
+use super::jwks::{self, Issuer};
+use argon2::{Argon2, Params};
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// The identity provider an OAuth Vault signs in with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthProvider {
+    Google,
+    #[serde(rename = "github")]
+    GitHub,
+    Apple,
+}
+
+impl OAuthProvider {
+    pub const ALL: [OAuthProvider; 3] = [
+        OAuthProvider::Google,
+        OAuthProvider::GitHub,
+        OAuthProvider::Apple,
+    ];
+
+    /// The provider of an account id as OAuth Vaults store it in their salt.
+    /// Google's ids predate the others and carry no prefix.
+    pub fn of_account(account: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|&p| {
+                let prefix = identity_provider(p).account_prefix();
+                !prefix.is_empty() && account.starts_with(prefix)
+            })
+            .unwrap_or(OAuthProvider::Google)
+    }
+
+    /// The provider a Vault with this `kdf` and `salt` signs in with; None for
+    /// Vaults that don't use OAuth.
+    pub fn for_vault(kdf: &str, salt: &str) -> Option<Self> {
+        (super::method::AuthMethod::from_vault_tag(kdf) == Some(super::method::AuthMethod::OAuth))
+            .then(|| Self::of_account(salt))
+    }
+}
+
+/// Someone a provider vouches for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub provider: OAuthProvider,
+    /// The provider's id for the account, which never changes (unlike emails or
+    /// logins).
+    pub subject: String,
+}
+
+impl Identity {
+    /// The account id the Vault key is derived from and stored as the salt.
+    pub fn account(&self) -> String {
+        format!(
+            "{}{}",
+            identity_provider(self.provider).account_prefix(),
+            self.subject
+        )
+    }
+}
+
+pub type IdentityFuture<'a> = Pin<Box<dyn Future<Output = Result<Identity, String>> + Send + 'a>>;
+
+/// A way to sign in to an OAuth Vault. Adding one means implementing this and
+/// listing it in `identity_provider`; the commands only go through the registry.
+pub trait IdentityProvider: Send + Sync {
+    fn kind(&self) -> OAuthProvider;
+
+    /// What the user sees, e.g. "This vault signs in with Google".
+    fn display_name(&self) -> &'static str;
+
+    /// Prepended to the subject in the stored account id, so accounts of
+    /// different providers can't collide.
+    fn account_prefix(&self) -> &'static str;
+
+    /// Checks what the provider's sign-in handed back and returns who it vouches
+    /// for. May go to the network, e.g. for signing keys.
+    fn validate<'a>(&'a self, token: &'a str) -> IdentityFuture<'a>;
+}
+
+/// Providers whose sign-in hands back an OpenID ID token.
+struct IdTokenProvider {
+    kind: OAuthProvider,
+    issuer: &'static Issuer,
+    account_prefix: &'static str,
+    /// Environment variable holding the client id its tokens are addressed to.
+    client_id_var: &'static str,
+    /// Overrides the environment; see `set_client_id`.
+    client_id: Mutex<Option<String>>,
+}
+
+impl IdTokenProvider {
+    /// The client id tokens must be addressed to. A build without one can't
+    /// check the audience, so it refuses the token rather than skip the check.
+    fn client_id(&self) -> Result<String, String> {
+        let configured = self.client_id.lock().ok().and_then(|id| id.clone());
+        configured
+            .or_else(|| env::var(self.client_id_var).ok())
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "{} sign-in is not configured in this build",
+                    self.issuer.name
+                )
+            })
+    }
+}
+
+impl IdentityProvider for IdTokenProvider {
+    fn kind(&self) -> OAuthProvider {
+        self.kind
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.issuer.name
+    }
+
+    fn account_prefix(&self) -> &'static str {
+        self.account_prefix
+    }
+
+    fn validate<'a>(&'a self, token: &'a str) -> IdentityFuture<'a> {
+        Box::pin(async move {
+            let client_id = self.client_id()?;
+            jwks::refresh_for(token).await;
+            let claims = decode_id_token(token, &client_id)
+                .map_err(|e| format!("Invalid ID token: {}", e))?;
+            if !self.issuer.issuers.contains(&claims.iss.as_str()) {
+                return Err(format!("This is not a {} sign-in", self.issuer.name));
+            }
+            Ok(Identity {
+                provider: self.kind,
+                subject: claims.sub,
+            })
+        })
+    }
+}
+
+/// GitHub hands back an access token, which only GitHub itself can vouch for.
+struct GitHubProvider;
+
+impl IdentityProvider for GitHubProvider {
+    fn kind(&self) -> OAuthProvider {
+        OAuthProvider::GitHub
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn account_prefix(&self) -> &'static str {
+        "github:"
+    }
+
+    fn validate<'a>(&'a self, token: &'a str) -> IdentityFuture<'a> {
+        Box::pin(async move {
+            Ok(Identity {
+                provider: self.kind(),
+                subject: super::github::user_id(token).await?,
+            })
+        })
+    }
+}
+
+static GOOGLE: IdTokenProvider = IdTokenProvider {
+    kind: OAuthProvider::Google,
+    issuer: &jwks::GOOGLE,
+    account_prefix: "",
+    client_id_var: "LATCH_OAUTH_CLIENT_ID",
+    client_id: Mutex::new(None),
+};
+static APPLE: IdTokenProvider = IdTokenProvider {
+    kind: OAuthProvider::Apple,
+    issuer: &jwks::APPLE,
+    account_prefix: "apple:",
+    client_id_var: "LATCH_APPLE_CLIENT_ID",
+    client_id: Mutex::new(None),
+};
+static GITHUB: GitHubProvider = GitHubProvider;
+
+pub fn identity_provider(kind: OAuthProvider) -> &'static dyn IdentityProvider {
+    match kind {
+        OAuthProvider::Google => &GOOGLE,
+        OAuthProvider::GitHub => &GITHUB,
+        OAuthProvider::Apple => &APPLE,
+    }
+}
+
+/// Sets the client id `kind`'s ID tokens must be addressed to, in place of the
+/// one read from the environment. GitHub has no ID tokens and ignores it.
+pub fn set_client_id(kind: OAuthProvider, client_id: &str) {
+    let provider = match kind {
+        OAuthProvider::Google => &GOOGLE,
+        OAuthProvider::Apple => &APPLE,
+        OAuthProvider::GitHub => return,
+    };
+    if let Ok(mut configured) = provider.client_id.lock() {
+        *configured = Some(client_id.to_string());
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub iss: String,
+}
+
+fn get_app_secret() -> String {
+    let secret = env::var("LATCH_OAUTH_SECRET")
+        .unwrap_or_else(|_| "test-secret-for-development-only-32b".to_string());
+
+    if secret.len() < 32 {
+        panic!(
+            "LATCH_OAUTH_SECRET must be at least 32 bytes for security. Current length: {}",
+            secret.len()
+        )
+    }
+
+    secret
+}
+
+pub fn derive_key(user_id: &str) -> Result<[u8; 32], String> {
+    let app_secret = get_app_secret();
+
+    // Use Argon2id to derive a 32-byte key
+    // Parameters: memory_cost=65536 (64MB), time_cost=3, parallelism=4
+    let params =
+        Params::new(65536, 3, 4, Some(32)).map_err(|e| format!("Invalid Argon2 params: {}", e))?;
+
+    let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+
+    // Salt includes user_id to make keys user-specific
+    let salt = format!("latch-vault-oauth-{}", user_id);
+    let salt_bytes = salt.as_bytes();
+
+    // Derive key using Argon2id
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(app_secret.as_bytes(), salt_bytes, &mut key)
+        .map_err(|e| format!("Argon2 hashing failed: {}", e))?;
+
+    Ok(key)
+}
+
+/// Checks `id_token`'s signature against its issuer's keys (see `jwks`), then
+/// its issuer, expiry and that it is addressed to `client_id`. Google and Apple
+/// tokens are accepted.
+pub fn decode_id_token(id_token: &str, client_id: &str) -> Result<IdTokenClaims, String> {
+    if client_id.is_empty() {
+        return Err("No client id to check the ID token's audience against".to_string());
+    }
+    let issuer = jwks::issuer_of(id_token).ok_or("ID token is from an unknown issuer")?;
+
+    let header = decode_header(id_token).map_err(|e| format!("Failed to decode token: {}", e))?;
+    if header.alg != Algorithm::RS256 {
+        return Err("ID token is not signed with RS256".to_string());
+    }
+    let kid = header.kid.ok_or("ID token does not name its signing key")?;
+    let key = issuer.key_for(&kid, crate::vault::unix_now())?;
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.validate_aud = true;
+    validation.validate_exp = true;
+    validation.validate_nbf = true;
+    validation.set_issuer(issuer.issuers);
+    validation.set_audience(&[client_id]);
+
+    let token_data = decode::<IdTokenClaims>(id_token, &key, &validation)
+        .map_err(|e| format!("Failed to decode token: {}", e))?;
+
+    Ok(token_data.claims)
+}
+
+/// The account id a verified ID token's key is derived from: Google's `sub` as
+/// is, Apple's prefixed.
+pub fn extract_user_id(id_token: &str, client_id: &str) -> Result<String, String> {
+    let claims = decode_id_token(id_token, client_id)?;
+    let provider = if jwks::APPLE.issuers.contains(&claims.iss.as_str()) {
+        OAuthProvider::Apple
+    } else {
+        OAuthProvider::Google
+    };
+    Ok(Identity {
+        provider,
+        subject: claims.sub,
+    }
+    .account())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose, Engine as _};
+    use serde_json::json;
+
+    const CLIENT_ID: &str = "latch-test";
+
+    #[test]
+    fn test_decode_id_token_valid_structure() {
+        let valid_token = "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYXVkIjoiY2xpZW50X2lkIn0.signature";
+        let result = decode_id_token(valid_token, CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    /// A token signed with the test key, whose public half is installed as
+    /// Google's and Apple's, addressed to the test client unless it names another.
+    fn signed_token(mut claims: serde_json::Value) -> String {
+        if claims.get("aud").is_none() {
+            claims["aud"] = json!(CLIENT_ID);
+        }
+        let keys = include_str!("../../testdata/google-jwks.json");
+        for issuer in [&jwks::GOOGLE, &jwks::APPLE] {
+            issuer.install(jwks::KeySet::parse(keys, crate::vault::unix_now(), 3600).unwrap());
+        }
+        let mut header = jsonwebtoken::Header::new(Algorithm::RS256);
+        header.kid = Some("latch-test".to_string());
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(include_bytes!(
+            "../../testdata/google-signing-key.pem"
+        ))
+        .unwrap();
+        jsonwebtoken::encode(&header, &claims, &key).unwrap()
+    }
+
+    #[test]
+    fn only_tokens_signed_by_google_are_accepted() {
+        let claims = json!({
+            "sub": "user-42",
+            "iss": "https://accounts.google.com",
+            "exp": crate::vault::unix_now() + 3600,
+        });
+        let token = signed_token(claims.clone());
+        assert_eq!(extract_user_id(&token, CLIENT_ID).unwrap(), "user-42");
+
+        // The same claims under a forged signature, or naming a key Google doesn't have.
+        let (signed, _) = token.rsplit_once('.').unwrap();
+        assert!(extract_user_id(&format!("{}.c2ln", signed), CLIENT_ID).is_err());
+        let header = general_purpose::URL_SAFE_NO_PAD
+            .encode(r#"{"alg":"RS256","typ":"JWT","kid":"someone-else"}"#);
+        let (_, rest) = token.split_once('.').unwrap();
+        assert!(extract_user_id(&format!("{}.{}", header, rest), CLIENT_ID).is_err());
+
+        let expired = signed_token(json!({
+            "sub": "user-42",
+            "iss": "https://accounts.google.com",
+            "exp": 1000,
+        }));
+        assert!(extract_user_id(&expired, CLIENT_ID).is_err());
+
+        let elsewhere = signed_token(json!({
+            "sub": "user-42",
+            "iss": "https://accounts.google.com",
+            "aud": "another-app",
+            "exp": crate::vault::unix_now() + 3600,
+        }));
+        assert!(extract_user_id(&elsewhere, CLIENT_ID).is_err());
+        // Without a client id there is no audience to check, so nothing passes.
+        assert!(extract_user_id(&token, "").is_err());
+    }
+
+    #[test]
+    fn apple_tokens_map_to_their_own_accounts() {
+        let token = signed_token(json!({
+            "sub": "001234.abcdef.0987",
+            "iss": "https://appleid.apple.com",
+            "exp": crate::vault::unix_now() + 3600,
+        }));
+        let account = extract_user_id(&token, CLIENT_ID).unwrap();
+        assert_eq!(account, "apple:001234.abcdef.0987");
+        assert_eq!(OAuthProvider::of_account(&account), OAuthProvider::Apple);
+
+        // An issuer Latch doesn't know is refused even under a known key.
+        let unknown = signed_token(json!({
+            "sub": "1",
+            "iss": "https://login.example.com",
+            "exp": crate::vault::unix_now() + 3600,
+        }));
+        assert!(extract_user_id(&unknown, CLIENT_ID).is_err());
+    }
+
+    #[tokio::test]
+    async fn providers_only_vouch_for_their_own_tokens() {
+        set_client_id(OAuthProvider::Google, CLIENT_ID);
+        set_client_id(OAuthProvider::Apple, CLIENT_ID);
+        let apple = signed_token(json!({
+            "sub": "001234",
+            "iss": "https://appleid.apple.com",
+            "exp": crate::vault::unix_now() + 3600,
+        }));
+        let identity = identity_provider(OAuthProvider::Apple)
+            .validate(&apple)
+            .await
+            .unwrap();
+        assert_eq!(identity.account(), "apple:001234");
+        assert!(identity_provider(OAuthProvider::Google)
+            .validate(&apple)
+            .await
+            .is_err());
+
+        for provider in OAuthProvider::ALL {
+            let identity = Identity {
+                provider,
+                subject: "42".to_string(),
+            };
+            assert_eq!(identity_provider(provider).kind(), provider);
+            assert_eq!(OAuthProvider::of_account(&identity.account()), provider);
+        }
+    }
+
+    #[test]
+    fn test_decode_id_token_invalid_format() {
+        let invalid_token = "invalid.token.format";
+        let result = decode_id_token(invalid_token, CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_id_token_empty() {
+        let result = decode_id_token("", CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_id_token_no_payload() {
+        let result = decode_id_token("header.", CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_user_id_valid() {
+        let user_id = "test-user-id-123";
+        let payload = json!({ "sub": user_id });
+        let encoded = general_purpose::URL_SAFE_NO_PAD.encode(payload.to_string());
+        let token = format!("header.{}.signature", encoded);
+
+        let result = extract_user_id(&token, CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_user_id_missing_sub() {
+        let payload = json!({ "name": "John Doe" });
+        let encoded = general_purpose::URL_SAFE_NO_PAD.encode(payload.to_string());
+        let token = format!("header.{}.signature", encoded);
+
+        let result = extract_user_id(&token, CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_user_id_invalid_json() {
+        let encoded = general_purpose::URL_SAFE_NO_PAD.encode("invalid json");
+        let token = format!("header.{}.signature", encoded);
+
+        let result = extract_user_id(&token, CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extract_user_id_empty_string() {
+        let result = extract_user_id("", CLIENT_ID);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_derive_key_returns_valid_key() {
+        let user_id = "test-user-id-123";
+        let key = derive_key(user_id).unwrap();
+        assert_eq!(key.len(), 32);
+    }
+
+    #[test]
+    fn test_derive_key_different_users() {
+        let user_id_1 = "user-1";
+        let user_id_2 = "user-2";
+
+        let key1 = derive_key(user_id_1).unwrap();
+        let key2 = derive_key(user_id_2).unwrap();
+
+        assert_ne!(key1, key2);
+    }
+
+    #[test]
+    fn test_derive_key_empty_user_id() {
+        let key = derive_key("").unwrap();
+        assert_eq!(key.len(), 32);
+    }
+
+    #[test]
+    fn test_derive_key_long_user_id() {
+        let long_user_id = "a".repeat(1000);
+        let key = derive_key(&long_user_id).unwrap();
+        assert_eq!(key.len(), 32);
+    }
+
+    #[test]
+    fn test_derive_key_unicode() {
+        let unicode_user_id = "用户-123-пользователь";
+        let key = derive_key(unicode_user_id).unwrap();
+        assert_eq!(key.len(), 32);
+    }
+}
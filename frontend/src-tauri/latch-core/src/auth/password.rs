@@ -13,3 +13,13 @@ pub fn derive_key(password: &str, salt: &[u8; 32]) -> [u8; 32] {
 pub fn generate_salt() -> [u8; 32] {
     rand::thread_rng().gen()
 }
+
+pub fn decode_salt_hex(salt_hex: &str) -> Result<[u8; 32], String> {
+    let salt_bytes = hex::decode(salt_hex).map_err(|e| format!("Invalid salt: {}", e))?;
+    if salt_bytes.len() != 32 {
+        return Err("Salt must be 32 bytes".to_string());
+    }
+    let mut salt = [0u8; 32];
+    salt.copy_from_slice(&salt_bytes);
+    Ok(salt)
+}
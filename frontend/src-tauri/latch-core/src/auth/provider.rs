@@ -0,0 +1,282 @@
+use super::method::AuthMethod;
+use super::oauth::{Identity, OAuthProvider};
+
+/// What the user presented to open a Vault.
+pub enum Credentials<'a> {
+    Password(&'a str),
+    /// An account an identity provider vouched for (see `oauth::IdentityProvider`).
+    Identity(&'a Identity),
+    /// A key released by the OS keychain or a hardware key, already 32 bytes.
+    Key([u8; 32]),
+}
+
+impl Credentials<'_> {
+    fn describe(&self) -> &'static str {
+        match self {
+            Credentials::Password(_) => "a master password",
+            Credentials::Identity(_) => "an OAuth sign-in",
+            Credentials::Key(_) => "a stored key",
+        }
+    }
+}
+
+/// A key for a new Vault, with the salt to store in its header.
+pub struct Enrollment {
+    pub key: [u8; 32],
+    pub method: AuthMethod,
+    pub salt: String,
+}
+
+/// Turns one kind of credentials into a Vault key. Each auth method has exactly one.
+pub trait KeyProvider: Send + Sync {
+    fn method(&self) -> AuthMethod;
+
+    /// Derives the key for a new Vault and the salt to store alongside it.
+    fn enroll(&self, credentials: &Credentials) -> Result<Enrollment, String>;
+
+    /// Derives the key of an existing Vault from the salt stored in its header.
+    fn derive(&self, credentials: &Credentials, salt: &str) -> Result<[u8; 32], String>;
+
+    fn unsupported(&self, credentials: &Credentials) -> String {
+        format!(
+            "This vault cannot be unlocked with {}",
+            credentials.describe()
+        )
+    }
+}
+
+struct PasswordKeys;
+
+impl KeyProvider for PasswordKeys {
+    fn method(&self) -> AuthMethod {
+        AuthMethod::Password
+    }
+
+    fn enroll(&self, credentials: &Credentials) -> Result<Enrollment, String> {
+        let Credentials::Password(password) = credentials else {
+            return Err(self.unsupported(credentials));
+        };
+        let salt = super::password::generate_salt();
+        Ok(Enrollment {
+            key: super::password::derive_key(password, &salt),
+            method: self.method(),
+            salt: hex::encode(salt),
+        })
+    }
+
+    fn derive(&self, credentials: &Credentials, salt: &str) -> Result<[u8; 32], String> {
+        let Credentials::Password(password) = credentials else {
+            return Err(self.unsupported(credentials));
+        };
+        let salt = super::password::decode_salt_hex(salt)?;
+        Ok(super::password::derive_key(password, &salt))
+    }
+}
+
+/// OAuth Vaults store the account id as their salt; the key comes from the id alone.
+struct OAuthKeys;
+
+impl OAuthKeys {
+    fn user_id(&self, credentials: &Credentials) -> Result<String, String> {
+        match credentials {
+            Credentials::Identity(identity) => Ok(identity.account()),
+            _ => Err(self.unsupported(credentials)),
+        }
+    }
+}
+
+impl KeyProvider for OAuthKeys {
+    fn method(&self) -> AuthMethod {
+        AuthMethod::OAuth
+    }
+
+    fn enroll(&self, credentials: &Credentials) -> Result<Enrollment, String> {
+        let user_id = self.user_id(credentials)?;
+        Ok(Enrollment {
+            key: super::oauth::derive_key(&user_id)?,
+            method: self.method(),
+            salt: user_id,
+        })
+    }
+
+    fn derive(&self, credentials: &Credentials, salt: &str) -> Result<[u8; 32], String> {
+        let user_id = self.user_id(credentials)?;
+        let provider = OAuthProvider::of_account(salt);
+        if OAuthProvider::of_account(&user_id) != provider {
+            return Err(format!(
+                "This vault signs in with {}",
+                super::oauth::identity_provider(provider).display_name()
+            ));
+        }
+        super::oauth::derive_key(&user_id)
+    }
+}
+
+/// Methods where the key itself is stored elsewhere (OS keychain, hardware key) and
+/// handed over as-is.
+struct StoredKeys(AuthMethod);
+
+impl KeyProvider for StoredKeys {
+    fn method(&self) -> AuthMethod {
+        self.0
+    }
+
+    fn enroll(&self, credentials: &Credentials) -> Result<Enrollment, String> {
+        Ok(Enrollment {
+            key: self.derive(credentials, "")?,
+            method: self.method(),
+            salt: String::new(),
+        })
+    }
+
+    fn derive(&self, credentials: &Credentials, _salt: &str) -> Result<[u8; 32], String> {
+        match credentials {
+            Credentials::Key(key) => Ok(*key),
+            _ => Err(self.unsupported(credentials)),
+        }
+    }
+}
+
+static PASSWORD: PasswordKeys = PasswordKeys;
+static OAUTH: OAuthKeys = OAuthKeys;
+/// The biometric key never lives in the backend between unlocks: the frontend keeps
+/// it in the OS keychain through the biometry plugin (`utils/biometricKeys.ts`) and
+/// hands it over with `unlock_vault_with_key`, so it survives restarts.
+static KEYCHAIN: StoredKeys = StoredKeys(AuthMethod::Biometric);
+static HARDWARE_KEY: StoredKeys = StoredKeys(AuthMethod::HardwareKey);
+
+pub fn provider(method: AuthMethod) -> &'static dyn KeyProvider {
+    match method {
+        AuthMethod::Password => &PASSWORD,
+        AuthMethod::OAuth => &OAUTH,
+        AuthMethod::Biometric => &KEYCHAIN,
+        AuthMethod::HardwareKey => &HARDWARE_KEY,
+    }
+}
+
+/// Looks up the provider for a Vault's `kdf` tag.
+pub fn for_tag(kdf: &str) -> Result<&'static dyn KeyProvider, String> {
+    AuthMethod::from_vault_tag(kdf)
+        .map(provider)
+        .ok_or_else(|| format!("Unknown KDF: {}", kdf))
+}
+
+/// Derives the key of a Vault (or a file written like one) from its header.
+pub fn vault_key(kdf: &str, salt: &str, credentials: &Credentials) -> Result<[u8; 32], String> {
+    for_tag(kdf)?.derive(credentials, salt)
+}
+
+/// Turns what the user typed for a Vault into credentials for its method: its
+/// master password, or a stored key in hex. An OAuth Vault takes the identity
+/// its provider vouched for instead.
+pub fn credentials_for<'a>(
+    kdf: &str,
+    credential: &'a str,
+    identity: Option<&'a Identity>,
+) -> Result<Credentials<'a>, String> {
+    match AuthMethod::from_vault_tag(kdf) {
+        Some(AuthMethod::Password) => Ok(Credentials::Password(credential)),
+        Some(AuthMethod::OAuth) => identity
+            .map(Credentials::Identity)
+            .ok_or_else(|| "Sign in to open this vault".to_string()),
+        Some(AuthMethod::Biometric | AuthMethod::HardwareKey) => {
+            Ok(Credentials::Key(decode_key_hex(credential)?))
+        }
+        None => Err(format!("Unknown KDF: {}", kdf)),
+    }
+}
+
+/// Decodes a hex key handed over by the keychain or a hardware key.
+pub fn decode_key_hex(key_hex: &str) -> Result<[u8; 32], String> {
+    hex::decode(key_hex)
+        .map_err(|e| format!("Invalid key hex: {}", e))?
+        .try_into()
+        .map_err(|_| "Invalid key: must be 32 bytes".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_tag_resolves_to_its_provider() {
+        for tag in AuthMethod::all_tags() {
+            let method = AuthMethod::from_vault_tag(tag).unwrap();
+            assert_eq!(for_tag(tag).unwrap().method(), method);
+        }
+        assert!(for_tag("rot13").is_err());
+    }
+
+    #[test]
+    fn password_keys_roundtrip_through_the_stored_salt() {
+        let enrollment = provider(AuthMethod::Password)
+            .enroll(&Credentials::Password("correct horse"))
+            .unwrap();
+        let tag = enrollment.method.vault_tag();
+
+        let key = vault_key(
+            tag,
+            &enrollment.salt,
+            &Credentials::Password("correct horse"),
+        );
+        assert_eq!(key.unwrap(), enrollment.key);
+        let wrong = vault_key(tag, &enrollment.salt, &Credentials::Password("battery"));
+        assert_ne!(wrong.unwrap(), enrollment.key);
+    }
+
+    #[test]
+    fn providers_reject_other_kinds_of_credentials() {
+        let key = [7u8; 32];
+        assert!(vault_key("password-pbkdf2", "", &Credentials::Key(key)).is_err());
+        assert!(vault_key("biometric-keychain", "", &Credentials::Password("pw")).is_err());
+        assert_eq!(
+            vault_key("hardware-key", "", &Credentials::Key(key)).unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn github_accounts_get_their_own_keys_and_vaults() {
+        let github = Identity {
+            provider: OAuthProvider::GitHub,
+            subject: "583231".to_string(),
+        };
+        let enrollment = provider(AuthMethod::OAuth)
+            .enroll(&Credentials::Identity(&github))
+            .unwrap();
+        assert_eq!(enrollment.salt, "github:583231");
+        assert_eq!(
+            OAuthProvider::for_vault(enrollment.method.vault_tag(), &enrollment.salt),
+            Some(OAuthProvider::GitHub)
+        );
+        let key = vault_key(
+            "oauth-argon2id",
+            &enrollment.salt,
+            &Credentials::Identity(&github),
+        );
+        assert_eq!(key.unwrap(), enrollment.key);
+        assert_ne!(
+            enrollment.key,
+            super::super::oauth::derive_key("583231").unwrap()
+        );
+
+        // A Google Vault won't take a GitHub sign-in.
+        assert!(vault_key(
+            "oauth-argon2id",
+            "1234567890",
+            &Credentials::Identity(&github)
+        )
+        .is_err());
+        assert_eq!(
+            OAuthProvider::for_vault("password-pbkdf2", "github:1"),
+            None
+        );
+    }
+
+    #[test]
+    fn key_hex_must_be_32_bytes() {
+        assert!(decode_key_hex(&"ab".repeat(32)).is_ok());
+        assert!(decode_key_hex("abcd").is_err());
+        assert!(decode_key_hex("zz").is_err());
+    }
+}
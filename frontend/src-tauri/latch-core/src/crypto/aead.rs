@@ -0,0 +1,170 @@
+use super::stream::{self, PREFIX_SIZE};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+
+/// AES-GCM nonces are 12 bytes, stored as hex.
+const NONCE_HEX_LEN: usize = 24;
+/// Ciphertext can't exceed the Vault file it is stored in.
+const MAX_CIPHERTEXT_LEN: usize = crate::vault::storage::MAX_VAULT_BYTES as usize;
+/// Plaintext larger than this is sealed in chunks (see `stream`) and stored as
+/// base64, which grows it by a third rather than doubling it as hex does.
+pub const CHUNKED_ABOVE: usize = 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Encoding {
+    /// One AES-GCM seal, hex-encoded, with a 12-byte nonce.
+    #[default]
+    Hex,
+    /// Chunks sealed by `stream`, base64-encoded; the nonce is their 7-byte prefix.
+    ChunkedBase64,
+}
+
+impl Encoding {
+    fn is_hex(&self) -> bool {
+        *self == Encoding::Hex
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedData {
+    pub nonce: String,
+    pub ciphertext: String,
+    #[serde(default, skip_serializing_if = "Encoding::is_hex")]
+    pub encoding: Encoding,
+}
+
+impl EncryptedData {
+    /// Rejects malformed nonces and oversized ciphertext before anything is decoded.
+    pub fn check_lengths(&self) -> Result<(), String> {
+        let (nonce_len, block) = match self.encoding {
+            Encoding::Hex => (NONCE_HEX_LEN, 2),
+            Encoding::ChunkedBase64 => (PREFIX_SIZE * 2, 4),
+        };
+        if self.nonce.len() != nonce_len {
+            return Err(format!(
+                "Invalid nonce length: expected {} hex characters",
+                nonce_len
+            ));
+        }
+        if self.ciphertext.len() > MAX_CIPHERTEXT_LEN || self.ciphertext.len() % block != 0 {
+            return Err("Invalid ciphertext length".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn encrypt(key: &[u8; 32], plaintext: &str) -> Result<EncryptedData, String> {
+    if plaintext.len() > CHUNKED_ABOVE {
+        let (prefix, sealed) = stream::encrypt_chunked(key, plaintext.as_bytes())?;
+        return Ok(EncryptedData {
+            nonce: hex::encode(prefix),
+            ciphertext: STANDARD.encode(sealed),
+            encoding: Encoding::ChunkedBase64,
+        });
+    }
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    Ok(EncryptedData {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+        encoding: Encoding::Hex,
+    })
+}
+
+pub fn decrypt(key: &[u8; 32], data: &EncryptedData) -> Result<String, String> {
+    data.check_lengths()?;
+    if data.encoding == Encoding::ChunkedBase64 {
+        let mut prefix = [0u8; PREFIX_SIZE];
+        hex::decode_to_slice(&data.nonce, &mut prefix)
+            .map_err(|e| format!("Invalid nonce encoding: {}", e))?;
+        let sealed = STANDARD
+            .decode(&data.ciphertext)
+            .map_err(|e| format!("Invalid ciphertext encoding: {}", e))?;
+        let plaintext = stream::decrypt_chunked(key, prefix, &sealed)?;
+        return String::from_utf8(plaintext)
+            .map_err(|e| format!("Invalid UTF-8 in decrypted data: {}", e));
+    }
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce_bytes =
+        hex::decode(&data.nonce).map_err(|e| format!("Invalid nonce encoding: {}", e))?;
+    let ciphertext =
+        hex::decode(&data.ciphertext).map_err(|e| format!("Invalid ciphertext encoding: {}", e))?;
+
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext.as_ref())
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("Invalid UTF-8 in decrypted data: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key = [42u8; 32];
+        let plaintext = "hello world";
+        let encrypted = encrypt(&key, plaintext).unwrap();
+        let decrypted = decrypt(&key, &encrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_key_fails() {
+        let key1 = [1u8; 32];
+        let key2 = [2u8; 32];
+        let encrypted = encrypt(&key1, "secret").unwrap();
+        assert!(decrypt(&key2, &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tampered_ciphertext_fails() {
+        let key = [1u8; 32];
+        let mut encrypted = encrypt(&key, "secret").unwrap();
+        encrypted.ciphertext = "deadbeef".to_string();
+        assert!(decrypt(&key, &encrypted).is_err());
+    }
+
+    #[test]
+    fn malformed_lengths_are_rejected_before_decoding() {
+        let key = [1u8; 32];
+        let mut short_nonce = encrypt(&key, "secret").unwrap();
+        short_nonce.nonce.truncate(8);
+        assert!(decrypt(&key, &short_nonce)
+            .unwrap_err()
+            .contains("nonce length"));
+
+        let mut odd = encrypt(&key, "secret").unwrap();
+        odd.ciphertext.push('a');
+        assert_eq!(
+            decrypt(&key, &odd).unwrap_err(),
+            "Invalid ciphertext length"
+        );
+    }
+
+    #[test]
+    fn large_payloads_are_chunked_and_base64_encoded() {
+        let key = [1u8; 32];
+        let plaintext = "x".repeat(CHUNKED_ABOVE + 1);
+        let encrypted = encrypt(&key, &plaintext).unwrap();
+        assert_eq!(encrypted.encoding, Encoding::ChunkedBase64);
+        assert!(encrypted.ciphertext.len() < plaintext.len() * 3 / 2);
+        assert_eq!(decrypt(&key, &encrypted).unwrap(), plaintext);
+
+        let json = serde_json::to_string(&encrypt(&key, "small").unwrap()).unwrap();
+        assert!(!json.contains("encoding"));
+    }
+}
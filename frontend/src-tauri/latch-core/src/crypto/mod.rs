@@ -0,0 +1,2 @@
+pub mod aead;
+pub mod stream;
@@ -0,0 +1,253 @@
+//! Segmented AES-GCM for payloads too large to seal in one piece. The plaintext is
+//! cut into `CHUNK_SIZE` chunks, each sealed on its own under a nonce made of a
+//! random prefix, the chunk's index and a flag marking the last chunk, so chunks
+//! can't be reordered, dropped or cut off at the end without failing to decrypt.
+//! Only one chunk is held in memory at a time when reading and writing streams.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::io::{Read, Write};
+
+/// Plaintext bytes per chunk.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+/// Bytes an AES-GCM tag adds to each chunk.
+const TAG_SIZE: usize = 16;
+pub const PREFIX_SIZE: usize = 7;
+/// Starts a stream written by `encrypt_stream`, ahead of the nonce prefix.
+const MAGIC: &[u8; 4] = b"LCS1";
+
+struct Segments {
+    cipher: Aes256Gcm,
+    prefix: [u8; PREFIX_SIZE],
+    counter: u32,
+}
+
+impl Segments {
+    fn new(key: &[u8; 32], prefix: [u8; PREFIX_SIZE]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+            prefix,
+            counter: 0,
+        }
+    }
+
+    fn nonce(&mut self, last: bool) -> Result<[u8; 12], String> {
+        let mut nonce = [0u8; 12];
+        nonce[..PREFIX_SIZE].copy_from_slice(&self.prefix);
+        nonce[PREFIX_SIZE..11].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[11] = u8::from(last);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or("Payload is too large to encrypt")?;
+        Ok(nonce)
+    }
+
+    fn seal(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, String> {
+        let nonce = self.nonce(last)?;
+        self.cipher
+            .encrypt(Nonce::from_slice(&nonce), chunk)
+            .map_err(|e| format!("Encryption failed: {}", e))
+    }
+
+    fn open(&mut self, sealed: &[u8], last: bool) -> Result<Vec<u8>, String> {
+        let nonce = self.nonce(last)?;
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), sealed)
+            .map_err(|e| format!("Decryption failed: {}", e))
+    }
+}
+
+fn random_prefix() -> [u8; PREFIX_SIZE] {
+    let mut prefix = [0u8; PREFIX_SIZE];
+    OsRng.fill_bytes(&mut prefix);
+    prefix
+}
+
+/// Reads until `buf` is full or the input ends; returns how much was read.
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read payload: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// Seals `reader` into `chunk_size`-byte chunks and hands each to `emit`. An
+/// empty input still gets one (empty) last chunk, so truncation to nothing is
+/// caught.
+fn seal_chunks(
+    segments: &mut Segments,
+    reader: &mut impl Read,
+    chunk_size: usize,
+    mut emit: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut current = vec![0u8; chunk_size];
+    let mut next = vec![0u8; chunk_size];
+    let mut len = fill(reader, &mut current)?;
+    loop {
+        let next_len = if len == chunk_size {
+            fill(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        emit(&segments.seal(&current[..len], last)?)?;
+        if last {
+            return Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+    }
+}
+
+/// Opens sealed chunks from `reader`, handing each plaintext chunk to `emit`.
+fn open_chunks(
+    segments: &mut Segments,
+    reader: &mut impl Read,
+    chunk_size: usize,
+    mut emit: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let sealed_size = chunk_size + TAG_SIZE;
+    let mut current = vec![0u8; sealed_size];
+    let mut next = vec![0u8; sealed_size];
+    let mut len = fill(reader, &mut current)?;
+    loop {
+        if len < TAG_SIZE {
+            return Err("Encrypted payload is truncated".to_string());
+        }
+        let next_len = if len == sealed_size {
+            fill(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        emit(&segments.open(&current[..len], last)?)?;
+        if last {
+            return Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+    }
+}
+
+/// Encrypts everything `reader` yields into `writer`, a chunk at a time.
+pub fn encrypt_stream(
+    key: &[u8; 32],
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), String> {
+    let prefix = random_prefix();
+    let write_err = |e: std::io::Error| format!("Failed to write encrypted payload: {}", e);
+    writer.write_all(MAGIC).map_err(write_err)?;
+    writer.write_all(&prefix).map_err(write_err)?;
+    let mut segments = Segments::new(key, prefix);
+    seal_chunks(&mut segments, &mut reader, CHUNK_SIZE, |sealed| {
+        writer.write_all(sealed).map_err(write_err)
+    })?;
+    writer.flush().map_err(write_err)
+}
+
+/// Decrypts a stream written by `encrypt_stream`. Plaintext is written as each
+/// chunk checks out, so on failure the caller must discard what was written.
+pub fn decrypt_stream(
+    key: &[u8; 32],
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), String> {
+    let mut header = [0u8; MAGIC.len() + PREFIX_SIZE];
+    if fill(&mut reader, &mut header)? != header.len() || &header[..MAGIC.len()] != MAGIC {
+        return Err("Not an encrypted Latch stream".to_string());
+    }
+    let mut prefix = [0u8; PREFIX_SIZE];
+    prefix.copy_from_slice(&header[MAGIC.len()..]);
+    let write_err = |e: std::io::Error| format!("Failed to write decrypted payload: {}", e);
+    let mut segments = Segments::new(key, prefix);
+    open_chunks(&mut segments, &mut reader, CHUNK_SIZE, |chunk| {
+        writer.write_all(chunk).map_err(write_err)
+    })?;
+    writer.flush().map_err(write_err)
+}
+
+/// Seals `plaintext` in memory; returns the nonce prefix and the sealed chunks
+/// back to back.
+pub fn encrypt_chunked(
+    key: &[u8; 32],
+    plaintext: &[u8],
+) -> Result<([u8; PREFIX_SIZE], Vec<u8>), String> {
+    let prefix = random_prefix();
+    let chunks = plaintext.len() / CHUNK_SIZE + 1;
+    let mut sealed = Vec::with_capacity(plaintext.len() + chunks * TAG_SIZE);
+    let mut segments = Segments::new(key, prefix);
+    seal_chunks(&mut segments, &mut &plaintext[..], CHUNK_SIZE, |chunk| {
+        sealed.extend_from_slice(chunk);
+        Ok(())
+    })?;
+    Ok((prefix, sealed))
+}
+
+pub fn decrypt_chunked(
+    key: &[u8; 32],
+    prefix: [u8; PREFIX_SIZE],
+    sealed: &[u8],
+) -> Result<Vec<u8>, String> {
+    let mut plaintext = Vec::with_capacity(sealed.len());
+    let mut segments = Segments::new(key, prefix);
+    open_chunks(&mut segments, &mut &sealed[..], CHUNK_SIZE, |chunk| {
+        plaintext.extend_from_slice(chunk);
+        Ok(())
+    })?;
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn streams_round_trip_across_chunk_boundaries() {
+        let key = [7u8; 32];
+        for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE] {
+            let plaintext = payload(len);
+            let mut sealed = Vec::new();
+            encrypt_stream(&key, &plaintext[..], &mut sealed).unwrap();
+            let mut opened = Vec::new();
+            decrypt_stream(&key, &sealed[..], &mut opened).unwrap();
+            assert_eq!(opened, plaintext, "length {}", len);
+
+            let (prefix, chunks) = encrypt_chunked(&key, &plaintext).unwrap();
+            assert_eq!(decrypt_chunked(&key, prefix, &chunks).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn dropped_or_reordered_chunks_fail() {
+        let key = [7u8; 32];
+        let plaintext = payload(3 * CHUNK_SIZE);
+        let (prefix, sealed) = encrypt_chunked(&key, &plaintext).unwrap();
+        let sealed_size = CHUNK_SIZE + TAG_SIZE;
+
+        // Cut off after the first two chunks, which then ends on one not marked last.
+        assert!(decrypt_chunked(&key, prefix, &sealed[..2 * sealed_size]).is_err());
+
+        let mut swapped = sealed.clone();
+        swapped[..sealed_size].copy_from_slice(&sealed[sealed_size..2 * sealed_size]);
+        swapped[sealed_size..2 * sealed_size].copy_from_slice(&sealed[..sealed_size]);
+        assert!(decrypt_chunked(&key, prefix, &swapped).is_err());
+
+        assert!(decrypt_chunked(&[8u8; 32], prefix, &sealed).is_err());
+        assert!(decrypt_stream(&key, &b"not a stream"[..], Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Site icons for Credentials. An icon is looked up by domain from DuckDuckGo's or
+//! Google's favicon service, or the site's own `/favicon.ico`, and kept in an
+//! on-disk cache so Credentials on the same site, and later refreshes, don't
+//! fetch it again.
+//!
+//! Which icons are cached would tell anyone reading the disk which sites the user
+//! has accounts on, so each is encrypted with the Session key and filed under a
+//! keyed hash of its domain. A Credential's `icon_url` is a `latch-icon://` URL
+//! that the app's protocol handler answers from the cache while unlocked.
+
+use crate::crypto::aead::{self, EncryptedData};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// The URI scheme icons are served under.
+pub const SCHEME: &str = "latch-icon";
+const MAX_ICON_BYTES: usize = 64 * 1024;
+/// A cached icon older than this is fetched again, but still used offline.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The host a Credential's URL points at, lowercased. A URL without a scheme is
+/// taken as `https`.
+pub fn domain_of(url: &str) -> Option<String> {
+    let url = url.trim();
+    let parsed = if url.contains("://") {
+        Url::parse(url).ok()?
+    } else {
+        Url::parse(&format!("https://{}", url)).ok()?
+    };
+    if !matches!(parsed.scheme(), "https" | "http") {
+        return None;
+    }
+    let host = parsed.host_str()?.trim_end_matches('.').to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+fn sources(domain: &str) -> [String; 3] {
+    [
+        format!("https://icons.duckduckgo.com/ip3/{}.ico", domain),
+        format!("https://www.google.com/s2/favicons?domain={}&sz=64", domain),
+        format!("https://{}/favicon.ico", domain),
+    ]
+}
+
+/// The image type of `bytes`, from their first bytes; None for anything that
+/// isn't a bitmap image. SVG is left out since it can carry script.
+fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0, 0, 1, 0, ..] => Some("image/x-icon"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// The `icon_url` for a domain's cached icon.
+pub fn icon_url(domain: &str) -> String {
+    format!("{}://localhost/{}", SCHEME, domain)
+}
+
+/// The domain a `latch-icon://` request or `icon_url` asks for.
+pub fn requested_domain(path: &str) -> &str {
+    path.trim_start_matches('/').trim_end_matches('/')
+}
+
+/// A plain tile with the domain's first letter, for when no icon can be fetched.
+pub fn fallback(domain: &str) -> String {
+    let name = domain.strip_prefix("www.").unwrap_or(domain);
+    let letter = name
+        .chars()
+        .find(|c| c.is_ascii_alphanumeric())
+        .unwrap_or('?')
+        .to_ascii_uppercase();
+    let svg = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 32 32'>\
+         <rect width='32' height='32' rx='6' fill='#6b7280'/>\
+         <text x='16' y='22' font-size='18' font-family='sans-serif' \
+         text-anchor='middle' fill='#ffffff'>{}</text></svg>",
+        letter
+    );
+    format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg))
+}
+
+pub struct IconCache {
+    dir: PathBuf,
+}
+
+impl IconCache {
+    pub fn new() -> Result<Self, String> {
+        let cache = Self::at(crate::vault::storage::config_dir()?.join("icons"));
+        cache.remove_unencrypted();
+        Ok(cache)
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Earlier versions kept icons in plain files named after their domain.
+    fn remove_unencrypted(&self) {
+        let Ok(files) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for file in files.flatten() {
+            let name = file.file_name();
+            let name = name.to_string_lossy();
+            if !(name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit())) {
+                let _ = fs::remove_file(file.path());
+            }
+        }
+    }
+
+    fn path(&self, key: &[u8; 32], domain: &str) -> PathBuf {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(b"latch-icon:");
+        mac.update(domain.as_bytes());
+        self.dir.join(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// The cached icon and whether it is due for a refetch. One written under
+    /// another key reads as missing.
+    fn cached(&self, key: &[u8; 32], domain: &str) -> Option<(Vec<u8>, bool)> {
+        let path = self.path(key, domain);
+        let sealed = fs::read_to_string(&path).ok()?;
+        let encrypted: EncryptedData = serde_json::from_str(&sealed).ok()?;
+        let bytes = STANDARD.decode(aead::decrypt(key, &encrypted).ok()?).ok()?;
+        let stale = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(true, |age| age > MAX_AGE);
+        Some((bytes, stale))
+    }
+
+    fn store(&self, key: &[u8; 32], domain: &str, bytes: &[u8]) -> Result<(), String> {
+        let sealed = serde_json::to_string(&aead::encrypt(key, &STANDARD.encode(bytes))?)
+            .map_err(|e| format!("Failed to serialize icon: {}", e))?;
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create icon cache: {}", e))?;
+        fs::write(self.path(key, domain), sealed)
+            .map_err(|e| format!("Failed to cache icon: {}", e))
+    }
+
+    /// A cached icon and its content type, for the protocol handler.
+    pub fn load(&self, key: &[u8; 32], domain: &str) -> Option<(Vec<u8>, &'static str)> {
+        let (bytes, _) = self.cached(key, domain)?;
+        let mime = sniff(&bytes)?;
+        Some((bytes, mime))
+    }
+
+    /// The first source with a usable icon.
+    async fn fetch(domain: &str) -> Result<Vec<u8>, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("Failed to start icon fetch: {}", e))?;
+        for source in sources(domain) {
+            let Ok(response) = client.get(&source).send().await else {
+                continue;
+            };
+            if !response.status().is_success() {
+                continue;
+            }
+            let Ok(bytes) = response.bytes().await else {
+                continue;
+            };
+            if bytes.len() <= MAX_ICON_BYTES && sniff(&bytes).is_some() {
+                return Ok(bytes.to_vec());
+            }
+        }
+        Err(format!("No icon found for {}", domain))
+    }
+
+    /// The `icon_url` for the site at `url`: its cached icon when fresh (or when
+    /// it can't be refetched), a newly fetched one otherwise, and the letter tile
+    /// when neither exists. `refresh` skips a fresh cached copy.
+    pub async fn icon_for(&self, key: &[u8; 32], url: &str, refresh: bool) -> Option<String> {
+        let domain = domain_of(url)?;
+        let cached = self.cached(key, &domain);
+        if let Some((_, false)) = &cached {
+            if !refresh {
+                return Some(icon_url(&domain));
+            }
+        }
+        match Self::fetch(&domain).await {
+            Ok(bytes) => match self.store(key, &domain, &bytes) {
+                Ok(()) => Some(icon_url(&domain)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    Some(fallback(&domain))
+                }
+            },
+            Err(_) if cached.is_some() => Some(icon_url(&domain)),
+            Err(_) => Some(fallback(&domain)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+
+    #[test]
+    fn finds_the_domain_of_a_url() {
+        assert_eq!(
+            domain_of("https://Accounts.Example.com/login?next=/"),
+            Some("accounts.example.com".to_string())
+        );
+        assert_eq!(domain_of("example.com"), Some("example.com".to_string()));
+        assert_eq!(domain_of("ftp://example.com"), None);
+        assert_eq!(domain_of(""), None);
+    }
+
+    #[test]
+    fn only_bitmap_images_are_served() {
+        assert_eq!(sniff(PNG), Some("image/png"));
+        assert_eq!(sniff(&[0, 0, 1, 0, 1, 0]), Some("image/x-icon"));
+        assert!(sniff(b"<svg onload='alert(1)'/>").is_none());
+        assert!(sniff(b"<html>not found</html>").is_none());
+        assert!(fallback("www.github.com").starts_with("data:image/svg+xml;base64,"));
+        assert_eq!(requested_domain("/example.com"), "example.com");
+    }
+
+    #[tokio::test]
+    async fn cached_icons_are_encrypted_and_named_by_keyed_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("example.com"), PNG).unwrap();
+        let cache = IconCache::at(dir.path().to_path_buf());
+        cache.remove_unencrypted();
+        let key = [5u8; 32];
+        cache.store(&key, "example.com", PNG).unwrap();
+
+        let files: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|f| f.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert!(!files[0].contains("example"));
+        let sealed = fs::read_to_string(dir.path().join(&files[0])).unwrap();
+        assert!(!sealed.contains(&STANDARD.encode(PNG)));
+
+        assert_eq!(
+            cache.icon_for(&key, "https://example.com/a", false).await,
+            Some(icon_url("example.com"))
+        );
+        assert_eq!(
+            cache.load(&key, "example.com"),
+            Some((PNG.to_vec(), "image/png"))
+        );
+        assert_eq!(cache.load(&[6u8; 32], "example.com"), None);
+    }
+}
@@ -0,0 +1,11 @@
+//! The Vault, its unlock methods and password health, free of any UI. The Tauri app
+//! and the `latch` command line are both thin layers over this crate.
+
+pub mod aliases;
+pub mod auth;
+pub mod crypto;
+pub mod icons;
+pub mod password_generator;
+pub mod share;
+pub mod vault;
+pub mod vault_health;
@@ -0,0 +1,1049 @@
+pub mod history;
+pub mod wordlist;
+
+use rand::distributions::Distribution;
+use rand::distributions::Uniform;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use wordlist::Wordlist;
+use zxcvbn::zxcvbn;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct PasswordOptions {
+    pub length: u32,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub numbers: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    /// Extra characters to draw from on top of the selected classes.
+    #[serde(default)]
+    pub include_chars: String,
+    /// Characters never to use, for sites that reject some symbols.
+    #[serde(default)]
+    pub exclude_chars: String,
+    /// Characters guaranteed from each selected class, since many sites reject a
+    /// password that lacks one; 0 leaves it to chance.
+    #[serde(default = "default_min_per_class")]
+    pub min_per_class: u32,
+    /// Random characters by default; the fields above only apply to those.
+    #[serde(default)]
+    pub mode: PasswordMode,
+}
+
+fn default_min_per_class() -> u32 {
+    1
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        PasswordOptions {
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            numbers: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            include_chars: String::new(),
+            exclude_chars: String::new(),
+            min_per_class: default_min_per_class(),
+            mode: PasswordMode::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PasswordMode {
+    #[default]
+    Characters,
+    /// Diceware: words drawn at random from an EFF list.
+    Passphrase(PassphraseOptions),
+    /// Made-up syllables, for passwords read aloud or typed on a TV remote.
+    Pronounceable(PronounceableOptions),
+    /// Digits only, for device PINs and bank codes.
+    Pin(PinOptions),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct PinOptions {
+    pub digits: u32,
+}
+
+impl Default for PinOptions {
+    fn default() -> Self {
+        PinOptions { digits: 6 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct PronounceableOptions {
+    pub syllables: u32,
+    /// End each syllable in a consonant too (`bak` rather than `ba`).
+    pub closed_syllables: bool,
+    /// Start one syllable, picked at random, with a capital.
+    pub capitalize: bool,
+    /// Random digits at the end.
+    pub digits: u32,
+}
+
+impl Default for PronounceableOptions {
+    fn default() -> Self {
+        PronounceableOptions {
+            syllables: 8,
+            closed_syllables: false,
+            capitalize: true,
+            digits: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Capitalization {
+    #[default]
+    Lowercase,
+    TitleCase,
+    Uppercase,
+    /// One word, picked at random, in capitals.
+    RandomWord,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct PassphraseOptions {
+    pub wordlist: Wordlist,
+    pub words: u32,
+    pub separator: String,
+    pub capitalization: Capitalization,
+    /// Random digits, each appended to a word picked at random.
+    pub digits: u32,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            wordlist: Wordlist::EffLong,
+            words: 6,
+            separator: "-".to_string(),
+            capitalization: Capitalization::Lowercase,
+            digits: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct StrengthReport {
+    pub score: u8,
+    pub entropy: f64,
+    pub label: String,
+    pub warnings: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+/// Where a password is used. Its parts are handed to zxcvbn as words an attacker
+/// would try first, so `facebook-john-2024` scores weak for john's Facebook login.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct PasswordContext {
+    pub title: String,
+    pub username: String,
+    pub url: Option<String>,
+}
+
+/// Pieces of a URL or address that say nothing about the account.
+const CONTEXT_NOISE: &[&str] = &["www", "com", "org", "net", "http", "https", "login"];
+
+impl PasswordContext {
+    /// Each field whole and split into words, lowercased.
+    fn words(&self) -> Vec<String> {
+        let fields = [
+            self.title.as_str(),
+            self.username.as_str(),
+            self.url.as_deref().unwrap_or_default(),
+        ];
+        let mut words: Vec<String> = Vec::new();
+        for field in fields {
+            let field = field.trim().to_lowercase();
+            let parts = field
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|part| part.chars().count() >= MIN_DICTIONARY_WORD_LEN)
+                .filter(|part| !CONTEXT_NOISE.contains(part));
+            for word in std::iter::once(field.as_str()).chain(parts) {
+                if !word.is_empty() && !words.iter().any(|w| w == word) {
+                    words.push(word.to_string());
+                }
+            }
+        }
+        words
+    }
+}
+
+/// Largest custom dictionary accepted, across settings and imported word lists.
+pub const MAX_DICTIONARY_WORDS: usize = 5000;
+pub const MIN_DICTIONARY_WORD_LEN: usize = 3;
+const MAX_GENERATION_ATTEMPTS: usize = 32;
+const MIN_PASSPHRASE_WORDS: u32 = 3;
+const MAX_PASSPHRASE_WORDS: u32 = 20;
+const MAX_SEPARATOR_LEN: usize = 5;
+const MAX_PASSPHRASE_DIGITS: u32 = 10;
+const MAX_CUSTOM_CHARS: usize = 256;
+const MIN_SYLLABLES: u32 = 4;
+const MAX_SYLLABLES: u32 = 20;
+const MIN_PIN_DIGITS: u32 = 4;
+const MAX_PIN_DIGITS: u32 = 12;
+
+const AMBIGUOUS_CHARS: &[char] = &['0', 'O', '1', 'l', 'I'];
+
+const LOWERCASE: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+const UPPERCASE: &[char] = &[
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+
+const NUMBERS: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+/// Consonants that sound the same in most languages; `c`, `q`, `x` and `y` are
+/// left out because they don't.
+const SYLLABLE_CONSONANTS: &[char] = &[
+    'b', 'd', 'f', 'g', 'h', 'j', 'k', 'l', 'm', 'n', 'p', 'r', 's', 't', 'v', 'z',
+];
+
+const SYLLABLE_VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u'];
+
+const SYMBOLS: &[char] = &[
+    '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '-', '_', '=', '+', '[', ']', '{', '}', '|',
+    '\\', ':', ';', '"', '\'', '<', '>', ',', '.', '?', '/', '~', '`',
+];
+
+/// The characters a `Characters` password draws from, after validating its length.
+fn charset(options: &PasswordOptions) -> Result<Vec<char>, String> {
+    if options.length < 8 {
+        return Err("Password length must be at least 8 characters".to_string());
+    }
+    if options.length > 128 {
+        return Err("Password length cannot exceed 128 characters".to_string());
+    }
+
+    let mut charset: Vec<char> = Vec::new();
+
+    if options.lowercase {
+        charset.extend(LOWERCASE);
+    }
+    if options.uppercase {
+        charset.extend(UPPERCASE);
+    }
+    if options.numbers {
+        charset.extend(NUMBERS);
+    }
+    if options.symbols {
+        charset.extend(SYMBOLS);
+    }
+    if options.include_chars.chars().count() > MAX_CUSTOM_CHARS {
+        return Err(format!(
+            "Included characters cannot exceed {}",
+            MAX_CUSTOM_CHARS
+        ));
+    }
+    if let Some(c) = options
+        .include_chars
+        .chars()
+        .find(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(format!(
+            "Invalid included character {:?}: spaces and control characters are not allowed",
+            c
+        ));
+    }
+    for c in options.include_chars.chars() {
+        if !charset.contains(&c) {
+            charset.push(c);
+        }
+    }
+
+    if charset.is_empty() {
+        return Err("At least one character type must be selected".to_string());
+    }
+
+    let final_charset: Vec<char> = charset
+        .into_iter()
+        .filter(|c| allowed(options, *c))
+        .collect();
+
+    if final_charset.is_empty() {
+        return Err("No characters available after exclusions".to_string());
+    }
+    Ok(final_charset)
+}
+
+fn allowed(options: &PasswordOptions, c: char) -> bool {
+    let ambiguous = options.exclude_ambiguous && AMBIGUOUS_CHARS.contains(&c);
+    !ambiguous && !options.exclude_chars.contains(c)
+}
+
+/// The selected classes, less excluded characters, that each have to appear
+/// `min_per_class` times. Empty when nothing is guaranteed.
+fn required_classes(options: &PasswordOptions) -> Result<Vec<Vec<char>>, String> {
+    if options.min_per_class == 0 {
+        return Ok(Vec::new());
+    }
+    let selected = [
+        (options.lowercase, LOWERCASE, "lowercase"),
+        (options.uppercase, UPPERCASE, "uppercase"),
+        (options.numbers, NUMBERS, "number"),
+        (options.symbols, SYMBOLS, "symbol"),
+    ];
+    let mut classes = Vec::new();
+    for (enabled, class, name) in selected {
+        if !enabled {
+            continue;
+        }
+        let chars: Vec<char> = class
+            .iter()
+            .copied()
+            .filter(|c| allowed(options, *c))
+            .collect();
+        if chars.is_empty() {
+            return Err(format!("No {} characters left after exclusions", name));
+        }
+        classes.push(chars);
+    }
+    if classes.len() as u32 * options.min_per_class > options.length {
+        return Err(format!(
+            "Invalid minimum per class: {} classes of {} do not fit in {} characters",
+            classes.len(),
+            options.min_per_class,
+            options.length
+        ));
+    }
+    Ok(classes)
+}
+
+pub fn generate_password(options: &PasswordOptions) -> Result<String, String> {
+    match &options.mode {
+        PasswordMode::Characters => {}
+        PasswordMode::Passphrase(passphrase) => return generate_passphrase(passphrase),
+        PasswordMode::Pronounceable(pronounceable) => return generate_pronounceable(pronounceable),
+        PasswordMode::Pin(pin) => return generate_pin(pin),
+    }
+    let final_charset = charset(options)?;
+    let classes = required_classes(options)?;
+
+    // The guaranteed characters come first and the shuffle spreads them out;
+    // ThreadRng is a CSPRNG, so every draw and the shuffle stay unpredictable.
+    let mut rng = thread_rng();
+    let mut password: Vec<char> = Vec::with_capacity(options.length as usize);
+    for class in &classes {
+        let dist = Uniform::new(0, class.len());
+        for _ in 0..options.min_per_class {
+            password.push(class[dist.sample(&mut rng)]);
+        }
+    }
+    let dist = Uniform::new(0, final_charset.len());
+    while password.len() < options.length as usize {
+        password.push(final_charset[dist.sample(&mut rng)]);
+    }
+    password.shuffle(&mut rng);
+
+    Ok(password.into_iter().collect())
+}
+
+/// Bits of entropy in a password generated with `options`, counting only the
+/// random choices made (not how guessable the result looks). Where choices can
+/// collide, as digits appended to the same passphrase word, the smaller figure is
+/// given. Characters guaranteed per class count as drawn from their class alone.
+pub fn entropy_bits(options: &PasswordOptions) -> Result<f64, String> {
+    match &options.mode {
+        PasswordMode::Characters => {
+            let charset = charset(options)?;
+            let classes = required_classes(options)?;
+            let guaranteed: u32 = classes.len() as u32 * options.min_per_class;
+            let class_bits: f64 = classes
+                .iter()
+                .map(|class| f64::from(options.min_per_class) * (class.len() as f64).log2())
+                .sum();
+            Ok(class_bits + f64::from(options.length - guaranteed) * (charset.len() as f64).log2())
+        }
+        PasswordMode::Passphrase(passphrase) => {
+            check_passphrase(passphrase)?;
+            let words = f64::from(passphrase.words);
+            let mut bits = words * (passphrase.wordlist.word_count() as f64).log2();
+            if passphrase.capitalization == Capitalization::RandomWord {
+                bits += words.log2();
+            }
+            Ok(bits + f64::from(passphrase.digits) * (NUMBERS.len() as f64).log2())
+        }
+        PasswordMode::Pronounceable(pronounceable) => {
+            check_pronounceable(pronounceable)?;
+            let mut per_syllable =
+                ((SYLLABLE_CONSONANTS.len() * SYLLABLE_VOWELS.len()) as f64).log2();
+            if pronounceable.closed_syllables {
+                per_syllable += (SYLLABLE_CONSONANTS.len() as f64).log2();
+            }
+            let syllables = f64::from(pronounceable.syllables);
+            let mut bits = syllables * per_syllable;
+            if pronounceable.capitalize {
+                bits += syllables.log2();
+            }
+            Ok(bits + f64::from(pronounceable.digits) * (NUMBERS.len() as f64).log2())
+        }
+        PasswordMode::Pin(pin) => {
+            check_pin(pin)?;
+            let all = (NUMBERS.len() as f64).powi(pin.digits as i32);
+            Ok((all - weak_pin_count(pin.digits) as f64).log2())
+        }
+    }
+}
+
+fn check_passphrase(options: &PassphraseOptions) -> Result<(), String> {
+    if !(MIN_PASSPHRASE_WORDS..=MAX_PASSPHRASE_WORDS).contains(&options.words) {
+        return Err(format!(
+            "Passphrases must have {} to {} words",
+            MIN_PASSPHRASE_WORDS, MAX_PASSPHRASE_WORDS
+        ));
+    }
+    if options.separator.chars().count() > MAX_SEPARATOR_LEN {
+        return Err(format!(
+            "Separator cannot exceed {} characters",
+            MAX_SEPARATOR_LEN
+        ));
+    }
+    if options.digits > MAX_PASSPHRASE_DIGITS {
+        return Err(format!(
+            "Passphrases cannot have more than {} digits",
+            MAX_PASSPHRASE_DIGITS
+        ));
+    }
+    Ok(())
+}
+
+pub fn generate_passphrase(options: &PassphraseOptions) -> Result<String, String> {
+    check_passphrase(options)?;
+    Ok(passphrase_from(
+        options,
+        wordlist::words(options.wordlist)?,
+        &mut thread_rng(),
+    ))
+}
+
+fn check_pronounceable(options: &PronounceableOptions) -> Result<(), String> {
+    if !(MIN_SYLLABLES..=MAX_SYLLABLES).contains(&options.syllables) {
+        return Err(format!(
+            "Pronounceable passwords must have {} to {} syllables",
+            MIN_SYLLABLES, MAX_SYLLABLES
+        ));
+    }
+    if options.digits > MAX_PASSPHRASE_DIGITS {
+        return Err(format!(
+            "Pronounceable passwords cannot have more than {} digits",
+            MAX_PASSPHRASE_DIGITS
+        ));
+    }
+    Ok(())
+}
+
+/// Syllables of a consonant and a vowel (plus a closing consonant when asked),
+/// then the digits. The pattern is fixed so every choice counts in `entropy_bits`.
+pub fn generate_pronounceable(options: &PronounceableOptions) -> Result<String, String> {
+    check_pronounceable(options)?;
+    let mut rng = thread_rng();
+    let mut pick = |letters: &[char]| letters[rng.gen_range(0..letters.len())];
+    let mut syllables: Vec<String> = (0..options.syllables)
+        .map(|_| {
+            let mut syllable = String::new();
+            syllable.push(pick(SYLLABLE_CONSONANTS));
+            syllable.push(pick(SYLLABLE_VOWELS));
+            if options.closed_syllables {
+                syllable.push(pick(SYLLABLE_CONSONANTS));
+            }
+            syllable
+        })
+        .collect();
+    let digits: String = (0..options.digits).map(|_| pick(NUMBERS)).collect();
+    if options.capitalize {
+        let idx = rng.gen_range(0..syllables.len());
+        syllables[idx] = title_case(&syllables[idx]);
+    }
+    let mut password = syllables.concat();
+    password.push_str(&digits);
+    Ok(password)
+}
+
+fn check_pin(options: &PinOptions) -> Result<(), String> {
+    if !(MIN_PIN_DIGITS..=MAX_PIN_DIGITS).contains(&options.digits) {
+        return Err(format!(
+            "PINs must have {} to {} digits",
+            MIN_PIN_DIGITS, MAX_PIN_DIGITS
+        ));
+    }
+    Ok(())
+}
+
+/// Random digits, redrawn whole while they make a PIN anyone would try first.
+/// Redrawing rather than patching keeps the rest equally likely.
+pub fn generate_pin(options: &PinOptions) -> Result<String, String> {
+    check_pin(options)?;
+    let mut rng = thread_rng();
+    loop {
+        let pin: String = (0..options.digits)
+            .map(|_| NUMBERS[rng.gen_range(0..NUMBERS.len())])
+            .collect();
+        if !is_weak_pin(&pin) {
+            return Ok(pin);
+        }
+    }
+}
+
+/// One digit repeated, a run up or down (`1234`, `9876`), or a year (`1900`
+/// to `2099`).
+fn is_weak_pin(pin: &str) -> bool {
+    let digits: Vec<i8> = pin.bytes().map(|b| (b - b'0') as i8).collect();
+    let steps: Vec<i8> = digits.windows(2).map(|w| w[1] - w[0]).collect();
+    if [0, 1, -1].iter().any(|&d| steps.iter().all(|&s| s == d)) {
+        return true;
+    }
+    pin.len() == 4 && matches!(pin.parse::<u32>(), Ok(1900..=2099))
+}
+
+/// How many PINs of `digits` digits `is_weak_pin` rejects; the sets don't
+/// overlap.
+fn weak_pin_count(digits: u32) -> u64 {
+    let repeated = NUMBERS.len() as u64;
+    let runs = 2 * (NUMBERS.len() as u64 + 1).saturating_sub(u64::from(digits));
+    let years = if digits == 4 { 200 } else { 0 };
+    repeated + runs + years
+}
+
+fn title_case(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn passphrase_from<R: Rng>(options: &PassphraseOptions, list: &[String], rng: &mut R) -> String {
+    let mut words: Vec<String> = (0..options.words)
+        .map(|_| list[rng.gen_range(0..list.len())].clone())
+        .collect();
+    match options.capitalization {
+        Capitalization::Lowercase => {}
+        Capitalization::TitleCase => words.iter_mut().for_each(|w| *w = title_case(w)),
+        Capitalization::Uppercase => words.iter_mut().for_each(|w| *w = w.to_uppercase()),
+        Capitalization::RandomWord => {
+            let idx = rng.gen_range(0..words.len());
+            words[idx] = words[idx].to_uppercase();
+        }
+    }
+    for _ in 0..options.digits {
+        let idx = rng.gen_range(0..words.len());
+        words[idx].push(NUMBERS[rng.gen_range(0..NUMBERS.len())]);
+    }
+    words.join(&options.separator)
+}
+
+/// Generates a password that contains none of the words in `dictionary`, retrying
+/// with fresh randomness when one slips through.
+pub fn generate_password_avoiding(
+    options: &PasswordOptions,
+    dictionary: &[String],
+) -> Result<String, String> {
+    for _ in 0..MAX_GENERATION_ATTEMPTS {
+        let password = generate_password(options)?;
+        if banned_word(&password, dictionary).is_none() {
+            return Ok(password);
+        }
+    }
+    Err("Could not generate a password that avoids the custom dictionary".to_string())
+}
+
+/// Lowercases, trims and de-duplicates dictionary words, dropping ones too short to matter.
+pub fn normalize_dictionary<I: IntoIterator<Item = String>>(words: I) -> Vec<String> {
+    let mut normalized: Vec<String> = words
+        .into_iter()
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| w.chars().count() >= MIN_DICTIONARY_WORD_LEN)
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Undoes common character substitutions so `4cm3` still matches `acme`.
+fn unleet(password: &str) -> String {
+    password
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            other => other,
+        })
+        .collect()
+}
+
+/// Returns the first dictionary word found in the password, plain or leetspeak.
+pub fn banned_word<'a>(password: &str, dictionary: &'a [String]) -> Option<&'a str> {
+    let lower = password.to_lowercase();
+    let unleeted = unleet(password);
+    dictionary
+        .iter()
+        .find(|word| lower.contains(word.as_str()) || unleeted.contains(word.as_str()))
+        .map(|word| word.as_str())
+}
+
+pub fn analyze_password_strength(password: &str, dictionary: &[String]) -> StrengthReport {
+    analyze_password_strength_in_context(password, dictionary, &PasswordContext::default())
+}
+
+/// `analyze_password_strength`, also counting the title, username and URL of the
+/// Credential the password belongs to as easy guesses.
+pub fn analyze_password_strength_in_context(
+    password: &str,
+    dictionary: &[String],
+    context: &PasswordContext,
+) -> StrengthReport {
+    let context_words = context.words();
+    let user_inputs: Vec<&str> = dictionary
+        .iter()
+        .chain(&context_words)
+        .map(|w| w.as_str())
+        .collect();
+    let result = zxcvbn(password, &user_inputs);
+
+    let mut score_u8 = result.score() as u8;
+    let guesses = result.guesses();
+
+    let entropy = guesses.ilog2() as f64;
+
+    let banned = banned_word(password, dictionary);
+    if banned.is_some() {
+        score_u8 = score_u8.min(1);
+    }
+
+    let label = match score_u8 {
+        0 => "Very Weak",
+        1 => "Weak",
+        2 => "Fair",
+        3 => "Strong",
+        4 => "Very Strong",
+        _ => "Excellent",
+    }
+    .to_string();
+
+    let mut warnings: Vec<String> = Vec::new();
+    let mut suggestions: Vec<String> = Vec::new();
+
+    if let Some(word) = banned {
+        warnings.push(format!(
+            "Contains \"{}\" from your custom dictionary.",
+            word
+        ));
+    }
+    if let Some(feedback) = result.feedback() {
+        if let Some(warning) = feedback.warning() {
+            warnings.push(warning.to_string());
+        }
+        for suggestion in feedback.suggestions() {
+            suggestions.push(suggestion.to_string());
+        }
+    }
+
+    StrengthReport {
+        score: score_u8,
+        entropy,
+        label,
+        warnings,
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password_default_options() {
+        let options = PasswordOptions::default();
+        let password = generate_password(&options).unwrap();
+
+        assert_eq!(password.len(), options.length as usize);
+        assert!(password.is_ascii());
+    }
+
+    #[test]
+    fn test_generate_password_custom_length() {
+        let options = PasswordOptions {
+            length: 32,
+            ..Default::default()
+        };
+
+        let password = generate_password(&options).unwrap();
+        assert_eq!(password.len(), 32);
+    }
+
+    #[test]
+    fn test_generate_password_lowercase_only() {
+        let options = PasswordOptions {
+            lowercase: true,
+            uppercase: false,
+            numbers: false,
+            symbols: false,
+            exclude_ambiguous: false,
+            ..Default::default()
+        };
+
+        let password = generate_password(&options).unwrap();
+        assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_generate_password_exclude_ambiguous() {
+        let options = PasswordOptions {
+            exclude_ambiguous: true,
+            ..Default::default()
+        };
+
+        let password = generate_password(&options).unwrap();
+        for c in password.chars() {
+            assert!(!AMBIGUOUS_CHARS.contains(&c));
+        }
+    }
+
+    #[test]
+    fn test_generate_password_too_short() {
+        let options = PasswordOptions {
+            length: 4,
+            ..Default::default()
+        };
+
+        let result = generate_password(&options);
+        assert!(result.is_err());
+    }
+
+    /// Letters only, so injected digits stand out.
+    fn test_wordlist() -> Vec<String> {
+        (0..1296u32)
+            .map(|n| {
+                let (hi, lo) = (n / 26 % 26, n % 26);
+                format!(
+                    "word{}{}",
+                    char::from(b'a' + hi as u8),
+                    char::from(b'a' + lo as u8)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_passphrase_options() {
+        let options = PassphraseOptions {
+            words: 5,
+            separator: " ".to_string(),
+            capitalization: Capitalization::TitleCase,
+            digits: 2,
+            ..Default::default()
+        };
+        let passphrase = passphrase_from(&options, &test_wordlist(), &mut thread_rng());
+        let words: Vec<&str> = passphrase.split(' ').collect();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|w| w.starts_with("Word")));
+        assert_eq!(passphrase.chars().filter(char::is_ascii_digit).count(), 2);
+
+        let options = PassphraseOptions {
+            capitalization: Capitalization::RandomWord,
+            ..Default::default()
+        };
+        let passphrase = passphrase_from(&options, &test_wordlist(), &mut thread_rng());
+        assert_eq!(passphrase.split('-').count(), 6);
+        assert_eq!(
+            passphrase
+                .split('-')
+                .filter(|w| w.starts_with("WORD"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_passphrase_limits() {
+        let too_few = PasswordOptions {
+            mode: PasswordMode::Passphrase(PassphraseOptions {
+                words: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(generate_password(&too_few).is_err());
+
+        let long_separator = PassphraseOptions {
+            separator: "------".to_string(),
+            ..Default::default()
+        };
+        assert!(generate_passphrase(&long_separator).is_err());
+    }
+
+    #[test]
+    fn test_pin() {
+        let options = PinOptions { digits: 4 };
+        for _ in 0..200 {
+            let pin = generate_pin(&options).unwrap();
+            assert_eq!(pin.len(), 4);
+            assert!(pin.chars().all(|c| c.is_ascii_digit()));
+            assert!(!is_weak_pin(&pin));
+        }
+        for weak in ["7777", "0123", "6789", "3210", "1987", "2024", "012345678"] {
+            assert!(is_weak_pin(weak), "{}", weak);
+        }
+        for fine in ["1235", "2100", "1899", "8520", "123455"] {
+            assert!(!is_weak_pin(fine), "{}", fine);
+        }
+
+        let rejected = (0..10_000)
+            .filter(|n| is_weak_pin(&format!("{:04}", n)))
+            .count();
+        assert_eq!(rejected as u64, weak_pin_count(4));
+        let rejected = (0..1_000_000)
+            .filter(|n| is_weak_pin(&format!("{:06}", n)))
+            .count();
+        assert_eq!(rejected as u64, weak_pin_count(6));
+
+        let bits = entropy_bits(&PasswordOptions {
+            mode: PasswordMode::Pin(options),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!((bits - 9776f64.log2()).abs() < 1e-9);
+        assert!(generate_pin(&PinOptions { digits: 3 }).is_err());
+        assert!(generate_pin(&PinOptions { digits: 13 }).is_err());
+    }
+
+    #[test]
+    fn test_min_per_class() {
+        let options = PasswordOptions {
+            length: 8,
+            min_per_class: 2,
+            ..Default::default()
+        };
+        for _ in 0..200 {
+            let password = generate_password(&options).unwrap();
+            for class in [LOWERCASE, UPPERCASE, NUMBERS, SYMBOLS] {
+                assert!(password.chars().filter(|c| class.contains(c)).count() >= 2);
+            }
+        }
+        let expected = 2.0 * (26f64.log2() * 2.0 + 10f64.log2() + 32f64.log2());
+        assert!((entropy_bits(&options).unwrap() - expected).abs() < 1e-9);
+
+        let too_many = PasswordOptions {
+            min_per_class: 3,
+            ..options.clone()
+        };
+        assert!(generate_password(&too_many).is_err());
+
+        let symbols_excluded = PasswordOptions {
+            exclude_chars: SYMBOLS.iter().collect(),
+            ..Default::default()
+        };
+        assert!(generate_password(&symbols_excluded).is_err());
+        let unguaranteed = PasswordOptions {
+            min_per_class: 0,
+            ..symbols_excluded
+        };
+        assert!(generate_password(&unguaranteed).is_ok());
+    }
+
+    #[test]
+    fn test_include_and_exclude_chars() {
+        let options = PasswordOptions {
+            uppercase: false,
+            lowercase: false,
+            numbers: true,
+            symbols: false,
+            include_chars: "ab_".to_string(),
+            exclude_chars: "0123_".to_string(),
+            ..Default::default()
+        };
+        let password = generate_password(&options).unwrap();
+        assert!(password.chars().all(|c| "456789ab".contains(c)));
+        let bits = entropy_bits(&options).unwrap();
+        assert!((bits - (6f64.log2() + 15.0 * 8f64.log2())).abs() < 1e-9);
+
+        let only_included = PasswordOptions {
+            uppercase: false,
+            lowercase: false,
+            numbers: false,
+            symbols: false,
+            include_chars: "xyzx".to_string(),
+            ..Default::default()
+        };
+        let bits = entropy_bits(&only_included).unwrap();
+        assert!((bits - 16.0 * 3f64.log2()).abs() < 1e-9);
+
+        let nothing_left = PasswordOptions {
+            exclude_chars: "xyz".to_string(),
+            ..only_included.clone()
+        };
+        assert!(generate_password(&nothing_left).is_err());
+
+        let with_space = PasswordOptions {
+            include_chars: "a b".to_string(),
+            ..Default::default()
+        };
+        assert!(generate_password(&with_space).is_err());
+    }
+
+    #[test]
+    fn test_pronounceable_password() {
+        let options = PronounceableOptions {
+            syllables: 6,
+            capitalize: false,
+            digits: 0,
+            ..Default::default()
+        };
+        let password = generate_pronounceable(&options).unwrap();
+        assert_eq!(password.len(), 12);
+        for pair in password.as_bytes().chunks(2) {
+            assert!(SYLLABLE_CONSONANTS.contains(&char::from(pair[0])));
+            assert!(SYLLABLE_VOWELS.contains(&char::from(pair[1])));
+        }
+
+        let password = generate_password(&PasswordOptions {
+            mode: PasswordMode::Pronounceable(PronounceableOptions::default()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(password.len(), 18);
+        assert_eq!(password.chars().filter(char::is_ascii_uppercase).count(), 1);
+        assert!(password.ends_with(|c: char| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn test_entropy_bits() {
+        let lowercase = PasswordOptions {
+            length: 10,
+            uppercase: false,
+            numbers: false,
+            symbols: false,
+            ..Default::default()
+        };
+        let bits = entropy_bits(&lowercase).unwrap();
+        assert!((bits - 10.0 * 26f64.log2()).abs() < 1e-9);
+
+        let pronounceable = PasswordOptions {
+            mode: PasswordMode::Pronounceable(PronounceableOptions {
+                syllables: 4,
+                closed_syllables: false,
+                capitalize: false,
+                digits: 0,
+            }),
+            ..Default::default()
+        };
+        let bits = entropy_bits(&pronounceable).unwrap();
+        assert!((bits - 4.0 * 80f64.log2()).abs() < 1e-9);
+
+        let passphrase = PasswordOptions {
+            mode: PasswordMode::Passphrase(PassphraseOptions::default()),
+            ..Default::default()
+        };
+        let bits = entropy_bits(&passphrase).unwrap();
+        assert!((bits - 6.0 * 7776f64.log2()).abs() < 1e-9);
+
+        let too_short = PasswordOptions {
+            mode: PasswordMode::Pronounceable(PronounceableOptions {
+                syllables: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(entropy_bits(&too_short).is_err());
+    }
+
+    #[test]
+    fn test_analyze_weak_password() {
+        let report = analyze_password_strength("password123", &[]);
+        assert!(report.score <= 2);
+        assert!(!report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_strong_password() {
+        let report = analyze_password_strength("Tr0ub4dor&3!p@ss", &[]);
+        assert!(report.score >= 4);
+        assert!(report.entropy >= 40.0);
+    }
+
+    #[test]
+    fn test_analyze_common_password() {
+        let report = analyze_password_strength("qwerty123", &[]);
+        println!(
+            "Score: {}, Entropy: {}, Warnings: {:?}",
+            report.score, report.entropy, report.warnings
+        );
+        assert!(report.score <= 1);
+        assert!(report.entropy < 70.0);
+        assert!(!report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_custom_dictionary_caps_score() {
+        let dictionary = normalize_dictionary(vec!["  AcmeCorp ".to_string(), "ab".to_string()]);
+        assert_eq!(dictionary, vec!["acmecorp".to_string()]);
+
+        let report = analyze_password_strength("Xq!4cm3c0rp#Zt9w", &dictionary);
+        assert!(report.score <= 1);
+        assert!(report.warnings.iter().any(|w| w.contains("acmecorp")));
+    }
+
+    #[test]
+    fn test_context_weakens_site_derived_passwords() {
+        let context = PasswordContext {
+            title: "Facebook".to_string(),
+            username: "john.doe@example.org".to_string(),
+            url: Some("https://www.facebook.com/login".to_string()),
+        };
+        assert_eq!(
+            context.words(),
+            vec![
+                "facebook",
+                "john.doe@example.org",
+                "john",
+                "doe",
+                "example",
+                "https://www.facebook.com/login",
+            ]
+        );
+
+        let plain = analyze_password_strength("facebook-john-2024", &[]);
+        let in_context = analyze_password_strength_in_context("facebook-john-2024", &[], &context);
+        assert!(plain.score >= 3);
+        assert!(in_context.score <= 1);
+        assert!(in_context.entropy < plain.entropy);
+    }
+
+    #[test]
+    fn test_generate_password_avoiding_dictionary() {
+        let options = PasswordOptions {
+            length: 8,
+            uppercase: false,
+            numbers: false,
+            symbols: false,
+            ..Default::default()
+        };
+        let dictionary = vec!["e".repeat(3)];
+        for _ in 0..20 {
+            let password = generate_password_avoiding(&options, &dictionary).unwrap();
+            assert!(banned_word(&password, &dictionary).is_none());
+        }
+    }
+}
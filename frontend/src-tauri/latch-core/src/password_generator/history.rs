@@ -0,0 +1,125 @@
+//! The passwords generated lately, for when one went into a site's sign-up form
+//! before its Credential was saved. The log is encrypted with the Session key,
+//! so one left behind by an earlier key (the master password changed) reads as
+//! empty and is started over.
+
+use crate::crypto::aead::{self, EncryptedData};
+use crate::vault::unix_now;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Passwords kept, newest first; older ones drop off.
+pub const HISTORY_CAP: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct GeneratedPassword {
+    pub password: String,
+    /// Unix time it was generated.
+    pub generated_at: u64,
+}
+
+pub struct GenerationHistory {
+    path: PathBuf,
+    /// Held across read-modify-write so two generations can't drop each other.
+    writing: Mutex<()>,
+}
+
+impl GenerationHistory {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(
+            crate::vault::storage::config_dir()?.join("generated.enc"),
+        ))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            writing: Mutex::new(()),
+        }
+    }
+
+    pub fn load(&self, key: &[u8; 32]) -> Vec<GeneratedPassword> {
+        let Ok(sealed) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        serde_json::from_str::<EncryptedData>(&sealed)
+            .ok()
+            .and_then(|encrypted| aead::decrypt(key, &encrypted).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn record(&self, key: &[u8; 32], password: &str) -> Result<(), String> {
+        let _writing = self
+            .writing
+            .lock()
+            .map_err(|_| "Generation history is temporarily unavailable")?;
+        let mut history = self.load(key);
+        history.insert(
+            0,
+            GeneratedPassword {
+                password: password.to_string(),
+                generated_at: unix_now(),
+            },
+        );
+        history.truncate(HISTORY_CAP);
+
+        let json = serde_json::to_string(&history)
+            .map_err(|e| format!("Failed to serialize generation history: {}", e))?;
+        let sealed = serde_json::to_string(&aead::encrypt(key, &json)?)
+            .map_err(|e| format!("Failed to serialize generation history: {}", e))?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let tmp_path = self.path.with_extension("enc.tmp");
+        fs::write(&tmp_path, sealed)
+            .map_err(|e| format!("Failed to save generation history: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to save generation history: {}", e))
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        let _writing = self
+            .writing
+            .lock()
+            .map_err(|_| "Generation history is temporarily unavailable")?;
+        if self.path.exists() {
+            fs::remove_file(&self.path)
+                .map_err(|e| format!("Failed to clear generation history: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_passwords_under_their_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = GenerationHistory::at(dir.path().join("generated.enc"));
+        let key = [3u8; 32];
+
+        for i in 0..HISTORY_CAP + 5 {
+            history.record(&key, &format!("password-{}", i)).unwrap();
+        }
+        let kept = history.load(&key);
+        assert_eq!(kept.len(), HISTORY_CAP);
+        assert_eq!(kept[0].password, format!("password-{}", HISTORY_CAP + 4));
+        assert!(!fs::read_to_string(dir.path().join("generated.enc"))
+            .unwrap()
+            .contains("password-"));
+
+        assert!(history.load(&[4u8; 32]).is_empty());
+        history.record(&[4u8; 32], "after-rekey").unwrap();
+        assert_eq!(history.load(&[4u8; 32]).len(), 1);
+
+        history.clear().unwrap();
+        assert!(history.load(&[4u8; 32]).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! The EFF Diceware word lists passphrases are drawn from. They are compiled in,
+//! in the EFF's published format (`11111<TAB>abacus`), and each is parsed once, on
+//! first use.
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Wordlist {
+    /// 7776 words, five dice each; about 12.9 bits per word.
+    #[default]
+    EffLong,
+    /// 1296 short words, four dice each; about 10.3 bits per word.
+    EffShort,
+}
+
+impl Wordlist {
+    fn text(self) -> &'static str {
+        match self {
+            Wordlist::EffLong => include_str!("wordlists/eff_large_wordlist.txt"),
+            Wordlist::EffShort => include_str!("wordlists/eff_short_wordlist_1.txt"),
+        }
+    }
+
+    fn dice(self) -> u32 {
+        match self {
+            Wordlist::EffLong => 5,
+            Wordlist::EffShort => 4,
+        }
+    }
+
+    pub fn word_count(self) -> usize {
+        6usize.pow(self.dice())
+    }
+}
+
+static EFF_LONG: OnceLock<Vec<String>> = OnceLock::new();
+static EFF_SHORT: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Reads a list in the EFF format, insisting on one word for every roll.
+pub fn parse(list: Wordlist, text: &str) -> Result<Vec<String>, String> {
+    let dice = list.dice() as usize;
+    let words: Vec<String> = text
+        .lines()
+        .filter_map(|line| {
+            let (roll, word) = line.trim().split_once(char::is_whitespace)?;
+            let is_roll = roll.len() == dice && roll.chars().all(|c| ('1'..='6').contains(&c));
+            is_roll.then(|| word.trim().to_string())
+        })
+        .collect();
+    if words.len() != list.word_count() {
+        return Err(format!(
+            "Invalid word list: expected {} words, found {}",
+            list.word_count(),
+            words.len()
+        ));
+    }
+    Ok(words)
+}
+
+pub fn words(list: Wordlist) -> Result<&'static [String], String> {
+    let cell = match list {
+        Wordlist::EffLong => &EFF_LONG,
+        Wordlist::EffShort => &EFF_SHORT,
+    };
+    if let Some(words) = cell.get() {
+        return Ok(words);
+    }
+    let words = parse(list, list.text())?;
+    Ok(cell.get_or_init(|| words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every roll of four dice, in order, as the EFF files list them.
+    fn short_rolls() -> Vec<String> {
+        (0..1296)
+            .map(|n| {
+                (0..4)
+                    .rev()
+                    .map(|d| char::from(b'1' + (n / 6usize.pow(d) % 6) as u8))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_eff_format() {
+        let text: String = short_rolls()
+            .iter()
+            .map(|roll| format!("{}\tw{}\n", roll, roll))
+            .collect();
+        let words = parse(Wordlist::EffShort, &text).unwrap();
+        assert_eq!(words.len(), 1296);
+        assert_eq!(words[0], "w1111");
+        assert_eq!(words[1295], "w6666");
+
+        assert!(parse(Wordlist::EffLong, &text).is_err());
+        assert!(parse(Wordlist::EffShort, "1111\tacid\n").is_err());
+    }
+
+    #[test]
+    fn bundled_lists_are_complete() {
+        for list in [Wordlist::EffLong, Wordlist::EffShort] {
+            let words = words(list).unwrap();
+            let unique: std::collections::HashSet<_> = words.iter().collect();
+            assert_eq!(unique.len(), list.word_count());
+        }
+        assert_eq!(words(Wordlist::EffLong).unwrap()[0], "abacus");
+        assert_eq!(words(Wordlist::EffShort).unwrap()[1295], "zoom");
+    }
+}
@@ -0,0 +1,302 @@
+//! One-time sharing of a single secret. The secret is sealed with a fresh random
+//! key, and the sealed copy goes either to a relay or into a file of its own. The
+//! key only travels in a link's fragment (after `#`), which is never sent to the
+//! relay, so the relay holds nothing it can read.
+//!
+//! A relay answers two requests:
+//! - `POST /api/secrets` with a `SealedSecret` as JSON, answering `{"id": "..."}`.
+//! - `GET /api/secrets/<id>`, answering the `SealedSecret`, or 404 once it has
+//!   expired or, if it burns after reading, been fetched.
+//!
+//! Links look like `<relay>/s/<id>#<key>`; a relay may serve a page there that
+//! decrypts in the browser, and Latch opens them itself with `open_link`.
+
+use crate::crypto::aead::{self, EncryptedData};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::Path;
+use url::Url;
+use zeroize::Zeroizing;
+
+pub const DEFAULT_SHARE_TTL_SECS: u64 = 24 * 60 * 60;
+pub const MAX_SHARE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+const SHARE_VERSION: u32 = 1;
+/// A sealed secret is one field, so anything much larger isn't one.
+const MAX_SEALED_BYTES: usize = 256 * 1024;
+
+/// What a relay stores and a share file holds. Readable by anyone; only the
+/// link's key opens `secret`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SealedSecret {
+    pub version: u32,
+    pub expires_at: u64,
+    /// The relay deletes it on the first fetch, or Latch deletes the file once
+    /// opened.
+    pub burn_after_reading: bool,
+    pub secret: EncryptedData,
+}
+
+/// Where the sealed secret goes.
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShareDestination {
+    /// The relay in the settings.
+    Relay,
+    /// A `.latchshare` file, to hand over some other way.
+    File { path: String },
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct OneTimeShare {
+    /// What to send the recipient: the relay link, or for a file `#<key>`, to send
+    /// separately from the file itself.
+    pub link: String,
+    pub expires_at: u64,
+}
+
+pub fn clamp_ttl(ttl_secs: Option<u64>) -> u64 {
+    ttl_secs
+        .unwrap_or(DEFAULT_SHARE_TTL_SECS)
+        .clamp(60, MAX_SHARE_TTL_SECS)
+}
+
+/// Seals `secret` under a new key, returned alongside for the link.
+pub fn seal(
+    secret: &str,
+    ttl_secs: u64,
+    burn_after_reading: bool,
+    now: u64,
+) -> Result<(SealedSecret, Zeroizing<[u8; 32]>), String> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    rand::thread_rng().fill_bytes(key.as_mut());
+    let sealed = SealedSecret {
+        version: SHARE_VERSION,
+        expires_at: now + ttl_secs,
+        burn_after_reading,
+        secret: aead::encrypt(&key, secret)?,
+    };
+    Ok((sealed, key))
+}
+
+pub fn unseal(
+    sealed: &SealedSecret,
+    key: &[u8; 32],
+    now: u64,
+) -> Result<Zeroizing<String>, String> {
+    if sealed.version != SHARE_VERSION {
+        return Err("This share was made by a newer version of Latch".to_string());
+    }
+    if now >= sealed.expires_at {
+        return Err("This share has expired".to_string());
+    }
+    aead::decrypt(key, &sealed.secret)
+        .map(Zeroizing::new)
+        .map_err(|_| "This share's key doesn't match".to_string())
+}
+
+pub fn encode_key(key: &[u8; 32]) -> String {
+    URL_SAFE_NO_PAD.encode(key)
+}
+
+/// The key from a link's fragment, or from the fragment alone.
+pub fn decode_key(link: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let fragment = link.rsplit_once('#').map_or(link, |(_, f)| f).trim();
+    let bytes = Zeroizing::new(
+        URL_SAFE_NO_PAD
+            .decode(fragment)
+            .map_err(|_| "Invalid share key".to_string())?,
+    );
+    let mut key = Zeroizing::new([0u8; 32]);
+    if bytes.len() != key.len() {
+        return Err("Invalid share key".to_string());
+    }
+    key.copy_from_slice(&bytes);
+    Ok(key)
+}
+
+fn parse_sealed(bytes: &[u8]) -> Result<SealedSecret, String> {
+    if bytes.len() > MAX_SEALED_BYTES {
+        return Err("Share is too large".to_string());
+    }
+    let sealed: SealedSecret =
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid share: {}", e))?;
+    sealed.secret.check_lengths()?;
+    Ok(sealed)
+}
+
+pub fn write_file(path: &Path, sealed: &SealedSecret) -> Result<(), String> {
+    let json = serde_json::to_vec(sealed).map_err(|e| format!("Failed to write share: {}", e))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write share: {}", e))
+}
+
+/// Opens a share file with the key from its link, deleting the file afterwards
+/// if it burns after reading.
+pub fn open_file(path: &Path, link: &str, now: u64) -> Result<Zeroizing<String>, String> {
+    let key = decode_key(link)?;
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read share: {}", e))?;
+    let sealed = parse_sealed(&bytes)?;
+    let secret = unseal(&sealed, &key, now)?;
+    if sealed.burn_after_reading {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Failed to remove opened share: {}", e);
+        }
+    }
+    Ok(secret)
+}
+
+/// The relay's base URL and the share id in a relay link.
+fn parse_link(link: &str) -> Result<(Url, String), String> {
+    let url = Url::parse(link).map_err(|_| "Invalid share link".to_string())?;
+    let path = url.path().trim_end_matches('/');
+    let (base, id) = path
+        .rsplit_once("/s/")
+        .filter(|(_, id)| check_id(id).is_ok())
+        .ok_or_else(|| "Invalid share link".to_string())?;
+    let mut relay = url.clone();
+    relay.set_path(&format!("{}/", base));
+    relay.set_query(None);
+    relay.set_fragment(None);
+    Ok((relay, id.to_string()))
+}
+
+/// Ids end up in request paths, so only URL-safe ones are let through.
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty()
+        || id.len() > 128
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Invalid share id".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Created {
+    id: String,
+}
+
+pub struct RelayClient {
+    base: Url,
+    client: reqwest::Client,
+}
+
+impl RelayClient {
+    pub fn new(relay_url: &str) -> Result<Self, String> {
+        let mut base = Url::parse(relay_url).map_err(|_| "Invalid share relay URL".to_string())?;
+        if base.scheme() != "https" || base.host_str().is_none() {
+            return Err("Invalid share relay URL: use an https URL".to_string());
+        }
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to start share client: {}", e))?;
+        Ok(Self { base, client })
+    }
+
+    fn url(&self, path: &str) -> Result<Url, String> {
+        self.base
+            .join(path)
+            .map_err(|_| "Invalid share relay URL".to_string())
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Vec<u8>, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach the share relay: {}", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err("This share has expired or was already opened".to_string());
+        }
+        if !status.is_success() {
+            return Err(format!("The share relay refused the request ({})", status));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read from the share relay: {}", e))?;
+        Ok(body.to_vec())
+    }
+
+    /// Uploads `sealed` and returns the link to it, with `key` in the fragment.
+    pub async fn upload(&self, sealed: &SealedSecret, key: &[u8; 32]) -> Result<String, String> {
+        let body =
+            serde_json::to_string(sealed).map_err(|e| format!("Failed to upload share: {}", e))?;
+        let request = self
+            .client
+            .post(self.url("api/secrets")?)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        let created: Created = serde_json::from_slice(&self.send(request).await?)
+            .map_err(|e| format!("Failed to parse share relay response: {}", e))?;
+        check_id(&created.id)?;
+        let mut link = self.url(&format!("s/{}", created.id))?;
+        link.set_fragment(Some(&encode_key(key)));
+        Ok(link.to_string())
+    }
+}
+
+/// Fetches and opens a relay link. A burn-after-reading share is gone from the
+/// relay afterwards, whether or not the key matched.
+pub async fn open_link(link: &str, now: u64) -> Result<Zeroizing<String>, String> {
+    let key = decode_key(link)?;
+    let (relay, id) = parse_link(link)?;
+    let relay = RelayClient::new(relay.as_str())?;
+    let request = relay.client.get(relay.url(&format!("api/secrets/{}", id))?);
+    let sealed = parse_sealed(&relay.send(request).await?)?;
+    unseal(&sealed, &key, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_secrets_open_only_with_their_key_before_expiry() {
+        let (sealed, key) = seal("hunter2", 3600, true, 1_000).unwrap();
+        assert_eq!(unseal(&sealed, &key, 1_000).unwrap().as_str(), "hunter2");
+        assert!(unseal(&sealed, &[0u8; 32], 1_000).is_err());
+        assert_eq!(
+            unseal(&sealed, &key, 4_600).unwrap_err(),
+            "This share has expired"
+        );
+
+        let link = format!("https://relay.example/s/abc#{}", encode_key(&key));
+        assert_eq!(*decode_key(&link).unwrap(), *key);
+        assert_eq!(
+            *decode_key(&format!("#{}", encode_key(&key))).unwrap(),
+            *key
+        );
+        assert!(decode_key("https://relay.example/s/abc#short").is_err());
+    }
+
+    #[test]
+    fn links_name_their_relay_and_id() {
+        let (relay, id) = parse_link("https://relay.example/share/s/a1_b2#key").unwrap();
+        assert_eq!(relay.as_str(), "https://relay.example/share/");
+        assert_eq!(id, "a1_b2");
+        assert!(parse_link("https://relay.example/s/../admin#key").is_err());
+        assert!(parse_link("https://relay.example/other#key").is_err());
+    }
+
+    #[test]
+    fn burned_files_are_gone_once_opened() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.latchshare");
+        let (sealed, key) = seal("hunter2", 3600, true, 1_000).unwrap();
+        write_file(&path, &sealed).unwrap();
+        let link = format!("#{}", encode_key(&key));
+
+        assert!(open_file(&path, "#AAAA", 1_000).is_err());
+        assert!(path.exists());
+        assert_eq!(open_file(&path, &link, 1_000).unwrap().as_str(), "hunter2");
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,30 @@
+use super::journal::Baseline;
+use super::{storage::VaultStorage, workspace::Workspace};
+
+pub fn access(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    key: &[u8; 32],
+) -> Result<(), String> {
+    if !storage.exists() {
+        return Err("Vault does not exist".to_string());
+    }
+
+    let generation = storage.generation();
+    storage.mark_current();
+    let vault = storage.read()?;
+    let entries = vault.entries(key)?;
+
+    workspace.start(*key);
+    *workspace
+        .saved
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")? = Some(Baseline {
+        generation,
+        pending: vault.journal.len(),
+        entries: entries.clone(),
+    });
+    workspace.credentials = entries;
+
+    Ok(())
+}
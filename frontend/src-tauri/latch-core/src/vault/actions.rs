@@ -0,0 +1,85 @@
+use super::{workspace::Workspace, Entry};
+use serde::Serialize;
+use specta::Type;
+
+/// Something the palette can do with a Credential. Only the actions the entry has
+/// the fields for are offered, so the palette never assumes an entry's shape.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EntryAction {
+    CopyPassword,
+    CopyUsername,
+    OpenUrl { url: String },
+}
+
+fn openable(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"))
+}
+
+pub fn for_entry(entry: &Entry) -> Vec<EntryAction> {
+    let mut actions = Vec::new();
+    if !entry.password.is_empty() {
+        actions.push(EntryAction::CopyPassword);
+    }
+    if !entry.username.is_empty() {
+        actions.push(EntryAction::CopyUsername);
+    }
+    // Anything but http(s) is refused at save time; older entries may predate that.
+    if let Some(url) = entry.url.as_deref().filter(|url| openable(url)) {
+        actions.push(EntryAction::OpenUrl {
+            url: url.to_string(),
+        });
+    }
+    actions
+}
+
+pub fn available(workspace: &mut Workspace, id: &str) -> Result<Vec<EntryAction>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    workspace
+        .credentials
+        .iter()
+        .find(|e| e.id == id)
+        .map(for_entry)
+        .ok_or_else(|| format!("Credential '{}' not found", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(username: &str, url: Option<&str>) -> Entry {
+        Entry {
+            id: "entry-1".to_string(),
+            title: "Example".to_string(),
+            username: username.to_string(),
+            password: "secret".to_string(),
+            url: url.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn offers_only_actions_the_entry_has_fields_for() {
+        assert_eq!(for_entry(&entry("", None)), vec![EntryAction::CopyPassword]);
+        assert_eq!(
+            for_entry(&entry("user", Some("https://example.com"))),
+            vec![
+                EntryAction::CopyPassword,
+                EntryAction::CopyUsername,
+                EntryAction::OpenUrl {
+                    url: "https://example.com".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn never_offers_to_open_non_web_urls() {
+        for url in ["javascript:alert(1)", "file:///etc/passwd", "not a url"] {
+            assert!(!for_entry(&entry("user", Some(url)))
+                .iter()
+                .any(|action| matches!(action, EntryAction::OpenUrl { .. })));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! API key Credentials: a key id, its scopes and the secret, kept in `password`.
+//! An expiry date becomes the Credential's `PasswordExpiry`, so it is reminded
+//! about like any other; each field can be fetched alone with `get_field`, which
+//! is what `latch get` uses for scripts.
+
+use super::EntryKind;
+
+pub const MAX_SCOPES: usize = 64;
+
+/// The fields `get_field` serves from an API key, besides `title`.
+pub const FIELDS: [&str; 4] = ["key_id", "secret", "scopes", "expires_at"];
+
+/// Checks and tidies an API key's fields. Scopes are trimmed and deduplicated,
+/// keeping their order.
+pub fn validate(key_id: &str, secret: &str, scopes: Vec<String>) -> Result<EntryKind, String> {
+    let key_id = key_id.trim();
+    if key_id.is_empty() || key_id.len() > 256 {
+        return Err("Invalid key id: use 1 to 256 characters".to_string());
+    }
+    if secret.trim().is_empty() || secret.len() > 4096 {
+        return Err("Invalid secret: use 1 to 4096 characters".to_string());
+    }
+
+    let mut tidied: Vec<String> = Vec::new();
+    for scope in scopes {
+        let scope = scope.trim().to_string();
+        if scope.is_empty() {
+            continue;
+        }
+        if scope.len() > 128 || scope.chars().any(char::is_whitespace) {
+            return Err(format!("Invalid scope '{}': use one word per scope", scope));
+        }
+        if !tidied.contains(&scope) {
+            tidied.push(scope);
+        }
+    }
+    if tidied.len() > MAX_SCOPES {
+        return Err(format!("Invalid scopes: at most {} per key", MAX_SCOPES));
+    }
+
+    Ok(EntryKind::ApiKey {
+        key_id: key_id.to_string(),
+        scopes: tidied,
+    })
+}
+
+/// `key_id` or `scopes` (space-separated) from an API key; None for other kinds
+/// and the other fields.
+pub fn field(kind: &EntryKind, field: &str) -> Option<String> {
+    let EntryKind::ApiKey { key_id, scopes } = kind else {
+        return None;
+    };
+    match field {
+        "key_id" => Some(key_id.clone()),
+        "scopes" => Some(scopes.join(" ")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::entries::get_field;
+    use crate::vault::workspace::Workspace;
+    use crate::vault::Entry;
+    use crate::vault_health::expiry::{check_expired, PasswordExpiry};
+
+    const DAY_SECS: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn api_keys_serve_each_field_and_expire_on_their_date() {
+        let kind = validate(
+            " AKIAEXAMPLE ",
+            "s3cr3t",
+            vec![
+                "read:repo".to_string(),
+                " ".to_string(),
+                "write:repo".to_string(),
+                "read:repo".to_string(),
+            ],
+        )
+        .unwrap();
+        assert!(validate("", "s3cr3t", Vec::new()).is_err());
+        assert!(validate("id", " ", Vec::new()).is_err());
+        assert!(validate("id", "s3cr3t", vec!["two words".to_string()]).is_err());
+
+        let now = 1_000 * DAY_SECS;
+        let expires_at = now + 30 * DAY_SECS + 60;
+        let expiry = PasswordExpiry::until(expires_at, now).unwrap();
+        assert_eq!(expiry.due_at(), expires_at);
+        assert!(PasswordExpiry::until(now, now).is_err());
+
+        let entry = Entry {
+            id: "key".to_string(),
+            title: "CI deploy key".to_string(),
+            username: String::new(),
+            password: "s3cr3t".to_string(),
+            kind,
+            expiry: Some(expiry),
+            ..Default::default()
+        };
+        assert!(check_expired(std::slice::from_ref(&entry), now).is_empty());
+        assert_eq!(
+            check_expired(std::slice::from_ref(&entry), expires_at).len(),
+            1
+        );
+
+        let mut workspace = Workspace::new();
+        workspace.credentials = vec![entry];
+        workspace.start([1u8; 32]);
+        let get = |workspace: &mut Workspace, field: &str| get_field(workspace, "key", field);
+        assert_eq!(get(&mut workspace, "key_id").unwrap(), "AKIAEXAMPLE");
+        assert_eq!(
+            get(&mut workspace, "scopes").unwrap(),
+            "read:repo write:repo"
+        );
+        assert_eq!(get(&mut workspace, "secret").unwrap(), "s3cr3t");
+        assert_eq!(
+            get(&mut workspace, "expires_at").unwrap(),
+            expires_at.to_string()
+        );
+        assert!(get(&mut workspace, "account_number").is_err());
+    }
+}
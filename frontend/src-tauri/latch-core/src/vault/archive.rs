@@ -0,0 +1,256 @@
+//! The archive: Credentials deleted while archiving is on are kept in a second file
+//! next to the Vault, encrypted with the same key. It is only read when searched or
+//! restored from, so the Vault itself stays small.
+
+use super::collation::Collation;
+use super::{search, storage::VaultStorage, workspace::Workspace, Entry, EntryPreview};
+use crate::crypto::aead::{self, EncryptedData};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedEntry {
+    pub entry: Entry,
+    pub archived_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ArchivedPreview {
+    pub entry: EntryPreview,
+    pub archived_at: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ArchiveData {
+    entries: Vec<ArchivedEntry>,
+}
+
+fn archive_path(storage: &VaultStorage) -> Result<PathBuf, String> {
+    if storage.is_ephemeral() {
+        return Err("An ephemeral vault has no archive".to_string());
+    }
+    Ok(storage.path.with_extension("archive.enc"))
+}
+
+fn session_key(workspace: &mut Workspace) -> Result<[u8; 32], String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    // A guest must not see what was deleted from the rest of the Vault.
+    if workspace.guest.is_some() {
+        return Err(super::guest::GUEST_DENIED.to_string());
+    }
+    workspace
+        .session_key
+        .as_deref()
+        .copied()
+        .ok_or_else(|| "Vault is locked".to_string())
+}
+
+fn load(storage: &VaultStorage, key: &[u8; 32]) -> Result<Vec<ArchivedEntry>, String> {
+    let path = archive_path(storage)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::read(&path).map_err(|e| format!("Failed to read archive: {}", e))?;
+    let encrypted: EncryptedData =
+        serde_json::from_slice(&file).map_err(|e| format!("Failed to parse archive: {}", e))?;
+    let json = aead::decrypt(key, &encrypted)
+        .map_err(|_| "Archive could not be decrypted with this Session's key".to_string())?;
+    let data: ArchiveData =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse archive: {}", e))?;
+    Ok(data.entries)
+}
+
+fn save(storage: &VaultStorage, key: &[u8; 32], entries: Vec<ArchivedEntry>) -> Result<(), String> {
+    let path = archive_path(storage)?;
+    let json = serde_json::to_string(&ArchiveData { entries })
+        .map_err(|e| format!("Failed to serialize archive: {}", e))?;
+    let encrypted = serde_json::to_string_pretty(&aead::encrypt(key, &json)?)
+        .map_err(|e| format!("Failed to serialize archive: {}", e))?;
+    let tmp_path = path.with_extension("enc.tmp");
+    fs::write(&tmp_path, encrypted).map_err(|e| format!("Failed to write archive: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+/// Files `entries` in the archive. Call before deleting them from the Vault, so a
+/// failure here leaves them where they were.
+pub fn append(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    entries: Vec<Entry>,
+) -> Result<(), String> {
+    let key = session_key(workspace)?;
+    let mut archived = load(storage, &key)?;
+    let now = super::unix_now();
+    archived.extend(entries.into_iter().map(|entry| ArchivedEntry {
+        entry,
+        archived_at: now,
+    }));
+    save(storage, &key, archived)
+}
+
+/// Archived Credentials matching `query`, ranked like a Vault search.
+pub fn search(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    query: &str,
+    collation: &Collation,
+    options: &search::MatchOptions,
+) -> Result<Vec<ArchivedPreview>, String> {
+    let key = session_key(workspace)?;
+    let archived = load(storage, &key)?;
+    let entries: Vec<Entry> = archived.iter().map(|a| a.entry.clone()).collect();
+    Ok(search::rank(&entries, query, collation, options)
+        .into_iter()
+        .map(|idx| ArchivedPreview {
+            entry: archived[idx].entry.clone().into(),
+            archived_at: archived[idx].archived_at,
+        })
+        .collect())
+}
+
+/// Moves an archived Credential back into the Vault, under a new id if its old one
+/// has been reused since.
+pub fn restore(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    id: &str,
+) -> Result<Entry, String> {
+    let key = session_key(workspace)?;
+    let mut archived = load(storage, &key)?;
+    let idx = archived
+        .iter()
+        .position(|a| a.entry.id == id)
+        .ok_or("Archived credential not found")?;
+    let mut entry = archived.remove(idx).entry;
+    if workspace.credentials.iter().any(|e| e.id == entry.id) {
+        entry.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    workspace.credentials.push(entry.clone());
+    if let Err(e) = super::entries::persist(workspace, storage) {
+        workspace.credentials.pop();
+        return Err(e);
+    }
+    save(storage, &key, archived)?;
+    Ok(entry)
+}
+
+/// The archive decrypted with `old_key`, for `rekey` once the Vault has a new key.
+pub fn take(storage: &VaultStorage, old_key: &[u8; 32]) -> Result<Vec<ArchivedEntry>, String> {
+    if storage.is_ephemeral() {
+        return Ok(Vec::new());
+    }
+    load(storage, old_key)
+}
+
+/// Writes the archive back under the Vault's new key, keeping it readable with the
+/// same credentials after a key rotation.
+pub fn rekey(
+    storage: &VaultStorage,
+    new_key: &[u8; 32],
+    archived: Vec<ArchivedEntry>,
+) -> Result<(), String> {
+    if archived.is_empty() {
+        return Ok(());
+    }
+    save(storage, new_key, archived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::provision;
+
+    fn entry(id: &str, title: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: title.to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn open_vault(dir: &std::path::Path) -> (VaultStorage, Workspace) {
+        let storage = VaultStorage::at(dir.join("vault.enc"));
+        let mut workspace = Workspace::new();
+        provision::provision(
+            &storage,
+            &mut workspace,
+            &[4u8; 32],
+            AuthMethod::Password,
+            "",
+        )
+        .unwrap();
+        (storage, workspace)
+    }
+
+    #[test]
+    fn archived_credentials_can_be_found_and_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = open_vault(dir.path());
+        append(
+            &storage,
+            &mut workspace,
+            vec![entry("a", "GitHub"), entry("b", "Bank")],
+        )
+        .unwrap();
+        assert!(dir.path().join("vault.archive.enc").exists());
+
+        let found = search(
+            &storage,
+            &mut workspace,
+            "git",
+            &Collation::for_locale(None),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entry.id, "a");
+
+        let restored = restore(&storage, &mut workspace, "a").unwrap();
+        assert_eq!(restored.title, "GitHub");
+        assert_eq!(workspace.credentials.len(), 1);
+        let left = search(
+            &storage,
+            &mut workspace,
+            "",
+            &Collation::for_locale(None),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(left.len(), 1);
+        assert!(restore(&storage, &mut workspace, "a").is_err());
+    }
+
+    #[test]
+    fn archive_follows_the_vault_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = open_vault(dir.path());
+        append(&storage, &mut workspace, vec![entry("a", "GitHub")]).unwrap();
+
+        crate::vault::rotate::rotate(
+            &storage,
+            &mut workspace,
+            &[5u8; 32],
+            AuthMethod::Password,
+            "",
+        )
+        .unwrap();
+
+        let found = search(
+            &storage,
+            &mut workspace,
+            "",
+            &Collation::for_locale(None),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(load(&storage, &[4u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,560 @@
+use super::events::VaultEvent;
+use super::journal::JournalEntry;
+use super::{
+    storage::VaultStorage, unix_now, workspace::Workspace, EncryptedVault, Entry, VaultData,
+    VaultSummary,
+};
+use crate::auth::oauth::OAuthProvider;
+use crate::crypto::aead;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of differential backups taken against one full backup before consolidating.
+const MAX_DIFFS_PER_FULL: usize = 20;
+/// Full backups (and the differentials chained to them) kept on disk.
+const KEEP_FULL_BACKUPS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    Off,
+    Full,
+    #[default]
+    Differential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    Full,
+    Diff,
+}
+
+impl BackupKind {
+    fn tag(&self) -> &'static str {
+        match self {
+            BackupKind::Full => "full",
+            BackupKind::Diff => "diff",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub kind: BackupKind,
+    pub base: Option<String>,
+    pub created_at: u64,
+    pub kdf: String,
+    pub salt: String,
+    pub data: aead::EncryptedData,
+    /// The Vault's unsaved journal, for a full backup taken before it was compacted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub journal: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BackupInfo {
+    pub id: String,
+    pub kind: BackupKind,
+    pub base: Option<String>,
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// Per-entry changes relative to a full backup, or to the last save (see `journal`).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EntryDelta {
+    pub upserted: Vec<Entry>,
+    pub removed: Vec<String>,
+}
+
+impl EntryDelta {
+    pub fn between(base: &[Entry], current: &[Entry]) -> Self {
+        let base_by_id: HashMap<&str, &Entry> = base.iter().map(|e| (e.id.as_str(), e)).collect();
+        let current_ids: HashSet<&str> = current.iter().map(|e| e.id.as_str()).collect();
+
+        let upserted = current
+            .iter()
+            .filter(|e| base_by_id.get(e.id.as_str()) != Some(e))
+            .cloned()
+            .collect();
+        let removed = base
+            .iter()
+            .filter(|e| !current_ids.contains(e.id.as_str()))
+            .map(|e| e.id.clone())
+            .collect();
+
+        EntryDelta { upserted, removed }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upserted.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn apply(&self, base: Vec<Entry>) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = base
+            .into_iter()
+            .filter(|e| !self.removed.contains(&e.id))
+            .collect();
+        for entry in &self.upserted {
+            match entries.iter_mut().find(|e| e.id == entry.id) {
+                Some(existing) => *existing = entry.clone(),
+                None => entries.push(entry.clone()),
+            }
+        }
+        entries
+    }
+}
+
+pub struct BackupStore {
+    pub dir: PathBuf,
+}
+
+impl BackupStore {
+    pub fn for_vault(storage: &VaultStorage) -> Result<Self, String> {
+        if storage.is_ephemeral() {
+            return Err("Backups are unavailable for an ephemeral vault".to_string());
+        }
+        let parent = storage.path.parent().ok_or("Invalid vault path")?;
+        Ok(Self {
+            dir: parent.join("backups"),
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<BackupInfo>, String> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        let read_dir =
+            fs::read_dir(&self.dir).map_err(|e| format!("Failed to read backups: {}", e))?;
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("enc") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let Ok(file) = self.read(id) else {
+                continue;
+            };
+            let size_bytes = dir_entry.metadata().map(|m| m.len()).unwrap_or(0);
+            backups.push(BackupInfo {
+                id: id.to_string(),
+                kind: file.kind,
+                base: file.base,
+                created_at: file.created_at,
+                size_bytes,
+            });
+        }
+
+        backups.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(backups)
+    }
+
+    /// Records the current Vault state after a save. Returns the id of the backup written, if any.
+    pub fn snapshot(
+        &self,
+        storage: &VaultStorage,
+        workspace: &Workspace,
+        mode: BackupMode,
+    ) -> Result<Option<String>, String> {
+        if mode == BackupMode::Off {
+            return Ok(None);
+        }
+        let key = workspace.session_key.as_ref().ok_or("Vault is locked")?;
+        let vault = storage.read()?;
+
+        if mode == BackupMode::Full {
+            return self.write_full(vault).map(Some);
+        }
+
+        let backups = self.list()?;
+        let Some(base) = backups.iter().rev().find(|b| b.kind == BackupKind::Full) else {
+            return self.write_full(vault).map(Some);
+        };
+
+        let diffs_on_base = backups
+            .iter()
+            .filter(|b| b.base.as_deref() == Some(base.id.as_str()))
+            .count();
+        if diffs_on_base >= MAX_DIFFS_PER_FULL {
+            return self.write_full(vault).map(Some);
+        }
+
+        let base_file = self.read(&base.id)?;
+        if base_file.kdf != vault.kdf || base_file.salt != vault.salt {
+            return self.write_full(vault).map(Some);
+        }
+        let Ok(base_entries) = decrypt_entries(key, &base_file) else {
+            // The key was rotated since the last full backup; start a new chain.
+            return self.write_full(vault).map(Some);
+        };
+
+        let delta = EntryDelta::between(&base_entries, &workspace.credentials);
+        if delta.is_empty() {
+            return Ok(None);
+        }
+        if delta.upserted.len() * 2 > workspace.credentials.len().max(1) {
+            return self.write_full(vault).map(Some);
+        }
+
+        let json =
+            serde_json::to_string(&delta).map_err(|e| format!("Failed to serialize: {}", e))?;
+        let file = BackupFile {
+            kind: BackupKind::Diff,
+            base: Some(base.id.clone()),
+            created_at: unix_now(),
+            kdf: vault.kdf,
+            salt: vault.salt,
+            data: aead::encrypt(key, &json)?,
+            journal: Vec::new(),
+        };
+        self.write(&file).map(Some)
+    }
+
+    /// Decrypts a backup and reconstructs the full entry set it represents.
+    pub fn load_entries(
+        &self,
+        id: &str,
+        key: &[u8; 32],
+    ) -> Result<(BackupFile, Vec<Entry>), String> {
+        let file = self.read(id)?;
+        let entries = match file.kind {
+            BackupKind::Full => decrypt_entries(key, &file)?,
+            BackupKind::Diff => {
+                let base_id = file.base.as_deref().ok_or("Backup is missing its base")?;
+                let base_file = self.read(base_id)?;
+                let base_entries = decrypt_entries(key, &base_file)?;
+                let json = aead::decrypt(key, &file.data)?;
+                let delta: EntryDelta = serde_json::from_str(&json)
+                    .map_err(|e| format!("Failed to parse backup: {}", e))?;
+                delta.apply(base_entries)
+            }
+        };
+        Ok((file, entries))
+    }
+
+    /// The newest full backup as a Vault of its own. Differentials need the key to
+    /// apply, so they are left for `restore`.
+    pub fn newest_full(&self) -> Result<Option<(BackupInfo, EncryptedVault)>, String> {
+        let Some(info) = self
+            .list()?
+            .into_iter()
+            .rev()
+            .find(|b| b.kind == BackupKind::Full)
+        else {
+            return Ok(None);
+        };
+        let file = self.read(&info.id)?;
+        let vault = EncryptedVault {
+            version: "2".to_string(),
+            provider: OAuthProvider::for_vault(&file.kdf, &file.salt),
+            kdf: file.kdf,
+            salt: file.salt,
+            data: file.data,
+            summary: None,
+            journal: file.journal,
+        };
+        Ok(Some((info, vault)))
+    }
+
+    pub fn path_for(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.enc", id))
+    }
+
+    fn read(&self, id: &str) -> Result<BackupFile, String> {
+        if id.contains(['/', '\\']) || id.contains("..") {
+            return Err("Invalid backup id".to_string());
+        }
+        let content = fs::read_to_string(self.path_for(id))
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse backup: {}", e))
+    }
+
+    fn write(&self, file: &BackupFile) -> Result<String, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+        let mut stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let mut id = format!("{:013}-{}", stamp, file.kind.tag());
+        while self.path_for(&id).exists() {
+            stamp += 1;
+            id = format!("{:013}-{}", stamp, file.kind.tag());
+        }
+
+        let json = serde_json::to_string(file)
+            .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+        let path = self.path_for(&id);
+        let tmp_path = path.with_extension("enc.tmp");
+        fs::write(&tmp_path, &json).map_err(|e| format!("Failed to write backup: {}", e))?;
+        fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to rename backup: {}", e))?;
+        Ok(id)
+    }
+
+    fn write_full(&self, vault: EncryptedVault) -> Result<String, String> {
+        let file = BackupFile {
+            kind: BackupKind::Full,
+            base: None,
+            created_at: unix_now(),
+            kdf: vault.kdf,
+            salt: vault.salt,
+            data: vault.data,
+            journal: vault.journal,
+        };
+        let id = self.write(&file)?;
+        self.prune()?;
+        Ok(id)
+    }
+
+    fn prune(&self) -> Result<(), String> {
+        let backups = self.list()?;
+        let fulls: Vec<&BackupInfo> = backups
+            .iter()
+            .filter(|b| b.kind == BackupKind::Full)
+            .collect();
+        if fulls.len() <= KEEP_FULL_BACKUPS {
+            return Ok(());
+        }
+
+        let kept: HashSet<&str> = fulls[fulls.len() - KEEP_FULL_BACKUPS..]
+            .iter()
+            .map(|b| b.id.as_str())
+            .collect();
+        for backup in &backups {
+            let anchor = backup.base.as_deref().unwrap_or(backup.id.as_str());
+            if !kept.contains(anchor) {
+                fs::remove_file(self.path_for(&backup.id))
+                    .map_err(|e| format!("Failed to remove old backup: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Commands that overwrite or drop data the user could not get back otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destructive {
+    DeleteEntries,
+    Reencrypt,
+    MigrateToOAuth,
+    RestoreBackup,
+    MergeVault,
+}
+
+impl Destructive {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Destructive::DeleteEntries => "deleting credentials",
+            Destructive::Reencrypt => "re-encrypting the vault",
+            Destructive::MigrateToOAuth => "migrating to OAuth",
+            Destructive::RestoreBackup => "restoring a backup",
+            Destructive::MergeVault => "merging another vault",
+        }
+    }
+}
+
+/// Copies the Vault file as it stands into a full backup, whatever the backup
+/// mode. Needs no key, so it works before the Vault is unlocked. Returns `None`
+/// for an ephemeral Vault, which has nothing on disk to lose.
+pub fn safety_backup(storage: &VaultStorage) -> Result<Option<String>, String> {
+    if storage.is_ephemeral() || !storage.exists() {
+        return Ok(None);
+    }
+    let backups = BackupStore::for_vault(storage)?;
+    let id = backups.write_full(storage.read()?)?;
+    record_backup(storage)?;
+    Ok(Some(id))
+}
+
+/// Replaces the Vault with the state captured in a backup. The backup must decrypt
+/// with the current Session key.
+pub fn restore(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    backups: &BackupStore,
+    id: &str,
+) -> Result<usize, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let key = **workspace.session_key.as_ref().ok_or("Vault is locked")?;
+
+    let (file, entries) = backups
+        .load_entries(id, &key)
+        .map_err(|_| "Backup could not be decrypted with the current credentials".to_string())?;
+
+    let vault_data = VaultData {
+        entries: entries.clone(),
+    };
+    let json =
+        serde_json::to_string(&vault_data).map_err(|e| format!("Failed to serialize: {}", e))?;
+    let previous = storage.read_header().ok().and_then(|v| v.summary);
+    let vault = EncryptedVault {
+        version: "2".to_string(),
+        provider: OAuthProvider::for_vault(&file.kdf, &file.salt),
+        kdf: file.kdf,
+        salt: file.salt,
+        data: aead::encrypt(&key, &json)?,
+        summary: Some(VaultSummary::updated(previous.as_ref(), entries.len())),
+        journal: Vec::new(),
+    };
+    storage.write(&vault)?;
+
+    let count = entries.len();
+    workspace.credentials = entries;
+    workspace.notify(VaultEvent::EntriesChanged { count });
+    Ok(count)
+}
+
+/// Stamps the Vault header with the time of the latest backup.
+pub fn record_backup(storage: &VaultStorage) -> Result<(), String> {
+    let mut summary = storage.read_header()?.summary.unwrap_or_default();
+    summary.last_backup = Some(unix_now());
+    storage.append(JournalEntry::summary(summary))
+}
+
+fn decrypt_entries(key: &[u8; 32], file: &BackupFile) -> Result<Vec<Entry>, String> {
+    super::journal::open(key, &file.data, &file.journal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+
+    fn entry(id: &str, password: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: format!("Title {}", id),
+            username: "user".to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn provisioned(dir: &tempfile::TempDir) -> (VaultStorage, Workspace, BackupStore) {
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        crate::vault::provision::provision(
+            &storage,
+            &mut workspace,
+            &[9u8; 32],
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        let backups = BackupStore::for_vault(&storage).unwrap();
+        (storage, workspace, backups)
+    }
+
+    #[test]
+    fn delta_captures_changes_and_removals() {
+        let base = vec![entry("1", "a"), entry("2", "b"), entry("3", "c")];
+        let current = vec![entry("1", "a"), entry("2", "changed"), entry("4", "d")];
+
+        let delta = EntryDelta::between(&base, &current);
+        assert_eq!(delta.upserted.len(), 2);
+        assert_eq!(delta.removed, vec!["3".to_string()]);
+
+        let rebuilt = delta.apply(base);
+        assert_eq!(rebuilt, current);
+    }
+
+    #[test]
+    fn first_snapshot_is_full_then_differential() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace, backups) = provisioned(&dir);
+        for i in 0..4 {
+            let e = entry(&i.to_string(), "pw");
+            crate::vault::entries::add(&mut workspace, &storage, e).unwrap();
+        }
+
+        let first = backups
+            .snapshot(&storage, &workspace, BackupMode::Differential)
+            .unwrap()
+            .unwrap();
+        assert!(first.ends_with("-full"));
+
+        crate::vault::entries::add(&mut workspace, &storage, entry("new", "pw")).unwrap();
+        let second = backups
+            .snapshot(&storage, &workspace, BackupMode::Differential)
+            .unwrap()
+            .unwrap();
+        assert!(second.ends_with("-diff"));
+
+        let (_, restored) = backups.load_entries(&second, &[9u8; 32]).unwrap();
+        assert_eq!(restored, workspace.credentials);
+    }
+
+    #[test]
+    fn unchanged_vault_skips_differential() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, workspace, backups) = provisioned(&dir);
+        backups
+            .snapshot(&storage, &workspace, BackupMode::Differential)
+            .unwrap();
+
+        let result = backups
+            .snapshot(&storage, &workspace, BackupMode::Differential)
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn header_summary_tracks_entries_and_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace, _) = provisioned(&dir);
+        crate::vault::entries::add(&mut workspace, &storage, entry("1", "pw")).unwrap();
+
+        let summary = storage.read().unwrap().summary.unwrap();
+        assert_eq!(summary.entry_count, 1);
+        assert!(summary.last_backup.is_none());
+
+        record_backup(&storage).unwrap();
+        crate::vault::entries::add(&mut workspace, &storage, entry("2", "pw")).unwrap();
+
+        let summary = storage.read().unwrap().summary.unwrap();
+        assert_eq!(summary.entry_count, 2);
+        assert!(summary.last_backup.is_some());
+    }
+
+    #[test]
+    fn safety_backup_is_full_and_restorable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace, backups) = provisioned(&dir);
+        crate::vault::entries::add(&mut workspace, &storage, entry("1", "pw")).unwrap();
+
+        let id = safety_backup(&storage).unwrap().unwrap();
+        assert!(id.ends_with("-full"));
+        let (_, saved) = backups.load_entries(&id, &[9u8; 32]).unwrap();
+        assert_eq!(saved, workspace.credentials);
+        assert_eq!(safety_backup(&VaultStorage::ephemeral()).unwrap(), None);
+    }
+
+    #[test]
+    fn restore_replaces_workspace_and_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace, backups) = provisioned(&dir);
+        crate::vault::entries::add(&mut workspace, &storage, entry("1", "pw")).unwrap();
+        let id = backups
+            .snapshot(&storage, &workspace, BackupMode::Full)
+            .unwrap()
+            .unwrap();
+
+        crate::vault::entries::delete(&mut workspace, &storage, "1").unwrap();
+        assert!(workspace.credentials.is_empty());
+
+        let count = restore(&storage, &mut workspace, &backups, &id).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(workspace.credentials[0].id, "1");
+    }
+}
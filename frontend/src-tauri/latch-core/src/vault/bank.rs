@@ -0,0 +1,167 @@
+//! Bank account Credentials. The account number, routing code and SWIFT code are
+//! kept on the `EntryKind`, the PIN in `password`; previews show the account
+//! number masked, and each field is fetched on its own with `get_field`.
+
+use super::EntryKind;
+
+/// The fields `get_field` serves from a bank account, besides `title` and `pin`.
+pub const FIELDS: [&str; 3] = ["account_number", "routing_code", "swift"];
+
+/// IBANs are checked against their checksum; anything else is taken as a
+/// domestic account number of digits, spaces and dashes.
+fn is_iban(account: &str) -> bool {
+    let chars: Vec<char> = account.chars().collect();
+    chars.len() >= 4 && chars[..2].iter().all(char::is_ascii_alphabetic)
+}
+
+fn iban_checksum_ok(iban: &str) -> bool {
+    if iban.len() < 15 || iban.len() > 34 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = c.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+fn digits(value: &str) -> usize {
+    value.chars().filter(char::is_ascii_digit).count()
+}
+
+fn only_digits_spaces_dashes(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == ' ' || c == '-')
+}
+
+/// A BIC: bank, country and location codes, then an optional branch.
+fn is_swift(code: &str) -> bool {
+    code.is_ascii()
+        && (code.len() == 8 || code.len() == 11)
+        && code[..6].chars().all(|c| c.is_ascii_alphabetic())
+        && code[6..].chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Checks and tidies a bank account's fields: IBANs and SWIFT codes are stored
+/// uppercase without spaces, blank optional fields as None.
+pub fn validate(
+    account_number: &str,
+    routing_code: Option<String>,
+    swift: Option<String>,
+    pin: Option<&str>,
+) -> Result<EntryKind, String> {
+    let account = account_number.trim();
+    let account_number = if is_iban(account) {
+        let iban: String = account
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if !iban_checksum_ok(&iban) {
+            return Err("Invalid IBAN: the check digits don't match".to_string());
+        }
+        iban
+    } else if only_digits_spaces_dashes(account) && (4..=34).contains(&digits(account)) {
+        account.to_string()
+    } else {
+        return Err("Invalid account number: use an IBAN or 4 to 34 digits".to_string());
+    };
+
+    let routing_code = routing_code
+        .map(|code| code.trim().to_string())
+        .filter(|code| !code.is_empty());
+    if routing_code
+        .as_deref()
+        .is_some_and(|code| !only_digits_spaces_dashes(code) || !(4..=12).contains(&digits(code)))
+    {
+        return Err("Invalid routing or sort code: use 4 to 12 digits".to_string());
+    }
+
+    let swift = swift
+        .map(|code| code.trim().to_ascii_uppercase())
+        .filter(|code| !code.is_empty());
+    if swift.as_deref().is_some_and(|code| !is_swift(code)) {
+        return Err("Invalid SWIFT code: use 8 or 11 letters and digits".to_string());
+    }
+
+    if pin.is_some_and(|pin| {
+        !pin.chars().all(|c| c.is_ascii_digit()) || !(4..=12).contains(&pin.len())
+    }) {
+        return Err("Invalid PIN: use 4 to 12 digits".to_string());
+    }
+
+    Ok(EntryKind::BankAccount {
+        account_number,
+        routing_code,
+        swift,
+    })
+}
+
+/// The last four characters of the account number, the rest hidden.
+pub fn mask(account_number: &str) -> String {
+    let shown: String = account_number
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let tail = &shown[shown.len().saturating_sub(4)..];
+    format!("•••• {}", tail)
+}
+
+/// One of `FIELDS` from a bank account; None for other kinds, or an optional
+/// field left blank.
+pub fn field(kind: &EntryKind, field: &str) -> Option<String> {
+    let EntryKind::BankAccount {
+        account_number,
+        routing_code,
+        swift,
+    } = kind
+    else {
+        return None;
+    };
+    match field {
+        "account_number" => Some(account_number.clone()),
+        "routing_code" => routing_code.clone(),
+        "swift" => swift.clone(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_are_checked_tidied_and_masked() {
+        let kind = validate(
+            "gb82 west 1234 5698 7654 32",
+            Some(" 12-34-56 ".to_string()),
+            Some("westgb2l".to_string()),
+            Some("1234"),
+        )
+        .unwrap();
+        assert_eq!(
+            field(&kind, "account_number").as_deref(),
+            Some("GB82WEST12345698765432")
+        );
+        assert_eq!(field(&kind, "routing_code").as_deref(), Some("12-34-56"));
+        assert_eq!(field(&kind, "swift").as_deref(), Some("WESTGB2L"));
+        assert_eq!(mask("GB82WEST12345698765432"), "•••• 5432");
+
+        let domestic = validate("000123456789", Some(String::new()), None, None).unwrap();
+        assert_eq!(field(&domestic, "routing_code"), None);
+        assert_eq!(field(&EntryKind::Login, "account_number"), None);
+
+        assert!(validate("GB82WEST12345698765431", None, None, None).is_err());
+        assert!(validate("12", None, None, None).is_err());
+        assert!(validate("000123456789", Some("12ab".to_string()), None, None).is_err());
+        assert!(validate("000123456789", None, Some("WEST".to_string()), None).is_err());
+        assert!(validate("000123456789", None, None, Some("12")).is_err());
+    }
+}
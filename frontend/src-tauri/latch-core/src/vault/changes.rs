@@ -0,0 +1,135 @@
+//! Per-Credential change log, kept on the Credential inside the encrypted Vault:
+//! which field an edit changed, when, and in which Session. For passwords only
+//! the fact of the change is logged, and URLs have their secrets redacted.
+
+use super::Entry;
+use crate::vault_health::url_secrets::without_secrets;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Changes kept per Credential before the oldest is dropped.
+pub const MAX_CHANGES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangedField {
+    Title,
+    Username,
+    Password,
+    Url,
+    Folder,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct EntryChange {
+    pub field: ChangedField,
+    /// The value before the change; never set for the password, and for the URL,
+    /// the URL with its secrets redacted.
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub at: u64,
+    /// Unix time the Session that made the change was unlocked.
+    pub session: Option<u64>,
+}
+
+/// `change` with any URL secrets redacted and password fingerprints dropped, for
+/// changes logged before `record` left them out itself.
+pub fn redacted(mut change: EntryChange) -> EntryChange {
+    match change.field {
+        ChangedField::Url => {
+            change.old = change.old.as_deref().map(without_secrets);
+            change.new = change.new.as_deref().map(without_secrets);
+        }
+        ChangedField::Password => {
+            change.old = None;
+            change.new = None;
+        }
+        _ => {}
+    }
+    change
+}
+
+/// Appends to `after`'s log whatever changed since `before`.
+pub fn record(before: &Entry, after: &mut Entry, at: u64, session: Option<u64>) {
+    let text = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty());
+    let fields = [
+        (ChangedField::Title, text(&before.title), text(&after.title)),
+        (
+            ChangedField::Username,
+            text(&before.username),
+            text(&after.username),
+        ),
+        (ChangedField::Password, None, None),
+        (
+            ChangedField::Url,
+            before.url.as_deref().map(without_secrets),
+            after.url.as_deref().map(without_secrets),
+        ),
+        (
+            ChangedField::Folder,
+            before.folder.clone(),
+            after.folder.clone(),
+        ),
+    ];
+    for (field, old, new) in fields {
+        let changed = match field {
+            // Even a hash of the password would let a short one be guessed offline.
+            ChangedField::Password => before.password != after.password,
+            _ => old != new,
+        };
+        if changed {
+            after.changes.push(EntryChange {
+                field,
+                old,
+                new,
+                at,
+                session,
+            });
+        }
+    }
+    let excess = after.changes.len().saturating_sub(MAX_CHANGES);
+    after.changes.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_are_logged_per_field_without_passwords() {
+        let before = Entry {
+            id: "1".to_string(),
+            title: "Bank".to_string(),
+            username: "old@example.com".to_string(),
+            password: "hunter2".to_string(),
+            ..Default::default()
+        };
+        let mut after = Entry {
+            username: "new@example.com".to_string(),
+            password: "correct horse".to_string(),
+            ..before.clone()
+        };
+
+        record(&before, &mut after, 100, Some(50));
+        assert_eq!(after.changes.len(), 2);
+        assert_eq!(after.changes[0].field, ChangedField::Username);
+        assert_eq!(after.changes[0].old.as_deref(), Some("old@example.com"));
+        assert_eq!(after.changes[0].session, Some(50));
+        let password = &after.changes[1];
+        assert_eq!(password.field, ChangedField::Password);
+        assert_eq!((&password.old, &password.new), (&None, &None));
+        let legacy = EntryChange {
+            old: Some("0a1b2c3d".to_string()),
+            ..password.clone()
+        };
+        assert_eq!(redacted(legacy).old, None);
+
+        for at in 0..MAX_CHANGES as u64 {
+            let before = after.clone();
+            after.title = format!("Bank {}", at);
+            record(&before, &mut after, at, None);
+        }
+        assert_eq!(after.changes.len(), MAX_CHANGES);
+        assert_eq!(after.changes[0].field, ChangedField::Title);
+    }
+}
@@ -0,0 +1,70 @@
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
+use std::cmp::Ordering;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Orders titles the way the user's language does: accented letters sit next to
+/// their base letter and CJK follows the CLDR order rather than code points.
+pub struct Collation {
+    collator: Collator,
+}
+
+impl Collation {
+    /// Collation for a BCP 47 tag such as `fr-CA`, falling back to the CLDR root
+    /// order for tags that are missing, malformed or have no tailoring.
+    pub fn for_locale(tag: Option<&str>) -> Self {
+        let locale: Locale = tag
+            .and_then(|tag| tag.replace('_', "-").parse().ok())
+            .unwrap_or_default();
+        let collator = Collator::try_new(&(&locale).into(), CollatorOptions::new())
+            .or_else(|_| Collator::try_new(&Default::default(), CollatorOptions::new()))
+            .expect("root collation data is compiled in");
+        Self { collator }
+    }
+
+    /// Collation for the operating system's language.
+    pub fn system() -> Self {
+        Self::for_locale(sys_locale::get_locale().as_deref())
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collator.compare(a, b)
+    }
+}
+
+/// Folds text for matching: compatibility forms (full-width Latin, ligatures) are
+/// unified, accents dropped and case ignored, so "École" matches "ecole". Scripts
+/// without case or accents, such as CJK, pass through unchanged.
+pub fn fold(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_accents_case_and_width() {
+        assert_eq!(fold("École"), "ecole");
+        assert_eq!(fold("ＧｉｔＨｕｂ"), "github");
+        assert_eq!(fold("Straße"), "straße");
+        assert_eq!(fold("東京"), "東京");
+    }
+
+    #[test]
+    fn sorts_accented_titles_beside_their_base_letter() {
+        let collation = Collation::for_locale(Some("en-US"));
+        let mut titles = vec!["Zoom", "école", "Ebay", "apple", "Éclair"];
+        titles.sort_by(|a, b| collation.compare(a, b));
+        assert_eq!(titles, ["apple", "Ebay", "Éclair", "école", "Zoom"]);
+    }
+
+    #[test]
+    fn unknown_locales_use_the_root_order() {
+        let collation = Collation::for_locale(Some("not a locale"));
+        assert_eq!(collation.compare("a", "B"), Ordering::Less);
+    }
+}
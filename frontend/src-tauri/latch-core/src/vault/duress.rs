@@ -0,0 +1,156 @@
+use super::{storage::VaultStorage, workspace::Workspace, Entry};
+use crate::auth::method::AuthMethod;
+
+const DECOY_DIR: &str = "secondary";
+
+const DECOY_TITLES: &[(&str, &str)] = &[
+    ("Weather Forum", "https://forum.example.com"),
+    ("Recipe Club", "https://recipes.example.com"),
+    ("Library Account", "https://library.example.org"),
+];
+
+/// Storage for the decoy Vault opened by the duress password. It lives in its own
+/// directory so its backups never mix with the real Vault's.
+pub fn decoy_storage(storage: &VaultStorage) -> Result<VaultStorage, String> {
+    if storage.is_ephemeral() {
+        return Err("Duress vault is unavailable for an ephemeral vault".to_string());
+    }
+    let parent = storage.path.parent().ok_or("Invalid vault path")?;
+    let file_name = storage.path.file_name().ok_or("Invalid vault path")?;
+    Ok(VaultStorage::at(parent.join(DECOY_DIR).join(file_name)))
+}
+
+pub fn is_configured(storage: &VaultStorage) -> bool {
+    decoy_storage(storage).map(|s| s.exists()).unwrap_or(false)
+}
+
+/// Creates (or replaces) the decoy Vault, seeded with a few innocuous Credentials.
+/// Only callable from a real, unlocked Workspace.
+pub fn configure(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    key: &[u8; 32],
+    salt_hex: &str,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    if workspace.decoy {
+        return Err("Vault is locked".to_string());
+    }
+    if workspace.session_key.as_deref() == Some(key) {
+        return Err("Duress password must differ from the master password".to_string());
+    }
+
+    let decoy = decoy_storage(storage)?;
+    let dir = decoy.path.parent().ok_or("Invalid vault path")?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    if decoy.exists() {
+        std::fs::remove_file(&decoy.path)
+            .map_err(|e| format!("Failed to replace duress vault: {}", e))?;
+    }
+
+    let mut decoy_workspace = Workspace::new();
+    super::provision::provision(
+        &decoy,
+        &mut decoy_workspace,
+        key,
+        AuthMethod::Password,
+        salt_hex,
+    )?;
+
+    let options = crate::password_generator::PasswordOptions::default();
+    for (title, url) in DECOY_TITLES {
+        decoy_workspace.credentials.push(Entry {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            username: "user".to_string(),
+            password: crate::password_generator::generate_password(&options)?,
+            url: Some(url.to_string()),
+            ..Default::default()
+        });
+    }
+    let result = super::entries::persist(&decoy_workspace, &decoy);
+    decoy_workspace.lock();
+    result
+}
+
+pub fn remove(storage: &VaultStorage, workspace: &mut Workspace) -> Result<(), String> {
+    workspace.check_session()?;
+    if workspace.decoy {
+        return Err("Vault is locked".to_string());
+    }
+    let decoy = decoy_storage(storage)?;
+    if decoy.exists() {
+        std::fs::remove_file(&decoy.path)
+            .map_err(|e| format!("Failed to remove duress vault: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Tries the password against the decoy Vault. On success the Workspace is bound to
+/// the decoy and every later save goes to the decoy file.
+pub fn access_with_password(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    password: &str,
+) -> Result<(), String> {
+    let decoy = decoy_storage(storage)?;
+    if !decoy.exists() {
+        return Err("Failed to unlock vault".to_string());
+    }
+    let vault_file = decoy.read()?;
+    let salt = crate::auth::password::decode_salt_hex(&vault_file.salt)?;
+    let key = crate::auth::password::derive_key(password, &salt);
+    super::access::access(&decoy, workspace, &key)?;
+    workspace.decoy = true;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(dir: &tempfile::TempDir) -> (VaultStorage, Workspace) {
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        super::super::provision::provision(
+            &storage,
+            &mut workspace,
+            &[1u8; 32],
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        (storage, workspace)
+    }
+
+    #[test]
+    fn duress_password_opens_decoy_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = setup(&dir);
+
+        let salt = crate::auth::password::generate_salt();
+        let key = crate::auth::password::derive_key("duress", &salt);
+        configure(&storage, &mut workspace, &key, &hex::encode(salt)).unwrap();
+        assert!(is_configured(&storage));
+
+        workspace.lock();
+        access_with_password(&storage, &mut workspace, "duress").unwrap();
+
+        assert!(workspace.decoy);
+        assert_eq!(workspace.credentials.len(), DECOY_TITLES.len());
+
+        workspace.lock();
+        assert!(!workspace.decoy);
+        assert!(access_with_password(&storage, &mut workspace, "wrong").is_err());
+    }
+
+    #[test]
+    fn duress_key_must_differ_from_session_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = setup(&dir);
+
+        let result = configure(&storage, &mut workspace, &[1u8; 32], "salt");
+        assert!(result.is_err());
+        assert!(!is_configured(&storage));
+    }
+}
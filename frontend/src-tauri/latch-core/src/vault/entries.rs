@@ -0,0 +1,819 @@
+use super::backup::EntryDelta;
+use super::changes::{self, EntryChange};
+use super::collation::Collation;
+use super::events::VaultEvent;
+use super::history::Operation;
+use super::journal::{Baseline, JournalEntry, COMPACT_AFTER};
+use super::{api_key, bank, server};
+use super::{
+    storage::VaultStorage, unix_now, workspace::Workspace, Entry, EntryIcon, EntryKind, VaultData,
+    VaultSummary,
+};
+use crate::crypto::aead;
+use crate::password_generator::{self, PasswordOptions};
+use crate::vault_health::expiry::PasswordExpiry;
+use crate::vault_health::watch::AlertChannel;
+use std::time::UNIX_EPOCH;
+
+pub fn add(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    mut entry: Entry,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    entry.updated_at = Some(unix_now());
+    let before = workspace.credentials.clone();
+    workspace.credentials.push(entry);
+    persist_as(workspace, storage, Operation::Add, before)
+}
+
+/// Adds several Credentials in one save.
+pub fn add_all(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    entries: Vec<Entry>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let now = unix_now();
+    let before = workspace.credentials.clone();
+    workspace
+        .credentials
+        .extend(entries.into_iter().map(|entry| Entry {
+            updated_at: Some(now),
+            ..entry
+        }));
+    persist_as(workspace, storage, Operation::BulkAdd, before)
+}
+
+pub fn get_full(workspace: &mut Workspace, id: &str) -> Result<Entry, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter()
+        .find(|e| e.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    workspace.reveal(&entry)?;
+    Ok(Entry {
+        password: resolved_password(&workspace.credentials, &entry),
+        ..entry
+    })
+}
+
+pub fn update(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    mut entry: Entry,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let idx = workspace
+        .credentials
+        .iter()
+        .position(|e| e.id == entry.id)
+        .ok_or_else(|| format!("Credential '{}' not found", entry.id))?;
+    // Set by their own calls, never by an edit.
+    let current = &workspace.credentials[idx];
+    let now = unix_now();
+    entry.verification = current.verification.touched();
+    entry.usage = current.usage;
+    entry.favorite = current.favorite;
+    entry.icon = current.icon.clone();
+    entry.shared_from = current.shared_from.clone();
+    entry.expiry = match current.expiry {
+        Some(expiry) if entry.password != current.password => Some(expiry.rotated(now)),
+        expiry => expiry,
+    };
+    entry.updated_at = Some(now);
+    entry.changes = current.changes.clone();
+    let rotated = entry.password != current.password;
+    entry.linked_to = current.linked_to.clone().filter(|_| !rotated);
+    let session = session_id(workspace);
+    changes::record(current, &mut entry, now, session);
+    let before = workspace.credentials.clone();
+    let (id, password) = (entry.id.clone(), entry.password.clone());
+    workspace.credentials[idx] = entry;
+    if rotated {
+        for linked in workspace
+            .credentials
+            .iter_mut()
+            .filter(|e| e.linked_to.as_ref() == Some(&id))
+        {
+            share_password(linked, &password, now, session);
+        }
+    }
+    persist_as(workspace, storage, Operation::Update, before)
+}
+
+/// Gives a linked Credential its parent's new password.
+fn share_password(entry: &mut Entry, password: &str, now: u64, session: Option<u64>) {
+    if entry.password == password {
+        return;
+    }
+    let before = entry.clone();
+    entry.password = password.to_string();
+    entry.expiry = entry.expiry.map(|expiry| expiry.rotated(now));
+    entry.updated_at = Some(now);
+    changes::record(&before, entry, now, session);
+}
+
+/// Makes Credential `id` share `parent_id`'s password from now on, or unlinks it.
+/// Links are one level deep: a Credential that others link to can't be linked.
+pub fn link(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let idx = workspace
+        .credentials
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    let before = workspace.credentials.clone();
+    let Some(parent_id) = parent_id else {
+        workspace.credentials[idx].linked_to = None;
+        return persist_as(workspace, storage, Operation::Update, before);
+    };
+
+    if parent_id == id {
+        return Err("Invalid link: a Credential can't link to itself".to_string());
+    }
+    let parent = before
+        .iter()
+        .find(|e| e.id == parent_id)
+        .ok_or_else(|| format!("Credential '{}' not found", parent_id))?;
+    if parent.linked_to.is_some() {
+        return Err(format!(
+            "Invalid link: '{}' is itself linked to another Credential",
+            parent.title
+        ));
+    }
+    if before.iter().any(|e| e.linked_to.as_deref() == Some(id)) {
+        return Err("Invalid link: other Credentials link to this one".to_string());
+    }
+    let is_reference = |e: &Entry| matches!(e.kind, EntryKind::Reference { .. });
+    if is_reference(parent) || is_reference(&before[idx]) {
+        return Err("Invalid link: reference Credentials have no password to share".to_string());
+    }
+
+    let session = session_id(workspace);
+    let entry = &mut workspace.credentials[idx];
+    entry.linked_to = Some(parent_id.to_string());
+    share_password(entry, &parent.password, unix_now(), session);
+    persist_as(workspace, storage, Operation::Update, before)
+}
+
+/// A Credential's password, read through its link if it has one.
+fn resolved_password(credentials: &[Entry], entry: &Entry) -> String {
+    entry
+        .linked_to
+        .as_ref()
+        .and_then(|parent| credentials.iter().find(|e| &e.id == parent))
+        .map_or_else(|| entry.password.clone(), |parent| parent.password.clone())
+}
+
+/// Unlinks Credentials whose parent is gone, and servers whose SSH key is; they
+/// keep the password they had.
+fn unlink_orphans(credentials: &mut [Entry]) {
+    let ids: std::collections::HashSet<String> = credentials.iter().map(|e| e.id.clone()).collect();
+    for entry in credentials.iter_mut() {
+        if entry.linked_to.as_ref().is_some_and(|p| !ids.contains(p)) {
+            entry.linked_to = None;
+        }
+        if let EntryKind::Server { ssh_key, .. } = &mut entry.kind {
+            if ssh_key.as_ref().is_some_and(|k| !ids.contains(k)) {
+                *ssh_key = None;
+            }
+        }
+    }
+}
+
+/// Identifies the Session in change logs by when it was unlocked.
+fn session_id(workspace: &Workspace) -> Option<u64> {
+    workspace
+        .session_start
+        .and_then(|start| start.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+}
+
+/// The Credential's change log, newest first.
+pub fn history(workspace: &mut Workspace, id: &str) -> Result<Vec<EntryChange>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    Ok(entry
+        .changes
+        .iter()
+        .rev()
+        .cloned()
+        .map(changes::redacted)
+        .collect())
+}
+
+pub fn delete(workspace: &mut Workspace, storage: &VaultStorage, id: &str) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let before = workspace.credentials.clone();
+    workspace.credentials.retain(|e| e.id != id);
+    if workspace.credentials.len() == before.len() {
+        return Err("Credential not found".to_string());
+    }
+    unlink_orphans(&mut workspace.credentials);
+    persist_as(workspace, storage, Operation::Delete, before)
+}
+
+/// Deletes every listed Credential in one save. Unknown ids fail the whole call.
+pub fn delete_many(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    ids: &[String],
+) -> Result<usize, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    if let Some(missing) = ids
+        .iter()
+        .find(|id| !workspace.credentials.iter().any(|e| &e.id == *id))
+    {
+        return Err(format!("Credential '{}' not found", missing));
+    }
+    let before = workspace.credentials.clone();
+    workspace.credentials.retain(|e| !ids.contains(&e.id));
+    let deleted = before.len() - workspace.credentials.len();
+    unlink_orphans(&mut workspace.credentials);
+    persist_as(workspace, storage, Operation::BulkDelete, before)?;
+    Ok(deleted)
+}
+
+/// Undoes the last add, edit or delete this Session. None if there is nothing
+/// to undo.
+pub fn undo(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+) -> Result<Option<Operation>, String> {
+    step_history(workspace, storage, true)
+}
+
+/// Repeats the last undone operation. None if there is nothing to redo.
+pub fn redo(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+) -> Result<Option<Operation>, String> {
+    step_history(workspace, storage, false)
+}
+
+fn step_history(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    undo: bool,
+) -> Result<Option<Operation>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let credentials = &mut workspace.credentials;
+    let operation = if undo {
+        workspace.history.undo(credentials)
+    } else {
+        workspace.history.redo(credentials)
+    };
+    let Some(operation) = operation else {
+        return Ok(None);
+    };
+    if let Err(e) = persist(workspace, storage) {
+        workspace.history.revert(undo, &mut workspace.credentials);
+        return Err(e);
+    }
+    Ok(Some(operation))
+}
+
+pub fn get_field(workspace: &mut Workspace, id: &str, field: &str) -> Result<String, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter()
+        .find(|e| e.id == id)
+        .cloned()
+        .ok_or("Credential not found".to_string())?;
+    match field {
+        "title" => Ok(entry.title),
+        "username" => Ok(entry.username),
+        "password" => {
+            workspace.reveal(&entry)?;
+            Ok(resolved_password(&workspace.credentials, &entry))
+        }
+        "pin" if matches!(entry.kind, EntryKind::BankAccount { .. }) => {
+            workspace.reveal(&entry)?;
+            Ok(entry.password)
+        }
+        field if bank::FIELDS.contains(&field) => {
+            bank::field(&entry.kind, field).ok_or("Field not found".to_string())
+        }
+        "secret" if matches!(entry.kind, EntryKind::ApiKey { .. }) => {
+            workspace.reveal(&entry)?;
+            Ok(entry.password)
+        }
+        "expires_at" if matches!(entry.kind, EntryKind::ApiKey { .. }) => entry
+            .expiry
+            .map(|expiry| expiry.due_at().to_string())
+            .ok_or("Field not found".to_string()),
+        field if api_key::FIELDS.contains(&field) => {
+            api_key::field(&entry.kind, field).ok_or("Field not found".to_string())
+        }
+        "private_key" => {
+            let EntryKind::Server {
+                ssh_key: Some(key_id),
+                ..
+            } = &entry.kind
+            else {
+                return Err("Field not found".to_string());
+            };
+            let key = workspace
+                .credentials
+                .iter()
+                .find(|e| &e.id == key_id)
+                .cloned()
+                .ok_or("SSH key not found".to_string())?;
+            workspace.reveal(&key)?;
+            Ok(key.password)
+        }
+        field if server::FIELDS.contains(&field) => {
+            server::field(&entry, field).ok_or("Field not found".to_string())
+        }
+        _ => Err("Field not found".to_string()),
+    }
+}
+
+/// Counts a use of the Credential, e.g. a secret copied from it, towards its
+/// search ranking. A guest's uses aren't counted, since a guest can't save.
+pub fn record_use(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    if workspace.guest.is_some() {
+        return Ok(());
+    }
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.usage.record(unix_now());
+    persist(workspace, storage)
+}
+
+/// Files a Credential under `folder`, or takes it out of any folder.
+pub fn set_folder(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    folder: Option<String>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let session = session_id(workspace);
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    let before = entry.clone();
+    entry.folder = folder;
+    changes::record(&before, entry, unix_now(), session);
+    persist(workspace, storage)
+}
+
+/// Puts a Credential on the breach watch with `channel` for its alerts, or takes
+/// it off.
+pub fn set_breach_watch(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    channel: Option<AlertChannel>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.breach_watch = channel;
+    persist(workspace, storage)
+}
+
+/// Pins the Credential to the tray menu, or unpins it.
+pub fn set_favorite(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    favorite: bool,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.favorite = favorite;
+    persist(workspace, storage)
+}
+
+/// Shown in place of the site icon; None goes back to it.
+pub fn set_custom_icon(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    icon: Option<EntryIcon>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.icon = icon;
+    persist(workspace, storage)
+}
+
+fn change_expiry(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    change: impl FnOnce(&Entry, u64) -> Result<Option<PasswordExpiry>, String>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.expiry = change(entry, unix_now())?;
+    persist(workspace, storage)
+}
+
+/// Asks for the password to be changed every `interval_days`, counting from its
+/// last change; None stops the reminders.
+pub fn set_expiry(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    interval_days: Option<u32>,
+) -> Result<(), String> {
+    change_expiry(workspace, storage, id, |entry, now| {
+        let Some(days) = interval_days else {
+            return Ok(None);
+        };
+        let rotated_at = entry
+            .expiry
+            .map(|e| e.rotated_at)
+            .or(entry.updated_at)
+            .unwrap_or(now);
+        PasswordExpiry::new(days, rotated_at).map(Some)
+    })
+}
+
+/// Holds off the reminder for `days` without changing the password.
+pub fn snooze_expiry(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    days: u32,
+) -> Result<(), String> {
+    change_expiry(workspace, storage, id, |entry, now| {
+        let expiry = entry.expiry.ok_or("This Credential has no expiry")?;
+        expiry.snoozed(days, now).map(Some)
+    })
+}
+
+/// Restarts the interval without an edit, e.g. once the secret a reference points
+/// at has been changed.
+pub fn mark_rotated(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+) -> Result<(), String> {
+    change_expiry(workspace, storage, id, |entry, now| {
+        let expiry = entry.expiry.ok_or("This Credential has no expiry")?;
+        Ok(Some(expiry.rotated(now)))
+    })
+}
+
+/// Title and id of each favorite, by title, for the tray menu.
+pub fn favorites(workspace: &mut Workspace) -> Vec<(String, String)> {
+    if workspace.check_session().is_err() || workspace.guest.is_some() {
+        return Vec::new();
+    }
+    let mut favorites: Vec<(String, String)> = workspace
+        .credentials
+        .iter()
+        .filter(|e| e.favorite)
+        .map(|e| (e.id.clone(), e.title.clone()))
+        .collect();
+    favorites.sort_by_cached_key(|(_, title)| title.to_lowercase());
+    favorites
+}
+
+pub fn set_icon(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    icon_url: Option<String>,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.icon_url = icon_url;
+    persist(workspace, storage)
+}
+
+/// Sets how new passwords for the Credential are generated, or goes back to the
+/// defaults with None. Options `generate_password` would refuse are refused here.
+pub fn set_password_policy(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    policy: Option<PasswordOptions>,
+) -> Result<(), String> {
+    if let Some(policy) = &policy {
+        password_generator::entropy_bits(policy)?;
+    }
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.password_policy = policy;
+    persist(workspace, storage)
+}
+
+/// The options to generate the Credential's next password with.
+pub fn password_policy(workspace: &mut Workspace, id: &str) -> Result<PasswordOptions, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    Ok(entry.password_policy.clone().unwrap_or_default())
+}
+
+/// Every folder in use, sorted.
+pub fn folders(workspace: &mut Workspace, collation: &Collation) -> Result<Vec<String>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let mut folders: Vec<String> = workspace
+        .credentials
+        .iter()
+        .filter_map(|e| e.folder.clone())
+        .collect();
+    folders.sort_by(|a, b| collation.compare(a, b));
+    folders.dedup();
+    Ok(folders)
+}
+
+/// Saves, and records the change from `before` in the Session's history.
+fn persist_as(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    operation: Operation,
+    before: Vec<Entry>,
+) -> Result<(), String> {
+    persist(workspace, storage)?;
+    workspace
+        .history
+        .record(operation, &before, &workspace.credentials);
+    Ok(())
+}
+
+/// Saves the Workspace's Credentials: as a journaled delta against the last save,
+/// or by rewriting the Vault whole when there is no usable last save or the
+/// journal is due for compacting.
+pub fn persist(workspace: &Workspace, storage: &VaultStorage) -> Result<(), String> {
+    let key = workspace.session_key.as_ref().ok_or("Vault is locked")?;
+    // A guest holds only part of the Vault; saving would drop the rest.
+    if workspace.guest.is_some() {
+        return Err(super::guest::GUEST_DENIED.to_string());
+    }
+    if storage.changed_on_disk() {
+        return Err(super::external::CHANGED_ON_DISK.to_string());
+    }
+    let mut saved = workspace
+        .saved
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")?;
+    let count = workspace.credentials.len();
+
+    let journaled = saved.as_mut().filter(|baseline| {
+        baseline.generation == storage.generation() && baseline.pending < COMPACT_AFTER
+    });
+    if let Some(baseline) = journaled {
+        let delta = EntryDelta::between(&baseline.entries, &workspace.credentials);
+        let previous = storage.read_header()?.summary;
+        let summary = VaultSummary::updated(previous.as_ref(), count);
+        storage.append(JournalEntry::sealed(key, &delta, summary)?)?;
+        baseline.entries = workspace.credentials.clone();
+        baseline.pending += 1;
+    } else {
+        let vault_data = VaultData {
+            entries: workspace.credentials.clone(),
+        };
+        let json = serde_json::to_string(&vault_data)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        let encrypted = aead::encrypt(key, &json)?;
+
+        let mut vault = storage.read()?;
+        vault.data = encrypted;
+        vault.journal.clear();
+        vault.summary = Some(VaultSummary::updated(vault.summary.as_ref(), count));
+        storage.write(&vault)?;
+        *saved = Some(Baseline {
+            generation: storage.generation(),
+            pending: 0,
+            entries: vault_data.entries,
+        });
+    }
+    workspace.notify(VaultEvent::EntriesChanged { count });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn unlocked_workspace() -> Workspace {
+        let mut workspace = Workspace::new();
+        workspace.credentials.push(Entry {
+            id: "entry-1".to_string(),
+            title: "Example".to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        });
+        workspace.start([7u8; 32]);
+        workspace
+    }
+
+    #[test]
+    fn password_policy_falls_back_to_the_defaults() {
+        let mut workspace = unlocked_workspace();
+        assert_eq!(
+            password_policy(&mut workspace, "entry-1").unwrap(),
+            PasswordOptions::default()
+        );
+
+        let bank = PasswordOptions {
+            length: 12,
+            symbols: false,
+            exclude_chars: "0O".to_string(),
+            ..Default::default()
+        };
+        workspace.credentials[0].password_policy = Some(bank.clone());
+        let policy = password_policy(&mut workspace, "entry-1").unwrap();
+        assert_eq!(policy, bank);
+        let password = password_generator::generate_password(&policy).unwrap();
+        assert_eq!(password.len(), 12);
+        assert!(password
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && c != '0' && c != 'O'));
+        assert!(password_policy(&mut workspace, "missing").is_err());
+    }
+
+    #[test]
+    fn get_full_rejects_expired_session() {
+        let mut workspace = unlocked_workspace();
+        workspace.session_start =
+            Some(SystemTime::now() - Duration::from_secs(super::super::SESSION_TIMEOUT_SECS + 1));
+
+        let result = get_full(&mut workspace, "entry-1");
+
+        assert_eq!(result.unwrap_err(), "Session expired");
+        assert!(workspace.session_key.is_none());
+        assert!(workspace.credentials.is_empty());
+    }
+
+    #[test]
+    fn reveal_cooldown_holds_until_the_next_unlock() {
+        let mut workspace = unlocked_workspace();
+        workspace.credentials[0].reveal_cooldown_secs = 300;
+
+        assert_eq!(
+            get_field(&mut workspace, "entry-1", "password").unwrap(),
+            "secret"
+        );
+        assert_eq!(
+            get_field(&mut workspace, "entry-1", "username").unwrap(),
+            "user"
+        );
+        assert!(get_field(&mut workspace, "entry-1", "password")
+            .unwrap_err()
+            .starts_with("Unlock again"));
+        assert!(get_full(&mut workspace, "entry-1").is_err());
+
+        workspace.start([7u8; 32]);
+        assert!(get_full(&mut workspace, "entry-1").is_ok());
+    }
+
+    #[test]
+    fn favorites_are_listed_by_title_only_while_unlocked() {
+        let mut workspace = unlocked_workspace();
+        let mut bank = workspace.credentials[0].clone();
+        bank.id = "entry-2".to_string();
+        bank.title = "bank".to_string();
+        workspace.credentials.push(bank);
+        assert!(favorites(&mut workspace).is_empty());
+
+        for entry in &mut workspace.credentials {
+            entry.favorite = true;
+        }
+        assert_eq!(
+            favorites(&mut workspace),
+            vec![
+                ("entry-2".to_string(), "bank".to_string()),
+                ("entry-1".to_string(), "Example".to_string()),
+            ]
+        );
+
+        workspace.lock();
+        assert!(favorites(&mut workspace).is_empty());
+    }
+
+    #[test]
+    fn linked_credentials_follow_their_parents_password() {
+        let storage = VaultStorage::ephemeral();
+        let mut workspace = Workspace::new();
+        super::super::provision::provision(
+            &storage,
+            &mut workspace,
+            &[7u8; 32],
+            crate::auth::method::AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        let console = unlocked_workspace().credentials.remove(0);
+        let cli = Entry {
+            id: "entry-2".to_string(),
+            title: "AWS CLI".to_string(),
+            password: "other".to_string(),
+            ..console.clone()
+        };
+        add_all(&mut workspace, &storage, vec![console.clone(), cli]).unwrap();
+
+        link(&mut workspace, &storage, "entry-2", Some("entry-1")).unwrap();
+        assert_eq!(workspace.credentials[1].password, "secret");
+        assert!(link(&mut workspace, &storage, "entry-1", Some("entry-2")).is_err());
+
+        let rotated = Entry {
+            password: "rotated".to_string(),
+            ..console
+        };
+        update(&mut workspace, &storage, rotated.clone()).unwrap();
+        assert_eq!(
+            get_field(&mut workspace, "entry-2", "password").unwrap(),
+            "rotated"
+        );
+        assert_eq!(workspace.credentials[1].changes.len(), 2);
+
+        // Giving the linked one its own password unlinks it.
+        let own = Entry {
+            password: "own".to_string(),
+            ..workspace.credentials[1].clone()
+        };
+        update(&mut workspace, &storage, own).unwrap();
+        assert_eq!(workspace.credentials[1].linked_to, None);
+        update(
+            &mut workspace,
+            &storage,
+            Entry {
+                password: "again".to_string(),
+                ..rotated
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            get_field(&mut workspace, "entry-2", "password").unwrap(),
+            "own"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use super::{storage::VaultStorage, workspace::Workspace, Entry};
+use crate::auth::method::AuthMethod;
+
+/// Launch flag that swaps the real Vault for an ephemeral one.
+pub const EPHEMERAL_FLAG: &str = "--ephemeral";
+/// Unlocks the ephemeral Vault. It only guards fake data, so it is no secret.
+pub const DEMO_PASSWORD: &str = "latch-demo";
+
+/// Fake Credentials covering what the UI has to handle: plain logins, a reused
+/// and a weak password for the health check, a cooldown and an entry with no URL.
+const DEMO_ENTRIES: &[(&str, &str, &str, Option<&str>)] = &[
+    ("GitHub", "octo.dev", "", Some("https://github.com")),
+    (
+        "Mail",
+        "demo@example.com",
+        "",
+        Some("https://mail.example.com"),
+    ),
+    ("Bank", "demo-user", "", Some("https://bank.example.com")),
+    (
+        "Forum",
+        "demo",
+        "correct-horse",
+        Some("https://forum.example.org"),
+    ),
+    (
+        "Old Forum",
+        "demo",
+        "correct-horse",
+        Some("https://old.example.org"),
+    ),
+    ("Router", "admin", "admin123", None),
+];
+const DEMO_COOLDOWN_TITLE: &str = "Bank";
+
+/// Whether this launch should use an ephemeral Vault, from the launch flag or the
+/// developer setting.
+pub fn requested<I: IntoIterator<Item = String>>(args: I, setting: bool) -> bool {
+    setting || args.into_iter().skip(1).any(|arg| arg == EPHEMERAL_FLAG)
+}
+
+/// An in-memory Vault, locked with `DEMO_PASSWORD` and seeded with fake Credentials.
+pub fn seeded() -> Result<VaultStorage, String> {
+    let storage = VaultStorage::ephemeral();
+    let salt = crate::auth::password::generate_salt();
+    let key = crate::auth::password::derive_key(DEMO_PASSWORD, &salt);
+
+    let mut workspace = Workspace::new();
+    super::provision::provision(
+        &storage,
+        &mut workspace,
+        &key,
+        AuthMethod::Password,
+        &hex::encode(salt),
+    )?;
+
+    let options = crate::password_generator::PasswordOptions::default();
+    for (title, username, password, url) in DEMO_ENTRIES {
+        let password = if password.is_empty() {
+            crate::password_generator::generate_password(&options)?
+        } else {
+            password.to_string()
+        };
+        workspace.credentials.push(Entry {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            username: username.to_string(),
+            password,
+            url: url.map(str::to_string),
+            reveal_cooldown_secs: if *title == DEMO_COOLDOWN_TITLE { 60 } else { 0 },
+            ..Default::default()
+        });
+    }
+    let result = super::entries::persist(&workspace, &storage);
+    workspace.lock();
+    result.map(|_| storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_or_setting_requests_an_ephemeral_vault() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(requested(args(&["latch", "--ephemeral"]), false));
+        assert!(requested(args(&["latch"]), true));
+        assert!(!requested(args(&["latch"]), false));
+        assert!(!requested(args(&["--ephemeral"]), false));
+    }
+
+    #[test]
+    fn seeded_vault_unlocks_with_the_demo_password() {
+        let storage = seeded().unwrap();
+        assert!(storage.is_ephemeral());
+        assert!(crate::vault::backup::BackupStore::for_vault(&storage).is_err());
+        assert!(crate::vault::duress::decoy_storage(&storage).is_err());
+
+        let header = storage.read_header().unwrap();
+        let salt = crate::auth::password::decode_salt_hex(&header.salt).unwrap();
+        let key = crate::auth::password::derive_key(DEMO_PASSWORD, &salt);
+        let mut workspace = Workspace::new();
+        crate::vault::access::access(&storage, &mut workspace, &key).unwrap();
+        assert_eq!(workspace.credentials.len(), DEMO_ENTRIES.len());
+    }
+}
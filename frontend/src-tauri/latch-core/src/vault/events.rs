@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+
+/// Vault state changes pushed to the frontend so it never has to poll `vault_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultEvent {
+    Locked,
+    Unlocked,
+    /// The idle lock is about to lock the Vault.
+    SessionExpiring {
+        seconds_remaining: u64,
+    },
+    /// The Session is about to time out; extending it keeps the Vault open.
+    SessionEnding {
+        seconds_remaining: u64,
+    },
+    /// The Session timed out. Followed by `Locked`.
+    SessionExpired,
+    EntriesChanged {
+        count: usize,
+    },
+}
+
+impl VaultEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VaultEvent::Locked => "vault://locked",
+            VaultEvent::Unlocked => "vault://unlocked",
+            VaultEvent::SessionExpiring { .. } => "vault://session-expiring",
+            VaultEvent::SessionEnding { .. } => "session://expiring",
+            VaultEvent::SessionExpired => "session://expired",
+            VaultEvent::EntriesChanged { .. } => "vault://entries-changed",
+        }
+    }
+
+    pub fn payload(&self) -> Value {
+        match self {
+            VaultEvent::Locked | VaultEvent::Unlocked | VaultEvent::SessionExpired => Value::Null,
+            VaultEvent::SessionExpiring { seconds_remaining }
+            | VaultEvent::SessionEnding { seconds_remaining } => {
+                json!({ "seconds_remaining": seconds_remaining })
+            }
+            VaultEvent::EntriesChanged { count } => json!({ "count": count }),
+        }
+    }
+}
+
+/// Where the Workspace reports its events. The app wires in its `AppHandle`; tests and
+/// throwaway Workspaces (like the one that seeds the duress Vault) have none.
+pub trait VaultEvents: Send + Sync {
+    fn notify(&self, event: VaultEvent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::workspace::Workspace;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<VaultEvent>>);
+
+    impl VaultEvents for Recorder {
+        fn notify(&self, event: VaultEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn workspace_reports_unlock_changes_and_lock_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = crate::vault::storage::VaultStorage::at(dir.path().join("vault.enc"));
+        let recorder = Arc::new(Recorder::default());
+        let mut workspace = Workspace::new().with_events(recorder.clone());
+
+        crate::vault::provision::provision(
+            &storage,
+            &mut workspace,
+            &[2u8; 32],
+            crate::auth::method::AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        let entry = crate::vault::Entry {
+            id: "1".to_string(),
+            title: "Example".to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            ..Default::default()
+        };
+        crate::vault::entries::add(&mut workspace, &storage, entry).unwrap();
+        workspace.lock();
+        workspace.lock();
+
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(events.first(), Some(&VaultEvent::Unlocked));
+        assert!(events.contains(&VaultEvent::EntriesChanged { count: 1 }));
+        assert_eq!(events.last(), Some(&VaultEvent::Locked));
+        assert_eq!(
+            events.iter().filter(|e| **e == VaultEvent::Locked).count(),
+            1
+        );
+        assert_eq!(VaultEvent::Locked.name(), "vault://locked");
+        let ending = VaultEvent::SessionEnding {
+            seconds_remaining: 120,
+        };
+        assert_eq!(ending.name(), "session://expiring");
+        assert_eq!(ending.payload()["seconds_remaining"], 120);
+    }
+}
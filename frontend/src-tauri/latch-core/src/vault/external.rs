@@ -0,0 +1,179 @@
+//! Notices when something other than Latch, like Dropbox or Syncthing, replaces
+//! the Vault file while it is unlocked. Saves are refused until the change is
+//! taken in, so they can't silently overwrite it: a copy that opens with the
+//! Session key is reloaded, merged with anything not yet saved here; one that
+//! doesn't is left for the user to settle with `keep_this_copy` or by locking.
+
+use super::journal::Baseline;
+use super::sync::three_way::{self, SyncConflict};
+use super::{events::VaultEvent, storage::VaultStorage, unix_now, workspace::Workspace};
+use serde::Serialize;
+use specta::Type;
+use std::fs;
+
+pub const CHANGED_ON_DISK: &str =
+    "The vault file was changed outside Latch; reload it before saving";
+
+/// How often the unlocked Vault's file is checked.
+pub const WATCH_INTERVAL_SECS: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExternalChange {
+    /// The new file was loaded; clashes with unsaved edits here were settled by
+    /// `updated_at`.
+    Reloaded {
+        count: usize,
+        conflicts: Vec<SyncConflict>,
+    },
+    /// The new file doesn't open with this Session's key: it was re-keyed or
+    /// replaced with another Vault, or is still being written.
+    Unreadable,
+}
+
+/// Takes in a change to the Vault file made outside Latch, if there is one.
+pub fn check(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+) -> Result<Option<ExternalChange>, String> {
+    if !workspace.is_unlocked() || workspace.guest.is_some() || !storage.changed_on_disk() {
+        return Ok(None);
+    }
+    let key = **workspace.session_key.as_ref().ok_or("Vault is locked")?;
+    let generation = storage.generation();
+    let opened = storage
+        .read()
+        .and_then(|vault| Ok((vault.entries(&key)?, vault.journal.len())));
+    let Ok((remote, pending)) = opened else {
+        return Ok(Some(ExternalChange::Unreadable));
+    };
+
+    let mut saved = workspace
+        .saved
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")?;
+    let base = saved
+        .take()
+        .map(|baseline| baseline.entries)
+        .unwrap_or_else(|| workspace.credentials.clone());
+    let merged = three_way::merge(&base, &workspace.credentials, &remote);
+    let unsaved = merged.entries != remote;
+    *saved = Some(Baseline {
+        generation,
+        pending,
+        entries: remote,
+    });
+    drop(saved);
+
+    storage.mark_current();
+    workspace.credentials = merged.entries;
+    // Undoing across the reload could bring back what the other copy changed.
+    workspace.history.clear();
+    if unsaved {
+        super::entries::persist(workspace, storage)?;
+    } else {
+        workspace.notify(VaultEvent::EntriesChanged {
+            count: workspace.credentials.len(),
+        });
+    }
+
+    Ok(Some(ExternalChange::Reloaded {
+        count: workspace.credentials.len(),
+        conflicts: merged.conflicts,
+    }))
+}
+
+/// Overwrites a Vault file changed outside Latch with the unlocked copy, after
+/// copying the other file aside. Returns where it was copied to.
+pub fn keep_this_copy(storage: &VaultStorage, workspace: &Workspace) -> Result<String, String> {
+    if !storage.changed_on_disk() {
+        return Err("The vault file hasn't changed outside Latch".to_string());
+    }
+    let stamp = unix_now();
+    let aside = storage
+        .path
+        .with_extension(format!("enc.replaced-{}", stamp));
+    fs::copy(&storage.path, &aside)
+        .map_err(|e| format!("Failed to copy the changed vault aside: {}", e))?;
+    let journal = storage.path.with_extension("journal");
+    if journal.exists() {
+        fs::copy(
+            &journal,
+            journal.with_extension(format!("journal.replaced-{}", stamp)),
+        )
+        .map_err(|e| format!("Failed to copy the changed vault aside: {}", e))?;
+    }
+
+    // Saved whole, since the journal on disk belongs to the other file.
+    *workspace
+        .saved
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")? = None;
+    storage.mark_current();
+    super::entries::persist(workspace, storage)?;
+    Ok(aside.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::{access::access, entries, provision::provision, Entry};
+
+    fn entry(id: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_vault_replaced_on_disk_is_reloaded_before_the_next_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = [5u8; 32];
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        provision(&storage, &mut workspace, &key, AuthMethod::Password, "salt").unwrap();
+        entries::add(&mut workspace, &storage, entry("here")).unwrap();
+        assert_eq!(check(&storage, &mut workspace).unwrap(), None);
+
+        // Another machine's copy, synced over this one.
+        let other = VaultStorage::at(storage.path.clone());
+        let mut elsewhere = Workspace::new();
+        access(&other, &mut elsewhere, &key).unwrap();
+        entries::add(&mut elsewhere, &other, entry("there")).unwrap();
+
+        assert!(storage.changed_on_disk());
+        let refused = entries::add(&mut workspace, &storage, entry("unsaved")).unwrap_err();
+        assert_eq!(refused, CHANGED_ON_DISK);
+
+        // Both copies' Credentials survive, and the merge is saved.
+        let change = check(&storage, &mut workspace).unwrap().unwrap();
+        assert!(matches!(change, ExternalChange::Reloaded { count: 3, .. }));
+        assert!(!storage.changed_on_disk());
+        entries::add(&mut workspace, &storage, entry("after")).unwrap();
+        assert_eq!(storage.read().unwrap().entries(&key).unwrap().len(), 4);
+
+        // Re-keyed elsewhere: nothing opens, so it waits for the user.
+        let mut rekeyed = Workspace::new();
+        other.remove().unwrap();
+        provision(
+            &other,
+            &mut rekeyed,
+            &[6u8; 32],
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        assert_eq!(
+            check(&storage, &mut workspace).unwrap(),
+            Some(ExternalChange::Unreadable)
+        );
+        let aside = keep_this_copy(&storage, &workspace).unwrap();
+        assert!(std::path::Path::new(&aside).exists());
+        assert_eq!(storage.read().unwrap().entries(&key).unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,147 @@
+use super::workspace::Workspace;
+use std::time::{Duration, SystemTime};
+
+/// Longest a guest may keep the Vault; never past the Session itself.
+pub const MAX_GUEST_SECS: u64 = 60 * 60;
+pub const GUEST_DENIED: &str = "Not available in a guest session";
+
+/// Commands a guest may run: finding and using the Credentials they were given,
+/// and locking. Anything else is refused before it reaches its handler.
+const GUEST_COMMANDS: &[&str] = &[
+    "search_entries",
+    "request_secret",
+    "get_entry_actions",
+    "get_full_entry",
+    "copy_secret_to_clipboard",
+    "set_secrets_revealed",
+    "vault_status",
+    "is_app_ready",
+    "get_guest_session",
+    "end_guest_session",
+    "lock_vault",
+];
+
+/// A Session handed to someone else: only Credentials in `folders` stay loaded,
+/// nothing can be saved, and the Vault locks at `expires_at`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuestSession {
+    pub folders: Vec<String>,
+    pub expires_at: SystemTime,
+}
+
+impl GuestSession {
+    pub fn expired(&self) -> bool {
+        SystemTime::now() >= self.expires_at
+    }
+}
+
+pub fn allows(command: &str) -> bool {
+    GUEST_COMMANDS.contains(&command)
+}
+
+/// Turns the owner's Session into a guest one. Credentials outside `folders` are
+/// dropped from memory; only unlocking again brings them back.
+pub fn start(
+    workspace: &mut Workspace,
+    folders: Vec<String>,
+    duration_secs: u64,
+) -> Result<GuestSession, String> {
+    workspace.check_session()?;
+    if workspace.guest.is_some() {
+        return Err(GUEST_DENIED.to_string());
+    }
+    if folders.iter().all(|folder| folder.trim().is_empty()) {
+        return Err("Invalid guest session: choose at least one folder".to_string());
+    }
+    if duration_secs == 0 || duration_secs > MAX_GUEST_SECS {
+        return Err(format!(
+            "Invalid guest session: duration must be between 1 and {} minutes",
+            MAX_GUEST_SECS / 60
+        ));
+    }
+
+    workspace.credentials.retain(|entry| {
+        entry
+            .folder
+            .as_ref()
+            .is_some_and(|folder| folders.contains(folder))
+    });
+    let session = GuestSession {
+        folders,
+        expires_at: SystemTime::now() + Duration::from_secs(duration_secs),
+    };
+    workspace.guest = Some(session.clone());
+    workspace.refresh();
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::Entry;
+
+    fn entry(id: &str, folder: Option<&str>) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            folder: folder.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn unlocked() -> Workspace {
+        let mut workspace = Workspace::new();
+        workspace.start([3u8; 32]);
+        workspace.credentials = vec![
+            entry("netflix", Some("Family")),
+            entry("bank", Some("Finance")),
+            entry("loose", None),
+        ];
+        workspace
+    }
+
+    #[test]
+    fn guest_sees_only_the_chosen_folders() {
+        let mut workspace = unlocked();
+        start(&mut workspace, vec!["Family".to_string()], 600).unwrap();
+
+        let ids: Vec<_> = workspace
+            .credentials
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["netflix"]);
+        assert!(start(&mut workspace, vec!["Finance".to_string()], 600).is_err());
+    }
+
+    #[test]
+    fn expired_guest_session_locks_the_vault() {
+        let mut workspace = unlocked();
+        start(&mut workspace, vec!["Family".to_string()], 600).unwrap();
+        workspace.guest.as_mut().unwrap().expires_at = SystemTime::now();
+
+        assert_eq!(
+            workspace.check_session().unwrap_err(),
+            "Guest session expired"
+        );
+        assert!(!workspace.is_unlocked());
+        assert!(workspace.guest.is_none());
+    }
+
+    #[test]
+    fn guest_session_needs_a_folder_and_a_sane_duration() {
+        let mut workspace = unlocked();
+        assert!(start(&mut workspace, vec![], 600).is_err());
+        assert!(start(&mut workspace, vec!["Family".to_string()], 0).is_err());
+        assert!(start(
+            &mut workspace,
+            vec!["Family".to_string()],
+            MAX_GUEST_SECS + 1
+        )
+        .is_err());
+        assert!(allows("get_full_entry"));
+        assert!(!allows("delete_entry"));
+    }
+}
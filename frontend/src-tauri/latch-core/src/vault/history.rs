@@ -0,0 +1,124 @@
+//! Undo and redo for adding, editing and deleting Credentials. The history lives
+//! in memory for one Session and is dropped on lock. Each operation keeps only
+//! the Credentials it touched, as a delta each way.
+
+use super::backup::EntryDelta;
+use super::Entry;
+use serde::Serialize;
+use specta::Type;
+
+/// Operations kept before the oldest is forgotten.
+const MAX_OPERATIONS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Add,
+    Update,
+    Delete,
+    BulkAdd,
+    BulkDelete,
+}
+
+struct Step {
+    operation: Operation,
+    /// Takes the Credentials back to before the operation.
+    undo: EntryDelta,
+    /// Takes them forward again.
+    redo: EntryDelta,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Records an operation that took the Credentials from `before` to `after`.
+    /// Anything that was undone can no longer be redone.
+    pub fn record(&mut self, operation: Operation, before: &[Entry], after: &[Entry]) {
+        let redo = EntryDelta::between(before, after);
+        if redo.is_empty() {
+            return;
+        }
+        self.undo.push(Step {
+            operation,
+            undo: EntryDelta::between(after, before),
+            redo,
+        });
+        if self.undo.len() > MAX_OPERATIONS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Reverts the last operation on `entries`, returning which one it was.
+    pub fn undo(&mut self, entries: &mut Vec<Entry>) -> Option<Operation> {
+        let step = self.undo.pop()?;
+        *entries = step.undo.apply(std::mem::take(entries));
+        let operation = step.operation;
+        self.redo.push(step);
+        Some(operation)
+    }
+
+    /// Repeats the last undone operation on `entries`.
+    pub fn redo(&mut self, entries: &mut Vec<Entry>) -> Option<Operation> {
+        let step = self.redo.pop()?;
+        *entries = step.redo.apply(std::mem::take(entries));
+        let operation = step.operation;
+        self.undo.push(step);
+        Some(operation)
+    }
+
+    /// Puts an undo or redo back after it couldn't be saved.
+    pub fn revert(&mut self, undone: bool, entries: &mut Vec<Entry>) {
+        if undone {
+            self.redo(entries);
+        } else {
+            self.undo(entries);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, password: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_operations_back_and_forth() {
+        let mut history = History::default();
+        let added = vec![entry("a", "one")];
+        history.record(Operation::Add, &[], &added);
+        let edited = vec![entry("a", "two")];
+        history.record(Operation::Update, &added, &edited);
+
+        let mut entries = edited.clone();
+        assert_eq!(history.undo(&mut entries), Some(Operation::Update));
+        assert_eq!(entries, added);
+        assert_eq!(history.undo(&mut entries), Some(Operation::Add));
+        assert!(entries.is_empty());
+        assert_eq!(history.undo(&mut entries), None);
+
+        assert_eq!(history.redo(&mut entries), Some(Operation::Add));
+        assert_eq!(entries, added);
+
+        history.record(Operation::Delete, &added, &[]);
+        assert_eq!(history.redo(&mut entries), None);
+    }
+}
@@ -0,0 +1,85 @@
+pub const IDLE_WARNING_SECS: u64 = 60;
+pub const IDLE_POLL_SECS: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    Wait(u64),
+    Warn(u64),
+    Lock,
+}
+
+pub struct IdleWatch {
+    warned: bool,
+}
+
+impl Default for IdleWatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdleWatch {
+    pub fn new() -> Self {
+        Self { warned: false }
+    }
+
+    /// Decides what the watcher should do given how long the Workspace has been
+    /// idle. A `timeout_secs` of zero disables idle locking.
+    pub fn step(&mut self, idle_secs: u64, timeout_secs: u64) -> IdleAction {
+        if timeout_secs == 0 {
+            self.warned = false;
+            return IdleAction::Wait(IDLE_POLL_SECS);
+        }
+
+        if idle_secs >= timeout_secs {
+            return IdleAction::Lock;
+        }
+
+        let warn_at = timeout_secs.saturating_sub(IDLE_WARNING_SECS);
+        if idle_secs < warn_at {
+            self.warned = false;
+            return IdleAction::Wait((warn_at - idle_secs).min(IDLE_POLL_SECS));
+        }
+
+        if !self.warned {
+            self.warned = true;
+            return IdleAction::Warn(timeout_secs - idle_secs);
+        }
+
+        IdleAction::Wait((timeout_secs - idle_secs).min(IDLE_POLL_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_timeout_never_locks() {
+        let mut watch = IdleWatch::new();
+        assert_eq!(watch.step(u64::MAX, 0), IdleAction::Wait(IDLE_POLL_SECS));
+    }
+
+    #[test]
+    fn warns_once_before_locking() {
+        let mut watch = IdleWatch::new();
+        assert_eq!(watch.step(250, 300), IdleAction::Warn(50));
+        assert_eq!(watch.step(260, 300), IdleAction::Wait(IDLE_POLL_SECS));
+        assert_eq!(watch.step(295, 300), IdleAction::Wait(5));
+        assert_eq!(watch.step(300, 300), IdleAction::Lock);
+    }
+
+    #[test]
+    fn activity_rearms_warning() {
+        let mut watch = IdleWatch::new();
+        assert_eq!(watch.step(245, 300), IdleAction::Warn(55));
+        assert_eq!(watch.step(0, 300), IdleAction::Wait(IDLE_POLL_SECS));
+        assert_eq!(watch.step(241, 300), IdleAction::Warn(59));
+    }
+
+    #[test]
+    fn waits_until_warning_threshold() {
+        let mut watch = IdleWatch::new();
+        assert_eq!(watch.step(235, 300), IdleAction::Wait(5));
+    }
+}
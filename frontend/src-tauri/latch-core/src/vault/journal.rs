@@ -0,0 +1,171 @@
+//! Saves as encrypted deltas. Rewriting the whole Vault on every edit re-encrypts
+//! every Credential, so a save only appends what changed since the last one; the
+//! Vault is rewritten whole (compacted) once enough deltas pile up, or whenever
+//! something else rewrites it anyway.
+//!
+//! Anything that decrypts a Vault, or a full backup of one, goes through `open`
+//! so the deltas are replayed on top.
+
+use super::backup::EntryDelta;
+use super::{Entry, VaultData, VaultSummary};
+use crate::crypto::aead::{self, EncryptedData};
+use serde::{Deserialize, Serialize};
+
+/// Deltas a Vault carries before the next save rewrites it whole.
+pub const COMPACT_AFTER: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Nonce of the Vault data the entry was appended to. An entry left behind
+    /// after that data was replaced is ignored.
+    #[serde(default)]
+    pub base: String,
+    /// The `EntryDelta`, encrypted with the Session key. None when only the
+    /// summary changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<EncryptedData>,
+    pub summary: VaultSummary,
+}
+
+impl JournalEntry {
+    pub fn sealed(
+        key: &[u8; 32],
+        delta: &EntryDelta,
+        summary: VaultSummary,
+    ) -> Result<Self, String> {
+        let json = serde_json::to_string(delta)
+            .map_err(|e| format!("Failed to serialize vault delta: {}", e))?;
+        Ok(Self {
+            base: String::new(),
+            delta: Some(aead::encrypt(key, &json)?),
+            summary,
+        })
+    }
+
+    pub fn summary(summary: VaultSummary) -> Self {
+        Self {
+            base: String::new(),
+            delta: None,
+            summary,
+        }
+    }
+}
+
+/// The Credentials as last saved: decrypted from `data`, with `journal` replayed.
+/// Replaying the same deltas twice changes nothing.
+pub fn open(
+    key: &[u8; 32],
+    data: &EncryptedData,
+    journal: &[JournalEntry],
+) -> Result<Vec<Entry>, String> {
+    let json = aead::decrypt(key, data)?;
+    let vault_data: VaultData =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse vault data: {}", e))?;
+    let mut entries = vault_data.entries;
+    for delta in journal.iter().filter_map(|j| j.delta.as_ref()) {
+        let json = aead::decrypt(key, delta)?;
+        let delta: EntryDelta = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse vault delta: {}", e))?;
+        entries = delta.apply(entries);
+    }
+    Ok(entries)
+}
+
+/// The summary of the latest save.
+pub fn latest_summary(
+    summary: Option<VaultSummary>,
+    journal: &[JournalEntry],
+) -> Option<VaultSummary> {
+    journal.last().map(|j| j.summary.clone()).or(summary)
+}
+
+/// What was last saved from a Workspace, so the next save only has to write
+/// the difference.
+pub struct Baseline {
+    /// `VaultStorage::generation` when it was saved; a Vault rewritten by
+    /// anything else since is saved whole.
+    pub generation: u64,
+    pub pending: usize,
+    pub entries: Vec<Entry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::storage::VaultStorage;
+    use crate::vault::workspace::Workspace;
+    use crate::vault::{access::access, entries, provision::provision};
+    use std::fs;
+
+    fn entry(id: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn saves_are_journaled_until_compacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let journal_path = dir.path().join("vault.journal");
+        let key = [9u8; 32];
+        let mut workspace = Workspace::new();
+        provision(&storage, &mut workspace, &key, AuthMethod::Password, "ab").unwrap();
+        access(&storage, &mut workspace, &key).unwrap();
+        let written = fs::read(&storage.path).unwrap();
+
+        entries::add(&mut workspace, &storage, entry("a")).unwrap();
+        entries::add(&mut workspace, &storage, entry("b")).unwrap();
+        entries::delete(&mut workspace, &storage, "a").unwrap();
+        assert_eq!(fs::read(&storage.path).unwrap(), written);
+        assert!(!fs::read_to_string(&journal_path)
+            .unwrap()
+            .contains("secret"));
+        assert_eq!(
+            storage.read_header().unwrap().summary.unwrap().entry_count,
+            1
+        );
+
+        let mut reopened = Workspace::new();
+        access(&storage, &mut reopened, &key).unwrap();
+        assert_eq!(reopened.credentials, workspace.credentials);
+        assert_eq!(reopened.credentials[0].id, "b");
+
+        // Three saves are journaled already; the one after COMPACT_AFTER rewrites the Vault.
+        for i in 0..=COMPACT_AFTER - 3 {
+            entries::add(&mut workspace, &storage, entry(&format!("c{}", i))).unwrap();
+        }
+        assert!(!journal_path.exists());
+        assert!(storage.read().unwrap().journal.is_empty());
+        access(&storage, &mut reopened, &key).unwrap();
+        assert_eq!(reopened.credentials, workspace.credentials);
+    }
+
+    #[test]
+    fn a_journal_left_from_an_older_vault_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let key = [9u8; 32];
+        let mut workspace = Workspace::new();
+        provision(&storage, &mut workspace, &key, AuthMethod::Password, "ab").unwrap();
+        access(&storage, &mut workspace, &key).unwrap();
+        entries::add(&mut workspace, &storage, entry("a")).unwrap();
+        let stale = fs::read(dir.path().join("vault.journal")).unwrap();
+
+        // Saved whole by a save that can't journal, e.g. from another Workspace.
+        let mut other = Workspace::new();
+        access(&storage, &mut other, &key).unwrap();
+        other.credentials.clear();
+        storage.write(&storage.read().unwrap()).unwrap();
+        entries::persist(&other, &storage).unwrap();
+        fs::write(dir.path().join("vault.journal"), stale).unwrap();
+
+        access(&storage, &mut workspace, &key).unwrap();
+        assert!(workspace.credentials.is_empty());
+    }
+}
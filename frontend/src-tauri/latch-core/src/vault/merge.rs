@@ -0,0 +1,260 @@
+//! Folds a second Vault file into the open one, e.g. when consolidating the Vaults
+//! of two machines. The other file is only read; nothing in it changes.
+
+use super::verification::Verification;
+use super::{storage::VaultStorage, workspace::Workspace, Entry};
+use crate::auth::oauth::{Identity, OAuthProvider};
+use crate::auth::provider;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::Path;
+
+/// What to do with an incoming Credential that matches one already in the Vault,
+/// by id or by title and username.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Keep the Vault's copy.
+    SkipDuplicates,
+    /// Keep whichever copy was edited last; the Vault's copy wins a tie.
+    KeepNewest,
+    /// Keep both, filing the incoming one under a new id. Identical copies are
+    /// still skipped.
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type)]
+pub struct MergeReport {
+    pub added: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+}
+
+/// Who the Vault file at `path` signs in with, if it is an OAuth Vault. Its
+/// credential has to be validated with that provider before `read_entries`.
+pub fn identity_provider(path: &Path) -> Result<Option<OAuthProvider>, String> {
+    let other = VaultStorage::at(path.to_path_buf());
+    if !other.exists() {
+        return Err("Vault to merge does not exist".to_string());
+    }
+    Ok(other.read_header()?.identity_provider())
+}
+
+/// Decrypts the Vault file at `path` with `credential` and returns its Credentials.
+pub fn read_entries(
+    path: &Path,
+    credential: &str,
+    identity: Option<&Identity>,
+) -> Result<Vec<Entry>, String> {
+    let other = VaultStorage::at(path.to_path_buf());
+    if !other.exists() {
+        return Err("Vault to merge does not exist".to_string());
+    }
+    let vault = other.read()?;
+    let key = provider::vault_key(
+        &vault.kdf,
+        &vault.salt,
+        &provider::credentials_for(&vault.kdf, credential, identity)?,
+    )?;
+    vault
+        .entries(&key)
+        .map_err(|_| "Vault could not be decrypted with these credentials".to_string())
+}
+
+fn same_login(a: &Entry, b: &Entry) -> bool {
+    a.title.eq_ignore_ascii_case(&b.title) && a.username == b.username
+}
+
+fn same_content(a: &Entry, b: &Entry) -> bool {
+    a.password == b.password && a.url == b.url && a.kind == b.kind
+}
+
+pub fn merge(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    incoming: Vec<Entry>,
+    strategy: MergeStrategy,
+) -> Result<MergeReport, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+
+    let mut report = MergeReport::default();
+    for mut entry in incoming {
+        entry.verification = Verification::imported();
+        let existing = workspace
+            .credentials
+            .iter()
+            .position(|e| e.id == entry.id)
+            .or_else(|| {
+                workspace
+                    .credentials
+                    .iter()
+                    .position(|e| same_login(e, &entry))
+            });
+        let Some(idx) = existing else {
+            workspace.credentials.push(entry);
+            report.added += 1;
+            continue;
+        };
+
+        let current = &workspace.credentials[idx];
+        match strategy {
+            MergeStrategy::KeepNewest if entry.updated_at > current.updated_at => {
+                entry.id = current.id.clone();
+                workspace.credentials[idx] = entry;
+                report.replaced += 1;
+            }
+            MergeStrategy::KeepBoth if !same_content(current, &entry) => {
+                entry.id = uuid::Uuid::new_v4().to_string();
+                workspace.credentials.push(entry);
+                report.added += 1;
+            }
+            _ => report.skipped += 1,
+        }
+    }
+
+    if report.added + report.replaced > 0 {
+        super::entries::persist(workspace, storage)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::provision;
+
+    fn entry(id: &str, title: &str, password: &str, updated_at: u64) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: title.to_string(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            updated_at: Some(updated_at),
+            ..Default::default()
+        }
+    }
+
+    fn open_vault(dir: &Path) -> (VaultStorage, Workspace) {
+        let storage = VaultStorage::at(dir.join("vault.enc"));
+        let mut workspace = Workspace::new();
+        provision::provision(
+            &storage,
+            &mut workspace,
+            &[3u8; 32],
+            AuthMethod::Password,
+            "",
+        )
+        .unwrap();
+        workspace.credentials = vec![
+            entry("a", "GitHub", "old", 100),
+            entry("b", "Bank", "same", 100),
+        ];
+        (storage, workspace)
+    }
+
+    fn incoming() -> Vec<Entry> {
+        vec![
+            entry("a", "GitHub", "new", 200),
+            entry("x", "bank", "same", 300),
+            entry("c", "Mail", "pw", 100),
+        ]
+    }
+
+    fn passwords(workspace: &Workspace, title: &str) -> Vec<String> {
+        workspace
+            .credentials
+            .iter()
+            .filter(|e| e.title.eq_ignore_ascii_case(title))
+            .map(|e| e.password.clone())
+            .collect()
+    }
+
+    #[test]
+    fn skip_duplicates_only_adds_new_logins() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = open_vault(dir.path());
+
+        let report = merge(
+            &storage,
+            &mut workspace,
+            incoming(),
+            MergeStrategy::SkipDuplicates,
+        )
+        .unwrap();
+
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                replaced: 0,
+                skipped: 2
+            }
+        );
+        assert_eq!(passwords(&workspace, "GitHub"), ["old"]);
+    }
+
+    #[test]
+    fn keep_newest_replaces_older_copies_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = open_vault(dir.path());
+
+        let report = merge(
+            &storage,
+            &mut workspace,
+            incoming(),
+            MergeStrategy::KeepNewest,
+        )
+        .unwrap();
+
+        assert_eq!(report.replaced, 2);
+        assert_eq!(passwords(&workspace, "GitHub"), ["new"]);
+        assert!(workspace.credentials.iter().any(|e| e.id == "b"));
+        assert!(!workspace.credentials.iter().any(|e| e.id == "x"));
+    }
+
+    #[test]
+    fn keep_both_adds_conflicts_under_new_ids_but_skips_identical_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, mut workspace) = open_vault(dir.path());
+
+        let report = merge(
+            &storage,
+            &mut workspace,
+            incoming(),
+            MergeStrategy::KeepBoth,
+        )
+        .unwrap();
+
+        assert_eq!(report.added, 2);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(passwords(&workspace, "GitHub"), ["old", "new"]);
+        assert_eq!(passwords(&workspace, "Bank"), ["same"]);
+    }
+
+    #[test]
+    fn reads_another_password_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let salt = crate::auth::password::generate_salt();
+        let key = crate::auth::password::derive_key("other", &salt);
+        let other = VaultStorage::at(dir.path().join("other.enc"));
+        let mut workspace = Workspace::new();
+        provision::provision(
+            &other,
+            &mut workspace,
+            &key,
+            AuthMethod::Password,
+            &hex::encode(salt),
+        )
+        .unwrap();
+        workspace.credentials.push(entry("c", "Mail", "pw", 100));
+        crate::vault::entries::persist(&workspace, &other).unwrap();
+
+        let entries = read_entries(&other.path, "other", None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(read_entries(&other.path, "wrong", None)
+            .unwrap_err()
+            .contains("could not be decrypted"));
+    }
+}
@@ -0,0 +1,288 @@
+pub mod access;
+pub mod actions;
+pub mod api_key;
+pub mod archive;
+pub mod backup;
+pub mod bank;
+pub mod changes;
+pub mod collation;
+pub mod duress;
+pub mod entries;
+pub mod ephemeral;
+pub mod events;
+pub mod external;
+pub mod guest;
+pub mod history;
+pub mod idle;
+pub mod journal;
+pub mod merge;
+pub mod portable;
+pub mod provision;
+pub mod query;
+pub mod reference;
+pub mod repair;
+pub mod reuse;
+pub mod rotate;
+pub mod search;
+pub mod server;
+pub mod storage;
+pub mod sync;
+pub mod verification;
+pub mod workspace;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SESSION_TIMEOUT_SECS: u64 = 30 * 60;
+/// How long before the Session times out the frontend is warned.
+pub const SESSION_WARNING_SECS: u64 = 2 * 60;
+/// Extending never keeps a Session open longer than this after unlocking.
+pub const MAX_SESSION_SECS: u64 = 8 * 60 * 60;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct Entry {
+    pub id: String,
+    pub title: String,
+    pub username: String,
+    pub password: String,
+    pub url: Option<String>,
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub verification: verification::Verification,
+    /// Once the password is revealed, refuse to reveal it again for this many seconds
+    /// unless the Vault is unlocked again. Zero means no limit.
+    #[serde(default)]
+    pub reveal_cooldown_secs: u64,
+    /// What auto-type types for this Credential, e.g. `{USERNAME}{TAB}{PASSWORD}{ENTER}`.
+    /// None means the default sequence.
+    #[serde(default)]
+    pub autotype_sequence: Option<String>,
+    /// Folder the Credential is filed under, if any.
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub kind: EntryKind,
+    /// Unix time the Credential was added or last edited; None in older Vaults.
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+    /// Checked against breach data in the background while unlocked, alerting
+    /// through this channel. None leaves it to the manual health check.
+    #[serde(default)]
+    pub breach_watch: Option<crate::vault_health::watch::AlertChannel>,
+    /// How to generate a new password for this site, for one with rules the
+    /// default options break. None uses the defaults.
+    #[serde(default)]
+    pub password_policy: Option<crate::password_generator::PasswordOptions>,
+    #[serde(default)]
+    pub usage: search::Usage,
+    /// Pinned to the tray menu for copying without opening the window.
+    #[serde(default)]
+    pub favorite: bool,
+    /// Set on Credentials imported from someone else's share.
+    #[serde(default)]
+    pub shared_from: Option<Provenance>,
+    /// Remind the user to change the password this often. None never reminds.
+    #[serde(default)]
+    pub expiry: Option<crate::vault_health::expiry::PasswordExpiry>,
+    /// What edits changed, oldest first (see `changes`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<changes::EntryChange>,
+    /// The Credential whose password this one shares. Changing that password
+    /// changes this one's too; changing this one's own password unlinks it.
+    #[serde(default)]
+    pub linked_to: Option<String>,
+    /// Shown instead of the site icon, for services with no favicon.
+    #[serde(default)]
+    pub icon: Option<EntryIcon>,
+}
+
+/// A Credential's own icon: an emoji, or a `#rrggbb` swatch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum EntryIcon {
+    Emoji(String),
+    Color(String),
+}
+
+impl EntryIcon {
+    pub fn is_blank(&self) -> bool {
+        match self {
+            EntryIcon::Emoji(value) | EntryIcon::Color(value) => value.trim().is_empty(),
+        }
+    }
+}
+
+/// Who shared a Credential into this Vault, and when.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct Provenance {
+    /// The sender's name as saved in the sharing contacts.
+    pub sender: String,
+    /// Fingerprint of the sender's sharing key.
+    pub fingerprint: String,
+    pub shared_at: u64,
+}
+
+/// What a Credential holds. Anything other than a login keeps its secret in
+/// `password` and is never filled into websites or typed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Login,
+    /// `password` holds the unencrypted OpenSSH private key.
+    SshKey {
+        public_key: String,
+        /// Ask before every signature made with this key.
+        confirm: bool,
+    },
+    /// Points at a secret kept elsewhere; `password` stays empty.
+    Reference {
+        source: reference::ReferenceSource,
+        location: String,
+    },
+    /// A made-up login registered with `service` only, to catch it leaking.
+    Canary { service: String },
+    /// `password` holds the PIN, if any (see `bank`).
+    BankAccount {
+        /// An IBAN or a domestic account number.
+        account_number: String,
+        /// A routing number, sort code or the like.
+        routing_code: Option<String>,
+        swift: Option<String>,
+    },
+    /// `password` holds the secret; an expiry date is kept as `Entry::expiry`
+    /// (see `api_key`).
+    ApiKey { key_id: String, scopes: Vec<String> },
+    /// Logs in with `username` and `password`, or with the SSH key Credential
+    /// `ssh_key` names (see `server`).
+    Server {
+        hostname: String,
+        /// None means the protocol's usual port.
+        port: Option<u16>,
+        protocol: server::ServerProtocol,
+        ssh_key: Option<String>,
+    },
+}
+
+impl Entry {
+    pub fn is_login(&self) -> bool {
+        self.kind == EntryKind::Login
+    }
+
+    pub fn is_canary(&self) -> bool {
+        matches!(self.kind, EntryKind::Canary { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct EntryPreview {
+    pub id: String,
+    pub title: String,
+    pub username: String,
+    pub url: Option<String>,
+    pub icon_url: Option<String>,
+    pub icon: Option<EntryIcon>,
+    /// The field a search matched on; None outside search results.
+    pub matched: Option<search::MatchedField>,
+    pub favorite: bool,
+}
+
+impl From<Entry> for EntryPreview {
+    fn from(entry: Entry) -> Self {
+        // Bank accounts and API keys have no username; the masked account number
+        // or the key id stands in. A server shows where it logs in.
+        let username = match &entry.kind {
+            EntryKind::BankAccount { account_number, .. } => bank::mask(account_number),
+            EntryKind::ApiKey { key_id, .. } => key_id.clone(),
+            EntryKind::Server { .. } => {
+                server::address(&entry.username, &entry.kind).unwrap_or(entry.username)
+            }
+            _ => entry.username,
+        };
+        EntryPreview {
+            id: entry.id,
+            title: entry.title,
+            username,
+            url: entry.url,
+            icon_url: entry.icon_url,
+            icon: entry.icon,
+            matched: None,
+            favorite: entry.favorite,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedVault {
+    pub version: String,
+    pub kdf: String,
+    pub salt: String,
+    pub data: crate::crypto::aead::EncryptedData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<VaultSummary>,
+    /// Saves since `data` was written; see `journal`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub journal: Vec<journal::JournalEntry>,
+    /// Who the user signs in with, for OAuth Vaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<crate::auth::oauth::OAuthProvider>,
+}
+
+impl EncryptedVault {
+    pub fn entries(&self, key: &[u8; 32]) -> Result<Vec<Entry>, String> {
+        journal::open(key, &self.data, &self.journal)
+    }
+}
+
+/// The unencrypted fields of an `EncryptedVault`, read without loading the ciphertext.
+#[derive(Debug, Deserialize)]
+pub struct VaultHeader {
+    pub version: String,
+    pub kdf: String,
+    pub salt: String,
+    #[serde(default)]
+    pub summary: Option<VaultSummary>,
+    #[serde(default)]
+    pub provider: Option<crate::auth::oauth::OAuthProvider>,
+}
+
+impl VaultHeader {
+    /// Who an OAuth Vault signs in with. Vaults from before the header said so
+    /// are recognised by their salt.
+    pub fn identity_provider(&self) -> Option<crate::auth::oauth::OAuthProvider> {
+        self.provider
+            .or_else(|| crate::auth::oauth::OAuthProvider::for_vault(&self.kdf, &self.salt))
+    }
+}
+
+/// Non-sensitive, unencrypted metadata kept in the Vault header so the lock screen
+/// can show context without decrypting anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct VaultSummary {
+    pub entry_count: usize,
+    pub last_modified: u64,
+    pub last_backup: Option<u64>,
+}
+
+impl VaultSummary {
+    pub fn updated(previous: Option<&VaultSummary>, entry_count: usize) -> Self {
+        VaultSummary {
+            entry_count,
+            last_modified: unix_now(),
+            last_backup: previous.and_then(|s| s.last_backup),
+        }
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultData {
+    pub entries: Vec<Entry>,
+}
@@ -0,0 +1,212 @@
+use super::backup::{BackupFile, BackupKind};
+use super::verification::Verification;
+use super::{storage::VaultStorage, workspace::Workspace, EncryptedVault, Entry};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PORTABLE_EXTENSION: &str = "latch";
+pub const MAX_PORTABLE_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A `.latch` file: either a copy of a Vault or a full backup of one.
+pub enum PortableFile {
+    Vault(EncryptedVault),
+    Backup(BackupFile),
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct PortableSummary {
+    pub path: String,
+    pub kind: &'static str,
+    pub kdf: String,
+    pub created_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    Merge,
+    Replace,
+}
+
+impl PortableFile {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        if path.extension().and_then(|e| e.to_str()) != Some(PORTABLE_EXTENSION) {
+            return Err("Only .latch files can be opened".to_string());
+        }
+        let size = fs::metadata(path)
+            .map_err(|e| format!("Failed to read file: {}", e))?
+            .len();
+        if size > MAX_PORTABLE_FILE_BYTES {
+            return Err("File is too large to be a Latch vault".to_string());
+        }
+
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|_| "File is not a Latch vault or backup".to_string())?;
+
+        if value.get("kind").is_some() {
+            let backup: BackupFile = serde_json::from_value(value)
+                .map_err(|_| "File is not a Latch vault or backup".to_string())?;
+            if backup.kind == BackupKind::Diff {
+                return Err("Differential backups cannot be opened on their own".to_string());
+            }
+            Ok(PortableFile::Backup(backup))
+        } else {
+            let vault: EncryptedVault = serde_json::from_value(value)
+                .map_err(|_| "File is not a Latch vault or backup".to_string())?;
+            Ok(PortableFile::Vault(vault))
+        }
+    }
+
+    pub fn kdf(&self) -> &str {
+        match self {
+            PortableFile::Vault(v) => &v.kdf,
+            PortableFile::Backup(b) => &b.kdf,
+        }
+    }
+
+    pub fn salt(&self) -> &str {
+        match self {
+            PortableFile::Vault(v) => &v.salt,
+            PortableFile::Backup(b) => &b.salt,
+        }
+    }
+
+    pub fn summary(&self, path: &Path) -> PortableSummary {
+        let (kind, created_at) = match self {
+            PortableFile::Vault(_) => ("vault", None),
+            PortableFile::Backup(b) => ("backup", Some(b.created_at)),
+        };
+        PortableSummary {
+            path: path.display().to_string(),
+            kind,
+            kdf: self.kdf().to_string(),
+            created_at,
+        }
+    }
+
+    pub fn decrypt_entries(&self, key: &[u8; 32]) -> Result<Vec<Entry>, String> {
+        let (data, journal) = match self {
+            PortableFile::Vault(v) => (&v.data, &v.journal),
+            PortableFile::Backup(b) => (&b.data, &b.journal),
+        };
+        super::journal::open(key, data, journal)
+            .map_err(|_| "File could not be decrypted with these credentials".to_string())
+    }
+}
+
+/// Returns the `.latch` path the app was launched with, if any (Windows/Linux file association).
+pub fn from_launch_args<I: IntoIterator<Item = String>>(args: I) -> Option<PathBuf> {
+    args.into_iter()
+        .skip(1)
+        .map(PathBuf::from)
+        .find(|p| p.extension().and_then(|e| e.to_str()) == Some(PORTABLE_EXTENSION))
+}
+
+/// Brings entries from an opened file into the Workspace. `Merge` only adds entries
+/// whose id is not already present; `Replace` swaps the whole entry set.
+pub fn import(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    entries: Vec<Entry>,
+    mode: ImportMode,
+) -> Result<usize, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+
+    let entries = entries.into_iter().map(|mut entry| {
+        entry.verification = Verification::imported();
+        entry
+    });
+    let imported = match mode {
+        ImportMode::Replace => {
+            workspace.credentials = entries.collect();
+            workspace.credentials.len()
+        }
+        ImportMode::Merge => {
+            let mut count = 0;
+            for entry in entries {
+                if !workspace.credentials.iter().any(|e| e.id == entry.id) {
+                    workspace.credentials.push(entry);
+                    count += 1;
+                }
+            }
+            count
+        }
+    };
+
+    super::entries::persist(workspace, storage)?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+
+    fn entry(id: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn launch_args_pick_latch_file() {
+        let args = vec![
+            "latch".to_string(),
+            "--flag".to_string(),
+            "/home/me/backup.latch".to_string(),
+        ];
+        assert_eq!(
+            from_launch_args(args),
+            Some(PathBuf::from("/home/me/backup.latch"))
+        );
+        assert_eq!(from_launch_args(vec!["latch".to_string()]), None);
+    }
+
+    #[test]
+    fn rejects_other_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "{}").unwrap();
+        assert!(PortableFile::read(&path).is_err());
+    }
+
+    #[test]
+    fn opens_vault_copy_and_merges_missing_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        let key = [4u8; 32];
+        crate::vault::provision::provision(
+            &storage,
+            &mut workspace,
+            &key,
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        crate::vault::entries::add(&mut workspace, &storage, entry("a")).unwrap();
+
+        let copy = dir.path().join("copy.latch");
+        fs::copy(&storage.path, &copy).unwrap();
+        crate::vault::entries::delete(&mut workspace, &storage, "a").unwrap();
+        crate::vault::entries::add(&mut workspace, &storage, entry("b")).unwrap();
+
+        let file = PortableFile::read(&copy).unwrap();
+        assert_eq!(file.summary(&copy).kind, "vault");
+        let entries = file.decrypt_entries(&key).unwrap();
+        let imported = import(&storage, &mut workspace, entries, ImportMode::Merge).unwrap();
+
+        assert_eq!(imported, 1);
+        assert_eq!(workspace.credentials.len(), 2);
+        assert!(file.decrypt_entries(&[5u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use super::{storage::VaultStorage, workspace::Workspace, EncryptedVault, VaultData, VaultSummary};
+use crate::auth::method::AuthMethod;
+use crate::auth::oauth::OAuthProvider;
+use crate::crypto::aead;
+
+pub fn provision(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    key: &[u8; 32],
+    method: AuthMethod,
+    salt: &str,
+) -> Result<(), String> {
+    if storage.exists() {
+        return Err("Vault already exists".to_string());
+    }
+
+    let vault_data = VaultData {
+        entries: Vec::new(),
+    };
+    let json = serde_json::to_string(&vault_data)
+        .map_err(|e| format!("Failed to serialize vault data: {}", e))?;
+
+    let encrypted = aead::encrypt(key, &json)?;
+
+    let vault = EncryptedVault {
+        version: "2".to_string(),
+        kdf: method.vault_tag().to_string(),
+        salt: salt.to_string(),
+        data: encrypted,
+        summary: Some(VaultSummary::updated(None, 0)),
+        journal: Vec::new(),
+        provider: OAuthProvider::for_vault(method.vault_tag(), salt),
+    };
+
+    storage.write(&vault)?;
+    workspace.start(*key);
+
+    Ok(())
+}
+
+/// Deletes the Vault for good: the file, its backups and any duress Vault. The
+/// Session ends with it.
+pub fn destroy(storage: &VaultStorage, workspace: &mut Workspace) -> Result<(), String> {
+    workspace.check_session()?;
+    super::duress::remove(storage, workspace)?;
+    if let Ok(backups) = super::backup::BackupStore::for_vault(storage) {
+        if backups.dir.exists() {
+            std::fs::remove_dir_all(&backups.dir)
+                .map_err(|e| format!("Failed to remove backups: {}", e))?;
+        }
+    }
+    storage.remove()?;
+    workspace.lock();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destroy_removes_the_vault_and_ends_the_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        provision(
+            &storage,
+            &mut workspace,
+            &[4u8; 32],
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+
+        destroy(&storage, &mut workspace).unwrap();
+
+        assert!(!storage.exists());
+        assert!(!workspace.is_unlocked());
+        assert!(destroy(&storage, &mut workspace).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! The search box's filter syntax: `folder:banking`, `url:github.com`, `is:ssh`
+//! and `age:>1y` narrow the results, and whatever else is typed is matched as
+//! free text. A value with spaces goes in quotes (`folder:"Work stuff"`). A
+//! `key:value` that isn't one of these, or is half typed, is taken as text.
+
+use super::collation::fold;
+use super::{Entry, EntryKind};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// In the folder, or one nested under it (`Work` takes in `Work/Clients`).
+    Folder(String),
+    /// The URL's host is the domain or one of its subdomains.
+    Url(String),
+    Is(Class),
+    /// Last changed more (`Older`) or less (`Newer`) than this many seconds ago.
+    Age(Age, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Login,
+    Ssh,
+    Reference,
+    Canary,
+    Bank,
+    Api,
+    Server,
+    /// Watched for breaches.
+    Watched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Age {
+    Older,
+    Newer,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// The free text, to be fuzzy matched.
+    pub text: String,
+    pub filters: Vec<Filter>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut text = Vec::new();
+        let mut filters = Vec::new();
+        for token in tokens(input) {
+            match filter(&token) {
+                Some(f) => filters.push(f),
+                None => text.push(token),
+            }
+        }
+        Query {
+            text: text.join(" "),
+            filters,
+        }
+    }
+
+    /// Whether `entry` passes every filter, as of `now` (Unix seconds).
+    pub fn admits(&self, entry: &Entry, now: u64) -> bool {
+        self.filters.iter().all(|f| f.admits(entry, now))
+    }
+}
+
+impl Filter {
+    fn admits(&self, entry: &Entry, now: u64) -> bool {
+        match self {
+            Filter::Folder(folder) => entry.folder.as_deref().is_some_and(|f| {
+                let f = fold(f);
+                f == *folder || f.starts_with(&format!("{}/", folder))
+            }),
+            Filter::Url(domain) => entry
+                .url
+                .as_deref()
+                .and_then(crate::icons::domain_of)
+                .is_some_and(|host| host == *domain || host.ends_with(&format!(".{}", domain))),
+            Filter::Is(class) => match class {
+                Class::Login => entry.is_login(),
+                Class::Ssh => matches!(entry.kind, EntryKind::SshKey { .. }),
+                Class::Reference => matches!(entry.kind, EntryKind::Reference { .. }),
+                Class::Canary => entry.is_canary(),
+                Class::Bank => matches!(entry.kind, EntryKind::BankAccount { .. }),
+                Class::Api => matches!(entry.kind, EntryKind::ApiKey { .. }),
+                Class::Server => matches!(entry.kind, EntryKind::Server { .. }),
+                Class::Watched => entry.breach_watch.is_some(),
+            },
+            Filter::Age(age, secs) => entry.updated_at.is_some_and(|at| {
+                let elapsed = now.saturating_sub(at);
+                match age {
+                    Age::Older => elapsed > *secs,
+                    Age::Newer => elapsed < *secs,
+                }
+            }),
+        }
+    }
+}
+
+/// Splits on whitespace, keeping quoted runs together and dropping the quotes.
+fn tokens(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn filter(token: &str) -> Option<Filter> {
+    let (key, value) = token.split_once(':')?;
+    let value = fold(value.trim());
+    if value.is_empty() {
+        return None;
+    }
+    match fold(key).as_str() {
+        "folder" => Some(Filter::Folder(value.trim_end_matches('/').to_string())),
+        "url" => Some(Filter::Url(value.trim_start_matches("www.").to_string())),
+        "is" => Some(Filter::Is(match value.as_str() {
+            "login" => Class::Login,
+            "ssh" => Class::Ssh,
+            "reference" => Class::Reference,
+            "canary" => Class::Canary,
+            "bank" => Class::Bank,
+            "api" => Class::Api,
+            "server" => Class::Server,
+            "watched" => Class::Watched,
+            _ => return None,
+        })),
+        "age" => age(&value),
+        _ => None,
+    }
+}
+
+/// `>1y`, `<30d`, `>6m` or `>2w`; a bare span means older than it.
+fn age(value: &str) -> Option<Filter> {
+    let (age, span) = match value.as_bytes().first()? {
+        b'>' => (Age::Older, &value[1..]),
+        b'<' => (Age::Newer, &value[1..]),
+        _ => (Age::Older, value),
+    };
+    let unit = match span.chars().last()? {
+        'd' => DAY_SECS,
+        'w' => 7 * DAY_SECS,
+        'm' => 30 * DAY_SECS,
+        'y' => 365 * DAY_SECS,
+        _ => return None,
+    };
+    let count: u64 = span[..span.len() - 1].parse().ok()?;
+    Some(Filter::Age(age, count.checked_mul(unit)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(folder: Option<&str>, url: Option<&str>, updated_at: Option<u64>) -> Entry {
+        Entry {
+            id: "1".to_string(),
+            title: "Example".to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            url: url.map(str::to_string),
+            folder: folder.map(str::to_string),
+            updated_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn filters_are_pulled_out_of_the_text() {
+        let query = Query::parse(r#"bank folder:"Work Stuff" url:www.GitHub.com age:>1y is:ssh"#);
+        assert_eq!(query.text, "bank");
+        assert_eq!(
+            query.filters,
+            vec![
+                Filter::Folder("work stuff".to_string()),
+                Filter::Url("github.com".to_string()),
+                Filter::Age(Age::Older, 365 * DAY_SECS),
+                Filter::Is(Class::Ssh),
+            ]
+        );
+
+        // Unknown or unfinished filters are searched for as text.
+        let query = Query::parse("tag:work folder: is:favorite age:>1x 10:30");
+        assert!(query.filters.is_empty());
+        assert_eq!(query.text, "tag:work folder: is:favorite age:>1x 10:30");
+    }
+
+    #[test]
+    fn filters_narrow_the_entries() {
+        let now = 1_000 * DAY_SECS;
+        let old = entry(
+            Some("Work/Clients"),
+            Some("https://gist.github.com/x"),
+            Some(now - 400 * DAY_SECS),
+        );
+        let fresh = entry(
+            Some("Workshop"),
+            Some("notgithub.com"),
+            Some(now - DAY_SECS),
+        );
+
+        let query = Query::parse("folder:work url:github.com age:>1y");
+        assert!(query.admits(&old, now));
+        assert!(!query.admits(&fresh, now));
+
+        assert!(Query::parse("age:<30d").admits(&fresh, now));
+        assert!(!Query::parse("age:<30d").admits(&entry(None, None, None), now));
+        assert!(Query::parse("is:login").admits(&fresh, now));
+        assert!(!Query::parse("is:watched").admits(&fresh, now));
+    }
+}
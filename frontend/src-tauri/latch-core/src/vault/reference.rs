@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+pub const MAX_LOCATION_LEN: usize = 1024;
+
+/// Where a reference Credential's secret actually lives. Latch keeps only the
+/// pointer, never the secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceSource {
+    /// The name of an environment variable.
+    EnvVar,
+    /// A secret ARN, `arn:aws:secretsmanager:<region>:<account>:secret:<name>`.
+    AwsSecretsManager,
+    /// `projects/<project>/secrets/<name>`, optionally with `/versions/<version>`.
+    GcpSecretManager,
+    /// `https://<vault>.vault.azure.net/secrets/<name>`.
+    AzureKeyVault,
+    /// A path in a pass(1) store, such as `work/github`.
+    Pass,
+    Other,
+}
+
+fn is_env_var(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_aws_arn(arn: &str) -> bool {
+    let parts: Vec<&str> = arn.splitn(7, ':').collect();
+    parts.len() == 7
+        && parts[0] == "arn"
+        && parts[1].starts_with("aws")
+        && parts[2] == "secretsmanager"
+        && parts[5] == "secret"
+        && parts.iter().all(|part| !part.is_empty())
+}
+
+fn is_gcp_secret(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('/').collect();
+    let named = |i: usize, label: &str| parts[i] == label && !parts[i + 1].is_empty();
+    match parts.len() {
+        4 => named(0, "projects") && named(2, "secrets"),
+        6 => named(0, "projects") && named(2, "secrets") && named(4, "versions"),
+        _ => false,
+    }
+}
+
+fn is_azure_secret(location: &str) -> bool {
+    url::Url::parse(location).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| host.ends_with(".vault.azure.net"))
+            && url
+                .path()
+                .strip_prefix("/secrets/")
+                .is_some_and(|name| !name.is_empty())
+    })
+}
+
+fn is_pass_path(path: &str) -> bool {
+    !path.starts_with('/')
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+pub fn validate(source: ReferenceSource, location: &str) -> Result<(), String> {
+    if location.trim().is_empty() {
+        return Err("Invalid reference location: it cannot be empty".to_string());
+    }
+    if location.len() > MAX_LOCATION_LEN {
+        return Err(format!(
+            "Invalid reference location: longer than {} characters",
+            MAX_LOCATION_LEN
+        ));
+    }
+    let valid = match source {
+        ReferenceSource::EnvVar => is_env_var(location),
+        ReferenceSource::AwsSecretsManager => is_aws_arn(location),
+        ReferenceSource::GcpSecretManager => is_gcp_secret(location),
+        ReferenceSource::AzureKeyVault => is_azure_secret(location),
+        ReferenceSource::Pass => is_pass_path(location),
+        ReferenceSource::Other => true,
+    };
+    if !valid {
+        return Err(format!(
+            "Invalid reference location for {:?}: {}",
+            source, location
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_are_checked_against_their_source() {
+        use ReferenceSource::*;
+        let valid = [
+            (EnvVar, "GITHUB_TOKEN"),
+            (
+                AwsSecretsManager,
+                "arn:aws:secretsmanager:eu-west-1:123456789012:secret:prod/db-AbCdEf",
+            ),
+            (GcpSecretManager, "projects/acme/secrets/api-key"),
+            (GcpSecretManager, "projects/acme/secrets/api-key/versions/3"),
+            (AzureKeyVault, "https://acme.vault.azure.net/secrets/db"),
+            (Pass, "work/github"),
+            (Other, "1Password: Shared/Deploy"),
+        ];
+        for (source, location) in valid {
+            assert!(validate(source, location).is_ok(), "{}", location);
+        }
+
+        let invalid = [
+            (EnvVar, "1TOKEN"),
+            (EnvVar, "MY-TOKEN"),
+            (AwsSecretsManager, "arn:aws:s3:::bucket"),
+            (GcpSecretManager, "projects/acme/keys/api-key"),
+            (AzureKeyVault, "http://acme.vault.azure.net/secrets/db"),
+            (AzureKeyVault, "https://evil.example/secrets/db"),
+            (Pass, "../outside"),
+            (Pass, "/etc/passwd"),
+            (Other, "  "),
+        ];
+        for (source, location) in invalid {
+            assert!(validate(source, location).is_err(), "{}", location);
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Repairs a Vault file that no longer parses, e.g. after a crash or a full
+//! disk cut it short. Works without the key, so before the Vault can be unlocked.
+//! In order, it tries:
+//! - the `.enc.tmp` copy left by a save that was interrupted before its rename;
+//! - what can be read of the damaged file, as long as the encrypted Credentials
+//!   are whole (usually only the fields after them are lost);
+//! - the newest full backup.
+//!
+//! The damaged file and its journal are moved aside rather than overwritten.
+//! Nothing here can check the ciphertext itself; the next unlock does.
+
+use super::backup::{BackupKind, BackupStore};
+use super::journal::JournalEntry;
+use super::storage::{self, VaultStorage, MAX_VAULT_BYTES};
+use super::{unix_now, EncryptedVault, VaultSummary};
+use crate::auth::oauth::OAuthProvider;
+use crate::crypto::aead::EncryptedData;
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Where the repaired Vault came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RepairSource {
+    /// The copy an interrupted save left behind.
+    UnfinishedSave,
+    /// What could be read of the damaged file.
+    DamagedVault,
+    Backup {
+        id: String,
+        created_at: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct RepairReport {
+    pub source: RepairSource,
+    /// What the repaired Vault holds, one line each.
+    pub salvaged: Vec<String>,
+    /// What could not be recovered, one line each.
+    pub lost: Vec<String>,
+    /// Where the damaged file was moved to.
+    pub damaged_copy: String,
+}
+
+struct Recovered {
+    vault: EncryptedVault,
+    source: RepairSource,
+    salvaged: Vec<String>,
+    lost: Vec<String>,
+}
+
+/// Replaces a Vault that fails to parse with the best copy that can be found.
+pub fn repair(storage: &VaultStorage) -> Result<RepairReport, String> {
+    if storage.is_ephemeral() {
+        return Err("An ephemeral vault has nothing on disk to repair".to_string());
+    }
+    if !storage.path.exists() {
+        return Err("There is no vault to repair".to_string());
+    }
+    if storage.read().is_ok() {
+        return Err("The vault isn't damaged; there is nothing to repair".to_string());
+    }
+
+    let tmp_path = storage.path.with_extension("enc.tmp");
+    let mut recovered = match read_whole(&tmp_path) {
+        Some(vault) => Recovered {
+            vault,
+            source: RepairSource::UnfinishedSave,
+            salvaged: vec!["Every Credential, from the interrupted save".to_string()],
+            lost: Vec::new(),
+        },
+        None => match salvage(&read_capped(&storage.path)?) {
+            Ok(recovered) => recovered,
+            Err(damage) => from_backup(storage).map_err(|e| format!("{}, and {}", damage, e))?,
+        },
+    };
+    if !matches!(recovered.source, RepairSource::Backup { .. }) {
+        // Saves appended since the data was written still apply to it.
+        let appended = storage::appended(&storage.path, &recovered.vault.data.nonce);
+        recovered.vault.journal.extend(appended);
+        let saves = recovered
+            .vault
+            .journal
+            .iter()
+            .filter(|e| e.delta.is_some())
+            .count();
+        if saves > 0 {
+            recovered
+                .salvaged
+                .push(format!("{} later saves from the vault's journal", saves));
+        }
+    }
+
+    let stamp = unix_now();
+    let damaged_copy = storage
+        .path
+        .with_extension(format!("enc.damaged-{}", stamp));
+    fs::rename(&storage.path, &damaged_copy)
+        .map_err(|e| format!("Failed to move the damaged vault aside: {}", e))?;
+    let journal = storage.path.with_extension("journal");
+    if journal.exists() {
+        fs::rename(
+            &journal,
+            journal.with_extension(format!("journal.damaged-{}", stamp)),
+        )
+        .map_err(|e| format!("Failed to move the damaged vault aside: {}", e))?;
+    }
+    storage.write(&recovered.vault)?;
+    if tmp_path.exists() {
+        if let Err(e) = fs::remove_file(&tmp_path) {
+            eprintln!("Failed to remove unfinished save: {}", e);
+        }
+    }
+
+    Ok(RepairReport {
+        source: recovered.source,
+        salvaged: recovered.salvaged,
+        lost: recovered.lost,
+        damaged_copy: damaged_copy.to_string_lossy().into_owned(),
+    })
+}
+
+fn read_capped(path: &Path) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(MAX_VAULT_BYTES).read_to_end(&mut bytes))
+        .map_err(|e| format!("Failed to read vault: {}", e))?;
+    Ok(bytes)
+}
+
+fn read_whole(path: &Path) -> Option<EncryptedVault> {
+    let vault: EncryptedVault = serde_json::from_slice(&read_capped(path).ok()?).ok()?;
+    vault.data.check_lengths().ok()?;
+    Some(vault)
+}
+
+/// Rebuilds what it can of a damaged Vault file: everything up to the first
+/// byte that doesn't parse, as long as that includes the encrypted Credentials.
+fn salvage(bytes: &[u8]) -> Result<Recovered, String> {
+    let text = String::from_utf8_lossy(bytes);
+    let (value, readable) = match serde_json::from_str::<Value>(&text) {
+        Ok(value) => (value, text.len()),
+        Err(e) => {
+            let readable = error_offset(&text, e.line(), e.column());
+            let closed = close_truncated(&text[..readable])
+                .ok_or("Nothing in the vault file could be read")?;
+            let value = serde_json::from_str(&closed)
+                .map_err(|_| "Nothing in the vault file could be read".to_string())?;
+            (value, readable)
+        }
+    };
+    let field = |name: &str| value.get(name).cloned();
+    let text_field = |name: &str| field(name).and_then(|v| v.as_str().map(str::to_string));
+
+    let damaged = "The vault's encrypted Credentials are damaged";
+    let data: EncryptedData = field("data")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or(damaged)?;
+    data.check_lengths().map_err(|_| damaged.to_string())?;
+    let (Some(kdf), Some(salt)) = (text_field("kdf"), text_field("salt")) else {
+        return Err("The vault's sign-in details are damaged".to_string());
+    };
+
+    let salvaged = vec!["Every Credential, with the vault's sign-in details".to_string()];
+    let mut lost = Vec::new();
+    if readable < text.len() {
+        lost.push(format!(
+            "{} unreadable bytes at the end of the file",
+            text.len() - readable
+        ));
+    }
+    let summary: Option<VaultSummary> =
+        field("summary").and_then(|v| serde_json::from_value(v).ok());
+    if summary.is_none() {
+        lost.push("The summary shown on the lock screen, until the next save".to_string());
+    }
+    let journal: Vec<JournalEntry> = match field("journal") {
+        Some(Value::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|e| serde_json::from_value(e).ok())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(Recovered {
+        vault: EncryptedVault {
+            version: text_field("version").unwrap_or_else(|| "2".to_string()),
+            provider: field("provider")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .or_else(|| OAuthProvider::for_vault(&kdf, &salt)),
+            kdf,
+            salt,
+            data,
+            summary,
+            journal,
+        },
+        source: RepairSource::DamagedVault,
+        salvaged,
+        lost,
+    })
+}
+
+fn from_backup(storage: &VaultStorage) -> Result<Recovered, String> {
+    let backups = BackupStore::for_vault(storage)?;
+    let (info, vault) = backups
+        .newest_full()?
+        .ok_or("there is no full backup to restore")?;
+    let mut lost = vec!["Changes saved after the backup was taken".to_string()];
+    if backups
+        .list()?
+        .iter()
+        .any(|b| b.kind == BackupKind::Diff && b.id > info.id)
+    {
+        lost.push(
+            "Newer differential backups, until restored after unlocking (see Backups)".to_string(),
+        );
+    }
+    Ok(Recovered {
+        vault,
+        source: RepairSource::Backup {
+            id: info.id,
+            created_at: info.created_at,
+        },
+        salvaged: vec!["Every Credential as of the newest full backup".to_string()],
+        lost,
+    })
+}
+
+/// The byte offset of serde_json's 1-based `line` and `column`.
+fn error_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let mut offset = (line_start + column.saturating_sub(1)).min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Cuts JSON that stops early back to its last complete value and closes every
+/// object and array still open. None if it never gets that far.
+fn close_truncated(json: &str) -> Option<String> {
+    let mut open = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    let mut cut: Option<(usize, Vec<char>)> = None;
+    for (i, c) in json.char_indices() {
+        if in_string {
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => open.push('}'),
+            '[' => open.push(']'),
+            '}' | ']' => {
+                if open.pop() != Some(c) {
+                    break;
+                }
+                cut = Some((i + 1, open.clone()));
+            }
+            ',' => cut = Some((i, open.clone())),
+            _ => {}
+        }
+    }
+    let (end, open) = cut?;
+    let mut closed = json[..end].to_string();
+    closed.extend(open.iter().rev());
+    Some(closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::workspace::Workspace;
+
+    fn provisioned(dir: &Path) -> VaultStorage {
+        let storage = VaultStorage::at(dir.join("vault.enc"));
+        crate::vault::provision::provision(
+            &storage,
+            &mut Workspace::new(),
+            &[7u8; 32],
+            AuthMethod::Password,
+            &"ab".repeat(32),
+        )
+        .unwrap();
+        storage
+    }
+
+    #[test]
+    fn truncated_json_is_closed_at_its_last_complete_value() {
+        assert_eq!(
+            close_truncated(r#"{"a":"x,y","b":[1,2"#).unwrap(),
+            r#"{"a":"x,y","b":[1]}"#
+        );
+        assert_eq!(
+            close_truncated(r#"{"a":{"b":1},"c":"#).unwrap(),
+            r#"{"a":{"b":1}}"#
+        );
+        assert!(close_truncated(r#"{"a":"#).is_none());
+    }
+
+    #[test]
+    fn a_vault_cut_off_after_its_credentials_is_salvaged() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = provisioned(dir.path());
+        let whole = storage.read().unwrap();
+        let json = fs::read_to_string(&storage.path).unwrap();
+        let cut = json.find("\"summary\"").unwrap() + 20;
+        fs::write(&storage.path, &json[..cut]).unwrap();
+        assert!(storage.read().is_err());
+
+        let report = repair(&storage).unwrap();
+        assert_eq!(report.source, RepairSource::DamagedVault);
+        assert_eq!(report.lost.len(), 2);
+        assert!(Path::new(&report.damaged_copy).exists());
+        let repaired = storage.read().unwrap();
+        assert_eq!(repaired.data.ciphertext, whole.data.ciphertext);
+        assert_eq!(repaired.entries(&[7u8; 32]).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn damaged_credentials_fall_back_to_the_newest_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = provisioned(dir.path());
+        assert!(repair(&storage).unwrap_err().contains("isn't damaged"));
+        crate::vault::backup::safety_backup(&storage).unwrap();
+
+        let json = fs::read_to_string(&storage.path).unwrap();
+        let cut = json.find("\"ciphertext\"").unwrap() + 20;
+        fs::write(&storage.path, &json[..cut]).unwrap();
+
+        let report = repair(&storage).unwrap();
+        assert!(matches!(report.source, RepairSource::Backup { .. }));
+        assert!(storage.read().unwrap().entries(&[7u8; 32]).is_ok());
+    }
+}
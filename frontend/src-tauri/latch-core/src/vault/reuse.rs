@@ -0,0 +1,76 @@
+//! A quick reuse check for the Credential just saved, so the save can warn about a
+//! password other Credentials already use without running a vault health scan.
+
+use super::workspace::Workspace;
+use super::Entry;
+use serde::Serialize;
+use specta::Type;
+
+/// What a save returns: the Credential's id, and how many others already use its
+/// password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+pub struct SavedEntry {
+    pub id: String,
+    /// Other Credentials with the same password; 0 when it is only used here.
+    pub reused_by: usize,
+}
+
+/// Whether an entry's password counts towards reuse. Linked Credentials share a
+/// password on purpose, as in the health report.
+fn counted(entry: &Entry) -> bool {
+    entry.linked_to.is_none() && !entry.password.is_empty()
+}
+
+/// How many Credentials other than `id` use its password.
+pub fn reused_by(workspace: &Workspace, id: &str) -> usize {
+    let Some(entry) = workspace.credentials.iter().find(|e| e.id == id) else {
+        return 0;
+    };
+    if !counted(entry) {
+        return 0;
+    }
+    workspace
+        .credentials
+        .iter()
+        .filter(|e| e.id != id && counted(e) && e.password == entry.password)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, password: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "me".to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn saves_count_other_credentials_sharing_the_password() {
+        let mut workspace = Workspace::new();
+        workspace.credentials = vec![
+            entry("a", "hunter2"),
+            entry("b", "hunter2"),
+            entry("c", "hunter2"),
+            entry("d", "correct horse"),
+        ];
+        assert_eq!(reused_by(&workspace, "a"), 2);
+        assert_eq!(reused_by(&workspace, "d"), 0);
+        assert_eq!(reused_by(&workspace, "gone"), 0);
+
+        workspace.credentials[1].password = "correct horse".to_string();
+        assert_eq!(reused_by(&workspace, "a"), 1);
+        assert_eq!(reused_by(&workspace, "d"), 1);
+
+        workspace.credentials[2].linked_to = Some("a".to_string());
+        assert_eq!(reused_by(&workspace, "a"), 0);
+
+        workspace.credentials.retain(|e| e.id != "b");
+        assert_eq!(reused_by(&workspace, "d"), 0);
+    }
+}
@@ -1,5 +1,6 @@
-use super::{storage::VaultStorage, workspace::Workspace, EncryptedVault, VaultData};
+use super::{storage::VaultStorage, workspace::Workspace, EncryptedVault, VaultData, VaultSummary};
 use crate::auth::method::AuthMethod;
+use crate::auth::oauth::OAuthProvider;
 use crate::crypto::aead;
 
 pub fn rotate(
@@ -10,6 +11,12 @@ pub fn rotate(
     new_salt: &str,
 ) -> Result<(), String> {
     workspace.check_session()?;
+    let old_key = workspace
+        .session_key
+        .as_deref()
+        .copied()
+        .ok_or("Vault is locked")?;
+    let archived = super::archive::take(storage, &old_key)?;
 
     let vault_data = VaultData {
         entries: workspace.credentials.clone(),
@@ -17,16 +24,24 @@ pub fn rotate(
     let json = serde_json::to_string(&vault_data)
         .map_err(|e| format!("Failed to serialize vault data: {}", e))?;
     let encrypted = aead::encrypt(new_key, &json)?;
+    let previous = storage.read_header().ok().and_then(|v| v.summary);
 
     let vault = EncryptedVault {
         version: "2".to_string(),
         kdf: new_method.vault_tag().to_string(),
         salt: new_salt.to_string(),
         data: encrypted,
+        summary: Some(VaultSummary::updated(
+            previous.as_ref(),
+            workspace.credentials.len(),
+        )),
+        journal: Vec::new(),
+        provider: OAuthProvider::for_vault(new_method.vault_tag(), new_salt),
     };
 
     storage.write(&vault)?;
     workspace.start(*new_key);
+    super::archive::rekey(storage, new_key, archived)?;
 
     Ok(())
 }
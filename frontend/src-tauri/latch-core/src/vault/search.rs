@@ -0,0 +1,637 @@
+use super::collation::{fold, Collation};
+use super::query::Query;
+use super::{unix_now, workspace::Workspace, Entry, EntryPreview};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+/// Highest `MatchOptions::min_score` accepted; above it even exact matches of
+/// short queries fall short.
+pub const MAX_MIN_SCORE: u32 = 200;
+
+/// Public suffixes of two labels common enough to matter; under these the
+/// registrable domain keeps three labels (`bbc.co.uk`, not `co.uk`).
+const TWO_LABEL_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "me.uk", "com.au", "net.au", "org.au", "co.nz", "co.jp",
+    "ne.jp", "or.jp", "co.in", "co.kr", "co.za", "com.br", "com.cn", "com.mx", "com.sg", "com.tr",
+    "com.tw", "com.hk", "com.ar",
+];
+
+/// Frecency weights by days since the last use: uses this week count for the most.
+const RECENCY_WEIGHTS: &[(u64, i64)] = &[(4, 100), (14, 70), (31, 50), (90, 30)];
+const STALE_WEIGHT: i64 = 10;
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// How often and how lately a Credential was used, so the ones in daily use rank
+/// first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Usage {
+    pub count: u32,
+    pub last_used: Option<u64>,
+}
+
+impl Usage {
+    pub fn record(&mut self, now: u64) {
+        self.count = self.count.saturating_add(1);
+        self.last_used = Some(now);
+    }
+
+    /// Two machines' records of the same Credential, combined.
+    pub fn merged(self, other: Usage) -> Usage {
+        Usage {
+            count: self.count.max(other.count),
+            last_used: self.last_used.max(other.last_used),
+        }
+    }
+
+    /// Added to a match's fuzzy score: grows with the number of uses and fades
+    /// as the last one gets older.
+    fn frecency(&self, now: u64) -> i64 {
+        let Some(last_used) = self.last_used else {
+            return 0;
+        };
+        let days = now.saturating_sub(last_used) / DAY_SECS;
+        let weight = RECENCY_WEIGHTS
+            .iter()
+            .find(|(within, _)| days < *within)
+            .map_or(STALE_WEIGHT, |(_, weight)| *weight);
+        (weight as f64 * f64::from(self.count).ln_1p() / 2.0) as i64
+    }
+}
+
+/// How search matches queries, as set in Settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct MatchOptions {
+    /// Fuzzy scores below this don't count as a match. Lower lets short or
+    /// scattered queries match more.
+    pub min_score: u32,
+    /// Match case exactly instead of folding it (and accents) away.
+    pub case_sensitive: bool,
+    pub empty_query: EmptyQuery,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            min_score: 50,
+            case_sensitive: false,
+            empty_query: EmptyQuery::Everything,
+        }
+    }
+}
+
+/// What a search with nothing typed lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyQuery {
+    /// Every Credential, most used first.
+    #[default]
+    Everything,
+    /// Only Credentials used before, most used first.
+    Used,
+    Nothing,
+}
+
+/// Which field a search result matched on, for the UI to point out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedField {
+    Title,
+    Username,
+    Url,
+}
+
+/// The domain a Credential's URL was registered under, e.g. `github.com` for
+/// `https://gist.github.com/x`. IP addresses are kept whole.
+pub fn registrable_domain(url: &str) -> Option<String> {
+    let host = crate::icons::domain_of(url)?;
+    if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+        return Some(host);
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let n = labels.len();
+    let keep = if n >= 3 && TWO_LABEL_SUFFIXES.contains(&labels[n - 2..].join(".").as_str()) {
+        3
+    } else {
+        2
+    };
+    Some(labels[n.saturating_sub(keep)..].join("."))
+}
+
+/// Credentials matching `query`, best match first. Filters in the query (see
+/// `query`) narrow the results; the rest is matched against titles, usernames
+/// and the registrable domain of URLs, folded, with a boost for Credentials in
+/// frequent and recent use. No text lists everything that passes the filters,
+/// most used first, or less as `options.empty_query` says; `collation` order
+/// breaks ties.
+pub fn search(
+    workspace: &mut Workspace,
+    query: &str,
+    collation: &Collation,
+    options: &MatchOptions,
+) -> Result<Vec<EntryPreview>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+
+    let query = Query::parse(query);
+    if query == Query::default() && options.empty_query == EmptyQuery::Nothing {
+        return Ok(Vec::new());
+    }
+    let used_only = query == Query::default() && options.empty_query == EmptyQuery::Used;
+    let now = unix_now();
+    workspace.search_index.sync(&workspace.credentials);
+    Ok(workspace
+        .search_index
+        .matches(&workspace.credentials, &query.text, collation, options)
+        .into_iter()
+        .filter(|(idx, _)| query.admits(&workspace.credentials[*idx], now))
+        .filter(|(idx, _)| !used_only || workspace.credentials[*idx].usage.count > 0)
+        .map(|(idx, matched)| EntryPreview {
+            matched,
+            ..workspace.credentials[idx].clone().into()
+        })
+        .collect())
+}
+
+/// Indices into `entries` of those matching `query`, ordered as `search` orders
+/// them, with an empty query listing everything. Folds and scores every entry;
+/// `SearchIndex` avoids that for the Vault.
+pub fn rank(
+    entries: &[Entry],
+    query: &str,
+    collation: &Collation,
+    options: &MatchOptions,
+) -> Vec<usize> {
+    let matcher = matcher(options);
+    let scored = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| {
+            if fold(query).is_empty() {
+                return Some((0, idx, None));
+            }
+            score(&matcher, &Doc::new(entry), query, options)
+                .map(|(best, field)| (best, idx, Some(field)))
+        })
+        .collect();
+    order(entries, scored, collation)
+        .into_iter()
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn matcher(options: &MatchOptions) -> SkimMatcherV2 {
+    if options.case_sensitive {
+        SkimMatcherV2::default().respect_case()
+    } else {
+        SkimMatcherV2::default().ignore_case()
+    }
+}
+
+/// The best field's score for `query` as typed. On a tie the title wins, then
+/// the username.
+fn score(
+    matcher: &SkimMatcherV2,
+    doc: &Doc,
+    query: &str,
+    options: &MatchOptions,
+) -> Option<(i64, MatchedField)> {
+    let (query, title, username) = if options.case_sensitive {
+        (query.trim().to_string(), &doc.title, &doc.username)
+    } else {
+        (fold(query), &doc.folded_title, &doc.folded_username)
+    };
+    [
+        (title, MatchedField::Title),
+        (username, MatchedField::Username),
+        (&doc.folded_domain, MatchedField::Url),
+    ]
+    .into_iter()
+    .filter_map(|(text, field)| {
+        matcher
+            .fuzzy_match(text, &query)
+            .map(|score| (score, field))
+    })
+    .fold(
+        None,
+        |best: Option<(i64, MatchedField)>, (score, field)| match best {
+            Some((top, _)) if top >= score => best,
+            _ => Some((score, field)),
+        },
+    )
+    .filter(|(best, _)| *best >= i64::from(options.min_score))
+}
+
+type Scored = (i64, usize, Option<MatchedField>);
+
+fn order(
+    entries: &[Entry],
+    mut scored: Vec<Scored>,
+    collation: &Collation,
+) -> Vec<(usize, Option<MatchedField>)> {
+    let now = unix_now();
+    for (score, idx, _) in &mut scored {
+        *score += entries[*idx].usage.frecency(now);
+    }
+    scored.sort_by(|(a_score, a, _), (b_score, b, _)| {
+        let (a, b) = (&entries[*a], &entries[*b]);
+        Reverse(a_score)
+            .cmp(&Reverse(b_score))
+            .then_with(|| collation.compare(&a.title, &b.title))
+            .then_with(|| collation.compare(&a.username, &b.username))
+    });
+    scored
+        .into_iter()
+        .map(|(_, idx, field)| (idx, field))
+        .collect()
+}
+
+type Trigram = [char; 3];
+
+fn trigrams(text: &str) -> impl Iterator<Item = Trigram> + '_ {
+    let chars: Vec<char> = text.chars().collect();
+    (0..chars.len().saturating_sub(2)).map(move |i| [chars[i], chars[i + 1], chars[i + 2]])
+}
+
+/// One bit per character (modulo 64) that occurs in `text`. A fuzzy match needs
+/// every character of the query, so an entry missing one of its bits can't match.
+fn char_mask(text: &str) -> u64 {
+    text.chars().fold(0, |mask, c| mask | 1 << (c as u32 % 64))
+}
+
+/// A Credential's searchable fields as indexed, and their folded forms.
+struct Doc {
+    title: String,
+    username: String,
+    url: Option<String>,
+    folded_title: String,
+    folded_username: String,
+    folded_domain: String,
+    mask: u64,
+}
+
+impl Doc {
+    fn new(entry: &Entry) -> Self {
+        let folded_title = fold(&entry.title);
+        let folded_username = fold(&entry.username);
+        let folded_domain = entry
+            .url
+            .as_deref()
+            .and_then(registrable_domain)
+            .map(|domain| fold(&domain))
+            .unwrap_or_default();
+        let mask =
+            char_mask(&folded_title) | char_mask(&folded_username) | char_mask(&folded_domain);
+        Self {
+            title: entry.title.clone(),
+            username: entry.username.clone(),
+            url: entry.url.clone(),
+            folded_title,
+            folded_username,
+            folded_domain,
+            mask,
+        }
+    }
+
+    fn is_current(&self, entry: &Entry) -> bool {
+        self.title == entry.title && self.username == entry.username && self.url == entry.url
+    }
+
+    fn trigrams(&self) -> HashSet<Trigram> {
+        trigrams(&self.folded_title)
+            .chain(trigrams(&self.folded_username))
+            .chain(trigrams(&self.folded_domain))
+            .collect()
+    }
+}
+
+/// Folded titles, usernames and domains of the Workspace's Credentials, with a
+/// trigram map over them. Queries of three or more characters only score the
+/// Credentials holding every trigram of the query, falling back to the fuzzy
+/// matches among the rest when none contain it outright. Kept in step by `sync`,
+/// which only re-folds Credentials whose fields changed.
+#[derive(Default)]
+pub struct SearchIndex {
+    docs: HashMap<String, Doc>,
+    postings: HashMap<Trigram, HashSet<String>>,
+}
+
+impl SearchIndex {
+    /// Brings the index in line with `entries`: indexes new and edited
+    /// Credentials and drops deleted ones.
+    pub fn sync(&mut self, entries: &[Entry]) {
+        let mut present = HashSet::with_capacity(entries.len());
+        for entry in entries {
+            present.insert(entry.id.as_str());
+            let current = self
+                .docs
+                .get(&entry.id)
+                .is_some_and(|doc| doc.is_current(entry));
+            if !current {
+                self.insert(entry);
+            }
+        }
+        let removed: Vec<String> = self
+            .docs
+            .keys()
+            .filter(|id| !present.contains(id.as_str()))
+            .cloned()
+            .collect();
+        for id in removed {
+            self.remove(&id);
+        }
+    }
+
+    fn insert(&mut self, entry: &Entry) {
+        self.remove(&entry.id);
+        let doc = Doc::new(entry);
+        for trigram in doc.trigrams() {
+            self.postings
+                .entry(trigram)
+                .or_default()
+                .insert(entry.id.clone());
+        }
+        self.docs.insert(entry.id.clone(), doc);
+    }
+
+    fn remove(&mut self, id: &str) {
+        let Some(doc) = self.docs.remove(id) else {
+            return;
+        };
+        for trigram in doc.trigrams() {
+            if let Some(ids) = self.postings.get_mut(&trigram) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.docs.clear();
+        self.postings.clear();
+    }
+
+    /// Ids of the Credentials holding every trigram of `query`; None when it is
+    /// too short to have any.
+    fn candidates(&self, query: &str) -> Option<HashSet<&str>> {
+        let mut wanted = trigrams(query).collect::<HashSet<_>>().into_iter();
+        let first = wanted.next()?;
+        let mut ids: HashSet<&str> = self
+            .postings
+            .get(&first)
+            .map(|ids| ids.iter().map(String::as_str).collect())
+            .unwrap_or_default();
+        for trigram in wanted {
+            let Some(more) = self.postings.get(&trigram) else {
+                return Some(HashSet::new());
+            };
+            ids.retain(|id| more.contains(*id));
+        }
+        Some(ids)
+    }
+
+    /// As `rank`, for `entries` this index was last synced with, along with the
+    /// field each matched on.
+    pub fn matches(
+        &self,
+        entries: &[Entry],
+        query: &str,
+        collation: &Collation,
+        options: &MatchOptions,
+    ) -> Vec<(usize, Option<MatchedField>)> {
+        let matcher = matcher(options);
+        let typed = query;
+        let query = fold(query);
+        if query.is_empty() {
+            let all = (0..entries.len()).map(|idx| (0, idx, None)).collect();
+            return order(entries, all, collation);
+        }
+
+        let scored = |keep: &dyn Fn(&Entry, &Doc) -> bool| -> Vec<Scored> {
+            entries
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, entry)| self.docs.get(&entry.id).map(|doc| (idx, entry, doc)))
+                .filter(|(_, entry, doc)| keep(entry, doc))
+                .filter_map(|(idx, _, doc)| {
+                    score(&matcher, doc, typed, options)
+                        .map(|(best, field)| (best, idx, Some(field)))
+                })
+                .collect()
+        };
+
+        if let Some(candidates) = self.candidates(&query) {
+            let found = scored(&|entry, _| candidates.contains(entry.id.as_str()));
+            if !found.is_empty() {
+                return order(entries, found, collation);
+            }
+        }
+        let mask = char_mask(&query);
+        order(
+            entries,
+            scored(&|_, doc| doc.mask & mask == mask),
+            collation,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(titles: &[&str]) -> Workspace {
+        let mut workspace = Workspace::new();
+        workspace.credentials = titles
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Entry {
+                id: i.to_string(),
+                title: title.to_string(),
+                username: "user".to_string(),
+                password: "pw".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        workspace.start([1u8; 32]);
+        workspace
+    }
+
+    fn titles(results: Vec<EntryPreview>) -> Vec<String> {
+        results.into_iter().map(|p| p.title).collect()
+    }
+
+    #[test]
+    fn accents_do_not_block_a_match() {
+        let collation = Collation::for_locale(Some("fr"));
+        let options = MatchOptions::default();
+        let mut ws = workspace(&["École Polytechnique", "Banque"]);
+        assert_eq!(
+            titles(search(&mut ws, "ecole", &collation, &options).unwrap()),
+            ["École Polytechnique"]
+        );
+        assert_eq!(
+            titles(search(&mut ws, "ÉCOLE", &collation, &options).unwrap()),
+            ["École Polytechnique"]
+        );
+    }
+
+    #[test]
+    fn empty_query_lists_in_collation_order() {
+        let collation = Collation::for_locale(Some("en"));
+        let options = MatchOptions::default();
+        let mut ws = workspace(&["zeta", "Éclair", "apple", "Ebay"]);
+        assert_eq!(
+            titles(search(&mut ws, "", &collation, &options).unwrap()),
+            ["apple", "Ebay", "Éclair", "zeta"]
+        );
+    }
+
+    #[test]
+    fn urls_match_on_their_registrable_domain() {
+        let collation = Collation::for_locale(Some("en"));
+        let options = MatchOptions::default();
+        let mut ws = workspace(&["Work SSO", "Bank"]);
+        ws.credentials[0].url = Some("https://login.github.com/sso".to_string());
+        ws.credentials[1].url = Some("https://www.bank.co.uk".to_string());
+
+        let found = search(&mut ws, "github", &collation, &options).unwrap();
+        assert_eq!(titles(found.clone()), ["Work SSO"]);
+        assert_eq!(found[0].matched, Some(MatchedField::Url));
+        assert!(search(&mut ws, "login", &collation, &options)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            search(&mut ws, "bank", &collation, &options).unwrap()[0].matched,
+            Some(MatchedField::Title)
+        );
+
+        assert_eq!(
+            registrable_domain("https://www.bank.co.uk/x").unwrap(),
+            "bank.co.uk"
+        );
+        assert_eq!(
+            registrable_domain("http://192.168.1.1:8080").unwrap(),
+            "192.168.1.1"
+        );
+        assert_eq!(registrable_domain("localhost").unwrap(), "localhost");
+    }
+
+    #[test]
+    fn the_index_follows_edits_and_still_finds_fuzzy_matches() {
+        let collation = Collation::for_locale(Some("en"));
+        let options = MatchOptions::default();
+        let mut ws = workspace(&["GitHub", "GitLab", "Bank"]);
+        assert_eq!(
+            titles(search(&mut ws, "git", &collation, &options).unwrap()),
+            ["GitHub", "GitLab"]
+        );
+        assert_eq!(
+            titles(search(&mut ws, "gthb", &collation, &options).unwrap()),
+            ["GitHub"]
+        );
+
+        ws.credentials[1].title = "Codeberg".to_string();
+        ws.credentials.remove(0);
+        assert!(search(&mut ws, "git", &collation, &options)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            titles(search(&mut ws, "berg", &collation, &options).unwrap()),
+            ["Codeberg"]
+        );
+
+        for query in ["", "ban", "co", "xyz", "example"] {
+            let indexed: Vec<String> =
+                titles(search(&mut ws, query, &collation, &options).unwrap());
+            let scanned: Vec<String> = rank(&ws.credentials, query, &collation, &options)
+                .into_iter()
+                .map(|idx| ws.credentials[idx].title.clone())
+                .collect();
+            assert_eq!(indexed, scanned, "query {:?}", query);
+        }
+    }
+
+    #[test]
+    fn credentials_in_daily_use_rank_first() {
+        let collation = Collation::for_locale(Some("en"));
+        let options = MatchOptions::default();
+        let mut ws = workspace(&["GitHub", "GitHub Enterprise", "Bank"]);
+        let now = unix_now();
+        for _ in 0..20 {
+            ws.credentials[1].usage.record(now);
+        }
+        assert_eq!(
+            titles(search(&mut ws, "github", &collation, &options).unwrap()),
+            ["GitHub Enterprise", "GitHub"]
+        );
+        assert_eq!(
+            titles(search(&mut ws, "", &collation, &options).unwrap()),
+            ["GitHub Enterprise", "Bank", "GitHub"]
+        );
+
+        // The same uses count for less as they get older.
+        let used = |days: u64| Usage {
+            count: 20,
+            last_used: Some(now - days * DAY_SECS),
+        };
+        assert!(used(1).frecency(now) > used(10).frecency(now));
+        assert!(used(10).frecency(now) > used(400).frecency(now));
+        assert_eq!(Usage::default().frecency(now), 0);
+    }
+
+    #[test]
+    fn match_options_change_what_counts_as_a_match() {
+        let collation = Collation::for_locale(Some("en"));
+        let mut ws = workspace(&["GitHub", "gitea", "Bank"]);
+        let strict = MatchOptions {
+            case_sensitive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(search(&mut ws, "Git", &collation, &strict).unwrap()),
+            ["GitHub"]
+        );
+
+        let lenient = MatchOptions {
+            min_score: 0,
+            ..Default::default()
+        };
+        let strictest = MatchOptions {
+            min_score: MAX_MIN_SCORE,
+            ..Default::default()
+        };
+        let default = search(&mut ws, "gb", &collation, &MatchOptions::default()).unwrap();
+        assert!(search(&mut ws, "gb", &collation, &lenient).unwrap().len() >= default.len());
+        assert!(search(&mut ws, "gb", &collation, &strictest)
+            .unwrap()
+            .is_empty());
+
+        ws.credentials[2].usage.record(unix_now());
+        let used = MatchOptions {
+            empty_query: EmptyQuery::Used,
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(search(&mut ws, "", &collation, &used).unwrap()),
+            ["Bank"]
+        );
+        let nothing = MatchOptions {
+            empty_query: EmptyQuery::Nothing,
+            ..Default::default()
+        };
+        assert!(search(&mut ws, " ", &collation, &nothing)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            search(&mut ws, "git", &collation, &nothing).unwrap().len(),
+            2
+        );
+    }
+}
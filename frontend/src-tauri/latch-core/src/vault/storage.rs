@@ -0,0 +1,375 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use super::journal::{latest_summary, JournalEntry};
+use super::{EncryptedVault, VaultHeader, VaultSummary};
+use crate::auth::oauth::OAuthProvider;
+
+/// Far beyond any real Vault; anything bigger is corrupt or was swapped in, and is
+/// refused before it is loaded into memory.
+pub const MAX_VAULT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Stands in for the path of an ephemeral Vault, which has no file.
+const EPHEMERAL_PATH: &str = ":memory:";
+
+/// Source of `VaultStorage::generation`, shared so no two Vaults ever hand out
+/// the same one.
+static GENERATIONS: AtomicU64 = AtomicU64::new(1);
+
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, Ordering::SeqCst)
+}
+
+/// The header fields of a Vault file, plus what ties a journal to it.
+#[derive(Deserialize)]
+struct FileHeader {
+    version: String,
+    kdf: String,
+    salt: String,
+    #[serde(default)]
+    summary: Option<VaultSummary>,
+    data: Nonce,
+    #[serde(default)]
+    journal: Vec<JournalEntry>,
+    #[serde(default)]
+    provider: Option<OAuthProvider>,
+}
+
+#[derive(Deserialize)]
+struct Nonce {
+    nonce: String,
+}
+
+/// The journal entries appended to the data with `nonce` in the Vault file at
+/// `vault_path`. One torn by a crash mid-append is skipped, as is anything left
+/// from before the Vault was last rewritten.
+pub(super) fn appended(vault_path: &Path, nonce: &str) -> Vec<JournalEntry> {
+    let Ok(file) = File::open(vault_path.with_extension("journal")) else {
+        return Vec::new();
+    };
+    BufReader::new(file.take(MAX_VAULT_BYTES))
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(&line).ok())
+        .filter(|entry| entry.base == nonce)
+        .collect()
+}
+
+fn keep_in(memory: &Mutex<Option<String>>, vault: &EncryptedVault) -> Result<(), String> {
+    let json =
+        serde_json::to_string(vault).map_err(|e| format!("Failed to serialize vault: {}", e))?;
+    *memory
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")? = Some(json);
+    Ok(())
+}
+
+/// Size and modification time of the Vault file and its journal, to notice when
+/// something else, like a sync tool, replaces them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DiskStamp([Option<(u64, SystemTime)>; 2]);
+
+/// The Vault as one JSON file, rewritten whole when the journal is compacted.
+/// Saves in between are appended, one per line, to a journal file beside it.
+pub struct VaultStorage {
+    pub path: PathBuf,
+    /// The serialized Vault, when it lives only in RAM.
+    memory: Option<Mutex<Option<String>>>,
+    /// Replaced whenever the Vault is rewritten whole.
+    generation: AtomicU64,
+    /// How the files looked when this app last read or wrote them.
+    on_disk: Mutex<Option<DiskStamp>>,
+}
+
+impl VaultStorage {
+    /// Only works out the path; the directory is created on first write so startup
+    /// doesn't touch the disk.
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(get_vault_path()?))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            memory: None,
+            generation: AtomicU64::new(next_generation()),
+            on_disk: Mutex::new(None),
+        }
+    }
+
+    /// A Vault that is never written to disk and is gone when the app quits.
+    pub fn ephemeral() -> Self {
+        Self {
+            memory: Some(Mutex::new(None)),
+            ..Self::at(PathBuf::from(EPHEMERAL_PATH))
+        }
+    }
+
+    pub fn is_ephemeral(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    pub fn exists(&self) -> bool {
+        match &self.memory {
+            Some(memory) => memory.lock().map(|m| m.is_some()).unwrap_or(false),
+            None => self.path.exists(),
+        }
+    }
+
+    /// Opens the Vault file for reading, capped at `MAX_VAULT_BYTES` even if it grows
+    /// while being read.
+    fn open(&self) -> Result<Box<dyn Read>, String> {
+        if let Some(memory) = &self.memory {
+            let json = memory
+                .lock()
+                .map_err(|_| "Vault is temporarily unavailable")?
+                .clone()
+                .ok_or("Failed to read vault: no vault in memory")?;
+            return Ok(Box::new(Cursor::new(json.into_bytes())));
+        }
+        let file = File::open(&self.path).map_err(|e| format!("Failed to read vault: {}", e))?;
+        let size = file
+            .metadata()
+            .map_err(|e| format!("Failed to read vault: {}", e))?
+            .len();
+        if size > MAX_VAULT_BYTES {
+            return Err(format!(
+                "Vault file is too large ({} bytes, limit {})",
+                size, MAX_VAULT_BYTES
+            ));
+        }
+        Ok(Box::new(BufReader::new(file.take(MAX_VAULT_BYTES))))
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.path.with_extension("journal")
+    }
+
+    /// Entries appended to the journal file; an in-memory Vault keeps its journal
+    /// inside the Vault.
+    fn appended(&self, nonce: &str) -> Vec<JournalEntry> {
+        if self.is_ephemeral() {
+            return Vec::new();
+        }
+        appended(&self.path, nonce)
+    }
+
+    fn remove_journal(&self) -> Result<(), String> {
+        let path = self.journal_path();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove vault journal: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// The Vault with its journal, and the summary of its latest save.
+    pub fn read(&self) -> Result<EncryptedVault, String> {
+        let mut vault: EncryptedVault = serde_json::from_reader(self.open()?)
+            .map_err(|e| format!("Failed to parse vault: {}", e))?;
+        let appended = self.appended(&vault.data.nonce);
+        vault.journal.extend(appended);
+        vault.data.check_lengths()?;
+        vault.summary = latest_summary(vault.summary.take(), &vault.journal);
+        Ok(vault)
+    }
+
+    /// Reads only the unencrypted header, streaming past the ciphertext.
+    pub fn read_header(&self) -> Result<VaultHeader, String> {
+        let mut header: FileHeader = serde_json::from_reader(self.open()?)
+            .map_err(|e| format!("Failed to parse vault: {}", e))?;
+        header.journal.extend(self.appended(&header.data.nonce));
+        Ok(VaultHeader {
+            version: header.version,
+            kdf: header.kdf,
+            salt: header.salt,
+            summary: latest_summary(header.summary, &header.journal),
+            provider: header.provider,
+        })
+    }
+
+    /// Replaces the Vault and its journal. The old journal is removed once the
+    /// new file is in place; until then both hold the same entries, and replaying
+    /// them twice is harmless.
+    pub fn write(&self, vault: &EncryptedVault) -> Result<(), String> {
+        self.generation.store(next_generation(), Ordering::SeqCst);
+
+        if let Some(memory) = &self.memory {
+            return keep_in(memory, vault);
+        }
+
+        let json = serde_json::to_string_pretty(vault)
+            .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+        let config_dir = self.path.parent().ok_or("Invalid vault path")?;
+        fs::create_dir_all(config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        let tmp_path = self.path.with_extension("enc.tmp");
+        fs::write(&tmp_path, &json).map_err(|e| format!("Failed to write vault: {}", e))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to rename vault: {}", e))?;
+        self.remove_journal()?;
+        self.mark_current();
+        Ok(())
+    }
+
+    /// Adds to the Vault's journal without rewriting the Vault.
+    pub fn append(&self, mut entry: JournalEntry) -> Result<(), String> {
+        if let Some(memory) = &self.memory {
+            let mut vault: EncryptedVault = serde_json::from_reader(self.open()?)
+                .map_err(|e| format!("Failed to parse vault: {}", e))?;
+            vault.journal.push(entry);
+            return keep_in(memory, &vault);
+        }
+
+        let header: FileHeader = serde_json::from_reader(self.open()?)
+            .map_err(|e| format!("Failed to parse vault: {}", e))?;
+        entry.base = header.data.nonce;
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize vault journal: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())
+            .map_err(|e| format!("Failed to write vault journal: {}", e))?;
+        // Starting on a fresh line keeps a torn entry from swallowing this one.
+        writeln!(file, "\n{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to write vault journal: {}", e))?;
+        self.mark_current();
+        Ok(())
+    }
+
+    /// Deletes the Vault file, or forgets the in-memory Vault.
+    pub fn remove(&self) -> Result<(), String> {
+        self.generation.store(next_generation(), Ordering::SeqCst);
+        if let Some(memory) = &self.memory {
+            *memory
+                .lock()
+                .map_err(|_| "Vault is temporarily unavailable")? = None;
+            return Ok(());
+        }
+        if self.path.exists() {
+            fs::remove_file(&self.path).map_err(|e| format!("Failed to remove vault: {}", e))?;
+        }
+        self.remove_journal()?;
+        *self.on_disk.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+    fn disk_stamp(&self) -> Option<DiskStamp> {
+        if self.is_ephemeral() {
+            return None;
+        }
+        let stamp = |path: &Path| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.len(), metadata.modified().ok()?))
+        };
+        Some(DiskStamp([
+            stamp(&self.path),
+            stamp(&self.path.with_extension("journal")),
+        ]))
+    }
+
+    /// Takes the files as they are now to be the ones this app last read.
+    pub fn mark_current(&self) {
+        *self.on_disk.lock().unwrap_or_else(|e| e.into_inner()) = self.disk_stamp();
+    }
+
+    /// Whether something other than this app rewrote the Vault since it was last
+    /// read at unlock or written here. Never for an ephemeral Vault.
+    pub fn changed_on_disk(&self) -> bool {
+        let known = *self.on_disk.lock().unwrap_or_else(|e| e.into_inner());
+        matches!((known, self.disk_stamp()), (Some(known), Some(now)) if known != now)
+    }
+
+    /// Changes whenever the Vault is rewritten whole, so a save can tell whether
+    /// the Vault still holds what it last saved.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
+pub fn config_dir() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|p| {
+            if cfg!(target_os = "linux") {
+                p.join("latch")
+            } else {
+                p.join("Latch")
+            }
+        })
+        .ok_or_else(|| "Failed to get config dir".to_string())
+}
+
+/// For large downloaded data that isn't settings, like the offline breach database.
+pub fn data_dir() -> Result<PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|p| {
+            if cfg!(target_os = "linux") {
+                p.join("latch")
+            } else {
+                p.join("Latch")
+            }
+        })
+        .ok_or_else(|| "Failed to get data dir".to_string())
+}
+
+fn get_vault_path() -> Result<PathBuf, String> {
+    Ok(config_dir()?.join("vault.enc"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_vault_is_refused_before_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        File::create(&storage.path)
+            .unwrap()
+            .set_len(MAX_VAULT_BYTES + 1)
+            .unwrap();
+
+        assert!(storage.read().unwrap_err().contains("too large"));
+        assert!(storage.read_header().unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn header_is_read_without_the_ciphertext() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        fs::write(
+            &storage.path,
+            r#"{"version":"2","kdf":"password-pbkdf2","salt":"ab","data":{"nonce":"00","ciphertext":"zz"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(storage.read_header().unwrap().kdf, "password-pbkdf2");
+        assert!(storage.read().unwrap_err().contains("nonce length"));
+    }
+
+    #[test]
+    fn ephemeral_vault_round_trips_without_a_file() {
+        let storage = VaultStorage::ephemeral();
+        assert!(!storage.exists());
+
+        let mut workspace = crate::vault::workspace::Workspace::new();
+        crate::vault::provision::provision(
+            &storage,
+            &mut workspace,
+            &[7u8; 32],
+            crate::auth::method::AuthMethod::Password,
+            &"ab".repeat(32),
+        )
+        .unwrap();
+
+        assert!(storage.exists());
+        assert!(!storage.path.exists());
+        assert_eq!(storage.read_header().unwrap().kdf, "password-pbkdf2");
+    }
+}
@@ -0,0 +1,137 @@
+//! Entry-level deltas for syncing straight with another Latch instance. The peer
+//! keeps its own Vault under its own key, so files cannot be swapped as with a
+//! provider; instead each side sends what changed since the copy both agreed on
+//! last time, and merges the other's changes with `three_way`.
+
+use super::three_way::{self, SyncConflict};
+use crate::crypto::aead::{self, EncryptedData};
+use crate::vault::events::VaultEvent;
+use crate::vault::{storage::VaultStorage, workspace::Workspace, Entry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Delta {
+    /// Credentials added or edited since the base, in full.
+    pub changed: Vec<Entry>,
+    /// Ids of Credentials deleted since the base.
+    pub removed: Vec<String>,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What it takes to turn `base` into `current`.
+pub fn diff(base: &[Entry], current: &[Entry]) -> Delta {
+    Delta {
+        changed: current
+            .iter()
+            .filter(|entry| !base.contains(entry))
+            .cloned()
+            .collect(),
+        removed: base
+            .iter()
+            .filter(|entry| !current.iter().any(|e| e.id == entry.id))
+            .map(|entry| entry.id.clone())
+            .collect(),
+    }
+}
+
+/// The other side's Credentials, rebuilt from `base` and the delta it sent.
+pub fn apply(base: &[Entry], delta: &Delta) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = base
+        .iter()
+        .filter(|entry| !delta.removed.contains(&entry.id))
+        .map(|entry| {
+            delta
+                .changed
+                .iter()
+                .find(|e| e.id == entry.id)
+                .unwrap_or(entry)
+                .clone()
+        })
+        .collect();
+    for entry in &delta.changed {
+        if !entries.iter().any(|e| e.id == entry.id) {
+            entries.push(entry.clone());
+        }
+    }
+    entries
+}
+
+/// Merges a peer's `delta` into the Vault against `base` and saves the result.
+/// Returns the Credentials as saved, which are the next base for this peer, and
+/// the clashes that were settled.
+pub fn merge_delta(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    base: &[Entry],
+    delta: &Delta,
+) -> Result<(Vec<Entry>, Vec<SyncConflict>), String> {
+    workspace.check_session()?;
+    let remote = apply(base, delta);
+    let merged = three_way::merge(base, &workspace.credentials, &remote);
+    let previous = std::mem::replace(&mut workspace.credentials, merged.entries);
+    if let Err(e) = crate::vault::entries::persist(workspace, storage) {
+        workspace.credentials = previous;
+        return Err(e);
+    }
+    workspace.notify(VaultEvent::EntriesChanged {
+        count: workspace.credentials.len(),
+    });
+    Ok((workspace.credentials.clone(), merged.conflicts))
+}
+
+/// Encrypts a peer's base with the Session key, to be kept on disk until the next
+/// exchange.
+pub fn seal(key: &[u8; 32], entries: &[Entry]) -> Result<String, String> {
+    let json = serde_json::to_string(entries)
+        .map_err(|e| format!("Failed to serialize sync base: {}", e))?;
+    serde_json::to_string(&aead::encrypt(key, &json)?)
+        .map_err(|e| format!("Failed to serialize sync base: {}", e))
+}
+
+/// Opens a base written by `seal`. One that no longer decrypts (the Vault was
+/// re-keyed since) is `None`, and the next exchange starts without an ancestor.
+pub fn open(key: &[u8; 32], sealed: &str) -> Option<Vec<Entry>> {
+    let encrypted: EncryptedData = serde_json::from_str(sealed).ok()?;
+    let json = aead::decrypt(key, &encrypted).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, password: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_delta_rebuilds_the_other_side() {
+        let base = vec![entry("a", "1"), entry("b", "1"), entry("c", "1")];
+        let current = vec![entry("a", "1"), entry("c", "2"), entry("d", "1")];
+
+        let delta = diff(&base, &current);
+        assert_eq!(delta.changed, vec![entry("c", "2"), entry("d", "1")]);
+        assert_eq!(delta.removed, vec!["b".to_string()]);
+        assert_eq!(apply(&base, &delta), current);
+        assert!(diff(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn a_sealed_base_only_opens_with_its_key() {
+        let base = vec![entry("a", "1")];
+        let sealed = seal(&[1u8; 32], &base).unwrap();
+        assert_eq!(open(&[1u8; 32], &sealed), Some(base));
+        assert_eq!(open(&[2u8; 32], &sealed), None);
+    }
+}
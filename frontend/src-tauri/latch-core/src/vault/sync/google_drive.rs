@@ -0,0 +1,151 @@
+//! Keeps the Vault in the Drive app data folder of the Google account the user signs
+//! in with. The folder is hidden from the Drive UI and from other apps; reaching it
+//! needs only the `drive.appdata` scope on top of the OAuth sign-in.
+
+use super::{RemoteVault, SyncFuture, SyncProvider};
+use serde::Deserialize;
+use url::Url;
+
+pub const SCOPE: &str = "https://www.googleapis.com/auth/drive.appdata";
+/// Returned when Google refuses the access token, which lasts about an hour.
+pub const SIGN_IN_EXPIRED: &str = "Google Drive sign-in expired; sign in again to keep syncing";
+
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const UPLOAD_URL: &str = "https://www.googleapis.com/upload/drive/v3/files";
+const FILE_NAME: &str = "vault.enc";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveFile {
+    id: String,
+    head_revision_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FileList {
+    files: Vec<DriveFile>,
+}
+
+pub struct GoogleDrive {
+    access_token: String,
+    client: reqwest::Client,
+}
+
+impl GoogleDrive {
+    pub fn new(access_token: String) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| format!("Failed to start sync: {}", e))?;
+        Ok(Self {
+            access_token,
+            client,
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Vec<u8>, String> {
+        let response = request
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Google Drive: {}", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(SIGN_IN_EXPIRED.to_string());
+        }
+        if !status.is_success() {
+            return Err(format!("Google Drive refused the request ({})", status));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read from Google Drive: {}", e))?;
+        Ok(body.to_vec())
+    }
+
+    async fn send_json<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, String> {
+        let body = self.send(request).await?;
+        serde_json::from_slice(&body)
+            .map_err(|e| format!("Failed to parse Google Drive response: {}", e))
+    }
+
+    async fn find(&self) -> Result<Option<DriveFile>, String> {
+        let url = Url::parse_with_params(
+            FILES_URL,
+            [
+                ("spaces", "appDataFolder"),
+                ("q", &format!("name = '{}'", FILE_NAME)),
+                ("fields", "files(id,headRevisionId)"),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        let list: FileList = self.send_json(self.client.get(url)).await?;
+        Ok(list.files.into_iter().next())
+    }
+
+    async fn create(&self) -> Result<DriveFile, String> {
+        let metadata = serde_json::json!({ "name": FILE_NAME, "parents": ["appDataFolder"] });
+        let request = self
+            .client
+            .post(format!("{}?fields=id", FILES_URL))
+            .header("Content-Type", "application/json")
+            .body(metadata.to_string());
+        self.send_json(request).await
+    }
+}
+
+fn revision_of(file: DriveFile) -> Result<String, String> {
+    file.head_revision_id
+        .ok_or_else(|| "Google Drive did not report a revision".to_string())
+}
+
+impl SyncProvider for GoogleDrive {
+    fn revision(&self) -> SyncFuture<'_, Option<String>> {
+        Box::pin(async move {
+            match self.find().await? {
+                Some(file) => revision_of(file).map(Some),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn download(&self) -> SyncFuture<'_, RemoteVault> {
+        Box::pin(async move {
+            let file = self
+                .find()
+                .await?
+                .ok_or_else(|| "Synced vault not found on Google Drive".to_string())?;
+            let bytes = self
+                .send(
+                    self.client
+                        .get(format!("{}/{}?alt=media", FILES_URL, file.id)),
+                )
+                .await?;
+            Ok(RemoteVault {
+                revision: revision_of(file)?,
+                bytes,
+            })
+        })
+    }
+
+    fn upload(&self, bytes: Vec<u8>) -> SyncFuture<'_, String> {
+        Box::pin(async move {
+            let file = match self.find().await? {
+                Some(file) => file,
+                None => self.create().await?,
+            };
+            let request = self
+                .client
+                .patch(format!(
+                    "{}/{}?uploadType=media&fields=id,headRevisionId",
+                    UPLOAD_URL, file.id
+                ))
+                .header("Content-Type", "application/octet-stream")
+                .body(bytes);
+            revision_of(self.send_json(request).await?)
+        })
+    }
+}
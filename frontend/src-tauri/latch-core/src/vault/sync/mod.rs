@@ -0,0 +1,380 @@
+//! Keeps the encrypted Vault file in step with a copy held by a sync provider. Only
+//! the ciphertext ever leaves the machine; each side's revision is remembered, and
+//! when both changed since the last sync they are merged entry by entry against
+//! the copy both started from (see `three_way`).
+
+pub mod delta;
+pub mod google_drive;
+pub mod s3;
+pub mod three_way;
+
+use super::{storage::VaultStorage, workspace::Workspace, EncryptedVault, Entry};
+use crate::vault::events::VaultEvent;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use three_way::SyncConflict;
+
+/// How often the Vault is synced while unlocked.
+pub const SYNC_INTERVAL_SECS: u64 = 5 * 60;
+
+pub type SyncFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// Somewhere the encrypted Vault file can be kept. Revisions are opaque; a provider
+/// only promises that every upload gets a new one.
+pub trait SyncProvider: Send + Sync {
+    /// The remote copy's current revision, or `None` if nothing was uploaded yet.
+    fn revision(&self) -> SyncFuture<'_, Option<String>>;
+    fn download(&self) -> SyncFuture<'_, RemoteVault>;
+    /// Replaces the remote copy with `bytes` and returns its new revision.
+    fn upload(&self, bytes: Vec<u8>) -> SyncFuture<'_, String>;
+}
+
+pub struct RemoteVault {
+    pub revision: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncProviderKind {
+    /// The hidden app data folder of the Google account used to sign in.
+    GoogleDrive,
+    /// A bucket on an S3-compatible service.
+    S3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    UpToDate,
+    Uploaded,
+    Downloaded,
+    /// Both copies changed since the last sync and were merged; the result was
+    /// kept here and uploaded.
+    Merged,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct SyncReport {
+    pub outcome: SyncOutcome,
+    /// Credentials both sides changed, and which change was kept.
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// What both sides looked like after the last successful sync.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub revision: Option<String>,
+    /// SHA-256 of the local Vault file, in hex.
+    pub local_hash: Option<String>,
+    pub last_synced: Option<u64>,
+    /// The Vault file both sides agreed on at the last sync, still encrypted; the
+    /// common ancestor for a three-way merge.
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+impl SyncRecord {
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize sync record: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write sync record: {}", e))
+    }
+}
+
+pub fn record_path() -> Result<PathBuf, String> {
+    Ok(super::storage::config_dir()?.join("sync.json"))
+}
+
+pub fn hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// What a sync has to do, given the last record and where each side is now.
+#[derive(Debug, PartialEq)]
+enum Plan {
+    Nothing,
+    Upload,
+    Download,
+    Merge,
+}
+
+fn plan(record: &SyncRecord, local_hash: &str, remote: Option<&str>) -> Plan {
+    let local_changed = record.local_hash.as_deref() != Some(local_hash);
+    let Some(remote) = remote else {
+        return Plan::Upload;
+    };
+    match (local_changed, record.revision.as_deref() != Some(remote)) {
+        (false, false) => Plan::Nothing,
+        (true, false) => Plan::Upload,
+        (false, true) => Plan::Download,
+        (true, true) => Plan::Merge,
+    }
+}
+
+/// The result of one sync. A download still has to be written with `apply_remote`.
+pub enum Synced {
+    UpToDate,
+    Uploaded {
+        revision: String,
+    },
+    Downloaded(RemoteVault),
+    /// Both sides changed; the remote copy still has to be merged with `merge_remote`.
+    Diverged(RemoteVault),
+}
+
+/// Compares the local Vault file `local` with the remote copy and uploads or
+/// downloads whichever side is behind. No lock is held here, so the caller reads
+/// the file before and applies a download after.
+pub async fn sync(
+    provider: &dyn SyncProvider,
+    local: &[u8],
+    record: &SyncRecord,
+) -> Result<Synced, String> {
+    let remote = provider.revision().await?;
+    match plan(record, &hash(local), remote.as_deref()) {
+        Plan::Nothing => Ok(Synced::UpToDate),
+        Plan::Upload => Ok(Synced::Uploaded {
+            revision: provider.upload(local.to_vec()).await?,
+        }),
+        Plan::Download => Ok(Synced::Downloaded(provider.download().await?)),
+        Plan::Merge => Ok(Synced::Diverged(provider.download().await?)),
+    }
+}
+
+/// The Vault file as it would be written, with any journal folded in, for `sync`.
+pub fn read_local(storage: &VaultStorage) -> Result<Vec<u8>, String> {
+    if storage.is_ephemeral() {
+        return Err("An ephemeral vault is never synced".to_string());
+    }
+    serde_json::to_vec_pretty(&storage.read()?)
+        .map_err(|e| format!("Failed to serialize vault: {}", e))
+}
+
+fn check_unchanged(storage: &VaultStorage, local_hash: &str) -> Result<(), String> {
+    if hash(&read_local(storage)?) != local_hash {
+        return Err("Vault changed during sync; it will be synced again".to_string());
+    }
+    Ok(())
+}
+
+/// Parses a synced copy of the Vault file and decrypts it with `key`.
+fn open_copy(bytes: &[u8], key: &[u8; 32]) -> Result<(EncryptedVault, Vec<Entry>), String> {
+    let vault: EncryptedVault = serde_json::from_slice(bytes)
+        .map_err(|e| format!("Failed to parse synced vault: {}", e))?;
+    vault.data.check_lengths()?;
+    let entries = vault.entries(key).map_err(|_| {
+        "Synced vault could not be decrypted with this Session's key; it was re-keyed elsewhere"
+            .to_string()
+    })?;
+    Ok((vault, entries))
+}
+
+/// Replaces the Vault with a downloaded copy, provided the local file is still the
+/// one `sync` compared (`local_hash`) and the copy opens with the Session key.
+/// Returns the file as written.
+pub fn apply_remote(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    remote: &[u8],
+    local_hash: &str,
+) -> Result<Vec<u8>, String> {
+    workspace.check_session()?;
+    check_unchanged(storage, local_hash)?;
+    let key = workspace.session_key.as_ref().ok_or("Vault is locked")?;
+    let (vault, entries) = open_copy(remote, key)?;
+
+    storage.write(&vault)?;
+    workspace.credentials = entries;
+    workspace.notify(VaultEvent::EntriesChanged {
+        count: workspace.credentials.len(),
+    });
+    read_local(storage)
+}
+
+/// Merges a diverged remote copy into the Vault against `base`, the file as of the
+/// last sync, and saves the result. An ancestor that no longer opens (the Vault was
+/// re-keyed since) is treated as missing. Returns the file as written, to be
+/// uploaded, and the clashes that were settled.
+pub fn merge_remote(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    remote: &[u8],
+    base: Option<&[u8]>,
+    local_hash: &str,
+) -> Result<(Vec<u8>, Vec<SyncConflict>), String> {
+    workspace.check_session()?;
+    check_unchanged(storage, local_hash)?;
+    let key = workspace.session_key.as_ref().ok_or("Vault is locked")?;
+    let (_, remote) = open_copy(remote, key)?;
+    let base = base
+        .and_then(|b| open_copy(b, key).ok())
+        .map(|(_, entries)| entries)
+        .unwrap_or_default();
+
+    let merged = three_way::merge(&base, &workspace.credentials, &remote);
+    let previous = std::mem::replace(&mut workspace.credentials, merged.entries);
+    if let Err(e) = super::entries::persist(workspace, storage) {
+        workspace.credentials = previous;
+        return Err(e);
+    }
+    Ok((read_local(storage)?, merged.conflicts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::provision;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryProvider {
+        remote: Mutex<Option<RemoteVault>>,
+        uploads: Mutex<usize>,
+    }
+
+    impl SyncProvider for MemoryProvider {
+        fn revision(&self) -> SyncFuture<'_, Option<String>> {
+            let revision = self
+                .remote
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|r| r.revision.clone());
+            Box::pin(async move { Ok(revision) })
+        }
+
+        fn download(&self) -> SyncFuture<'_, RemoteVault> {
+            let remote = self.remote.lock().unwrap().as_ref().map(|r| RemoteVault {
+                revision: r.revision.clone(),
+                bytes: r.bytes.clone(),
+            });
+            Box::pin(async move { remote.ok_or_else(|| "Remote vault not found".to_string()) })
+        }
+
+        fn upload(&self, bytes: Vec<u8>) -> SyncFuture<'_, String> {
+            let mut uploads = self.uploads.lock().unwrap();
+            *uploads += 1;
+            let revision = format!("r{}", uploads);
+            *self.remote.lock().unwrap() = Some(RemoteVault {
+                revision: revision.clone(),
+                bytes,
+            });
+            Box::pin(async move { Ok(revision) })
+        }
+    }
+
+    #[test]
+    fn plans_by_which_side_moved() {
+        let record = SyncRecord {
+            revision: Some("r1".to_string()),
+            local_hash: Some("h1".to_string()),
+            last_synced: None,
+            base: None,
+        };
+        assert_eq!(plan(&record, "h1", Some("r1")), Plan::Nothing);
+        assert_eq!(plan(&record, "h2", Some("r1")), Plan::Upload);
+        assert_eq!(plan(&record, "h1", Some("r2")), Plan::Download);
+        assert_eq!(plan(&record, "h2", Some("r2")), Plan::Merge);
+        assert_eq!(plan(&SyncRecord::default(), "h1", None), Plan::Upload);
+        assert_eq!(plan(&SyncRecord::default(), "h1", Some("r1")), Plan::Merge);
+    }
+
+    #[tokio::test]
+    async fn syncs_with_another_machine() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        provision::provision(
+            &storage,
+            &mut workspace,
+            &[7u8; 32],
+            AuthMethod::Password,
+            "",
+        )
+        .unwrap();
+        let provider = MemoryProvider::default();
+
+        let local = read_local(&storage).unwrap();
+        let Synced::Uploaded { revision } = sync(&provider, &local, &SyncRecord::default())
+            .await
+            .unwrap()
+        else {
+            panic!("expected an upload");
+        };
+        let record = SyncRecord {
+            revision: Some(revision),
+            local_hash: Some(hash(&local)),
+            last_synced: None,
+            base: None,
+        };
+
+        // Another machine adds a Credential and uploads.
+        let other = VaultStorage::at(dir.path().join("other.enc"));
+        fs::write(&other.path, &local).unwrap();
+        let mut other_workspace = Workspace::new();
+        crate::vault::access::access(&other, &mut other_workspace, &[7u8; 32]).unwrap();
+        other_workspace.credentials.push(Entry {
+            id: "mail".to_string(),
+            title: "Mail".to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            ..Default::default()
+        });
+        crate::vault::entries::persist(&other_workspace, &other).unwrap();
+        provider.upload(read_local(&other).unwrap()).await.unwrap();
+
+        let Synced::Downloaded(remote) = sync(&provider, &local, &record).await.unwrap() else {
+            panic!("expected a download");
+        };
+        apply_remote(&storage, &mut workspace, &remote.bytes, &hash(&local)).unwrap();
+        assert_eq!(workspace.credentials.len(), 1);
+
+        let synced = read_local(&storage).unwrap();
+        let record = SyncRecord {
+            revision: Some(remote.revision),
+            local_hash: Some(hash(&synced)),
+            last_synced: None,
+            base: Some(String::from_utf8(synced.clone()).unwrap()),
+        };
+
+        // Both machines now edit: this one adds a Credential, the other changes Mail.
+        workspace.credentials.push(Entry {
+            id: "bank".to_string(),
+            ..workspace.credentials[0].clone()
+        });
+        crate::vault::entries::persist(&workspace, &storage).unwrap();
+        other_workspace.credentials[0].password = "changed".to_string();
+        crate::vault::entries::persist(&other_workspace, &other).unwrap();
+        provider.upload(read_local(&other).unwrap()).await.unwrap();
+
+        let local = read_local(&storage).unwrap();
+        let Synced::Diverged(remote) = sync(&provider, &local, &record).await.unwrap() else {
+            panic!("expected diverged copies");
+        };
+        let (_, conflicts) = merge_remote(
+            &storage,
+            &mut workspace,
+            &remote.bytes,
+            record.base.as_deref().map(str::as_bytes),
+            &hash(&local),
+        )
+        .unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(workspace.credentials.len(), 2);
+        assert_eq!(workspace.credentials[0].password, "changed");
+    }
+}
@@ -0,0 +1,185 @@
+//! Entry-level three-way merge for when both copies of the Vault changed since the
+//! last sync. Each Credential is compared with its copy in the common ancestor; a
+//! side that left it alone takes the other side's version, and a real clash is
+//! settled by `updated_at` and reported.
+
+use crate::vault::Entry;
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Both sides edited it; this machine's edit was newer or tied.
+    KeptLocal,
+    /// Both sides edited it; the other machine's edit was newer.
+    KeptRemote,
+    /// One side deleted it and the other edited it; the edit was kept.
+    KeptEdit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct SyncConflict {
+    pub entry_id: String,
+    pub title: String,
+    pub resolution: ConflictResolution,
+}
+
+#[derive(Debug, Default)]
+pub struct Merged {
+    pub entries: Vec<Entry>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+fn find<'a>(entries: &'a [Entry], id: &str) -> Option<&'a Entry> {
+    entries.iter().find(|e| e.id == id)
+}
+
+fn conflict(entry: &Entry, resolution: ConflictResolution) -> SyncConflict {
+    SyncConflict {
+        entry_id: entry.id.clone(),
+        title: entry.title.clone(),
+        resolution,
+    }
+}
+
+/// Whether two copies hold the same Credential. `usage` changes on every use
+/// without editing anything, so it is left out.
+fn same(a: Option<&Entry>, b: Option<&Entry>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            *a == Entry {
+                usage: a.usage,
+                ..b.clone()
+            }
+        }
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Merges `local` and `remote` against `base`, their state at the last sync. With
+/// no ancestor (`base` empty), nothing counts as deleted and any differing copy of
+/// the same Credential is a clash.
+pub fn merge(base: &[Entry], local: &[Entry], remote: &[Entry]) -> Merged {
+    let mut merged = Merged::default();
+    let mut seen = HashSet::new();
+    let ids = local.iter().chain(remote).map(|e| e.id.as_str());
+    for id in ids {
+        if !seen.insert(id) {
+            continue;
+        }
+        let (b, l, r) = (find(base, id), find(local, id), find(remote, id));
+        let kept = match (l, r) {
+            (Some(l), Some(r)) if same(Some(l), Some(r)) => Some(l),
+            _ if same(l, b) => r,
+            _ if same(r, b) => l,
+            (Some(l), Some(r)) => {
+                let (winner, resolution) = if r.updated_at > l.updated_at {
+                    (r, ConflictResolution::KeptRemote)
+                } else {
+                    (l, ConflictResolution::KeptLocal)
+                };
+                merged.conflicts.push(conflict(winner, resolution));
+                Some(winner)
+            }
+            (Some(edited), None) | (None, Some(edited)) => {
+                merged
+                    .conflicts
+                    .push(conflict(edited, ConflictResolution::KeptEdit));
+                Some(edited)
+            }
+            (None, None) => None,
+        };
+        merged.entries.extend(kept.cloned().map(|mut entry| {
+            if let (Some(l), Some(r)) = (l, r) {
+                entry.usage = l.usage.merged(r.usage);
+            }
+            entry
+        }));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, password: &str, updated_at: u64) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            updated_at: Some(updated_at),
+            ..Default::default()
+        }
+    }
+
+    fn passwords(merged: &Merged) -> Vec<(&str, &str)> {
+        merged
+            .entries
+            .iter()
+            .map(|e| (e.id.as_str(), e.password.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn takes_each_sides_changes_without_conflict() {
+        let base = [entry("a", "1", 1), entry("b", "1", 1), entry("c", "1", 1)];
+        let local = [entry("a", "2", 2), entry("b", "1", 1), entry("new", "1", 2)];
+        let remote = [entry("a", "1", 1), entry("b", "3", 3), entry("c", "1", 1)];
+
+        let merged = merge(&base, &local, &remote);
+        assert_eq!(passwords(&merged), [("a", "2"), ("b", "3"), ("new", "1")]);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn settles_clashes_by_timestamp_and_reports_them() {
+        let base = [entry("a", "1", 1), entry("b", "1", 1), entry("c", "1", 1)];
+        let local = [entry("a", "local", 5), entry("b", "local", 9)];
+        let remote = [
+            entry("a", "remote", 7),
+            entry("b", "remote", 8),
+            entry("c", "edited", 4),
+        ];
+
+        let merged = merge(&base, &local, &remote);
+        assert_eq!(
+            passwords(&merged),
+            [("a", "remote"), ("b", "local"), ("c", "edited")]
+        );
+        let resolutions: Vec<_> = merged.conflicts.iter().map(|c| c.resolution).collect();
+        assert_eq!(
+            resolutions,
+            [
+                ConflictResolution::KeptRemote,
+                ConflictResolution::KeptLocal,
+                ConflictResolution::KeptEdit
+            ]
+        );
+    }
+
+    #[test]
+    fn without_an_ancestor_nothing_is_deleted() {
+        let merged = merge(&[], &[entry("a", "1", 1)], &[entry("b", "1", 1)]);
+        assert_eq!(passwords(&merged), [("a", "1"), ("b", "1")]);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn uses_on_both_sides_are_not_a_clash() {
+        let base = [entry("a", "1", 1)];
+        let mut local = base.clone();
+        local[0].usage.record(10);
+        let mut remote = base.clone();
+        remote[0].usage.record(20);
+        remote[0].usage.record(30);
+
+        let merged = merge(&base, &local, &remote);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.entries[0].usage.count, 2);
+        assert_eq!(merged.entries[0].usage.last_used, Some(30));
+    }
+}
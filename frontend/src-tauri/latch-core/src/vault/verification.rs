@@ -0,0 +1,174 @@
+use super::{storage::VaultStorage, unix_now, workspace::Workspace, Entry};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    #[default]
+    Unreviewed,
+    Verified,
+    NeedsReview,
+}
+
+/// Review state of a Credential. `imported_at` stays set until the Credential is
+/// edited, so entries brought in from elsewhere and never touched can be flagged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct Verification {
+    pub status: VerificationStatus,
+    pub reviewed_at: Option<u64>,
+    pub imported_at: Option<u64>,
+}
+
+impl Verification {
+    pub fn imported() -> Self {
+        Verification {
+            imported_at: Some(unix_now()),
+            ..Default::default()
+        }
+    }
+
+    /// The Credential was edited: it is no longer untouched since import.
+    pub fn touched(&self) -> Self {
+        Verification {
+            imported_at: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewReason {
+    NeedsReview,
+    UntouchedSinceImport,
+    Unreviewed,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct UnverifiedEntry {
+    pub id: String,
+    pub title: String,
+    pub username: String,
+    pub icon_url: Option<String>,
+    pub reason: ReviewReason,
+    /// When the entry was flagged or imported, if known.
+    pub since: Option<u64>,
+}
+
+fn review_reason(entry: &Entry) -> Option<(ReviewReason, Option<u64>)> {
+    let v = &entry.verification;
+    match v.status {
+        VerificationStatus::Verified => None,
+        VerificationStatus::NeedsReview => Some((ReviewReason::NeedsReview, v.reviewed_at)),
+        VerificationStatus::Unreviewed => match v.imported_at {
+            Some(at) => Some((ReviewReason::UntouchedSinceImport, Some(at))),
+            None => Some((ReviewReason::Unreviewed, None)),
+        },
+    }
+}
+
+pub fn mark(
+    workspace: &mut Workspace,
+    storage: &VaultStorage,
+    id: &str,
+    status: VerificationStatus,
+) -> Result<(), String> {
+    workspace.check_session()?;
+    workspace.refresh();
+    let entry = workspace
+        .credentials
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Credential '{}' not found", id))?;
+    entry.verification.status = status;
+    entry.verification.reviewed_at = Some(unix_now());
+    super::entries::persist(workspace, storage)
+}
+
+/// Credentials not yet verified, most urgent first: explicitly flagged, then
+/// untouched imports (oldest first), then everything else never reviewed.
+pub fn unverified(workspace: &mut Workspace) -> Result<Vec<UnverifiedEntry>, String> {
+    workspace.check_session()?;
+    workspace.refresh();
+
+    let mut pending: Vec<UnverifiedEntry> = workspace
+        .credentials
+        .iter()
+        .filter_map(|entry| {
+            let (reason, since) = review_reason(entry)?;
+            Some(UnverifiedEntry {
+                id: entry.id.clone(),
+                title: entry.title.clone(),
+                username: entry.username.clone(),
+                icon_url: entry.icon_url.clone(),
+                reason,
+                since,
+            })
+        })
+        .collect();
+    pending.sort_by_key(|e| {
+        let rank = match e.reason {
+            ReviewReason::NeedsReview => 0,
+            ReviewReason::UntouchedSinceImport => 1,
+            ReviewReason::Unreviewed => 2,
+        };
+        (rank, e.since.unwrap_or(u64::MAX))
+    });
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::portable::{import, ImportMode};
+
+    fn entry(id: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            verification: Verification::default(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn imports_are_flagged_until_edited_or_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = VaultStorage::at(dir.path().join("vault.enc"));
+        let mut workspace = Workspace::new();
+        crate::vault::provision::provision(
+            &storage,
+            &mut workspace,
+            &[6u8; 32],
+            AuthMethod::Password,
+            "salt",
+        )
+        .unwrap();
+        crate::vault::entries::add(&mut workspace, &storage, entry("own")).unwrap();
+        import(
+            &storage,
+            &mut workspace,
+            vec![entry("a"), entry("b")],
+            ImportMode::Merge,
+        )
+        .unwrap();
+
+        let pending = unverified(&mut workspace).unwrap();
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending[0].reason, ReviewReason::UntouchedSinceImport);
+        assert_eq!(pending[2].id, "own");
+
+        mark(&mut workspace, &storage, "a", VerificationStatus::Verified).unwrap();
+        let mut edited = entry("b");
+        edited.title = "renamed".to_string();
+        crate::vault::entries::update(&mut workspace, &storage, edited).unwrap();
+
+        let pending = unverified(&mut workspace).unwrap();
+        assert!(pending.iter().all(|e| e.id != "a"));
+        assert!(pending.iter().all(|e| e.reason == ReviewReason::Unreviewed));
+    }
+}
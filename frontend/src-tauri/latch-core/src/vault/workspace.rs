@@ -0,0 +1,182 @@
+use super::events::{VaultEvent, VaultEvents};
+use super::guest::GuestSession;
+use super::history::History;
+use super::journal::Baseline;
+use super::search::SearchIndex;
+use super::{Entry, MAX_SESSION_SECS, SESSION_TIMEOUT_SECS};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use zeroize::Zeroize;
+
+pub struct Workspace {
+    pub credentials: Vec<Entry>,
+    pub session_key: Option<zeroize::Zeroizing<[u8; 32]>>,
+    pub session_start: Option<SystemTime>,
+    /// When the Session was last extended; it times out from here instead.
+    pub session_extended: Option<SystemTime>,
+    pub last_activity: Option<SystemTime>,
+    /// Set when the Session was opened with the duress password.
+    pub decoy: bool,
+    /// Set while someone else is using a restricted slice of the Session.
+    pub guest: Option<GuestSession>,
+    /// When each Credential with a reveal cooldown was last revealed this Session.
+    revealed_at: HashMap<String, SystemTime>,
+    /// What the last save wrote, for the next to journal against.
+    pub(super) saved: Mutex<Option<Baseline>>,
+    pub(super) search_index: SearchIndex,
+    /// Undo and redo for this Session.
+    pub(super) history: History,
+    events: Option<Arc<dyn VaultEvents>>,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self {
+            credentials: Vec::new(),
+            session_key: None,
+            session_start: None,
+            session_extended: None,
+            last_activity: None,
+            decoy: false,
+            guest: None,
+            revealed_at: HashMap::new(),
+            saved: Mutex::new(None),
+            search_index: SearchIndex::default(),
+            history: History::default(),
+            events: None,
+        }
+    }
+
+    pub fn with_events(mut self, events: Arc<dyn VaultEvents>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn notify(&self, event: VaultEvent) {
+        if let Some(events) = &self.events {
+            events.notify(event);
+        }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.session_key.is_some()
+    }
+
+    pub fn check_session(&mut self) -> Result<(), String> {
+        if self.session_key.is_none() {
+            return Err("Vault is locked".to_string());
+        }
+        if self.session_start.is_none() {
+            return Err("Invalid session".to_string());
+        }
+        if self.session_remaining_secs() == 0 {
+            self.lock();
+            return Err("Session expired".to_string());
+        }
+        if self.guest.as_ref().is_some_and(GuestSession::expired) {
+            self.lock();
+            return Err("Guest session expired".to_string());
+        }
+        Ok(())
+    }
+
+    /// When the Session times out: `SESSION_TIMEOUT_SECS` after it started or was
+    /// last extended, but never past `MAX_SESSION_SECS`.
+    pub fn session_expires_at(&self) -> Option<SystemTime> {
+        let start = self.session_start.filter(|_| self.is_unlocked())?;
+        let from = self.session_extended.unwrap_or(start);
+        Some(
+            (from + Duration::from_secs(SESSION_TIMEOUT_SECS))
+                .min(start + Duration::from_secs(MAX_SESSION_SECS)),
+        )
+    }
+
+    /// Seconds until the Session times out; 0 when locked or already expired.
+    pub fn session_remaining_secs(&self) -> u64 {
+        self.session_expires_at()
+            .and_then(|at| at.duration_since(SystemTime::now()).ok())
+            .map_or(0, |left| left.as_secs())
+    }
+
+    /// Restarts the Session timeout from now, for explicit activity by the user.
+    /// Returns the seconds now remaining.
+    pub fn extend_session(&mut self) -> Result<u64, String> {
+        self.check_session()?;
+        if self.guest.is_some() {
+            return Err("Guest sessions can't be extended".to_string());
+        }
+        self.session_extended = Some(SystemTime::now());
+        self.refresh();
+        Ok(self.session_remaining_secs())
+    }
+
+    pub fn refresh(&mut self) {
+        self.last_activity = Some(SystemTime::now());
+    }
+
+    pub fn idle_secs(&self) -> Option<u64> {
+        self.last_activity
+            .and_then(|at| at.elapsed().ok())
+            .map(|elapsed| elapsed.as_secs())
+    }
+
+    pub fn lock(&mut self) {
+        let was_unlocked = self.session_key.is_some();
+        if let Some(ref mut key) = self.session_key {
+            key.zeroize();
+        }
+        self.session_key = None;
+        self.session_start = None;
+        self.session_extended = None;
+        self.last_activity = None;
+        self.decoy = false;
+        self.guest = None;
+        self.revealed_at.clear();
+        self.credentials.clear();
+        self.search_index.clear();
+        self.history.clear();
+        if let Ok(mut saved) = self.saved.lock() {
+            *saved = None;
+        }
+        if was_unlocked {
+            self.notify(VaultEvent::Locked);
+        }
+    }
+
+    pub fn start(&mut self, key: [u8; 32]) {
+        self.session_key = Some(zeroize::Zeroizing::new(key));
+        let now = SystemTime::now();
+        self.session_start = Some(now);
+        self.session_extended = None;
+        self.last_activity = Some(now);
+        self.revealed_at.clear();
+        self.notify(VaultEvent::Unlocked);
+    }
+
+    /// Records a reveal of `entry`'s password, or refuses it while the entry's
+    /// cooldown from the last reveal is still running. Unlocking again resets it.
+    pub fn reveal(&mut self, entry: &Entry) -> Result<(), String> {
+        if entry.reveal_cooldown_secs == 0 {
+            return Ok(());
+        }
+        let now = SystemTime::now();
+        if let Some(at) = self.revealed_at.get(&entry.id) {
+            let elapsed = now.duration_since(*at).map(|d| d.as_secs()).unwrap_or(0);
+            if elapsed < entry.reveal_cooldown_secs {
+                return Err(format!(
+                    "Unlock again to reveal this credential, or wait {} seconds",
+                    entry.reveal_cooldown_secs - elapsed
+                ));
+            }
+        }
+        self.revealed_at.insert(entry.id.clone(), now);
+        Ok(())
+    }
+}
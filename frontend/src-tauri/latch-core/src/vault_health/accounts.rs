@@ -0,0 +1,290 @@
+//! Opt-in monitoring of the email addresses used as usernames, against Have I Been
+//! Pwned's breached-account API. Unlike the password range check this sends the
+//! whole address, so it stays off unless the user turns it on and supplies their
+//! own API key (which the frontend keeps in the OS keychain).
+
+use crate::vault::Entry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+const HIBP_ACCOUNT_URL: &str = "https://haveibeenpwned.com/api/v3/breachedaccount/";
+/// Rate-limited requests are retried this often, waiting as long as HIBP asks.
+const MAX_RETRIES: u32 = 3;
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(30);
+/// Returned when HIBP refuses the API key.
+pub const KEY_REFUSED: &str = "Have I Been Pwned refused the API key; check it in Settings";
+
+/// A breach an address was found in, as HIBP names it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct AccountBreach {
+    /// HIBP's stable id for the breach.
+    pub name: String,
+    pub title: String,
+    pub domain: String,
+    pub breach_date: String,
+    /// Not seen in an earlier check.
+    #[serde(default)]
+    pub new: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct BreachedAccount {
+    pub email: String,
+    /// The Credentials using the address as their username.
+    pub entry_ids: Vec<String>,
+    pub breaches: Vec<AccountBreach>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HibpBreach {
+    name: String,
+    title: String,
+    #[serde(default)]
+    domain: String,
+    #[serde(default)]
+    breach_date: String,
+}
+
+impl From<HibpBreach> for AccountBreach {
+    fn from(breach: HibpBreach) -> Self {
+        AccountBreach {
+            name: breach.name,
+            title: breach.title,
+            domain: breach.domain,
+            breach_date: breach.breach_date,
+            new: false,
+        }
+    }
+}
+
+pub type AccountFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<AccountBreach>, String>> + Send + 'a>>;
+
+pub trait AccountChecker: Send + Sync {
+    /// The breaches `email` was found in; empty if none.
+    fn breaches<'a>(&'a self, email: &'a str) -> AccountFuture<'a>;
+}
+
+pub struct HibpAccountApi {
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl HibpAccountApi {
+    pub fn new(api_key: &str) -> Result<Self, String> {
+        let api_key = api_key.trim();
+        if api_key.is_empty() {
+            return Err("Invalid API key: it is empty".to_string());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| format!("Failed to start breach monitor: {}", e))?;
+        Ok(Self {
+            api_key: api_key.to_string(),
+            client,
+        })
+    }
+}
+
+impl AccountChecker for HibpAccountApi {
+    fn breaches<'a>(&'a self, email: &'a str) -> AccountFuture<'a> {
+        Box::pin(async move {
+            let url = format!(
+                "{}{}?truncateResponse=false",
+                HIBP_ACCOUNT_URL,
+                percent_encoding::utf8_percent_encode(email, percent_encoding::NON_ALPHANUMERIC)
+            );
+            let mut attempt = 0;
+            loop {
+                let response = self
+                    .client
+                    .get(&url)
+                    .header("hibp-api-key", &self.api_key)
+                    .header(reqwest::header::USER_AGENT, "Latch-Password-Manager")
+                    .send()
+                    .await
+                    .map_err(|e| format!("Failed to reach Have I Been Pwned: {}", e))?;
+                let status = response.status();
+                if status == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(Vec::new());
+                }
+                if status == reqwest::StatusCode::UNAUTHORIZED
+                    || status == reqwest::StatusCode::FORBIDDEN
+                {
+                    return Err(KEY_REFUSED.to_string());
+                }
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RETRIES {
+                    attempt += 1;
+                    let wait = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse().ok())
+                        .map_or(Duration::from_secs(2), Duration::from_secs);
+                    tokio::time::sleep(wait.min(MAX_RETRY_WAIT)).await;
+                    continue;
+                }
+                if !status.is_success() {
+                    return Err(format!(
+                        "Have I Been Pwned refused the request ({})",
+                        status
+                    ));
+                }
+                let body = response
+                    .bytes()
+                    .await
+                    .map_err(|e| format!("Failed to read from Have I Been Pwned: {}", e))?;
+                let breaches: Vec<HibpBreach> = serde_json::from_slice(&body)
+                    .map_err(|e| format!("Failed to parse Have I Been Pwned response: {}", e))?;
+                return Ok(breaches.into_iter().map(AccountBreach::from).collect());
+            }
+        })
+    }
+}
+
+fn looks_like_email(username: &str) -> bool {
+    let Some((local, domain)) = username.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && !username.chars().any(char::is_whitespace)
+}
+
+/// Each address used as a username, lowercased, with the Credentials using it.
+/// Canaries are left out; they are checked as bait (see `canary`).
+pub fn monitored_emails(entries: &[Entry]) -> BTreeMap<String, Vec<String>> {
+    let mut emails: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| !e.is_canary()) {
+        let username = entry.username.trim();
+        if looks_like_email(username) {
+            emails
+                .entry(username.to_lowercase())
+                .or_default()
+                .push(entry.id.clone());
+        }
+    }
+    emails
+}
+
+pub async fn check_accounts(
+    entries: &[Entry],
+    checker: &dyn AccountChecker,
+) -> Result<Vec<BreachedAccount>, String> {
+    check_emails(monitored_emails(entries), checker).await
+}
+
+/// Looks up each address from `monitored_emails`. A refused key stops the check;
+/// other failures skip the address.
+pub async fn check_emails(
+    emails: BTreeMap<String, Vec<String>>,
+    checker: &dyn AccountChecker,
+) -> Result<Vec<BreachedAccount>, String> {
+    let mut breached = Vec::new();
+    for (email, entry_ids) in emails {
+        match checker.breaches(&email).await {
+            Ok(breaches) if !breaches.is_empty() => breached.push(BreachedAccount {
+                email,
+                entry_ids,
+                breaches,
+            }),
+            Ok(_) => {}
+            Err(e) if e == KEY_REFUSED => return Err(e),
+            Err(e) => eprintln!("Failed to check an account for breaches: {}", e),
+        }
+    }
+    Ok(breached)
+}
+
+/// Marks the breaches not in `known` as new and adds them to it. The keys are
+/// opaque, so callers can store them hashed.
+pub fn mark_new(accounts: &mut [BreachedAccount], known: &mut HashSet<String>) -> usize {
+    let mut found = 0;
+    for account in accounts {
+        for breach in &mut account.breaches {
+            breach.new = known.insert(breach_key(&account.email, &breach.name));
+            found += usize::from(breach.new);
+        }
+    }
+    found
+}
+
+pub fn breach_key(email: &str, breach: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(format!("{}\n{}", email, breach)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubAccounts;
+
+    impl AccountChecker for StubAccounts {
+        fn breaches<'a>(&'a self, email: &'a str) -> AccountFuture<'a> {
+            Box::pin(async move {
+                Ok(match email {
+                    "octo@example.com" => vec![AccountBreach {
+                        name: "Adobe".to_string(),
+                        title: "Adobe".to_string(),
+                        domain: "adobe.com".to_string(),
+                        breach_date: "2013-10-04".to_string(),
+                        new: false,
+                    }],
+                    _ => Vec::new(),
+                })
+            })
+        }
+    }
+
+    fn entry(id: &str, username: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: username.to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_hibp_breaches() {
+        let body = br#"[{"Name": "Adobe", "Title": "Adobe", "Domain": "adobe.com",
+            "BreachDate": "2013-10-04", "PwnCount": 152445165, "IsVerified": true}]"#;
+        let breaches: Vec<HibpBreach> = serde_json::from_slice(body).unwrap();
+        let breach = AccountBreach::from(breaches.into_iter().next().unwrap());
+        assert_eq!(breach.name, "Adobe");
+        assert_eq!(breach.breach_date, "2013-10-04");
+    }
+
+    #[tokio::test]
+    async fn breached_addresses_are_grouped_and_new_only_once() {
+        let entries = vec![
+            entry("a", "Octo@Example.com"),
+            entry("b", "octo@example.com "),
+            entry("c", "octo"),
+            entry("d", "clean@example.org"),
+        ];
+        assert_eq!(monitored_emails(&entries).len(), 2);
+
+        let mut accounts = check_accounts(&entries, &StubAccounts).await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].email, "octo@example.com");
+        assert_eq!(accounts[0].entry_ids, vec!["a", "b"]);
+
+        let mut known = HashSet::new();
+        assert_eq!(mark_new(&mut accounts, &mut known), 1);
+        assert!(accounts[0].breaches[0].new);
+        assert_eq!(mark_new(&mut accounts, &mut known), 0);
+        assert!(!accounts[0].breaches[0].new);
+    }
+}
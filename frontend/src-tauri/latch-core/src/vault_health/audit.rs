@@ -1,11 +1,16 @@
+use crate::password_generator::PasswordContext;
 use crate::vault::Entry;
 use serde::{Deserialize, Serialize};
+use specta::Type;
 use std::cmp::Reverse;
 use std::collections::HashMap;
 
 use super::breach_checker::BreachChecker;
+use super::canary::LeakedCanary;
+use super::expiry::ExpiredCredential;
+use super::url_secrets::UrlSecret;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct WeakPassword {
     pub entry_id: String,
     pub title: String,
@@ -15,21 +20,21 @@ pub struct WeakPassword {
     pub label: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct ReusedPassword {
     pub password: String,
     pub entries: Vec<ReusedEntry>,
     pub count: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct ReusedEntry {
     pub entry_id: String,
     pub title: String,
     pub username: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct BreachedCredential {
     pub entry_id: String,
     pub title: String,
@@ -37,22 +42,43 @@ pub struct BreachedCredential {
     pub breach_count: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Type)]
 pub struct VaultHealthReport {
     pub overall_score: u8,
     pub weak_passwords: Vec<WeakPassword>,
     pub reused_passwords: Vec<ReusedPassword>,
     pub breached_credentials: Vec<BreachedCredential>,
+    /// Canaries found in breach data, each naming the service that leaked.
+    pub leaked_canaries: Vec<LeakedCanary>,
+    /// Credentials whose URL carries a password or token.
+    pub url_secrets: Vec<UrlSecret>,
+    /// Credentials past their password expiry and not snoozed.
+    pub expired_credentials: Vec<ExpiredCredential>,
+    /// Addresses used as usernames that appear in breaches; filled in by the app
+    /// when account monitoring is on (see `accounts`).
+    pub breached_accounts: Vec<super::accounts::BreachedAccount>,
     pub total_entries: usize,
     pub strong_passwords: usize,
     pub average_entropy: f64,
 }
 
-pub fn check_weak_passwords(entries: &[Entry]) -> Vec<WeakPassword> {
+fn context_of(entry: &Entry) -> PasswordContext {
+    PasswordContext {
+        title: entry.title.clone(),
+        username: entry.username.clone(),
+        url: entry.url.clone(),
+    }
+}
+
+pub fn check_weak_passwords(entries: &[Entry], dictionary: &[String]) -> Vec<WeakPassword> {
     let mut weak_passwords = Vec::new();
 
     for entry in entries {
-        let report = crate::password_generator::analyze_password_strength(&entry.password);
+        let report = crate::password_generator::analyze_password_strength_in_context(
+            &entry.password,
+            dictionary,
+            &context_of(entry),
+        );
 
         if report.score < 3 {
             weak_passwords.push(WeakPassword {
@@ -77,7 +103,9 @@ pub fn check_weak_passwords(entries: &[Entry]) -> Vec<WeakPassword> {
 pub fn check_reused_passwords(entries: &[Entry]) -> Vec<ReusedPassword> {
     let mut password_map: HashMap<String, Vec<ReusedEntry>> = HashMap::new();
 
-    for entry in entries {
+    // Linked Credentials share a password on purpose; the one they link to stands
+    // for them all.
+    for entry in entries.iter().filter(|e| e.linked_to.is_none()) {
         password_map
             .entry(entry.password.clone())
             .or_default()
@@ -154,8 +182,17 @@ pub fn calculate_vault_health_score(
 pub async fn check_vault_health(
     entries: &[Entry],
     checker: &dyn BreachChecker,
+    dictionary: &[String],
 ) -> VaultHealthReport {
-    let weak_passwords = check_weak_passwords(entries);
+    // Canaries are bait, not the user's logins; they never count against the score.
+    let (canaries, entries): (Vec<Entry>, Vec<Entry>) =
+        entries.iter().cloned().partition(Entry::is_canary);
+    let entries = entries.as_slice();
+    let leaked_canaries = super::canary::check_canaries(&canaries, checker).await;
+    let url_secrets = super::url_secrets::check_url_secrets(entries);
+    let expired_credentials = super::expiry::check_expired(entries, crate::vault::unix_now());
+
+    let weak_passwords = check_weak_passwords(entries, dictionary);
     let reused_passwords = check_reused_passwords(entries);
     let breached_credentials = check_breach_status(entries, checker).await;
 
@@ -172,7 +209,14 @@ pub async fn check_vault_health(
 
     let total_entropy: f64 = entries
         .iter()
-        .map(|e| crate::password_generator::analyze_password_strength(&e.password).entropy)
+        .map(|e| {
+            crate::password_generator::analyze_password_strength_in_context(
+                &e.password,
+                dictionary,
+                &context_of(e),
+            )
+            .entropy
+        })
         .sum();
 
     let average_entropy = if entries.is_empty() {
@@ -186,6 +230,10 @@ pub async fn check_vault_health(
         weak_passwords,
         reused_passwords,
         breached_credentials,
+        leaked_canaries,
+        url_secrets,
+        expired_credentials,
+        breached_accounts: Vec::new(),
         total_entries: entries.len(),
         strong_passwords,
         average_entropy,
@@ -203,8 +251,7 @@ mod tests {
             title: title.to_string(),
             username: username.to_string(),
             password: password.to_string(),
-            url: None,
-            icon_url: None,
+            ..Default::default()
         }
     }
 
@@ -216,12 +263,30 @@ mod tests {
             create_test_entry("3", "Test3", "user3", "Tr0ub4dor&3!p@ss"),
         ];
 
-        let weak_passwords = check_weak_passwords(&entries);
+        let weak_passwords = check_weak_passwords(&entries, &[]);
 
         assert_eq!(weak_passwords.len(), 2);
         assert!(weak_passwords.iter().all(|wp| wp.score < 3));
     }
 
+    #[test]
+    fn test_check_weak_passwords_flags_dictionary_words() {
+        let entries = vec![create_test_entry("1", "Work", "me", "Zq9!Globex#2024x")];
+        assert!(check_weak_passwords(&entries, &[]).is_empty());
+
+        let weak = check_weak_passwords(&entries, &["globex".to_string()]);
+        assert_eq!(weak.len(), 1);
+    }
+
+    #[test]
+    fn test_check_weak_passwords_uses_entry_context() {
+        let mut entry = create_test_entry("1", "Facebook", "john", "facebook-john-2024");
+        entry.url = Some("https://facebook.com".to_string());
+        let weak = check_weak_passwords(&[entry], &[]);
+        assert_eq!(weak.len(), 1);
+        assert!(weak[0].score <= 1);
+    }
+
     #[test]
     fn test_check_reused_passwords() {
         let entries = vec![
@@ -260,7 +325,7 @@ mod tests {
             create_test_entry("3", "Test3", "user3", "Tr0ub4dor&3!p@ss"),
         ];
 
-        let report = check_vault_health(&entries, &checker).await;
+        let report = check_vault_health(&entries, &checker, &[]).await;
 
         assert_eq!(report.total_entries, 3);
         assert!(!report.weak_passwords.is_empty());
@@ -268,6 +333,25 @@ mod tests {
         assert!(report.overall_score < 100);
     }
 
+    #[tokio::test]
+    async fn test_canaries_are_reported_apart_from_logins() {
+        let canary = crate::vault_health::canary::generate("Forum", None).unwrap();
+        let checker = StubBreachChecker {
+            results: vec![(canary.password.clone(), 7)],
+        };
+        let entries = vec![
+            create_test_entry("1", "Test1", "user1", "Tr0ub4dor&3!p@ss"),
+            canary,
+        ];
+
+        let report = check_vault_health(&entries, &checker, &[]).await;
+
+        assert_eq!(report.total_entries, 1);
+        assert!(report.breached_credentials.is_empty());
+        assert_eq!(report.leaked_canaries.len(), 1);
+        assert_eq!(report.leaked_canaries[0].service, "Forum");
+    }
+
     #[tokio::test]
     async fn test_stub_breach_checker_detects_breach() {
         let checker = StubBreachChecker {
@@ -278,8 +362,7 @@ mod tests {
             title: "Test".into(),
             username: "user".into(),
             password: "password123".into(),
-            url: None,
-            icon_url: None,
+            ..Default::default()
         }];
         let breached = check_breach_status(&entries, &checker).await;
         assert_eq!(breached.len(), 1);
@@ -294,8 +377,7 @@ mod tests {
             title: "Safe".into(),
             username: "user".into(),
             password: "Str0ng!P@ss".into(),
-            url: None,
-            icon_url: None,
+            ..Default::default()
         }];
         let breached = check_breach_status(&entries, &checker).await;
         assert_eq!(breached.len(), 0);
@@ -0,0 +1,140 @@
+//! Canary Credentials: a made-up email and password registered with one service
+//! and used nowhere else. The password is random and unique, so finding it in
+//! breach data means that service's database leaked.
+
+use super::breach_checker::BreachChecker;
+use crate::password_generator::{generate_password, PasswordOptions};
+use crate::vault::{Entry, EntryKind};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::cmp::Reverse;
+
+/// Used when no domain is given; pass one with a catch-all inbox to receive
+/// the service's sign-up mail.
+pub const DEFAULT_EMAIL_DOMAIN: &str = "example.com";
+const CANARY_PASSWORD_LEN: u32 = 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct LeakedCanary {
+    pub entry_id: String,
+    pub service: String,
+    pub email: String,
+    pub breach_count: u32,
+}
+
+fn slug(service: &str) -> String {
+    let slug: String = service
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .take(16)
+        .collect();
+    if slug.is_empty() {
+        "canary".to_string()
+    } else {
+        slug
+    }
+}
+
+fn valid_domain(domain: &str) -> bool {
+    domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// A fresh canary for `service`, ready to be saved and registered there.
+pub fn generate(service: &str, email_domain: Option<&str>) -> Result<Entry, String> {
+    let service = service.trim();
+    if service.is_empty() {
+        return Err("Invalid canary: name the service it is for".to_string());
+    }
+    let domain = email_domain
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .unwrap_or(DEFAULT_EMAIL_DOMAIN)
+        .to_lowercase();
+    if !valid_domain(&domain) {
+        return Err(format!("Invalid email domain: {}", domain));
+    }
+
+    let tag = Alphanumeric
+        .sample_string(&mut rand::thread_rng(), 8)
+        .to_lowercase();
+    let password = generate_password(&PasswordOptions {
+        length: CANARY_PASSWORD_LEN,
+        ..PasswordOptions::default()
+    })?;
+    Ok(Entry {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: format!("Canary: {}", service),
+        username: format!("{}.{}@{}", slug(service), tag, domain),
+        password,
+        kind: EntryKind::Canary {
+            service: service.to_string(),
+        },
+        ..Default::default()
+    })
+}
+
+/// Canaries whose password shows up in breach data, worst first.
+pub async fn check_canaries(entries: &[Entry], checker: &dyn BreachChecker) -> Vec<LeakedCanary> {
+    let mut leaked = Vec::new();
+
+    for entry in entries {
+        let EntryKind::Canary { service } = &entry.kind else {
+            continue;
+        };
+        if let Some(breach_data) = checker.check(&entry.password).await {
+            if breach_data.count > 0 {
+                leaked.push(LeakedCanary {
+                    entry_id: entry.id.clone(),
+                    service: service.clone(),
+                    email: entry.username.clone(),
+                    breach_count: breach_data.count,
+                });
+            }
+        }
+    }
+
+    leaked.sort_by_key(|canary| Reverse(canary.breach_count));
+    leaked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault_health::breach_checker::StubBreachChecker;
+
+    #[test]
+    fn each_canary_is_unique_to_its_service() {
+        let first = generate("Acme Shop", None).unwrap();
+        let second = generate("Acme Shop", Some("mail.me.org")).unwrap();
+
+        assert!(first.username.starts_with("acmeshop."));
+        assert!(first.username.ends_with("@example.com"));
+        assert!(second.username.ends_with("@mail.me.org"));
+        assert_ne!(first.username, second.username);
+        assert_ne!(first.password, second.password);
+        assert!(generate(" ", None).is_err());
+        assert!(generate("Acme", Some("not a domain")).is_err());
+    }
+
+    #[tokio::test]
+    async fn leaked_canary_names_the_service() {
+        let canary = generate("Forum", None).unwrap();
+        let checker = StubBreachChecker {
+            results: vec![(canary.password.clone(), 3)],
+        };
+        let quiet = generate("Bank", None).unwrap();
+
+        let leaked = check_canaries(&[canary.clone(), quiet], &checker).await;
+
+        assert_eq!(leaked.len(), 1);
+        assert_eq!(leaked[0].service, "Forum");
+        assert_eq!(leaked[0].email, canary.username);
+    }
+}
@@ -0,0 +1,143 @@
+//! Password expiry: a Credential can ask for its password to be changed every so
+//! many days. Overdue ones show in the health report and raise a reminder while
+//! the Vault is unlocked, until the password changes or the reminder is snoozed.
+
+use crate::vault::Entry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::cmp::Reverse;
+
+pub const MAX_EXPIRY_DAYS: u32 = 3650;
+pub const MAX_SNOOZE_DAYS: u32 = 365;
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct PasswordExpiry {
+    pub interval_days: u32,
+    /// Unix time the password last changed, or was marked as rotated.
+    pub rotated_at: u64,
+    /// No reminders until this Unix time.
+    #[serde(default)]
+    pub snoozed_until: Option<u64>,
+}
+
+impl PasswordExpiry {
+    pub fn new(interval_days: u32, rotated_at: u64) -> Result<Self, String> {
+        check_days(interval_days, MAX_EXPIRY_DAYS, "expiry interval")?;
+        Ok(Self {
+            interval_days,
+            rotated_at,
+            snoozed_until: None,
+        })
+    }
+
+    /// Due at `expires_at`, as if the secret was issued whole days before it, so
+    /// a replacement gets the same lifetime.
+    pub fn until(expires_at: u64, now: u64) -> Result<Self, String> {
+        if expires_at <= now {
+            return Err("Invalid expiry date: it has already passed".to_string());
+        }
+        let days = (expires_at - now).div_ceil(DAY_SECS);
+        let interval_days = u32::try_from(days).unwrap_or(u32::MAX);
+        check_days(interval_days, MAX_EXPIRY_DAYS, "expiry date")?;
+        Self::new(interval_days, expires_at - days * DAY_SECS)
+    }
+
+    pub fn due_at(&self) -> u64 {
+        self.rotated_at + u64::from(self.interval_days) * DAY_SECS
+    }
+
+    /// Overdue and not snoozed.
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.due_at() && self.snoozed_until.map_or(true, |until| now >= until)
+    }
+
+    pub fn rotated(self, now: u64) -> Self {
+        Self {
+            rotated_at: now,
+            snoozed_until: None,
+            ..self
+        }
+    }
+
+    pub fn snoozed(self, days: u32, now: u64) -> Result<Self, String> {
+        check_days(days, MAX_SNOOZE_DAYS, "snooze")?;
+        Ok(Self {
+            snoozed_until: Some(now + u64::from(days) * DAY_SECS),
+            ..self
+        })
+    }
+}
+
+fn check_days(days: u32, max: u32, what: &str) -> Result<(), String> {
+    if days == 0 || days > max {
+        return Err(format!("Invalid {}: use 1 to {} days", what, max));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ExpiredCredential {
+    pub entry_id: String,
+    pub title: String,
+    pub username: String,
+    pub due_at: u64,
+    pub days_overdue: u64,
+}
+
+/// The Credentials due for a new password, most overdue first.
+pub fn check_expired(entries: &[Entry], now: u64) -> Vec<ExpiredCredential> {
+    let mut expired: Vec<ExpiredCredential> = entries
+        .iter()
+        .filter_map(|entry| {
+            let expiry = entry.expiry.filter(|e| e.is_due(now))?;
+            Some(ExpiredCredential {
+                entry_id: entry.id.clone(),
+                title: entry.title.clone(),
+                username: entry.username.clone(),
+                due_at: expiry.due_at(),
+                days_overdue: (now - expiry.due_at()) / DAY_SECS,
+            })
+        })
+        .collect();
+    expired.sort_by_key(|e| Reverse(e.days_overdue));
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, expiry: Option<PasswordExpiry>) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            expiry,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn overdue_passwords_are_flagged_until_snoozed_or_rotated() {
+        let now = 100 * DAY_SECS;
+        let monthly = PasswordExpiry::new(30, 0).unwrap();
+        let entries = vec![
+            entry("overdue", Some(monthly)),
+            entry("fresh", Some(monthly.rotated(now - DAY_SECS))),
+            entry("snoozed", Some(monthly.snoozed(7, now).unwrap())),
+            entry("never", None),
+        ];
+
+        let expired = check_expired(&entries, now);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].entry_id, "overdue");
+        assert_eq!(expired[0].days_overdue, 70);
+
+        let snoozed = entries[2].expiry.unwrap();
+        assert!(snoozed.is_due(now + 7 * DAY_SECS));
+        assert!(PasswordExpiry::new(0, now).is_err());
+        assert!(monthly.snoozed(MAX_SNOOZE_DAYS + 1, now).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod accounts;
+pub mod audit;
+pub mod breach_checker;
+pub mod canary;
+pub mod expiry;
+pub mod offline;
+pub mod url_secrets;
+pub mod watch;
@@ -0,0 +1,366 @@
+//! Breach checks against a local copy of Have I Been Pwned's Pwned Passwords, for
+//! users who won't send even a five-character hash prefix over the network. The
+//! copy is the ordered-by-hash text format, one `SHA1:count` line per hash, as
+//! HIBP's own downloader writes it; `Download` builds one from the range API, a
+//! range at a time, and can pick up where an interrupted download stopped.
+
+use super::breach_checker::{BreachChecker, BreachResult};
+use serde::Serialize;
+use specta::Type;
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+pub const DATASET_FILE: &str = "pwned-passwords.txt";
+/// One per five-hex-digit hash prefix.
+pub const RANGES: u32 = 1 << 20;
+/// Ranges written between checkpoints of a download.
+pub const CHECKPOINT_EVERY: u32 = 256;
+
+pub const DATASET_MISSING: &str =
+    "Offline breach database not found; download it in Settings or turn offline checks off";
+
+pub fn dataset_path() -> Result<PathBuf, String> {
+    Ok(crate::vault::storage::data_dir()?
+        .join("breach")
+        .join(DATASET_FILE))
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension("partial")
+}
+
+fn checkpoint_path(path: &Path) -> PathBuf {
+    path.with_extension("checkpoint")
+}
+
+/// How far along the local copy is.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct DatasetStatus {
+    /// Complete and used for checks when offline checks are on.
+    pub ready: bool,
+    pub bytes: u64,
+    pub ranges_done: u32,
+    pub ranges_total: u32,
+}
+
+pub fn status(path: &Path) -> DatasetStatus {
+    if let Ok(meta) = fs::metadata(path) {
+        return DatasetStatus {
+            ready: true,
+            bytes: meta.len(),
+            ranges_done: RANGES,
+            ranges_total: RANGES,
+        };
+    }
+    let (ranges_done, bytes) = read_checkpoint(path).unwrap_or((0, 0));
+    DatasetStatus {
+        ready: false,
+        bytes,
+        ranges_done,
+        ranges_total: RANGES,
+    }
+}
+
+/// Removes the copy and any unfinished download.
+pub fn remove(path: &Path) -> Result<(), String> {
+    for file in [
+        path.to_path_buf(),
+        partial_path(path),
+        checkpoint_path(path),
+    ] {
+        match fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to remove breach database: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+fn read_checkpoint(path: &Path) -> Option<(u32, u64)> {
+    let checkpoint = fs::read_to_string(checkpoint_path(path)).ok()?;
+    let (next, len) = checkpoint.trim().split_once(':')?;
+    Some((next.parse().ok()?, len.parse().ok()?))
+}
+
+pub fn prefix_hex(prefix: u32) -> String {
+    format!("{:05X}", prefix)
+}
+
+/// Fetches ranges from the API for a `Download`. Cheap to clone, so ranges can be
+/// fetched side by side.
+#[derive(Clone)]
+pub struct RangeClient(reqwest::Client);
+
+impl RangeClient {
+    pub fn new() -> Result<Self, String> {
+        reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map(Self)
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))
+    }
+
+    /// One range, as `SUFFIX:count` lines.
+    pub async fn fetch(&self, prefix: u32) -> Result<String, String> {
+        let response = self
+            .0
+            .get(format!(
+                "https://api.pwnedpasswords.com/range/{}",
+                prefix_hex(prefix)
+            ))
+            .header("User-Agent", "Latch-Password-Manager")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to download breach range: {}", e))?;
+        response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to download breach range: {}", e))
+    }
+}
+
+/// A download in progress, written to a partial file next to the copy.
+pub struct Download {
+    path: PathBuf,
+    file: BufWriter<File>,
+    next: u32,
+    len: u64,
+}
+
+impl Download {
+    /// Carries on from the last checkpoint, dropping whatever was written after it.
+    pub fn resume(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create breach database folder: {}", e))?;
+        }
+        let (next, len) = read_checkpoint(path).unwrap_or((0, 0));
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(partial_path(path))
+            .map_err(|e| format!("Failed to open breach database: {}", e))?;
+        file.set_len(len)
+            .and_then(|()| (&file).seek(SeekFrom::End(0)).map(|_| ()))
+            .map_err(|e| format!("Failed to open breach database: {}", e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: BufWriter::new(file),
+            next,
+            len,
+        })
+    }
+
+    /// The next range to append; `RANGES` once all are in.
+    pub fn next_range(&self) -> u32 {
+        self.next
+    }
+
+    /// Appends the next range's hashes in full, and checkpoints now and then.
+    pub fn append(&mut self, prefix: u32, body: &str) -> Result<(), String> {
+        if prefix != self.next {
+            return Err(format!(
+                "Breach range {} arrived out of order",
+                prefix_hex(prefix)
+            ));
+        }
+        let hex = prefix_hex(prefix);
+        for line in body.lines() {
+            let Some((suffix, count)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let line = format!("{}{}:{}\n", hex, suffix.to_ascii_uppercase(), count);
+            self.file
+                .write_all(line.as_bytes())
+                .map_err(|e| format!("Failed to write breach database: {}", e))?;
+            self.len += line.len() as u64;
+        }
+        self.next += 1;
+        if self.next % CHECKPOINT_EVERY == 0 {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> Result<(), String> {
+        self.file
+            .flush()
+            .and_then(|()| self.file.get_ref().sync_data())
+            .map_err(|e| format!("Failed to write breach database: {}", e))?;
+        fs::write(
+            checkpoint_path(&self.path),
+            format!("{}:{}", self.next, self.len),
+        )
+        .map_err(|e| format!("Failed to write breach database: {}", e))
+    }
+
+    pub fn status(&self) -> DatasetStatus {
+        DatasetStatus {
+            ready: false,
+            bytes: self.len,
+            ranges_done: self.next,
+            ranges_total: RANGES,
+        }
+    }
+
+    /// Puts the finished copy in place.
+    pub fn finish(mut self) -> Result<(), String> {
+        if self.next != RANGES {
+            return Err("The breach database download isn't finished".to_string());
+        }
+        self.checkpoint()?;
+        fs::rename(partial_path(&self.path), &self.path)
+            .map_err(|e| format!("Failed to rename breach database: {}", e))?;
+        let _ = fs::remove_file(checkpoint_path(&self.path));
+        Ok(())
+    }
+}
+
+/// Looks hashes up in the local copy by binary search over its sorted lines.
+pub struct OfflinePwnedPasswords {
+    path: PathBuf,
+}
+
+impl OfflinePwnedPasswords {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if !path.is_file() {
+            return Err(DATASET_MISSING.to_string());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// How often the SHA-1 `hash` (40 hex digits, uppercase) was seen in breaches.
+    pub fn count(&self, hash: &str) -> Result<Option<u32>, String> {
+        let file =
+            File::open(&self.path).map_err(|e| format!("Failed to open breach database: {}", e))?;
+        let mut reader = BufReader::new(file);
+        let (mut lo, mut hi) = (
+            0u64,
+            reader
+                .get_ref()
+                .metadata()
+                .map_err(|e| format!("Failed to read breach database: {}", e))?
+                .len(),
+        );
+        let mut line = String::new();
+        // Every line starting in `lo..hi` is still a candidate.
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            // The first line starting at or after `mid`.
+            let start = if mid == 0 {
+                reader.seek(SeekFrom::Start(0)).map(|_| 0)
+            } else {
+                reader.seek(SeekFrom::Start(mid - 1)).and_then(|_| {
+                    let mut skipped = Vec::new();
+                    reader
+                        .read_until(b'\n', &mut skipped)
+                        .map(|n| mid - 1 + n as u64)
+                })
+            }
+            .map_err(|e| format!("Failed to read breach database: {}", e))?;
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+            line.clear();
+            let read = reader
+                .by_ref()
+                .take(128)
+                .read_line(&mut line)
+                .map_err(|e| format!("Failed to read breach database: {}", e))?;
+            let (found, count) = line.trim_end().split_once(':').unwrap_or((&line, ""));
+            match found.cmp(hash) {
+                std::cmp::Ordering::Equal => return Ok(Some(count.trim().parse().unwrap_or(0))),
+                std::cmp::Ordering::Less => lo = start + read as u64,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl BreachChecker for OfflinePwnedPasswords {
+    fn check(
+        &self,
+        password: &str,
+    ) -> Pin<Box<dyn Future<Output = Option<BreachResult>> + Send + '_>> {
+        use sha1::{Digest, Sha1};
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        Box::pin(async move {
+            let count = self.count(&hash).ok()??;
+            Some(BreachResult {
+                hash_suffix: hash[5..].to_string(),
+                count,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+
+    fn hash(password: &str) -> String {
+        hex::encode_upper(Sha1::digest(password.as_bytes()))
+    }
+
+    #[tokio::test]
+    async fn a_resumed_download_is_searched_without_the_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATASET_FILE);
+        let breached = ["password", "123456", "hunter2", "letmein"];
+        let mut hashes: Vec<String> = breached.iter().map(|p| hash(p)).collect();
+        hashes.sort();
+
+        // Ranges as the API serves them: suffixes only, CRLF line ends.
+        let range = |prefix: u32| -> String {
+            let hex = prefix_hex(prefix);
+            hashes
+                .iter()
+                .filter(|h| h.starts_with(&hex))
+                .map(|h| format!("{}:{}\r\n", &h[5..], h.len()))
+                .collect()
+        };
+        let mut download = Download::resume(&path).unwrap();
+        for prefix in 0..CHECKPOINT_EVERY + 3 {
+            download.append(prefix, &range(prefix)).unwrap();
+        }
+        assert!(download.append(0, "").is_err());
+        drop(download);
+        assert_eq!(status(&path).ranges_done, CHECKPOINT_EVERY);
+        assert!(OfflinePwnedPasswords::open(&path).is_err());
+
+        // Interrupted past the checkpoint: those ranges are fetched again.
+        let mut download = Download::resume(&path).unwrap();
+        assert_eq!(download.next_range(), CHECKPOINT_EVERY);
+        while download.next_range() < RANGES {
+            let prefix = download.next_range();
+            download.append(prefix, &range(prefix)).unwrap();
+        }
+        download.finish().unwrap();
+        assert!(status(&path).ready);
+
+        let offline = OfflinePwnedPasswords::open(&path).unwrap();
+        for password in breached {
+            assert_eq!(offline.check(password).await.unwrap().count, 40);
+        }
+        assert!(offline
+            .check("correct horse battery staple")
+            .await
+            .is_none());
+        assert_eq!(offline.count(&"0".repeat(40)).unwrap(), None);
+        assert_eq!(offline.count(&"F".repeat(40)).unwrap(), None);
+
+        remove(&path).unwrap();
+        assert!(!status(&path).ready);
+    }
+}
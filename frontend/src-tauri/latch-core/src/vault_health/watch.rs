@@ -0,0 +1,108 @@
+//! Breach watch: Credentials the user marked as high-value are checked against
+//! breach data on a timer while the Vault is unlocked, instead of waiting for the
+//! next manual health check.
+
+use super::breach_checker::BreachChecker;
+use crate::vault::Entry;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashSet;
+
+/// How often watched Credentials are checked while the Vault is unlocked.
+pub const WATCH_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Where a breach alert for a watched Credential goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertChannel {
+    /// A desktop notification.
+    Notification,
+    /// A count on the tray icon.
+    TrayBadge,
+    /// The maintenance hooks registered for breach alerts.
+    Hook,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct BreachAlert {
+    pub entry_id: String,
+    pub title: String,
+    pub breach_count: u32,
+    pub channel: AlertChannel,
+}
+
+/// Checks the watched Credentials and returns an alert for each one found in breach
+/// data, skipping those in `alerted` so a Session raises each alert once.
+pub async fn check_watched(
+    entries: &[Entry],
+    checker: &dyn BreachChecker,
+    alerted: &mut HashSet<String>,
+) -> Vec<BreachAlert> {
+    let mut alerts = Vec::new();
+    for entry in entries {
+        let Some(channel) = entry.breach_watch else {
+            continue;
+        };
+        if alerted.contains(&entry.id) || entry.password.is_empty() {
+            continue;
+        }
+        match checker.check(&entry.password).await {
+            Some(result) if result.count > 0 => {
+                alerted.insert(entry.id.clone());
+                alerts.push(BreachAlert {
+                    entry_id: entry.id.clone(),
+                    title: entry.title.clone(),
+                    breach_count: result.count,
+                    channel,
+                });
+            }
+            _ => {}
+        }
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault_health::breach_checker::StubBreachChecker;
+
+    fn entry(id: &str, password: &str, breach_watch: Option<AlertChannel>) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            breach_watch,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn alerts_once_per_watched_breach() {
+        let checker = StubBreachChecker {
+            results: vec![("leaked".to_string(), 12)],
+        };
+        let entries = [
+            entry("bank", "leaked", Some(AlertChannel::TrayBadge)),
+            entry("forum", "leaked", None),
+            entry("mail", "fine", Some(AlertChannel::Notification)),
+        ];
+        let mut alerted = HashSet::new();
+
+        let alerts = check_watched(&entries, &checker, &mut alerted).await;
+        assert_eq!(
+            alerts,
+            [BreachAlert {
+                entry_id: "bank".to_string(),
+                title: "bank".to_string(),
+                breach_count: 12,
+                channel: AlertChannel::TrayBadge,
+            }]
+        );
+
+        assert!(check_watched(&entries, &checker, &mut alerted)
+            .await
+            .is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use crate::crypto::aead::{self, EncryptedData};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use specta::Type;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+const SEAL_INFO: &[u8] = b"latch-audit-v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Unlock,
+    FailedUnlock,
+    Reveal,
+    Export,
+    SettingsChanged,
+    /// A full backup taken before a destructive command.
+    SafetyBackup,
+    /// The Vault's credential re-entered for a sensitive action.
+    Reauthenticated,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct AuditEvent {
+    pub at: u64,
+    pub kind: AuditKind,
+    pub detail: Option<String>,
+}
+
+/// One line of the log: an event sealed to the audit public key.
+#[derive(Serialize, Deserialize)]
+struct SealedEvent {
+    ephemeral: String,
+    data: EncryptedData,
+}
+
+/// The audit keypair. The public half is stored in the clear so events can be
+/// appended while the Vault is locked; the secret half is encrypted under the Vault key.
+#[derive(Serialize, Deserialize)]
+struct AuditKeyFile {
+    public: String,
+    secret: EncryptedData,
+}
+
+/// Append-only, encrypted record of security-relevant events. Anyone can append,
+/// but only a real Session can read it back.
+pub struct AuditLog {
+    pub path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new() -> Result<Self, String> {
+        let config_dir = crate::vault::storage::config_dir()?;
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        Ok(Self {
+            path: config_dir.join("audit.log"),
+            key_path: config_dir.join("audit.key"),
+        })
+    }
+
+    /// Records an event. Failures are logged and never surface to the caller, so
+    /// auditing can't block an unlock or a save.
+    pub fn record(&self, kind: AuditKind, detail: Option<String>) {
+        let event = AuditEvent {
+            at: crate::vault::unix_now(),
+            kind,
+            detail,
+        };
+        if let Err(e) = self.append(&event) {
+            eprintln!("Failed to write audit log: {}", e);
+        }
+    }
+
+    /// Makes sure a keypair readable with `vault_key` exists. If the current one was
+    /// made for a different Vault (it was re-provisioned), a fresh log is started.
+    pub fn ensure_key(&self, vault_key: &[u8; 32]) -> Result<(), String> {
+        if let Some(file) = self.read_key_file()? {
+            if aead::decrypt(vault_key, &file.secret).is_ok() {
+                return Ok(());
+            }
+        }
+
+        let secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
+        let public = PublicKey::from(&secret);
+        let secret_hex = Zeroizing::new(hex::encode(secret.to_bytes()));
+        let file = AuditKeyFile {
+            public: hex::encode(public.as_bytes()),
+            secret: aead::encrypt(vault_key, &secret_hex)?,
+        };
+        let json = serde_json::to_string(&file)
+            .map_err(|e| format!("Failed to serialize audit key: {}", e))?;
+        write_atomic(&self.key_path, &json)?;
+        if self.path.exists() {
+            fs::remove_file(&self.path).map_err(|e| format!("Failed to reset audit log: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Re-encrypts the audit secret after the Vault key changes.
+    pub fn rekey(&self, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<(), String> {
+        let Some(mut file) = self.read_key_file()? else {
+            return self.ensure_key(new_key);
+        };
+        let secret_hex = Zeroizing::new(aead::decrypt(old_key, &file.secret)?);
+        file.secret = aead::encrypt(new_key, &secret_hex)?;
+        let json = serde_json::to_string(&file)
+            .map_err(|e| format!("Failed to serialize audit key: {}", e))?;
+        write_atomic(&self.key_path, &json)
+    }
+
+    /// Decrypts the whole log. Returns the events in order and the number of lines
+    /// that could not be read (corrupted or written under another key).
+    pub fn read(&self, vault_key: &[u8; 32]) -> Result<(Vec<AuditEvent>, usize), String> {
+        let Some(file) = self.read_key_file()? else {
+            return Ok((Vec::new(), 0));
+        };
+        let secret_hex = Zeroizing::new(
+            aead::decrypt(vault_key, &file.secret).map_err(|_| "Audit log is unavailable")?,
+        );
+        let secret_bytes: [u8; 32] = hex::decode(secret_hex.as_str())
+            .map_err(|_| "Invalid audit key")?
+            .try_into()
+            .map_err(|_| "Invalid audit key")?;
+        let secret = StaticSecret::from(secret_bytes);
+
+        if !self.path.exists() {
+            return Ok((Vec::new(), 0));
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read audit log: {}", e))?;
+
+        let mut events = Vec::new();
+        let mut unreadable = 0;
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            match open(&secret, line) {
+                Ok(event) => events.push(event),
+                Err(_) => unreadable += 1,
+            }
+        }
+        Ok((events, unreadable))
+    }
+
+    fn append(&self, event: &AuditEvent) -> Result<(), String> {
+        let Some(file) = self.read_key_file()? else {
+            // No Session has been opened since auditing was added; nothing to seal to yet.
+            return Ok(());
+        };
+        let public_bytes: [u8; 32] = hex::decode(&file.public)
+            .map_err(|_| "Invalid audit key")?
+            .try_into()
+            .map_err(|_| "Invalid audit key")?;
+        let line = seal(&PublicKey::from(public_bytes), event)?;
+
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open audit log: {}", e))?;
+        writeln!(log, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))
+    }
+
+    fn read_key_file(&self) -> Result<Option<AuditKeyFile>, String> {
+        if !self.key_path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.key_path)
+            .map_err(|e| format!("Failed to read audit key: {}", e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse audit key: {}", e))
+    }
+}
+
+fn seal_key(shared: &[u8; 32], ephemeral: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>, String> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(ephemeral), shared)
+        .expand(SEAL_INFO, key.as_mut())
+        .map_err(|_| "Failed to derive audit key".to_string())?;
+    Ok(key)
+}
+
+fn seal(public: &PublicKey, event: &AuditEvent) -> Result<String, String> {
+    let ephemeral = StaticSecret::random_from_rng(rand::rngs::OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(public);
+    let key = seal_key(shared.as_bytes(), ephemeral_public.as_bytes())?;
+
+    let json = serde_json::to_string(event)
+        .map_err(|e| format!("Failed to serialize audit event: {}", e))?;
+    let sealed = SealedEvent {
+        ephemeral: hex::encode(ephemeral_public.as_bytes()),
+        data: aead::encrypt(&key, &json)?,
+    };
+    serde_json::to_string(&sealed).map_err(|e| format!("Failed to serialize audit event: {}", e))
+}
+
+fn open(secret: &StaticSecret, line: &str) -> Result<AuditEvent, String> {
+    let sealed: SealedEvent =
+        serde_json::from_str(line).map_err(|e| format!("Failed to parse audit event: {}", e))?;
+    let ephemeral: [u8; 32] = hex::decode(&sealed.ephemeral)
+        .map_err(|_| "Invalid audit event")?
+        .try_into()
+        .map_err(|_| "Invalid audit event")?;
+    let shared = secret.diffie_hellman(&PublicKey::from(ephemeral));
+    let key = seal_key(shared.as_bytes(), &ephemeral)?;
+    let json = aead::decrypt(&key, &sealed.data)?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse audit event: {}", e))
+}
+
+fn write_atomic(path: &PathBuf, json: &str) -> Result<(), String> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write audit key: {}", e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to rename audit key: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(dir: &tempfile::TempDir) -> AuditLog {
+        AuditLog {
+            path: dir.path().join("audit.log"),
+            key_path: dir.path().join("audit.key"),
+        }
+    }
+
+    #[test]
+    fn events_are_sealed_and_read_back_with_vault_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = log(&dir);
+        audit.ensure_key(&[1u8; 32]).unwrap();
+
+        audit.record(AuditKind::FailedUnlock, None);
+        audit.record(AuditKind::Reveal, Some("entry-1".to_string()));
+
+        let raw = fs::read_to_string(&audit.path).unwrap();
+        assert!(!raw.contains("entry-1"));
+
+        let (events, unreadable) = audit.read(&[1u8; 32]).unwrap();
+        assert_eq!(unreadable, 0);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, AuditKind::FailedUnlock);
+        assert_eq!(events[1].detail.as_deref(), Some("entry-1"));
+        assert!(audit.read(&[2u8; 32]).is_err());
+    }
+
+    #[test]
+    fn rekey_keeps_history_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = log(&dir);
+        audit.ensure_key(&[1u8; 32]).unwrap();
+        audit.record(AuditKind::Unlock, None);
+
+        audit.rekey(&[1u8; 32], &[2u8; 32]).unwrap();
+        audit.ensure_key(&[2u8; 32]).unwrap();
+
+        let (events, _) = audit.read(&[2u8; 32]).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn corrupted_lines_are_counted_not_fatal() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = log(&dir);
+        audit.ensure_key(&[1u8; 32]).unwrap();
+        audit.record(AuditKind::Export, None);
+        OpenOptions::new()
+            .append(true)
+            .open(&audit.path)
+            .unwrap()
+            .write_all(b"garbage\n")
+            .unwrap();
+
+        let (events, unreadable) = audit.read(&[1u8; 32]).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(unreadable, 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! Launching Latch at login, through the OS's own mechanism (a login item, the
+//! Run registry key, an XDG autostart entry).
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::{AutoLaunchManager, MacosLauncher};
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, None)
+}
+
+/// Registers or drops the login item to match the setting. Does nothing until
+/// the plugin is registered; startup syncs it then.
+pub fn sync(app: &AppHandle, enabled: bool) {
+    let Some(autolaunch) = app.try_state::<AutoLaunchManager>() else {
+        return;
+    };
+    let result = match autolaunch.is_enabled() {
+        Ok(registered) if registered == enabled => Ok(()),
+        Ok(_) if enabled => autolaunch.enable(),
+        Ok(_) => autolaunch.disable(),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to update launch at login: {}", e);
+    }
+}
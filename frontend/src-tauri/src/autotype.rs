@@ -0,0 +1,280 @@
+use crate::vault::Entry;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+pub const AUTOTYPE_SHORTCUT: &str = "Ctrl+Alt+A";
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+pub const MAX_SEQUENCE_LEN: usize = 256;
+const MAX_DELAY_MS: u64 = 10_000;
+/// Shorter titles would match far too many windows.
+const MIN_MATCH_LEN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Tab,
+    Enter,
+    Space,
+}
+
+/// One piece of a sequence such as `{USERNAME}{TAB}{PASSWORD}{ENTER}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Username,
+    Password,
+    Title,
+    Url,
+    Key(Key),
+    Delay(u64),
+    Literal(String),
+}
+
+pub enum Keystroke {
+    Text(Zeroizing<String>),
+    Key(Key),
+    Delay(Duration),
+}
+
+fn placeholder(name: &str) -> Result<Token, String> {
+    let upper = name.to_uppercase();
+    if let Some(ms) = upper.strip_prefix("DELAY ") {
+        let ms: u64 = ms
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid auto-type delay: {{{}}}", name))?;
+        if ms > MAX_DELAY_MS {
+            return Err(format!(
+                "Invalid auto-type delay: {{{}}} exceeds {} ms",
+                name, MAX_DELAY_MS
+            ));
+        }
+        return Ok(Token::Delay(ms));
+    }
+    Ok(match upper.as_str() {
+        "USERNAME" => Token::Username,
+        "PASSWORD" => Token::Password,
+        "TITLE" => Token::Title,
+        "URL" => Token::Url,
+        "TAB" => Token::Key(Key::Tab),
+        "ENTER" => Token::Key(Key::Enter),
+        "SPACE" => Token::Key(Key::Space),
+        // `{{}` and `{}}` type a literal brace.
+        "{" | "}" => Token::Literal(upper),
+        _ => return Err(format!("Unknown auto-type placeholder: {{{}}}", name)),
+    })
+}
+
+fn parse(sequence: &str) -> Result<Vec<Token>, String> {
+    if sequence.len() > MAX_SEQUENCE_LEN {
+        return Err(format!(
+            "Invalid auto-type sequence: longer than {} characters",
+            MAX_SEQUENCE_LEN
+        ));
+    }
+    let mut tokens = Vec::new();
+    let mut rest = sequence;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('{') {
+            // The closing brace may itself be the placeholder, as in `{}}`.
+            let end = after
+                .get(1..)
+                .and_then(|tail| tail.find('}'))
+                .map(|i| i + 1)
+                .ok_or("Invalid auto-type sequence: unclosed {")?;
+            tokens.push(placeholder(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('{').unwrap_or(rest.len());
+            tokens.push(Token::Literal(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    Ok(tokens)
+}
+
+pub fn validate(sequence: &str) -> Result<(), String> {
+    parse(sequence).map(|_| ())
+}
+
+/// What to type for `entry`, from its own sequence or the default one.
+pub fn keystrokes(entry: &Entry) -> Result<Vec<Keystroke>, String> {
+    let sequence = entry
+        .autotype_sequence
+        .as_deref()
+        .unwrap_or(DEFAULT_SEQUENCE);
+    let text = |s: &str| Keystroke::Text(Zeroizing::new(s.to_string()));
+    Ok(parse(sequence)?
+        .into_iter()
+        .map(|token| match token {
+            Token::Username => text(&entry.username),
+            Token::Password => text(&entry.password),
+            Token::Title => text(&entry.title),
+            Token::Url => text(entry.url.as_deref().unwrap_or("")),
+            Token::Key(key) => Keystroke::Key(key),
+            Token::Delay(ms) => Keystroke::Delay(Duration::from_millis(ms)),
+            Token::Literal(s) => text(&s),
+        })
+        .collect())
+}
+
+/// Names a window might show for `entry`: its title, and its site's host and
+/// bare name (`github.com`, `github`).
+fn match_names(entry: &Entry) -> Vec<String> {
+    let mut names = vec![entry.title.to_lowercase()];
+    if let Some(host) = entry
+        .url
+        .as_deref()
+        .and_then(|u| url::Url::parse(u).ok())
+        .and_then(|u| u.host_str().map(str::to_lowercase))
+    {
+        let host = host.trim_start_matches("www.").to_string();
+        if let Some(name) = host.split('.').next() {
+            names.push(name.to_string());
+        }
+        names.push(host);
+    }
+    names
+}
+
+/// The Credential whose name appears in the focused window's title. The longest
+/// match wins, so "GitHub Enterprise" beats "GitHub".
+pub fn best_match<'a>(entries: &'a [Entry], window_title: &str) -> Option<&'a Entry> {
+    let window_title = window_title.to_lowercase();
+    entries
+        .iter()
+        .filter(|entry| entry.is_login())
+        .filter_map(|entry| {
+            match_names(entry)
+                .into_iter()
+                .filter(|name| name.len() >= MIN_MATCH_LEN && window_title.contains(name.as_str()))
+                .map(|name| name.len())
+                .max()
+                .map(|score| (score, entry))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, entry)| entry)
+}
+
+/// The parts of the OS keyboard needed to type into another window.
+pub trait Typist {
+    fn text(&mut self, text: &str) -> Result<(), String>;
+    fn key(&mut self, key: Key) -> Result<(), String>;
+}
+
+pub fn type_out(typist: &mut impl Typist, keystrokes: &[Keystroke]) -> Result<(), String> {
+    for keystroke in keystrokes {
+        match keystroke {
+            Keystroke::Text(text) if text.is_empty() => {}
+            Keystroke::Text(text) => typist.text(text)?,
+            Keystroke::Key(key) => typist.key(*key)?,
+            Keystroke::Delay(delay) => std::thread::sleep(*delay),
+        }
+    }
+    Ok(())
+}
+
+/// Synthetic keystrokes through the OS input APIs.
+pub struct SystemTypist(enigo::Enigo);
+
+impl SystemTypist {
+    pub fn new() -> Result<Self, String> {
+        enigo::Enigo::new(&enigo::Settings::default())
+            .map(SystemTypist)
+            .map_err(|e| format!("Failed to open keyboard input: {}", e))
+    }
+}
+
+impl Typist for SystemTypist {
+    fn text(&mut self, text: &str) -> Result<(), String> {
+        enigo::Keyboard::text(&mut self.0, text).map_err(|e| format!("Failed to type: {}", e))
+    }
+
+    fn key(&mut self, key: Key) -> Result<(), String> {
+        let key = match key {
+            Key::Tab => enigo::Key::Tab,
+            Key::Enter => enigo::Key::Return,
+            Key::Space => enigo::Key::Space,
+        };
+        enigo::Keyboard::key(&mut self.0, key, enigo::Direction::Click)
+            .map_err(|e| format!("Failed to type: {}", e))
+    }
+}
+
+pub fn focused_window_title() -> Result<String, String> {
+    active_win_pos_rs::get_active_window()
+        .map(|window| window.title)
+        .map_err(|_| "Failed to read the focused window".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, url: Option<&str>) -> Entry {
+        Entry {
+            id: title.to_lowercase(),
+            title: title.to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            url: url.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeTypist(String);
+
+    impl Typist for FakeTypist {
+        fn text(&mut self, text: &str) -> Result<(), String> {
+            self.0.push_str(text);
+            Ok(())
+        }
+
+        fn key(&mut self, key: Key) -> Result<(), String> {
+            self.0.push_str(&format!("<{:?}>", key));
+            Ok(())
+        }
+    }
+
+    fn typed(entry: &Entry) -> String {
+        let mut typist = FakeTypist::default();
+        type_out(&mut typist, &keystrokes(entry).unwrap()).unwrap();
+        typist.0
+    }
+
+    #[test]
+    fn default_sequence_types_username_tab_password_enter() {
+        assert_eq!(typed(&entry("Example", None)), "user<Tab>secret<Enter>");
+    }
+
+    #[test]
+    fn custom_sequences_support_literals_braces_and_delays() {
+        let mut custom = entry("Example", None);
+        custom.autotype_sequence = Some("{PASSWORD}{DELAY 0}{{}x{}}{enter}".to_string());
+        assert_eq!(typed(&custom), "secret{x}<Enter>");
+
+        assert!(validate("{PASSWORD").is_err());
+        assert!(validate("{PIN}").unwrap_err().starts_with("Unknown"));
+        assert!(validate("{DELAY 999999}").is_err());
+    }
+
+    #[test]
+    fn longest_name_in_the_window_title_wins() {
+        let entries = vec![
+            entry("GitHub", Some("https://github.com")),
+            entry("GitHub Enterprise", None),
+            entry("Bank", Some("https://www.mybank.example")),
+        ];
+
+        let matched = |title| best_match(&entries, title).map(|e| e.title.as_str());
+        assert_eq!(
+            matched("Sign in to GitHub · GitHub - Firefox"),
+            Some("GitHub")
+        );
+        assert_eq!(
+            matched("GitHub Enterprise login"),
+            Some("GitHub Enterprise")
+        );
+        assert_eq!(matched("MyBank Online Banking"), Some("Bank"));
+        assert_eq!(matched("Terminal"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Downloads the offline breach database in the background and picks which breach
+//! checker password checks use (see `vault_health::offline`). With offline checks
+//! on, a missing database is an error, never a quiet fall back to the online API.
+
+use crate::settings::Settings;
+use crate::vault_health::breach_checker::{BreachChecker, PwnedPasswordsApi};
+use crate::vault_health::offline::{
+    self, DatasetStatus, Download, OfflinePwnedPasswords, RangeClient, CHECKPOINT_EVERY, RANGES,
+};
+use serde::Serialize;
+use specta::Type;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+pub const PROGRESS_EVENT: &str = "breach-database://progress";
+/// Ranges fetched side by side; divides `CHECKPOINT_EVERY`.
+const PARALLEL_RANGES: u32 = 32;
+const ATTEMPTS: u32 = 3;
+
+#[derive(Default)]
+pub struct BreachDatabaseState {
+    downloading: AtomicBool,
+    cancel: AtomicBool,
+}
+
+impl BreachDatabaseState {
+    pub fn is_downloading(&self) -> bool {
+        self.downloading.load(Ordering::SeqCst)
+    }
+
+    /// Stops a running download at its next batch; it resumes from its last
+    /// checkpoint when started again.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct BreachDatabaseProgress {
+    pub status: DatasetStatus,
+    pub downloading: bool,
+    /// Why the download stopped, if it failed.
+    pub error: Option<String>,
+}
+
+/// The checker password breach checks should use under `settings`.
+pub fn checker(settings: &Settings) -> Result<Box<dyn BreachChecker>, String> {
+    if settings.offline_breach_check {
+        Ok(Box::new(OfflinePwnedPasswords::open(
+            &offline::dataset_path()?,
+        )?))
+    } else {
+        Ok(Box::new(PwnedPasswordsApi))
+    }
+}
+
+/// Starts, or resumes, downloading the database. Progress and the outcome are
+/// emitted as `PROGRESS_EVENT`.
+pub fn spawn_download<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    let path = offline::dataset_path()?;
+    if offline::status(&path).ready {
+        return Err("The breach database is already downloaded".to_string());
+    }
+    let state = app.state::<BreachDatabaseState>();
+    if state.downloading.swap(true, Ordering::SeqCst) {
+        return Err("The breach database is already downloading".to_string());
+    }
+    state.cancel.store(false, Ordering::SeqCst);
+
+    tauri::async_runtime::spawn(async move {
+        let error = download(&app, &path).await.err();
+        app.state::<BreachDatabaseState>()
+            .downloading
+            .store(false, Ordering::SeqCst);
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            BreachDatabaseProgress {
+                status: offline::status(&path),
+                downloading: false,
+                error,
+            },
+        );
+    });
+    Ok(())
+}
+
+async fn fetch(client: RangeClient, prefix: u32) -> Result<String, String> {
+    let mut attempt = 1;
+    loop {
+        match client.fetch(prefix).await {
+            Ok(body) => return Ok(body),
+            Err(_) if attempt < ATTEMPTS => {
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn download<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<(), String> {
+    let client = RangeClient::new()?;
+    let mut download = Download::resume(path)?;
+    while download.next_range() < RANGES {
+        if app
+            .state::<BreachDatabaseState>()
+            .cancel
+            .load(Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        let first = download.next_range();
+        let batch: Vec<_> = (first..(first + PARALLEL_RANGES).min(RANGES))
+            .map(|prefix| tauri::async_runtime::spawn(fetch(client.clone(), prefix)))
+            .collect();
+        for (prefix, fetched) in (first..).zip(batch) {
+            let body = fetched
+                .await
+                .map_err(|e| format!("Failed to download breach range: {}", e))??;
+            download.append(prefix, &body)?;
+        }
+        if download.next_range() % CHECKPOINT_EVERY == 0 {
+            let _ = app.emit(
+                PROGRESS_EVENT,
+                BreachDatabaseProgress {
+                    status: download.status(),
+                    downloading: true,
+                    error: None,
+                },
+            );
+        }
+    }
+    download.finish()
+}
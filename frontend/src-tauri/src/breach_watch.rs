@@ -0,0 +1,229 @@
+//! Runs the breach watch for one Session and delivers its alerts through the
+//! channel each watched Credential asked for, and, if the user opted in, checks
+//! the email addresses used as usernames against HIBP's breached-account API.
+//! The same timer reminds the user of passwords past their expiry.
+
+use crate::commands::SettingsState;
+use crate::hooks::HookEvent;
+use crate::vault::{storage::VaultStorage, unix_now, workspace::Workspace};
+use crate::vault_health::accounts::{self, BreachedAccount, HibpAccountApi};
+use crate::vault_health::expiry::{self, ExpiredCredential};
+use crate::vault_health::watch::{self, AlertChannel, BreachAlert};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+pub const TRAY_ID: &str = "main-tray";
+pub const TRAY_TOOLTIP: &str = "Latch Password Manager";
+
+/// Shows how many watched Credentials were found in breaches this Session; zero
+/// restores the plain tooltip.
+pub fn set_tray_count<R: Runtime>(app: &AppHandle<R>, count: usize) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let tooltip = match count {
+        0 => TRAY_TOOLTIP.to_string(),
+        1 => format!("{} (1 breached credential)", TRAY_TOOLTIP),
+        n => format!("{} ({} breached credentials)", TRAY_TOOLTIP, n),
+    };
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        eprintln!("Failed to update tray: {}", e);
+    }
+}
+
+fn deliver<R: Runtime>(app: &AppHandle<R>, alert: &BreachAlert, tray_count: &mut usize) {
+    match alert.channel {
+        AlertChannel::Notification => {
+            let shown = app
+                .notification()
+                .builder()
+                .title("Password found in a breach")
+                .body(format!(
+                    "The password for {} appears in {} known breaches. Change it soon.",
+                    alert.title, alert.breach_count
+                ))
+                .show();
+            if let Err(e) = shown {
+                eprintln!("Failed to show breach alert: {}", e);
+            }
+        }
+        AlertChannel::TrayBadge => {
+            *tray_count += 1;
+            set_tray_count(app, *tray_count);
+        }
+        AlertChannel::Hook => {
+            let hooks = app.state::<SettingsState>().get().hooks;
+            crate::hooks::run(&hooks, HookEvent::BreachDetected, &alert.entry_id);
+        }
+    }
+}
+
+/// The breached-account API connected for this run of the app, and the account
+/// breaches already reported. The frontend keeps the API key in the OS keychain
+/// and connects again with it on each launch.
+pub struct AccountMonitor {
+    client: Mutex<Option<Arc<HibpAccountApi>>>,
+    /// Hashed, so the file doesn't list the user's addresses.
+    known_path: PathBuf,
+    known: Mutex<()>,
+}
+
+impl AccountMonitor {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(
+            crate::vault::storage::config_dir()?.join("account-breaches.json"),
+        ))
+    }
+
+    pub fn at(known_path: PathBuf) -> Self {
+        Self {
+            client: Mutex::new(None),
+            known_path,
+            known: Mutex::new(()),
+        }
+    }
+
+    pub fn connect(&self, client: Option<HibpAccountApi>) -> Result<(), String> {
+        *self
+            .client
+            .lock()
+            .map_err(|_| "Breach monitor is temporarily unavailable")? = client.map(Arc::new);
+        Ok(())
+    }
+
+    /// The API to check with, if monitoring is `enabled` and a key was given.
+    pub fn checker(&self, enabled: bool) -> Option<Arc<HibpAccountApi>> {
+        if !enabled {
+            return None;
+        }
+        self.client.lock().ok()?.clone()
+    }
+
+    /// Marks the breaches not reported before as new and remembers them.
+    pub fn mark_new(&self, accounts: &mut [BreachedAccount]) -> usize {
+        let Ok(_guard) = self.known.lock() else {
+            return 0;
+        };
+        let mut known: HashSet<String> = fs::read_to_string(&self.known_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let found = accounts::mark_new(accounts, &mut known);
+        if found > 0 {
+            let saved = serde_json::to_string(&known)
+                .map_err(|e| e.to_string())
+                .and_then(|json| fs::write(&self.known_path, json).map_err(|e| e.to_string()));
+            if let Err(e) = saved {
+                eprintln!("Failed to record account breaches: {}", e);
+            }
+        }
+        found
+    }
+}
+
+fn notify_account_breaches<R: Runtime>(app: &AppHandle<R>, accounts: &[BreachedAccount]) {
+    for account in accounts {
+        for breach in account.breaches.iter().filter(|b| b.new) {
+            let shown = app
+                .notification()
+                .builder()
+                .title("Account found in a breach")
+                .body(format!(
+                    "{} appears in the {} breach. Change the passwords that use it.",
+                    account.email, breach.title
+                ))
+                .show();
+            if let Err(e) = shown {
+                eprintln!("Failed to show breach alert: {}", e);
+            }
+        }
+    }
+}
+
+fn remind_expired<R: Runtime>(app: &AppHandle<R>, expired: &ExpiredCredential) {
+    let shown = app
+        .notification()
+        .builder()
+        .title("Time to change a password")
+        .body(format!(
+            "The password for {} is past its expiry. Change it, or snooze the reminder.",
+            expired.title
+        ))
+        .show();
+    if let Err(e) = shown {
+        eprintln!("Failed to show expiry reminder: {}", e);
+    }
+}
+
+/// Checks the watched Credentials now and every `WATCH_INTERVAL_SECS` until the
+/// Session ends. Reading them does not count as activity, so it never holds off
+/// the idle lock. Locking clears the tray count (see `AppEvents`). Each alert and
+/// reminder is raised once per Session.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    state_arc: Arc<Mutex<(VaultStorage, Workspace)>>,
+    session_start: SystemTime,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut alerted = HashSet::new();
+        let mut reminded = HashSet::new();
+        let mut tray_count = 0;
+        loop {
+            let (entries, emails, expired) = {
+                let Ok(guard) = state_arc.lock() else {
+                    return;
+                };
+                if guard.1.session_start != Some(session_start) || guard.1.decoy {
+                    return;
+                }
+                let watched = guard
+                    .1
+                    .credentials
+                    .iter()
+                    .filter(|e| e.breach_watch.is_some())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (
+                    watched,
+                    accounts::monitored_emails(&guard.1.credentials),
+                    expiry::check_expired(&guard.1.credentials, unix_now()),
+                )
+            };
+
+            for expired in expired {
+                if reminded.insert(expired.entry_id.clone()) {
+                    remind_expired(&app, &expired);
+                }
+            }
+
+            let settings = app.state::<SettingsState>().get();
+            // Offline checks without the database skip the passwords rather than
+            // going online.
+            if let Ok(checker) = crate::breach_database::checker(&settings) {
+                let alerts = watch::check_watched(&entries, checker.as_ref(), &mut alerted).await;
+                for alert in &alerts {
+                    deliver(&app, alert, &mut tray_count);
+                }
+            }
+
+            let monitor = app.state::<AccountMonitor>();
+            let enabled = settings.account_breach_monitoring;
+            if let Some(checker) = monitor.checker(enabled) {
+                match accounts::check_emails(emails, checker.as_ref()).await {
+                    Ok(mut breached) => {
+                        if monitor.mark_new(&mut breached) > 0 {
+                            notify_account_breaches(&app, &breached);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to check accounts for breaches: {}", e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(watch::WATCH_INTERVAL_SECS)).await;
+        }
+    });
+}
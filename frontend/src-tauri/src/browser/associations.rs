@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::PathBuf;
+
+pub const MAX_ASSOCIATIONS: usize = 32;
+
+/// A browser extension the user allowed to ask for logins. `id_key` is the
+/// extension's long-term public key; it proves the association on every request.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Association {
+    pub id: String,
+    pub id_key: String,
+    pub created_at: u64,
+}
+
+pub struct AssociationStore {
+    pub path: PathBuf,
+}
+
+impl AssociationStore {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            path: crate::vault::storage::config_dir()?.join("browser-associations.json"),
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<Association>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read browser associations: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse browser associations: {}", e))
+    }
+
+    fn write(&self, associations: &[Association]) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(associations)
+            .map_err(|e| format!("Failed to serialize browser associations: {}", e))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, &json)
+            .map_err(|e| format!("Failed to write browser associations: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to rename browser associations: {}", e))?;
+        Ok(())
+    }
+
+    /// Saves a newly approved extension under a fresh name and returns it.
+    pub fn add(&self, id_key: &str) -> Result<Association, String> {
+        let mut associations = self.list()?;
+        associations.retain(|a| a.id_key != id_key);
+        if associations.len() >= MAX_ASSOCIATIONS {
+            return Err(format!(
+                "Cannot connect more than {} browser extensions",
+                MAX_ASSOCIATIONS
+            ));
+        }
+        let association = Association {
+            id: format!("Latch {}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+            id_key: id_key.to_string(),
+            created_at: crate::vault::unix_now(),
+        };
+        associations.push(association.clone());
+        self.write(&associations)?;
+        Ok(association)
+    }
+
+    pub fn contains(&self, id: &str, id_key: &str) -> bool {
+        self.list()
+            .map(|all| all.iter().any(|a| a.id == id && a.id_key == id_key))
+            .unwrap_or(false)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let mut associations = self.list()?;
+        let len_before = associations.len();
+        associations.retain(|a| a.id != id);
+        if associations.len() == len_before {
+            return Err("Browser extension not found".to_string());
+        }
+        self.write(&associations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_extensions_can_be_checked_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AssociationStore {
+            path: dir.path().join("browser-associations.json"),
+        };
+
+        let association = store.add("key-1").unwrap();
+        assert!(store.contains(&association.id, "key-1"));
+        assert!(!store.contains(&association.id, "key-2"));
+
+        store.remove(&association.id).unwrap();
+        assert!(!store.contains(&association.id, "key-1"));
+        assert!(store.remove(&association.id).is_err());
+    }
+}
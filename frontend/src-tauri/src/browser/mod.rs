@@ -0,0 +1,6 @@
+//! Lets existing KeePassXC-Browser extensions fill logins from Latch. Latch answers
+//! on the socket KeePassXC would, so the stock keepassxc-proxy can reach it.
+
+pub mod associations;
+pub mod protocol;
+pub mod server;
@@ -0,0 +1,508 @@
+//! The keepassxc-browser protocol: JSON messages, each encrypted with a NaCl box
+//! between a per-connection key of the extension and one of ours.
+//! See https://github.com/keepassxreboot/keepassxc-browser/blob/develop/keepassxc-protocol.md
+
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use crypto_box::aead::{Aead, OsRng};
+use crypto_box::{Nonce, PublicKey, SalsaBox, SecretKey};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The KeePassXC version we answer as. Extensions gate features on it.
+pub const PROTOCOL_VERSION: &str = "2.7.6";
+const MAX_SESSIONS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    DatabaseNotOpened = 1,
+    ClientPublicKeyNotReceived = 3,
+    CannotDecryptMessage = 4,
+    ActionDenied = 6,
+    AssociationFailed = 8,
+    IncorrectAction = 12,
+    EmptyMessageReceived = 13,
+    NoUrlProvided = 14,
+    NoLoginsFound = 15,
+}
+
+impl ErrorCode {
+    fn message(self) -> &'static str {
+        match self {
+            ErrorCode::DatabaseNotOpened => "Database not opened",
+            ErrorCode::ClientPublicKeyNotReceived => "Client public key not received",
+            ErrorCode::CannotDecryptMessage => "Cannot decrypt message",
+            ErrorCode::ActionDenied => "Action cancelled or denied",
+            ErrorCode::AssociationFailed => "KeePassXC association failed, try again",
+            ErrorCode::IncorrectAction => "Incorrect action",
+            ErrorCode::EmptyMessageReceived => "Empty message received",
+            ErrorCode::NoUrlProvided => "No URL provided",
+            ErrorCode::NoLoginsFound => "No logins found",
+        }
+    }
+}
+
+/// A Credential as the extension expects it.
+pub struct Login {
+    pub uuid: String,
+    pub name: String,
+    pub login: String,
+    pub password: String,
+}
+
+/// What the protocol needs from the app. Everything that touches the Vault or asks
+/// the user goes through here.
+pub trait Backend {
+    /// A stable id for the open Vault, or `DatabaseNotOpened` while it is locked.
+    fn database_hash(&self) -> Result<String, ErrorCode>;
+    /// Asks the user to trust a new extension. Returns the name it is saved under.
+    fn associate(&self, id_key: &str) -> Result<String, ErrorCode>;
+    fn is_associated(&self, id: &str, id_key: &str) -> bool;
+    fn logins(&self, url: &str) -> Result<Vec<Login>, ErrorCode>;
+    fn generate_password(&self) -> Result<String, ErrorCode>;
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    action: String,
+    message: Option<String>,
+    nonce: Option<String>,
+    #[serde(rename = "clientID")]
+    client_id: Option<String>,
+    #[serde(rename = "publicKey")]
+    public_key: Option<String>,
+    #[serde(rename = "requestID")]
+    request_id: Option<String>,
+}
+
+#[derive(Clone)]
+struct Session {
+    client_key: PublicKey,
+    host_key: SecretKey,
+}
+
+impl Session {
+    fn cipher(&self) -> SalsaBox {
+        SalsaBox::new(&self.client_key, &self.host_key)
+    }
+}
+
+/// libsodium's `sodium_increment`: the nonce read as a little-endian number, plus one.
+/// Every reply uses the request's nonce incremented.
+pub fn increment_nonce(nonce: &[u8; 24]) -> [u8; 24] {
+    let mut next = *nonce;
+    for byte in next.iter_mut() {
+        let (value, carry) = byte.overflowing_add(1);
+        *byte = value;
+        if !carry {
+            break;
+        }
+    }
+    next
+}
+
+fn decode_nonce(nonce: Option<&str>) -> Result<[u8; 24], ErrorCode> {
+    nonce
+        .and_then(|n| B64.decode(n).ok())
+        .and_then(|n| n.try_into().ok())
+        .ok_or(ErrorCode::CannotDecryptMessage)
+}
+
+fn decode_public_key(key: Option<&str>) -> Result<PublicKey, ErrorCode> {
+    key.and_then(|k| B64.decode(k).ok())
+        .and_then(|k| <[u8; 32]>::try_from(k).ok())
+        .map(PublicKey::from)
+        .ok_or(ErrorCode::ClientPublicKeyNotReceived)
+}
+
+fn error_reply(action: &str, request_id: Option<&str>, code: ErrorCode) -> Value {
+    let mut reply = json!({
+        "action": action,
+        "errorCode": (code as u8).to_string(),
+        "error": code.message(),
+    });
+    if let Some(id) = request_id {
+        reply["requestID"] = json!(id);
+    }
+    reply
+}
+
+fn field<'a>(message: &'a Value, name: &str) -> Option<&'a str> {
+    message.get(name).and_then(Value::as_str)
+}
+
+/// Our end of every extension connection. Keys only live in memory: extensions
+/// send `change-public-keys` again whenever they reconnect.
+#[derive(Default)]
+pub struct Host {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Host {
+    /// Answers one request. Never fails: problems become protocol error replies.
+    pub fn handle(&self, request: Value, backend: &impl Backend) -> Value {
+        let Ok(envelope) = serde_json::from_value::<Envelope>(request) else {
+            return error_reply("", None, ErrorCode::EmptyMessageReceived);
+        };
+        let request_id = envelope.request_id.as_deref();
+        let reply = if envelope.action == "change-public-keys" {
+            self.change_public_keys(&envelope)
+        } else {
+            self.encrypted(&envelope, backend)
+        };
+        match reply {
+            Ok(mut reply) => {
+                if let Some(id) = request_id {
+                    reply["requestID"] = json!(id);
+                }
+                reply
+            }
+            Err(code) => error_reply(&envelope.action, request_id, code),
+        }
+    }
+
+    fn change_public_keys(&self, envelope: &Envelope) -> Result<Value, ErrorCode> {
+        let client_id = envelope
+            .client_id
+            .clone()
+            .ok_or(ErrorCode::ClientPublicKeyNotReceived)?;
+        let client_key = decode_public_key(envelope.public_key.as_deref())?;
+        let nonce = decode_nonce(envelope.nonce.as_deref())?;
+        let host_key = SecretKey::generate(&mut OsRng);
+        let public_key = B64.encode(host_key.public_key().as_bytes());
+
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if sessions.len() >= MAX_SESSIONS && !sessions.contains_key(&client_id) {
+            sessions.clear();
+        }
+        sessions.insert(
+            client_id,
+            Session {
+                client_key,
+                host_key,
+            },
+        );
+
+        Ok(json!({
+            "action": envelope.action,
+            "version": PROTOCOL_VERSION,
+            "publicKey": public_key,
+            "nonce": B64.encode(increment_nonce(&nonce)),
+            "success": "true",
+        }))
+    }
+
+    fn encrypted(&self, envelope: &Envelope, backend: &impl Backend) -> Result<Value, ErrorCode> {
+        let session = envelope
+            .client_id
+            .as_ref()
+            .and_then(|id| {
+                let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+                sessions.get(id).cloned()
+            })
+            .ok_or(ErrorCode::ClientPublicKeyNotReceived)?;
+        let nonce = decode_nonce(envelope.nonce.as_deref())?;
+        let ciphertext = envelope
+            .message
+            .as_deref()
+            .and_then(|m| B64.decode(m).ok())
+            .ok_or(ErrorCode::EmptyMessageReceived)?;
+        let plaintext = session
+            .cipher()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| ErrorCode::CannotDecryptMessage)?;
+        let message: Value =
+            serde_json::from_slice(&plaintext).map_err(|_| ErrorCode::CannotDecryptMessage)?;
+        if field(&message, "action") != Some(envelope.action.as_str()) {
+            return Err(ErrorCode::IncorrectAction);
+        }
+
+        let mut reply = self.dispatch(&envelope.action, &message, &session, backend)?;
+        let next = increment_nonce(&nonce);
+        reply.insert("version".into(), json!(PROTOCOL_VERSION));
+        reply.insert("success".into(), json!("true"));
+        reply.insert("nonce".into(), json!(B64.encode(next)));
+        let plaintext = serde_json::to_vec(&reply).map_err(|_| ErrorCode::CannotDecryptMessage)?;
+        let sealed = session
+            .cipher()
+            .encrypt(Nonce::from_slice(&next), plaintext.as_slice())
+            .map_err(|_| ErrorCode::CannotDecryptMessage)?;
+
+        Ok(json!({
+            "action": envelope.action,
+            "message": B64.encode(sealed),
+            "nonce": B64.encode(next),
+        }))
+    }
+
+    fn dispatch(
+        &self,
+        action: &str,
+        message: &Value,
+        session: &Session,
+        backend: &impl Backend,
+    ) -> Result<Map<String, Value>, ErrorCode> {
+        let mut reply = Map::new();
+        match action {
+            "get-databasehash" => {
+                reply.insert("hash".into(), json!(backend.database_hash()?));
+            }
+            "associate" => {
+                let hash = backend.database_hash()?;
+                // The extension proves it holds the key this connection was set up with.
+                let current = B64.encode(session.client_key.as_bytes());
+                if field(message, "key") != Some(current.as_str()) {
+                    return Err(ErrorCode::AssociationFailed);
+                }
+                let id_key = field(message, "idKey").ok_or(ErrorCode::AssociationFailed)?;
+                reply.insert("id".into(), json!(backend.associate(id_key)?));
+                reply.insert("hash".into(), json!(hash));
+            }
+            "test-associate" => {
+                let hash = backend.database_hash()?;
+                let id = field(message, "id").ok_or(ErrorCode::AssociationFailed)?;
+                let key = field(message, "key").ok_or(ErrorCode::AssociationFailed)?;
+                if !backend.is_associated(id, key) {
+                    return Err(ErrorCode::AssociationFailed);
+                }
+                reply.insert("id".into(), json!(id));
+                reply.insert("hash".into(), json!(hash));
+            }
+            "get-logins" => {
+                let hash = backend.database_hash()?;
+                let url = field(message, "url")
+                    .filter(|url| !url.is_empty())
+                    .ok_or(ErrorCode::NoUrlProvided)?;
+                let associated =
+                    message
+                        .get("keys")
+                        .and_then(Value::as_array)
+                        .is_some_and(|keys| {
+                            keys.iter()
+                                .any(|k| match (field(k, "id"), field(k, "key")) {
+                                    (Some(id), Some(key)) => backend.is_associated(id, key),
+                                    _ => false,
+                                })
+                        });
+                if !associated {
+                    return Err(ErrorCode::AssociationFailed);
+                }
+                let logins = backend.logins(url)?;
+                if logins.is_empty() {
+                    return Err(ErrorCode::NoLoginsFound);
+                }
+                let entries: Vec<Value> = logins
+                    .into_iter()
+                    .map(|l| {
+                        json!({
+                            "uuid": l.uuid,
+                            "name": l.name,
+                            "login": l.login,
+                            "password": l.password,
+                            "group": "",
+                            "totp": "",
+                            "expired": "false",
+                            "skipAutoSubmit": "false",
+                            "stringFields": [],
+                        })
+                    })
+                    .collect();
+                reply.insert("count".into(), json!(entries.len()));
+                reply.insert("entries".into(), Value::Array(entries));
+                reply.insert("hash".into(), json!(hash));
+            }
+            "generate-password" => {
+                reply.insert("password".into(), json!(backend.generate_password()?));
+            }
+            _ => return Err(ErrorCode::IncorrectAction),
+        }
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct FakeBackend {
+        unlocked: bool,
+        associations: RefCell<Vec<(String, String)>>,
+    }
+
+    impl Backend for FakeBackend {
+        fn database_hash(&self) -> Result<String, ErrorCode> {
+            if self.unlocked {
+                Ok("abc123".to_string())
+            } else {
+                Err(ErrorCode::DatabaseNotOpened)
+            }
+        }
+
+        fn associate(&self, id_key: &str) -> Result<String, ErrorCode> {
+            let id = "Latch 1".to_string();
+            self.associations
+                .borrow_mut()
+                .push((id.clone(), id_key.to_string()));
+            Ok(id)
+        }
+
+        fn is_associated(&self, id: &str, id_key: &str) -> bool {
+            self.associations
+                .borrow()
+                .iter()
+                .any(|(i, k)| i == id && k == id_key)
+        }
+
+        fn logins(&self, url: &str) -> Result<Vec<Login>, ErrorCode> {
+            Ok(vec![Login {
+                uuid: "entry-1".to_string(),
+                name: "Example".to_string(),
+                login: "user".to_string(),
+                password: format!("secret for {}", url),
+            }])
+        }
+
+        fn generate_password(&self) -> Result<String, ErrorCode> {
+            Ok("generated".to_string())
+        }
+    }
+
+    /// The extension's side of a connection.
+    struct Client {
+        key: SecretKey,
+        host_key: Option<PublicKey>,
+        nonce: [u8; 24],
+    }
+
+    impl Client {
+        fn new() -> Self {
+            Client {
+                key: SecretKey::generate(&mut OsRng),
+                host_key: None,
+                nonce: [0xff; 24],
+            }
+        }
+
+        fn public(&self) -> String {
+            B64.encode(self.key.public_key().as_bytes())
+        }
+
+        fn connect(&mut self, host: &Host, backend: &FakeBackend) {
+            let request = json!({
+                "action": "change-public-keys",
+                "publicKey": self.public(),
+                "nonce": B64.encode(self.nonce),
+                "clientID": "client-1",
+            });
+            let reply = host.handle(request, backend);
+            assert_eq!(reply["success"], "true");
+            assert_eq!(reply["nonce"], B64.encode(increment_nonce(&self.nonce)));
+            self.host_key = Some(decode_public_key(reply["publicKey"].as_str()).unwrap());
+        }
+
+        fn send(&mut self, host: &Host, backend: &FakeBackend, message: Value) -> Value {
+            self.nonce = increment_nonce(&self.nonce);
+            let cipher = SalsaBox::new(self.host_key.as_ref().unwrap(), &self.key);
+            let sealed = cipher
+                .encrypt(
+                    Nonce::from_slice(&self.nonce),
+                    message.to_string().as_bytes(),
+                )
+                .unwrap();
+            let request = json!({
+                "action": message["action"],
+                "message": B64.encode(sealed),
+                "nonce": B64.encode(self.nonce),
+                "clientID": "client-1",
+            });
+            let reply = host.handle(request, backend);
+            let Some(sealed) = reply["message"].as_str() else {
+                return reply;
+            };
+            let next = decode_nonce(reply["nonce"].as_str()).unwrap();
+            assert_eq!(next, increment_nonce(&self.nonce));
+            let plaintext = cipher
+                .decrypt(
+                    Nonce::from_slice(&next),
+                    B64.decode(sealed).unwrap().as_slice(),
+                )
+                .unwrap();
+            serde_json::from_slice(&plaintext).unwrap()
+        }
+    }
+
+    #[test]
+    fn nonce_increments_little_endian_with_carry() {
+        let mut nonce = [0u8; 24];
+        nonce[0] = 0xff;
+        let next = increment_nonce(&nonce);
+        assert_eq!((next[0], next[1]), (0, 1));
+        assert_eq!(increment_nonce(&[0xff; 24]), [0; 24]);
+    }
+
+    #[test]
+    fn associated_extension_gets_logins() {
+        let host = Host::default();
+        let backend = FakeBackend {
+            unlocked: true,
+            associations: RefCell::new(Vec::new()),
+        };
+        let mut client = Client::new();
+        client.connect(&host, &backend);
+        let id_key = B64.encode([9u8; 32]);
+
+        let denied = client.send(
+            &host,
+            &backend,
+            json!({"action": "get-logins", "url": "https://example.com", "keys": []}),
+        );
+        assert_eq!(denied["errorCode"], "8");
+
+        let associated = client.send(
+            &host,
+            &backend,
+            json!({"action": "associate", "key": client.public(), "idKey": id_key}),
+        );
+        assert_eq!(associated["success"], "true");
+        assert_eq!(associated["hash"], "abc123");
+        let id = associated["id"].as_str().unwrap().to_string();
+
+        let logins = client.send(
+            &host,
+            &backend,
+            json!({
+                "action": "get-logins",
+                "url": "https://example.com",
+                "keys": [{"id": id, "key": id_key}],
+            }),
+        );
+        assert_eq!(logins["count"], 1);
+        assert_eq!(
+            logins["entries"][0]["password"],
+            "secret for https://example.com"
+        );
+    }
+
+    #[test]
+    fn locked_vault_and_unknown_clients_are_refused() {
+        let host = Host::default();
+        let backend = FakeBackend {
+            unlocked: false,
+            associations: RefCell::new(Vec::new()),
+        };
+        let mut client = Client::new();
+
+        let unknown = host.handle(
+            json!({"action": "get-databasehash", "message": "AAAA", "nonce": "AAAA", "clientID": "nobody"}),
+            &backend,
+        );
+        assert_eq!(unknown["errorCode"], "3");
+
+        client.connect(&host, &backend);
+        let locked = client.send(&host, &backend, json!({"action": "get-databasehash"}));
+        assert_eq!(locked["errorCode"], "1");
+        assert_eq!(locked["error"], "Database not opened");
+    }
+}
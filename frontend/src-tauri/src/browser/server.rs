@@ -0,0 +1,294 @@
+use super::associations::AssociationStore;
+use super::protocol::{Backend, ErrorCode, Host, Login};
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::{SettingsState, VaultState};
+use crate::password_generator::{generate_password_avoiding, PasswordOptions};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::oneshot;
+
+/// Emitted with an `AssociationRequest` when an extension asks to connect.
+pub const ASSOCIATION_EVENT: &str = "browser://association-requested";
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(60);
+/// KeePassXC refuses anything larger, so extensions never send it.
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+/// The socket (or pipe) name keepassxc-proxy connects to.
+const SERVER_NAME: &str = "org.keepassxc.KeePassXC.BrowserServer";
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct AssociationRequest {
+    pub request_id: String,
+    /// Short fingerprint of the extension's key, to compare with what it shows.
+    pub fingerprint: String,
+}
+
+/// The listener, while browser integration is on, and connection requests waiting
+/// on the user.
+#[derive(Default)]
+pub struct BrowserState {
+    host: Host,
+    stop: Mutex<Option<oneshot::Sender<()>>>,
+    pending: Mutex<HashMap<String, mpsc::Sender<bool>>>,
+}
+
+impl BrowserState {
+    pub fn resolve(&self, request_id: &str, approved: bool) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let reply = pending
+            .remove(request_id)
+            .ok_or("Connection request has expired")?;
+        let _ = reply.send(approved);
+        Ok(())
+    }
+}
+
+/// Whether a login saved for `saved` should be offered on `requested`: same host or
+/// one of its subdomains, over http(s).
+fn matches_url(saved: &str, requested: &str) -> bool {
+    let host = |url: &str| {
+        url::Url::parse(url)
+            .ok()
+            .filter(|u| matches!(u.scheme(), "http" | "https"))
+            .and_then(|u| u.host_str().map(str::to_lowercase))
+    };
+    match (host(saved), host(requested)) {
+        (Some(saved), Some(requested)) => {
+            requested == saved || requested.ends_with(&format!(".{}", saved))
+        }
+        _ => false,
+    }
+}
+
+struct AppBackend<'a>(&'a AppHandle);
+
+impl Backend for AppBackend<'_> {
+    fn database_hash(&self) -> Result<String, ErrorCode> {
+        self.0
+            .state::<VaultState>()
+            .lock(|storage, workspace| {
+                workspace.check_session()?;
+                let path = storage.path.to_string_lossy();
+                Ok(hex::encode(Sha256::digest(path.as_bytes())))
+            })
+            .map_err(|_| ErrorCode::DatabaseNotOpened)
+    }
+
+    fn associate(&self, id_key: &str) -> Result<String, ErrorCode> {
+        let (reply, approval) = mpsc::channel();
+        let request = AssociationRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            fingerprint: hex::encode(&Sha256::digest(id_key.as_bytes())[..8]),
+        };
+        let browser = self.0.state::<BrowserState>();
+        browser
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request.request_id.clone(), reply);
+
+        if let Some(window) = self.0.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        let _ = self.0.emit(ASSOCIATION_EVENT, &request);
+        let approved = approval.recv_timeout(APPROVAL_TIMEOUT).unwrap_or(false);
+        browser
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&request.request_id);
+        if !approved {
+            return Err(ErrorCode::ActionDenied);
+        }
+
+        let association = AssociationStore::new()
+            .and_then(|store| store.add(id_key))
+            .map_err(|_| ErrorCode::AssociationFailed)?;
+        self.0.state::<AuditLog>().record(
+            AuditKind::SettingsChanged,
+            Some(format!("browser extension {} connected", association.id)),
+        );
+        Ok(association.id)
+    }
+
+    fn is_associated(&self, id: &str, id_key: &str) -> bool {
+        AssociationStore::new()
+            .map(|store| store.contains(id, id_key))
+            .unwrap_or(false)
+    }
+
+    fn logins(&self, url: &str) -> Result<Vec<Login>, ErrorCode> {
+        let entries = self
+            .0
+            .state::<VaultState>()
+            .lock(|_, workspace| {
+                workspace.check_session()?;
+                workspace.refresh();
+                let matching: Vec<_> = workspace
+                    .credentials
+                    .iter()
+                    .filter(|e| {
+                        e.is_login()
+                            && e.url
+                                .as_deref()
+                                .is_some_and(|saved| matches_url(saved, url))
+                    })
+                    .cloned()
+                    .collect();
+                // Entries still in their reveal cooldown are left out, not failed.
+                Ok(matching
+                    .into_iter()
+                    .filter(|entry| workspace.reveal(entry).is_ok())
+                    .collect::<Vec<_>>())
+            })
+            .map_err(|_| ErrorCode::DatabaseNotOpened)?;
+
+        let audit = self.0.state::<AuditLog>();
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                audit.record(
+                    AuditKind::Reveal,
+                    Some(format!("password of {} to browser extension", entry.id)),
+                );
+                Login {
+                    uuid: entry.id,
+                    name: entry.title,
+                    login: entry.username,
+                    password: entry.password,
+                }
+            })
+            .collect())
+    }
+
+    fn generate_password(&self) -> Result<String, ErrorCode> {
+        let dictionary = self.0.state::<SettingsState>().get().dictionary();
+        generate_password_avoiding(&PasswordOptions::default(), &dictionary)
+            .map_err(|_| ErrorCode::ActionDenied)
+    }
+}
+
+/// Reads requests off one extension connection (via keepassxc-proxy) and answers
+/// each in turn. Messages are bare JSON objects with no framing.
+async fn serve<S>(mut stream: S, app: AppHandle)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = Vec::new();
+    let mut chunk = vec![0u8; 16 * 1024];
+    loop {
+        let n = match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut requests = Vec::new();
+        let mut consumed = 0;
+        let mut parsed = serde_json::Deserializer::from_slice(&buf).into_iter::<Value>();
+        loop {
+            match parsed.next() {
+                Some(Ok(request)) => {
+                    requests.push(request);
+                    consumed = parsed.byte_offset();
+                }
+                Some(Err(e)) if e.is_eof() => break,
+                Some(Err(_)) => return,
+                None => break,
+            }
+        }
+        buf.drain(..consumed);
+        if buf.len() > MAX_MESSAGE_BYTES {
+            return;
+        }
+
+        for request in requests {
+            let handle = app.clone();
+            // Associating waits on the user, so keep it off the async workers.
+            let reply = tauri::async_runtime::spawn_blocking(move || {
+                handle
+                    .state::<BrowserState>()
+                    .host
+                    .handle(request, &AppBackend(&handle))
+            })
+            .await;
+            let Ok(reply) = reply else {
+                return;
+            };
+            let Ok(bytes) = serde_json::to_vec(&reply) else {
+                return;
+            };
+            if stream.write_all(&bytes).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, target_os = "macos"))]
+fn socket_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(SERVER_NAME)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn socket_path() -> std::path::PathBuf {
+    crate::local_socket::runtime_path(&["app", "org.keepassxc.KeePassXC", SERVER_NAME])
+}
+
+#[cfg(windows)]
+fn socket_path() -> String {
+    crate::local_socket::pipe_name(SERVER_NAME)
+}
+
+fn listen(app: AppHandle) -> Result<oneshot::Sender<()>, String> {
+    crate::local_socket::listen(socket_path(), move |stream| serve(stream, app.clone()))
+}
+
+/// Starts or stops answering extensions to match the setting.
+pub fn sync(app: &AppHandle, enabled: bool) {
+    let browser = app.state::<BrowserState>();
+    let mut stop = browser.stop.lock().unwrap_or_else(|e| e.into_inner());
+    if enabled && stop.is_none() {
+        match listen(app.clone()) {
+            Ok(handle) => *stop = Some(handle),
+            Err(e) => eprintln!("Failed to start browser integration: {}", e),
+        }
+    } else if !enabled {
+        if let Some(handle) = stop.take() {
+            let _ = handle.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logins_match_their_host_and_subdomains_only() {
+        assert!(matches_url(
+            "https://example.com/login",
+            "https://example.com/"
+        ));
+        assert!(matches_url(
+            "https://example.com",
+            "https://accounts.EXAMPLE.com/x"
+        ));
+        assert!(!matches_url(
+            "https://example.com",
+            "https://notexample.com"
+        ));
+        assert!(!matches_url(
+            "https://accounts.example.com",
+            "https://example.com"
+        ));
+        assert!(!matches_url("https://example.com", "file:///example.com"));
+    }
+}
@@ -0,0 +1,439 @@
+//! Scoped capability tokens for the frontend. After unlocking, the frontend
+//! claims one short-lived token per scope and presents the matching one, in the
+//! `latch-capability` header, with each command that reads, reveals, changes or
+//! exports Credentials. A script injected into the webview later finds the
+//! Session's tokens already claimed, so it can't call those commands itself.
+//! Every command needs a token unless it is listed in `OPEN`.
+//!
+//! The frontend renews its tokens with the renewal secret it got alongside them.
+//! Tokens belong to the Session they were claimed in, so locking voids them.
+
+use crate::commands::VaultState;
+use crate::error::LatchError;
+use crate::local_share::constant_time_eq;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::http::HeaderMap;
+use tauri::{Manager, Runtime, State, Webview};
+
+pub const CAPABILITY_HEADER: &str = "latch-capability";
+pub const CAPABILITY_TTL_SECS: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Listing and searching Credentials without their secrets, and reading the
+    /// Vault's settings and status.
+    ReadPreview,
+    /// Anything that hands out a secret, or lets something outside the app use one.
+    RevealSecret,
+    /// Adding, editing and deleting Credentials, and changing the Vault or the
+    /// settings and services that guard it.
+    Mutate,
+    /// Writing Vault data out of the app, or choosing where sync sends it.
+    Export,
+}
+
+/// Commands that need no token: unlocking and the lock screen, the Session's own
+/// plumbing, and generators that never read the Vault.
+const OPEN: &[&str] = &[
+    "init_vault_oauth",
+    "start_github_sign_in",
+    "poll_github_sign_in",
+    "init_vault_with_key",
+    "init_vault",
+    "unlock_vault_oauth",
+    "unlock_vault_with_key",
+    "unlock_vault",
+    "check_binary_integrity",
+    "get_vault_auth_method",
+    "get_vault_identity_provider",
+    "reauthenticate",
+    "vault_status",
+    "request_confirmation",
+    "lock_vault",
+    "get_session_info",
+    "extend_session",
+    "claim_capabilities",
+    "get_guest_session",
+    "end_guest_session",
+    "get_auth_preferences",
+    "get_vault_summary",
+    "is_app_ready",
+    "generate_password",
+    "password_entropy",
+    "analyze_password_strength",
+];
+
+impl Scope {
+    const ALL: [Scope; 4] = [
+        Scope::ReadPreview,
+        Scope::RevealSecret,
+        Scope::Mutate,
+        Scope::Export,
+    ];
+
+    fn commands(self) -> &'static [&'static str] {
+        match self {
+            Scope::ReadPreview => &[
+                "search_entries",
+                "search_archive",
+                "get_entry_actions",
+                "list_folders",
+                "get_entry_history",
+                "get_unverified_entries",
+                "generate_password_for_entry",
+                "get_settings",
+                "sync_status",
+                "list_lan_peers",
+                "discover_lan_peers",
+                "list_aliases",
+                "get_audit_log",
+                "list_backups",
+                "get_pending_vault_file",
+                "list_browser_associations",
+                "get_ssh_agent_socket",
+                "get_sharing_key",
+                "list_share_contacts",
+                "check_vault_health",
+                "get_breach_database_status",
+            ],
+            Scope::RevealSecret => &[
+                "request_secret",
+                "get_full_entry",
+                "copy_secret_to_clipboard",
+                "open_shared_secret",
+                "get_generation_history",
+                "resolve_browser_association",
+                "resolve_ssh_confirmation",
+                "set_secrets_revealed",
+            ],
+            Scope::Mutate => &[
+                "add_entry",
+                "add_secret_reference",
+                "add_bank_account",
+                "add_api_key",
+                "add_server",
+                "add_canary_entry",
+                "update_entry",
+                "delete_entry",
+                "delete_entries",
+                "undo_last_operation",
+                "redo",
+                "restore_archived_entry",
+                "set_entry_folder",
+                "set_entry_icon",
+                "set_entry_favorite",
+                "link_entry",
+                "set_entry_verification",
+                "set_breach_watch",
+                "set_password_policy",
+                "set_password_expiry",
+                "snooze_password_expiry",
+                "mark_password_rotated",
+                "add_ssh_key",
+                "import_shared_entries",
+                "keep_this_vault_copy",
+                "merge_vault",
+                "import_vault_file",
+                "restore_from_backup",
+                "attempt_vault_repair",
+                "fix_url_secret",
+                "update_settings",
+                "import_settings",
+                "reencrypt_vault",
+                "reencrypt_vault_to_oauth",
+                "migrate_to_oauth",
+                "delete_vault",
+                "configure_duress_vault",
+                "remove_duress_vault",
+                "start_guest_session",
+                "add_share_contact",
+                "remove_share_contact",
+                "refresh_icon",
+                "import_dictionary",
+                "connect_alias_service",
+                "disconnect_alias_service",
+                "create_alias",
+                "disable_alias",
+                "connect_breach_monitor",
+                "disconnect_breach_monitor",
+                "download_breach_database",
+                "cancel_breach_database_download",
+                "delete_breach_database",
+                "clear_generation_history",
+                "reenroll_vault_key",
+                "open_vault_file",
+                "remove_browser_association",
+            ],
+            Scope::Export => &[
+                "export_audit_log",
+                "share_secret_once",
+                "share_secret_locally",
+                "share_entries",
+                "export_settings",
+                "connect_s3",
+                "connect_google_drive",
+                "sync_now",
+                "sync_with_lan_peer",
+                "pair_lan_peer",
+                "confirm_lan_pairing",
+                "open_lan_pairing",
+                "reject_lan_pairing",
+                "forget_lan_peer",
+                "disconnect_sync",
+                "cancel_local_share",
+            ],
+        }
+    }
+
+    fn of(command: &str) -> Option<Self> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.commands().contains(&command))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct Capability {
+    pub scope: Scope,
+    pub token: String,
+    /// The commands that need this token.
+    pub commands: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct CapabilitySet {
+    pub capabilities: Vec<Capability>,
+    /// Presented to `claim_capabilities` to renew them before they expire.
+    pub renewal: String,
+    pub expires_in_secs: u64,
+}
+
+struct Grant {
+    session_start: SystemTime,
+    issued: Instant,
+    tokens: HashMap<Scope, String>,
+    renewal: String,
+}
+
+/// The tokens claimed in the current Session, if any.
+#[derive(Default)]
+pub struct CapabilityState(Mutex<Option<Grant>>);
+
+impl CapabilityState {
+    /// Issues a fresh set of tokens for the Session started at `session_start`.
+    /// Only the first claim in a Session goes without a token; later ones must
+    /// present that Session's renewal secret.
+    pub fn claim(
+        &self,
+        session_start: SystemTime,
+        presented: Option<&str>,
+    ) -> Result<CapabilitySet, String> {
+        let mut grant = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(current) = grant.as_ref().filter(|g| g.session_start == session_start) {
+            let renews = presented
+                .is_some_and(|p| constant_time_eq(p.as_bytes(), current.renewal.as_bytes()));
+            if !renews {
+                return Err(
+                    "Capabilities for this session were already claimed; lock and unlock again"
+                        .to_string(),
+                );
+            }
+        }
+
+        let tokens: HashMap<Scope, String> = Scope::ALL
+            .into_iter()
+            .map(|s| (s, random_token()))
+            .collect();
+        let set = CapabilitySet {
+            capabilities: Scope::ALL
+                .into_iter()
+                .map(|scope| Capability {
+                    scope,
+                    token: tokens[&scope].clone(),
+                    commands: scope.commands().iter().map(|c| c.to_string()).collect(),
+                })
+                .collect(),
+            renewal: random_token(),
+            expires_in_secs: CAPABILITY_TTL_SECS,
+        };
+        *grant = Some(Grant {
+            session_start,
+            issued: Instant::now(),
+            tokens,
+            renewal: set.renewal.clone(),
+        });
+        Ok(set)
+    }
+
+    /// Refuses `command` unless `presented` is this Session's live token for its
+    /// scope. Only the `OPEN` commands pass without one.
+    fn check(
+        &self,
+        command: &str,
+        session_start: SystemTime,
+        presented: Option<&str>,
+    ) -> Result<(), LatchError> {
+        let Some(scope) = Scope::of(command) else {
+            if OPEN.contains(&command) {
+                return Ok(());
+            }
+            return Err(LatchError::Forbidden(
+                "This action is not available to the frontend".to_string(),
+            ));
+        };
+        let grant = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let valid = grant.as_ref().is_some_and(|g| {
+            g.session_start == session_start
+                && g.issued.elapsed() < Duration::from_secs(CAPABILITY_TTL_SECS)
+                && presented
+                    .is_some_and(|p| constant_time_eq(p.as_bytes(), g.tokens[&scope].as_bytes()))
+        });
+        if !valid {
+            return Err(LatchError::Forbidden(
+                "Missing or expired capability for this action".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn presented(headers: &HeaderMap) -> Option<&str> {
+    headers.get(CAPABILITY_HEADER)?.to_str().ok()
+}
+
+/// The start of the unlocked Session, if there is one.
+fn session_start(state: &VaultState) -> Result<Option<SystemTime>, LatchError> {
+    let guard = state
+        .0
+        .lock()
+        .map_err(|_| LatchError::Unavailable("Vault is temporarily unavailable".to_string()))?;
+    Ok(guard.1.session_start.filter(|_| guard.1.is_unlocked()))
+}
+
+/// Refuses a scoped `command` sent without its capability. While the Vault is
+/// locked there is nothing to protect, and the command reports that itself.
+pub fn permit<R: Runtime>(
+    webview: &Webview<R>,
+    command: &str,
+    headers: &HeaderMap,
+) -> Result<(), LatchError> {
+    let Some(capabilities) = webview.try_state::<CapabilityState>() else {
+        return Ok(());
+    };
+    match session_start(&webview.state::<VaultState>())? {
+        Some(start) => capabilities.check(command, start, presented(headers)),
+        None => Ok(()),
+    }
+}
+
+/// Hands the frontend its capability tokens for this Session. Renewing them
+/// needs the `renewal` secret issued with the current ones.
+#[tauri::command]
+#[specta::specta]
+pub async fn claim_capabilities(
+    renewal: Option<String>,
+    state: State<'_, VaultState>,
+    capabilities: State<'_, CapabilityState>,
+) -> Result<CapabilitySet, LatchError> {
+    let start = state.lock(|_, workspace| {
+        workspace.check_session()?;
+        workspace
+            .session_start
+            .ok_or_else(|| "Invalid session".to_string())
+    })?;
+    let set = capabilities.claim(start, renewal.as_deref())?;
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_commands_need_the_sessions_token_for_their_scope() {
+        let state = CapabilityState::default();
+        let session = SystemTime::now();
+        let set = state.claim(session, None).unwrap();
+        let token = |scope| {
+            set.capabilities
+                .iter()
+                .find(|c| c.scope == scope)
+                .map(|c| c.token.clone())
+                .unwrap()
+        };
+        let reveal = token(Scope::RevealSecret);
+
+        assert!(state
+            .check("get_full_entry", session, Some(&reveal))
+            .is_ok());
+        assert!(state.check("get_full_entry", session, None).is_err());
+        let wrong_scope = token(Scope::ReadPreview);
+        assert!(state
+            .check("get_full_entry", session, Some(&wrong_scope))
+            .is_err());
+        assert!(state.check("generate_password", session, None).is_ok());
+        assert!(state.check("update_settings", session, None).is_err());
+        assert!(state.check("sync_now", session, None).is_err());
+        assert!(state
+            .check("get_generation_history", session, None)
+            .is_err());
+        assert!(state.check("not_a_command", session, None).is_err());
+
+        // A second claim needs the renewal secret, and replaces the tokens.
+        assert!(state.claim(session, None).is_err());
+        state.claim(session, Some(&set.renewal)).unwrap();
+        assert!(state
+            .check("get_full_entry", session, Some(&reveal))
+            .is_err());
+
+        // A new Session voids them and may claim afresh.
+        let next = session + Duration::from_secs(1);
+        let fresh = state.claim(next, None).unwrap();
+        assert!(state
+            .check("get_full_entry", session, Some(&reveal))
+            .is_err());
+
+        let export = &fresh.capabilities[3].token;
+        assert!(state.check("share_entries", next, Some(export)).is_ok());
+        state.0.lock().unwrap().as_mut().unwrap().issued = Instant::now()
+            .checked_sub(Duration::from_secs(CAPABILITY_TTL_SECS))
+            .unwrap();
+        assert!(state.check("share_entries", next, Some(export)).is_err());
+    }
+
+    #[test]
+    fn every_registered_command_has_a_scope_or_is_open() {
+        let source = include_str!("lib.rs");
+        let start = source.find("collect_commands![").unwrap();
+        let end = start + source[start..].find("])").unwrap();
+        let registered: Vec<&str> = source[start..end]
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.trim().split("::<").next()?.rsplit("::").next())
+            .map(|name| name.trim_end_matches(','))
+            .filter(|name| !name.is_empty())
+            .collect();
+        assert!(registered.contains(&"request_secret"));
+
+        for command in registered {
+            let open = OPEN.contains(&command);
+            match Scope::of(command) {
+                Some(scope) => assert!(!open, "{} is open and in {:?}", command, scope),
+                None => assert!(open, "{} has no capability scope", command),
+            }
+        }
+    }
+}
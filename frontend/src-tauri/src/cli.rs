@@ -0,0 +1,317 @@
+//! Headless access to the Vault for scripts and servers: `latch <command>`. Works on
+//! the Vault file directly with the master password, so the app need not run.
+//! Only password Vaults can be opened this way.
+
+use crate::audit::{AuditKind, AuditLog};
+use crate::auth::provider::{self, Credentials};
+use crate::password_generator::{generate_password, PasswordOptions};
+use crate::vault::collation::Collation;
+use crate::vault::portable::PORTABLE_EXTENSION;
+use crate::vault::{storage::VaultStorage, workspace::Workspace, Entry, EntryKind};
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Read instead of prompting when set, for scripts.
+pub const PASSWORD_ENV: &str = "LATCH_MASTER_PASSWORD";
+pub const COMMANDS: &[&str] = &["list", "show", "get", "add", "generate", "export"];
+
+const USAGE: &str = "usage:
+  latch list
+  latch show <title-or-id> [--password]
+  latch get <title-or-id> <field>
+  latch add <title> <username> [url] [--generate]
+  latch generate [length]
+  latch export <file.latch>";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Show {
+        query: String,
+        password: bool,
+    },
+    /// Prints one field alone, e.g. an API key's `secret`, for scripts.
+    Get {
+        query: String,
+        field: String,
+    },
+    Add {
+        title: String,
+        username: String,
+        url: Option<String>,
+        generate: bool,
+    },
+    Generate {
+        length: u32,
+    },
+    Export {
+        path: PathBuf,
+    },
+}
+
+fn parse(args: &[String]) -> Result<Command, String> {
+    let (flags, words): (Vec<&str>, Vec<&str>) = args
+        .iter()
+        .map(String::as_str)
+        .partition(|arg| arg.starts_with("--"));
+    let flag = |name: &str| flags.contains(&name);
+    if let Some(unknown) = flags
+        .iter()
+        .find(|f| !matches!(**f, "--password" | "--generate"))
+    {
+        return Err(format!("Unknown option: {}", unknown));
+    }
+
+    match words.as_slice() {
+        ["list"] => Ok(Command::List),
+        ["show", query] => Ok(Command::Show {
+            query: query.to_string(),
+            password: flag("--password"),
+        }),
+        ["get", query, field] => Ok(Command::Get {
+            query: query.to_string(),
+            field: field.to_string(),
+        }),
+        ["add", title, username, rest @ ..] if rest.len() <= 1 => Ok(Command::Add {
+            title: title.to_string(),
+            username: username.to_string(),
+            url: rest.first().map(|url| url.to_string()),
+            generate: flag("--generate"),
+        }),
+        ["generate"] => Ok(Command::Generate {
+            length: PasswordOptions::default().length,
+        }),
+        ["generate", length] => length
+            .parse()
+            .map(|length| Command::Generate { length })
+            .map_err(|_| format!("Invalid length: {}", length)),
+        ["export", path] => Ok(Command::Export {
+            path: PathBuf::from(path),
+        }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// The Credential with this id, or else the one titled like `query`.
+fn find<'a>(entries: &'a [Entry], query: &str) -> Option<&'a Entry> {
+    entries
+        .iter()
+        .find(|e| e.id == query)
+        .or_else(|| entries.iter().find(|e| e.title.eq_ignore_ascii_case(query)))
+}
+
+fn read_line(prompt: &str) -> Result<String, String> {
+    eprint!("{}", prompt);
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read input: {}", e))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn master_password() -> Result<String, String> {
+    match std::env::var(PASSWORD_ENV) {
+        Ok(password) => Ok(password),
+        Err(_) => read_line("Master password: "),
+    }
+}
+
+fn open() -> Result<(VaultStorage, Workspace), String> {
+    let storage = VaultStorage::new()?;
+    let header = storage.read_header()?;
+    let password = master_password()?;
+    let key = provider::vault_key(&header.kdf, &header.salt, &Credentials::Password(&password))?;
+    let mut workspace = Workspace::new();
+    crate::vault::access::access(&storage, &mut workspace, &key)
+        .map_err(|_| "Failed to unlock vault".to_string())?;
+    Ok((storage, workspace))
+}
+
+fn audit(kind: AuditKind, detail: String) {
+    if let Ok(log) = AuditLog::new() {
+        log.record(kind, Some(detail));
+    }
+}
+
+fn execute(command: Command) -> Result<(), String> {
+    match command {
+        Command::Generate { length } => {
+            let options = PasswordOptions {
+                length,
+                ..PasswordOptions::default()
+            };
+            println!("{}", generate_password(&options)?);
+        }
+        Command::List => {
+            let (_, workspace) = open()?;
+            let collation = Collation::system();
+            let mut logins: Vec<&Entry> = workspace
+                .credentials
+                .iter()
+                .filter(|e| e.is_login())
+                .collect();
+            logins.sort_by(|a, b| collation.compare(&a.title, &b.title));
+            for entry in logins {
+                println!("{}\t{}\t{}", entry.id, entry.title, entry.username);
+            }
+        }
+        Command::Show { query, password } => {
+            let (_, mut workspace) = open()?;
+            let entry = find(&workspace.credentials, &query)
+                .cloned()
+                .ok_or_else(|| format!("Credential '{}' not found", query))?;
+            println!("title: {}", entry.title);
+            println!("username: {}", entry.username);
+            if let Some(url) = &entry.url {
+                println!("url: {}", url);
+            }
+            if password {
+                workspace.reveal(&entry)?;
+                audit(
+                    AuditKind::Reveal,
+                    format!("{} from the command line", entry.id),
+                );
+                println!("password: {}", entry.password);
+            }
+        }
+        Command::Get { query, field } => {
+            let (_, mut workspace) = open()?;
+            let id = find(&workspace.credentials, &query)
+                .map(|e| e.id.clone())
+                .ok_or_else(|| format!("Credential '{}' not found", query))?;
+            let value = crate::vault::entries::get_field(&mut workspace, &id, &field)?;
+            audit(
+                AuditKind::Reveal,
+                format!("{} of {} from the command line", field, id),
+            );
+            println!("{}", value);
+        }
+        Command::Add {
+            title,
+            username,
+            url,
+            generate,
+        } => {
+            crate::commands::credential::validate_title(&title).map_err(|e| e.to_string())?;
+            let (storage, mut workspace) = open()?;
+            let password = if generate {
+                generate_password(&PasswordOptions::default())?
+            } else {
+                read_line("Password for the new credential: ")?
+            };
+            crate::commands::credential::validate_entry_fields(
+                &title,
+                &username,
+                &password,
+                url.as_ref(),
+                &EntryKind::Login,
+            )
+            .map_err(|e| e.to_string())?;
+            let id = uuid::Uuid::new_v4().to_string();
+            crate::vault::entries::add(
+                &mut workspace,
+                &storage,
+                Entry {
+                    id: id.clone(),
+                    title,
+                    username,
+                    password,
+                    url,
+                    ..Default::default()
+                },
+            )?;
+            println!("{}", id);
+        }
+        Command::Export { path } => {
+            if path.extension().and_then(|e| e.to_str()) != Some(PORTABLE_EXTENSION) {
+                return Err(format!(
+                    "Invalid file: export to a .{} file",
+                    PORTABLE_EXTENSION
+                ));
+            }
+            // Unlocking first proves the caller may take the Vault; the copy stays encrypted.
+            let (storage, _) = open()?;
+            let json = serde_json::to_string_pretty(&storage.read()?)
+                .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+            std::fs::write(&path, json).map_err(|e| format!("Failed to write export: {}", e))?;
+            audit(
+                AuditKind::Export,
+                format!("vault to {} from the command line", path.display()),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Runs one command and returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    match parse(args).and_then(execute) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("latch: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_flags() {
+        assert_eq!(parse(&args("list")), Ok(Command::List));
+        assert_eq!(
+            parse(&args("show --password github")),
+            Ok(Command::Show {
+                query: "github".to_string(),
+                password: true,
+            })
+        );
+        assert_eq!(
+            parse(&args("add GitHub octo https://github.com --generate")),
+            Ok(Command::Add {
+                title: "GitHub".to_string(),
+                username: "octo".to_string(),
+                url: Some("https://github.com".to_string()),
+                generate: true,
+            })
+        );
+        assert_eq!(
+            parse(&args("generate 24")),
+            Ok(Command::Generate { length: 24 })
+        );
+        assert_eq!(
+            parse(&args("get deploy-key secret")),
+            Ok(Command::Get {
+                query: "deploy-key".to_string(),
+                field: "secret".to_string(),
+            })
+        );
+        assert!(parse(&args("get deploy-key")).is_err());
+        assert!(parse(&args("generate many")).is_err());
+        assert!(parse(&args("show github --force")).is_err());
+        assert!(parse(&args("show")).is_err());
+    }
+
+    #[test]
+    fn finds_by_id_then_title() {
+        let entry = |id: &str, title: &str| Entry {
+            id: id.to_string(),
+            title: title.to_string(),
+            username: "user".to_string(),
+            password: "pw".to_string(),
+            ..Default::default()
+        };
+        let entries = vec![entry("1", "GitHub"), entry("github", "Work")];
+
+        assert_eq!(find(&entries, "github").unwrap().id, "github");
+        assert_eq!(find(&entries, "GITHUB").unwrap().id, "1");
+        assert!(find(&entries, "gitlab").is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+pub const DEFAULT_CLIPBOARD_CLEAR_SECS: u64 = 30;
+pub const MAX_CLIPBOARD_CLEAR_SECS: u64 = 10 * 60;
+
+/// The parts of the OS clipboard needed to copy a secret and clear it later.
+pub trait Clipboard {
+    fn write_text(&self, text: &str) -> Result<(), String>;
+    fn read_text(&self) -> Result<String, String>;
+    fn clear(&self) -> Result<(), String>;
+}
+
+/// The OS clipboard. Kept open for the life of the app because on X11 and Wayland the
+/// copied text is only served while the process that copied it holds the clipboard.
+#[derive(Default)]
+pub struct SystemClipboard(Mutex<Option<arboard::Clipboard>>);
+
+impl SystemClipboard {
+    fn with<T>(
+        &self,
+        op: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, arboard::Error> {
+        let mut guard = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let clipboard = match guard.as_mut() {
+            Some(clipboard) => clipboard,
+            None => guard.insert(arboard::Clipboard::new()?),
+        };
+        op(clipboard)
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    /// Marks the text as sensitive so clipboard history and sync tools skip it:
+    /// `ExcludeClipboardContentFromMonitorProcessing` on Windows,
+    /// `org.nspasteboard.ConcealedType` on macOS and the KDE password manager hint on
+    /// Linux.
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.with(|clipboard| {
+            let set = clipboard.set();
+            #[cfg(windows)]
+            let set = arboard::SetExtWindows::exclude_from_monitoring(set);
+            #[cfg(target_os = "macos")]
+            let set = arboard::SetExtApple::exclude_from_history(set);
+            #[cfg(all(unix, not(target_os = "macos")))]
+            let set = arboard::SetExtLinux::exclude_from_history(set);
+            set.text(text)
+        })
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+    }
+
+    fn read_text(&self) -> Result<String, String> {
+        self.with(|clipboard| clipboard.get_text())
+            .map_err(|e| format!("Failed to read clipboard: {}", e))
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.with(|clipboard| clipboard.clear())
+            .map_err(|e| format!("Failed to clear clipboard: {}", e))
+    }
+}
+
+/// Fingerprint of a copied secret, kept instead of the secret itself until the
+/// clipboard is cleared.
+pub fn fingerprint(text: &str) -> [u8; 32] {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+/// Clears the clipboard only if it still holds the copied value, so anything the
+/// user copied since is left alone. Returns whether it was cleared.
+pub fn clear_if_unchanged(clipboard: &impl Clipboard, copied: &[u8; 32]) -> Result<bool, String> {
+    // Unreadable contents (an image, say) can't be the secret we put there.
+    let Ok(current) = clipboard.read_text() else {
+        return Ok(false);
+    };
+    if fingerprint(&current) != *copied {
+        return Ok(false);
+    }
+    clipboard.clear()?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct FakeClipboard(RefCell<String>);
+
+    impl Clipboard for FakeClipboard {
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            *self.0.borrow_mut() = text.to_string();
+            Ok(())
+        }
+
+        fn read_text(&self) -> Result<String, String> {
+            Ok(self.0.borrow().clone())
+        }
+
+        fn clear(&self) -> Result<(), String> {
+            self.0.borrow_mut().clear();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn clears_only_the_value_that_was_copied() {
+        let clipboard = FakeClipboard(RefCell::new(String::new()));
+        clipboard.write_text("hunter2").unwrap();
+        let copied = fingerprint("hunter2");
+
+        assert!(clear_if_unchanged(&clipboard, &copied).unwrap());
+        assert_eq!(clipboard.read_text().unwrap(), "");
+
+        clipboard.write_text("hunter2").unwrap();
+        clipboard.write_text("something else").unwrap();
+        assert!(!clear_if_unchanged(&clipboard, &copied).unwrap());
+        assert_eq!(clipboard.read_text().unwrap(), "something else");
+    }
+}
@@ -0,0 +1,89 @@
+use crate::aliases::{Alias, AliasAccount, AliasClient};
+use crate::commands::VaultState;
+use crate::error::LatchError;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+/// The alias service signed in to for this run of the app. The frontend keeps the
+/// account in the OS keychain and connects again with it on each launch.
+#[derive(Default)]
+pub struct AliasState(pub Mutex<Option<Arc<AliasClient>>>);
+
+impl AliasState {
+    fn client(&self) -> Result<Arc<AliasClient>, String> {
+        self.0
+            .lock()
+            .map_err(|_| "Alias service is temporarily unavailable")?
+            .clone()
+            .ok_or_else(|| "Connect an alias service in Settings first".to_string())
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_alias_service(
+    account: AliasAccount,
+    aliases: State<'_, AliasState>,
+) -> Result<(), LatchError> {
+    let client = AliasClient::new(account)?;
+    *aliases
+        .0
+        .lock()
+        .map_err(|_| "Alias service is temporarily unavailable")? = Some(Arc::new(client));
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn disconnect_alias_service(aliases: State<'_, AliasState>) -> Result<(), LatchError> {
+    *aliases
+        .0
+        .lock()
+        .map_err(|_| "Alias service is temporarily unavailable")? = None;
+
+    Ok(())
+}
+
+/// Mints a fresh alias to use as the username of a new Login, noting `for_site`
+/// (its domain) with the service. Only while the Vault is unlocked.
+#[tauri::command]
+#[specta::specta]
+pub async fn create_alias(
+    for_site: Option<String>,
+    vault_state: State<'_, VaultState>,
+    aliases: State<'_, AliasState>,
+) -> Result<Alias, LatchError> {
+    vault_state.lock(|_, workspace| workspace.check_session())?;
+    let client = aliases.client()?;
+    let alias = client.create(for_site.as_deref()).await?;
+
+    Ok(alias)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_aliases(
+    vault_state: State<'_, VaultState>,
+    aliases: State<'_, AliasState>,
+) -> Result<Vec<Alias>, LatchError> {
+    vault_state.lock(|_, workspace| workspace.check_session())?;
+    let client = aliases.client()?;
+
+    Ok(client.list().await?)
+}
+
+/// Stops forwarding mail sent to an alias, e.g. once its Login is deleted.
+#[tauri::command]
+#[specta::specta]
+pub async fn disable_alias(
+    id: String,
+    vault_state: State<'_, VaultState>,
+    aliases: State<'_, AliasState>,
+) -> Result<(), LatchError> {
+    vault_state.lock(|_, workspace| workspace.check_session())?;
+    let client = aliases.client()?;
+    client.disable(&id).await?;
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::audit::{AuditEvent, AuditKind, AuditLog};
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::hooks::HookEvent;
+use crate::reauth::SensitiveAction;
+use crate::AuthState;
+use serde::Serialize;
+use specta::Type;
+use std::path::PathBuf;
+use tauri::State;
+
+#[derive(Debug, Serialize, Type)]
+pub struct AuditLogView {
+    pub events: Vec<AuditEvent>,
+    /// Lines that could not be decrypted (corrupted or written under another key).
+    pub unreadable: usize,
+}
+
+/// Reads the audit log. A duress Session sees an empty log rather than an error,
+/// so it can't tell that the real one exists.
+fn read_events(state: &VaultState, audit: &AuditLog) -> Result<(Vec<AuditEvent>, usize), String> {
+    state.lock(|_, workspace| {
+        workspace.check_session()?;
+        if workspace.decoy {
+            return Ok((Vec::new(), 0));
+        }
+        let key = workspace.session_key.as_deref().ok_or("Vault is locked")?;
+        audit.read(key)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_audit_log(
+    state: State<'_, VaultState>,
+    audit: State<'_, AuditLog>,
+) -> Result<AuditLogView, LatchError> {
+    let (events, unreadable) = read_events(&state, &audit)?;
+
+    Ok(AuditLogView { events, unreadable })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_audit_log(
+    path: String,
+    state: State<'_, VaultState>,
+    audit: State<'_, AuditLog>,
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+) -> Result<usize, LatchError> {
+    let settings = settings.get();
+    state.lock(|_, workspace| {
+        auth.reauth
+            .check(&settings.reauth, SensitiveAction::Export, workspace)
+    })?;
+    let (events, _) = read_events(&state, &audit)?;
+    let json = serde_json::to_string_pretty(&events)
+        .map_err(|e| format!("Failed to serialize audit log: {}", e))?;
+    let path = PathBuf::from(&path);
+    std::fs::write(&path, json).map_err(|e| format!("Failed to export audit log: {}", e))?;
+    audit.record(AuditKind::Export, Some("audit log".to_string()));
+    crate::hooks::run(&settings.hooks, HookEvent::ExportCompleted, &path);
+
+    Ok(events.len())
+}
@@ -0,0 +1,60 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::autotype::{SystemTypist, AUTOTYPE_SHORTCUT};
+use crate::commands::VaultState;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+/// Time for the user to let go of the shortcut, so Ctrl and Alt don't combine with
+/// what gets typed.
+const MODIFIER_RELEASE_DELAY: Duration = Duration::from_millis(300);
+
+/// Registers or drops the auto-type shortcut to match the setting.
+pub fn sync_shortcut(app_handle: &AppHandle, enabled: bool) {
+    let shortcuts = app_handle.global_shortcut();
+    let result = match (enabled, shortcuts.is_registered(AUTOTYPE_SHORTCUT)) {
+        (true, false) => shortcuts.register(AUTOTYPE_SHORTCUT),
+        (false, true) => shortcuts.unregister(AUTOTYPE_SHORTCUT),
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to update auto-type shortcut: {}", e);
+    }
+}
+
+fn autotype_focused_window(app_handle: &AppHandle) -> Result<(), String> {
+    let window_title = crate::autotype::focused_window_title()?;
+    let (entry_id, keystrokes) = app_handle.state::<VaultState>().lock(|_, workspace| {
+        workspace.check_session()?;
+        workspace.refresh();
+        let entry = crate::autotype::best_match(&workspace.credentials, &window_title)
+            .cloned()
+            .ok_or_else(|| format!("No credential matches \"{}\"", window_title))?;
+        workspace.reveal(&entry)?;
+        Ok((entry.id.clone(), crate::autotype::keystrokes(&entry)?))
+    })?;
+    app_handle.state::<AuditLog>().record(
+        AuditKind::Reveal,
+        Some(format!("password of {} by auto-type", entry_id)),
+    );
+
+    std::thread::sleep(MODIFIER_RELEASE_DELAY);
+    crate::autotype::type_out(&mut SystemTypist::new()?, &keystrokes)
+}
+
+/// The auto-type shortcut was pressed: type the best matching Credential into the
+/// focused window. With the Vault locked, bring the app up to unlock it instead.
+pub fn trigger(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = autotype_focused_window(&app_handle) {
+            eprintln!("Auto-type failed: {}", e);
+            if e == "Vault is locked" || e == "Session expired" {
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+        }
+    });
+}
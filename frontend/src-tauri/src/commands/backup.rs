@@ -0,0 +1,212 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::auth::oauth::identity_provider;
+use crate::auth::provider::Credentials;
+use crate::commands::{OpenedFileState, SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::hooks::HookEvent;
+use crate::settings::Settings;
+use crate::vault::backup::{BackupInfo, BackupStore, Destructive};
+use crate::vault::merge::{MergeReport, MergeStrategy};
+use crate::vault::portable::{ImportMode, PortableFile, PortableSummary};
+use crate::vault::{storage::VaultStorage, workspace::Workspace};
+use serde::Serialize;
+use specta::Type;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// What a destructive command returns: its own result and the safety backup
+/// taken before it, so the user can undo it from the backups list.
+#[derive(Debug, Serialize, Type)]
+pub struct Guarded<T> {
+    pub value: T,
+    pub safety_backup: Option<String>,
+}
+
+/// The policy every destructive command goes through: a full backup of the Vault
+/// first, recorded in the audit log. If the backup fails, the command does not run.
+pub fn before_destructive(
+    audit: &AuditLog,
+    storage: &VaultStorage,
+    workspace: &Workspace,
+    operation: Destructive,
+) -> Result<Option<String>, String> {
+    let id = crate::vault::backup::safety_backup(storage)?;
+    if let (Some(id), false) = (&id, workspace.decoy) {
+        audit.record(
+            AuditKind::SafetyBackup,
+            Some(format!("{} before {}", id, operation.describe())),
+        );
+    }
+    Ok(id)
+}
+
+/// Backs up the Vault after a successful save and runs any backup hooks. Backup
+/// failures never fail the save itself.
+pub fn snapshot_after_save(storage: &VaultStorage, workspace: &Workspace, settings: &Settings) {
+    if storage.is_ephemeral() {
+        return;
+    }
+    let result = BackupStore::for_vault(storage).and_then(|backups| {
+        let Some(id) = backups.snapshot(storage, workspace, settings.backup_mode)? else {
+            return Ok(());
+        };
+        crate::vault::backup::record_backup(storage)?;
+        crate::hooks::run(
+            &settings.hooks,
+            HookEvent::BackupCompleted,
+            backups.path_for(&id),
+        );
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to back up vault: {}", e);
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_backups(state: State<'_, VaultState>) -> Result<Vec<BackupInfo>, LatchError> {
+    let backups = state.lock(|storage, _| BackupStore::for_vault(storage)?.list())?;
+
+    Ok(backups)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_from_backup(
+    backup_id: String,
+    state: State<'_, VaultState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Guarded<usize>, LatchError> {
+    let restored = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let backups = BackupStore::for_vault(storage)?;
+        let safety_backup =
+            before_destructive(&audit, storage, workspace, Destructive::RestoreBackup)?;
+        let value = crate::vault::backup::restore(storage, workspace, &backups, &backup_id)?;
+        Ok(Guarded {
+            value,
+            safety_backup,
+        })
+    })?;
+
+    Ok(restored)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_pending_vault_file(
+    opened: State<'_, OpenedFileState>,
+) -> Result<Option<PortableSummary>, LatchError> {
+    let pending = opened
+        .0
+        .lock()
+        .map_err(|_| "Opened file state temporarily unavailable")?
+        .clone();
+    let summary = pending.and_then(|path| {
+        PortableFile::read(&path)
+            .ok()
+            .map(|file| file.summary(&path))
+    });
+
+    Ok(summary)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn open_vault_file(
+    path: String,
+    opened: State<'_, OpenedFileState>,
+) -> Result<PortableSummary, LatchError> {
+    let path = PathBuf::from(path);
+    let file = PortableFile::read(&path)?;
+    let summary = file.summary(&path);
+
+    *opened
+        .0
+        .lock()
+        .map_err(|_| "Opened file state temporarily unavailable")? = Some(path);
+
+    Ok(summary)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn import_vault_file(
+    mode: ImportMode,
+    password: Option<String>,
+    opened: State<'_, OpenedFileState>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<usize, LatchError> {
+    let path = opened
+        .0
+        .lock()
+        .map_err(|_| "Opened file state temporarily unavailable")?
+        .clone()
+        .ok_or("No file has been opened")?;
+    let file = PortableFile::read(&path)?;
+    let settings = settings.get();
+
+    let imported = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let key = match password {
+            Some(password) => crate::auth::provider::vault_key(
+                file.kdf(),
+                file.salt(),
+                &Credentials::Password(&password),
+            )?,
+            None => **workspace.session_key.as_ref().ok_or("Vault is locked")?,
+        };
+        let entries = file.decrypt_entries(&key)?;
+        let count = crate::vault::portable::import(storage, workspace, entries, mode)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(count)
+    })?;
+
+    if let Ok(mut pending) = opened.0.lock() {
+        *pending = None;
+    }
+
+    Ok(imported)
+}
+
+/// Merges the Credentials of another Vault file, unlocked with `credential`: its
+/// master password, an OAuth ID token, or a stored key in hex.
+#[tauri::command]
+#[specta::specta]
+pub async fn merge_vault(
+    path: String,
+    credential: String,
+    strategy: MergeStrategy,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Guarded<MergeReport>, LatchError> {
+    state.lock(|_, workspace| workspace.check_session())?;
+    // An OAuth Vault's credential is its provider's token, vouched for before
+    // anything is read.
+    let identity = match crate::vault::merge::identity_provider(Path::new(&path))? {
+        Some(kind) => Some(identity_provider(kind).validate(&credential).await?),
+        None => None,
+    };
+    let incoming =
+        crate::vault::merge::read_entries(Path::new(&path), &credential, identity.as_ref())?;
+    let settings = settings.get();
+
+    let merged = state.lock(|storage, workspace| {
+        if Path::new(&path) == storage.path {
+            return Err("Invalid vault: choose a vault other than the open one".to_string());
+        }
+        let safety_backup =
+            before_destructive(&audit, storage, workspace, Destructive::MergeVault)?;
+        let value = crate::vault::merge::merge(storage, workspace, incoming, strategy)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(Guarded {
+            value,
+            safety_backup,
+        })
+    })?;
+
+    Ok(merged)
+}
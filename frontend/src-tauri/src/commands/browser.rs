@@ -0,0 +1,37 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::browser::associations::{Association, AssociationStore};
+use crate::browser::server::BrowserState;
+use crate::error::LatchError;
+use tauri::State;
+
+/// The user's answer to a `browser://association-requested` prompt.
+#[tauri::command]
+#[specta::specta]
+pub async fn resolve_browser_association(
+    request_id: String,
+    approved: bool,
+    browser: State<'_, BrowserState>,
+) -> Result<(), LatchError> {
+    browser.resolve(&request_id, approved)?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_browser_associations() -> Result<Vec<Association>, LatchError> {
+    Ok(AssociationStore::new()?.list()?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_browser_association(
+    id: String,
+    audit: State<'_, AuditLog>,
+) -> Result<(), LatchError> {
+    AssociationStore::new()?.remove(&id)?;
+    audit.record(
+        AuditKind::SettingsChanged,
+        Some(format!("browser extension {} removed", id)),
+    );
+    Ok(())
+}
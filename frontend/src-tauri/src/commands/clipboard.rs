@@ -0,0 +1,61 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::clipboard::{Clipboard, SystemClipboard};
+use crate::commands::credential::is_secret_field;
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::reauth::SensitiveAction;
+use crate::AuthState;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use zeroize::Zeroizing;
+
+/// Copies a Credential field to the OS clipboard, hidden from clipboard history, and
+/// clears it after the configured delay, unless something else has been copied in the meantime. Returns the delay
+/// in seconds, 0 if clearing is turned off. Secret fields need the same reveal
+/// confirmation as `request_secret`.
+#[tauri::command]
+#[specta::specta]
+pub async fn copy_secret_to_clipboard(
+    entry_id: String,
+    field: String,
+    app_handle: AppHandle,
+) -> Result<u64, LatchError> {
+    copy_field(&app_handle, &entry_id, &field)
+}
+
+/// `copy_secret_to_clipboard` for callers without a window, like the tray menu.
+pub fn copy_field(app_handle: &AppHandle, entry_id: &str, field: &str) -> Result<u64, LatchError> {
+    let state = app_handle.state::<VaultState>();
+    let policy = app_handle.state::<SettingsState>().get().reauth;
+    let auth = app_handle.state::<AuthState>();
+    let secret = Zeroizing::new(state.lock(|_, workspace| {
+        if is_secret_field(field) {
+            auth.reauth
+                .check(&policy, SensitiveAction::RevealPassword, workspace)?;
+        }
+        crate::vault::entries::get_field(workspace, entry_id, field)
+    })?);
+    app_handle.state::<SystemClipboard>().write_text(&secret)?;
+    app_handle.state::<AuditLog>().record(
+        AuditKind::Reveal,
+        Some(format!("{} of {} to clipboard", field, entry_id)),
+    );
+
+    let clear_after = app_handle
+        .state::<SettingsState>()
+        .get()
+        .clipboard_clear_secs;
+    if clear_after > 0 {
+        let copied = crate::clipboard::fingerprint(&secret);
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(clear_after)).await;
+            let clipboard = app_handle.state::<SystemClipboard>();
+            if let Err(e) = crate::clipboard::clear_if_unchanged(clipboard.inner(), &copied) {
+                eprintln!("Failed to clear clipboard: {}", e);
+            }
+        });
+    }
+
+    Ok(clear_after)
+}
@@ -1,32 +1,89 @@
-use crate::commands::VaultState;
-use serde_json::json;
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::backup::{before_destructive, snapshot_after_save, Guarded};
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::icons::IconCache;
+use crate::reauth::SensitiveAction;
+use crate::settings::Settings;
+use crate::vault::actions::EntryAction;
+use crate::vault::archive::ArchivedPreview;
+use crate::vault::backup::Destructive;
+use crate::vault::changes::EntryChange;
+use crate::vault::collation::Collation;
+use crate::vault::history::Operation;
+use crate::vault::reference::ReferenceSource;
+use crate::vault::reuse::{self, SavedEntry};
+use crate::vault::server::ServerProtocol;
+use crate::vault::storage::VaultStorage;
+use crate::vault::verification::UnverifiedEntry;
+use crate::vault::workspace::Workspace;
+use crate::vault::{Entry, EntryIcon, EntryKind, EntryPreview};
+use crate::vault_health::expiry::PasswordExpiry;
+use crate::vault_health::watch::AlertChannel;
+use crate::AuthState;
 use tauri::State;
 
-fn validate_entry_fields(
+pub(crate) fn validate_title(title: &str) -> Result<(), LatchError> {
+    if title.trim().is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Title cannot be empty".to_string(),
+        ));
+    }
+    if title.len() > 256 {
+        return Err(LatchError::InvalidInput(
+            "Title is too long (max 256 characters)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a Credential of `kind` needs a username and a password. Other kinds
+/// keep what identifies them in `kind`, and a secret only if they have one.
+fn required_fields(kind: &EntryKind) -> (bool, bool) {
+    match kind {
+        EntryKind::BankAccount { .. } | EntryKind::Server { .. } | EntryKind::Reference { .. } => {
+            (false, false)
+        }
+        EntryKind::SshKey { .. } | EntryKind::ApiKey { .. } => (false, true),
+        _ => (true, true),
+    }
+}
+
+pub(crate) fn validate_entry_fields(
     title: &str,
     username: &str,
     password: &str,
     url: Option<&String>,
-) -> Result<(), String> {
-    if title.trim().is_empty() {
-        return Err("Title cannot be empty".to_string());
-    }
-    if title.len() > 256 {
-        return Err("Title is too long (max 256 characters)".to_string());
+    kind: &EntryKind,
+) -> Result<(), LatchError> {
+    validate_title(title)?;
+    let (needs_username, needs_password) = required_fields(kind);
+    if matches!(kind, EntryKind::Reference { .. }) && !password.is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Invalid secret: a reference only records where the secret is kept".to_string(),
+        ));
     }
 
-    if username.trim().is_empty() {
-        return Err("Username cannot be empty".to_string());
+    if needs_username && username.trim().is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Username cannot be empty".to_string(),
+        ));
     }
     if username.len() > 256 {
-        return Err("Username is too long (max 256 characters)".to_string());
+        return Err(LatchError::InvalidInput(
+            "Username is too long (max 256 characters)".to_string(),
+        ));
     }
 
-    if password.trim().is_empty() {
-        return Err("Password cannot be empty".to_string());
+    if needs_password && password.trim().is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Password cannot be empty".to_string(),
+        ));
     }
     if password.len() > 1024 {
-        return Err("Password is too long (max 1024 characters)".to_string());
+        return Err(LatchError::InvalidInput(
+            "Password is too long (max 1024 characters)".to_string(),
+        ));
     }
 
     if let Some(url_val) = url {
@@ -35,10 +92,12 @@ fn validate_entry_fields(
                 Ok(parsed) => {
                     let scheme = parsed.scheme();
                     if scheme != "http" && scheme != "https" {
-                        return Err("URL must use http or https scheme".to_string());
+                        return Err(LatchError::InvalidInput(
+                            "URL must use http or https scheme".to_string(),
+                        ));
                     }
                 }
-                Err(e) => return Err(format!("Invalid URL: {}", e)),
+                Err(e) => return Err(LatchError::InvalidInput(format!("Invalid URL: {}", e))),
             }
         }
     }
@@ -46,38 +105,156 @@ fn validate_entry_fields(
     Ok(())
 }
 
+/// Blank means no custom icon. Swatches are stored lowercase.
+fn validate_entry_icon(icon: Option<EntryIcon>) -> Result<Option<EntryIcon>, LatchError> {
+    match icon {
+        Some(icon) if icon.is_blank() => Ok(None),
+        Some(EntryIcon::Emoji(emoji)) => {
+            let emoji = emoji.trim().to_string();
+            // A ZWJ family or flag runs to several chars; words are what this keeps out.
+            if emoji.chars().count() > 8 || emoji.chars().any(|c| c.is_ascii() || c.is_control()) {
+                return Err(LatchError::InvalidInput(
+                    "Invalid icon: use a single emoji".to_string(),
+                ));
+            }
+            Ok(Some(EntryIcon::Emoji(emoji)))
+        }
+        Some(EntryIcon::Color(color)) => {
+            let hex = color.trim().strip_prefix('#').unwrap_or("");
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(LatchError::InvalidInput(
+                    "Invalid icon color: use #rrggbb".to_string(),
+                ));
+            }
+            Ok(Some(EntryIcon::Color(format!(
+                "#{}",
+                hex.to_ascii_lowercase()
+            ))))
+        }
+        None => Ok(None),
+    }
+}
+
+/// A cooldown longer than a Session would never expire before the next unlock anyway.
+fn validate_reveal_cooldown(secs: Option<u64>) -> Result<Option<u64>, LatchError> {
+    if secs.is_some_and(|secs| secs > crate::vault::SESSION_TIMEOUT_SECS) {
+        return Err(LatchError::InvalidInput(format!(
+            "Reveal cooldown cannot exceed the {} minute session limit",
+            crate::vault::SESSION_TIMEOUT_SECS / 60
+        )));
+    }
+    Ok(secs)
+}
+
+/// Blank means the default sequence.
+fn validate_autotype_sequence(sequence: Option<String>) -> Result<Option<String>, LatchError> {
+    match sequence {
+        Some(sequence) if sequence.trim().is_empty() => Ok(None),
+        Some(sequence) => {
+            crate::autotype::validate(&sequence).map_err(LatchError::InvalidInput)?;
+            Ok(Some(sequence))
+        }
+        None => Ok(None),
+    }
+}
+
 #[tauri::command]
-pub async fn search_entries(query: String, state: State<'_, VaultState>) -> Result<String, String> {
-    let results = state.lock(|_, workspace| crate::vault::search::search(workspace, &query))?;
-    Ok(json!({
-        "status": "success",
-        "entries": results
-    })
-    .to_string())
+#[specta::specta]
+pub async fn search_entries(
+    query: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<EntryPreview>, LatchError> {
+    let collation = Collation::system();
+    let options = settings.get().search;
+    let results = state.lock(|_, workspace| {
+        crate::vault::search::search(workspace, &query, &collation, &options)
+    })?;
+    Ok(results)
+}
+
+/// Ranking is only a convenience: a use that can't be saved doesn't fail the
+/// reveal it came with.
+fn record_use(storage: &VaultStorage, workspace: &mut Workspace, entry_id: &str) {
+    if let Err(e) = crate::vault::entries::record_use(workspace, storage, entry_id) {
+        eprintln!("Failed to record credential use: {}", e);
+    }
+}
+
+/// Fields that hold a secret, and so need reveal confirmation when the policy
+/// asks for it.
+pub(crate) fn is_secret_field(field: &str) -> bool {
+    matches!(field, "password" | "pin" | "secret" | "private_key")
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn request_secret(
     entry_id: String,
     field: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let secret = state
-        .lock(|_, workspace| crate::vault::entries::get_field(workspace, &entry_id, &field))?;
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<String, LatchError> {
+    let policy = settings.get().reauth;
+    let secret = state.lock(|storage, workspace| {
+        if is_secret_field(&field) {
+            auth.reauth
+                .check(&policy, SensitiveAction::RevealPassword, workspace)?;
+        }
+        let secret = crate::vault::entries::get_field(workspace, &entry_id, &field)?;
+        record_use(storage, workspace, &entry_id);
+        Ok(secret)
+    })?;
+    audit.record(
+        AuditKind::Reveal,
+        Some(format!("{} of {}", field, entry_id)),
+    );
 
-    Ok(json!({"status": "success", "value": secret}).to_string())
+    Ok(secret)
 }
 
+/// What the palette can offer for this entry, based on the fields it actually has.
 #[tauri::command]
+#[specta::specta]
+pub async fn get_entry_actions(
+    entry_id: String,
+    state: State<'_, VaultState>,
+) -> Result<Vec<EntryAction>, LatchError> {
+    let actions =
+        state.lock(|_, workspace| crate::vault::actions::available(workspace, &entry_id))?;
+    Ok(actions)
+}
+
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
 pub async fn add_entry(
     title: String,
     username: String,
     password: String,
     url: Option<String>,
     icon_url: Option<String>,
+    reveal_cooldown_secs: Option<u64>,
+    autotype_sequence: Option<String>,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    validate_entry_fields(&title, &username, &password, url.as_ref())?;
+    settings: State<'_, SettingsState>,
+    icons: State<'_, IconCache>,
+) -> Result<SavedEntry, LatchError> {
+    validate_entry_fields(
+        &title,
+        &username,
+        &password,
+        url.as_ref(),
+        &EntryKind::Login,
+    )?;
+    let reveal_cooldown_secs = validate_reveal_cooldown(reveal_cooldown_secs)?.unwrap_or(0);
+    let autotype_sequence = validate_autotype_sequence(autotype_sequence)?;
+    let icon_url = match (icon_url, &url) {
+        (None, Some(url)) => icons.icon_for(&state.session_key()?, url, false).await,
+        (icon_url, _) => icon_url,
+    };
 
     let id = uuid::Uuid::new_v4().to_string();
     let entry = crate::vault::Entry {
@@ -87,28 +264,244 @@ pub async fn add_entry(
         password,
         url,
         icon_url,
+        reveal_cooldown_secs,
+        autotype_sequence,
+        ..Default::default()
+    };
+
+    let settings = settings.get();
+    let reused_by = state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(reuse::reused_by(workspace, &id))
+    })?;
+
+    Ok(SavedEntry { id, reused_by })
+}
+
+/// Records where a secret kept outside Latch lives, without the secret itself.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_secret_reference(
+    title: String,
+    source: ReferenceSource,
+    location: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<String, LatchError> {
+    validate_title(&title)?;
+    crate::vault::reference::validate(source, &location).map_err(LatchError::InvalidInput)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let entry = Entry {
+        id: id.clone(),
+        title,
+        username: String::new(),
+        password: String::new(),
+        kind: EntryKind::Reference { source, location },
+        ..Default::default()
     };
 
-    state.lock(|storage, workspace| crate::vault::entries::add(workspace, storage, entry))?;
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
 
-    Ok(json!({"status": "success", "id": id}).to_string())
+    Ok(id)
 }
 
+/// Saves a bank account as its own kind of Credential, with the PIN, if any, as
+/// its secret (see `vault::bank`).
 #[tauri::command]
+#[specta::specta]
+pub async fn add_bank_account(
+    title: String,
+    account_number: String,
+    routing_code: Option<String>,
+    swift: Option<String>,
+    pin: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<String, LatchError> {
+    validate_title(&title)?;
+    let pin = pin.filter(|pin| !pin.is_empty());
+    let kind = crate::vault::bank::validate(&account_number, routing_code, swift, pin.as_deref())
+        .map_err(LatchError::InvalidInput)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let entry = Entry {
+        id: id.clone(),
+        title,
+        username: String::new(),
+        password: pin.unwrap_or_default(),
+        kind,
+        ..Default::default()
+    };
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(id)
+}
+
+/// Saves an API key as its own kind of Credential, with `secret` as its password.
+/// An `expires_at` (Unix time) turns on the expiry reminder for that date.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_api_key(
+    title: String,
+    key_id: String,
+    secret: String,
+    scopes: Vec<String>,
+    expires_at: Option<u64>,
+    url: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<String, LatchError> {
+    validate_title(&title)?;
+    let kind = crate::vault::api_key::validate(&key_id, &secret, scopes)
+        .map_err(LatchError::InvalidInput)?;
+    let expiry = expires_at
+        .map(|at| PasswordExpiry::until(at, crate::vault::unix_now()))
+        .transpose()?;
+    let url = url.filter(|url| !url.trim().is_empty());
+    if let Some(url) = &url {
+        url::Url::parse(url)
+            .map_err(|e| LatchError::InvalidInput(format!("Invalid URL: {}", e)))?;
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let entry = Entry {
+        id: id.clone(),
+        title,
+        username: String::new(),
+        password: secret,
+        url,
+        kind,
+        expiry,
+        ..Default::default()
+    };
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(id)
+}
+
+/// Saves a server to connect to, logging in with `password` or with the stored
+/// SSH key Credential `ssh_key_id` (see `vault::server`).
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_server(
+    title: String,
+    hostname: String,
+    port: Option<u16>,
+    protocol: ServerProtocol,
+    username: String,
+    password: Option<String>,
+    ssh_key_id: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<String, LatchError> {
+    validate_title(&title)?;
+    if username.len() > 256 {
+        return Err(LatchError::InvalidInput(
+            "Username is too long (max 256 characters)".to_string(),
+        ));
+    }
+    let password = password.unwrap_or_default();
+    if password.len() > 1024 {
+        return Err(LatchError::InvalidInput(
+            "Password is too long (max 1024 characters)".to_string(),
+        ));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let kind = crate::vault::server::validate(
+            &workspace.credentials,
+            &hostname,
+            port,
+            protocol,
+            ssh_key_id,
+        )?;
+        let entry = Entry {
+            id: id.clone(),
+            title,
+            username: username.trim().to_string(),
+            password,
+            kind,
+            ..Default::default()
+        };
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(id)
+}
+
+/// Saves a new canary for `service` and returns it, so the user can register the
+/// email and password there.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_canary_entry(
+    service: String,
+    email_domain: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Entry, LatchError> {
+    let entry = crate::vault_health::canary::generate(&service, email_domain.as_deref())?;
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry.clone())?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(entry)
+}
+
+#[tauri::command]
+#[specta::specta]
 pub async fn get_full_entry(
     entry_id: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let entry = state.lock(|_, workspace| crate::vault::entries::get_full(workspace, &entry_id))?;
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Entry, LatchError> {
+    let policy = settings.get().reauth;
+    let entry = state.lock(|storage, workspace| {
+        auth.reauth
+            .check(&policy, SensitiveAction::RevealPassword, workspace)?;
+        let entry = crate::vault::entries::get_full(workspace, &entry_id)?;
+        record_use(storage, workspace, &entry_id);
+        Ok(entry)
+    })?;
+    audit.record(AuditKind::Reveal, Some(entry_id));
 
-    Ok(json!({
-        "status": "success",
-        "entry": entry
-    })
-    .to_string())
+    Ok(entry)
 }
 
 #[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_entry(
     id: String,
     title: String,
@@ -116,31 +509,480 @@ pub async fn update_entry(
     password: String,
     url: Option<String>,
     icon_url: Option<String>,
+    reveal_cooldown_secs: Option<u64>,
+    autotype_sequence: Option<String>,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    validate_entry_fields(&title, &username, &password, url.as_ref())?;
+    settings: State<'_, SettingsState>,
+) -> Result<SavedEntry, LatchError> {
+    // What a save needs depends on the kind, which the caller leaves as it was.
+    let kind = state.lock(|_, workspace| {
+        Ok(workspace
+            .credentials
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.kind.clone())
+            .unwrap_or_default())
+    })?;
+    validate_entry_fields(&title, &username, &password, url.as_ref(), &kind)?;
+    let reveal_cooldown = validate_reveal_cooldown(reveal_cooldown_secs)?;
+    let keep_sequence = autotype_sequence.is_none();
+    let autotype_sequence = validate_autotype_sequence(autotype_sequence)?;
 
-    let entry = crate::vault::Entry {
+    let mut entry = crate::vault::Entry {
         id,
         title,
         username,
         password,
         url,
         icon_url,
+        reveal_cooldown_secs: reveal_cooldown.unwrap_or(0),
+        autotype_sequence,
+        ..Default::default()
     };
 
-    state.lock(|storage, workspace| crate::vault::entries::update(workspace, storage, entry))?;
+    let settings = settings.get();
+    let saved = state.lock(|storage, workspace| {
+        // Left out by the caller: keep whatever the Credential had.
+        if let Some(current) = workspace.credentials.iter().find(|e| e.id == entry.id) {
+            entry.kind = current.kind.clone();
+            entry.folder = current.folder.clone();
+            entry.breach_watch = current.breach_watch;
+            entry.password_policy = current.password_policy.clone();
+            // A new URL needs `refresh_icon`; the old icon would be for another site.
+            if entry.icon_url.is_none() && entry.url == current.url {
+                entry.icon_url = current.icon_url.clone();
+            }
+            if reveal_cooldown.is_none() {
+                entry.reveal_cooldown_secs = current.reveal_cooldown_secs;
+            }
+            if keep_sequence {
+                entry.autotype_sequence = current.autotype_sequence.clone();
+            }
+        }
+        let id = entry.id.clone();
+        crate::vault::entries::update(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(SavedEntry {
+            reused_by: reuse::reused_by(workspace, &id),
+            id,
+        })
+    })?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(saved)
+}
+
+/// Files the Credentials about to be deleted in the archive, when that is turned on.
+fn archive_before_delete(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    settings: &Settings,
+    ids: &[String],
+) -> Result<(), String> {
+    if !settings.archive_deleted {
+        return Ok(());
+    }
+    let doomed: Vec<Entry> = workspace
+        .credentials
+        .iter()
+        .filter(|e| ids.contains(&e.id))
+        .cloned()
+        .collect();
+    if doomed.is_empty() {
+        return Ok(());
+    }
+    crate::vault::archive::append(storage, workspace, doomed)
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn delete_entry(
     entry_id: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    state
-        .lock(|storage, workspace| crate::vault::entries::delete(workspace, storage, &entry_id))?;
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        archive_before_delete(
+            storage,
+            workspace,
+            &settings,
+            std::slice::from_ref(&entry_id),
+        )?;
+        crate::vault::entries::delete(workspace, storage, &entry_id)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Deletes several Credentials at once, after a safety backup.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_entries(
+    entry_ids: Vec<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Guarded<usize>, LatchError> {
+    if entry_ids.is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Invalid selection: choose credentials to delete".to_string(),
+        ));
+    }
+
+    let settings = settings.get();
+    let deleted = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let safety_backup =
+            before_destructive(&audit, storage, workspace, Destructive::DeleteEntries)?;
+        archive_before_delete(storage, workspace, &settings, &entry_ids)?;
+        let value = crate::vault::entries::delete_many(workspace, storage, &entry_ids)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(Guarded {
+            value,
+            safety_backup,
+        })
+    })?;
+
+    Ok(deleted)
+}
+
+/// Undoes the last add, edit or delete this Session, returning which it was; None
+/// if there is nothing to undo.
+#[tauri::command]
+#[specta::specta]
+pub async fn undo_last_operation(
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Option<Operation>, LatchError> {
+    let settings = settings.get();
+    let undone = state.lock(|storage, workspace| {
+        let undone = crate::vault::entries::undo(workspace, storage)?;
+        if undone.is_some() {
+            snapshot_after_save(storage, workspace, &settings);
+        }
+        Ok(undone)
+    })?;
+
+    Ok(undone)
+}
+
+/// Repeats the last undone operation; None if there is nothing to redo.
+#[tauri::command]
+#[specta::specta]
+pub async fn redo(
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Option<Operation>, LatchError> {
+    let settings = settings.get();
+    let redone = state.lock(|storage, workspace| {
+        let redone = crate::vault::entries::redo(workspace, storage)?;
+        if redone.is_some() {
+            snapshot_after_save(storage, workspace, &settings);
+        }
+        Ok(redone)
+    })?;
+
+    Ok(redone)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn search_archive(
+    query: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<ArchivedPreview>, LatchError> {
+    let collation = Collation::system();
+    let options = settings.get().search;
+    let found = state.lock(|storage, workspace| {
+        crate::vault::archive::search(storage, workspace, &query, &collation, &options)
+    })?;
+
+    Ok(found)
+}
+
+/// Moves an archived Credential back into the Vault.
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_archived_entry(
+    entry_id: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<EntryPreview, LatchError> {
+    let settings = settings.get();
+    let restored = state.lock(|storage, workspace| {
+        let entry = crate::vault::archive::restore(storage, workspace, &entry_id)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(entry)
+    })?;
+
+    Ok(restored.into())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_entry_verification(
+    entry_id: String,
+    status: crate::vault::verification::VerificationStatus,
+    state: State<'_, VaultState>,
+) -> Result<(), LatchError> {
+    state.lock(|storage, workspace| {
+        crate::vault::verification::mark(workspace, storage, &entry_id, status)
+    })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_unverified_entries(
+    state: State<'_, VaultState>,
+) -> Result<Vec<UnverifiedEntry>, LatchError> {
+    let entries = state.lock(|_, workspace| crate::vault::verification::unverified(workspace))?;
+
+    Ok(entries)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_entry_folder(
+    entry_id: String,
+    folder: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let folder = folder
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty());
+    if folder.as_ref().is_some_and(|f| f.len() > 64) {
+        return Err(LatchError::InvalidInput(
+            "Invalid folder: use at most 64 characters".to_string(),
+        ));
+    }
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_folder(workspace, storage, &entry_id, folder)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Shows an emoji or color swatch for the Credential instead of its site icon;
+/// None or blank goes back to the site icon.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_entry_icon(
+    entry_id: String,
+    icon: Option<EntryIcon>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let icon = validate_entry_icon(icon)?;
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_custom_icon(workspace, storage, &entry_id, icon)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Watches a Credential for breaches in the background, alerting through `channel`;
+/// None stops watching it.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_breach_watch(
+    entry_id: String,
+    channel: Option<AlertChannel>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_breach_watch(workspace, storage, &entry_id, channel)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Reminds the user to change the password every `interval_days`; None stops
+/// the reminders.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_password_expiry(
+    entry_id: String,
+    interval_days: Option<u32>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_expiry(workspace, storage, &entry_id, interval_days)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn snooze_password_expiry(
+    entry_id: String,
+    days: u32,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::snooze_expiry(workspace, storage, &entry_id, days)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Restarts the expiry interval for a password changed without editing it here.
+#[tauri::command]
+#[specta::specta]
+pub async fn mark_password_rotated(
+    entry_id: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::mark_rotated(workspace, storage, &entry_id)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Pins the Credential to the tray menu, or unpins it.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_entry_favorite(
+    entry_id: String,
+    favorite: bool,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_favorite(workspace, storage, &entry_id, favorite)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Makes the Credential share `parent_id`'s password, so changing that one
+/// changes both; None unlinks it, keeping the password it has.
+#[tauri::command]
+#[specta::specta]
+pub async fn link_entry(
+    entry_id: String,
+    parent_id: Option<String>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::link(workspace, storage, &entry_id, parent_id.as_deref())?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Fetches the Credential's site icon again, skipping the cache. Returns the new
+/// `icon_url`, the letter tile if the site can't be reached and none is cached.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_icon(
+    entry_id: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    icons: State<'_, IconCache>,
+) -> Result<Option<String>, LatchError> {
+    let url = state.lock(|_, workspace| {
+        workspace.check_session()?;
+        workspace
+            .credentials
+            .iter()
+            .find(|e| e.id == entry_id)
+            .map(|e| e.url.clone())
+            .ok_or_else(|| format!("Credential '{}' not found", entry_id))
+    })?;
+    let icon_url = match url {
+        Some(url) => icons.icon_for(&state.session_key()?, &url, true).await,
+        None => None,
+    };
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_icon(workspace, storage, &entry_id, icon_url.clone())?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(icon_url)
+}
+
+/// Sets the options `generate_password_for_entry` uses for this Credential, e.g. a
+/// bank that allows 12 characters and no symbols. None goes back to the defaults.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_password_policy(
+    entry_id: String,
+    policy: Option<crate::password_generator::PasswordOptions>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::set_password_policy(workspace, storage, &entry_id, policy)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// When each field of the Credential was edited, and in which Session, newest
+/// first. Passwords show as fingerprints.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_entry_history(
+    entry_id: String,
+    state: State<'_, VaultState>,
+) -> Result<Vec<EntryChange>, LatchError> {
+    let changes =
+        state.lock(|_, workspace| crate::vault::entries::history(workspace, &entry_id))?;
+
+    Ok(changes)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_folders(state: State<'_, VaultState>) -> Result<Vec<String>, LatchError> {
+    let collation = Collation::system();
+    let folders =
+        state.lock(|_, workspace| crate::vault::entries::folders(workspace, &collation))?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(folders)
 }
@@ -1,25 +1,122 @@
-use serde_json::json;
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::password_generator::history::{GeneratedPassword, GenerationHistory};
+use crate::password_generator::{PasswordContext, StrengthReport};
+use crate::reauth::SensitiveAction;
+use crate::AuthState;
+use tauri::State;
+
+/// Adds `password` to the generation history while the Vault is unlocked. A
+/// decoy Vault keeps none, and a failure here never holds the password back.
+fn remember(state: &VaultState, history: &GenerationHistory, password: &str) {
+    let result = state.lock(|_, workspace| match &workspace.session_key {
+        Some(key) if !workspace.decoy => history.record(key, password),
+        _ => Ok(()),
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record generated password: {}", e);
+    }
+}
 
 #[tauri::command]
+#[specta::specta]
 pub async fn generate_password(
     options: crate::password_generator::PasswordOptions,
-) -> Result<String, String> {
-    let password = crate::password_generator::generate_password(&options)?;
-
-    Ok(json!({
-        "status": "success",
-        "password": password
-    })
-    .to_string())
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    history: State<'_, GenerationHistory>,
+) -> Result<String, LatchError> {
+    let dictionary = settings.get().dictionary();
+    let password = crate::password_generator::generate_password_avoiding(&options, &dictionary)?;
+    remember(&state, &history, &password);
+
+    Ok(password)
+}
+
+/// A new password for a Credential, following the policy set for its site. The
+/// Credential is left as it is until the caller saves the password.
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_password_for_entry(
+    entry_id: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    history: State<'_, GenerationHistory>,
+) -> Result<String, LatchError> {
+    let options =
+        state.lock(|_, workspace| crate::vault::entries::password_policy(workspace, &entry_id))?;
+    let dictionary = settings.get().dictionary();
+    let password = crate::password_generator::generate_password_avoiding(&options, &dictionary)?;
+    remember(&state, &history, &password);
+
+    Ok(password)
+}
+
+/// Passwords generated lately, newest first, in case one was used before its
+/// Credential was saved. They are revealed like any password, so the same
+/// confirmation and audit apply.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_generation_history(
+    state: State<'_, VaultState>,
+    history: State<'_, GenerationHistory>,
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Vec<GeneratedPassword>, LatchError> {
+    let policy = settings.get().reauth;
+    let generated = state.lock(|_, workspace| {
+        workspace.check_session()?;
+        auth.reauth
+            .check(&policy, SensitiveAction::RevealPassword, workspace)?;
+        Ok(match &workspace.session_key {
+            Some(key) if !workspace.decoy => history.load(key),
+            _ => Vec::new(),
+        })
+    })?;
+    audit.record(AuditKind::Reveal, Some("generation history".to_string()));
+
+    Ok(generated)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn clear_generation_history(
+    state: State<'_, VaultState>,
+    history: State<'_, GenerationHistory>,
+) -> Result<(), LatchError> {
+    state.lock(|_, workspace| workspace.check_session())?;
+    history.clear()?;
+
+    Ok(())
+}
+
+/// Bits of entropy in passwords generated with `options`, from the random choices
+/// alone.
+#[tauri::command]
+#[specta::specta]
+pub async fn password_entropy(
+    options: crate::password_generator::PasswordOptions,
+) -> Result<f64, LatchError> {
+    Ok(crate::password_generator::entropy_bits(&options)?)
 }
 
+/// Scores `password`, treating the title, username and URL in `context` (the
+/// Credential being edited, if any) as easy guesses.
 #[tauri::command]
-pub async fn analyze_password_strength(password: String) -> Result<String, String> {
-    let report = crate::password_generator::analyze_password_strength(&password);
-
-    Ok(json!({
-        "status": "success",
-        "report": report
-    })
-    .to_string())
+#[specta::specta]
+pub async fn analyze_password_strength(
+    password: String,
+    context: Option<PasswordContext>,
+    settings: State<'_, SettingsState>,
+) -> Result<StrengthReport, LatchError> {
+    let dictionary = settings.get().dictionary();
+    let report = crate::password_generator::analyze_password_strength_in_context(
+        &password,
+        &dictionary,
+        &context.unwrap_or_default(),
+    );
+
+    Ok(report)
 }
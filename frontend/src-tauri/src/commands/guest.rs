@@ -0,0 +1,109 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::VaultState;
+use crate::error::LatchError;
+use crate::vault::guest::{self, GUEST_DENIED};
+use serde::Serialize;
+use specta::Type;
+use std::time::{Duration, SystemTime};
+use tauri::{Manager, Runtime, State, Webview};
+
+#[derive(Debug, Serialize, Type)]
+pub struct GuestSessionInfo {
+    pub folders: Vec<String>,
+    pub remaining_seconds: u64,
+    pub entries: usize,
+}
+
+fn info(workspace: &crate::vault::workspace::Workspace) -> Option<GuestSessionInfo> {
+    let session = workspace.guest.as_ref()?;
+    Some(GuestSessionInfo {
+        folders: session.folders.clone(),
+        remaining_seconds: session
+            .expires_at
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO)
+            .as_secs(),
+        entries: workspace.credentials.len(),
+    })
+}
+
+/// Refuses every command outside the guest allowlist while a guest session is
+/// running. Called for each IPC message before its handler.
+pub fn permit<R: Runtime>(webview: &Webview<R>, command: &str) -> Result<(), LatchError> {
+    if guest::allows(command) {
+        return Ok(());
+    }
+    let state = webview.state::<VaultState>();
+    let guard = state
+        .0
+        .lock()
+        .map_err(|_| LatchError::Unavailable("Vault is temporarily unavailable".to_string()))?;
+    if guard.1.guest.is_some() {
+        return Err(LatchError::Forbidden(GUEST_DENIED.to_string()));
+    }
+    Ok(())
+}
+
+/// Hands the unlocked app to someone else for `duration_secs`, showing only the
+/// Credentials filed in `folders`. Locking ends it early.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_guest_session(
+    folders: Vec<String>,
+    duration_secs: u64,
+    state: State<'_, VaultState>,
+    audit: State<'_, AuditLog>,
+) -> Result<GuestSessionInfo, LatchError> {
+    let (session, info) = state.lock(|_, workspace| {
+        let session = guest::start(workspace, folders, duration_secs)?;
+        let info = info(workspace).ok_or("Guest session not found")?;
+        Ok((session, info))
+    })?;
+    audit.record(
+        AuditKind::Unlock,
+        Some(format!(
+            "guest session for {} minutes: {}",
+            duration_secs.div_ceil(60),
+            session.folders.join(", ")
+        )),
+    );
+
+    let state_arc = state.0.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(duration_secs)).await;
+        if let Ok(mut guard) = state_arc.lock() {
+            if guard.1.guest.as_ref() == Some(&session) {
+                guard.1.lock();
+            }
+        }
+    });
+
+    Ok(info)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_guest_session(
+    state: State<'_, VaultState>,
+) -> Result<Option<GuestSessionInfo>, LatchError> {
+    let info = state.lock(|_, workspace| {
+        if workspace.guest.is_some() {
+            workspace.check_session()?;
+        }
+        Ok(info(workspace))
+    })?;
+
+    Ok(info)
+}
+
+/// Ending a guest session locks the Vault; the owner unlocks again for full access.
+#[tauri::command]
+#[specta::specta]
+pub async fn end_guest_session(state: State<'_, VaultState>) -> Result<(), LatchError> {
+    state.lock(|_, workspace| {
+        workspace.lock();
+        Ok(())
+    })?;
+
+    Ok(())
+}
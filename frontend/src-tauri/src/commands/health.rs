@@ -1,28 +1,150 @@
-use crate::commands::VaultState;
-use crate::vault_health::breach_checker::PwnedPasswordsApi;
-use serde_json::json;
-use tauri::State;
+use crate::breach_database::{BreachDatabaseProgress, BreachDatabaseState};
+use crate::breach_watch::AccountMonitor;
+use crate::commands::backup::snapshot_after_save;
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::vault_health::accounts::{self, HibpAccountApi};
+use crate::vault_health::audit::VaultHealthReport;
+use crate::vault_health::offline;
+use tauri::{AppHandle, State};
 
 fn session_checked_entries(
     workspace: &mut crate::vault::workspace::Workspace,
 ) -> Result<Vec<crate::vault::Entry>, String> {
     workspace.check_session()?;
     workspace.refresh();
-    Ok(workspace.credentials.clone())
+    // SSH keys and references have no password worth scoring; canaries are
+    // checked for breaches only.
+    Ok(workspace
+        .credentials
+        .iter()
+        .filter(|entry| entry.is_login() || entry.is_canary())
+        .cloned()
+        .collect())
 }
 
 #[tauri::command]
-pub async fn check_vault_health(state: State<'_, VaultState>) -> Result<String, String> {
+#[specta::specta]
+pub async fn check_vault_health(
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    monitor: State<'_, AccountMonitor>,
+) -> Result<VaultHealthReport, LatchError> {
     let entries = state.lock(|_, workspace| session_checked_entries(workspace))?;
+    let settings = settings.get();
+    let dictionary = settings.dictionary();
 
-    let checker = PwnedPasswordsApi;
-    let report = crate::vault_health::audit::check_vault_health(&entries, &checker).await;
+    let checker = crate::breach_database::checker(&settings)?;
+    let mut report =
+        crate::vault_health::audit::check_vault_health(&entries, checker.as_ref(), &dictionary)
+            .await;
+    if let Some(accounts_api) = monitor.checker(settings.account_breach_monitoring) {
+        report.breached_accounts =
+            accounts::check_accounts(&entries, accounts_api.as_ref()).await?;
+        monitor.mark_new(&mut report.breached_accounts);
+    }
+
+    Ok(report)
+}
+
+/// Starts checking username email addresses with the user's HIBP API key, while
+/// `account_breach_monitoring` is on.
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_breach_monitor(
+    api_key: String,
+    monitor: State<'_, AccountMonitor>,
+) -> Result<(), LatchError> {
+    monitor.connect(Some(HibpAccountApi::new(&api_key)?))?;
+
+    Ok(())
+}
 
-    Ok(json!({
-        "status": "success",
-        "report": report
+#[tauri::command]
+#[specta::specta]
+pub async fn disconnect_breach_monitor(
+    monitor: State<'_, AccountMonitor>,
+) -> Result<(), LatchError> {
+    monitor.connect(None)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_breach_database_status(
+    database: State<'_, BreachDatabaseState>,
+) -> Result<BreachDatabaseProgress, LatchError> {
+    Ok(BreachDatabaseProgress {
+        status: offline::status(&offline::dataset_path()?),
+        downloading: database.is_downloading(),
+        error: None,
     })
-    .to_string())
+}
+
+/// Starts or resumes downloading the breach database for offline checks; see
+/// `breach_database` for the progress events.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_breach_database(app: AppHandle) -> Result<(), LatchError> {
+    crate::breach_database::spawn_download(app)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_breach_database_download(
+    database: State<'_, BreachDatabaseState>,
+) -> Result<(), LatchError> {
+    database.cancel();
+
+    Ok(())
+}
+
+/// Deletes the downloaded breach database. Offline checks are turned off with it,
+/// so they can't fail until it is downloaded again.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_breach_database(
+    database: State<'_, BreachDatabaseState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    if database.is_downloading() {
+        return Err(LatchError::Unavailable(
+            "Cancel the breach database download first".to_string(),
+        ));
+    }
+    offline::remove(&offline::dataset_path()?)?;
+    let current = settings.get();
+    if current.offline_breach_check {
+        settings.update(crate::settings::Settings {
+            offline_breach_check: false,
+            ..current
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Moves the password or token out of a Credential's URL into its fields.
+#[tauri::command]
+#[specta::specta]
+pub async fn fix_url_secret(
+    entry_id: String,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        let entry = crate::vault::entries::get_full(workspace, &entry_id)?;
+        let cleaned = crate::vault_health::url_secrets::clean(&entry)?;
+        crate::vault::entries::update(workspace, storage, cleaned)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -38,8 +160,7 @@ mod tests {
             title: "Example".to_string(),
             username: "user".to_string(),
             password: "secret".to_string(),
-            url: None,
-            icon_url: None,
+            ..Default::default()
         });
         workspace.start([5u8; 32]);
         workspace.session_start =
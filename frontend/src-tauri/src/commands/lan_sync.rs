@@ -0,0 +1,108 @@
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::lan_sync::{self, LanPeer, LanSyncState, PairedPeer, PairingRequest};
+use crate::vault::sync::SyncReport;
+use tauri::State;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_lan_peers(lan: State<'_, LanSyncState>) -> Result<Vec<PairedPeer>, LatchError> {
+    Ok(lan.peers())
+}
+
+/// Latch instances announcing themselves on the local network right now.
+#[tauri::command]
+#[specta::specta]
+pub async fn discover_lan_peers(
+    vault_state: State<'_, VaultState>,
+    lan: State<'_, LanSyncState>,
+) -> Result<Vec<LanPeer>, LatchError> {
+    Ok(lan_sync::discover(&vault_state.0, &lan).await?)
+}
+
+/// Lets another device ask to pair for the next `PAIRING_WINDOW_SECS`. Returns
+/// how long the window stays open.
+#[tauri::command]
+#[specta::specta]
+pub async fn open_lan_pairing(
+    settings: State<'_, SettingsState>,
+    lan: State<'_, LanSyncState>,
+) -> Result<u64, LatchError> {
+    if !settings.get().lan_sync {
+        return Err("Turn on LAN sync in Settings first".into());
+    }
+    Ok(lan.open_pairing())
+}
+
+/// Asks the device at `address` to pair. Show the returned code and call
+/// `confirm_lan_pairing` once the user sees the same one on the other device.
+#[tauri::command]
+#[specta::specta]
+pub async fn pair_lan_peer(
+    address: String,
+    vault_state: State<'_, VaultState>,
+    lan: State<'_, LanSyncState>,
+) -> Result<PairingRequest, LatchError> {
+    let stream = lan_sync::connect(&address).await?;
+    let request = lan_sync::pair(stream, &vault_state.0, &lan, &lan_sync::device_name()).await?;
+
+    Ok(request)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn confirm_lan_pairing(
+    fingerprint: String,
+    lan: State<'_, LanSyncState>,
+) -> Result<PairedPeer, LatchError> {
+    Ok(lan.confirm(&fingerprint)?)
+}
+
+/// Drops a pairing request whose codes did not match.
+#[tauri::command]
+#[specta::specta]
+pub async fn reject_lan_pairing(
+    fingerprint: String,
+    lan: State<'_, LanSyncState>,
+) -> Result<(), LatchError> {
+    lan.reject(&fingerprint);
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn forget_lan_peer(
+    fingerprint: String,
+    lan: State<'_, LanSyncState>,
+) -> Result<(), LatchError> {
+    lan.forget(&fingerprint)?;
+
+    Ok(())
+}
+
+/// Exchanges changes with the paired device `fingerprint`, found at `address`.
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_with_lan_peer(
+    fingerprint: String,
+    address: String,
+    vault_state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    lan: State<'_, LanSyncState>,
+) -> Result<SyncReport, LatchError> {
+    if !settings.get().lan_sync {
+        return Err("Turn on LAN sync in Settings first".into());
+    }
+    let stream = lan_sync::connect(&address).await?;
+    let report = lan_sync::sync_with(
+        stream,
+        &vault_state.0,
+        &lan,
+        &lan_sync::device_name(),
+        &fingerprint,
+    )
+    .await?;
+
+    Ok(report)
+}
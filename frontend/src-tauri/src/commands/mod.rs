@@ -1,10 +1,30 @@
+pub mod aliases;
+pub mod audit;
+pub mod autotype;
+pub mod backup;
+pub mod browser;
+pub mod clipboard;
 pub mod credential;
 pub mod generator;
+pub mod guest;
 pub mod health;
+pub mod lan_sync;
+pub mod reveal;
 pub mod session;
+pub mod settings;
+pub mod share;
+pub mod sharing;
+pub mod ssh;
+pub mod sync;
 pub mod vault;
 
+#[cfg(test)]
+mod tests;
+
+use crate::settings::{Settings, SettingsStorage};
 use crate::vault::{storage::VaultStorage, workspace::Workspace};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
 pub struct VaultState(pub Arc<Mutex<(VaultStorage, Workspace)>>);
@@ -23,6 +43,64 @@ impl VaultState {
             .lock()
             .map_err(|_| "Vault is temporarily unavailable")?;
         let (ref storage, ref mut workspace) = *guard;
+        if workspace.decoy {
+            let decoy = crate::vault::duress::decoy_storage(storage)?;
+            return f(&decoy, workspace);
+        }
         f(storage, workspace)
     }
+
+    /// A copy of the Session key, for work done without holding the Vault.
+    pub fn session_key(&self) -> Result<[u8; 32], String> {
+        self.lock(|_, workspace| {
+            workspace.check_session()?;
+            workspace
+                .session_key
+                .as_deref()
+                .copied()
+                .ok_or_else(|| "Vault is locked".to_string())
+        })
+    }
+}
+
+/// A `.latch` file the app was asked to open, waiting for the import flow.
+pub struct OpenedFileState(pub Mutex<Option<PathBuf>>);
+
+/// Whether the frontend currently has a secret revealed on screen.
+pub struct RevealState(pub AtomicBool);
+
+pub struct SettingsState {
+    storage: SettingsStorage,
+    current: Mutex<Settings>,
+}
+
+impl SettingsState {
+    pub fn new(storage: SettingsStorage) -> Self {
+        let current = storage.read().unwrap_or_else(|e| {
+            eprintln!("Falling back to default settings: {}", e);
+            Settings::default()
+        });
+        Self {
+            storage,
+            current: Mutex::new(current),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn update(&self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        let mut current = self
+            .current
+            .lock()
+            .map_err(|_| "Settings are temporarily unavailable")?;
+        self.storage.write(&settings)?;
+        *current = settings;
+        Ok(())
+    }
 }
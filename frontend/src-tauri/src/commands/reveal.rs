@@ -0,0 +1,47 @@
+use crate::commands::{RevealState, SettingsState};
+use crate::error::LatchError;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+fn set_capture_protection(app_handle: &AppHandle, protected: bool) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        if let Err(e) = window.set_content_protected(protected) {
+            eprintln!("Failed to set content protection: {}", e);
+        }
+    }
+}
+
+/// Called by the frontend whenever a secret is shown or hidden, so the window can be
+/// excluded from screen capture for exactly as long as something sensitive is visible.
+/// Returns whether capture is now blocked.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_secrets_revealed(
+    revealed: bool,
+    app_handle: AppHandle,
+    reveal: State<'_, RevealState>,
+    settings: State<'_, SettingsState>,
+) -> Result<bool, LatchError> {
+    reveal.0.store(revealed, Ordering::SeqCst);
+    let protected = revealed && settings.get().block_capture_on_reveal;
+    set_capture_protection(&app_handle, protected);
+
+    Ok(protected)
+}
+
+/// Window focus was lost: if secrets are on screen and the setting is on, tell the
+/// frontend to hide them and drop capture protection.
+pub fn hide_on_blur(app_handle: &AppHandle) {
+    if !app_handle
+        .state::<SettingsState>()
+        .get()
+        .hide_secrets_on_blur
+    {
+        return;
+    }
+    let reveal = app_handle.state::<RevealState>();
+    if reveal.0.swap(false, Ordering::SeqCst) {
+        set_capture_protection(app_handle, false);
+        let _ = app_handle.emit("secrets-hidden", ());
+    }
+}
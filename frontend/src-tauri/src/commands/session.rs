@@ -1,63 +1,152 @@
 use crate::commands::VaultState;
-use serde_json::json;
+use crate::error::LatchError;
+use crate::startup::Readiness;
+use crate::vault::VaultSummary;
+use crate::vault::MAX_SESSION_SECS;
+use serde::Serialize;
+use specta::Type;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::State;
 
+#[derive(Debug, Serialize, Type)]
+pub struct AuthPreferences {
+    pub auth_method: String,
+    pub session_valid: bool,
+    pub session_remaining_seconds: u64,
+    pub duress_configured: bool,
+}
+
 fn session_remaining_seconds(workspace: &mut crate::vault::workspace::Workspace) -> u64 {
     if !workspace.is_unlocked() {
         return 0;
     }
 
-    let Some(start) = workspace.session_start else {
+    let remaining = workspace.session_remaining_secs();
+    if remaining == 0 {
         workspace.lock();
-        return 0;
-    };
+    }
+    remaining
+}
 
-    let Ok(elapsed) = start.elapsed() else {
-        workspace.lock();
-        return 0;
-    };
+#[derive(Debug, Serialize, Type)]
+pub struct SessionInfo {
+    pub unlocked: bool,
+    /// Unix time the Session started.
+    pub started_at: Option<u64>,
+    /// Unix time the Session will lock unless extended.
+    pub expires_at: Option<u64>,
+    pub remaining_seconds: u64,
+    /// False once the Session is as long as it can get, and in guest sessions.
+    pub extendable: bool,
+}
 
-    let elapsed_secs = elapsed.as_secs();
-    if elapsed_secs >= crate::vault::SESSION_TIMEOUT_SECS {
-        workspace.lock();
-        return 0;
+fn session_info(workspace: &mut crate::vault::workspace::Workspace) -> SessionInfo {
+    let remaining_seconds = session_remaining_seconds(workspace);
+    let unix = |at: SystemTime| at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let expires_at = workspace.session_expires_at();
+    let extendable = match (workspace.session_start, expires_at) {
+        (Some(start), Some(expires)) => {
+            workspace.guest.is_none() && expires < start + Duration::from_secs(MAX_SESSION_SECS)
+        }
+        _ => false,
+    };
+    SessionInfo {
+        unlocked: workspace.is_unlocked(),
+        started_at: workspace.session_start.map(unix),
+        expires_at: expires_at.map(unix),
+        remaining_seconds,
+        extendable,
     }
-
-    crate::vault::SESSION_TIMEOUT_SECS - elapsed_secs
 }
 
 #[tauri::command]
-pub async fn lock_vault(state: State<'_, VaultState>) -> Result<String, String> {
+#[specta::specta]
+pub async fn lock_vault(state: State<'_, VaultState>) -> Result<(), LatchError> {
     state.lock(|_, workspace| {
         workspace.lock();
         Ok(())
     })?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_auth_preferences(state: State<'_, VaultState>) -> Result<String, String> {
-    state.lock(|storage, workspace| {
-        let auth_method = if storage.exists() {
-            storage
-                .read()
-                .map(|v| v.kdf)
-                .unwrap_or_else(|_| "none".to_string())
-        } else {
-            "none".to_string()
-        };
-        let session_remaining = session_remaining_seconds(workspace);
-        let is_unlocked = workspace.is_unlocked();
-
-        Ok(json!({
-            "status": "success",
-            "auth_method": auth_method,
-            "session_valid": is_unlocked,
-            "session_remaining_seconds": session_remaining
+#[specta::specta]
+pub async fn get_auth_preferences(
+    state: State<'_, VaultState>,
+) -> Result<AuthPreferences, LatchError> {
+    state
+        .lock(|storage, workspace| {
+            let auth_method = if storage.exists() {
+                storage
+                    .read_header()
+                    .map(|v| v.kdf)
+                    .unwrap_or_else(|_| "none".to_string())
+            } else {
+                "none".to_string()
+            };
+            let session_remaining = session_remaining_seconds(workspace);
+            let is_unlocked = workspace.is_unlocked();
+            // Only reported inside a real Session so the decoy never reveals that it is one.
+            let duress_configured =
+                is_unlocked && !workspace.decoy && crate::vault::duress::is_configured(storage);
+
+            Ok(AuthPreferences {
+                auth_method,
+                session_valid: is_unlocked,
+                session_remaining_seconds: session_remaining,
+                duress_configured,
+            })
         })
-        .to_string())
-    })
+        .map_err(LatchError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_session_info(state: State<'_, VaultState>) -> Result<SessionInfo, LatchError> {
+    let info = state.lock(|_, workspace| Ok(session_info(workspace)))?;
+
+    Ok(info)
+}
+
+/// Restarts the 30-minute Session timeout. Call it for explicit user activity,
+/// not on a timer.
+#[tauri::command]
+#[specta::specta]
+pub async fn extend_session(state: State<'_, VaultState>) -> Result<SessionInfo, LatchError> {
+    let info = state.lock(|_, workspace| {
+        workspace.extend_session()?;
+        Ok(session_info(workspace))
+    })?;
+
+    Ok(info)
+}
+
+/// Header-only summary for the lock screen. Never decrypts, so it works while locked;
+/// null for Vaults written before summaries existed.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_vault_summary(
+    state: State<'_, VaultState>,
+) -> Result<Option<VaultSummary>, LatchError> {
+    state
+        .lock(|storage, _| {
+            let summary = if storage.exists() {
+                storage.read_header()?.summary
+            } else {
+                None
+            };
+
+            Ok(summary)
+        })
+        .map_err(LatchError::from)
+}
+
+/// Whether the plugins and tray registered after the first paint are up yet.
+#[tauri::command]
+#[specta::specta]
+pub async fn is_app_ready(readiness: State<'_, Readiness>) -> Result<bool, LatchError> {
+    Ok(readiness.is_ready())
 }
 
 #[cfg(test)]
@@ -77,4 +166,26 @@ mod tests {
         assert_eq!(remaining, 0);
         assert!(!workspace.is_unlocked());
     }
+
+    #[test]
+    fn extending_restarts_the_timeout_up_to_the_maximum() {
+        let mut workspace = Workspace::new();
+        assert!(workspace.extend_session().is_err());
+        workspace.start([3u8; 32]);
+        let start = SystemTime::now() - Duration::from_secs(20 * 60);
+        workspace.session_start = Some(start);
+        assert!(super::session_info(&mut workspace).remaining_seconds <= 10 * 60);
+
+        let remaining = workspace.extend_session().unwrap();
+        assert!(remaining > 29 * 60);
+        // The Session keeps its identity, so reauth windows and capabilities survive.
+        assert_eq!(workspace.session_start, Some(start));
+        assert!(super::session_info(&mut workspace).extendable);
+
+        let long_ago = SystemTime::now() - Duration::from_secs(crate::vault::MAX_SESSION_SECS - 60);
+        workspace.session_start = Some(long_ago);
+        let info = super::session_info(&mut workspace);
+        assert!(info.remaining_seconds <= 60);
+        assert!(!info.extendable);
+    }
 }
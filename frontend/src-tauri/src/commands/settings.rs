@@ -0,0 +1,131 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::password_generator::normalize_dictionary;
+use crate::settings::Settings;
+use crate::AuthState;
+use tauri::{AppHandle, Manager, State};
+
+const MAX_WORD_LIST_BYTES: u64 = 1024 * 1024;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_settings(state: State<'_, SettingsState>) -> Result<Settings, LatchError> {
+    Ok(state.get())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_settings(
+    settings: Settings,
+    app_handle: AppHandle,
+    state: State<'_, SettingsState>,
+    audit: State<'_, AuditLog>,
+) -> Result<(), LatchError> {
+    apply(settings, &app_handle, &state, &audit, None)
+}
+
+/// Saves `settings` and starts or stops whatever they switch on or off. Turning
+/// off a reauth guard needs the confirmation it guarded with.
+fn apply(
+    settings: Settings,
+    app_handle: &AppHandle,
+    state: &SettingsState,
+    audit: &AuditLog,
+    detail: Option<String>,
+) -> Result<(), LatchError> {
+    let current = state.get().reauth;
+    let auth = app_handle.state::<AuthState>();
+    app_handle.state::<VaultState>().lock(|_, workspace| {
+        auth.reauth
+            .check_policy_change(&current, &settings.reauth, workspace)
+    })?;
+    let browser_integration = settings.browser_integration;
+    let autotype = settings.autotype;
+    let ssh_agent = settings.ssh_agent;
+    let git_credential_helper = settings.git_credential_helper;
+    let lan_sync = settings.lan_sync;
+    let launch_at_login = settings.launch_at_login;
+    state.update(settings)?;
+    audit.record(AuditKind::SettingsChanged, detail);
+    crate::browser::server::sync(app_handle, browser_integration);
+    crate::commands::autotype::sync_shortcut(app_handle, autotype);
+    crate::ssh::sync(app_handle, ssh_agent);
+    crate::git_credential::sync(app_handle, git_credential_helper);
+    crate::lan_sync::sync(app_handle, lan_sync);
+    crate::autostart::sync(app_handle, launch_at_login);
+    Ok(())
+}
+
+/// Writes the settings to a file, to import on another device.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_settings(
+    path: String,
+    state: State<'_, SettingsState>,
+) -> Result<(), LatchError> {
+    crate::settings::export_to(&state.get(), std::path::Path::new(&path))?;
+
+    Ok(())
+}
+
+/// Replaces the settings with those from an exported file, keeping this device's
+/// hooks. Returns the settings now in effect.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_settings(
+    path: String,
+    app_handle: AppHandle,
+    state: State<'_, SettingsState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Settings, LatchError> {
+    let imported = crate::settings::import_from(std::path::Path::new(&path))?;
+    let settings = state.get().with_imported(imported);
+    apply(
+        settings.clone(),
+        &app_handle,
+        &state,
+        &audit,
+        Some("imported".to_string()),
+    )?;
+
+    Ok(settings)
+}
+
+/// Merges a word list file (one word per line, `#` for comments) into the custom
+/// dictionary, so organizations can hand out a shared list.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_dictionary(
+    path: String,
+    state: State<'_, SettingsState>,
+    audit: State<'_, AuditLog>,
+) -> Result<usize, LatchError> {
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read word list: {}", e))?
+        .len();
+    if size > MAX_WORD_LIST_BYTES {
+        return Err(LatchError::InvalidInput(
+            "Word list is too large".to_string(),
+        ));
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read word list: {}", e))?;
+    let words = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(str::to_string);
+
+    let mut settings = state.get();
+    let before = settings.dictionary().len();
+    settings.custom_dictionary =
+        normalize_dictionary(settings.custom_dictionary.iter().cloned().chain(words));
+    let added = settings.custom_dictionary.len() - before;
+    state.update(settings)?;
+    audit.record(
+        AuditKind::SettingsChanged,
+        Some("custom dictionary".to_string()),
+    );
+
+    Ok(added)
+}
@@ -0,0 +1,131 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::local_share::{LocalShare, LocalShareState};
+use crate::reauth::SensitiveAction;
+use crate::share::{OneTimeShare, RelayClient, ShareDestination};
+use crate::AuthState;
+use std::path::Path;
+use tauri::State;
+use zeroize::Zeroizing;
+
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn share_secret_locally(
+    entry_id: String,
+    field: String,
+    ttl_secs: Option<u64>,
+    state: State<'_, VaultState>,
+    shares: State<'_, LocalShareState>,
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<LocalShare, LatchError> {
+    let policy = settings.get().reauth;
+    let secret = Zeroizing::new(state.lock(|_, workspace| {
+        auth.reauth
+            .check(&policy, SensitiveAction::Export, workspace)?;
+        crate::vault::entries::get_field(workspace, &entry_id, &field)
+    })?);
+
+    let ttl = crate::local_share::clamp_ttl(ttl_secs);
+    let (share, cancel) = crate::local_share::start(secret, ttl).await?;
+    audit.record(
+        AuditKind::Export,
+        Some(format!("local share of {} for {}", field, entry_id)),
+    );
+
+    let mut active = shares
+        .0
+        .lock()
+        .map_err(|_| "Share state temporarily unavailable")?;
+    active.retain(|_, sender| !sender.is_closed());
+    active.insert(share.id.clone(), cancel);
+
+    Ok(share)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_local_share(
+    share_id: String,
+    shares: State<'_, LocalShareState>,
+) -> Result<(), LatchError> {
+    let cancel = shares
+        .0
+        .lock()
+        .map_err(|_| "Share state temporarily unavailable")?
+        .remove(&share_id);
+    if let Some(cancel) = cancel {
+        let _ = cancel.send(());
+    }
+
+    Ok(())
+}
+
+/// Seals a Credential field for one recipient, on the relay or in a file (see
+/// `share`), and returns the link to send them.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn share_secret_once(
+    entry_id: String,
+    field: String,
+    destination: ShareDestination,
+    ttl_secs: Option<u64>,
+    burn_after_reading: bool,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<OneTimeShare, LatchError> {
+    let settings = settings.get();
+    let secret = Zeroizing::new(state.lock(|_, workspace| {
+        auth.reauth
+            .check(&settings.reauth, SensitiveAction::Export, workspace)?;
+        crate::vault::entries::get_field(workspace, &entry_id, &field)
+    })?);
+
+    let ttl = crate::share::clamp_ttl(ttl_secs);
+    let (sealed, key) =
+        crate::share::seal(&secret, ttl, burn_after_reading, crate::vault::unix_now())?;
+    let (link, target) = match destination {
+        ShareDestination::Relay => {
+            let relay_url = settings.share_relay_url.as_deref().ok_or_else(|| {
+                LatchError::InvalidInput("Set a share relay in Settings first".to_string())
+            })?;
+            let link = RelayClient::new(relay_url)?.upload(&sealed, &key).await?;
+            (link, "relay".to_string())
+        }
+        ShareDestination::File { path } => {
+            crate::share::write_file(Path::new(&path), &sealed)?;
+            (format!("#{}", crate::share::encode_key(&key)), path)
+        }
+    };
+    audit.record(
+        AuditKind::Export,
+        Some(format!(
+            "one-time share of {} for {} to {}",
+            field, entry_id, target
+        )),
+    );
+
+    Ok(OneTimeShare {
+        link,
+        expires_at: sealed.expires_at,
+    })
+}
+
+/// Opens a one-time share: a relay link, or a share file with its `#<key>`. Needs
+/// no Vault.
+#[tauri::command]
+#[specta::specta]
+pub async fn open_shared_secret(link: String, path: Option<String>) -> Result<String, LatchError> {
+    let now = crate::vault::unix_now();
+    let secret = match path {
+        Some(path) => crate::share::open_file(Path::new(&path), &link, now)?,
+        None => crate::share::open_link(&link, now).await?,
+    };
+    Ok(secret.to_string())
+}
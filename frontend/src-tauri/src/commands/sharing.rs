@@ -0,0 +1,109 @@
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::backup::snapshot_after_save;
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::reauth::SensitiveAction;
+use crate::sharing::{ShareContact, SharingKey, SharingState};
+use crate::AuthState;
+use std::path::Path;
+use tauri::State;
+
+/// This Vault's public sharing key and its fingerprint, for contacts to save.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_sharing_key(state: State<'_, VaultState>) -> Result<SharingKey, LatchError> {
+    Ok(crate::sharing::sharing_key(&state.session_key()?)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_share_contacts(
+    sharing: State<'_, SharingState>,
+) -> Result<Vec<ShareContact>, LatchError> {
+    Ok(sharing.contacts())
+}
+
+/// Saves someone's public sharing key. Check the fingerprint with them first.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_share_contact(
+    name: String,
+    public_key: String,
+    sharing: State<'_, SharingState>,
+) -> Result<ShareContact, LatchError> {
+    Ok(sharing.add(&name, &public_key)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_share_contact(
+    fingerprint: String,
+    sharing: State<'_, SharingState>,
+) -> Result<(), LatchError> {
+    Ok(sharing.remove(&fingerprint)?)
+}
+
+/// Writes the Credentials to `path`, sealed for one contact.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn share_entries(
+    entry_ids: Vec<String>,
+    contact_fingerprint: String,
+    path: String,
+    state: State<'_, VaultState>,
+    sharing: State<'_, SharingState>,
+    settings: State<'_, SettingsState>,
+    auth: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<(), LatchError> {
+    let contact = sharing.find(&contact_fingerprint)?;
+    let policy = settings.get().reauth;
+    let bundle = state.lock(|_, workspace| {
+        auth.reauth
+            .check(&policy, SensitiveAction::Export, workspace)?;
+        let mut entries = Vec::with_capacity(entry_ids.len());
+        for id in &entry_ids {
+            entries.push(crate::vault::entries::get_full(workspace, id)?);
+        }
+        let key = workspace.session_key.as_deref().ok_or("Vault is locked")?;
+        crate::sharing::seal(key, &contact, &entries)
+    })?;
+    std::fs::write(Path::new(&path), bundle)
+        .map_err(|e| format!("Failed to write share: {}", e))?;
+    audit.record(
+        AuditKind::Export,
+        Some(format!(
+            "{} credentials shared with {}",
+            entry_ids.len(),
+            contact.fingerprint
+        )),
+    );
+
+    Ok(())
+}
+
+/// Adds the Credentials in a share from a contact. Returns how many.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_shared_entries(
+    path: String,
+    state: State<'_, VaultState>,
+    sharing: State<'_, SharingState>,
+    settings: State<'_, SettingsState>,
+) -> Result<usize, LatchError> {
+    let bundle = crate::sharing::read_bundle(Path::new(&path))?;
+    let contacts = sharing.contacts();
+    let settings = settings.get();
+    let count = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let key = workspace.session_key.as_deref().ok_or("Vault is locked")?;
+        let entries = crate::sharing::open(key, &contacts, &bundle)?;
+        let count = entries.len();
+        crate::vault::entries::add_all(workspace, storage, entries)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(count)
+    })?;
+
+    Ok(count)
+}
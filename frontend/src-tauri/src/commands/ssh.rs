@@ -0,0 +1,64 @@
+use crate::commands::backup::snapshot_after_save;
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::ssh::SshAgentState;
+use crate::vault::EntryKind;
+use tauri::State;
+
+/// Stores a pasted OpenSSH private key as a new Credential. An encrypted key is
+/// decrypted with `passphrase` first; the Vault keeps it from then on.
+#[tauri::command]
+#[specta::specta]
+pub async fn add_ssh_key(
+    title: String,
+    private_key: String,
+    passphrase: Option<String>,
+    confirm: Option<bool>,
+    state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+) -> Result<String, LatchError> {
+    crate::commands::credential::validate_title(&title)?;
+    let (private_key, public_key) = crate::ssh::import_key(&private_key, passphrase.as_deref())
+        .map_err(LatchError::InvalidInput)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let entry = crate::vault::Entry {
+        id: id.clone(),
+        title,
+        username: String::new(),
+        password: private_key.to_string(),
+        kind: EntryKind::SshKey {
+            public_key,
+            confirm: confirm.unwrap_or(true),
+        },
+        ..Default::default()
+    };
+
+    let settings = settings.get();
+    state.lock(|storage, workspace| {
+        crate::vault::entries::add(workspace, storage, entry)?;
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })?;
+
+    Ok(id)
+}
+
+/// The user's answer to an `ssh://confirm-requested` prompt.
+#[tauri::command]
+#[specta::specta]
+pub async fn resolve_ssh_confirmation(
+    request_id: String,
+    approved: bool,
+    agent: State<'_, SshAgentState>,
+) -> Result<(), LatchError> {
+    agent.resolve(&request_id, approved)?;
+    Ok(())
+}
+
+/// What to set `SSH_AUTH_SOCK` to for `ssh` to use the agent.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_ssh_agent_socket() -> Result<String, LatchError> {
+    Ok(crate::ssh::socket_path())
+}
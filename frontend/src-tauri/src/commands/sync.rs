@@ -0,0 +1,93 @@
+use crate::commands::{SettingsState, VaultState};
+use crate::error::LatchError;
+use crate::sync::{SyncCredentials, SyncState, SyncStatus};
+use crate::vault::sync::s3::S3Config;
+use crate::vault::sync::SyncReport;
+use tauri::State;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_status(
+    settings: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+) -> Result<SyncStatus, LatchError> {
+    Ok(sync_state.status(settings.get().sync_provider))
+}
+
+/// Syncs with Google Drive using the access token from a Google sign-in that asked
+/// for the `drive.appdata` scope. Call again with a fresh token once it expires.
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_google_drive(
+    access_token: String,
+    settings: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+) -> Result<SyncStatus, LatchError> {
+    connect(
+        &settings,
+        &sync_state,
+        SyncCredentials::GoogleDrive(access_token),
+    )
+}
+
+/// Syncs with an S3-compatible bucket. The frontend keeps `config` in the OS
+/// keychain and connects again with it on each launch.
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_s3(
+    config: S3Config,
+    settings: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+) -> Result<SyncStatus, LatchError> {
+    crate::vault::sync::s3::S3::new(config.clone())?;
+    connect(&settings, &sync_state, SyncCredentials::S3(config))
+}
+
+/// Switching providers forgets what was synced with the old one.
+fn connect(
+    settings: &SettingsState,
+    sync_state: &SyncState,
+    credentials: SyncCredentials,
+) -> Result<SyncStatus, LatchError> {
+    let kind = credentials.kind();
+    let mut current = settings.get();
+    if current.sync_provider != Some(kind) {
+        sync_state.reset()?;
+        current.sync_provider = Some(kind);
+        settings.update(current)?;
+    }
+    sync_state.set_credentials(Some(credentials));
+
+    Ok(sync_state.status(settings.get().sync_provider))
+}
+
+/// Stops syncing. The remote copy is left where it is.
+#[tauri::command]
+#[specta::specta]
+pub async fn disconnect_sync(
+    settings: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+) -> Result<(), LatchError> {
+    let mut current = settings.get();
+    current.sync_provider = None;
+    settings.update(current)?;
+    sync_state.reset()?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_now(
+    vault_state: State<'_, VaultState>,
+    settings: State<'_, SettingsState>,
+    sync_state: State<'_, SyncState>,
+) -> Result<SyncReport, LatchError> {
+    let kind = settings
+        .get()
+        .sync_provider
+        .ok_or("Choose a sync provider first")?;
+    let report = crate::sync::sync_now(&vault_state.0, &sync_state, kind).await?;
+
+    Ok(report)
+}
@@ -0,0 +1,669 @@
+//! End-to-end flows through the command handlers themselves, on a mock runtime with
+//! a temporary Vault directory. The keychain is faked by a key the test keeps.
+
+use super::backup::Guarded;
+use super::{credential, generator, health, session, vault, SettingsState, VaultState};
+use crate::audit::AuditLog;
+use crate::auth::lockout::AuthAttemptState;
+use crate::auth::oauth::OAuthProvider;
+use crate::integrity::BinaryIntegrity;
+use crate::settings::SettingsStorage;
+use crate::vault::{storage::VaultStorage, workspace::Workspace, EntryIcon};
+use crate::AuthState;
+use std::sync::Mutex;
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Manager};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+struct Harness {
+    app: App<MockRuntime>,
+    _dir: tempfile::TempDir,
+}
+
+fn harness() -> Harness {
+    let dir = tempfile::tempdir().unwrap();
+    let app = mock_builder().build(mock_context(noop_assets())).unwrap();
+    app.manage(VaultState::new(
+        VaultStorage::at(dir.path().join("vault.enc")),
+        Workspace::new(),
+    ));
+    app.manage(AuthState {
+        attempts: Mutex::new(AuthAttemptState::new()),
+        reauth: Default::default(),
+    });
+    app.manage(AuditLog {
+        path: dir.path().join("audit.log"),
+        key_path: dir.path().join("audit.key"),
+    });
+    app.manage(SettingsState::new(SettingsStorage {
+        path: dir.path().join("settings.json"),
+    }));
+    app.manage(BinaryIntegrity::at(dir.path().join("enrolled-binary")));
+    app.manage(crate::sync::SyncState::at(dir.path().join("sync.json")));
+    app.manage(crate::lan_sync::LanSyncState::at(
+        dir.path().join("lan-sync"),
+    ));
+    app.manage(crate::commands::aliases::AliasState::default());
+    app.manage(crate::breach_watch::AccountMonitor::at(
+        dir.path().join("account-breaches.json"),
+    ));
+    app.manage(crate::icons::IconCache::at(dir.path().join("icons")));
+    app.manage(crate::password_generator::history::GenerationHistory::at(
+        dir.path().join("generated.enc"),
+    ));
+    Harness { app, _dir: dir }
+}
+
+impl Harness {
+    async fn unlock(&self, password: &str) -> Result<(), crate::error::LatchError> {
+        let app = self.app.handle();
+        vault::unlock_vault(
+            password.to_string(),
+            app.clone(),
+            app.state(),
+            app.state(),
+            app.state(),
+        )
+        .await
+    }
+
+    async fn is_unlocked(&self) -> bool {
+        vault::vault_status(self.app.state())
+            .await
+            .unwrap()
+            .is_unlocked
+    }
+
+    async fn lock(&self) {
+        session::lock_vault(self.app.state()).await.unwrap();
+    }
+}
+
+/// An ID token signed with the test key, installed as Google's.
+fn id_token(sub: &str) -> String {
+    let keys = include_str!("../../latch-core/testdata/google-jwks.json");
+    crate::auth::jwks::GOOGLE
+        .install(crate::auth::jwks::KeySet::parse(keys, crate::vault::unix_now(), 3600).unwrap());
+    let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+    header.kid = Some("latch-test".to_string());
+    crate::auth::oauth::set_client_id(OAuthProvider::Google, "latch-test");
+    let claims = serde_json::json!({
+        "sub": sub,
+        "iss": "https://accounts.google.com",
+        "aud": "latch-test",
+        "exp": crate::vault::unix_now() + 3600,
+    });
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(include_bytes!(
+        "../../latch-core/testdata/google-signing-key.pem"
+    ))
+    .unwrap();
+    jsonwebtoken::encode(&header, &claims, &key).unwrap()
+}
+
+#[tokio::test]
+async fn password_vault_init_lock_and_unlock() {
+    let h = harness();
+    vault::init_vault(PASSWORD.to_string(), h.app.state())
+        .await
+        .unwrap();
+    assert!(h.is_unlocked().await);
+
+    credential::add_entry(
+        "GitHub".to_string(),
+        "octo".to_string(),
+        "hunter2".to_string(),
+        None,
+        None,
+        None,
+        None,
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+    )
+    .await
+    .unwrap();
+    h.lock().await;
+    assert!(!h.is_unlocked().await);
+
+    h.unlock(PASSWORD).await.unwrap();
+
+    let found = credential::search_entries("git".to_string(), h.app.state(), h.app.state())
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
+
+#[tokio::test]
+async fn failed_unlock_backs_off_even_the_right_password() {
+    let h = harness();
+    vault::init_vault(PASSWORD.to_string(), h.app.state())
+        .await
+        .unwrap();
+    h.lock().await;
+
+    let wrong = h.unlock("guess").await.unwrap_err();
+    assert_eq!(wrong.kind(), "DecryptionFailed");
+
+    let refused = h.unlock(PASSWORD).await.unwrap_err();
+    assert_eq!(refused.kind(), "LockedOut");
+    assert!(refused.retryable());
+    assert!(!h.is_unlocked().await);
+}
+
+#[tokio::test]
+async fn keychain_key_unlocks_only_its_vault() {
+    let h = harness();
+    let app = h.app.handle();
+    let key_hex = hex::encode([0x5au8; 32]);
+    vault::init_vault_with_key(
+        key_hex.clone(),
+        "biometric-keychain".to_string(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    h.lock().await;
+
+    vault::unlock_vault_with_key(
+        key_hex,
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    assert!(h.is_unlocked().await);
+    h.lock().await;
+
+    let other = vault::unlock_vault_with_key(
+        hex::encode([0x11u8; 32]),
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await;
+    assert!(other.is_err());
+    assert!(!h.is_unlocked().await);
+}
+
+#[tokio::test]
+async fn changed_binary_forces_reenrollment_of_the_keychain_key() {
+    let h = harness();
+    let app = h.app.handle();
+    let old_key = hex::encode([0x5au8; 32]);
+    let new_key = hex::encode([0x6bu8; 32]);
+    vault::init_vault_with_key(
+        old_key.clone(),
+        "biometric-keychain".to_string(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    vault::unlock_vault_with_key(
+        old_key.clone(),
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+
+    let integrity = app.state::<BinaryIntegrity>();
+    std::fs::write(&integrity.path, "0".repeat(64)).unwrap();
+    assert!(vault::check_binary_integrity(app.state(), app.state())
+        .await
+        .unwrap());
+    assert!(!h.is_unlocked().await);
+
+    let refused = vault::unlock_vault_with_key(
+        old_key.clone(),
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await;
+    assert_eq!(refused.unwrap_err().kind(), "ReauthRequired");
+
+    vault::reenroll_vault_key(
+        old_key.clone(),
+        new_key.clone(),
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    assert!(h.is_unlocked().await);
+    assert!(!integrity.changed());
+    h.lock().await;
+
+    vault::unlock_vault_with_key(
+        new_key.clone(),
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+
+    // Deleting the record doesn't let the next build that unlocks enroll itself.
+    h.lock().await;
+    std::fs::remove_file(&integrity.path).unwrap();
+    let unverified = vault::unlock_vault_with_key(
+        new_key,
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await;
+    assert_eq!(unverified.unwrap_err().kind(), "ReauthRequired");
+    assert!(!integrity.is_enrolled());
+}
+
+#[tokio::test]
+async fn migrating_to_oauth_moves_the_unlock_path() {
+    let h = harness();
+    let app = h.app.handle();
+    vault::init_vault(PASSWORD.to_string(), app.state())
+        .await
+        .unwrap();
+    h.lock().await;
+
+    let token = id_token("user-42");
+    let Guarded { safety_backup, .. } = vault::migrate_to_oauth(
+        PASSWORD.to_string(),
+        OAuthProvider::Google,
+        token.clone(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    assert!(safety_backup.is_some());
+    assert_eq!(
+        vault::get_vault_auth_method(app.state()).await.unwrap(),
+        "oauth-argon2id"
+    );
+    h.lock().await;
+
+    vault::unlock_vault_oauth(token, app.clone(), app.state(), app.state(), app.state())
+        .await
+        .unwrap();
+    assert!(h.is_unlocked().await);
+    h.lock().await;
+
+    assert!(h.unlock(PASSWORD).await.is_err());
+}
+
+#[tokio::test]
+async fn generated_passwords_are_kept_while_unlocked() {
+    let h = harness();
+    vault::init_vault(PASSWORD.to_string(), h.app.state())
+        .await
+        .unwrap();
+    let generate = || {
+        generator::generate_password(
+            Default::default(),
+            h.app.state(),
+            h.app.state(),
+            h.app.state(),
+        )
+    };
+    let first = generate().await.unwrap();
+    let second = generate().await.unwrap();
+
+    let history = generator::get_generation_history(
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+    )
+    .await
+    .unwrap();
+    let passwords: Vec<&str> = history.iter().map(|g| g.password.as_str()).collect();
+    assert_eq!(passwords, vec![second.as_str(), first.as_str()]);
+
+    h.lock().await;
+    generate().await.unwrap();
+    assert!(generator::get_generation_history(
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+    )
+    .await
+    .is_err());
+
+    h.unlock(PASSWORD).await.unwrap();
+    generator::clear_generation_history(h.app.state(), h.app.state())
+        .await
+        .unwrap();
+    assert!(generator::get_generation_history(
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+        h.app.state(),
+    )
+    .await
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn guarded_reveals_need_a_fresh_confirmation_each_session() {
+    let h = harness();
+    let app = h.app.handle();
+    vault::init_vault(PASSWORD.to_string(), app.state())
+        .await
+        .unwrap();
+    let entry_id = credential::add_entry(
+        "GitHub".to_string(),
+        "octo".to_string(),
+        "hunter2".to_string(),
+        None,
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap()
+    .id;
+    let settings = app.state::<SettingsState>();
+    settings
+        .update(crate::settings::Settings {
+            reauth: crate::reauth::ReauthPolicy {
+                reveal_password: true,
+                ..Default::default()
+            },
+            ..settings.get()
+        })
+        .unwrap();
+    let reveal = |field: &str| {
+        credential::request_secret(
+            entry_id.clone(),
+            field.to_string(),
+            app.state(),
+            app.state(),
+            app.state(),
+            app.state(),
+        )
+    };
+
+    assert_eq!(reveal("username").await.unwrap(), "octo");
+    assert_eq!(
+        reveal("password").await.unwrap_err().kind(),
+        "ReauthRequired"
+    );
+    let generated = || {
+        generator::get_generation_history(
+            app.state(),
+            app.state(),
+            app.state(),
+            app.state(),
+            app.state(),
+        )
+    };
+    assert_eq!(generated().await.unwrap_err().kind(), "ReauthRequired");
+    vault::reauthenticate(PASSWORD.to_string(), app.state(), app.state(), app.state())
+        .await
+        .unwrap();
+    assert_eq!(reveal("password").await.unwrap(), "hunter2");
+    assert!(generated().await.is_ok());
+
+    h.lock().await;
+    h.unlock(PASSWORD).await.unwrap();
+    assert_eq!(
+        reveal("password").await.unwrap_err().kind(),
+        "ReauthRequired"
+    );
+    let wrong = vault::reauthenticate("guess".to_string(), app.state(), app.state(), app.state())
+        .await
+        .unwrap_err();
+    assert_eq!(wrong.kind(), "DecryptionFailed");
+    assert_eq!(
+        reveal("password").await.unwrap_err().kind(),
+        "ReauthRequired"
+    );
+}
+
+#[tokio::test]
+async fn custom_icons_are_validated_and_survive_edits() {
+    let h = harness();
+    let app = h.app.handle();
+    vault::init_vault(PASSWORD.to_string(), app.state())
+        .await
+        .unwrap();
+    let id = credential::add_entry(
+        "Intranet".to_string(),
+        "me".to_string(),
+        "hunter2".to_string(),
+        None,
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap()
+    .id;
+    let set = |icon: EntryIcon| {
+        credential::set_entry_icon(id.clone(), Some(icon), app.state(), app.state())
+    };
+    let icon = || async {
+        credential::search_entries("intranet".to_string(), app.state(), app.state())
+            .await
+            .unwrap()[0]
+            .icon
+            .clone()
+    };
+
+    for bad in [
+        EntryIcon::Emoji("ok".to_string()),
+        EntryIcon::Color("red".to_string()),
+        EntryIcon::Color("#12345".to_string()),
+    ] {
+        assert_eq!(set(bad).await.unwrap_err().kind(), "InvalidInput");
+    }
+    set(EntryIcon::Color("#1A2B3C".to_string())).await.unwrap();
+    assert_eq!(icon().await, Some(EntryIcon::Color("#1a2b3c".to_string())));
+
+    credential::update_entry(
+        id.clone(),
+        "Intranet".to_string(),
+        "me".to_string(),
+        "hunter3".to_string(),
+        None,
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(icon().await, Some(EntryIcon::Color("#1a2b3c".to_string())));
+
+    set(EntryIcon::Emoji("🛠️".to_string())).await.unwrap();
+    assert_eq!(icon().await, Some(EntryIcon::Emoji("🛠️".to_string())));
+    set(EntryIcon::Emoji(" ".to_string())).await.unwrap();
+    assert_eq!(icon().await, None);
+}
+
+#[tokio::test]
+async fn fixed_url_secrets_stay_out_of_the_history() {
+    let h = harness();
+    let app = h.app.handle();
+    vault::init_vault(PASSWORD.to_string(), app.state())
+        .await
+        .unwrap();
+    let id = credential::add_entry(
+        "Router".to_string(),
+        "admin".to_string(),
+        "s3cr3t-token".to_string(),
+        Some("https://router.local/admin?token=s3cr3t-token".to_string()),
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap()
+    .id;
+
+    health::fix_url_secret(id.clone(), app.state(), app.state())
+        .await
+        .unwrap();
+    let history = credential::get_entry_history(id, app.state())
+        .await
+        .unwrap();
+    let url = history
+        .iter()
+        .find(|c| c.field == crate::vault::changes::ChangedField::Url)
+        .unwrap();
+    assert_eq!(
+        url.old.as_deref(),
+        Some("https://router.local/admin?token=***")
+    );
+    assert_eq!(url.new.as_deref(), Some("https://router.local/admin"));
+    assert!(!format!("{:?}", history).contains("s3cr3t-token"));
+}
+
+#[tokio::test]
+async fn credentials_without_a_login_can_be_edited() {
+    let h = harness();
+    let app = h.app.handle();
+    vault::init_vault(PASSWORD.to_string(), app.state())
+        .await
+        .unwrap();
+    let update = |id: &str, username: &str, password: &str| {
+        credential::update_entry(
+            id.to_string(),
+            "Renamed".to_string(),
+            username.to_string(),
+            password.to_string(),
+            None,
+            None,
+            None,
+            None,
+            app.state(),
+            app.state(),
+        )
+    };
+
+    let bank = credential::add_bank_account(
+        "Checking".to_string(),
+        "DE89370400440532013000".to_string(),
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    update(&bank, "", "").await.unwrap();
+    update(&bank, "", "4321").await.unwrap();
+
+    let api_key = credential::add_api_key(
+        "Stripe".to_string(),
+        "sk_live".to_string(),
+        "old-secret".to_string(),
+        Vec::new(),
+        None,
+        None,
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    update(&api_key, "", "rotated-secret").await.unwrap();
+    assert_eq!(
+        update(&api_key, "", "").await.unwrap_err().kind(),
+        "InvalidInput"
+    );
+
+    let server = credential::add_server(
+        "Build box".to_string(),
+        "build.internal".to_string(),
+        None,
+        crate::vault::server::ServerProtocol::Vnc,
+        String::new(),
+        None,
+        None,
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    update(&server, "", "").await.unwrap();
+    update(&server, "admin", "").await.unwrap();
+
+    let reference = credential::add_secret_reference(
+        "Deploy token".to_string(),
+        crate::vault::reference::ReferenceSource::EnvVar,
+        "DEPLOY_TOKEN".to_string(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap();
+    update(&reference, "", "").await.unwrap();
+    assert_eq!(
+        update(&reference, "", "the-token")
+            .await
+            .unwrap_err()
+            .kind(),
+        "InvalidInput"
+    );
+
+    let login = credential::add_entry(
+        "GitHub".to_string(),
+        "octo".to_string(),
+        "hunter2".to_string(),
+        None,
+        None,
+        None,
+        None,
+        app.state(),
+        app.state(),
+        app.state(),
+    )
+    .await
+    .unwrap()
+    .id;
+    assert_eq!(
+        update(&login, "", "hunter2").await.unwrap_err().kind(),
+        "InvalidInput"
+    );
+    assert_eq!(
+        update(&login, "octo", "").await.unwrap_err().kind(),
+        "InvalidInput"
+    );
+}
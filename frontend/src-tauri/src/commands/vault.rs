@@ -1,335 +1,638 @@
-use crate::commands::VaultState;
+use crate::audit::{AuditKind, AuditLog};
+use crate::auth::github::{self, DeviceCode};
+use crate::auth::lockout::AuthAttemptState;
+use crate::auth::method::AuthMethod;
+use crate::auth::oauth::{identity_provider, OAuthProvider};
+use crate::auth::provider::{self, Credentials, Enrollment};
+use crate::commands::backup::{before_destructive, Guarded};
+use crate::commands::{SettingsState, VaultState};
+use crate::confirmation::{ConfirmOperation, ConfirmationState, ConfirmationToken};
+use crate::error::LatchError;
+use crate::integrity::{self, BinaryIntegrity};
+use crate::reauth::SensitiveAction;
+use crate::vault::backup::Destructive;
+use crate::vault::repair::RepairReport;
+use crate::vault::{storage::VaultStorage, workspace::Workspace};
 use crate::AuthState;
-use serde_json::json;
-use tauri::{AppHandle, State};
+use serde::Serialize;
+use specta::Type;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, Runtime, State};
+
+const REENROLL_REQUIRED: &str =
+    "Unlock again to re-enroll: the app has changed since this key was enrolled";
+const UNVERIFIED_BINARY: &str =
+    "Unlock again to re-enroll: there is no record of the app this key was enrolled with";
+
+#[derive(Debug, Serialize, Type)]
+pub struct VaultStatus {
+    pub has_vault: bool,
+    pub is_unlocked: bool,
+    /// The Vault is in memory only (see `--ephemeral`) and holds fake data.
+    pub ephemeral: bool,
+}
 
-fn decode_salt_hex(salt_hex: &str) -> Result<[u8; 32], String> {
-    let salt_bytes = hex::decode(salt_hex).map_err(|e| format!("Invalid salt: {}", e))?;
-    if salt_bytes.len() != 32 {
-        return Err("Salt must be 32 bytes".to_string());
+fn audit_unlock(audit: &AuditLog, workspace: &Workspace, succeeded: bool) {
+    if !succeeded {
+        audit.record(AuditKind::FailedUnlock, None);
+        return;
+    }
+    if workspace.decoy {
+        audit.record(AuditKind::Unlock, Some("duress".to_string()));
+        return;
     }
-    let mut salt = [0u8; 32];
-    salt.copy_from_slice(&salt_bytes);
-    Ok(salt)
+    if let Some(key) = workspace.session_key.as_deref() {
+        if let Err(e) = audit.ensure_key(key) {
+            eprintln!("Failed to prepare audit log: {}", e);
+        }
+    }
+    audit.record(AuditKind::Unlock, None);
+}
+
+/// Rotates the Vault key and carries the audit log over to the new key.
+fn rotate_audited(
+    audit: &AuditLog,
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    enrollment: &Enrollment,
+) -> Result<(), String> {
+    let old_key = workspace.session_key.clone().ok_or("Vault is locked")?;
+    crate::vault::rotate::rotate(
+        storage,
+        workspace,
+        &enrollment.key,
+        enrollment.method,
+        &enrollment.salt,
+    )?;
+    if !workspace.decoy {
+        if let Err(e) = audit.rekey(&old_key, &enrollment.key) {
+            eprintln!("Failed to rekey audit log: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn provision(
+    storage: &VaultStorage,
+    workspace: &mut Workspace,
+    enrollment: &Enrollment,
+) -> Result<(), String> {
+    crate::vault::provision::provision(
+        storage,
+        workspace,
+        &enrollment.key,
+        enrollment.method,
+        &enrollment.salt,
+    )
+}
+
+/// Derives the key of the Vault on disk from whatever the user presented.
+fn vault_key(storage: &VaultStorage, credentials: &Credentials) -> Result<[u8; 32], String> {
+    let header = storage.read_header()?;
+    provider::vault_key(&header.kdf, &header.salt, credentials)
+}
+
+/// Has `kind` vouch for what its sign-in handed back, and derives the key of a
+/// Vault that account signs in to.
+async fn enroll_identity(kind: OAuthProvider, token: &str) -> Result<Enrollment, String> {
+    let identity = identity_provider(kind).validate(token).await?;
+    provider::provider(AuthMethod::OAuth).enroll(&Credentials::Identity(&identity))
 }
 
+/// Creates an OAuth Vault; `token` is what `provider`'s sign-in handed back.
 #[tauri::command]
+#[specta::specta]
 pub async fn init_vault_oauth(
-    id_token: String,
+    provider: OAuthProvider,
+    token: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let user_id = crate::auth::oauth::extract_user_id(&id_token)
-        .map_err(|e| format!("Invalid ID token: {}", e))?;
-    let key = crate::auth::oauth::derive_key(&user_id)?;
+) -> Result<(), LatchError> {
+    let enrollment = enroll_identity(provider, &token).await?;
 
-    state.lock(|storage, workspace| {
-        crate::vault::provision::provision(
-            storage,
-            workspace,
-            &key,
-            crate::auth::method::AuthMethod::OAuth,
-            &user_id,
-        )
-    })?;
+    state.lock(|storage, workspace| provision(storage, workspace, &enrollment))?;
+
+    Ok(())
+}
 
-    Ok(json!({"status": "success"}).to_string())
+/// Starts a GitHub sign-in: the code for the user to enter on GitHub, and the
+/// device code to poll `poll_github_sign_in` with.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_github_sign_in() -> Result<DeviceCode, LatchError> {
+    Ok(github::start().await?)
 }
 
+/// The GitHub access token once the user has entered the code; None until then.
 #[tauri::command]
+#[specta::specta]
+pub async fn poll_github_sign_in(device_code: String) -> Result<Option<String>, LatchError> {
+    Ok(github::poll(&device_code).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
 pub async fn init_vault_with_key(
     key_hex: String,
     kdf: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let key_bytes = hex::decode(&key_hex).map_err(|e| format!("Invalid key hex: {}", e))?;
-    if key_bytes.len() != 32 {
-        return Err("Key must be 32 bytes".to_string());
+    integrity: State<'_, BinaryIntegrity>,
+) -> Result<(), LatchError> {
+    let key = provider::decode_key_hex(&key_hex)?;
+    let enrollment = provider::for_tag(&kdf)?.enroll(&Credentials::Key(key))?;
+
+    state.lock(|storage, workspace| provision(storage, workspace, &enrollment))?;
+    if integrity::uses_stored_key(&kdf) {
+        integrity.enroll()?;
     }
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&key_bytes);
-
-    let auth_method = crate::auth::method::AuthMethod::from_vault_tag(&kdf)
-        .ok_or_else(|| format!("Unknown KDF: {}", kdf))?;
 
-    state.lock(|storage, workspace| {
-        crate::vault::provision::provision(storage, workspace, &key, auth_method, "")
-    })?;
-
-    Ok(json!({"status": "success"}).to_string())
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn init_vault(password: String, state: State<'_, VaultState>) -> Result<String, String> {
-    let salt = crate::auth::password::generate_salt();
-    let key = crate::auth::password::derive_key(&password, &salt);
-    let salt_hex = hex::encode(salt);
+#[specta::specta]
+pub async fn init_vault(password: String, state: State<'_, VaultState>) -> Result<(), LatchError> {
+    let enrollment =
+        provider::provider(AuthMethod::Password).enroll(&Credentials::Password(&password))?;
 
-    state.lock(|storage, workspace| {
-        crate::vault::provision::provision(
-            storage,
-            workspace,
-            &key,
-            crate::auth::method::AuthMethod::Password,
-            &salt_hex,
-        )
-    })?;
+    state.lock(|storage, workspace| provision(storage, workspace, &enrollment))?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(())
 }
 
-#[tauri::command]
-pub async fn unlock_vault_oauth(
-    id_token: String,
-    app_handle: AppHandle,
-    vault_state: State<'_, VaultState>,
-    auth_state: State<'_, AuthState>,
-) -> Result<String, String> {
-    let mut auth = auth_state
-        .0
+/// Finishes an unlock attempt: updates the lockout counter and the audit log, and
+/// starts the Session timers on success.
+fn complete_unlock<R: Runtime>(
+    result: Result<(), String>,
+    workspace: &Workspace,
+    auth: &mut AuthAttemptState,
+    audit: &AuditLog,
+    app_handle: AppHandle<R>,
+    state_arc: Arc<Mutex<(VaultStorage, Workspace)>>,
+) -> Result<(), String> {
+    match result {
+        Ok(()) => {
+            auth.reset();
+            audit_unlock(audit, workspace, true);
+            if let Some(start) = workspace.session_start {
+                crate::breach_watch::spawn(app_handle.clone(), state_arc.clone(), start);
+                crate::sync::spawn(app_handle.clone(), state_arc.clone(), start);
+                crate::lan_sync::spawn(app_handle.clone(), state_arc.clone(), start);
+                crate::file_watch::spawn(app_handle.clone(), state_arc.clone(), start);
+                crate::spawn_idle_watcher(app_handle, state_arc.clone(), start);
+                crate::spawn_session_timer(state_arc, start);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            audit_unlock(audit, workspace, false);
+            let error_msg = match auth.record_failure() {
+                Err(msg) => format!("\n{}", msg),
+                Ok(()) => String::new(),
+            };
+            Err(format!("{}{}", e, error_msg))
+        }
+    }
+}
+
+fn locked_out(auth_state: &AuthState) -> Result<MutexGuard<'_, AuthAttemptState>, LatchError> {
+    let auth = auth_state
+        .attempts
         .lock()
         .map_err(|_| "Auth state temporarily unavailable")?;
-
     if auth.is_locked_out() {
-        return Err("Too many failed attempts. Please try again later.".to_string());
+        return Err(LatchError::LockedOut(
+            "Too many failed attempts. Please try again later.".to_string(),
+        ));
     }
+    Ok(auth)
+}
 
-    let user_id = crate::auth::oauth::extract_user_id(&id_token).map_err(|e| {
-        auth.record_failure().ok();
-        format!("Invalid ID token: {}", e)
-    })?;
-    let key = crate::auth::oauth::derive_key(&user_id)?;
-
+/// Unlocks an OAuth Vault with what its provider's sign-in handed back; the
+/// Vault header says which provider that is.
+#[tauri::command]
+#[specta::specta]
+pub async fn unlock_vault_oauth<R: Runtime>(
+    token: String,
+    app_handle: AppHandle<R>,
+    vault_state: State<'_, VaultState>,
+    auth_state: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<(), LatchError> {
+    let kind = vault_state.lock(|storage, _| Ok(storage.read_header()?.identity_provider()))?;
+    let identity = match kind {
+        Some(kind) => identity_provider(kind).validate(&token).await,
+        None => Err("This vault cannot be unlocked with an OAuth sign-in".to_string()),
+    };
+    let mut auth = locked_out(&auth_state)?;
     let state_arc = vault_state.0.clone();
-    vault_state.lock(|storage, workspace| {
-        match crate::vault::access::access(storage, workspace, &key) {
-            Ok(_) => {
-                auth.reset();
-                if let Some(start) = workspace.session_start {
-                    crate::spawn_session_timer(app_handle, state_arc, start);
-                }
-                Ok(json!({"status": "success"}).to_string())
-            }
-            Err(e) => {
-                let auth_error = auth.record_failure();
-                let error_msg = if let Err(msg) = auth_error {
-                    format!("\n{}", msg)
-                } else {
-                    String::new()
-                };
-                Err(format!("{}{}", e, error_msg))
-            }
-        }
-    })
+    vault_state
+        .lock(|storage, workspace| {
+            let result = identity
+                .and_then(|identity| vault_key(storage, &Credentials::Identity(&identity)))
+                .and_then(|key| crate::vault::access::access(storage, workspace, &key));
+            complete_unlock(result, workspace, &mut auth, &audit, app_handle, state_arc)
+        })
+        .map_err(LatchError::from)
 }
 
 #[tauri::command]
-pub async fn unlock_vault_with_key(
+#[specta::specta]
+pub async fn unlock_vault_with_key<R: Runtime>(
     key_hex: String,
-    app_handle: AppHandle,
+    app_handle: AppHandle<R>,
     vault_state: State<'_, VaultState>,
     auth_state: State<'_, AuthState>,
-) -> Result<String, String> {
-    let mut auth = auth_state
-        .0
-        .lock()
-        .map_err(|_| "Auth state temporarily unavailable")?;
-
-    if auth.is_locked_out() {
-        return Err("Too many failed attempts. Please try again later.".to_string());
+    audit: State<'_, AuditLog>,
+    integrity: State<'_, BinaryIntegrity>,
+) -> Result<(), LatchError> {
+    if integrity.changed() {
+        return Err(LatchError::ReauthRequired(REENROLL_REQUIRED.to_string()));
     }
-
-    let key_bytes = hex::decode(&key_hex).map_err(|e| {
-        auth.record_failure().ok();
-        format!("Invalid key hex: {}", e)
-    })?;
-    if key_bytes.len() != 32 {
-        auth.record_failure().ok();
-        return Err("Key must be 32 bytes".to_string());
+    // A deleted record must not let whichever build unlocks next enroll itself.
+    if !integrity.is_enrolled() {
+        return Err(LatchError::ReauthRequired(UNVERIFIED_BINARY.to_string()));
     }
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&key_bytes);
-
+    let mut auth = locked_out(&auth_state)?;
     let state_arc = vault_state.0.clone();
     vault_state.lock(|storage, workspace| {
-        match crate::vault::access::access(storage, workspace, &key) {
-            Ok(_) => {
-                auth.reset();
-                if let Some(start) = workspace.session_start {
-                    crate::spawn_session_timer(app_handle, state_arc, start);
-                }
-                Ok(json!({"status": "success"}).to_string())
-            }
-            Err(e) => {
-                let auth_error = auth.record_failure();
-                let error_msg = if let Err(msg) = auth_error {
-                    format!("\n{}", msg)
-                } else {
-                    String::new()
-                };
-                Err(format!("{}{}", e, error_msg))
-            }
-        }
-    })
+        let result = provider::decode_key_hex(&key_hex)
+            .and_then(|key| vault_key(storage, &Credentials::Key(key)))
+            .and_then(|key| crate::vault::access::access(storage, workspace, &key));
+        complete_unlock(result, workspace, &mut auth, &audit, app_handle, state_arc)
+    })?;
+    Ok(())
 }
 
+/// Unlocks a stored-key Vault after the app binary changed and moves it onto
+/// `new_key_hex`, so a key the old binary could read is never used again. The
+/// caller fetches `key_hex` from the keychain with a full credential prompt rather
+/// than a biometric one.
 #[tauri::command]
-pub async fn unlock_vault(
-    password: String,
-    app_handle: AppHandle,
+#[specta::specta]
+pub async fn reenroll_vault_key<R: Runtime>(
+    key_hex: String,
+    new_key_hex: String,
+    app_handle: AppHandle<R>,
     vault_state: State<'_, VaultState>,
     auth_state: State<'_, AuthState>,
-) -> Result<String, String> {
-    let mut auth = auth_state
-        .0
-        .lock()
-        .map_err(|_| "Auth state temporarily unavailable")?;
+    audit: State<'_, AuditLog>,
+    integrity: State<'_, BinaryIntegrity>,
+) -> Result<Guarded<()>, LatchError> {
+    let new_key = provider::decode_key_hex(&new_key_hex)?;
+    let mut auth = locked_out(&auth_state)?;
+    let state_arc = vault_state.0.clone();
+    let rotated = vault_state.lock(|storage, workspace| {
+        let kdf = storage.read_header()?.kdf;
+        if !integrity::uses_stored_key(&kdf) {
+            return Err("Invalid re-enrollment: this Vault does not use a stored key".to_string());
+        }
+        let result = provider::decode_key_hex(&key_hex)
+            .and_then(|key| vault_key(storage, &Credentials::Key(key)))
+            .and_then(|key| crate::vault::access::access(storage, workspace, &key));
+        complete_unlock(result, workspace, &mut auth, &audit, app_handle, state_arc)?;
+
+        let enrollment = provider::for_tag(&kdf)?.enroll(&Credentials::Key(new_key))?;
+        let safety_backup = before_destructive(&audit, storage, workspace, Destructive::Reencrypt)?;
+        rotate_audited(&audit, storage, workspace, &enrollment)?;
+        Ok(Guarded {
+            value: (),
+            safety_backup,
+        })
+    })?;
+    integrity.enroll()?;
+    Ok(rotated)
+}
 
-    if auth.is_locked_out() {
-        return Err("Too many failed attempts. Please try again later.".to_string());
+/// Re-checks the app binary, e.g. once the updater has replaced it, and locks the
+/// Vault if it no longer matches the one that enrolled the stored key.
+#[tauri::command]
+#[specta::specta]
+pub async fn check_binary_integrity(
+    state: State<'_, VaultState>,
+    integrity: State<'_, BinaryIntegrity>,
+) -> Result<bool, LatchError> {
+    let changed = integrity.check();
+    if changed {
+        state.lock(|_, workspace| {
+            workspace.lock();
+            Ok(())
+        })?;
     }
+    Ok(changed)
+}
 
+#[tauri::command]
+#[specta::specta]
+pub async fn unlock_vault<R: Runtime>(
+    password: String,
+    app_handle: AppHandle<R>,
+    vault_state: State<'_, VaultState>,
+    auth_state: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<(), LatchError> {
+    let mut auth = locked_out(&auth_state)?;
     let state_arc = vault_state.0.clone();
-    vault_state.lock(|storage, workspace| {
-        let vault_file = storage.read()?;
-        if vault_file.kdf != "password-pbkdf2" {
-            return Err("Failed to unlock vault".to_string());
-        }
+    vault_state
+        .lock(|storage, workspace| {
+            let result = vault_key(storage, &Credentials::Password(&password))
+                .and_then(|key| crate::vault::access::access(storage, workspace, &key))
+                .or_else(|e| {
+                    crate::vault::duress::access_with_password(storage, workspace, &password)
+                        .map_err(|_| e)
+                });
+            complete_unlock(result, workspace, &mut auth, &audit, app_handle, state_arc)
+        })
+        .map_err(LatchError::from)
+}
 
-        let salt =
-            decode_salt_hex(&vault_file.salt).map_err(|_| "Failed to unlock vault".to_string())?;
+#[tauri::command]
+#[specta::specta]
+pub async fn get_vault_auth_method(state: State<'_, VaultState>) -> Result<String, LatchError> {
+    state
+        .lock(|storage, _| {
+            let method = if storage.exists() {
+                storage
+                    .read_header()
+                    .map(|v| v.kdf)
+                    .unwrap_or_else(|_| "none".to_string())
+            } else {
+                "none".to_string()
+            };
+
+            Ok(method)
+        })
+        .map_err(LatchError::from)
+}
 
-        let key = crate::auth::password::derive_key(&password, &salt);
+#[tauri::command]
+#[specta::specta]
+pub async fn vault_status(state: State<'_, VaultState>) -> Result<VaultStatus, LatchError> {
+    state
+        .lock(|storage, workspace| {
+            Ok(VaultStatus {
+                has_vault: storage.exists(),
+                is_unlocked: workspace.is_unlocked(),
+                ephemeral: storage.is_ephemeral(),
+            })
+        })
+        .map_err(LatchError::from)
+}
 
-        match crate::vault::access::access(storage, workspace, &key) {
-            Ok(_) => {
-                auth.reset();
-                if let Some(start) = workspace.session_start {
-                    crate::spawn_session_timer(app_handle, state_arc, start);
-                }
-                Ok(json!({"status": "success"}).to_string())
-            }
-            Err(e) => {
-                let auth_error = auth.record_failure();
-                let error_msg = if let Err(msg) = auth_error {
-                    format!("\n{}", msg)
-                } else {
-                    String::new()
-                };
-                Err(format!("{}{}", e, error_msg))
-            }
-        }
-    })
+/// Replaces a Vault file that no longer parses with the best copy that can be
+/// found, reporting what was recovered and what was lost. Works while locked.
+#[tauri::command]
+#[specta::specta]
+pub async fn attempt_vault_repair(
+    state: State<'_, VaultState>,
+) -> Result<RepairReport, LatchError> {
+    state
+        .lock(|storage, _| crate::vault::repair::repair(storage))
+        .map_err(LatchError::from)
 }
 
+/// Settles a Vault file changed outside Latch that doesn't open with this
+/// Session's key by saving the unlocked copy over it. The other file is copied
+/// aside first; returns where.
 #[tauri::command]
-pub async fn get_vault_auth_method(state: State<'_, VaultState>) -> Result<String, String> {
-    state.lock(|storage, _| {
-        let method = if storage.exists() {
-            storage
-                .read()
-                .map(|v| v.kdf)
-                .unwrap_or_else(|_| "none".to_string())
+#[specta::specta]
+pub async fn keep_this_vault_copy(state: State<'_, VaultState>) -> Result<String, LatchError> {
+    let aside = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        crate::vault::external::keep_this_copy(storage, workspace)
+    })?;
+
+    Ok(aside)
+}
+
+/// Confirms the user is the one who unlocked, for actions the re-auth policy
+/// guards (see `reauth`). `credential` is what unlocking takes: the master
+/// password, the provider's token, or the stored key in hex, fetched with a
+/// fresh biometric prompt. Returns how many seconds the confirmation lasts.
+#[tauri::command]
+#[specta::specta]
+pub async fn reauthenticate(
+    credential: String,
+    vault_state: State<'_, VaultState>,
+    auth_state: State<'_, AuthState>,
+    audit: State<'_, AuditLog>,
+) -> Result<u64, LatchError> {
+    let kind = vault_state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        if workspace.decoy {
+            return Ok(None);
+        }
+        Ok(storage.read_header()?.identity_provider())
+    })?;
+    let identity = match kind {
+        Some(kind) => Some(identity_provider(kind).validate(&credential).await),
+        None => None,
+    };
+    let mut auth = locked_out(&auth_state)?;
+    let confirmed = vault_state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        // A duress Session checks against the decoy Vault, so it can't be told apart.
+        let header = if workspace.decoy {
+            crate::vault::duress::decoy_storage(storage)?.read_header()?
         } else {
-            "none".to_string()
+            storage.read_header()?
         };
-
-        Ok(json!({
-            "status": "success",
-            "auth_method": method
-        })
-        .to_string())
-    })
+        let identity = identity.transpose();
+        let key = identity.and_then(|identity| {
+            let credentials =
+                provider::credentials_for(&header.kdf, &credential, identity.as_ref())?;
+            provider::vault_key(&header.kdf, &header.salt, &credentials)
+        });
+        let matches = key.is_ok_and(|key| workspace.session_key.as_deref() == Some(&key));
+        Ok(workspace.session_start.filter(|_| matches))
+    })?;
+    match confirmed {
+        Some(session_start) => {
+            audit.record(AuditKind::Reauthenticated, None);
+            auth.reset();
+            auth_state.reauth.open(session_start);
+            Ok(crate::reauth::REAUTH_WINDOW_SECS)
+        }
+        None => {
+            audit.record(
+                AuditKind::FailedUnlock,
+                Some("re-authentication".to_string()),
+            );
+            let error_msg = match auth.record_failure() {
+                Err(msg) => format!("\n{}", msg),
+                Ok(()) => String::new(),
+            };
+            Err(LatchError::DecryptionFailed(format!(
+                "That isn't this vault's credential{}",
+                error_msg
+            )))
+        }
+    }
 }
 
+/// Who an OAuth Vault signs in with, so the lock screen offers the right
+/// button; None for any other Vault, or none at all.
 #[tauri::command]
-pub async fn vault_status(state: State<'_, VaultState>) -> Result<String, String> {
-    state.lock(|storage, workspace| {
-        let unlocked = workspace.is_unlocked();
-        let has_vault = storage.exists();
-        Ok(json!({
-            "status": "success",
-            "has_vault": has_vault,
-            "is_unlocked": unlocked
+#[specta::specta]
+pub async fn get_vault_identity_provider(
+    state: State<'_, VaultState>,
+) -> Result<Option<OAuthProvider>, LatchError> {
+    state
+        .lock(|storage, _| {
+            if !storage.exists() {
+                return Ok(None);
+            }
+            Ok(storage.read_header()?.identity_provider())
         })
-        .to_string())
-    })
+        .map_err(LatchError::from)
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn reencrypt_vault(
     new_key_hex: String,
     new_kdf: String,
-    new_salt: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let key_bytes = hex::decode(&new_key_hex).map_err(|e| format!("Invalid key hex: {}", e))?;
-    if key_bytes.len() != 32 {
-        return Err("Key must be 32 bytes".to_string());
+    audit: State<'_, AuditLog>,
+    integrity: State<'_, BinaryIntegrity>,
+) -> Result<Guarded<()>, LatchError> {
+    let key = provider::decode_key_hex(&new_key_hex)?;
+    let enrollment = provider::for_tag(&new_kdf)?.enroll(&Credentials::Key(key))?;
+
+    let rotated = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let safety_backup = before_destructive(&audit, storage, workspace, Destructive::Reencrypt)?;
+        rotate_audited(&audit, storage, workspace, &enrollment)?;
+        Ok(Guarded {
+            value: (),
+            safety_backup,
+        })
+    })?;
+    if integrity::uses_stored_key(&new_kdf) {
+        integrity.enroll()?;
     }
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&key_bytes);
 
-    let auth_method = crate::auth::method::AuthMethod::from_vault_tag(&new_kdf)
-        .ok_or_else(|| format!("Unknown KDF: {}", new_kdf))?;
+    Ok(rotated)
+}
 
-    state.lock(|storage, workspace| {
-        crate::vault::rotate::rotate(storage, workspace, &key, auth_method, &new_salt)
+#[tauri::command]
+#[specta::specta]
+pub async fn reencrypt_vault_to_oauth(
+    provider: OAuthProvider,
+    token: String,
+    state: State<'_, VaultState>,
+    audit: State<'_, AuditLog>,
+) -> Result<Guarded<()>, LatchError> {
+    let enrollment = enroll_identity(provider, &token).await?;
+
+    let rotated = state.lock(|storage, workspace| {
+        workspace.check_session()?;
+        let safety_backup = before_destructive(&audit, storage, workspace, Destructive::Reencrypt)?;
+        rotate_audited(&audit, storage, workspace, &enrollment)?;
+        Ok(Guarded {
+            value: (),
+            safety_backup,
+        })
     })?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(rotated)
 }
 
 #[tauri::command]
-pub async fn reencrypt_vault_to_oauth(
-    id_token: String,
+#[specta::specta]
+pub async fn migrate_to_oauth(
+    password: String,
+    provider: OAuthProvider,
+    token: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let user_id = crate::auth::oauth::extract_user_id(&id_token)
-        .map_err(|e| format!("Invalid ID token: {}", e))?;
-    let key = crate::auth::oauth::derive_key(&user_id)?;
+    audit: State<'_, AuditLog>,
+) -> Result<Guarded<()>, LatchError> {
+    let enrollment = enroll_identity(provider, &token).await?;
 
-    state.lock(|storage, workspace| {
-        crate::vault::rotate::rotate(
-            storage,
-            workspace,
-            &key,
-            crate::auth::method::AuthMethod::OAuth,
-            &user_id,
-        )
+    let migrated = state.lock(|storage, workspace| {
+        let password_key = vault_key(storage, &Credentials::Password(&password))?;
+        crate::vault::access::access(storage, workspace, &password_key)?;
+        let safety_backup =
+            before_destructive(&audit, storage, workspace, Destructive::MigrateToOAuth)?;
+        rotate_audited(&audit, storage, workspace, &enrollment)?;
+        Ok(Guarded {
+            value: (),
+            safety_backup,
+        })
     })?;
 
-    Ok(json!({"status": "success"}).to_string())
+    Ok(migrated)
 }
 
 #[tauri::command]
-pub async fn migrate_to_oauth(
-    password: String,
-    id_token: String,
+#[specta::specta]
+pub async fn configure_duress_vault(
+    duress_password: String,
     state: State<'_, VaultState>,
-) -> Result<String, String> {
-    let user_id = crate::auth::oauth::extract_user_id(&id_token)
-        .map_err(|e| format!("Invalid ID token: {}", e))?;
+) -> Result<(), LatchError> {
+    if duress_password.trim().is_empty() {
+        return Err(LatchError::InvalidInput(
+            "Duress password cannot be empty".to_string(),
+        ));
+    }
+
+    let credentials = Credentials::Password(&duress_password);
+    let enrollment = provider::provider(AuthMethod::Password).enroll(&credentials)?;
 
     state.lock(|storage, workspace| {
-        let vault_file = storage.read()?;
-        if vault_file.kdf != "password-pbkdf2" {
-            return Err("Migration is only supported from password-based vaults".to_string());
+        // Only password Vaults can collide with the duress password.
+        if let Ok(real_key) = vault_key(storage, &credentials) {
+            if workspace.session_key.as_deref() == Some(&real_key) {
+                return Err("Duress password must differ from the master password".to_string());
+            }
         }
+        crate::vault::duress::configure(storage, workspace, &enrollment.key, &enrollment.salt)
+    })?;
 
-        let salt = decode_salt_hex(&vault_file.salt)?;
+    Ok(())
+}
 
-        let password_key = crate::auth::password::derive_key(&password, &salt);
-        crate::vault::access::access(storage, workspace, &password_key)?;
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_duress_vault(
+    confirmation_token: String,
+    state: State<'_, VaultState>,
+    confirmations: State<'_, ConfirmationState>,
+) -> Result<(), LatchError> {
+    confirmations.redeem(ConfirmOperation::RemoveDuressVault, &confirmation_token)?;
+    state.lock(crate::vault::duress::remove)?;
 
-        let oauth_key = crate::auth::oauth::derive_key(&user_id)?;
-        crate::vault::rotate::rotate(
-            storage,
-            workspace,
-            &oauth_key,
-            crate::auth::method::AuthMethod::OAuth,
-            &user_id,
-        )
+    Ok(())
+}
+
+/// Deletes the Vault, its backups and any duress Vault. Needs a token from
+/// `request_confirmation`.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_vault(
+    confirmation_token: String,
+    state: State<'_, VaultState>,
+    confirmations: State<'_, ConfirmationState>,
+    settings: State<'_, SettingsState>,
+    auth_state: State<'_, AuthState>,
+) -> Result<(), LatchError> {
+    let policy = settings.get().reauth;
+    state.lock(|_, workspace| {
+        auth_state
+            .reauth
+            .check(&policy, SensitiveAction::DeleteVault, workspace)
     })?;
+    confirmations.redeem(ConfirmOperation::DeleteVault, &confirmation_token)?;
+    state.lock(crate::vault::provision::destroy)?;
+
+    Ok(())
+}
 
-    Ok(json!({"status": "success"}).to_string())
+/// First step of every irreversible command: a single-use token for `operation`.
+#[tauri::command]
+#[specta::specta]
+pub async fn request_confirmation(
+    operation: ConfirmOperation,
+    confirmations: State<'_, ConfirmationState>,
+) -> Result<ConfirmationToken, LatchError> {
+    Ok(confirmations.issue(operation))
 }
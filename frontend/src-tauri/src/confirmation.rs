@@ -0,0 +1,94 @@
+//! Two-step confirmation for operations that cannot be undone. The frontend asks for
+//! a token naming the operation, shows its own prompt, then passes the token to the
+//! operation's command. Tokens are single-use and expire quickly, so a stray or
+//! replayed call can never run one of these on its own.
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const CONFIRMATION_TTL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmOperation {
+    DeleteVault,
+    RemoveDuressVault,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ConfirmationToken {
+    pub token: String,
+    pub operation: ConfirmOperation,
+    pub expires_in_secs: u64,
+}
+
+/// Tokens handed out and not yet used.
+#[derive(Default)]
+pub struct ConfirmationState(Mutex<HashMap<String, (ConfirmOperation, Instant)>>);
+
+impl ConfirmationState {
+    pub fn issue(&self, operation: ConfirmOperation) -> ConfirmationToken {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let expires = Instant::now() + Duration::from_secs(CONFIRMATION_TTL_SECS);
+
+        let mut pending = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        pending.retain(|_, (_, expires)| *expires > now);
+        pending.insert(token.clone(), (operation, expires));
+        ConfirmationToken {
+            token,
+            operation,
+            expires_in_secs: CONFIRMATION_TTL_SECS,
+        }
+    }
+
+    /// Uses up `token`. It must have been issued for this `operation` and not expired.
+    pub fn redeem(&self, operation: ConfirmOperation, token: &str) -> Result<(), String> {
+        let mut pending = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.remove(token) {
+            Some((issued_for, expires)) if issued_for == operation && expires > Instant::now() => {
+                Ok(())
+            }
+            _ => Err("Invalid confirmation: request a new one and try again".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_works_once_and_only_for_its_operation() {
+        let state = ConfirmationState::default();
+        let issued = state.issue(ConfirmOperation::DeleteVault);
+
+        assert!(state
+            .redeem(ConfirmOperation::RemoveDuressVault, &issued.token)
+            .is_err());
+        let issued = state.issue(ConfirmOperation::DeleteVault);
+        assert!(state
+            .redeem(ConfirmOperation::DeleteVault, &issued.token)
+            .is_ok());
+        assert!(state
+            .redeem(ConfirmOperation::DeleteVault, &issued.token)
+            .is_err());
+    }
+
+    #[test]
+    fn expired_token_is_refused() {
+        let state = ConfirmationState::default();
+        let issued = state.issue(ConfirmOperation::DeleteVault);
+        state.0.lock().unwrap().get_mut(&issued.token).unwrap().1 = Instant::now();
+
+        assert!(state
+            .redeem(ConfirmOperation::DeleteVault, &issued.token)
+            .is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use specta::Type;
+use std::fmt;
+
+/// Error returned by every command. Serializes as `{ kind, message, retryable }` so the
+/// frontend can branch on `kind` instead of matching English text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LatchError {
+    VaultLocked,
+    SessionExpired,
+    DecryptionFailed(String),
+    LockedOut(String),
+    /// The Credential's reveal cooldown is running, or the action needs the Vault's
+    /// credential re-entered first (see `reauthenticate`).
+    ReauthRequired(String),
+    /// Refused in a guest session.
+    Forbidden(String),
+    /// Called too often in a short time (see `rate_limit`).
+    RateLimited(String),
+    NotFound(String),
+    InvalidInput(String),
+    Unavailable(String),
+    Io(String),
+    Internal(String),
+}
+
+impl LatchError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LatchError::VaultLocked => "VaultLocked",
+            LatchError::SessionExpired => "SessionExpired",
+            LatchError::DecryptionFailed(_) => "DecryptionFailed",
+            LatchError::LockedOut(_) => "LockedOut",
+            LatchError::ReauthRequired(_) => "ReauthRequired",
+            LatchError::Forbidden(_) => "Forbidden",
+            LatchError::RateLimited(_) => "RateLimited",
+            LatchError::NotFound(_) => "NotFound",
+            LatchError::InvalidInput(_) => "InvalidInput",
+            LatchError::Unavailable(_) => "Unavailable",
+            LatchError::Io(_) => "Io",
+            LatchError::Internal(_) => "Internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            LatchError::VaultLocked => "Vault is locked",
+            LatchError::SessionExpired => "Session expired",
+            LatchError::DecryptionFailed(m)
+            | LatchError::LockedOut(m)
+            | LatchError::ReauthRequired(m)
+            | LatchError::Forbidden(m)
+            | LatchError::RateLimited(m)
+            | LatchError::NotFound(m)
+            | LatchError::InvalidInput(m)
+            | LatchError::Unavailable(m)
+            | LatchError::Io(m)
+            | LatchError::Internal(m) => m,
+        }
+    }
+
+    /// Whether trying the same thing again later can succeed without the user changing anything.
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            LatchError::LockedOut(_)
+                | LatchError::RateLimited(_)
+                | LatchError::Unavailable(_)
+                | LatchError::Io(_)
+        )
+    }
+}
+
+impl fmt::Display for LatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for LatchError {}
+
+impl Serialize for LatchError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("LatchError", 3)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("retryable", &self.retryable())?;
+        s.end()
+    }
+}
+
+// Describes the serialized shape above to the generated TypeScript bindings.
+#[derive(Type)]
+#[specta(remote = LatchError, rename = "LatchError")]
+#[allow(dead_code)]
+struct LatchErrorShape {
+    kind: String,
+    message: String,
+    retryable: bool,
+}
+
+/// The lower layers still report errors as strings; this maps their well-known
+/// messages onto kinds at the command boundary.
+impl From<String> for LatchError {
+    fn from(message: String) -> Self {
+        const IO_PREFIXES: &[&str] = &[
+            "Failed to read",
+            "Failed to write",
+            "Failed to rename",
+            "Failed to create",
+            "Failed to open",
+            "Failed to remove",
+        ];
+
+        if message == "Vault is locked" {
+            LatchError::VaultLocked
+        } else if message == "Session expired"
+            || message == "Invalid session"
+            || message == "Guest session expired"
+        {
+            LatchError::SessionExpired
+        } else if message.starts_with("Too many failed attempts") {
+            LatchError::LockedOut(message)
+        } else if message.starts_with("Unlock again to reveal")
+            || message.starts_with("Confirm it's you")
+        {
+            LatchError::ReauthRequired(message)
+        } else if message == crate::vault::guest::GUEST_DENIED {
+            LatchError::Forbidden(message)
+        } else if message.starts_with("Decryption failed")
+            || message.contains("could not be decrypted")
+            || message == "Failed to unlock vault"
+        {
+            LatchError::DecryptionFailed(message)
+        } else if message.contains("temporarily unavailable")
+            // Retried once the watcher has reloaded the file.
+            || message == crate::vault::external::CHANGED_ON_DISK
+        {
+            LatchError::Unavailable(message)
+        } else if message.to_lowercase().contains("not found")
+            || message.ends_with("does not exist")
+        {
+            LatchError::NotFound(message)
+        } else if IO_PREFIXES.iter().any(|p| message.starts_with(p)) {
+            LatchError::Io(message)
+        } else if message.starts_with("Invalid ")
+            || message.starts_with("Unknown ")
+            || message.contains("cannot be unlocked with")
+        {
+            LatchError::InvalidInput(message)
+        } else {
+            LatchError::Internal(message)
+        }
+    }
+}
+
+impl From<&str> for LatchError {
+    fn from(message: &str) -> Self {
+        LatchError::from(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_message_and_retryable() {
+        let value = serde_json::to_value(LatchError::VaultLocked).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "kind": "VaultLocked",
+                "message": "Vault is locked",
+                "retryable": false
+            })
+        );
+    }
+
+    #[test]
+    fn classifies_lower_layer_messages() {
+        assert_eq!(
+            LatchError::from("Vault is locked".to_string()),
+            LatchError::VaultLocked
+        );
+        assert_eq!(LatchError::from("Session expired").kind(), "SessionExpired");
+        assert_eq!(
+            LatchError::from("Decryption failed: aead::Error").kind(),
+            "DecryptionFailed"
+        );
+        assert_eq!(
+            LatchError::from("Credential 'x' not found").kind(),
+            "NotFound"
+        );
+        assert_eq!(
+            LatchError::from("Unlock again to reveal this credential, or wait 5 seconds").kind(),
+            "ReauthRequired"
+        );
+        assert_eq!(
+            LatchError::from("Confirm it's you to export").kind(),
+            "ReauthRequired"
+        );
+        assert_eq!(
+            LatchError::from(crate::vault::guest::GUEST_DENIED).kind(),
+            "Forbidden"
+        );
+        assert!(LatchError::from("Failed to write vault: denied").retryable());
+        assert_eq!(LatchError::from("Something odd").kind(), "Internal");
+    }
+}
@@ -0,0 +1,45 @@
+//! Watches the unlocked Vault's file for changes made outside Latch, like a sync
+//! tool dropping in another machine's copy, and tells the frontend what was done
+//! about them (see `vault::external`).
+
+use crate::vault::external::{self, ExternalChange};
+use crate::vault::{storage::VaultStorage, workspace::Workspace};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub const CHANGED_EVENT: &str = "vault://changed-externally";
+
+/// Checks the file every `WATCH_INTERVAL_SECS` until the Session ends. A file that
+/// can't be opened is reported once, until it changes into one that can.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    state_arc: Arc<Mutex<(VaultStorage, Workspace)>>,
+    session_start: SystemTime,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut reported_unreadable = false;
+        loop {
+            tokio::time::sleep(Duration::from_secs(external::WATCH_INTERVAL_SECS)).await;
+            let change = {
+                let Ok(mut guard) = state_arc.lock() else {
+                    return;
+                };
+                let (storage, workspace) = &mut *guard;
+                if workspace.session_start != Some(session_start) {
+                    return;
+                }
+                external::check(storage, workspace)
+            };
+            match change {
+                Ok(Some(ExternalChange::Unreadable)) if reported_unreadable => {}
+                Ok(Some(change)) => {
+                    reported_unreadable = change == ExternalChange::Unreadable;
+                    let _ = app.emit(CHANGED_EVENT, change);
+                }
+                Ok(None) => reported_unreadable = false,
+                Err(e) => eprintln!("Failed to reload the changed vault: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,386 @@
+//! `git credential` helper mode. Git runs `latch git-credential <get|store|erase>`;
+//! that process hands the request to the running app over a local socket and
+//! prints the answer, so it works however the Vault was unlocked.
+//!
+//! Set it up with `git config --global credential.helper "/path/to/latch git-credential"`.
+
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::backup::snapshot_after_save;
+use crate::commands::{SettingsState, VaultState};
+use crate::vault::Entry;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::oneshot;
+
+/// The first argument that switches the binary into helper mode.
+pub const CLI_COMMAND: &str = "git-credential";
+/// Git never sends more than a few short lines.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// What git sends and expects back: `key=value` lines ending with a blank line.
+/// Only the attributes Latch uses are kept.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitCredential {
+    pub protocol: Option<String>,
+    pub host: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl GitCredential {
+    pub fn parse(input: &str) -> Self {
+        let mut credential = GitCredential::default();
+        for line in input.lines() {
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = Some(value.to_string());
+            match key {
+                "protocol" => credential.protocol = value,
+                "host" => credential.host = value,
+                "username" => credential.username = value,
+                "password" => credential.password = value,
+                _ => {}
+            }
+        }
+        credential
+    }
+
+    /// The reply to `get`: only the username and password.
+    pub fn to_reply(&self) -> String {
+        let mut out = String::new();
+        if let Some(username) = &self.username {
+            out.push_str(&format!("username={}\n", username));
+        }
+        if let Some(password) = &self.password {
+            out.push_str(&format!("password={}\n", password));
+        }
+        out
+    }
+
+    /// Only https (and plain http) remotes are answered; git handles ssh itself.
+    fn web_host(&self) -> Option<String> {
+        matches!(self.protocol.as_deref(), Some("https" | "http"))
+            .then(|| self.host.as_deref().map(str::to_lowercase))
+            .flatten()
+            .filter(|host| !host.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Get,
+    Store,
+    Erase,
+}
+
+impl Operation {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "get" => Some(Operation::Get),
+            "store" => Some(Operation::Store),
+            "erase" => Some(Operation::Erase),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    operation: Operation,
+    credential: GitCredential,
+}
+
+/// `host[:port]` of a saved URL, the way git names hosts.
+fn entry_host(entry: &Entry) -> Option<String> {
+    let url = url::Url::parse(entry.url.as_deref()?).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_lowercase();
+    Some(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host,
+    })
+}
+
+/// The login saved for git's host, and for its username when git already has one.
+fn find<'a>(entries: &'a [Entry], credential: &GitCredential) -> Option<&'a Entry> {
+    let host = credential.web_host()?;
+    entries.iter().find(|entry| {
+        entry.is_login()
+            && entry_host(entry).as_deref() == Some(host.as_str())
+            && credential
+                .username
+                .as_deref()
+                .map_or(true, |username| entry.username == username)
+    })
+}
+
+fn get(app: &AppHandle, credential: &GitCredential) -> Result<Option<GitCredential>, String> {
+    let entry = app.state::<VaultState>().lock(|_, workspace| {
+        workspace.check_session()?;
+        workspace.refresh();
+        let Some(entry) = find(&workspace.credentials, credential).cloned() else {
+            return Ok(None);
+        };
+        workspace.reveal(&entry)?;
+        Ok(Some(entry))
+    })?;
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+    app.state::<AuditLog>().record(
+        AuditKind::Reveal,
+        Some(format!("password of {} to git", entry.id)),
+    );
+    Ok(Some(GitCredential {
+        username: Some(entry.username),
+        password: Some(entry.password),
+        ..GitCredential::default()
+    }))
+}
+
+/// Saves credentials git has just used successfully: a new login for an unknown
+/// host or username, or the new password for a known one.
+fn store(app: &AppHandle, credential: &GitCredential) -> Result<(), String> {
+    let (Some(host), Some(username), Some(password)) = (
+        credential.web_host(),
+        credential.username.clone(),
+        credential.password.clone(),
+    ) else {
+        return Ok(());
+    };
+    let settings = app.state::<SettingsState>().get();
+    app.state::<VaultState>().lock(|storage, workspace| {
+        workspace.check_session()?;
+        match find(&workspace.credentials, credential).cloned() {
+            Some(entry) if entry.password == password => return Ok(()),
+            Some(entry) => {
+                crate::vault::entries::update(workspace, storage, Entry { password, ..entry })?
+            }
+            None => crate::vault::entries::add(
+                workspace,
+                storage,
+                Entry {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    title: host.clone(),
+                    username,
+                    password,
+                    url: Some(format!(
+                        "{}://{}",
+                        credential.protocol.as_deref().unwrap_or("https"),
+                        host
+                    )),
+                    ..Default::default()
+                },
+            )?,
+        }
+        snapshot_after_save(storage, workspace, &settings);
+        Ok(())
+    })
+}
+
+fn answer(app: &AppHandle, request: Request) -> Option<GitCredential> {
+    let result = match request.operation {
+        Operation::Get => get(app, &request.credential),
+        Operation::Store => store(app, &request.credential).map(|_| None),
+        // Git erases after a rejected password. The Credential may still be right
+        // elsewhere, so deleting it is left to the user.
+        Operation::Erase => Ok(None),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("git credential {:?} failed: {}", request.operation, e);
+        None
+    })
+}
+
+async fn serve<S>(stream: S, app: AppHandle)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if (&mut reader)
+        .take(MAX_REQUEST_BYTES)
+        .read_line(&mut line)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let Ok(request) = serde_json::from_str::<Request>(&line) else {
+        return;
+    };
+    let reply = tauri::async_runtime::spawn_blocking(move || answer(&app, request))
+        .await
+        .ok()
+        .flatten();
+    let Ok(mut bytes) = serde_json::to_vec(&reply) else {
+        return;
+    };
+    bytes.push(b'\n');
+    let _ = reader.get_mut().write_all(&bytes).await;
+}
+
+#[cfg(unix)]
+fn socket_path() -> std::path::PathBuf {
+    crate::local_socket::runtime_path(&["latch", "git-credential.sock"])
+}
+
+#[cfg(windows)]
+fn socket_path() -> String {
+    crate::local_socket::pipe_name("latch-git-credential")
+}
+
+/// The listener, while the helper setting is on.
+#[derive(Default)]
+pub struct GitCredentialState(Mutex<Option<oneshot::Sender<()>>>);
+
+/// Starts or stops answering the helper to match the setting.
+pub fn sync(app: &AppHandle, enabled: bool) {
+    let state = app.state::<GitCredentialState>();
+    let mut stop = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    if enabled && stop.is_none() {
+        let handle = app.clone();
+        match crate::local_socket::listen(socket_path(), move |stream| {
+            serve(stream, handle.clone())
+        }) {
+            Ok(handle) => *stop = Some(handle),
+            Err(e) => eprintln!("Failed to start git credential helper: {}", e),
+        }
+    } else if !enabled {
+        if let Some(handle) = stop.take() {
+            let _ = handle.send(());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect() -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path())
+}
+
+#[cfg(windows)]
+fn connect() -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(socket_path())
+}
+
+fn ask_app(request: &Request) -> Result<Option<GitCredential>, String> {
+    let mut stream = connect().map_err(|_| {
+        "Latch is not running, or its git credential helper is turned off".to_string()
+    })?;
+    let mut bytes = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+    bytes.push(b'\n');
+    stream.write_all(&bytes).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    stream
+        .take(MAX_REQUEST_BYTES)
+        .read_to_string(&mut reply)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(reply.trim()).map_err(|e| e.to_string())
+}
+
+/// Runs one helper call for git and returns the exit code. Failing quietly lets git
+/// fall back to its other helpers or a prompt.
+pub fn run(operation: &str) -> i32 {
+    // Git may add operations; helpers are expected to ignore ones they don't know.
+    let Some(operation) = Operation::parse(operation) else {
+        return 0;
+    };
+    let mut input = String::new();
+    if std::io::stdin()
+        .take(MAX_REQUEST_BYTES)
+        .read_to_string(&mut input)
+        .is_err()
+    {
+        return 1;
+    }
+    let request = Request {
+        operation,
+        credential: GitCredential::parse(&input),
+    };
+    match ask_app(&request) {
+        Ok(Some(credential)) => {
+            print!("{}", credential.to_reply());
+            0
+        }
+        Ok(None) => 0,
+        Err(e) => {
+            eprintln!("latch: {}", e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(username: &str, url: &str) -> Entry {
+        Entry {
+            id: username.to_string(),
+            title: url.to_string(),
+            username: username.to_string(),
+            password: "token".to_string(),
+            url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_git_attributes_up_to_the_blank_line() {
+        let credential = GitCredential::parse(
+            "protocol=https\nhost=github.com\npath=org/repo.git\nusername=octo\n\npassword=late\n",
+        );
+        assert_eq!(credential.protocol.as_deref(), Some("https"));
+        assert_eq!(credential.host.as_deref(), Some("github.com"));
+        assert_eq!(credential.username.as_deref(), Some("octo"));
+        assert_eq!(credential.password, None);
+    }
+
+    #[test]
+    fn matches_logins_by_host_port_and_username() {
+        let entries = vec![
+            entry("octo", "https://github.com/login"),
+            entry("ci", "https://GitHub.com"),
+            entry("me", "https://git.example.com:8443"),
+        ];
+        let request =
+            |input: &str| find(&entries, &GitCredential::parse(input)).map(|e| e.id.as_str());
+
+        assert_eq!(request("protocol=https\nhost=github.com\n"), Some("octo"));
+        assert_eq!(
+            request("protocol=https\nhost=github.com\nusername=ci\n"),
+            Some("ci")
+        );
+        assert_eq!(
+            request("protocol=https\nhost=git.example.com:8443\n"),
+            Some("me")
+        );
+        assert_eq!(request("protocol=https\nhost=git.example.com\n"), None);
+        assert_eq!(request("protocol=ssh\nhost=github.com\n"), None);
+    }
+
+    #[test]
+    fn reply_carries_only_the_username_and_password() {
+        let credential = GitCredential {
+            protocol: Some("https".to_string()),
+            host: Some("github.com".to_string()),
+            username: Some("octo".to_string()),
+            password: Some("token".to_string()),
+        };
+        assert_eq!(credential.to_reply(), "username=octo\npassword=token\n");
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+pub const MAX_HOOKS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    BackupCompleted,
+    ExportCompleted,
+    /// A watched Credential turned up in breach data; the argument is its id.
+    BreachDetected,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::BackupCompleted => "backup-completed",
+            HookEvent::ExportCompleted => "export-completed",
+            HookEvent::BreachDetected => "breach-detected",
+        }
+    }
+}
+
+/// A user-registered local script run after maintenance events, e.g. to copy a
+/// backup off-site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct MaintenanceHook {
+    pub path: String,
+    pub events: Vec<HookEvent>,
+}
+
+impl MaintenanceHook {
+    pub fn validate(&self) -> Result<(), String> {
+        if !Path::new(&self.path).is_absolute() {
+            return Err(format!("Hook script path must be absolute: {}", self.path));
+        }
+        if self.events.is_empty() {
+            return Err(format!("Hook {} has no events", self.path));
+        }
+        Ok(())
+    }
+}
+
+/// Starts one hook. The script gets only the event name and the artifact path (or
+/// Credential id) as arguments and a minimal environment, so nothing secret can
+/// reach it.
+fn spawn(
+    hook: &MaintenanceHook,
+    event: HookEvent,
+    artifact: impl AsRef<OsStr>,
+) -> Result<Child, String> {
+    let mut command = Command::new(&hook.path);
+    command
+        .arg(event.as_str())
+        .arg(artifact)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(path) = std::env::var_os("PATH") {
+        command.env("PATH", path);
+    }
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to run hook {}: {}", hook.path, e))
+}
+
+/// Runs every hook registered for `event` in the background. Hook failures are
+/// logged and never affect the operation that triggered them.
+pub fn run(hooks: &[MaintenanceHook], event: HookEvent, artifact: impl AsRef<OsStr>) {
+    for hook in hooks.iter().filter(|h| h.events.contains(&event)) {
+        match spawn(hook, event, &artifact) {
+            Ok(mut child) => {
+                let path = hook.path.clone();
+                std::thread::spawn(move || match child.wait() {
+                    Ok(status) if !status.success() => {
+                        eprintln!("Hook {} exited with {}", path, status);
+                    }
+                    Err(e) => eprintln!("Failed to wait for hook {}: {}", path, e),
+                    _ => {}
+                });
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths_are_rejected() {
+        let hook = MaintenanceHook {
+            path: "copy.sh".to_string(),
+            events: vec![HookEvent::BackupCompleted],
+        };
+        assert!(hook.validate().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_receives_event_and_artifact_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let script = dir.path().join("hook.sh");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$1 $2 ${{LATCH_OAUTH_SECRET:-unset}}\" > {}\n",
+                out.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hook = MaintenanceHook {
+            path: script.display().to_string(),
+            events: vec![HookEvent::ExportCompleted],
+        };
+        let artifact = dir.path().join("audit.json");
+        let status = spawn(&hook, HookEvent::ExportCompleted, &artifact)
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        assert!(status.success());
+        let written = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(
+            written.trim(),
+            format!("export-completed {} unset", artifact.display())
+        );
+    }
+}
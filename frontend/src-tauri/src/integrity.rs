@@ -0,0 +1,125 @@
+//! Ties stored-key unlock to the app binary that enrolled the key. If the binary
+//! changes (an update, or tampering), the keychain key is refused until it is
+//! re-enrolled, which rotates the Vault onto a fresh key.
+
+use crate::auth::method::AuthMethod;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const RECORD_FILE: &str = "enrolled-binary";
+
+pub struct BinaryIntegrity {
+    pub path: PathBuf,
+    /// The last answer of `check`; None until the first, which startup runs in
+    /// the background.
+    changed: Mutex<Option<bool>>,
+}
+
+/// Whether Vaults with this `kdf` tag unlock with a key kept outside the Vault.
+pub fn uses_stored_key(kdf: &str) -> bool {
+    matches!(
+        AuthMethod::from_vault_tag(kdf),
+        Some(AuthMethod::Biometric | AuthMethod::HardwareKey)
+    )
+}
+
+fn current_hash() -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate app: {}", e))?;
+    let mut file = fs::File::open(&exe).map_err(|e| format!("Failed to read app: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read app: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+impl BinaryIntegrity {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(
+            crate::vault::storage::config_dir()?.join(RECORD_FILE),
+        ))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            changed: Mutex::new(None),
+        }
+    }
+
+    fn compare(&self) -> bool {
+        match fs::read_to_string(&self.path) {
+            Ok(recorded) => current_hash().map_or(true, |hash| hash != recorded.trim()),
+            Err(_) => false,
+        }
+    }
+
+    /// Compares the running binary with the one that enrolled the key and
+    /// remembers the answer. Nothing enrolled yet counts as unchanged.
+    pub fn check(&self) -> bool {
+        let mut changed = self.changed.lock().unwrap_or_else(|e| e.into_inner());
+        *changed.insert(self.compare())
+    }
+
+    /// The last answer of `check`, running it first if startup hasn't yet.
+    pub fn changed(&self) -> bool {
+        let mut changed = self.changed.lock().unwrap_or_else(|e| e.into_inner());
+        *changed.get_or_insert_with(|| self.compare())
+    }
+
+    pub fn is_enrolled(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Records the running binary as the one the stored key belongs to.
+    pub fn enroll(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        fs::write(&self.path, current_hash()?)
+            .map_err(|e| format!("Failed to write binary record: {}", e))?;
+        *self.changed.lock().unwrap_or_else(|e| e.into_inner()) = Some(false);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_a_binary_other_than_the_enrolled_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let integrity = BinaryIntegrity::at(dir.path().join(RECORD_FILE));
+        assert!(!integrity.check());
+
+        integrity.enroll().unwrap();
+        assert!(!integrity.check());
+
+        fs::write(&integrity.path, "0".repeat(64)).unwrap();
+        assert!(integrity.check());
+        assert!(integrity.changed());
+
+        integrity.enroll().unwrap();
+        assert!(!integrity.changed());
+    }
+
+    #[test]
+    fn only_keychain_and_hardware_keys_are_tied_to_the_binary() {
+        assert!(uses_stored_key("biometric-keychain"));
+        assert!(uses_stored_key("hardware-key"));
+        assert!(!uses_stored_key("password-pbkdf2"));
+        assert!(!uses_stored_key("oauth-argon2id"));
+    }
+}
@@ -0,0 +1,962 @@
+//! Opt-in sync with other Latch instances on the local network, with no cloud
+//! service in between. Instances find each other over mDNS and talk over a Noise XX
+//! channel. To pair, both screens show a six-digit code taken from the handshake;
+//! it only matches when nobody sits in the middle, and once both users confirm it
+//! each side pins the other's key. Later exchanges carry only what changed since
+//! the last one (see `vault::sync::delta`).
+//!
+//! The static key is derived from the Session key and a per-install salt, so it is
+//! never written to disk and stops working, like the peers' bases, when the Vault
+//! is re-keyed; pair again after a rotation.
+
+use crate::commands::{SettingsState, VaultState};
+use crate::vault::sync::delta::{self, Delta};
+use crate::vault::sync::three_way::SyncConflict;
+use crate::vault::sync::{SyncOutcome, SyncReport};
+use crate::vault::{storage::VaultStorage, workspace::Workspace, Entry};
+use hkdf::Hkdf;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snow::{Builder, HandshakeState, TransportState};
+use specta::Type;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use zeroize::Zeroizing;
+
+pub const PAIRING_EVENT: &str = "lan-sync://pairing-requested";
+/// How long this device accepts pairing requests after the user allows them.
+pub const PAIRING_WINDOW_SECS: u64 = 120;
+
+const SERVICE_TYPE: &str = "_latch-sync._tcp.local.";
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+const PROLOGUE: &[u8] = b"latch-lan-sync-v1";
+const IDENTITY_INFO: &[u8] = b"latch-lan-sync-identity";
+const MAX_NOISE_MESSAGE: usize = 65_535;
+const CHUNK_BYTES: usize = MAX_NOISE_MESSAGE - 16;
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+const DISCOVERY_TIME: Duration = Duration::from_secs(3);
+const EXCHANGE_TIMEOUT: Duration = Duration::from_secs(60);
+const SESSION_POLL: Duration = Duration::from_secs(5);
+
+/// A device this one has paired with, identified by its Noise static key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct PairedPeer {
+    pub fingerprint: String,
+    pub name: String,
+    /// Hex of the X25519 static key.
+    pub public_key: String,
+    pub paired_at: u64,
+}
+
+/// A Latch instance seen on the network.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct LanPeer {
+    pub name: String,
+    pub fingerprint: String,
+    /// `ip:port` to pair or sync with.
+    pub address: String,
+    pub paired: bool,
+}
+
+/// A pairing waiting for the user to confirm that both devices show `code`.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct PairingRequest {
+    pub fingerprint: String,
+    pub name: String,
+    pub code: String,
+}
+
+/// Sent by the initiator with the last handshake message, once it is authenticated.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Hello {
+    Pair { name: String },
+    Sync { name: String },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    /// The responder recorded the pairing and is waiting for its user.
+    Pairing,
+    Delta {
+        delta: Delta,
+    },
+    Refused {
+        reason: String,
+    },
+}
+
+struct Identity {
+    private: Zeroizing<[u8; 32]>,
+    public: [u8; 32],
+}
+
+struct Pending {
+    peer: PairedPeer,
+    expires: Instant,
+}
+
+pub struct LanSyncState {
+    dir: PathBuf,
+    pending: Mutex<HashMap<String, Pending>>,
+    pairing_until: Mutex<Option<Instant>>,
+    /// Set while this device is announced and listening.
+    serving: AtomicBool,
+}
+
+pub fn fingerprint(public_key: &[u8]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..16])
+}
+
+/// The code both users compare; equal on both sides only if they share one
+/// handshake.
+fn short_code(handshake_hash: &[u8]) -> String {
+    let mut prefix = [0u8; 4];
+    prefix.copy_from_slice(&handshake_hash[..4]);
+    format!("{:06}", u32::from_be_bytes(prefix) % 1_000_000)
+}
+
+pub fn device_name() -> String {
+    ["COMPUTERNAME", "HOSTNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "Latch".to_string())
+}
+
+fn noise_error(e: snow::Error) -> String {
+    format!("LAN sync channel failed: {}", e)
+}
+
+impl LanSyncState {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(
+            crate::vault::storage::config_dir()?.join("lan-sync"),
+        ))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            pending: Mutex::new(HashMap::new()),
+            pairing_until: Mutex::new(None),
+            serving: AtomicBool::new(false),
+        }
+    }
+
+    fn peers_path(&self) -> PathBuf {
+        self.dir.join("peers.json")
+    }
+
+    fn base_path(&self, fingerprint: &str) -> PathBuf {
+        self.dir.join(format!("base-{}.enc", fingerprint))
+    }
+
+    fn ensure_dir(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create LAN sync directory: {}", e))
+    }
+
+    /// Random per install, so two devices with the same Vault key still get
+    /// different identities.
+    fn install_salt(&self) -> Result<Vec<u8>, String> {
+        let path = self.dir.join("install-salt");
+        if let Some(salt) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|hex_salt| hex::decode(hex_salt.trim()).ok())
+        {
+            return Ok(salt);
+        }
+        self.ensure_dir()?;
+        let mut salt = vec![0u8; 16];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        fs::write(&path, hex::encode(&salt))
+            .map_err(|e| format!("Failed to write LAN sync salt: {}", e))?;
+        Ok(salt)
+    }
+
+    fn identity(&self, session_key: &[u8; 32]) -> Result<Identity, String> {
+        let salt = self.install_salt()?;
+        let mut private = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(Some(&salt), session_key)
+            .expand(IDENTITY_INFO, private.as_mut())
+            .map_err(|_| "Failed to derive LAN sync key".to_string())?;
+        let secret = x25519_dalek::StaticSecret::from(*private);
+        let public = x25519_dalek::PublicKey::from(&secret).to_bytes();
+        Ok(Identity { private, public })
+    }
+
+    pub fn peers(&self) -> Vec<PairedPeer> {
+        fs::read_to_string(self.peers_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_peers(&self, peers: &[PairedPeer]) -> Result<(), String> {
+        self.ensure_dir()?;
+        let json = serde_json::to_string_pretty(peers)
+            .map_err(|e| format!("Failed to serialize LAN peers: {}", e))?;
+        fs::write(self.peers_path(), json).map_err(|e| format!("Failed to write LAN peers: {}", e))
+    }
+
+    /// The paired peer whose static key is `public_key`, if any.
+    fn paired(&self, public_key: &[u8]) -> Option<PairedPeer> {
+        let public_key = hex::encode(public_key);
+        self.peers()
+            .into_iter()
+            .find(|peer| peer.public_key == public_key)
+    }
+
+    /// Accepts pairing requests for the next `PAIRING_WINDOW_SECS`.
+    pub fn open_pairing(&self) -> u64 {
+        if let Ok(mut until) = self.pairing_until.lock() {
+            *until = Some(Instant::now() + Duration::from_secs(PAIRING_WINDOW_SECS));
+        }
+        PAIRING_WINDOW_SECS
+    }
+
+    fn pairing_open(&self) -> bool {
+        self.pairing_until
+            .lock()
+            .is_ok_and(|until| until.is_some_and(|until| Instant::now() < until))
+    }
+
+    fn add_pending(&self, peer: PairedPeer) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.retain(|_, p| Instant::now() < p.expires);
+            pending.insert(
+                peer.fingerprint.clone(),
+                Pending {
+                    peer,
+                    expires: Instant::now() + Duration::from_secs(PAIRING_WINDOW_SECS),
+                },
+            );
+        }
+    }
+
+    /// Pins a pending peer once the user has seen the same code on both devices.
+    pub fn confirm(&self, fingerprint: &str) -> Result<PairedPeer, String> {
+        let pending = self
+            .pending
+            .lock()
+            .map_err(|_| "LAN sync is temporarily unavailable")?
+            .remove(fingerprint)
+            .filter(|p| Instant::now() < p.expires)
+            .ok_or("Pairing request not found; it may have expired")?;
+        let mut peer = pending.peer;
+        peer.paired_at = crate::vault::unix_now();
+
+        let mut peers = self.peers();
+        peers.retain(|p| p.fingerprint != peer.fingerprint);
+        peers.push(peer.clone());
+        self.save_peers(&peers)?;
+        if let Ok(mut until) = self.pairing_until.lock() {
+            *until = None;
+        }
+        Ok(peer)
+    }
+
+    pub fn reject(&self, fingerprint: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(fingerprint);
+        }
+    }
+
+    pub fn forget(&self, fingerprint: &str) -> Result<(), String> {
+        let mut peers = self.peers();
+        let before = peers.len();
+        peers.retain(|p| p.fingerprint != fingerprint);
+        if peers.len() == before {
+            return Err("Paired device not found".to_string());
+        }
+        self.save_peers(&peers)?;
+        let base = self.base_path(fingerprint);
+        if base.exists() {
+            fs::remove_file(base).map_err(|e| format!("Failed to remove sync base: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn base(&self, fingerprint: &str, session_key: &[u8; 32]) -> Vec<Entry> {
+        fs::read_to_string(self.base_path(fingerprint))
+            .ok()
+            .and_then(|sealed| delta::open(session_key, &sealed))
+            .unwrap_or_default()
+    }
+
+    fn save_base(
+        &self,
+        fingerprint: &str,
+        session_key: &[u8; 32],
+        entries: &[Entry],
+    ) -> Result<(), String> {
+        self.ensure_dir()?;
+        fs::write(
+            self.base_path(fingerprint),
+            delta::seal(session_key, entries)?,
+        )
+        .map_err(|e| format!("Failed to write sync base: {}", e))
+    }
+}
+
+type SharedVault = Mutex<(VaultStorage, Workspace)>;
+
+/// The Session key, provided the Vault is unlocked in a Session that may sync.
+fn session_key(state_arc: &SharedVault) -> Result<[u8; 32], String> {
+    let mut guard = state_arc
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")?;
+    let workspace = &mut guard.1;
+    workspace.check_session()?;
+    if workspace.decoy || workspace.guest.is_some() {
+        return Err("Sync is unavailable in this Session".to_string());
+    }
+    workspace
+        .session_key
+        .as_deref()
+        .copied()
+        .ok_or_else(|| "Vault is locked".to_string())
+}
+
+async fn write_frame<S: AsyncWrite + Unpin>(stream: &mut S, bytes: &[u8]) -> Result<(), String> {
+    let io_error = |e: std::io::Error| format!("LAN sync connection failed: {}", e);
+    stream
+        .write_u16(bytes.len() as u16)
+        .await
+        .map_err(io_error)?;
+    stream.write_all(bytes).await.map_err(io_error)?;
+    stream.flush().await.map_err(io_error)
+}
+
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, String> {
+    let io_error = |e: std::io::Error| format!("LAN sync connection failed: {}", e);
+    let len = stream.read_u16().await.map_err(io_error)?;
+    let mut frame = vec![0u8; len as usize];
+    stream.read_exact(&mut frame).await.map_err(io_error)?;
+    Ok(frame)
+}
+
+/// An authenticated Noise channel. A message goes as a chunk count and then that
+/// many chunks, each its own Noise message.
+struct Channel<S> {
+    stream: S,
+    transport: TransportState,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Channel<S> {
+    async fn send_frame(&mut self, plaintext: &[u8]) -> Result<(), String> {
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self
+            .transport
+            .write_message(plaintext, &mut buf)
+            .map_err(noise_error)?;
+        write_frame(&mut self.stream, &buf[..len]).await
+    }
+
+    async fn recv_frame(&mut self) -> Result<Vec<u8>, String> {
+        let frame = read_frame(&mut self.stream).await?;
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self
+            .transport
+            .read_message(&frame, &mut buf)
+            .map_err(noise_error)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<(), String> {
+        let bytes = serde_json::to_vec(message)
+            .map_err(|e| format!("Failed to serialize LAN sync message: {}", e))?;
+        if bytes.len() > MAX_MESSAGE_BYTES {
+            return Err("Vault is too large to sync over the LAN".to_string());
+        }
+        let chunks: Vec<&[u8]> = bytes.chunks(CHUNK_BYTES).collect();
+        self.send_frame(&(chunks.len() as u32).to_be_bytes())
+            .await?;
+        for chunk in chunks {
+            self.send_frame(chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<Message, String> {
+        let count: [u8; 4] = self
+            .recv_frame()
+            .await?
+            .try_into()
+            .map_err(|_| "Invalid LAN sync message".to_string())?;
+        let count = u32::from_be_bytes(count) as usize;
+        if count.saturating_mul(CHUNK_BYTES) > MAX_MESSAGE_BYTES + CHUNK_BYTES {
+            return Err("Invalid LAN sync message: too large".to_string());
+        }
+        let mut bytes = Vec::new();
+        for _ in 0..count {
+            bytes.extend(self.recv_frame().await?);
+        }
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid LAN sync message".to_string())
+    }
+
+    async fn refuse(mut self, reason: &str) -> String {
+        let _ = self
+            .send(&Message::Refused {
+                reason: reason.to_string(),
+            })
+            .await;
+        reason.to_string()
+    }
+}
+
+fn builder(identity: &Identity) -> Result<Builder<'_>, String> {
+    let params = NOISE_PARAMS.parse().map_err(noise_error)?;
+    Ok(Builder::new(params)
+        .local_private_key(identity.private.as_ref())
+        .prologue(PROLOGUE))
+}
+
+/// The other side's static key, the short code and the channel, once a handshake
+/// completes.
+struct Handshake<S> {
+    channel: Channel<S>,
+    remote: Vec<u8>,
+    code: String,
+}
+
+fn finish_handshake<S>(stream: S, noise: HandshakeState) -> Result<Handshake<S>, String> {
+    let remote = noise
+        .get_remote_static()
+        .ok_or("LAN sync peer sent no key")?
+        .to_vec();
+    let code = short_code(noise.get_handshake_hash());
+    let transport = noise.into_transport_mode().map_err(noise_error)?;
+    Ok(Handshake {
+        channel: Channel { stream, transport },
+        remote,
+        code,
+    })
+}
+
+/// `-> e`, `<- e, ee, s, es` with the responder's name, `-> s, se` with `hello`.
+/// Returns the responder's name as well.
+async fn initiate<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    identity: &Identity,
+    hello: &Hello,
+) -> Result<(Handshake<S>, String), String> {
+    let mut noise = builder(identity)?.build_initiator().map_err(noise_error)?;
+    let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+
+    let len = noise.write_message(&[], &mut buf).map_err(noise_error)?;
+    write_frame(&mut stream, &buf[..len]).await?;
+
+    let frame = read_frame(&mut stream).await?;
+    let len = noise.read_message(&frame, &mut buf).map_err(noise_error)?;
+    let peer_name = String::from_utf8_lossy(&buf[..len]).into_owned();
+
+    let hello = serde_json::to_vec(hello)
+        .map_err(|e| format!("Failed to serialize LAN sync message: {}", e))?;
+    let len = noise.write_message(&hello, &mut buf).map_err(noise_error)?;
+    write_frame(&mut stream, &buf[..len]).await?;
+
+    Ok((finish_handshake(stream, noise)?, peer_name))
+}
+
+async fn accept<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    identity: &Identity,
+    name: &str,
+) -> Result<(Handshake<S>, Hello), String> {
+    let mut noise = builder(identity)?.build_responder().map_err(noise_error)?;
+    let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+
+    let frame = read_frame(&mut stream).await?;
+    noise.read_message(&frame, &mut buf).map_err(noise_error)?;
+
+    let len = noise
+        .write_message(name.as_bytes(), &mut buf)
+        .map_err(noise_error)?;
+    write_frame(&mut stream, &buf[..len]).await?;
+
+    let frame = read_frame(&mut stream).await?;
+    let len = noise.read_message(&frame, &mut buf).map_err(noise_error)?;
+    let hello =
+        serde_json::from_slice(&buf[..len]).map_err(|_| "Invalid LAN sync message".to_string())?;
+
+    Ok((finish_handshake(stream, noise)?, hello))
+}
+
+/// This side's base and delta for `peer`, read under the Vault lock.
+fn prepare(
+    state_arc: &SharedVault,
+    lan: &LanSyncState,
+    peer: &PairedPeer,
+) -> Result<(Vec<Entry>, Delta), String> {
+    let key = session_key(state_arc)?;
+    let base = lan.base(&peer.fingerprint, &key);
+    let guard = state_arc
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable")?;
+    let delta = delta::diff(&base, &guard.1.credentials);
+    Ok((base, delta))
+}
+
+/// Merges the peer's delta and keeps the result as the next base for it.
+fn merge(
+    state_arc: &SharedVault,
+    lan: &LanSyncState,
+    peer: &PairedPeer,
+    base: &[Entry],
+    theirs: &Delta,
+) -> Result<Vec<SyncConflict>, String> {
+    let key = session_key(state_arc)?;
+    let (merged, conflicts) = {
+        let mut guard = state_arc
+            .lock()
+            .map_err(|_| "Vault is temporarily unavailable")?;
+        let (ref storage, ref mut workspace) = *guard;
+        delta::merge_delta(storage, workspace, base, theirs)?
+    };
+    lan.save_base(&peer.fingerprint, &key, &merged)?;
+    Ok(conflicts)
+}
+
+fn outcome(ours: &Delta, theirs: &Delta) -> SyncOutcome {
+    match (ours.is_empty(), theirs.is_empty()) {
+        (true, true) => SyncOutcome::UpToDate,
+        (false, true) => SyncOutcome::Uploaded,
+        (true, false) => SyncOutcome::Downloaded,
+        (false, false) => SyncOutcome::Merged,
+    }
+}
+
+/// Asks the device on `stream` to pair. Nothing is pinned until the user confirms
+/// the returned code matches the one on the other device.
+pub async fn pair<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    state_arc: &SharedVault,
+    lan: &LanSyncState,
+    name: &str,
+) -> Result<PairingRequest, String> {
+    let identity = lan.identity(&session_key(state_arc)?)?;
+    let hello = Hello::Pair {
+        name: name.to_string(),
+    };
+    let (mut handshake, peer_name) = initiate(stream, &identity, &hello).await?;
+    match handshake.channel.recv().await? {
+        Message::Pairing => {}
+        Message::Refused { reason } => return Err(reason),
+        Message::Delta { .. } => return Err("Invalid LAN sync message".to_string()),
+    }
+
+    let fingerprint = fingerprint(&handshake.remote);
+    lan.add_pending(PairedPeer {
+        fingerprint: fingerprint.clone(),
+        name: peer_name.clone(),
+        public_key: hex::encode(&handshake.remote),
+        paired_at: 0,
+    });
+    Ok(PairingRequest {
+        fingerprint,
+        name: peer_name,
+        code: handshake.code,
+    })
+}
+
+/// Syncs with the paired device `expected` on `stream`: this side's changes go
+/// first, then the peer's come back and both merge.
+pub async fn sync_with<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    state_arc: &SharedVault,
+    lan: &LanSyncState,
+    name: &str,
+    expected: &str,
+) -> Result<SyncReport, String> {
+    let identity = lan.identity(&session_key(state_arc)?)?;
+    let hello = Hello::Sync {
+        name: name.to_string(),
+    };
+    let (mut handshake, _) = initiate(stream, &identity, &hello).await?;
+    let peer = lan
+        .paired(&handshake.remote)
+        .filter(|peer| peer.fingerprint == expected)
+        .ok_or("The device at this address is not the paired one")?;
+
+    let (base, ours) = prepare(state_arc, lan, &peer)?;
+    handshake
+        .channel
+        .send(&Message::Delta {
+            delta: ours.clone(),
+        })
+        .await?;
+    let theirs = match handshake.channel.recv().await? {
+        Message::Delta { delta } => delta,
+        Message::Refused { reason } => {
+            return Err(format!("{} refused to sync: {}", peer.name, reason))
+        }
+        Message::Pairing => return Err("Invalid LAN sync message".to_string()),
+    };
+    let conflicts = merge(state_arc, lan, &peer, &base, &theirs)?;
+
+    Ok(SyncReport {
+        outcome: outcome(&ours, &theirs),
+        conflicts,
+    })
+}
+
+/// Answers one connection from another device. A pairing request is returned so
+/// the user can be asked to compare codes.
+pub async fn respond<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    state_arc: &SharedVault,
+    lan: &LanSyncState,
+    name: &str,
+) -> Result<Option<PairingRequest>, String> {
+    let identity = lan.identity(&session_key(state_arc)?)?;
+    let (mut handshake, hello) = accept(stream, &identity, name).await?;
+
+    match hello {
+        Hello::Pair { name: peer_name } => {
+            if !lan.pairing_open() {
+                return Err(handshake
+                    .channel
+                    .refuse("Pairing is not open on that device")
+                    .await);
+            }
+            let fingerprint = fingerprint(&handshake.remote);
+            lan.add_pending(PairedPeer {
+                fingerprint: fingerprint.clone(),
+                name: peer_name.clone(),
+                public_key: hex::encode(&handshake.remote),
+                paired_at: 0,
+            });
+            handshake.channel.send(&Message::Pairing).await?;
+            Ok(Some(PairingRequest {
+                fingerprint,
+                name: peer_name,
+                code: handshake.code,
+            }))
+        }
+        Hello::Sync { .. } => {
+            let Some(peer) = lan.paired(&handshake.remote) else {
+                return Err(handshake.channel.refuse("This device is not paired").await);
+            };
+            let theirs = match handshake.channel.recv().await? {
+                Message::Delta { delta } => delta,
+                _ => return Err("Invalid LAN sync message".to_string()),
+            };
+            let (base, ours) = match prepare(state_arc, lan, &peer) {
+                Ok(prepared) => prepared,
+                Err(e) => return Err(handshake.channel.refuse(&e).await),
+            };
+            handshake
+                .channel
+                .send(&Message::Delta { delta: ours })
+                .await?;
+            merge(state_arc, lan, &peer, &base, &theirs)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Looks for other Latch instances on the network for a few seconds.
+pub async fn discover(state_arc: &SharedVault, lan: &LanSyncState) -> Result<Vec<LanPeer>, String> {
+    let own = fingerprint(&lan.identity(&session_key(state_arc)?)?.public);
+    let paired = lan.peers();
+    let mdns = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    let events = mdns
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse for devices: {}", e))?;
+
+    let mut found: Vec<LanPeer> = Vec::new();
+    let deadline = tokio::time::sleep(DISCOVERY_TIME);
+    tokio::pin!(deadline);
+    loop {
+        let event = tokio::select! {
+            _ = &mut deadline => break,
+            event = events.recv_async() => event,
+        };
+        let Ok(event) = event else {
+            break;
+        };
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let Some(fingerprint) = info.get_property_val_str("fp").map(str::to_string) else {
+            continue;
+        };
+        let Some(ip) = info
+            .get_addresses()
+            .iter()
+            .min_by_key(|ip| ip.is_ipv6())
+            .copied()
+        else {
+            continue;
+        };
+        if fingerprint == own || found.iter().any(|p| p.fingerprint == fingerprint) {
+            continue;
+        }
+        found.push(LanPeer {
+            name: info
+                .get_property_val_str("name")
+                .unwrap_or("Latch")
+                .to_string(),
+            paired: paired.iter().any(|p| p.fingerprint == fingerprint),
+            address: std::net::SocketAddr::new(ip, info.get_port()).to_string(),
+            fingerprint,
+        });
+    }
+    let _ = mdns.shutdown();
+    Ok(found)
+}
+
+pub async fn connect(address: &str) -> Result<TcpStream, String> {
+    let address: std::net::SocketAddr = address
+        .parse()
+        .map_err(|_| "Invalid device address".to_string())?;
+    tokio::time::timeout(EXCHANGE_TIMEOUT, TcpStream::connect(address))
+        .await
+        .map_err(|_| "The device did not answer".to_string())?
+        .map_err(|e| format!("Failed to reach the device: {}", e))
+}
+
+fn keep_serving<R: Runtime>(
+    app: &AppHandle<R>,
+    state_arc: &SharedVault,
+    session_start: SystemTime,
+) -> bool {
+    app.state::<SettingsState>().get().lan_sync
+        && state_arc
+            .lock()
+            .is_ok_and(|guard| guard.1.session_start == Some(session_start) && !guard.1.decoy)
+}
+
+async fn serve<R: Runtime>(
+    app: &AppHandle<R>,
+    state_arc: &Arc<SharedVault>,
+    session_start: SystemTime,
+) -> Result<(), String> {
+    let lan = app.state::<LanSyncState>();
+    let fingerprint = fingerprint(&lan.identity(&session_key(state_arc)?)?.public);
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .await
+        .map_err(|e| format!("Failed to listen for LAN sync: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to listen for LAN sync: {}", e))?
+        .port();
+
+    let mdns = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    let name = device_name();
+    let properties = [("fp", fingerprint.as_str()), ("name", name.as_str())];
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &fingerprint,
+        &format!("latch-{}.local.", fingerprint),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| format!("Failed to announce LAN sync: {}", e))?
+    .enable_addr_auto();
+    mdns.register(service)
+        .map_err(|e| format!("Failed to announce LAN sync: {}", e))?;
+
+    while keep_serving(app, state_arc, session_start) {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = tokio::time::sleep(SESSION_POLL) => continue,
+        };
+        let Ok((stream, _)) = accepted else {
+            continue;
+        };
+        let (app, state_arc, name) = (app.clone(), state_arc.clone(), name.clone());
+        tauri::async_runtime::spawn(async move {
+            let lan = app.state::<LanSyncState>();
+            let exchange = respond(stream, &state_arc, &lan, &name);
+            match tokio::time::timeout(EXCHANGE_TIMEOUT, exchange).await {
+                Ok(Ok(Some(request))) => {
+                    let _ = app.emit(PAIRING_EVENT, &request);
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => eprintln!("LAN sync with a peer failed: {}", e),
+                Err(_) => eprintln!("LAN sync with a peer timed out"),
+            }
+        });
+    }
+    let _ = mdns.shutdown();
+    Ok(())
+}
+
+/// Announces this device and answers paired peers until the Session ends or LAN
+/// sync is turned off in Settings.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    state_arc: Arc<SharedVault>,
+    session_start: SystemTime,
+) {
+    if !app.state::<SettingsState>().get().lan_sync
+        || app
+            .state::<LanSyncState>()
+            .serving
+            .swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(&app, &state_arc, session_start).await {
+            eprintln!("LAN sync stopped: {}", e);
+        }
+        app.state::<LanSyncState>()
+            .serving
+            .store(false, Ordering::SeqCst);
+    });
+}
+
+/// Starts serving in the current Session once LAN sync is turned on. Turning it
+/// off stops the listener at its next check.
+pub fn sync(app: &AppHandle, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    let state_arc = app.state::<VaultState>().0.clone();
+    let session_start = state_arc
+        .lock()
+        .ok()
+        .and_then(|guard| guard.1.session_start);
+    if let Some(start) = session_start {
+        spawn(app.clone(), state_arc, start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::method::AuthMethod;
+    use crate::vault::provision;
+
+    fn entry(id: &str, password: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            username: "user".to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A device with its own Vault under its own key.
+    fn device(dir: &std::path::Path, name: &str, key: u8) -> (SharedVault, LanSyncState) {
+        let storage = VaultStorage::at(dir.join(format!("{}.enc", name)));
+        let mut workspace = Workspace::new();
+        provision::provision(
+            &storage,
+            &mut workspace,
+            &[key; 32],
+            AuthMethod::Password,
+            "",
+        )
+        .unwrap();
+        (
+            Mutex::new((storage, workspace)),
+            LanSyncState::at(dir.join(name)),
+        )
+    }
+
+    fn add(vault: &SharedVault, entry: Entry) {
+        let mut guard = vault.lock().unwrap();
+        let (ref storage, ref mut workspace) = *guard;
+        workspace.credentials.push(entry);
+        crate::vault::entries::persist(workspace, storage).unwrap();
+    }
+
+    fn passwords(vault: &SharedVault) -> Vec<(String, String)> {
+        let guard = vault.lock().unwrap();
+        let mut passwords: Vec<_> = guard
+            .1
+            .credentials
+            .iter()
+            .map(|e| (e.id.clone(), e.password.clone()))
+            .collect();
+        passwords.sort();
+        passwords
+    }
+
+    #[test]
+    fn short_codes_are_six_digits() {
+        assert_eq!(short_code(&[0, 0, 0, 7]), "000007");
+        assert_eq!(short_code(&[0xff; 32]).len(), 6);
+    }
+
+    #[tokio::test]
+    async fn pairs_and_syncs_two_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let (laptop, laptop_lan) = device(dir.path(), "laptop", 1);
+        let (desktop, desktop_lan) = device(dir.path(), "desktop", 2);
+        add(&laptop, entry("a", "1"));
+        add(&desktop, entry("b", "1"));
+
+        // Pairing is refused until the desktop allows it.
+        let (left, right) = tokio::io::duplex(MAX_NOISE_MESSAGE);
+        let (asked, answered) = tokio::join!(
+            pair(left, &laptop, &laptop_lan, "laptop"),
+            respond(right, &desktop, &desktop_lan, "desktop"),
+        );
+        assert!(asked.is_err() && answered.is_err());
+
+        desktop_lan.open_pairing();
+        let (left, right) = tokio::io::duplex(MAX_NOISE_MESSAGE);
+        let (asked, answered) = tokio::join!(
+            pair(left, &laptop, &laptop_lan, "laptop"),
+            respond(right, &desktop, &desktop_lan, "desktop"),
+        );
+        let asked = asked.unwrap();
+        let answered = answered.unwrap().unwrap();
+        assert_eq!(asked.code, answered.code);
+        assert_eq!(asked.name, "desktop");
+        assert_eq!(answered.name, "laptop");
+
+        // Syncing needs both sides to have confirmed.
+        laptop_lan.confirm(&asked.fingerprint).unwrap();
+        let (left, right) = tokio::io::duplex(MAX_NOISE_MESSAGE);
+        let (synced, _) = tokio::join!(
+            sync_with(left, &laptop, &laptop_lan, "laptop", &asked.fingerprint),
+            respond(right, &desktop, &desktop_lan, "desktop"),
+        );
+        assert!(synced.is_err());
+
+        desktop_lan.confirm(&answered.fingerprint).unwrap();
+        let (left, right) = tokio::io::duplex(MAX_NOISE_MESSAGE);
+        let (synced, responded) = tokio::join!(
+            sync_with(left, &laptop, &laptop_lan, "laptop", &asked.fingerprint),
+            respond(right, &desktop, &desktop_lan, "desktop"),
+        );
+        assert_eq!(synced.unwrap().outcome, SyncOutcome::Merged);
+        assert!(responded.unwrap().is_none());
+        assert_eq!(passwords(&laptop), passwords(&desktop));
+        assert_eq!(passwords(&laptop).len(), 2);
+
+        // The next exchange carries only the edit.
+        {
+            let mut guard = desktop.lock().unwrap();
+            let (ref storage, ref mut workspace) = *guard;
+            workspace.credentials[0].password = "2".to_string();
+            crate::vault::entries::persist(workspace, storage).unwrap();
+        }
+        let (left, right) = tokio::io::duplex(MAX_NOISE_MESSAGE);
+        let (synced, _) = tokio::join!(
+            sync_with(left, &laptop, &laptop_lan, "laptop", &asked.fingerprint),
+            respond(right, &desktop, &desktop_lan, "desktop"),
+        );
+        assert_eq!(synced.unwrap().outcome, SyncOutcome::Downloaded);
+        assert_eq!(passwords(&laptop), passwords(&desktop));
+    }
+}
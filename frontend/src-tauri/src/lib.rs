@@ -1,79 +1,345 @@
-mod auth;
+mod audit;
+mod autostart;
+mod autotype;
+mod breach_database;
+mod breach_watch;
+mod browser;
+mod capabilities;
+mod cli;
+mod clipboard;
 mod commands;
-mod crypto;
-mod password_generator;
-mod vault;
-mod vault_health;
+mod confirmation;
+mod error;
+mod file_watch;
+mod git_credential;
+mod hooks;
+mod integrity;
+mod lan_sync;
+mod local_share;
+mod local_socket;
+mod rate_limit;
+mod reauth;
+mod settings;
+mod sharing;
+mod ssh;
+mod startup;
+mod sync;
+mod tray;
 
-use auth::lockout::AuthAttemptState;
+use auth::lockout::{AuthAttemptState, LockoutStore};
+use latch_core::{aliases, auth, crypto, icons, password_generator, share, vault, vault_health};
+#[cfg(debug_assertions)]
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use std::sync::atomic::AtomicBool;
 use std::sync::Mutex;
 use std::time::SystemTime;
-use tauri::menu::{MenuBuilder, MenuItem};
-use tauri::tray::TrayIconBuilder;
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_global_shortcut::ShortcutState;
+use tauri_plugin_global_shortcut::{Shortcut, ShortcutState};
+use vault::events::{VaultEvent, VaultEvents};
+use vault::idle::{IdleAction, IdleWatch};
 use vault::SESSION_TIMEOUT_SECS;
 
-pub struct AuthState(pub Mutex<AuthAttemptState>);
+/// Forwards Vault events to the webview.
+struct AppEvents(AppHandle);
+
+impl VaultEvents for AppEvents {
+    fn notify(&self, event: VaultEvent) {
+        match event {
+            VaultEvent::Locked => tray::show_lock_state(&self.0, true),
+            VaultEvent::Unlocked => tray::show_lock_state(&self.0, false),
+            _ => {}
+        }
+        if matches!(
+            event,
+            VaultEvent::Locked | VaultEvent::Unlocked | VaultEvent::EntriesChanged { .. }
+        ) {
+            // Sent with the Vault lock held, which the rebuild needs.
+            let app = self.0.clone();
+            tauri::async_runtime::spawn(async move { tray::refresh(&app) });
+        }
+        let _ = self.0.emit(event.name(), event.payload());
+    }
+}
+
+pub struct AuthState {
+    pub attempts: Mutex<AuthAttemptState>,
+    /// The last fresh confirmation for sensitive actions.
+    pub reauth: reauth::ReauthWindow,
+}
 
 impl AuthState {
     fn new() -> Self {
-        Self(Mutex::new(AuthAttemptState::new()))
+        let attempts = match LockoutStore::new() {
+            Ok(store) => AuthAttemptState::persisted(store),
+            Err(e) => {
+                eprintln!("Lockout state will not persist: {}", e);
+                AuthAttemptState::new()
+            }
+        };
+        Self {
+            attempts: Mutex::new(attempts),
+            reauth: Default::default(),
+        }
     }
 }
 
+/// Warns before the Session times out and locks it when it does, following it
+/// as `extend_session` pushes the timeout back.
 pub fn spawn_session_timer(
-    app_handle: AppHandle,
     state_arc: std::sync::Arc<
         std::sync::Mutex<(vault::storage::VaultStorage, vault::workspace::Workspace)>,
     >,
     session_start: SystemTime,
 ) {
     tauri::async_runtime::spawn(async move {
-        tokio::time::sleep(std::time::Duration::from_secs(SESSION_TIMEOUT_SECS)).await;
-        if let Ok(mut guard) = state_arc.lock() {
-            if guard.1.session_start == Some(session_start) {
-                guard.1.lock();
-                let _ = app_handle.emit("vault-locked", ());
-            }
+        let warning = vault::SESSION_WARNING_SECS.min(SESSION_TIMEOUT_SECS);
+        let mut warned_for = None;
+        loop {
+            let remaining = {
+                let Ok(mut guard) = state_arc.lock() else {
+                    return;
+                };
+                if guard.1.session_start != Some(session_start) {
+                    return;
+                }
+                let remaining = guard.1.session_remaining_secs();
+                if remaining == 0 {
+                    guard.1.notify(VaultEvent::SessionExpired);
+                    guard.1.lock();
+                    return;
+                }
+                let expires_at = guard.1.session_expires_at();
+                if remaining <= warning && warned_for != expires_at {
+                    warned_for = expires_at;
+                    guard.1.notify(VaultEvent::SessionEnding {
+                        seconds_remaining: remaining,
+                    });
+                }
+                remaining
+            };
+            let wait = if remaining > warning {
+                remaining - warning
+            } else {
+                remaining
+            };
+            tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
         }
     });
 }
 
-fn setup_system_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    let show_item = MenuItem::with_id(app, "show", "Show Latch", true, None::<&str>)?;
-    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-
-    let menu = MenuBuilder::new(app)
-        .item(&show_item)
-        .separator()
-        .item(&quit_item)
-        .build()?;
-
-    let tray_icon = app
-        .default_window_icon()
-        .ok_or("Failed to get window icon")?
-        .clone();
-
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .menu(&menu)
-        .tooltip("Latch Password Manager")
-        .icon(tray_icon)
-        .on_menu_event(move |app, event| match event.id.0.as_str() {
-            "show" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
+pub fn spawn_idle_watcher<R: tauri::Runtime>(
+    app_handle: AppHandle<R>,
+    state_arc: std::sync::Arc<
+        std::sync::Mutex<(vault::storage::VaultStorage, vault::workspace::Workspace)>,
+    >,
+    session_start: SystemTime,
+) {
+    tauri::async_runtime::spawn(async move {
+        let mut watch = IdleWatch::new();
+        loop {
+            let timeout = app_handle
+                .state::<commands::SettingsState>()
+                .get()
+                .idle_lock_secs;
+            let action = {
+                let Ok(mut guard) = state_arc.lock() else {
+                    return;
+                };
+                if guard.1.session_start != Some(session_start) {
+                    return;
                 }
+                let action = watch.step(guard.1.idle_secs().unwrap_or(0), timeout);
+                match action {
+                    IdleAction::Warn(seconds_remaining) => guard
+                        .1
+                        .notify(VaultEvent::SessionExpiring { seconds_remaining }),
+                    IdleAction::Lock => guard.1.lock(),
+                    IdleAction::Wait(_) => {}
+                }
+                action
+            };
+
+            match action {
+                IdleAction::Wait(secs) => {
+                    tokio::time::sleep(std::time::Duration::from_secs(secs.max(1))).await;
+                }
+                IdleAction::Warn(_) => {}
+                IdleAction::Lock => return,
             }
-            "quit" => {
-                app.exit(0);
-            }
-            _ => {}
-        })
-        .build(app)?;
+        }
+    });
+}
+
+/// Answers `latch-icon://localhost/<domain>` from the encrypted icon cache, only
+/// while the Vault is unlocked.
+fn icon_protocol(
+    app: &AppHandle,
+    request: tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    let domain = icons::requested_domain(request.uri().path());
+    let icon = app
+        .state::<commands::VaultState>()
+        .session_key()
+        .ok()
+        .and_then(|key| app.state::<icons::IconCache>().load(&key, domain));
+    let response = match icon {
+        Some((bytes, mime)) => tauri::http::Response::builder()
+            .header(tauri::http::header::CONTENT_TYPE, mime)
+            .header(tauri::http::header::CACHE_CONTROL, "no-store")
+            .body(bytes),
+        None => tauri::http::Response::builder()
+            .status(tauri::http::StatusCode::NOT_FOUND)
+            .body(Vec::new()),
+    };
+    response.unwrap_or_default()
+}
+
+/// Generated TypeScript bindings for every command, rewritten on each debug run.
+#[cfg(debug_assertions)]
+const BINDINGS_PATH: &str = "../src/api/bindings.ts";
 
-    Ok(())
+fn specta_builder() -> tauri_specta::Builder<tauri::Wry> {
+    tauri_specta::Builder::<tauri::Wry>::new().commands(tauri_specta::collect_commands![
+        commands::vault::init_vault_oauth,
+        commands::vault::start_github_sign_in,
+        commands::vault::poll_github_sign_in,
+        commands::vault::init_vault_with_key,
+        commands::vault::init_vault,
+        commands::vault::unlock_vault_oauth::<tauri::Wry>,
+        commands::vault::unlock_vault_with_key::<tauri::Wry>,
+        commands::vault::reenroll_vault_key::<tauri::Wry>,
+        commands::vault::check_binary_integrity,
+        commands::sync::sync_status,
+        commands::sync::connect_google_drive,
+        commands::sync::connect_s3,
+        commands::sync::disconnect_sync,
+        commands::sync::sync_now,
+        commands::lan_sync::list_lan_peers,
+        commands::lan_sync::discover_lan_peers,
+        commands::lan_sync::open_lan_pairing,
+        commands::lan_sync::pair_lan_peer,
+        commands::lan_sync::confirm_lan_pairing,
+        commands::lan_sync::reject_lan_pairing,
+        commands::lan_sync::forget_lan_peer,
+        commands::lan_sync::sync_with_lan_peer,
+        commands::aliases::connect_alias_service,
+        commands::aliases::disconnect_alias_service,
+        commands::aliases::create_alias,
+        commands::aliases::list_aliases,
+        commands::aliases::disable_alias,
+        commands::credential::search_archive,
+        commands::credential::restore_archived_entry,
+        commands::vault::unlock_vault::<tauri::Wry>,
+        commands::vault::get_vault_auth_method,
+        commands::vault::get_vault_identity_provider,
+        commands::vault::reauthenticate,
+        commands::vault::reencrypt_vault,
+        commands::vault::reencrypt_vault_to_oauth,
+        commands::vault::migrate_to_oauth,
+        commands::vault::vault_status,
+        commands::vault::attempt_vault_repair,
+        commands::vault::keep_this_vault_copy,
+        commands::vault::configure_duress_vault,
+        commands::vault::remove_duress_vault,
+        commands::vault::delete_vault,
+        commands::vault::request_confirmation,
+        commands::audit::get_audit_log,
+        commands::audit::export_audit_log,
+        commands::backup::list_backups,
+        commands::backup::restore_from_backup,
+        commands::backup::get_pending_vault_file,
+        commands::backup::open_vault_file,
+        commands::backup::import_vault_file,
+        commands::backup::merge_vault,
+        commands::session::lock_vault,
+        commands::session::get_session_info,
+        commands::session::extend_session,
+        capabilities::claim_capabilities,
+        commands::guest::start_guest_session,
+        commands::guest::get_guest_session,
+        commands::guest::end_guest_session,
+        commands::session::get_auth_preferences,
+        commands::session::get_vault_summary,
+        commands::settings::get_settings,
+        commands::settings::update_settings,
+        commands::settings::import_dictionary,
+        commands::settings::export_settings,
+        commands::settings::import_settings,
+        commands::reveal::set_secrets_revealed,
+        commands::session::is_app_ready,
+        commands::credential::search_entries,
+        commands::credential::request_secret,
+        commands::credential::get_entry_actions,
+        commands::credential::add_entry,
+        commands::credential::add_secret_reference,
+        commands::credential::add_bank_account,
+        commands::credential::add_api_key,
+        commands::credential::add_server,
+        commands::credential::get_full_entry,
+        commands::credential::update_entry,
+        commands::credential::delete_entry,
+        commands::credential::delete_entries,
+        commands::credential::undo_last_operation,
+        commands::credential::redo,
+        commands::credential::add_canary_entry,
+        commands::credential::set_entry_folder,
+        commands::credential::set_entry_icon,
+        commands::credential::list_folders,
+        commands::credential::get_entry_history,
+        commands::credential::set_breach_watch,
+        commands::credential::set_password_expiry,
+        commands::credential::snooze_password_expiry,
+        commands::credential::mark_password_rotated,
+        commands::credential::set_entry_favorite,
+        commands::credential::link_entry,
+        commands::credential::set_password_policy,
+        commands::credential::refresh_icon,
+        commands::credential::set_entry_verification,
+        commands::credential::get_unverified_entries,
+        commands::clipboard::copy_secret_to_clipboard,
+        commands::browser::resolve_browser_association,
+        commands::browser::list_browser_associations,
+        commands::browser::remove_browser_association,
+        commands::ssh::add_ssh_key,
+        commands::ssh::resolve_ssh_confirmation,
+        commands::ssh::get_ssh_agent_socket,
+        commands::share::share_secret_locally,
+        commands::share::cancel_local_share,
+        commands::share::share_secret_once,
+        commands::share::open_shared_secret,
+        commands::sharing::get_sharing_key,
+        commands::sharing::list_share_contacts,
+        commands::sharing::add_share_contact,
+        commands::sharing::remove_share_contact,
+        commands::sharing::share_entries,
+        commands::sharing::import_shared_entries,
+        commands::generator::generate_password,
+        commands::generator::generate_password_for_entry,
+        commands::generator::get_generation_history,
+        commands::generator::clear_generation_history,
+        commands::generator::password_entropy,
+        commands::generator::analyze_password_strength,
+        commands::health::check_vault_health,
+        commands::health::connect_breach_monitor,
+        commands::health::disconnect_breach_monitor,
+        commands::health::get_breach_database_status,
+        commands::health::download_breach_database,
+        commands::health::cancel_breach_database_download,
+        commands::health::delete_breach_database,
+        commands::health::fix_url_secret,
+    ])
+}
+
+/// Command-line modes that do their job and exit without opening the app. Returns
+/// the exit code if one ran.
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    match args.get(1).map(String::as_str) {
+        Some(git_credential::CLI_COMMAND) => Some(git_credential::run(
+            args.get(2).map(String::as_str).unwrap_or_default(),
+        )),
+        Some(command) if cli::COMMANDS.contains(&command) => Some(cli::run(&args[1..])),
+        _ => None,
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -82,13 +348,22 @@ pub fn run() {
         dotenvy::dotenv().ok();
     }
 
+    let builder = specta_builder();
+    #[cfg(debug_assertions)]
+    builder
+        .export(
+            Typescript::default().bigint(BigIntExportBehavior::Number),
+            BINDINGS_PATH,
+        )
+        .expect("Failed to export TypeScript bindings");
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_google_auth::init())
-        .plugin(tauri_plugin_biometry::init())
-        .plugin(tauri_plugin_updater::Builder::new().build())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_notification::init())
+        .register_uri_scheme_protocol(icons::SCHEME, |ctx, request| {
+            icon_protocol(ctx.app_handle(), request)
+        })
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -98,19 +373,61 @@ pub fn run() {
                 )?;
             }
 
-            let storage =
-                vault::storage::VaultStorage::new().expect("Failed to initialize vault storage");
-            let workspace = vault::workspace::Workspace::new();
+            let settings_storage =
+                settings::SettingsStorage::new().expect("Failed to initialize settings storage");
+            app.manage(commands::SettingsState::new(settings_storage));
+            let ephemeral = vault::ephemeral::requested(
+                std::env::args(),
+                app.state::<commands::SettingsState>().get().ephemeral_vault,
+            );
+            let storage = if ephemeral {
+                vault::ephemeral::seeded().expect("Failed to create ephemeral vault")
+            } else {
+                vault::storage::VaultStorage::new().expect("Failed to initialize vault storage")
+            };
+            let workspace = vault::workspace::Workspace::new()
+                .with_events(std::sync::Arc::new(AppEvents(app.handle().clone())));
             app.manage(commands::VaultState::new(storage, workspace));
             app.manage(AuthState::new());
+            app.manage(audit::AuditLog::new().expect("Failed to initialize audit log"));
+            app.manage(local_share::LocalShareState::new());
+            app.manage(sharing::SharingState::new().expect("Failed to locate share contacts"));
+            app.manage(commands::OpenedFileState(Mutex::new(
+                vault::portable::from_launch_args(std::env::args()),
+            )));
+            app.manage(commands::RevealState(AtomicBool::new(false)));
+            app.manage(clipboard::SystemClipboard::default());
+            app.manage(startup::Readiness::default());
+            app.manage(browser::server::BrowserState::default());
+            app.manage(ssh::SshAgentState::default());
+            app.manage(git_credential::GitCredentialState::default());
+            app.manage(confirmation::ConfirmationState::default());
+            app.manage(integrity::BinaryIntegrity::new().expect("Failed to locate binary record"));
+            app.manage(sync::SyncState::new().expect("Failed to locate sync record"));
+            app.manage(lan_sync::LanSyncState::new().expect("Failed to locate LAN sync data"));
+            app.manage(commands::aliases::AliasState::default());
+            app.manage(rate_limit::CommandRateLimiter::default());
+            app.manage(capabilities::CapabilityState::default());
+            app.manage(breach_database::BreachDatabaseState::default());
+            app.manage(
+                breach_watch::AccountMonitor::new().expect("Failed to locate breach monitor data"),
+            );
+            app.manage(icons::IconCache::new().expect("Failed to locate icon cache"));
+            app.manage(
+                password_generator::history::GenerationHistory::new()
+                    .expect("Failed to locate generation history"),
+            );
 
             let handle = app.handle().clone();
+            let autotype_shortcut: Shortcut = autotype::AUTOTYPE_SHORTCUT.parse()?;
             app.handle().plugin(
                 tauri_plugin_global_shortcut::Builder::new()
                     .with_shortcut("Ctrl+Space")?
-                    .with_handler(move |_app, _shortcut, event| {
+                    .with_handler(move |_app, shortcut, event| {
                         if event.state == ShortcutState::Pressed {
-                            if let Some(window) = handle.get_webview_window("main") {
+                            if *shortcut == autotype_shortcut {
+                                commands::autotype::trigger(&handle);
+                            } else if let Some(window) = handle.get_webview_window("main") {
                                 let is_visible = window.is_visible().unwrap_or(false);
                                 if is_visible {
                                     let _ = window.hide();
@@ -123,48 +440,82 @@ pub fn run() {
                     })
                     .build(),
             )?;
-
-            if let Err(e) = setup_system_tray(app) {
-                eprintln!("Failed to setup system tray: {}", e);
-            }
+            commands::autotype::sync_shortcut(
+                app.handle(),
+                app.state::<commands::SettingsState>().get().autotype,
+            );
 
             let window = app
                 .get_webview_window("main")
                 .ok_or("Failed to get main window")?;
+            if !app.state::<commands::SettingsState>().get().start_minimized {
+                window.show()?;
+            }
             let window_clone = window.clone();
-            window.on_window_event(move |event| {
-                if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+            window.on_window_event(move |event| match event {
+                tauri::WindowEvent::CloseRequested { api, .. } => {
                     let _ = window_clone.hide();
                     api.prevent_close();
                 }
+                tauri::WindowEvent::Focused(false) => {
+                    commands::reveal::hide_on_blur(window_clone.app_handle());
+                }
+                _ => {}
             });
 
+            startup::finish_in_background(app.handle().clone());
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            commands::vault::init_vault_oauth,
-            commands::vault::init_vault_with_key,
-            commands::vault::init_vault,
-            commands::vault::unlock_vault_oauth,
-            commands::vault::unlock_vault_with_key,
-            commands::vault::unlock_vault,
-            commands::vault::get_vault_auth_method,
-            commands::vault::reencrypt_vault,
-            commands::vault::reencrypt_vault_to_oauth,
-            commands::vault::migrate_to_oauth,
-            commands::vault::vault_status,
-            commands::session::lock_vault,
-            commands::session::get_auth_preferences,
-            commands::credential::search_entries,
-            commands::credential::request_secret,
-            commands::credential::add_entry,
-            commands::credential::get_full_entry,
-            commands::credential::update_entry,
-            commands::credential::delete_entry,
-            commands::generator::generate_password,
-            commands::generator::analyze_password_strength,
-            commands::health::check_vault_health,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler({
+            let handler = builder.invoke_handler();
+            move |invoke| {
+                let webview = invoke.message.webview_ref();
+                let command = invoke.message.command();
+                if let Err(e) = commands::guest::permit(webview, command)
+                    .and_then(|_| rate_limit::permit(webview, command))
+                    .and_then(|_| capabilities::permit(webview, command, invoke.message.headers()))
+                {
+                    invoke.resolver.reject(e);
+                    return true;
+                }
+                handler(invoke)
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(handle_run_event);
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn handle_run_event(app_handle: &AppHandle, event: tauri::RunEvent) {
+    if let tauri::RunEvent::Opened { urls } = event {
+        let opened = urls
+            .into_iter()
+            .filter_map(|url| url.to_file_path().ok())
+            .find(|path| {
+                path.extension().and_then(|e| e.to_str())
+                    == Some(vault::portable::PORTABLE_EXTENSION)
+            });
+        if let Some(path) = opened {
+            if let Ok(mut pending) = app_handle.state::<commands::OpenedFileState>().0.lock() {
+                *pending = Some(path.clone());
+            }
+            let _ = app_handle.emit("vault-file-opened", path.display().to_string());
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn handle_run_event(_app_handle: &AppHandle, _event: tauri::RunEvent) {}
+
+#[cfg(test)]
+mod tests {
+    use specta_typescript::{BigIntExportBehavior, Typescript};
+
+    #[test]
+    fn typescript_bindings_export() {
+        let bindings = super::specta_builder()
+            .export_str(Typescript::default().bigint(BigIntExportBehavior::Number));
+        assert!(bindings.is_ok());
+    }
 }
@@ -0,0 +1,247 @@
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::{crypto::ring, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use zeroize::Zeroizing;
+
+pub const DEFAULT_SHARE_TTL_SECS: u64 = 60;
+pub const MAX_SHARE_TTL_SECS: u64 = 300;
+const MAX_FAILED_REQUESTS: u32 = 5;
+const MAX_REQUEST_BYTES: usize = 4096;
+/// How long one connection gets for its TLS handshake and request together.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct LocalShare {
+    pub id: String,
+    pub url: String,
+    pub expires_in_secs: u64,
+    /// SHA-256 of the self-signed certificate, so the receiving side can pin it.
+    pub cert_sha256: String,
+}
+
+/// Cancellation handles for shares that are still being served.
+pub struct LocalShareState(pub Mutex<HashMap<String, oneshot::Sender<()>>>);
+
+impl LocalShareState {
+    pub fn new() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+}
+
+pub fn clamp_ttl(ttl_secs: Option<u64>) -> u64 {
+    ttl_secs
+        .unwrap_or(DEFAULT_SHARE_TTL_SECS)
+        .clamp(1, MAX_SHARE_TTL_SECS)
+}
+
+/// Serves `secret` exactly once over HTTPS on 127.0.0.1 at a random port and path.
+/// The listener shuts down after the first successful read, on expiry, on
+/// cancellation, or after repeated bad requests.
+pub async fn start(
+    secret: Zeroizing<String>,
+    ttl_secs: u64,
+) -> Result<(LocalShare, oneshot::Sender<()>), String> {
+    let certified =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+            .map_err(|e| format!("Failed to create certificate: {}", e))?;
+    let cert_der = certified.cert.der().clone();
+    let cert_sha256 = hex::encode(Sha256::digest(cert_der.as_ref()));
+    let key_der =
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+
+    let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der], key_der)
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?;
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to open local port: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to open local port: {}", e))?
+        .port();
+
+    let mut token_bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token_bytes);
+    let token = hex::encode(token_bytes);
+
+    let (cancel_tx, mut cancel_rx) = oneshot::channel::<()>();
+    let share = LocalShare {
+        id: uuid::Uuid::new_v4().to_string(),
+        url: format!("https://127.0.0.1:{}/{}", port, token),
+        expires_in_secs: ttl_secs,
+        cert_sha256,
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let deadline = tokio::time::sleep(Duration::from_secs(ttl_secs));
+        tokio::pin!(deadline);
+        let mut failures = 0;
+
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                _ = &mut cancel_rx => break,
+                accepted = listener.accept() => {
+                    let Ok((tcp, _)) = accepted else {
+                        continue;
+                    };
+                    let serve = async {
+                        let tls = acceptor
+                            .accept(tcp)
+                            .await
+                            .map_err(|e| format!("TLS handshake failed: {}", e))?;
+                        serve_request(tls, &token, &secret).await
+                    };
+                    // A client that stalls mid-request must not keep the share
+                    // open past its deadline or a cancel.
+                    let served = tokio::select! {
+                        _ = &mut deadline => break,
+                        _ = &mut cancel_rx => break,
+                        served = tokio::time::timeout(REQUEST_TIMEOUT, serve) => {
+                            matches!(served, Ok(Ok(true)))
+                        }
+                    };
+                    if served {
+                        break;
+                    }
+                    failures += 1;
+                    if failures >= MAX_FAILED_REQUESTS {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok((share, cancel_tx))
+}
+
+/// Answers a single HTTP request. Returns `true` if the secret was handed out.
+pub async fn serve_request<S>(mut stream: S, token: &str, secret: &str) -> Result<bool, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = Zeroizing::new(vec![0u8; MAX_REQUEST_BYTES]);
+    let mut read = 0;
+    while read < buf.len() {
+        let n = stream
+            .read(&mut buf[read..])
+            .await
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+        if buf[..read].windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let authorized = request_path(&request)
+        .map(|path| constant_time_eq(path.as_bytes(), token.as_bytes()))
+        .unwrap_or(false);
+
+    let response = if authorized {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nCache-Control: no-store\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            secret.len(),
+            secret
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    let response = Zeroizing::new(response);
+
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))?;
+    let _ = stream.shutdown().await;
+    Ok(authorized)
+}
+
+fn request_path(request: &str) -> Option<&str> {
+    let mut parts = request.lines().next()?.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    parts.next()?.strip_prefix('/')
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ttl_is_clamped() {
+        assert_eq!(clamp_ttl(None), DEFAULT_SHARE_TTL_SECS);
+        assert_eq!(clamp_ttl(Some(0)), 1);
+        assert_eq!(clamp_ttl(Some(10_000)), MAX_SHARE_TTL_SECS);
+    }
+
+    #[test]
+    fn request_path_requires_get() {
+        assert_eq!(request_path("GET /abc HTTP/1.1\r\n"), Some("abc"));
+        assert_eq!(request_path("POST /abc HTTP/1.1\r\n"), None);
+        assert_eq!(request_path(""), None);
+    }
+
+    #[tokio::test]
+    async fn serves_secret_for_matching_token() {
+        let (mut client, server) = tokio::io::duplex(8192);
+        let handle =
+            tokio::spawn(async move { serve_request(server, "token123", "hunter2").await });
+
+        client
+            .write_all(b"GET /token123 HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+
+        assert!(handle.await.unwrap().unwrap());
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_token() {
+        let (mut client, server) = tokio::io::duplex(8192);
+        let handle =
+            tokio::spawn(async move { serve_request(server, "token123", "hunter2").await });
+
+        client
+            .write_all(b"GET /token124 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+
+        assert!(!handle.await.unwrap().unwrap());
+        assert!(response.starts_with("HTTP/1.1 404"));
+        assert!(!response.contains("hunter2"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Sockets (named pipes on Windows) that other local programs use to reach the
+//! running app: browser extensions, `ssh` and `git`. Only the current user can
+//! connect.
+
+use std::future::Future;
+use tokio::sync::oneshot;
+
+#[cfg(unix)]
+pub type Stream = tokio::net::UnixStream;
+#[cfg(windows)]
+pub type Stream = tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// A socket path under the user's runtime directory.
+#[cfg(unix)]
+pub fn runtime_path(parts: &[&str]) -> std::path::PathBuf {
+    let mut path = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    path.extend(parts);
+    path
+}
+
+/// A pipe name that is unique to the user.
+#[cfg(windows)]
+pub fn pipe_name(name: &str) -> String {
+    format!(
+        r"\\.\pipe\{}_{}",
+        name,
+        std::env::var("USERNAME").unwrap_or_default()
+    )
+}
+
+/// Hands every connection on `path` to `serve` until the returned sender fires or
+/// is dropped.
+#[cfg(unix)]
+pub fn listen<F, Fut>(path: std::path::PathBuf, serve: F) -> Result<oneshot::Sender<()>, String>
+where
+    F: Fn(Stream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!(
+            "Something is already listening on {}",
+            path.display()
+        ));
+    }
+    let _ = std::fs::remove_file(&path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+    }
+    let listener =
+        UnixListener::bind(&path).map_err(|e| format!("Failed to open socket: {}", e))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict socket: {}", e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to open socket: {}", e))?;
+
+    let (stop, mut stopped) = oneshot::channel();
+    tauri::async_runtime::spawn(async move {
+        match tokio::net::UnixListener::from_std(listener) {
+            Ok(listener) => loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    accepted = listener.accept() => {
+                        if let Ok((stream, _)) = accepted {
+                            tauri::async_runtime::spawn(serve(stream));
+                        }
+                    }
+                }
+            },
+            Err(e) => eprintln!("Failed to open socket: {}", e),
+        }
+        let _ = std::fs::remove_file(&path);
+    });
+    Ok(stop)
+}
+
+/// Hands every connection on the pipe `name` to `serve` until the returned sender
+/// fires or is dropped.
+#[cfg(windows)]
+pub fn listen<F, Fut>(name: String, serve: F) -> Result<oneshot::Sender<()>, String>
+where
+    F: Fn(Stream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let (stop, mut stopped) = oneshot::channel();
+    tauri::async_runtime::spawn(async move {
+        let mut first = true;
+        loop {
+            let server = match ServerOptions::new()
+                .first_pipe_instance(first)
+                .reject_remote_clients(true)
+                .create(&name)
+            {
+                Ok(server) => server,
+                Err(e) => {
+                    eprintln!("Failed to open pipe {}: {}", name, e);
+                    break;
+                }
+            };
+            first = false;
+            let connected = tokio::select! {
+                _ = &mut stopped => break,
+                connected = server.connect() => connected,
+            };
+            if connected.is_ok() {
+                tauri::async_runtime::spawn(serve(server));
+            }
+        }
+    });
+    Ok(stop)
+}
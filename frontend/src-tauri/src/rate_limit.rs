@@ -0,0 +1,120 @@
+//! Rate limits on the commands a compromised frontend script would hammer to
+//! walk the Vault: reading secrets or data derived from them, unlocking, and
+//! exporting or sharing. Each
+//! kind is counted over a sliding window, separately from the unlock lockout
+//! (see `auth::lockout`), and checked for each IPC message before its handler.
+
+use crate::error::LatchError;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Manager, Runtime, Webview};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Limited {
+    Secrets,
+    Unlock,
+    Export,
+}
+
+impl Limited {
+    fn of(command: &str) -> Option<Self> {
+        match command {
+            "request_secret"
+            | "get_full_entry"
+            | "copy_secret_to_clipboard"
+            | "get_generation_history"
+            | "get_entry_history" => Some(Limited::Secrets),
+            "share_secret_once" | "share_secret_locally" | "share_entries" => Some(Limited::Export),
+            c if c.starts_with("unlock_") => Some(Limited::Unlock),
+            c if c.starts_with("export_") => Some(Limited::Export),
+            _ => None,
+        }
+    }
+
+    /// Calls allowed per `WINDOW`; well above what a person clicking through the
+    /// app makes.
+    fn max_calls(self) -> usize {
+        match self {
+            Limited::Secrets => 60,
+            Limited::Unlock => 10,
+            Limited::Export => 5,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandRateLimiter {
+    calls: Mutex<HashMap<Limited, VecDeque<Instant>>>,
+}
+
+impl CommandRateLimiter {
+    /// Counts a call to `command`, or refuses it if its kind is over the limit.
+    pub fn check(&self, command: &str) -> Result<(), LatchError> {
+        let Some(kind) = Limited::of(command) else {
+            return Ok(());
+        };
+        self.check_at(kind, Instant::now())
+    }
+
+    fn check_at(&self, kind: Limited, now: Instant) -> Result<(), LatchError> {
+        let mut calls = self
+            .calls
+            .lock()
+            .map_err(|_| LatchError::Unavailable("Latch is temporarily unavailable".to_string()))?;
+        let recent = calls.entry(kind).or_default();
+        while recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= WINDOW)
+        {
+            recent.pop_front();
+        }
+        if let (true, Some(oldest)) = (recent.len() >= kind.max_calls(), recent.front()) {
+            let wait = WINDOW.saturating_sub(now.duration_since(*oldest));
+            return Err(LatchError::RateLimited(format!(
+                "Too many requests; try again in {} seconds",
+                wait.as_secs().max(1)
+            )));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Refuses `command` while its kind is over the limit.
+pub fn permit<R: Runtime>(webview: &Webview<R>, command: &str) -> Result<(), LatchError> {
+    match webview.try_state::<CommandRateLimiter>() {
+        Some(limiter) => limiter.check(command),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_kind_is_limited_over_its_own_window() {
+        let limiter = CommandRateLimiter::default();
+        let start = Instant::now();
+        for _ in 0..Limited::Export.max_calls() {
+            limiter.check_at(Limited::Export, start).unwrap();
+        }
+        let refused = limiter.check_at(Limited::Export, start).unwrap_err();
+        assert_eq!(refused.kind(), "RateLimited");
+        assert!(refused.retryable());
+        assert!(limiter.check_at(Limited::Unlock, start).is_ok());
+        assert!(limiter.check_at(Limited::Export, start + WINDOW).is_ok());
+
+        assert_eq!(Limited::of("unlock_vault_oauth"), Some(Limited::Unlock));
+        assert_eq!(Limited::of("export_audit_log"), Some(Limited::Export));
+        assert_eq!(
+            Limited::of("copy_secret_to_clipboard"),
+            Some(Limited::Secrets)
+        );
+        assert_eq!(Limited::of("get_entry_history"), Some(Limited::Secrets));
+        assert_eq!(Limited::of("search_entries"), None);
+    }
+}
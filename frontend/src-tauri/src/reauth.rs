@@ -0,0 +1,167 @@
+//! Fresh confirmation before sensitive actions. The settings say which actions
+//! need it; the user then re-enters the Vault's credential (`reauthenticate`),
+//! which lets those actions through for `REAUTH_WINDOW_SECS`. The window belongs
+//! to the Session it was opened in, so locking closes it.
+
+use crate::vault::workspace::Workspace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+pub const REAUTH_WINDOW_SECS: u64 = 2 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveAction {
+    /// Showing or returning a Credential's password.
+    RevealPassword,
+    /// Writing Vault data out: the audit log, or a share link for a secret.
+    Export,
+    DeleteVault,
+}
+
+impl SensitiveAction {
+    fn describe(self) -> &'static str {
+        match self {
+            SensitiveAction::RevealPassword => "reveal passwords",
+            SensitiveAction::Export => "export",
+            SensitiveAction::DeleteVault => "delete the vault",
+        }
+    }
+}
+
+/// Which actions need a fresh confirmation. None do by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct ReauthPolicy {
+    pub reveal_password: bool,
+    pub export: bool,
+    pub delete_vault: bool,
+}
+
+impl ReauthPolicy {
+    pub fn requires(&self, action: SensitiveAction) -> bool {
+        match action {
+            SensitiveAction::RevealPassword => self.reveal_password,
+            SensitiveAction::Export => self.export,
+            SensitiveAction::DeleteVault => self.delete_vault,
+        }
+    }
+
+    /// Whether `next` stops guarding an action this policy guards.
+    pub fn weakened_by(&self, next: &ReauthPolicy) -> bool {
+        [
+            SensitiveAction::RevealPassword,
+            SensitiveAction::Export,
+            SensitiveAction::DeleteVault,
+        ]
+        .into_iter()
+        .any(|action| self.requires(action) && !next.requires(action))
+    }
+}
+
+/// When the user last confirmed, and the Session they confirmed in.
+#[derive(Default)]
+pub struct ReauthWindow(Mutex<Option<(SystemTime, Instant)>>);
+
+impl ReauthWindow {
+    pub fn open(&self, session_start: SystemTime) {
+        let mut window = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *window = Some((session_start, Instant::now()));
+    }
+
+    fn is_open(&self, session_start: Option<SystemTime>) -> bool {
+        let window = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(
+            (*window, session_start),
+            (Some((confirmed_in, at)), Some(current))
+                if confirmed_in == current
+                    && at.elapsed() < Duration::from_secs(REAUTH_WINDOW_SECS)
+        )
+    }
+
+    /// Refuses `action` if `policy` guards it and the user hasn't confirmed in
+    /// this Session recently.
+    pub fn check(
+        &self,
+        policy: &ReauthPolicy,
+        action: SensitiveAction,
+        workspace: &Workspace,
+    ) -> Result<(), String> {
+        if !policy.requires(action) || self.is_open(workspace.session_start) {
+            return Ok(());
+        }
+        Err(format!("Confirm it's you to {}", action.describe()))
+    }
+
+    /// Refuses replacing `current` with a `next` that guards less, unless the
+    /// user has confirmed in this Session recently. A locked Vault can't confirm,
+    /// so guards only come off while it is unlocked.
+    pub fn check_policy_change(
+        &self,
+        current: &ReauthPolicy,
+        next: &ReauthPolicy,
+        workspace: &Workspace,
+    ) -> Result<(), String> {
+        if !current.weakened_by(next) || self.is_open(workspace.session_start) {
+            return Ok(());
+        }
+        Err("Confirm it's you to turn off confirmations".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guarded_actions_pass_only_within_the_sessions_window() {
+        let policy = ReauthPolicy {
+            reveal_password: true,
+            ..Default::default()
+        };
+        let window = ReauthWindow::default();
+        let mut workspace = Workspace::new();
+        workspace.session_start = Some(SystemTime::now());
+
+        assert!(window
+            .check(&policy, SensitiveAction::Export, &workspace)
+            .is_ok());
+        assert!(window
+            .check(&policy, SensitiveAction::RevealPassword, &workspace)
+            .is_err());
+
+        window.open(workspace.session_start.unwrap());
+        assert!(window
+            .check(&policy, SensitiveAction::RevealPassword, &workspace)
+            .is_ok());
+
+        // A new Session needs a new confirmation.
+        workspace.session_start = Some(SystemTime::now() + Duration::from_secs(1));
+        assert!(window
+            .check(&policy, SensitiveAction::RevealPassword, &workspace)
+            .is_err());
+
+        let stale = Instant::now()
+            .checked_sub(Duration::from_secs(REAUTH_WINDOW_SECS))
+            .unwrap();
+        *window.0.lock().unwrap() = Some((workspace.session_start.unwrap(), stale));
+        assert!(window
+            .check(&policy, SensitiveAction::RevealPassword, &workspace)
+            .is_err());
+
+        // Guards come off only with a fresh confirmation; adding them needs none.
+        let none = ReauthPolicy::default();
+        assert!(window
+            .check_policy_change(&policy, &none, &workspace)
+            .is_err());
+        assert!(window
+            .check_policy_change(&none, &policy, &workspace)
+            .is_ok());
+        window.open(workspace.session_start.unwrap());
+        assert!(window
+            .check_policy_change(&policy, &none, &workspace)
+            .is_ok());
+    }
+}
@@ -0,0 +1,327 @@
+use crate::hooks::MaintenanceHook;
+use crate::reauth::ReauthPolicy;
+use crate::vault::backup::BackupMode;
+use crate::vault::search::MatchOptions;
+use crate::vault::sync::SyncProviderKind;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_IDLE_LOCK_SECS: u64 = 10 * 60;
+const EXPORT_FORMAT: &str = "latch-settings";
+const EXPORT_VERSION: u32 = 1;
+const MAX_EXPORT_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct Settings {
+    /// Lock the Workspace after this many seconds without activity. Zero disables idle locking.
+    pub idle_lock_secs: u64,
+    pub backup_mode: BackupMode,
+    /// Exclude the window from screenshots and screen capture while a secret is revealed
+    /// (Windows and macOS only).
+    pub block_capture_on_reveal: bool,
+    /// Hide revealed secrets as soon as the window loses focus.
+    pub hide_secrets_on_blur: bool,
+    /// Context-specific words (company, products, local teams) treated as weak by the
+    /// strength analysis, the health scanner and the generator.
+    pub custom_dictionary: Vec<String>,
+    /// Clear a copied secret from the clipboard after this many seconds. Zero disables clearing.
+    pub clipboard_clear_secs: u64,
+    /// Local scripts run after backups or exports, given only the artifact path.
+    pub hooks: Vec<MaintenanceHook>,
+    /// Answer KeePassXC-Browser extensions. Each one still has to be approved once.
+    pub browser_integration: bool,
+    /// Type the matching Credential into the focused window on Ctrl+Alt+A.
+    pub autotype: bool,
+    /// Offer the Vault's SSH keys to `ssh` through an agent socket while unlocked.
+    pub ssh_agent: bool,
+    /// Answer `latch git-credential`, so git can use the Vault's logins for HTTPS remotes.
+    pub git_credential_helper: bool,
+    /// Developer mode: start with an in-memory Vault of fake data instead of the real
+    /// one. Takes effect on the next launch.
+    pub ephemeral_vault: bool,
+    /// Keep deleted Credentials in the encrypted archive instead of discarding them.
+    pub archive_deleted: bool,
+    /// Keep the encrypted Vault in step with this provider while unlocked.
+    pub sync_provider: Option<SyncProviderKind>,
+    /// Let paired Latch instances on the local network find and sync with this one.
+    pub lan_sync: bool,
+    /// How strictly search matches, and what it lists before anything is typed.
+    pub search: MatchOptions,
+    /// Actions that need the Vault's credential re-entered shortly before.
+    pub reauth: ReauthPolicy,
+    /// Start Latch when the user logs in.
+    pub launch_at_login: bool,
+    /// Start with the window hidden, leaving only the tray icon and shortcuts.
+    pub start_minimized: bool,
+    /// Where one-time shares are uploaded (see `share`); None allows only share files.
+    pub share_relay_url: Option<String>,
+    /// Check the email addresses used as usernames against Have I Been Pwned's
+    /// breached-account API, with the key connected from the keychain.
+    pub account_breach_monitoring: bool,
+    /// Check passwords against the downloaded breach database instead of sending
+    /// hash prefixes to Have I Been Pwned.
+    pub offline_breach_check: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            idle_lock_secs: DEFAULT_IDLE_LOCK_SECS,
+            backup_mode: BackupMode::default(),
+            block_capture_on_reveal: true,
+            hide_secrets_on_blur: true,
+            custom_dictionary: Vec::new(),
+            clipboard_clear_secs: crate::clipboard::DEFAULT_CLIPBOARD_CLEAR_SECS,
+            hooks: Vec::new(),
+            browser_integration: false,
+            autotype: false,
+            ssh_agent: false,
+            git_credential_helper: false,
+            ephemeral_vault: false,
+            archive_deleted: false,
+            sync_provider: None,
+            lan_sync: false,
+            search: MatchOptions::default(),
+            reauth: ReauthPolicy::default(),
+            launch_at_login: false,
+            start_minimized: false,
+            share_relay_url: None,
+            account_breach_monitoring: false,
+            offline_breach_check: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.idle_lock_secs != 0 {
+            if self.idle_lock_secs < 2 * crate::vault::idle::IDLE_WARNING_SECS {
+                return Err(format!(
+                    "Idle lock timeout must be at least {} seconds",
+                    2 * crate::vault::idle::IDLE_WARNING_SECS
+                ));
+            }
+            if self.idle_lock_secs > crate::vault::SESSION_TIMEOUT_SECS {
+                return Err(format!(
+                    "Idle lock timeout cannot exceed the {} minute session limit",
+                    crate::vault::SESSION_TIMEOUT_SECS / 60
+                ));
+            }
+        }
+        if self.clipboard_clear_secs > crate::clipboard::MAX_CLIPBOARD_CLEAR_SECS {
+            return Err(format!(
+                "Clipboard clearing delay cannot exceed {} seconds",
+                crate::clipboard::MAX_CLIPBOARD_CLEAR_SECS
+            ));
+        }
+        if self.custom_dictionary.len() > crate::password_generator::MAX_DICTIONARY_WORDS {
+            return Err(format!(
+                "Custom dictionary cannot exceed {} words",
+                crate::password_generator::MAX_DICTIONARY_WORDS
+            ));
+        }
+        if self.hooks.len() > crate::hooks::MAX_HOOKS {
+            return Err(format!(
+                "Cannot register more than {} hooks",
+                crate::hooks::MAX_HOOKS
+            ));
+        }
+        if self.search.min_score > crate::vault::search::MAX_MIN_SCORE {
+            return Err(format!(
+                "Search match threshold cannot exceed {}",
+                crate::vault::search::MAX_MIN_SCORE
+            ));
+        }
+        for hook in &self.hooks {
+            hook.validate()?;
+        }
+        Ok(())
+    }
+
+    /// These settings with `imported` taken over, except what belongs to this
+    /// device (see `export_to`).
+    pub fn with_imported(&self, imported: Settings) -> Settings {
+        Settings {
+            hooks: self.hooks.clone(),
+            ephemeral_vault: self.ephemeral_vault,
+            ..imported
+        }
+    }
+
+    /// The custom dictionary in the form the strength checks expect.
+    pub fn dictionary(&self) -> Vec<String> {
+        crate::password_generator::normalize_dictionary(self.custom_dictionary.iter().cloned())
+    }
+}
+
+/// A settings file for setting up another device.
+#[derive(Serialize, Deserialize)]
+struct SettingsExport {
+    format: String,
+    version: u32,
+    settings: Settings,
+}
+
+/// Writes the settings for another device to import. None of them are secret
+/// (API keys stay in the keychain). Hooks are left out, since they name scripts
+/// on this machine, and so is developer mode.
+pub fn export_to(settings: &Settings, path: &Path) -> Result<(), String> {
+    let export = SettingsExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        settings: Settings {
+            hooks: Vec::new(),
+            ephemeral_vault: false,
+            ..settings.clone()
+        },
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Reads settings written by `export_to`, checked like any other change.
+pub fn import_from(path: &Path) -> Result<Settings, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read settings: {}", e))?
+        .len();
+    if size > MAX_EXPORT_BYTES {
+        return Err("Settings file is too large".to_string());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    let export: SettingsExport = serde_json::from_str(&content)
+        .map_err(|_| "This is not a Latch settings file".to_string())?;
+    if export.format != EXPORT_FORMAT {
+        return Err("This is not a Latch settings file".to_string());
+    }
+    if export.version > EXPORT_VERSION {
+        return Err("These settings were exported by a newer version of Latch".to_string());
+    }
+    export.settings.validate()?;
+    Ok(export.settings)
+}
+
+pub struct SettingsStorage {
+    pub path: PathBuf,
+}
+
+impl SettingsStorage {
+    pub fn new() -> Result<Self, String> {
+        let config_dir = crate::vault::storage::config_dir()?;
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        Ok(Self {
+            path: config_dir.join("settings.json"),
+        })
+    }
+
+    pub fn read(&self) -> Result<Settings, String> {
+        if !self.path.exists() {
+            return Ok(Settings::default());
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read settings: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {}", e))
+    }
+
+    pub fn write(&self, settings: &Settings) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, &json).map_err(|e| format!("Failed to write settings: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to rename settings: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.idle_lock_secs, DEFAULT_IDLE_LOCK_SECS);
+        assert_eq!(settings.backup_mode, BackupMode::Differential);
+        assert!(settings.block_capture_on_reveal);
+        assert!(settings.hide_secrets_on_blur);
+    }
+
+    #[test]
+    fn idle_lock_can_be_disabled() {
+        let settings = Settings {
+            idle_lock_secs: 0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn idle_lock_rejects_out_of_range_values() {
+        assert!(Settings {
+            idle_lock_secs: 30,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(Settings {
+            idle_lock_secs: crate::vault::SESSION_TIMEOUT_SECS + 1,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn exported_settings_leave_this_device_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latch-settings.json");
+        let here = Settings {
+            idle_lock_secs: 300,
+            custom_dictionary: vec!["acme".to_string()],
+            ephemeral_vault: true,
+            ..Default::default()
+        };
+        export_to(&here, &path).unwrap();
+
+        let imported = import_from(&path).unwrap();
+        assert_eq!(imported.idle_lock_secs, 300);
+        assert_eq!(imported.custom_dictionary, vec!["acme"]);
+        assert!(!imported.ephemeral_vault);
+        let merged = here.with_imported(imported);
+        assert!(merged.ephemeral_vault);
+        assert_eq!(merged.idle_lock_secs, 300);
+
+        fs::write(&path, r#"{"idle_lock_secs": 300}"#).unwrap();
+        assert_eq!(
+            import_from(&path).unwrap_err(),
+            "This is not a Latch settings file"
+        );
+    }
+
+    #[test]
+    fn roundtrip_through_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SettingsStorage {
+            path: dir.path().join("settings.json"),
+        };
+        assert_eq!(
+            storage.read().unwrap().idle_lock_secs,
+            DEFAULT_IDLE_LOCK_SECS
+        );
+
+        storage
+            .write(&Settings {
+                idle_lock_secs: 300,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(storage.read().unwrap().idle_lock_secs, 300);
+    }
+}
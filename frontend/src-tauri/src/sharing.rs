@@ -0,0 +1,307 @@
+//! Sharing Credentials between Vaults. Each Vault has an X25519 sharing key; users
+//! swap public keys (and compare fingerprints out of band) and save each other as
+//! contacts. A share is a bundle sealed with crypto_box from the sender's key to
+//! the recipient's, so only the recipient can open it and it can only have come
+//! from the sender. The recipient imports it as new Credentials marked with who
+//! shared them.
+//!
+//! Like the LAN sync identity, the sharing key is derived from the Session key, so
+//! it is never written to disk and changes when the Vault is re-keyed; contacts
+//! then need the new public key.
+
+use crate::vault::{unix_now, Entry, Provenance};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use crypto_box::aead::{Aead, AeadCore, OsRng};
+use crypto_box::{PublicKey, SalsaBox, SecretKey};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use specta::Type;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+const IDENTITY_INFO: &[u8] = b"latch-sharing-identity";
+const BUNDLE_VERSION: u32 = 1;
+const MAX_BUNDLE_BYTES: u64 = 16 * 1024 * 1024;
+pub const MAX_CONTACT_NAME_LEN: usize = 64;
+
+/// This Vault's public sharing key, to give to contacts.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SharingKey {
+    /// Base64 of the X25519 public key.
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// Someone whose public key the user has saved, to share with or accept shares
+/// from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ShareContact {
+    pub name: String,
+    /// Base64 of the X25519 public key.
+    pub public_key: String,
+    pub fingerprint: String,
+    pub added_at: u64,
+}
+
+/// What a share file holds.
+#[derive(Serialize, Deserialize)]
+struct SharedBundle {
+    version: u32,
+    /// Base64 of the sender's public sharing key.
+    sender: String,
+    /// Base64 of the recipient's, so a misdirected bundle is refused clearly.
+    recipient: String,
+    created_at: u64,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub fn fingerprint(public_key: &PublicKey) -> String {
+    crate::lan_sync::fingerprint(public_key.as_bytes())
+}
+
+fn secret_key(session_key: &[u8; 32]) -> Result<SecretKey, String> {
+    let mut private = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, session_key)
+        .expand(IDENTITY_INFO, private.as_mut())
+        .map_err(|_| "Failed to derive sharing key".to_string())?;
+    Ok(SecretKey::from(*private))
+}
+
+pub fn sharing_key(session_key: &[u8; 32]) -> Result<SharingKey, String> {
+    let public = secret_key(session_key)?.public_key();
+    Ok(SharingKey {
+        public_key: B64.encode(public.as_bytes()),
+        fingerprint: fingerprint(&public),
+    })
+}
+
+fn parse_public_key(encoded: &str) -> Result<PublicKey, String> {
+    let bytes: [u8; 32] = B64
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Invalid public key")?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// Seals copies of `entries` for `recipient`. Their usage and tray pins stay
+/// behind; they are the sender's.
+pub fn seal(
+    session_key: &[u8; 32],
+    recipient: &ShareContact,
+    entries: &[Entry],
+) -> Result<Vec<u8>, String> {
+    let secret = secret_key(session_key)?;
+    let recipient_key = parse_public_key(&recipient.public_key)?;
+    let entries: Vec<Entry> = entries
+        .iter()
+        .cloned()
+        .map(|mut entry| {
+            entry.usage = Default::default();
+            entry.favorite = false;
+            entry
+        })
+        .collect();
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(&entries).map_err(|e| format!("Failed to serialize: {}", e))?,
+    );
+
+    let nonce = SalsaBox::generate_nonce(&mut OsRng);
+    let ciphertext = SalsaBox::new(&recipient_key, &secret)
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to seal the share".to_string())?;
+    let bundle = SharedBundle {
+        version: BUNDLE_VERSION,
+        sender: B64.encode(secret.public_key().as_bytes()),
+        recipient: recipient.public_key.clone(),
+        created_at: unix_now(),
+        nonce: B64.encode(nonce),
+        ciphertext: B64.encode(ciphertext),
+    };
+    serde_json::to_vec(&bundle).map_err(|e| format!("Failed to serialize: {}", e))
+}
+
+/// Opens a bundle from one of `contacts`, returning its Credentials with fresh
+/// ids and marked with their sender.
+pub fn open(
+    session_key: &[u8; 32],
+    contacts: &[ShareContact],
+    bundle: &[u8],
+) -> Result<Vec<Entry>, String> {
+    let bundle: SharedBundle =
+        serde_json::from_slice(bundle).map_err(|_| "This is not a Latch share".to_string())?;
+    if bundle.version != BUNDLE_VERSION {
+        return Err("This share was made by a newer version of Latch".to_string());
+    }
+    let secret = secret_key(session_key)?;
+    if parse_public_key(&bundle.recipient)? != secret.public_key() {
+        return Err("This share is for a different vault".to_string());
+    }
+    let sender_key = parse_public_key(&bundle.sender)?;
+    let sender = contacts
+        .iter()
+        .find(|c| c.fingerprint == fingerprint(&sender_key))
+        .ok_or("This share is from someone not in your contacts; add them first")?;
+
+    let nonce: [u8; 24] = B64
+        .decode(&bundle.nonce)
+        .ok()
+        .and_then(|n| n.try_into().ok())
+        .ok_or("Invalid share nonce")?;
+    let ciphertext = B64
+        .decode(&bundle.ciphertext)
+        .map_err(|_| "Invalid share data".to_string())?;
+    let plaintext = Zeroizing::new(
+        SalsaBox::new(&sender_key, &secret)
+            .decrypt(&nonce.into(), ciphertext.as_slice())
+            .map_err(|_| "This share could not be opened".to_string())?,
+    );
+    let entries: Vec<Entry> =
+        serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid share: {}", e))?;
+
+    let provenance = Provenance {
+        sender: sender.name.clone(),
+        fingerprint: sender.fingerprint.clone(),
+        shared_at: bundle.created_at,
+    };
+    Ok(entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.id = uuid::Uuid::new_v4().to_string();
+            entry.shared_from = Some(provenance.clone());
+            entry
+        })
+        .collect())
+}
+
+pub fn read_bundle(path: &Path) -> Result<Vec<u8>, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read share: {}", e))?
+        .len();
+    if size > MAX_BUNDLE_BYTES {
+        return Err("Share is too large".to_string());
+    }
+    fs::read(path).map_err(|e| format!("Failed to read share: {}", e))
+}
+
+/// The saved contacts. Public keys aren't secret, so they live beside the Vault
+/// rather than in it, like the LAN sync peers.
+pub struct SharingState {
+    path: PathBuf,
+}
+
+impl SharingState {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(
+            crate::vault::storage::config_dir()?.join("share-contacts.json"),
+        ))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn contacts(&self) -> Vec<ShareContact> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, contacts: &[ShareContact]) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to write contacts: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(contacts)
+            .map_err(|e| format!("Failed to serialize contacts: {}", e))?;
+        fs::write(&self.path, json).map_err(|e| format!("Failed to write contacts: {}", e))
+    }
+
+    /// Saves a contact, replacing any with the same key.
+    pub fn add(&self, name: &str, public_key: &str) -> Result<ShareContact, String> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_CONTACT_NAME_LEN {
+            return Err(format!(
+                "Contact name must be 1 to {} characters",
+                MAX_CONTACT_NAME_LEN
+            ));
+        }
+        let key = parse_public_key(public_key)?;
+        let contact = ShareContact {
+            name: name.to_string(),
+            public_key: B64.encode(key.as_bytes()),
+            fingerprint: fingerprint(&key),
+            added_at: unix_now(),
+        };
+        let mut contacts = self.contacts();
+        contacts.retain(|c| c.fingerprint != contact.fingerprint);
+        contacts.push(contact.clone());
+        self.save(&contacts)?;
+        Ok(contact)
+    }
+
+    pub fn remove(&self, fingerprint: &str) -> Result<(), String> {
+        let mut contacts = self.contacts();
+        contacts.retain(|c| c.fingerprint != fingerprint);
+        self.save(&contacts)
+    }
+
+    pub fn find(&self, fingerprint: &str) -> Result<ShareContact, String> {
+        self.contacts()
+            .into_iter()
+            .find(|c| c.fingerprint == fingerprint)
+            .ok_or_else(|| "Contact not found".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> Entry {
+        Entry {
+            id: "entry-1".to_string(),
+            title: title.to_string(),
+            username: "octo".to_string(),
+            password: "hunter2".to_string(),
+            favorite: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_the_recipient_opens_a_share_from_a_known_sender() {
+        let dir = tempfile::tempdir().unwrap();
+        let (alice, bob, eve) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let alice_contacts = SharingState::at(dir.path().join("alice.json"));
+        let bob_contacts = SharingState::at(dir.path().join("bob.json"));
+        let bob_as_contact = alice_contacts
+            .add("Bob", &sharing_key(&bob).unwrap().public_key)
+            .unwrap();
+
+        let bundle = seal(&alice, &bob_as_contact, &[entry("Router")]).unwrap();
+        assert!(open(&bob, &bob_contacts.contacts(), &bundle)
+            .unwrap_err()
+            .contains("not in your contacts"));
+
+        let alice_as_contact = bob_contacts
+            .add("Alice", &sharing_key(&alice).unwrap().public_key)
+            .unwrap();
+        let opened = open(&bob, &bob_contacts.contacts(), &bundle).unwrap();
+        assert_eq!(opened.len(), 1);
+        assert_eq!(opened[0].password, "hunter2");
+        assert_ne!(opened[0].id, "entry-1");
+        assert!(!opened[0].favorite);
+        let from = opened[0].shared_from.as_ref().unwrap();
+        assert_eq!(from.sender, "Alice");
+        assert_eq!(from.fingerprint, alice_as_contact.fingerprint);
+
+        assert_eq!(
+            open(&eve, &[alice_as_contact], &bundle).unwrap_err(),
+            "This share is for a different vault"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+//! SSH keys kept in the Vault, and an agent that offers them to `ssh` while the
+//! Vault is unlocked. Point `SSH_AUTH_SOCK` at `socket_path()` to use it.
+//!
+//! Only listing keys and signing are answered; adding, removing and locking are
+//! refused, since the Vault decides which keys exist.
+
+use crate::audit::{AuditKind, AuditLog};
+use crate::commands::VaultState;
+use crate::vault::{Entry, EntryKind};
+use serde::Serialize;
+use specta::Type;
+use ssh_key::private::KeypairData;
+use ssh_key::{Algorithm, EcdsaCurve, HashAlg, LineEnding, PrivateKey};
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::oneshot;
+use zeroize::Zeroizing;
+
+/// Emitted with an `SshConfirmRequest` when a key that needs confirming is asked to sign.
+pub const CONFIRM_EVENT: &str = "ssh://confirm-requested";
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+/// OpenSSH's own agent refuses anything larger.
+const MAX_MESSAGE_BYTES: u32 = 256 * 1024;
+
+const FAILURE: u8 = 5;
+const REQUEST_IDENTITIES: u8 = 11;
+const IDENTITIES_ANSWER: u8 = 12;
+const SIGN_REQUEST: u8 = 13;
+const SIGN_RESPONSE: u8 = 14;
+const RSA_SHA2_256: u32 = 2;
+const RSA_SHA2_512: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SshConfirmRequest {
+    pub request_id: String,
+    pub title: String,
+    pub fingerprint: String,
+}
+
+/// Parses a pasted OpenSSH private key, decrypting it with `passphrase` if it has
+/// one, and returns it unencrypted with its public half. From then on the Vault's
+/// own encryption protects it.
+pub fn import_key(
+    pem: &str,
+    passphrase: Option<&str>,
+) -> Result<(Zeroizing<String>, String), String> {
+    let key = PrivateKey::from_openssh(pem.trim())
+        .map_err(|e| format!("Invalid SSH private key: {}", e))?;
+    let key = if key.is_encrypted() {
+        let passphrase = passphrase.ok_or("Invalid SSH private key: passphrase required")?;
+        key.decrypt(passphrase)
+            .map_err(|_| "Invalid SSH key passphrase".to_string())?
+    } else {
+        key
+    };
+    match key.algorithm() {
+        Algorithm::Ed25519
+        | Algorithm::Rsa { .. }
+        | Algorithm::Ecdsa {
+            curve: EcdsaCurve::NistP256,
+        } => {}
+        other => return Err(format!("Unsupported SSH key type: {}", other.as_str())),
+    }
+
+    let private = key
+        .to_openssh(LineEnding::LF)
+        .map_err(|e| format!("Failed to encode SSH key: {}", e))?;
+    let public = key
+        .public_key()
+        .to_openssh()
+        .map_err(|e| format!("Failed to encode SSH key: {}", e))?;
+    Ok((private, public))
+}
+
+/// Where the agent's keys come from, and who approves their use.
+pub trait KeyStore {
+    /// SSH key Credentials on offer; none while the Vault is locked.
+    fn keys(&self) -> Vec<Entry>;
+    fn confirm(&self, entry: &Entry) -> bool;
+    fn used(&self, entry: &Entry);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+fn put_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn loaded(entries: Vec<Entry>) -> Vec<(Entry, PrivateKey)> {
+    entries
+        .into_iter()
+        .filter(|entry| matches!(entry.kind, EntryKind::SshKey { .. }))
+        .filter_map(|entry| {
+            let key = PrivateKey::from_openssh(&entry.password).ok()?;
+            Some((entry, key))
+        })
+        .collect()
+}
+
+/// Signs with the RSA hash the client asked for; the key's own algorithm otherwise.
+fn sign(key: &PrivateKey, data: &[u8], flags: u32) -> Result<ssh_key::Signature, String> {
+    match key.key_data() {
+        KeypairData::Rsa(keypair) if flags & RSA_SHA2_512 == 0 => {
+            if flags & RSA_SHA2_256 == 0 {
+                return Err("SHA-1 RSA signatures are not supported".to_string());
+            }
+            let private = rsa::RsaPrivateKey::try_from(keypair)
+                .map_err(|e| format!("Invalid SSH key: {}", e))?;
+            let signature = signature::Signer::try_sign(
+                &rsa::pkcs1v15::SigningKey::<sha2::Sha256>::new(private),
+                data,
+            )
+            .map_err(|e| format!("Failed to sign: {}", e))?;
+            ssh_key::Signature::new(
+                Algorithm::Rsa {
+                    hash: Some(HashAlg::Sha256),
+                },
+                signature::SignatureEncoding::to_vec(&signature),
+            )
+            .map_err(|e| format!("Failed to sign: {}", e))
+        }
+        _ => signature::Signer::try_sign(key, data).map_err(|e| format!("Failed to sign: {}", e)),
+    }
+}
+
+fn answer(request: &[u8], store: &impl KeyStore) -> Option<Vec<u8>> {
+    let mut reader = Reader(request);
+    match reader.byte()? {
+        REQUEST_IDENTITIES => {
+            let keys = loaded(store.keys());
+            let mut out = vec![IDENTITIES_ANSWER];
+            out.extend_from_slice(&(keys.len() as u32).to_be_bytes());
+            for (entry, key) in keys {
+                put_string(&mut out, &key.public_key().to_bytes().ok()?);
+                put_string(&mut out, entry.title.as_bytes());
+            }
+            Some(out)
+        }
+        SIGN_REQUEST => {
+            let blob = reader.string()?;
+            let data = reader.string()?;
+            let flags = reader.u32()?;
+            let (entry, key) = loaded(store.keys())
+                .into_iter()
+                .find(|(_, key)| key.public_key().to_bytes().ok().as_deref() == Some(blob))?;
+            let confirm = matches!(entry.kind, EntryKind::SshKey { confirm: true, .. });
+            if confirm && !store.confirm(&entry) {
+                return None;
+            }
+            let signature = sign(&key, data, flags).ok()?;
+            store.used(&entry);
+
+            let mut signature_blob = Vec::new();
+            put_string(
+                &mut signature_blob,
+                signature.algorithm().as_str().as_bytes(),
+            );
+            put_string(&mut signature_blob, signature.as_bytes());
+            let mut out = vec![SIGN_RESPONSE];
+            put_string(&mut out, &signature_blob);
+            Some(out)
+        }
+        _ => None,
+    }
+}
+
+/// Answers one agent request (without its length prefix).
+pub fn handle(request: &[u8], store: &impl KeyStore) -> Vec<u8> {
+    answer(request, store).unwrap_or_else(|| vec![FAILURE])
+}
+
+/// The listener, while the agent is on, and signatures waiting on the user.
+#[derive(Default)]
+pub struct SshAgentState {
+    stop: Mutex<Option<oneshot::Sender<()>>>,
+    pending: Mutex<HashMap<String, mpsc::Sender<bool>>>,
+}
+
+impl SshAgentState {
+    pub fn resolve(&self, request_id: &str, approved: bool) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let reply = pending
+            .remove(request_id)
+            .ok_or("Signature request has expired")?;
+        let _ = reply.send(approved);
+        Ok(())
+    }
+}
+
+struct AppKeys<'a>(&'a AppHandle);
+
+impl KeyStore for AppKeys<'_> {
+    fn keys(&self) -> Vec<Entry> {
+        self.0
+            .state::<VaultState>()
+            .lock(|_, workspace| {
+                workspace.check_session()?;
+                Ok(workspace
+                    .credentials
+                    .iter()
+                    .filter(|e| matches!(e.kind, EntryKind::SshKey { .. }))
+                    .cloned()
+                    .collect())
+            })
+            .unwrap_or_default()
+    }
+
+    fn confirm(&self, entry: &Entry) -> bool {
+        let (reply, approval) = mpsc::channel();
+        let fingerprint = match &entry.kind {
+            EntryKind::SshKey { public_key, .. } => ssh_key::PublicKey::from_openssh(public_key)
+                .map(|key| key.fingerprint(HashAlg::Sha256).to_string())
+                .unwrap_or_default(),
+            _ => return false,
+        };
+        let request = SshConfirmRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            title: entry.title.clone(),
+            fingerprint,
+        };
+        let agent = self.0.state::<SshAgentState>();
+        agent
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request.request_id.clone(), reply);
+
+        if let Some(window) = self.0.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        let _ = self.0.emit(CONFIRM_EVENT, &request);
+        let approved = approval.recv_timeout(CONFIRM_TIMEOUT).unwrap_or(false);
+        agent
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&request.request_id);
+        approved
+    }
+
+    fn used(&self, entry: &Entry) {
+        let _ = self.0.state::<VaultState>().lock(|_, workspace| {
+            workspace.refresh();
+            Ok(())
+        });
+        self.0.state::<AuditLog>().record(
+            AuditKind::Reveal,
+            Some(format!("ssh key {} used to sign", entry.id)),
+        );
+    }
+}
+
+/// Reads length-prefixed requests off one `ssh` connection and answers each in turn.
+async fn serve<S>(mut stream: S, app: AppHandle)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let Ok(len) = stream.read_u32().await else {
+            return;
+        };
+        if len == 0 || len > MAX_MESSAGE_BYTES {
+            return;
+        }
+        let mut request = vec![0u8; len as usize];
+        if stream.read_exact(&mut request).await.is_err() {
+            return;
+        }
+
+        let handle = app.clone();
+        // Confirming waits on the user, so keep it off the async workers.
+        let reply =
+            tauri::async_runtime::spawn_blocking(move || handle_request(&request, &handle)).await;
+        let Ok(reply) = reply else {
+            return;
+        };
+        let mut framed = (reply.len() as u32).to_be_bytes().to_vec();
+        framed.extend_from_slice(&reply);
+        if stream.write_all(&framed).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn handle_request(request: &[u8], app: &AppHandle) -> Vec<u8> {
+    handle(request, &AppKeys(app))
+}
+
+/// What `SSH_AUTH_SOCK` should be set to.
+pub fn socket_path() -> String {
+    #[cfg(unix)]
+    let path = crate::local_socket::runtime_path(&["latch", "ssh-agent.sock"])
+        .display()
+        .to_string();
+    #[cfg(windows)]
+    let path = crate::local_socket::pipe_name("latch-ssh-agent");
+    path
+}
+
+fn listen(app: AppHandle) -> Result<oneshot::Sender<()>, String> {
+    #[cfg(unix)]
+    let path = std::path::PathBuf::from(socket_path());
+    #[cfg(windows)]
+    let path = socket_path();
+    crate::local_socket::listen(path, move |stream| serve(stream, app.clone()))
+}
+
+/// Starts or stops the agent to match the setting.
+pub fn sync(app: &AppHandle, enabled: bool) {
+    let agent = app.state::<SshAgentState>();
+    let mut stop = agent.stop.lock().unwrap_or_else(|e| e.into_inner());
+    if enabled && stop.is_none() {
+        match listen(app.clone()) {
+            Ok(handle) => *stop = Some(handle),
+            Err(e) => eprintln!("Failed to start SSH agent: {}", e),
+        }
+    } else if !enabled {
+        if let Some(handle) = stop.take() {
+            let _ = handle.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeStore {
+        entries: Vec<Entry>,
+        approve: bool,
+        asked: Cell<usize>,
+    }
+
+    impl KeyStore for FakeStore {
+        fn keys(&self) -> Vec<Entry> {
+            self.entries.clone()
+        }
+
+        fn confirm(&self, _: &Entry) -> bool {
+            self.asked.set(self.asked.get() + 1);
+            self.approve
+        }
+
+        fn used(&self, _: &Entry) {}
+    }
+
+    fn store(confirm: bool, approve: bool) -> (FakeStore, PrivateKey) {
+        let key = PrivateKey::random(&mut rand::rngs::OsRng, Algorithm::Ed25519).unwrap();
+        let pem = key.to_openssh(LineEnding::LF).unwrap();
+        let (private, public_key) = import_key(&pem, None).unwrap();
+        let entry = Entry {
+            id: "key".to_string(),
+            title: "Deploy key".to_string(),
+            username: String::new(),
+            password: private.to_string(),
+            kind: EntryKind::SshKey {
+                public_key,
+                confirm,
+            },
+            ..Default::default()
+        };
+        let store = FakeStore {
+            entries: vec![entry],
+            approve,
+            asked: Cell::new(0),
+        };
+        (store, key)
+    }
+
+    fn sign_request(key: &PrivateKey, data: &[u8]) -> Vec<u8> {
+        let mut request = vec![SIGN_REQUEST];
+        put_string(&mut request, &key.public_key().to_bytes().unwrap());
+        put_string(&mut request, data);
+        request.extend_from_slice(&0u32.to_be_bytes());
+        request
+    }
+
+    #[test]
+    fn lists_keys_with_their_titles() {
+        let (store, key) = store(false, false);
+        let reply = handle(&[REQUEST_IDENTITIES], &store);
+
+        let mut reader = Reader(&reply);
+        assert_eq!(reader.byte(), Some(IDENTITIES_ANSWER));
+        assert_eq!(reader.u32(), Some(1));
+        assert_eq!(
+            reader.string(),
+            Some(key.public_key().to_bytes().unwrap().as_slice())
+        );
+        assert_eq!(reader.string(), Some(b"Deploy key".as_slice()));
+    }
+
+    #[test]
+    fn signs_with_the_requested_key() {
+        let (store, key) = store(false, false);
+        let reply = handle(&sign_request(&key, b"challenge"), &store);
+
+        let mut reader = Reader(&reply);
+        assert_eq!(reader.byte(), Some(SIGN_RESPONSE));
+        let mut blob = Reader(reader.string().unwrap());
+        assert_eq!(blob.string(), Some(b"ssh-ed25519".as_slice()));
+        let signature =
+            ssh_key::Signature::new(Algorithm::Ed25519, blob.string().unwrap()).unwrap();
+        assert!(signature::Verifier::verify(key.public_key(), b"challenge", &signature).is_ok());
+    }
+
+    #[test]
+    fn confirmation_gates_signing() {
+        let (denied, key) = store(true, false);
+        assert_eq!(handle(&sign_request(&key, b"x"), &denied), vec![FAILURE]);
+        assert_eq!(denied.asked.get(), 1);
+
+        let (approved, key) = store(true, true);
+        assert_eq!(
+            handle(&sign_request(&key, b"x"), &approved)[0],
+            SIGN_RESPONSE
+        );
+    }
+
+    #[test]
+    fn unknown_keys_and_requests_fail() {
+        let (store, _) = store(false, false);
+        let other = PrivateKey::random(&mut rand::rngs::OsRng, Algorithm::Ed25519).unwrap();
+        assert_eq!(handle(&sign_request(&other, b"x"), &store), vec![FAILURE]);
+        assert_eq!(handle(&[17], &store), vec![FAILURE]);
+        assert_eq!(handle(&[], &store), vec![FAILURE]);
+    }
+
+    #[test]
+    fn encrypted_keys_need_their_passphrase() {
+        let key = PrivateKey::random(&mut rand::rngs::OsRng, Algorithm::Ed25519).unwrap();
+        let pem = key
+            .encrypt(&mut rand::rngs::OsRng, "hunter2")
+            .unwrap()
+            .to_openssh(LineEnding::LF)
+            .unwrap();
+
+        assert!(import_key(&pem, None).is_err());
+        assert!(import_key(&pem, Some("wrong")).is_err());
+        let (private, _) = import_key(&pem, Some("hunter2")).unwrap();
+        assert!(!PrivateKey::from_openssh(&*private).unwrap().is_encrypted());
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted once the deferred half of startup has finished.
+pub const READY_EVENT: &str = "app://ready";
+
+/// Whether the deferred half of startup has run. The frontend checks this before
+/// using anything registered late, in case it missed `READY_EVENT`.
+#[derive(Default)]
+pub struct Readiness(AtomicBool);
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Plugins nothing on the first screen needs. Biometry probes the keychain when
+/// it loads, so it waits here too; the frontend holds its calls until ready.
+fn register_deferred_plugins(app: &AppHandle) -> tauri::Result<()> {
+    app.plugin(tauri_plugin_biometry::init())?;
+    app.plugin(tauri_plugin_updater::Builder::new().build())?;
+    app.plugin(tauri_plugin_dialog::init())?;
+    app.plugin(tauri_plugin_process::init())?;
+    app.plugin(crate::autostart::plugin())?;
+    Ok(())
+}
+
+/// Runs everything the window and the global shortcut don't need on a background
+/// thread, so `setup` returns and the first frame paints straight away.
+pub fn finish_in_background(app: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = register_deferred_plugins(&app) {
+            eprintln!("Failed to register plugins: {}", e);
+        }
+        if let Err(e) = crate::tray::setup(&app) {
+            eprintln!("Failed to setup system tray: {}", e);
+        }
+        // Hashes the whole app binary; an unlock that comes first runs it itself.
+        if app.state::<crate::integrity::BinaryIntegrity>().check() {
+            eprintln!("App binary changed since the stored key was enrolled");
+        }
+        let settings = app.state::<crate::commands::SettingsState>().get();
+        crate::browser::server::sync(&app, settings.browser_integration);
+        crate::ssh::sync(&app, settings.ssh_agent);
+        crate::git_credential::sync(&app, settings.git_credential_helper);
+        crate::autostart::sync(&app, settings.launch_at_login);
+
+        app.state::<Readiness>().0.store(true, Ordering::SeqCst);
+        let _ = app.emit(READY_EVENT, ());
+    });
+}
@@ -0,0 +1,258 @@
+//! Syncs the Vault with the provider chosen in Settings, on a timer while it is
+//! unlocked and on demand. Provider credentials are kept in memory only; the
+//! frontend hands them over again each run.
+
+use crate::commands::SettingsState;
+use crate::vault::sync::google_drive::{self, GoogleDrive};
+use crate::vault::sync::s3::{S3Config, S3};
+use crate::vault::sync::three_way::SyncConflict;
+use crate::vault::sync::{
+    self, SyncOutcome, SyncProvider, SyncProviderKind, SyncRecord, SyncReport, Synced,
+};
+use crate::vault::{storage::VaultStorage, workspace::Workspace};
+use serde::Serialize;
+use specta::Type;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, Runtime};
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SyncStatus {
+    pub provider: Option<SyncProviderKind>,
+    /// Whether the provider has a sign-in for this run of the app.
+    pub signed_in: bool,
+    pub last_synced: Option<u64>,
+    pub last_outcome: Option<SyncOutcome>,
+    /// Clashes settled by the last sync, if it merged.
+    pub conflicts: Vec<SyncConflict>,
+    pub last_error: Option<String>,
+}
+
+/// What a provider needs to sign in.
+pub enum SyncCredentials {
+    /// An OAuth access token with the `drive.appdata` scope.
+    GoogleDrive(String),
+    S3(S3Config),
+}
+
+impl SyncCredentials {
+    pub fn kind(&self) -> SyncProviderKind {
+        match self {
+            SyncCredentials::GoogleDrive(_) => SyncProviderKind::GoogleDrive,
+            SyncCredentials::S3(_) => SyncProviderKind::S3,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LastRun {
+    report: Option<SyncReport>,
+    error: Option<String>,
+}
+
+pub struct SyncState {
+    pub record_path: PathBuf,
+    credentials: Mutex<Option<SyncCredentials>>,
+    last_run: Mutex<LastRun>,
+    /// Set while a sync runs, so the timer and `sync_now` never overlap.
+    running: AtomicBool,
+}
+
+impl SyncState {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self::at(sync::record_path()?))
+    }
+
+    pub fn at(record_path: PathBuf) -> Self {
+        Self {
+            record_path,
+            credentials: Mutex::new(None),
+            last_run: Mutex::new(LastRun::default()),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_credentials(&self, credentials: Option<SyncCredentials>) {
+        if let Ok(mut current) = self.credentials.lock() {
+            *current = credentials;
+        }
+    }
+
+    fn signed_in(&self, kind: SyncProviderKind) -> bool {
+        self.credentials
+            .lock()
+            .is_ok_and(|c| c.as_ref().is_some_and(|c| c.kind() == kind))
+    }
+
+    pub fn status(&self, provider: Option<SyncProviderKind>) -> SyncStatus {
+        let (report, last_error) = self
+            .last_run
+            .lock()
+            .map(|run| (run.report.clone(), run.error.clone()))
+            .unwrap_or_default();
+        SyncStatus {
+            provider,
+            signed_in: provider.is_some_and(|kind| self.signed_in(kind)),
+            last_synced: SyncRecord::load(&self.record_path).last_synced,
+            last_outcome: report.as_ref().map(|r| r.outcome),
+            conflicts: report.map(|r| r.conflicts).unwrap_or_default(),
+            last_error,
+        }
+    }
+
+    /// Forgets the sign-in and what was last synced, so a later connection starts over.
+    pub fn reset(&self) -> Result<(), String> {
+        self.set_credentials(None);
+        if let Ok(mut last_run) = self.last_run.lock() {
+            *last_run = LastRun::default();
+        }
+        if self.record_path.exists() {
+            std::fs::remove_file(&self.record_path)
+                .map_err(|e| format!("Failed to remove sync record: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn provider(&self, kind: SyncProviderKind) -> Result<Box<dyn SyncProvider>, String> {
+        let credentials = self
+            .credentials
+            .lock()
+            .map_err(|_| "Sync is temporarily unavailable")?;
+        match (kind, credentials.as_ref()) {
+            (SyncProviderKind::GoogleDrive, Some(SyncCredentials::GoogleDrive(token))) => {
+                Ok(Box::new(GoogleDrive::new(token.clone())?))
+            }
+            (SyncProviderKind::S3, Some(SyncCredentials::S3(config))) => {
+                Ok(Box::new(S3::new(config.clone())?))
+            }
+            _ => Err("Sign in to the sync provider to sync".to_string()),
+        }
+    }
+
+    fn finish(&self, result: &Result<SyncReport, String>) {
+        if let Err(e) = result {
+            if e == google_drive::SIGN_IN_EXPIRED {
+                self.set_credentials(None);
+            }
+        }
+        if let Ok(mut last_run) = self.last_run.lock() {
+            *last_run = match result {
+                Ok(report) => LastRun {
+                    report: Some(report.clone()),
+                    error: None,
+                },
+                Err(e) => LastRun {
+                    report: None,
+                    error: Some(e.clone()),
+                },
+            };
+        }
+    }
+}
+
+fn lock_vault(
+    state_arc: &Mutex<(VaultStorage, Workspace)>,
+) -> Result<MutexGuard<'_, (VaultStorage, Workspace)>, String> {
+    state_arc
+        .lock()
+        .map_err(|_| "Vault is temporarily unavailable".to_string())
+}
+
+async fn run_sync(
+    state_arc: &Mutex<(VaultStorage, Workspace)>,
+    sync_state: &SyncState,
+    kind: SyncProviderKind,
+) -> Result<SyncReport, String> {
+    let provider = sync_state.provider(kind)?;
+    let local = {
+        let mut guard = lock_vault(state_arc)?;
+        let (ref storage, ref mut workspace) = *guard;
+        workspace.check_session()?;
+        // The duress Vault must look local-only, and a guest holds only part of it.
+        if workspace.decoy || workspace.guest.is_some() {
+            return Err("Sync is unavailable in this Session".to_string());
+        }
+        sync::read_local(storage)?
+    };
+    let local_hash = sync::hash(&local);
+
+    let mut record = SyncRecord::load(&sync_state.record_path);
+    let mut conflicts = Vec::new();
+    let (outcome, revision, synced) = match sync::sync(provider.as_ref(), &local, &record).await? {
+        Synced::UpToDate => (SyncOutcome::UpToDate, record.revision.clone(), local),
+        Synced::Uploaded { revision } => (SyncOutcome::Uploaded, Some(revision), local),
+        Synced::Downloaded(remote) => {
+            let written = {
+                let mut guard = lock_vault(state_arc)?;
+                let (ref storage, ref mut workspace) = *guard;
+                sync::apply_remote(storage, workspace, &remote.bytes, &local_hash)?
+            };
+            (SyncOutcome::Downloaded, Some(remote.revision), written)
+        }
+        Synced::Diverged(remote) => {
+            let (merged, settled) = {
+                let mut guard = lock_vault(state_arc)?;
+                let (ref storage, ref mut workspace) = *guard;
+                let base = record.base.as_deref().map(str::as_bytes);
+                sync::merge_remote(storage, workspace, &remote.bytes, base, &local_hash)?
+            };
+            conflicts = settled;
+            let revision = provider.upload(merged.clone()).await?;
+            (SyncOutcome::Merged, Some(revision), merged)
+        }
+    };
+
+    record.revision = revision;
+    record.local_hash = Some(sync::hash(&synced));
+    record.base = String::from_utf8(synced).ok();
+    record.last_synced = Some(crate::vault::unix_now());
+    record.save(&sync_state.record_path)?;
+    Ok(SyncReport { outcome, conflicts })
+}
+
+/// Syncs once with `kind` and remembers the result for `sync_status`.
+pub async fn sync_now(
+    state_arc: &Mutex<(VaultStorage, Workspace)>,
+    sync_state: &SyncState,
+    kind: SyncProviderKind,
+) -> Result<SyncReport, String> {
+    if sync_state.running.swap(true, Ordering::SeqCst) {
+        return Err("A sync is already running".to_string());
+    }
+    let result = run_sync(state_arc, sync_state, kind).await;
+    sync_state.running.store(false, Ordering::SeqCst);
+    sync_state.finish(&result);
+    result
+}
+
+/// Syncs now and every `SYNC_INTERVAL_SECS` until the Session ends, whenever a
+/// provider is chosen and signed in.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    state_arc: Arc<Mutex<(VaultStorage, Workspace)>>,
+    session_start: SystemTime,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            {
+                let Ok(guard) = state_arc.lock() else {
+                    return;
+                };
+                if guard.1.session_start != Some(session_start) || guard.1.decoy {
+                    return;
+                }
+            }
+
+            let sync_state = app.state::<SyncState>();
+            let provider = app.state::<SettingsState>().get().sync_provider;
+            if let Some(kind) = provider.filter(|kind| sync_state.signed_in(*kind)) {
+                if let Err(e) = sync_now(&state_arc, &sync_state, kind).await {
+                    eprintln!("Background sync failed: {}", e);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(sync::SYNC_INTERVAL_SECS)).await;
+        }
+    });
+}
@@ -0,0 +1,205 @@
+//! The tray icon and its menu. The icon greys out while the Vault is locked.
+//! While it's unlocked the menu can lock it, and lists the favorite Credentials
+//! so a username or password can be copied without opening the window, and how
+//! many passwords are past their expiry.
+
+use crate::breach_watch;
+use crate::commands::VaultState;
+use crate::vault::unix_now;
+use crate::vault_health::expiry;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuBuilder, MenuItem, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager};
+
+/// Menu ids of favorites are `favorite:<field>:<entry id>`.
+const FAVORITE_PREFIX: &str = "favorite:";
+
+const LOCKED_TOOLTIP: &str = "Latch Password Manager (locked)";
+
+pub fn setup(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let tray_icon = app
+        .default_window_icon()
+        .ok_or("Failed to get window icon")?;
+    let unlocked = app.state::<VaultState>().session_key().is_ok();
+
+    let _tray = TrayIconBuilder::with_id(breach_watch::TRAY_ID)
+        .menu(&build_menu(app, &[], 0, unlocked)?)
+        .tooltip(if unlocked {
+            breach_watch::TRAY_TOOLTIP
+        } else {
+            LOCKED_TOOLTIP
+        })
+        .icon(if unlocked {
+            tray_icon.clone()
+        } else {
+            greyed(tray_icon)
+        })
+        .on_menu_event(move |app, event| match event.id.0.as_str() {
+            "show" | "expired" => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            "lock" => {
+                let locked = app.state::<VaultState>().lock(|_, workspace| {
+                    workspace.lock();
+                    Ok(())
+                });
+                if let Err(e) = locked {
+                    eprintln!("Failed to lock from tray: {}", e);
+                }
+            }
+            "quit" => {
+                app.exit(0);
+            }
+            id => {
+                if let Some((field, entry_id)) = favorite_item(id) {
+                    if let Err(e) = crate::commands::clipboard::copy_field(app, entry_id, field) {
+                        eprintln!("Failed to copy from tray: {}", e);
+                    }
+                }
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+/// Swaps the icon and tooltip for the Vault's lock state. Clears the breach
+/// count from the tooltip, which belongs to the Session.
+pub fn show_lock_state(app: &AppHandle, locked: bool) {
+    let (Some(tray), Some(icon)) = (
+        app.tray_by_id(breach_watch::TRAY_ID),
+        app.default_window_icon(),
+    ) else {
+        return;
+    };
+    let (icon, tooltip) = if locked {
+        (greyed(icon), LOCKED_TOOLTIP)
+    } else {
+        (icon.clone(), breach_watch::TRAY_TOOLTIP)
+    };
+    if let Err(e) = tray
+        .set_icon(Some(icon))
+        .and_then(|_| tray.set_tooltip(Some(tooltip)))
+    {
+        eprintln!("Failed to update tray: {}", e);
+    }
+}
+
+/// Rebuilds the menu from the current favorites; a locked Vault has none. Takes
+/// the Vault lock, so call it off the thread that sent the Vault event.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(breach_watch::TRAY_ID) else {
+        return;
+    };
+    let (unlocked, favorites, expired) = app
+        .state::<VaultState>()
+        .lock(|_, workspace| {
+            let unlocked = workspace.check_session().is_ok();
+            let expired = if unlocked && workspace.guest.is_none() {
+                expiry::check_expired(&workspace.credentials, unix_now()).len()
+            } else {
+                0
+            };
+            Ok((
+                unlocked,
+                crate::vault::entries::favorites(workspace),
+                expired,
+            ))
+        })
+        .unwrap_or_default();
+    let menu = match build_menu(app, &favorites, expired, unlocked) {
+        Ok(menu) => menu,
+        Err(e) => {
+            eprintln!("Failed to build tray menu: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = tray.set_menu(Some(menu)) {
+        eprintln!("Failed to update tray: {}", e);
+    }
+}
+
+fn build_menu(
+    app: &AppHandle,
+    favorites: &[(String, String)],
+    expired: usize,
+    unlocked: bool,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut menu = MenuBuilder::new(app);
+    if expired > 0 {
+        let label = match expired {
+            1 => "1 Password to Change".to_string(),
+            n => format!("{} Passwords to Change", n),
+        };
+        let item = MenuItem::with_id(app, "expired", label, true, None::<&str>)?;
+        menu = menu.item(&item).separator();
+    }
+    if !favorites.is_empty() {
+        let heading = MenuItem::with_id(app, "favorites", "Favorites", false, None::<&str>)?;
+        menu = menu.item(&heading);
+        for (id, title) in favorites {
+            let submenu = SubmenuBuilder::new(app, title)
+                .text(favorite_id("username", id), "Copy Username")
+                .text(favorite_id("password", id), "Copy Password")
+                .build()?;
+            menu = menu.item(&submenu);
+        }
+        menu = menu.separator();
+    }
+
+    let show_item = MenuItem::with_id(app, "show", "Show Latch", true, None::<&str>)?;
+    let lock_item = MenuItem::with_id(app, "lock", "Lock Vault", unlocked, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    menu.item(&show_item)
+        .item(&lock_item)
+        .separator()
+        .item(&quit_item)
+        .build()
+}
+
+/// The icon in half-transparent greyscale.
+fn greyed(icon: &Image<'_>) -> Image<'static> {
+    Image::new_owned(greyscale(icon.rgba()), icon.width(), icon.height())
+}
+
+fn greyscale(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|p| {
+            let luma = (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000;
+            [luma as u8, luma as u8, luma as u8, p[3] / 2]
+        })
+        .collect()
+}
+
+fn favorite_id(field: &str, entry_id: &str) -> String {
+    format!("{}{}:{}", FAVORITE_PREFIX, field, entry_id)
+}
+
+/// The field and entry id of a favorite's menu item.
+fn favorite_item(id: &str) -> Option<(&str, &str)> {
+    let (field, entry_id) = id.strip_prefix(FAVORITE_PREFIX)?.split_once(':')?;
+    matches!(field, "username" | "password").then_some((field, entry_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favorite_ids_round_trip_only_for_copyable_fields() {
+        let id = favorite_id("password", "a1:b2");
+        assert_eq!(favorite_item(&id), Some(("password", "a1:b2")));
+        assert_eq!(favorite_item("favorite:notes:a1"), None);
+        assert_eq!(favorite_item("show"), None);
+    }
+
+    #[test]
+    fn greyscale_keeps_brightness_and_halves_opacity() {
+        let pixels = [255, 255, 255, 255, 255, 0, 0, 128];
+        assert_eq!(greyscale(&pixels), vec![255, 255, 255, 127, 76, 76, 76, 64]);
+    }
+}
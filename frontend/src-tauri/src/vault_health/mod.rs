@@ -1,2 +0,0 @@
-pub mod audit;
-pub mod breach_checker;
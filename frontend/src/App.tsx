@@ -1,8 +1,10 @@
 import { useState, useEffect, useRef } from 'react'
 import { listen } from '@tauri-apps/api/event'
+import { ask, message } from '@tauri-apps/plugin-dialog'
 import CommandPalette from './components/CommandPalette'
 import { useWindowAutoResize } from './hooks/useWindowAutoResize'
 import { api } from './api/client'
+import { type AssociationRequest, type ExternalChange, type PairingRequest, type SshConfirmRequest } from './api/types'
 
 type InitialMode =
   | 'auth-selector'
@@ -15,6 +17,7 @@ type InitialMode =
 function App() {
   const [hasVault, setHasVault] = useState(false)
   const [isUnlocked, setIsUnlocked] = useState(false)
+  const [ephemeral, setEphemeral] = useState(false)
   const [authMethod, setAuthMethod] = useState<string>('none')
   const [loading, setLoading] = useState(true)
   const appRef = useRef<HTMLDivElement>(null)
@@ -25,8 +28,98 @@ function App() {
   }, [])
 
   useEffect(() => {
-    const unlisten = listen('vault-locked', () => {
-      checkVaultStatus()
+    const unlisteners = ['vault://locked', 'vault://unlocked', 'session://expired'].map((event) =>
+      listen(event, () => {
+        checkVaultStatus()
+      })
+    )
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()))
+    }
+  }, [])
+
+  // The session times out 30 minutes after unlocking unless the user extends it.
+  useEffect(() => {
+    const unlisten = listen<{ seconds_remaining: number }>('session://expiring', async ({ payload }) => {
+      const minutes = Math.max(1, Math.round(payload.seconds_remaining / 60))
+      const stay = await ask(
+        `Your vault will lock in about ${minutes} minute${minutes === 1 ? '' : 's'}. Save anything you're editing, or stay unlocked.`,
+        { title: 'Session ending', kind: 'warning', okLabel: 'Stay unlocked', cancelLabel: 'Let it lock' }
+      )
+      if (stay) {
+        await api.extendSession().catch((error) => console.error('Failed to extend session:', error))
+      }
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  // A sync tool replaced the vault file while it was unlocked.
+  useEffect(() => {
+    const unlisten = listen<ExternalChange>('vault://changed-externally', async ({ payload }) => {
+      if (payload.kind === 'reloaded') {
+        if (payload.conflicts.length > 0) {
+          const titles = payload.conflicts.map((c) => c.title).join(', ')
+          await message(`The vault was changed on another device and reloaded. Edited on both: ${titles}.`, {
+            title: 'Vault reloaded',
+            kind: 'info',
+          })
+        }
+        return
+      }
+      const keep = await ask(
+        "The vault file was replaced by a copy this session can't open, probably re-keyed on another device.\n\nKeep this copy (the other is saved beside it), or lock and unlock the other copy?",
+        { title: 'Vault changed on disk', kind: 'warning', okLabel: 'Keep this copy', cancelLabel: 'Lock' }
+      )
+      const settle = keep ? api.keepThisVaultCopy() : api.lockVault()
+      await settle.catch((error) => console.error('Failed to settle the changed vault:', error))
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  useEffect(() => {
+    const unlisten = listen<AssociationRequest>('browser://association-requested', async ({ payload }) => {
+      const approved = await ask(
+        `A KeePassXC-Browser extension wants to fill logins from Latch.\n\nKey fingerprint: ${payload.fingerprint}\n\nOnly allow this if you just clicked Connect in the extension.`,
+        { title: 'Connect browser extension?', kind: 'warning' }
+      )
+      await api.resolveBrowserAssociation(payload.request_id, approved).catch((error) =>
+        console.error('Failed to answer browser extension:', error)
+      )
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  useEffect(() => {
+    const unlisten = listen<SshConfirmRequest>('ssh://confirm-requested', async ({ payload }) => {
+      const approved = await ask(
+        `Something wants to sign with the SSH key "${payload.title}".\n\n${payload.fingerprint}`,
+        { title: 'Use SSH key?', kind: 'warning' }
+      )
+      await api.resolveSshConfirmation(payload.request_id, approved).catch((error) =>
+        console.error('Failed to answer SSH agent:', error)
+      )
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  useEffect(() => {
+    const unlisten = listen<PairingRequest>('lan-sync://pairing-requested', async ({ payload }) => {
+      const approved = await ask(
+        `"${payload.name}" wants to sync with this device over the local network.\n\nCode: ${payload.code}\n\nOnly pair if the other device shows the same code.`,
+        { title: 'Pair with device?', kind: 'warning' }
+      )
+      const answer = approved
+        ? api.confirmLanPairing(payload.fingerprint)
+        : api.rejectLanPairing(payload.fingerprint)
+      await answer.catch((error) => console.error('Failed to answer pairing request:', error))
     })
     return () => {
       unlisten.then((fn) => fn())
@@ -39,6 +132,7 @@ function App() {
       const status = await api.vaultStatus()
       setHasVault(status.has_vault)
       setIsUnlocked(status.is_unlocked)
+      setEphemeral(status.ephemeral)
 
       if (status.has_vault && !status.is_unlocked) {
         const method = await api.getAuthMethod()
@@ -71,6 +165,11 @@ function App() {
 
   return (
     <div className="w-full p-0 bg-theme-bg relative z-1 overflow-y-auto overflow-x-hidden" ref={appRef}>
+      {ephemeral && (
+        <div className="px-4 py-1 text-xs text-center text-theme-accent uppercase tracking-wider">
+          Ephemeral vault · fake data · password latch-demo
+        </div>
+      )}
       <CommandPalette initialMode={initialMode} />
     </div>
   )
@@ -1,150 +1,585 @@
-import { invoke } from '@tauri-apps/api/core'
+import { invoke as tauriInvoke, type InvokeArgs } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { z } from 'zod'
+import { toLatchError } from './errors'
 import {
-  SecretResponseSchema,
-  ResponseSchema,
-  AuthMethodResponseSchema,
-  AddEntryResponseSchema,
-  FullEntryResponseSchema,
-  SearchEntriesResponseSchema,
-  VaultStatusResponseSchema,
+  ArchivedPreviewSchema,
+  CredentialPreviewSchema,
+  CredentialSchema,
+  DeviceCodeSchema,
+  EntryActionSchema,
+  EntryChangeSchema,
+  ConfirmationTokenSchema,
+  GuestSessionSchema,
+  MergeReportSchema,
+  OAuthProviderSchema,
+  OperationSchema,
+  RepairReportSchema,
+  SafetyBackupSchema,
+  SessionInfoSchema,
+  SyncReportSchema,
+  AliasSchema,
+  CapabilitySetSchema,
+  GeneratedPasswordSchema,
+  LanPeerSchema,
+  PairedPeerSchema,
+  PairingRequestSchema,
+  SyncStatusSchema,
+  guardedSchema,
   VaultHealthReportSchema,
+  SavedEntrySchema,
+  type SavedEntry,
+  BreachDatabaseProgressSchema,
+  type BreachDatabaseProgress,
+  VaultStatusSchema,
+  type AlertChannel,
+  type ApiKeyField,
+  type ArchivedPreview,
+  type AuthPreferences,
+  type BankField,
+  type Alias,
+  type AliasAccount,
+  type BrowserAssociation,
   type Credential,
   type CredentialPreview,
+  type ConfirmOperation,
+  type DeviceCode,
+  type EntryAction,
+  type EntryIcon,
+  type EntryChange,
+  type GeneratedPassword,
+  type GuestSession,
+  type MergeReport,
+  type MergeStrategy,
+  type Operation,
+  type RepairReport,
+  type OAuthProvider,
+  type PasswordContext,
   type PasswordOptions,
+  type ReferenceSource,
+  type S3Config,
+  type ServerField,
+  type ServerProtocol,
+  type SessionInfo,
   type StrengthReport,
+  type SyncReport,
+  type LanPeer,
+  type PairedPeer,
+  type PairingRequest,
+  type SyncStatus,
   type VaultHealthReport,
 } from './types'
 
-function parse<T>(result: unknown, schema: { parse: (v: unknown) => T }): T {
-  return schema.parse(JSON.parse(result as string))
+// Commands return typed values. Debug builds regenerate their TypeScript
+// bindings in ./bindings.ts from the Rust signatures.
+async function invoke<T = void>(cmd: string, args?: InvokeArgs): Promise<T> {
+  try {
+    const token = await capabilityFor(cmd)
+    const options = token ? { headers: { 'latch-capability': token } } : undefined
+    return await tauriInvoke<T>(cmd, args, options)
+  } catch (err) {
+    throw toLatchError(err)
+  }
+}
+
+// Commands that read, reveal, change or export credentials present a token the
+// backend hands out once per session. Kept here, out of reach of other scripts.
+interface Capabilities {
+  tokens: Map<string, string>
+  renewal: string
+  expiresAt: number
+}
+let capabilities: Capabilities | null = null
+
+async function claimCapabilities(renewal?: string): Promise<void> {
+  const set = CapabilitySetSchema.parse(await tauriInvoke('claim_capabilities', { renewal: renewal ?? null }))
+  const tokens = new Map<string, string>()
+  for (const capability of set.capabilities) {
+    for (const command of capability.commands) tokens.set(command, capability.token)
+  }
+  capabilities = { tokens, renewal: set.renewal, expiresAt: Date.now() + set.expires_in_secs * 1000 }
+}
+
+async function capabilityFor(cmd: string): Promise<string | undefined> {
+  if (!capabilities?.tokens.has(cmd)) return undefined
+  // Renew a little early so a slow call doesn't arrive after expiry.
+  if (Date.now() > capabilities.expiresAt - 30_000) {
+    await claimCapabilities(capabilities.renewal)
+  }
+  return capabilities.tokens.get(cmd)
+}
+
+async function startSession(unlock: Promise<unknown>): Promise<void> {
+  await unlock
+  capabilities = null
+  try {
+    await claimCapabilities()
+  } catch (err) {
+    throw toLatchError(err)
+  }
 }
 
 export const api = {
+  // The updater, dialog and process plugins register after the first paint.
+  async whenReady(): Promise<void> {
+    let markReady = () => {}
+    const ready = new Promise<void>((resolve) => {
+      markReady = resolve
+    })
+    const unlisten = await listen('app://ready', () => markReady())
+    try {
+      if (!(await invoke<boolean>('is_app_ready'))) {
+        await ready
+      }
+    } finally {
+      unlisten()
+    }
+  },
+
   // Vault lifecycle
   async provisionPassword(password: string): Promise<void> {
-    const result = await invoke('init_vault', { password })
-    parse(result, ResponseSchema)
+    await startSession(invoke('init_vault', { password }))
   },
 
-  async provisionOAuth(idToken: string): Promise<void> {
-    const result = await invoke('init_vault_oauth', { idToken })
-    parse(result, ResponseSchema)
+  // `token` is whatever the provider's sign-in handed back: an ID token, or
+  // GitHub's access token.
+  async provisionOAuth(provider: OAuthProvider, token: string): Promise<void> {
+    await startSession(invoke('init_vault_oauth', { provider, token }))
   },
 
   async provisionWithKey(keyHex: string, kdf: string): Promise<void> {
-    const result = await invoke('init_vault_with_key', { keyHex, kdf })
-    parse(result, ResponseSchema)
+    await startSession(invoke('init_vault_with_key', { keyHex, kdf }))
   },
 
   async accessPassword(password: string): Promise<void> {
-    const result = await invoke('unlock_vault', { password })
-    parse(result, ResponseSchema)
+    await startSession(invoke('unlock_vault', { password }))
   },
 
-  async accessOAuth(idToken: string): Promise<void> {
-    const result = await invoke('unlock_vault_oauth', { idToken })
-    parse(result, ResponseSchema)
+  async accessOAuth(token: string): Promise<void> {
+    await startSession(invoke('unlock_vault_oauth', { token }))
+  },
+
+  // GitHub sign-in runs as a device flow: show the code, then poll until approved.
+  async startGithubSignIn(): Promise<DeviceCode> {
+    return DeviceCodeSchema.parse(await invoke('start_github_sign_in'))
+  },
+
+  async pollGithubSignIn(deviceCode: string): Promise<string | null> {
+    return z.string().nullable().parse(await invoke('poll_github_sign_in', { deviceCode }))
   },
 
   async accessKey(keyHex: string): Promise<void> {
-    const result = await invoke('unlock_vault_with_key', { keyHex })
-    parse(result, ResponseSchema)
+    await startSession(invoke('unlock_vault_with_key', { keyHex }))
+  },
+
+  async reenrollKey(keyHex: string, newKeyHex: string): Promise<string | null> {
+    return SafetyBackupSchema.parse(await invoke('reenroll_vault_key', { keyHex, newKeyHex })).safety_backup
+  },
+
+  async checkBinaryIntegrity(): Promise<boolean> {
+    return z.boolean().parse(await invoke('check_binary_integrity'))
+  },
+
+  // Saves this session's copy over a vault file replaced outside Latch that it
+  // can't open. Resolves to where the other file was copied.
+  async keepThisVaultCopy(): Promise<string> {
+    return z.string().parse(await invoke('keep_this_vault_copy'))
   },
 
   async lockVault(): Promise<void> {
     await invoke('lock_vault')
+    capabilities = null
+  },
+
+  // Irreversible operations take a token from requestConfirmation
+  async requestConfirmation(operation: ConfirmOperation): Promise<string> {
+    return ConfirmationTokenSchema.parse(await invoke('request_confirmation', { operation })).token
+  },
+
+  async deleteVault(confirmationToken: string): Promise<void> {
+    await invoke('delete_vault', { confirmationToken })
   },
 
-  async vaultStatus(): Promise<{ has_vault: boolean; is_unlocked: boolean }> {
-    const result = await invoke('vault_status')
-    const parsed = VaultStatusResponseSchema.parse(JSON.parse(result as string))
-    return { has_vault: parsed.has_vault, is_unlocked: parsed.is_unlocked }
+  async removeDuressVault(confirmationToken: string): Promise<void> {
+    await invoke('remove_duress_vault', { confirmationToken })
+  },
+
+  async vaultStatus(): Promise<{ has_vault: boolean; is_unlocked: boolean; ephemeral: boolean }> {
+    return VaultStatusSchema.parse(await invoke('vault_status'))
+  },
+
+  async attemptVaultRepair(): Promise<RepairReport> {
+    return RepairReportSchema.parse(await invoke('attempt_vault_repair'))
   },
 
   async getAuthMethod(): Promise<string> {
-    const result = await invoke('get_vault_auth_method')
-    return AuthMethodResponseSchema.parse(JSON.parse(result as string)).auth_method
+    return z.string().parse(await invoke('get_vault_auth_method'))
+  },
+
+  // Re-enters the vault's credential before an action the re-auth policy guards;
+  // resolves to how many seconds the confirmation lasts.
+  async reauthenticate(credential: string): Promise<number> {
+    return z.number().parse(await invoke('reauthenticate', { credential }))
+  },
+
+  async getIdentityProvider(): Promise<OAuthProvider | null> {
+    return OAuthProviderSchema.nullable().parse(await invoke('get_vault_identity_provider'))
   },
 
   // Credentials
   async searchEntries(query: string): Promise<CredentialPreview[]> {
-    const result = await invoke('search_entries', { query })
-    return SearchEntriesResponseSchema.parse(JSON.parse(result as string)).entries
+    return z.array(CredentialPreviewSchema).parse(await invoke('search_entries', { query }))
   },
 
-  async copyField(entryId: string, field: 'password' | 'username'): Promise<string> {
-    const result = await invoke('request_secret', { entryId, field })
-    const parsed = SecretResponseSchema.parse(JSON.parse(result as string))
-    if (parsed.status === 'success') return parsed.value
-    throw new Error(parsed.message)
+  async searchArchive(query: string): Promise<ArchivedPreview[]> {
+    return z.array(ArchivedPreviewSchema).parse(await invoke('search_archive', { query }))
+  },
+
+  async restoreArchivedEntry(entryId: string): Promise<CredentialPreview> {
+    return CredentialPreviewSchema.parse(await invoke('restore_archived_entry', { entryId }))
+  },
+
+  async copyField(
+    entryId: string,
+    field: 'password' | 'username' | BankField | ApiKeyField | ServerField,
+  ): Promise<string> {
+    return z.string().parse(await invoke('request_secret', { entryId, field }))
+  },
+
+  // Copies through the OS clipboard and clears it from the backend. Resolves to the
+  // clearing delay in seconds, 0 if clearing is off.
+  async copySecretToClipboard(entryId: string, field: 'password' | 'username'): Promise<number> {
+    return invoke<number>('copy_secret_to_clipboard', { entryId, field })
+  },
+
+  async getEntryActions(entryId: string): Promise<EntryAction[]> {
+    return z.array(EntryActionSchema).parse(await invoke('get_entry_actions', { entryId }))
   },
 
   async getFullEntry(entryId: string): Promise<Credential> {
-    const result = await invoke('get_full_entry', { entryId })
-    return FullEntryResponseSchema.parse(JSON.parse(result as string)).entry
+    return CredentialSchema.parse(await invoke('get_full_entry', { entryId }))
   },
 
   async addEntry(entry: {
     title: string; username: string; password: string;
-    url?: string; iconUrl?: string;
+    url?: string; iconUrl?: string; revealCooldownSecs?: number;
+    autotypeSequence?: string;
+  }): Promise<SavedEntry> {
+    return SavedEntrySchema.parse(await invoke('add_entry', entry))
+  },
+
+  async addBankAccount(account: {
+    title: string; accountNumber: string; routingCode?: string;
+    swift?: string; pin?: string;
   }): Promise<string> {
-    const result = await invoke('add_entry', entry)
-    return AddEntryResponseSchema.parse(JSON.parse(result as string)).id
+    return z.string().parse(await invoke('add_bank_account', account))
+  },
+
+  /** `expiresAt` is Unix time in seconds. */
+  async addApiKey(apiKey: {
+    title: string; keyId: string; secret: string; scopes: string[];
+    expiresAt?: number; url?: string;
+  }): Promise<string> {
+    return z.string().parse(await invoke('add_api_key', apiKey))
+  },
+
+  /** Logs in with `password`, or with the SSH key Credential `sshKeyId`. */
+  async addServer(server: {
+    title: string; hostname: string; port?: number; protocol: ServerProtocol;
+    username: string; password?: string; sshKeyId?: string;
+  }): Promise<string> {
+    return z.string().parse(await invoke('add_server', server))
+  },
+
+  async addSecretReference(reference: {
+    title: string; source: ReferenceSource; location: string;
+  }): Promise<string> {
+    return z.string().parse(await invoke('add_secret_reference', reference))
   },
 
   async updateEntry(entry: {
     id: string; title: string; username: string;
     password: string; url?: string; iconUrl?: string;
-  }): Promise<void> {
-    const result = await invoke('update_entry', entry)
-    parse(result, ResponseSchema)
+    revealCooldownSecs?: number; autotypeSequence?: string;
+  }): Promise<SavedEntry> {
+    return SavedEntrySchema.parse(await invoke('update_entry', entry))
   },
 
   async deleteEntry(entryId: string): Promise<void> {
-    const result = await invoke('delete_entry', { entryId })
-    parse(result, ResponseSchema)
+    await invoke('delete_entry', { entryId })
+  },
+
+  async deleteEntries(entryIds: string[]): Promise<{ deleted: number; safetyBackup: string | null }> {
+    const result = guardedSchema(z.number()).parse(await invoke('delete_entries', { entryIds }))
+    return { deleted: result.value, safetyBackup: result.safety_backup }
+  },
+
+  async undoLastOperation(): Promise<Operation | null> {
+    return OperationSchema.nullable().parse(await invoke('undo_last_operation'))
+  },
+
+  async redo(): Promise<Operation | null> {
+    return OperationSchema.nullable().parse(await invoke('redo'))
+  },
+
+  async mergeVault(
+    path: string,
+    credential: string,
+    strategy: MergeStrategy,
+  ): Promise<{ report: MergeReport; safetyBackup: string | null }> {
+    const result = guardedSchema(MergeReportSchema).parse(await invoke('merge_vault', { path, credential, strategy }))
+    return { report: result.value, safetyBackup: result.safety_backup }
+  },
+
+  async addCanaryEntry(service: string, emailDomain?: string): Promise<Credential> {
+    return CredentialSchema.parse(await invoke('add_canary_entry', { service, emailDomain: emailDomain ?? null }))
+  },
+
+  async setEntryIcon(entryId: string, icon: EntryIcon | null): Promise<void> {
+    await invoke('set_entry_icon', { entryId, icon })
+  },
+
+  async setEntryFolder(entryId: string, folder: string | null): Promise<void> {
+    await invoke('set_entry_folder', { entryId, folder })
+  },
+
+  async setBreachWatch(entryId: string, channel: AlertChannel | null): Promise<void> {
+    await invoke('set_breach_watch', { entryId, channel })
+  },
+
+  async setPasswordExpiry(entryId: string, intervalDays: number | null): Promise<void> {
+    await invoke('set_password_expiry', { entryId, intervalDays })
+  },
+
+  async snoozePasswordExpiry(entryId: string, days: number): Promise<void> {
+    await invoke('snooze_password_expiry', { entryId, days })
+  },
+
+  async markPasswordRotated(entryId: string): Promise<void> {
+    await invoke('mark_password_rotated', { entryId })
+  },
+
+  async setEntryFavorite(entryId: string, favorite: boolean): Promise<void> {
+    await invoke('set_entry_favorite', { entryId, favorite })
+  },
+
+  // Shares parentId's password with this entry; null unlinks it.
+  async linkEntry(entryId: string, parentId: string | null): Promise<void> {
+    await invoke('link_entry', { entryId, parentId })
+  },
+
+  async refreshIcon(entryId: string): Promise<string | null> {
+    return invoke<string | null>('refresh_icon', { entryId })
+  },
+
+  async setPasswordPolicy(entryId: string, policy: PasswordOptions | null): Promise<void> {
+    await invoke('set_password_policy', { entryId, policy })
+  },
+
+  async syncStatus(): Promise<SyncStatus> {
+    return SyncStatusSchema.parse(await invoke('sync_status'))
+  },
+
+  async connectGoogleDrive(accessToken: string): Promise<SyncStatus> {
+    return SyncStatusSchema.parse(await invoke('connect_google_drive', { accessToken }))
+  },
+
+  async connectS3(config: S3Config): Promise<SyncStatus> {
+    return SyncStatusSchema.parse(await invoke('connect_s3', { config }))
+  },
+
+  async disconnectSync(): Promise<void> {
+    await invoke('disconnect_sync')
+  },
+
+  async syncNow(): Promise<SyncReport> {
+    return SyncReportSchema.parse(await invoke('sync_now'))
+  },
+
+  async listLanPeers(): Promise<PairedPeer[]> {
+    return z.array(PairedPeerSchema).parse(await invoke('list_lan_peers'))
+  },
+
+  async discoverLanPeers(): Promise<LanPeer[]> {
+    return z.array(LanPeerSchema).parse(await invoke('discover_lan_peers'))
+  },
+
+  async openLanPairing(): Promise<number> {
+    return invoke<number>('open_lan_pairing')
+  },
+
+  async pairLanPeer(address: string): Promise<PairingRequest> {
+    return PairingRequestSchema.parse(await invoke('pair_lan_peer', { address }))
+  },
+
+  async confirmLanPairing(fingerprint: string): Promise<PairedPeer> {
+    return PairedPeerSchema.parse(await invoke('confirm_lan_pairing', { fingerprint }))
+  },
+
+  async rejectLanPairing(fingerprint: string): Promise<void> {
+    await invoke('reject_lan_pairing', { fingerprint })
+  },
+
+  async forgetLanPeer(fingerprint: string): Promise<void> {
+    await invoke('forget_lan_peer', { fingerprint })
+  },
+
+  async syncWithLanPeer(fingerprint: string, address: string): Promise<SyncReport> {
+    return SyncReportSchema.parse(await invoke('sync_with_lan_peer', { fingerprint, address }))
+  },
+
+  async connectAliasService(account: AliasAccount): Promise<void> {
+    await invoke('connect_alias_service', { account })
+  },
+
+  async disconnectAliasService(): Promise<void> {
+    await invoke('disconnect_alias_service')
+  },
+
+  async createAlias(forSite?: string): Promise<Alias> {
+    return AliasSchema.parse(await invoke('create_alias', { forSite: forSite ?? null }))
+  },
+
+  async listAliases(): Promise<Alias[]> {
+    return z.array(AliasSchema).parse(await invoke('list_aliases'))
+  },
+
+  async disableAlias(id: string): Promise<void> {
+    await invoke('disable_alias', { id })
+  },
+
+  // Newest first; `session` is when the editing session was unlocked.
+  async getEntryHistory(entryId: string): Promise<EntryChange[]> {
+    return z.array(EntryChangeSchema).parse(await invoke('get_entry_history', { entryId }))
+  },
+
+  async listFolders(): Promise<string[]> {
+    return z.array(z.string()).parse(await invoke('list_folders'))
+  },
+
+  // Guest sessions
+  async startGuestSession(folders: string[], durationSecs: number): Promise<GuestSession> {
+    return GuestSessionSchema.parse(await invoke('start_guest_session', { folders, durationSecs }))
+  },
+
+  async getGuestSession(): Promise<GuestSession | null> {
+    return GuestSessionSchema.nullable().parse(await invoke('get_guest_session'))
+  },
+
+  async endGuestSession(): Promise<void> {
+    await invoke('end_guest_session')
   },
 
   // Password generator
   async generatePassword(options: PasswordOptions): Promise<string> {
-    const result = await invoke('generate_password', { options })
-    return JSON.parse(result as string).password
+    return invoke<string>('generate_password', { options })
+  },
+
+  async generatePasswordForEntry(entryId: string): Promise<string> {
+    return invoke<string>('generate_password_for_entry', { entryId })
+  },
+
+  async getGenerationHistory(): Promise<GeneratedPassword[]> {
+    return z.array(GeneratedPasswordSchema).parse(await invoke('get_generation_history'))
+  },
+
+  async clearGenerationHistory(): Promise<void> {
+    await invoke('clear_generation_history')
+  },
+
+  async passwordEntropy(options: PasswordOptions): Promise<number> {
+    return invoke<number>('password_entropy', { options })
   },
 
-  async analyzePassword(password: string): Promise<StrengthReport> {
-    const result = await invoke('analyze_password_strength', { password })
-    return JSON.parse(result as string).report as StrengthReport
+  async analyzePassword(password: string, context?: PasswordContext): Promise<StrengthReport> {
+    return invoke<StrengthReport>('analyze_password_strength', { password, context: context ?? null })
   },
 
   // Vault health
   async checkVaultHealth(): Promise<VaultHealthReport> {
-    const result = await invoke('check_vault_health')
-    return VaultHealthReportSchema.parse(JSON.parse(result as string).report)
+    return VaultHealthReportSchema.parse(await invoke('check_vault_health'))
+  },
+
+  async connectBreachMonitor(apiKey: string): Promise<void> {
+    await invoke('connect_breach_monitor', { apiKey })
+  },
+
+  async disconnectBreachMonitor(): Promise<void> {
+    await invoke('disconnect_breach_monitor')
+  },
+
+  async fixUrlSecret(entryId: string): Promise<void> {
+    await invoke('fix_url_secret', { entryId })
+  },
+
+  // Offline breach checks. Download progress arrives as `breach-database://progress`.
+  async getBreachDatabaseStatus(): Promise<BreachDatabaseProgress> {
+    return BreachDatabaseProgressSchema.parse(await invoke('get_breach_database_status'))
+  },
+
+  async downloadBreachDatabase(): Promise<void> {
+    await invoke('download_breach_database')
+  },
+
+  async cancelBreachDatabaseDownload(): Promise<void> {
+    await invoke('cancel_breach_database_download')
+  },
+
+  async deleteBreachDatabase(): Promise<void> {
+    await invoke('delete_breach_database')
   },
 
   // Auth preferences
-  async getAuthPreferences(): Promise<{
-    auth_method: string; session_valid: boolean; session_remaining_seconds: number
-  }> {
-    const result = await invoke('get_auth_preferences')
-    return JSON.parse(result as string)
+  async getAuthPreferences(): Promise<AuthPreferences> {
+    return invoke<AuthPreferences>('get_auth_preferences')
   },
 
-  // Re-encryption & migration
-  async reencryptVault(newKeyHex: string, newKdf: string, newSalt: string): Promise<void> {
-    const result = await invoke('reencrypt_vault', { newKeyHex, newKdf, newSalt })
-    parse(result, ResponseSchema)
+  async getSessionInfo(): Promise<SessionInfo> {
+    return SessionInfoSchema.parse(await invoke('get_session_info'))
   },
 
-  async reencryptVaultToOAuth(idToken: string): Promise<void> {
-    const result = await invoke('reencrypt_vault_to_oauth', { idToken })
-    parse(result, ResponseSchema)
+  // Restarts the session timeout. Only for explicit user activity.
+  async extendSession(): Promise<SessionInfo> {
+    return SessionInfoSchema.parse(await invoke('extend_session'))
   },
 
-  async migrateToOAuth(password: string, idToken: string): Promise<void> {
-    const result = await invoke('migrate_to_oauth', { password, idToken })
-    parse(result, ResponseSchema)
+  // Re-encryption & migration. Each resolves to the id of the safety backup taken first.
+  async reencryptVault(newKeyHex: string, newKdf: string): Promise<string | null> {
+    return SafetyBackupSchema.parse(await invoke('reencrypt_vault', { newKeyHex, newKdf })).safety_backup
+  },
+
+  async reencryptVaultToOAuth(provider: OAuthProvider, token: string): Promise<string | null> {
+    return SafetyBackupSchema.parse(await invoke('reencrypt_vault_to_oauth', { provider, token })).safety_backup
+  },
+
+  async migrateToOAuth(password: string, provider: OAuthProvider, token: string): Promise<string | null> {
+    return SafetyBackupSchema.parse(await invoke('migrate_to_oauth', { password, provider, token })).safety_backup
+  },
+  // KeePassXC-Browser extensions
+  async resolveBrowserAssociation(requestId: string, approved: boolean): Promise<void> {
+    await invoke('resolve_browser_association', { requestId, approved })
   },
-}
 
+  async listBrowserAssociations(): Promise<BrowserAssociation[]> {
+    return invoke<BrowserAssociation[]>('list_browser_associations')
+  },
+
+  async removeBrowserAssociation(id: string): Promise<void> {
+    await invoke('remove_browser_association', { id })
+  },
+
+  // SSH agent
+  async addSshKey(key: {
+    title: string; privateKey: string; passphrase?: string; confirm?: boolean;
+  }): Promise<string> {
+    return z.string().parse(await invoke('add_ssh_key', key))
+  },
+
+  async resolveSshConfirmation(requestId: string, approved: boolean): Promise<void> {
+    await invoke('resolve_ssh_confirmation', { requestId, approved })
+  },
 
+  async getSshAgentSocket(): Promise<string> {
+    return invoke<string>('get_ssh_agent_socket')
+  },
+}
@@ -0,0 +1,44 @@
+export type LatchErrorKind =
+  | 'VaultLocked'
+  | 'SessionExpired'
+  | 'DecryptionFailed'
+  | 'LockedOut'
+  | 'ReauthRequired'
+  | 'Forbidden'
+  | 'RateLimited'
+  | 'NotFound'
+  | 'InvalidInput'
+  | 'Unavailable'
+  | 'Io'
+  | 'Internal'
+
+/** Error thrown by every backend command, mirroring the Rust `LatchError`. */
+export class LatchError extends Error {
+  readonly kind: LatchErrorKind
+  readonly retryable: boolean
+
+  constructor(kind: LatchErrorKind, message: string, retryable: boolean) {
+    super(message)
+    this.name = 'LatchError'
+    this.kind = kind
+    this.retryable = retryable
+  }
+
+  // Keeps `String(err)` call sites showing just the message.
+  toString(): string {
+    return this.message
+  }
+}
+
+export function toLatchError(err: unknown): LatchError {
+  if (err instanceof LatchError) return err
+  if (err && typeof err === 'object' && 'kind' in err && 'message' in err) {
+    const { kind, message, retryable } = err as {
+      kind: LatchErrorKind
+      message: string
+      retryable?: boolean
+    }
+    return new LatchError(kind, message, Boolean(retryable))
+  }
+  return new LatchError('Internal', err instanceof Error ? err.message : String(err), false)
+}
@@ -2,6 +2,13 @@ import { z } from 'zod'
 
 const OptionalStringSchema = z.string().nullable().optional()
 
+/** An emoji or `#rrggbb` swatch shown instead of the site icon. */
+export const EntryIconSchema = z.discriminatedUnion('type', [
+  z.object({ type: z.literal('emoji'), value: z.string() }),
+  z.object({ type: z.literal('color'), value: z.string() }),
+])
+export type EntryIcon = z.infer<typeof EntryIconSchema>
+
 export const CredentialSchema = z.object({
   id: z.string(),
   title: z.string(),
@@ -9,57 +16,291 @@ export const CredentialSchema = z.object({
   password: z.string(),
   url: OptionalStringSchema,
   icon_url: OptionalStringSchema,
+  linked_to: z.string().nullable().optional(),
+  icon: EntryIconSchema.nullable().optional(),
 })
 export type Credential = z.infer<typeof CredentialSchema>
 
-export const CredentialPreviewSchema = CredentialSchema.omit({ password: true })
-export type CredentialPreview = z.infer<typeof CredentialPreviewSchema>
+export const MatchedFieldSchema = z.enum(['title', 'username', 'url'])
+export type MatchedField = z.infer<typeof MatchedFieldSchema>
 
-export const SuccessResponseSchema = z.object({
-  status: z.literal('success'),
-  message: z.string().optional(),
+export const CredentialPreviewSchema = CredentialSchema.omit({ password: true }).extend({
+  matched: MatchedFieldSchema.nullable().optional(),
+  favorite: z.boolean().optional(),
 })
+export type CredentialPreview = z.infer<typeof CredentialPreviewSchema>
 
-export const ErrorResponseSchema = z.object({
-  status: z.literal('error'),
-  message: z.string(),
+export const ArchivedPreviewSchema = z.object({
+  entry: CredentialPreviewSchema,
+  archived_at: z.number(),
 })
+export type ArchivedPreview = z.infer<typeof ArchivedPreviewSchema>
 
-export const ResponseSchema = z.discriminatedUnion('status', [
-  SuccessResponseSchema,
-  ErrorResponseSchema,
+export const EntryActionSchema = z.discriminatedUnion('kind', [
+  z.object({ kind: z.literal('copy_password') }),
+  z.object({ kind: z.literal('copy_username') }),
+  z.object({ kind: z.literal('open_url'), url: z.string() }),
 ])
+export type EntryAction = z.infer<typeof EntryActionSchema>
 
-export const SecretResponseSchema = z.discriminatedUnion('status', [
-  z.object({ status: z.literal('success'), value: z.string() }),
-  z.object({ status: z.literal('error'), message: z.string() }),
-])
+export const VaultStatusSchema = z.object({
+  has_vault: z.boolean(),
+  is_unlocked: z.boolean(),
+  ephemeral: z.boolean(),
+})
 
-export const AuthMethodResponseSchema = z.object({
-  status: z.string(),
-  auth_method: z.string(),
+/** What `attempt_vault_repair` recovered a damaged vault from, and what was lost. */
+export const RepairReportSchema = z.object({
+  source: z.discriminatedUnion('kind', [
+    z.object({ kind: z.literal('unfinished_save') }),
+    z.object({ kind: z.literal('damaged_vault') }),
+    z.object({ kind: z.literal('backup'), id: z.string(), created_at: z.number() }),
+  ]),
+  salvaged: z.array(z.string()),
+  lost: z.array(z.string()),
+  damaged_copy: z.string(),
 })
+export type RepairReport = z.infer<typeof RepairReportSchema>
 
-export const VaultStatusResponseSchema = z.object({
-  status: z.literal('success'),
-  has_vault: z.boolean(),
-  is_unlocked: z.boolean(),
+/** Short-lived tokens, one per scope, that scoped commands must present. */
+export const CapabilitySetSchema = z.object({
+  capabilities: z.array(
+    z.object({
+      scope: z.enum(['read_preview', 'reveal_secret', 'mutate', 'export']),
+      token: z.string(),
+      commands: z.array(z.string()),
+    })
+  ),
+  renewal: z.string(),
+  expires_in_secs: z.number(),
 })
+export type CapabilitySet = z.infer<typeof CapabilitySetSchema>
+
+export const OAuthProviderSchema = z.enum(['google', 'github', 'apple'])
+export type OAuthProvider = z.infer<typeof OAuthProviderSchema>
 
-export const SearchEntriesResponseSchema = z.object({
-  status: z.literal('success'),
-  entries: z.array(CredentialPreviewSchema),
+export const DeviceCodeSchema = z.object({
+  device_code: z.string(),
+  user_code: z.string(),
+  verification_uri: z.string(),
+  expires_in: z.number(),
+  interval: z.number(),
 })
+export type DeviceCode = z.infer<typeof DeviceCodeSchema>
 
-export const FullEntryResponseSchema = z.object({
-  status: z.literal('success'),
-  entry: CredentialSchema,
+export const GuestSessionSchema = z.object({
+  folders: z.array(z.string()),
+  remaining_seconds: z.number(),
+  entries: z.number(),
 })
+export type GuestSession = z.infer<typeof GuestSessionSchema>
+
+/** Result of a destructive command, with the backup taken before it ran. */
+export const guardedSchema = <T extends z.ZodTypeAny>(value: T) =>
+  z.object({ value, safety_backup: z.string().nullable() })
+export const SafetyBackupSchema = guardedSchema(z.null())
+
+/** Where breach alerts for a watched Credential go. */
+export type AlertChannel = 'notification' | 'tray_badge' | 'hook'
+
+/** A change to the Credentials that can be undone. */
+export const OperationSchema = z.enum(['add', 'update', 'delete', 'bulk_add', 'bulk_delete'])
+export type Operation = z.infer<typeof OperationSchema>
+
+export type MergeStrategy = 'skip_duplicates' | 'keep_newest' | 'keep_both'
+
+export const MergeReportSchema = z.object({
+  added: z.number(),
+  replaced: z.number(),
+  skipped: z.number(),
+})
+export type MergeReport = z.infer<typeof MergeReportSchema>
+
+export const SyncProviderKindSchema = z.enum(['google_drive', 's3'])
+export type SyncProviderKind = z.infer<typeof SyncProviderKindSchema>
+
+export interface S3Config {
+  endpoint: string
+  region: string | null
+  bucket: string
+  access_key_id: string
+  secret_access_key: string
+}
+
+export const SyncOutcomeSchema = z.enum(['up_to_date', 'uploaded', 'downloaded', 'merged'])
+export type SyncOutcome = z.infer<typeof SyncOutcomeSchema>
+
+export const SyncConflictSchema = z.object({
+  entry_id: z.string(),
+  title: z.string(),
+  resolution: z.enum(['kept_local', 'kept_remote', 'kept_edit']),
+})
+export type SyncConflict = z.infer<typeof SyncConflictSchema>
+
+export const SyncReportSchema = z.object({
+  outcome: SyncOutcomeSchema,
+  conflicts: z.array(SyncConflictSchema),
+})
+export type SyncReport = z.infer<typeof SyncReportSchema>
+
+export const SyncStatusSchema = z.object({
+  provider: SyncProviderKindSchema.nullable(),
+  signed_in: z.boolean(),
+  last_synced: z.number().nullable(),
+  last_outcome: SyncOutcomeSchema.nullable(),
+  conflicts: z.array(SyncConflictSchema),
+  last_error: z.string().nullable(),
+})
+export type SyncStatus = z.infer<typeof SyncStatusSchema>
+
+export const PairedPeerSchema = z.object({
+  fingerprint: z.string(),
+  name: z.string(),
+  public_key: z.string(),
+  paired_at: z.number(),
+})
+export type PairedPeer = z.infer<typeof PairedPeerSchema>
+
+export const LanPeerSchema = z.object({
+  name: z.string(),
+  fingerprint: z.string(),
+  address: z.string(),
+  paired: z.boolean(),
+})
+export type LanPeer = z.infer<typeof LanPeerSchema>
+
+export const PairingRequestSchema = z.object({
+  fingerprint: z.string(),
+  name: z.string(),
+  code: z.string(),
+})
+export type PairingRequest = z.infer<typeof PairingRequestSchema>
+
+export const AliasServiceSchema = z.enum(['simple_login', 'firefox_relay', 'addy'])
+export type AliasService = z.infer<typeof AliasServiceSchema>
+
+export interface AliasAccount {
+  service: AliasService
+  api_key: string
+  base_url: string | null
+}
 
-export const AddEntryResponseSchema = z.object({
-  status: z.literal('success'),
+export const AliasSchema = z.object({
   id: z.string(),
+  email: z.string(),
+  enabled: z.boolean(),
+  note: z.string().nullable(),
 })
+export type Alias = z.infer<typeof AliasSchema>
+
+export type ConfirmOperation = 'delete_vault' | 'remove_duress_vault'
+
+export const ConfirmationTokenSchema = z.object({
+  token: z.string(),
+  operation: z.enum(['delete_vault', 'remove_duress_vault']),
+  expires_in_secs: z.number(),
+})
+
+/** Payload of `vault://changed-externally`: what was done about a vault file replaced outside Latch. */
+export type ExternalChange =
+  | {
+      kind: 'reloaded'
+      count: number
+      conflicts: { entry_id: string; title: string; resolution: 'kept_local' | 'kept_remote' | 'kept_edit' }[]
+    }
+  | { kind: 'unreadable' }
+
+/** One edit from `get_entry_history`. Password values are short fingerprints, not the password. */
+export const EntryChangeSchema = z.object({
+  field: z.enum(['title', 'username', 'password', 'url', 'folder']),
+  old: z.string().nullable(),
+  new: z.string().nullable(),
+  at: z.number(),
+  session: z.number().nullable(),
+})
+export type EntryChange = z.infer<typeof EntryChangeSchema>
+
+export const SessionInfoSchema = z.object({
+  unlocked: z.boolean(),
+  started_at: z.number().nullable(),
+  expires_at: z.number().nullable(),
+  remaining_seconds: z.number(),
+  extendable: z.boolean(),
+})
+export type SessionInfo = z.infer<typeof SessionInfoSchema>
+
+export type AuthPreferences = {
+  auth_method: string
+  session_valid: boolean
+  session_remaining_seconds: number
+  duress_configured: boolean
+}
+
+export type AssociationRequest = {
+  request_id: string
+  fingerprint: string
+}
+
+export type BrowserAssociation = {
+  id: string
+  id_key: string
+  created_at: number
+}
+
+export type ReferenceSource =
+  | 'env_var'
+  | 'aws_secrets_manager'
+  | 'gcp_secret_manager'
+  | 'azure_key_vault'
+  | 'pass'
+  | 'other'
+
+/** Fields `request_secret` serves from a bank account Credential. */
+export type BankField = 'account_number' | 'routing_code' | 'swift' | 'pin'
+
+/** Fields `request_secret` serves from an API key Credential. */
+export type ApiKeyField = 'key_id' | 'secret' | 'scopes' | 'expires_at'
+
+export type ServerProtocol = 'ssh' | 'sftp' | 'rdp' | 'vnc' | 'telnet' | 'ftp' | 'other'
+
+/** Fields `request_secret` serves from a server Credential. */
+export type ServerField = 'hostname' | 'port' | 'protocol' | 'address' | 'private_key'
+
+export type SshConfirmRequest = {
+  request_id: string
+  title: string
+  fingerprint: string
+}
+
+export const PassphraseOptionsSchema = z.object({
+  wordlist: z.enum(['eff_long', 'eff_short']),
+  words: z.number().min(3).max(20),
+  separator: z.string().max(5),
+  capitalization: z.enum(['lowercase', 'title_case', 'uppercase', 'random_word']),
+  digits: z.number().min(0).max(10),
+})
+export type PassphraseOptions = z.infer<typeof PassphraseOptionsSchema>
+
+export const PronounceableOptionsSchema = z.object({
+  syllables: z.number().min(4).max(20),
+  closed_syllables: z.boolean(),
+  capitalize: z.boolean(),
+  digits: z.number().min(0).max(10),
+})
+export type PronounceableOptions = z.infer<typeof PronounceableOptionsSchema>
+
+export const PinOptionsSchema = z.object({
+  digits: z.number().min(4).max(12),
+})
+export type PinOptions = z.infer<typeof PinOptionsSchema>
+
+export const PasswordModeSchema = z.discriminatedUnion('kind', [
+  z.object({ kind: z.literal('characters') }),
+  PassphraseOptionsSchema.extend({ kind: z.literal('passphrase') }),
+  PronounceableOptionsSchema.extend({ kind: z.literal('pronounceable') }),
+  PinOptionsSchema.extend({ kind: z.literal('pin') }),
+])
+export type PasswordMode = z.infer<typeof PasswordModeSchema>
 
 export const PasswordOptionsSchema = z.object({
   length: z.number().min(8).max(128),
@@ -68,9 +309,32 @@ export const PasswordOptionsSchema = z.object({
   numbers: z.boolean(),
   symbols: z.boolean(),
   exclude_ambiguous: z.boolean(),
+  include_chars: z.string().max(256).optional(),
+  exclude_chars: z.string().optional(),
+  min_per_class: z.number().int().min(0).optional(),
+  mode: PasswordModeSchema.optional(),
 })
 export type PasswordOptions = z.infer<typeof PasswordOptionsSchema>
 
+export interface PasswordContext {
+  title: string
+  username: string
+  url: string | null
+}
+
+/** What `add_entry` and `update_entry` return: the id, and how many other Credentials already use the password. */
+export const SavedEntrySchema = z.object({
+  id: z.string(),
+  reused_by: z.number(),
+})
+export type SavedEntry = z.infer<typeof SavedEntrySchema>
+
+export const GeneratedPasswordSchema = z.object({
+  password: z.string(),
+  generated_at: z.number(),
+})
+export type GeneratedPassword = z.infer<typeof GeneratedPasswordSchema>
+
 export const StrengthReportSchema = z.object({
   score: z.number(),
   entropy: z.number(),
@@ -85,12 +349,58 @@ export const VaultHealthReportSchema = z.object({
   weak_passwords: z.array(z.any()),
   reused_passwords: z.array(z.any()),
   breached_credentials: z.array(z.any()),
+  leaked_canaries: z.array(z.object({
+    entry_id: z.string(),
+    service: z.string(),
+    email: z.string(),
+    breach_count: z.number(),
+  })),
+  url_secrets: z.array(z.object({
+    entry_id: z.string(),
+    title: z.string(),
+    redacted_url: z.string(),
+    kinds: z.array(z.discriminatedUnion('type', [
+      z.object({ type: z.literal('userinfo') }),
+      z.object({ type: z.literal('queryToken'), params: z.array(z.string()) }),
+    ])),
+  })),
+  expired_credentials: z.array(z.object({
+    entry_id: z.string(),
+    title: z.string(),
+    username: z.string(),
+    due_at: z.number(),
+    days_overdue: z.number(),
+  })),
+  breached_accounts: z.array(z.object({
+    email: z.string(),
+    entry_ids: z.array(z.string()),
+    breaches: z.array(z.object({
+      name: z.string(),
+      title: z.string(),
+      domain: z.string(),
+      breach_date: z.string(),
+      new: z.boolean(),
+    })),
+  })),
   total_entries: z.number(),
   strong_passwords: z.number(),
   average_entropy: z.number(),
 })
 export type VaultHealthReport = z.infer<typeof VaultHealthReportSchema>
 
+/** The local breach database; also the payload of `breach-database://progress`. */
+export const BreachDatabaseProgressSchema = z.object({
+  status: z.object({
+    ready: z.boolean(),
+    bytes: z.number(),
+    ranges_done: z.number(),
+    ranges_total: z.number(),
+  }),
+  downloading: z.boolean(),
+  error: z.string().nullable(),
+})
+export type BreachDatabaseProgress = z.infer<typeof BreachDatabaseProgressSchema>
+
 export type PaletteMode =
   | 'search'
   | 'actions'
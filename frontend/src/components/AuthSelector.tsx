@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react'
-import { checkStatus } from '@choochmeque/tauri-plugin-biometry-api'
+import { checkStatus } from '../utils/keychain'
 import { Google } from './ui/svgs/google'
 import FingerprintIcon from './icons/FingerprintIcon'
 
@@ -37,7 +37,7 @@ export default function MigrateVault({ onSuccess, onError }: MigrateVaultProps)
         throw new Error('No ID token received from Google')
       }
 
-      await api.migrateToOAuth(password, response.idToken)
+      await api.migrateToOAuth(password, 'google', response.idToken)
       onSuccess()
     } catch (err) {
       onError?.(String(err))
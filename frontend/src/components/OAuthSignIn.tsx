@@ -1,8 +1,11 @@
 import { Loader2 } from 'lucide-react'
-import { useState } from 'react'
+import { useEffect, useRef, useState } from 'react'
 import { signIn } from '@choochmeque/tauri-plugin-google-auth-api'
+import { open } from '@tauri-apps/plugin-shell'
 import { api } from '../api/client'
+import { type DeviceCode, type OAuthProvider } from '../api/types'
 import { Google } from './ui/svgs/google'
+import { GitHub } from './ui/svgs/github'
 
 interface OAuthSignInProps {
   mode: 'setup' | 'login'
@@ -10,11 +13,30 @@ interface OAuthSignInProps {
   onError?: (error: string) => void
 }
 
+const buttonClass =
+  'flex items-center justify-center gap-2.5 px-6 py-3 bg-theme-bg text-theme-text border-2 border-theme-accent font-extrabold font-theme uppercase tracking-wider cursor-pointer transition-all duration-100 w-full max-w-[300px] shadow-theme-sm hover:bg-theme-surface hover:translate-x-[2px] hover:translate-y-[2px] hover:shadow-theme-sm active:translate-x-[4px] active:translate-y-[4px] active:shadow-none disabled:opacity-45 disabled:cursor-not-allowed disabled:shadow-none'
+
+const sleep = (secs: number) => new Promise((resolve) => setTimeout(resolve, secs * 1000))
+
 export default function OAuthSignIn({ mode, onSuccess, onError }: OAuthSignInProps) {
-  const [isProcessing, setIsProcessing] = useState(false)
+  const [processing, setProcessing] = useState<OAuthProvider | null>(null)
+  // Which provider the Vault signs in with; null offers both (setup, or unknown).
+  const [vaultProvider, setVaultProvider] = useState<OAuthProvider | null>(null)
+  const [deviceCode, setDeviceCode] = useState<DeviceCode | null>(null)
+  const cancelled = useRef(false)
+
+  useEffect(() => {
+    cancelled.current = false
+    if (mode === 'login') {
+      api.getIdentityProvider().then(setVaultProvider).catch(() => setVaultProvider(null))
+    }
+    return () => {
+      cancelled.current = true
+    }
+  }, [mode])
 
-  const handleSignIn = async () => {
-    setIsProcessing(true)
+  const handleGoogleSignIn = async () => {
+    setProcessing('google')
     try {
       const response = await signIn({
         clientId: import.meta.env.VITE_GOOGLE_CLIENT_ID,
@@ -28,7 +50,7 @@ export default function OAuthSignIn({ mode, onSuccess, onError }: OAuthSignInPro
       }
 
       if (mode === 'setup') {
-        await api.provisionOAuth(response.idToken)
+        await api.provisionOAuth('google', response.idToken)
       } else {
         await api.accessOAuth(response.idToken)
       }
@@ -36,43 +58,100 @@ export default function OAuthSignIn({ mode, onSuccess, onError }: OAuthSignInPro
     } catch (err) {
       onError?.(String(err))
     } finally {
-      setIsProcessing(false)
+      setProcessing(null)
+    }
+  }
+
+  const handleGithubSignIn = async () => {
+    setProcessing('github')
+    try {
+      const code = await api.startGithubSignIn()
+      setDeviceCode(code)
+      await open(code.verification_uri)
+
+      const deadline = Date.now() + code.expires_in * 1000
+      let accessToken: string | null = null
+      while (!accessToken) {
+        if (cancelled.current) return
+        if (Date.now() > deadline) throw new Error('The GitHub code expired; start again')
+        await sleep(code.interval)
+        accessToken = await api.pollGithubSignIn(code.device_code)
+      }
+
+      if (mode === 'setup') {
+        await api.provisionOAuth('github', accessToken)
+      } else {
+        await api.accessOAuth(accessToken)
+      }
+      onSuccess()
+    } catch (err) {
+      onError?.(String(err))
+    } finally {
+      setDeviceCode(null)
+      setProcessing(null)
     }
   }
 
+  const signInWith = (provider: OAuthProvider) => (vaultProvider ?? provider) === provider
+
   return (
     <div className="px-5 py-6 flex flex-col items-center gap-4 bg-theme-bg">
       <div className="text-center">
         {mode === 'setup' ? (
           <>
             <h2 className="text-[28px] leading-[1.1] font-extrabold font-theme text-theme-text uppercase tracking-wider mb-1.5">Welcome to Latch</h2>
-            <p className="text-sm text-theme-text-secondary font-theme">Sign in with Google to secure your password vault</p>
+            <p className="text-sm text-theme-text-secondary font-theme">Sign in with Google or GitHub to secure your password vault</p>
           </>
         ) : (
           <>
             <h2 className="text-[28px] leading-[1.1] font-extrabold font-theme text-theme-text uppercase tracking-wider mb-1.5">Unlock Latch</h2>
-            <p className="text-sm text-theme-text-secondary font-theme">Sign in with Google to access your passwords</p>
+            <p className="text-sm text-theme-text-secondary font-theme">Sign in to access your passwords</p>
           </>
         )}
       </div>
 
-      <button
-        onClick={handleSignIn}
-        disabled={isProcessing}
-        className="flex items-center justify-center gap-2.5 px-6 py-3 bg-theme-bg text-theme-text border-2 border-theme-accent font-extrabold font-theme uppercase tracking-wider cursor-pointer transition-all duration-100 w-full max-w-[300px] shadow-theme-sm hover:bg-theme-surface hover:translate-x-[2px] hover:translate-y-[2px] hover:shadow-theme-sm active:translate-x-[4px] active:translate-y-[4px] active:shadow-none disabled:opacity-45 disabled:cursor-not-allowed disabled:shadow-none"
-      >
-        {isProcessing ? (
-          <>
-            <Loader2 size={20} className="animate-spin" />
-            <span>Authenticating...</span>
-          </>
-        ) : (
-          <>
-            <Google className="w-5 h-5 flex-shrink-0" />
-            <span>Sign in with Google</span>
-          </>
-        )}
-      </button>
+      {signInWith('google') && (
+        <button onClick={handleGoogleSignIn} disabled={processing !== null} className={buttonClass}>
+          {processing === 'google' ? (
+            <>
+              <Loader2 size={20} className="animate-spin" />
+              <span>Authenticating...</span>
+            </>
+          ) : (
+            <>
+              <Google className="w-5 h-5 flex-shrink-0" />
+              <span>Sign in with Google</span>
+            </>
+          )}
+        </button>
+      )}
+
+      {signInWith('github') && (
+        <button onClick={handleGithubSignIn} disabled={processing !== null} className={buttonClass}>
+          {processing === 'github' ? (
+            <>
+              <Loader2 size={20} className="animate-spin" />
+              <span>Waiting for GitHub...</span>
+            </>
+          ) : (
+            <>
+              <GitHub className="w-5 h-5 flex-shrink-0" />
+              <span>Sign in with GitHub</span>
+            </>
+          )}
+        </button>
+      )}
+
+      {vaultProvider === 'apple' && (
+        <p className="text-sm text-theme-text-secondary font-theme text-center">This vault signs in with Apple. Unlock it from the macOS or iOS app.</p>
+      )}
+
+      {deviceCode && (
+        <div className="text-center">
+          <p className="text-xs text-theme-text-secondary font-theme">Enter this code on GitHub</p>
+          <p className="text-2xl font-extrabold font-theme text-theme-text tracking-widest select-all">{deviceCode.user_code}</p>
+        </div>
+      )}
 
       <div className="text-center border-t border-theme-border w-full pt-3">
         <p className="text-xs text-theme-text-secondary font-theme">Your vault will be encrypted and stored locally</p>
@@ -80,6 +159,3 @@ export default function OAuthSignIn({ mode, onSuccess, onError }: OAuthSignInPro
     </div>
   )
 }
-
-
-
@@ -1,5 +1,6 @@
 import { LucideIcon } from 'lucide-react'
-import { Key, User, ArrowLeft, LogOut, Edit, Dice1, Shield, Trash2 } from 'lucide-react'
+import { Key, User, ArrowLeft, LogOut, Edit, Dice1, Shield, Trash2, ExternalLink, Pin, PinOff } from 'lucide-react'
+import { type EntryAction } from '../api/types'
 
 export interface Action {
   id: string
@@ -34,28 +35,48 @@ export function createUtilityActions(
 export function createEntryActions(
   entryId: string,
   entryTitle: string,
+  favorite: boolean,
+  available: EntryAction[],
   onCopyPassword: (id: string) => Promise<void>,
   onCopyUsername: (id: string) => Promise<void>,
+  onOpenUrl: (url: string) => Promise<void>,
   onEdit: (id: string) => void | Promise<void>,
+  onToggleFavorite: () => void | Promise<void>,
   onLock: () => void,
   onBack: () => void,
   onDelete: () => void | Promise<void>
 ): Action[] {
+  const fieldActions = available.map((action): Action => {
+    switch (action.kind) {
+      case 'copy_password':
+        return {
+          id: 'copy-password',
+          title: 'Copy Password',
+          subtitle: entryTitle,
+          icon: Key,
+          handler: () => onCopyPassword(entryId),
+        }
+      case 'copy_username':
+        return {
+          id: 'copy-username',
+          title: 'Copy Username',
+          subtitle: entryTitle,
+          icon: User,
+          handler: () => onCopyUsername(entryId),
+        }
+      case 'open_url':
+        return {
+          id: 'open-url',
+          title: 'Open URL',
+          subtitle: action.url,
+          icon: ExternalLink,
+          handler: () => onOpenUrl(action.url),
+        }
+    }
+  })
+
   return [
-    {
-      id: 'copy-password',
-      title: 'Copy Password',
-      subtitle: entryTitle,
-      icon: Key,
-      handler: () => onCopyPassword(entryId),
-    },
-    {
-      id: 'copy-username',
-      title: 'Copy Username',
-      subtitle: entryTitle,
-      icon: User,
-      handler: () => onCopyUsername(entryId),
-    },
+    ...fieldActions,
     {
       id: 'edit',
       title: 'Edit Entry',
@@ -63,6 +84,13 @@ export function createEntryActions(
       icon: Edit,
       handler: () => onEdit(entryId),
     },
+    {
+      id: 'favorite',
+      title: favorite ? 'Unpin from Tray' : 'Pin to Tray',
+      subtitle: entryTitle,
+      icon: favorite ? PinOff : Pin,
+      handler: () => onToggleFavorite(),
+    },
     {
       id: 'delete',
       title: 'Delete credential',
@@ -84,6 +112,3 @@ export function createEntryActions(
     },
   ]
 }
-
-
-
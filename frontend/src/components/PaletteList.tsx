@@ -1,5 +1,6 @@
 import { useEffect, useRef, useState } from 'react'
 import { LucideIcon } from 'lucide-react'
+import { type EntryIcon } from '../api/types'
 
 export interface PaletteListItem {
   id: string
@@ -7,6 +8,8 @@ export interface PaletteListItem {
   subtitle?: string
   icon?: LucideIcon
   iconUrl?: string
+  /** Takes the place of `iconUrl` when set. */
+  customIcon?: EntryIcon | null
 }
 
 interface PaletteListProps {
@@ -50,7 +53,16 @@ function PaletteList({ items, selectedIndex, onSelect, emptyMessage = 'No result
               : 'text-theme-text hover:bg-theme-surface'
           }`}
         >
-          {item.iconUrl && !failedIcons.has(item.iconUrl) ? (
+          {item.customIcon?.type === 'emoji' ? (
+            <span className="w-[18px] h-[18px] flex-shrink-0 flex items-center justify-center text-[15px] leading-none">
+              {item.customIcon.value}
+            </span>
+          ) : item.customIcon?.type === 'color' ? (
+            <span
+              className="w-[18px] h-[18px] flex-shrink-0 rounded-[3px]"
+              style={{ backgroundColor: item.customIcon.value }}
+            />
+          ) : item.iconUrl && !failedIcons.has(item.iconUrl) ? (
             <img
               src={item.iconUrl}
               alt=""
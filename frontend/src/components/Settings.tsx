@@ -4,7 +4,7 @@ import { ask, message } from '@tauri-apps/plugin-dialog'
 import { relaunch } from '@tauri-apps/plugin-process'
 import { getVersion } from '@tauri-apps/api/app'
 import { signIn } from '@choochmeque/tauri-plugin-google-auth-api'
-import { checkStatus } from '@choochmeque/tauri-plugin-biometry-api'
+import { checkStatus } from '../utils/keychain'
 import {
   generateAndStoreKey,
   clearStoredKey
@@ -44,6 +44,7 @@ function Settings() {
   const [biometricAvailable, setBiometricAvailable] = useState(false)
   const [selectedMethod, setSelectedMethod] = useState<AuthMethod>('oauth-pbkdf2')
   const [liveRemainingSeconds, setLiveRemainingSeconds] = useState<number | null>(null)
+  const [sessionExtendable, setSessionExtendable] = useState(false)
   const [loading, setLoading] = useState(true)
   const [switching, setSwitching] = useState(false)
   const [error, setError] = useState('')
@@ -79,17 +80,18 @@ function Settings() {
       setLoading(true)
       setError('')
 
-      const prefs = await api.getAuthPreferences()
+      const [prefs, session] = await Promise.all([api.getAuthPreferences(), api.getSessionInfo()])
 
       const authMethod = prefs.auth_method ?? 'none'
-      const sessionValid = prefs.session_valid ?? false
-      const remaining = prefs.session_remaining_seconds ?? 0
+      const sessionValid = session.unlocked
+      const remaining = session.remaining_seconds
       setPreferences({
         auth_method: authMethod,
         session_valid: sessionValid,
         session_remaining_seconds: remaining
       })
       setLiveRemainingSeconds(sessionValid && remaining > 0 ? remaining : null)
+      setSessionExtendable(session.extendable)
       setSelectedMethod(
         authMethod === 'biometric-keychain' ? 'biometric-keychain' : authMethod === 'oauth-argon2id' ? 'oauth-argon2id' : 'oauth-pbkdf2'
       )
@@ -118,6 +120,7 @@ function Settings() {
       setCheckingUpdate(true)
       setError('')
 
+      await api.whenReady()
       const update = await check()
 
       if (update?.available) {
@@ -145,6 +148,7 @@ function Settings() {
             }
           })
 
+          await api.checkBinaryIntegrity()
           await message('Update downloaded successfully! The app will now restart to install the update.', { kind: 'info' })
           await relaunch()
         }
@@ -219,7 +223,7 @@ function Settings() {
     try {
       const keyHex = await generateAndStoreKey()
       try {
-        await api.reencryptVault(keyHex, 'biometric-keychain', '')
+        await api.reencryptVault(keyHex, 'biometric-keychain')
         await loadPreferences()
       } catch (reencryptErr) {
         await clearStoredKey()
@@ -250,7 +254,7 @@ function Settings() {
         throw new Error('No ID token received from Google')
       }
 
-      await api.reencryptVaultToOAuth(response.idToken)
+      await api.reencryptVaultToOAuth('google', response.idToken)
       await clearStoredKey()
       await loadPreferences()
     } catch (err) {
@@ -270,6 +274,17 @@ function Settings() {
     setError('')
   }
 
+  const extendSession = async () => {
+    try {
+      const session = await api.extendSession()
+      setLiveRemainingSeconds(session.remaining_seconds)
+      setSessionExtendable(session.extendable)
+    } catch (err) {
+      console.error('Failed to extend session:', err)
+      setError('Failed to extend session')
+    }
+  }
+
   const getSessionTimeRemaining = () => {
     if (!preferences.session_valid) return null
     const remaining =
@@ -304,6 +319,14 @@ function Settings() {
                   {getSessionTimeRemaining()}
                 </span>
               )}
+              {preferences.session_valid && sessionExtendable && (
+                <button
+                  onClick={extendSession}
+                  className="font-theme text-[11px] text-theme-text-secondary hover:text-theme-accent underline"
+                >
+                  Extend
+                </button>
+              )}
             </div>
           </header>
 
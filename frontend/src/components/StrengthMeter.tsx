@@ -1,10 +1,13 @@
 import { useState, useEffect } from 'react'
 import { api } from '../api/client'
 import { Shield, AlertTriangle, AlertOctagon } from 'lucide-react'
+import type { PasswordContext } from '../api/types'
 
 interface StrengthMeterProps {
   password: string
   showEntropy?: boolean
+  // The entry the password is for (keep it memoized); its title, username and URL count as easy guesses
+  context?: PasswordContext
 }
 
 type StrengthLevel = 'very-weak' | 'weak' | 'fair' | 'strong' | 'very-strong'
@@ -57,7 +60,7 @@ function getStrengthInfo(score: number): StrengthInfo {
   }
 }
 
-export default function StrengthMeter({ password, showEntropy = false }: StrengthMeterProps) {
+export default function StrengthMeter({ password, showEntropy = false, context }: StrengthMeterProps) {
   const [analysis, setAnalysis] = useState<{
     score: number
     entropy: number
@@ -74,7 +77,7 @@ export default function StrengthMeter({ password, showEntropy = false }: Strengt
     setIsLoading(true)
     const analyzePassword = async () => {
       try {
-        const report = await api.analyzePassword(password)
+        const report = await api.analyzePassword(password, context)
         setAnalysis({
           score: report.score,
           entropy: report.entropy
@@ -89,7 +92,7 @@ export default function StrengthMeter({ password, showEntropy = false }: Strengt
 
     const timer = setTimeout(analyzePassword, 300)
     return () => clearTimeout(timer)
-  }, [password])
+  }, [password, context])
 
   if (!password || password === 'Generating...') {
     return null
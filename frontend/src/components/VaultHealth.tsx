@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react'
-import { AlertTriangle, AlertOctagon, RefreshCw, ArrowRight, CheckCircle } from 'lucide-react'
+import { AlertTriangle, AlertOctagon, RefreshCw, ArrowRight, CheckCircle, Link2 } from 'lucide-react'
 import { api } from '../api/client'
 import type { VaultHealthReport } from '../api/types'
 
@@ -12,6 +12,7 @@ interface VaultHealthProps {
 export default function VaultHealth({ onWeakPasswords, onReusedPasswords, onBreachedCredentials }: VaultHealthProps) {
   const [healthData, setHealthData] = useState<VaultHealthReport | null>(null)
   const [isLoading, setIsLoading] = useState(true)
+  const [fixErrors, setFixErrors] = useState<Record<string, string>>({})
 
   useEffect(() => {
     loadVaultHealth()
@@ -29,6 +30,15 @@ export default function VaultHealth({ onWeakPasswords, onReusedPasswords, onBrea
     }
   }
 
+  const fixUrlSecret = async (entryId: string) => {
+    try {
+      await api.fixUrlSecret(entryId)
+      await loadVaultHealth()
+    } catch (error) {
+      setFixErrors((errors) => ({ ...errors, [entryId]: String(error) }))
+    }
+  }
+
   const getScoreColor = (score: number) => {
     if (score >= 90) return 'var(--color-theme-accent)'
     if (score >= 80) return '#90ee90'
@@ -77,6 +87,8 @@ export default function VaultHealth({ onWeakPasswords, onReusedPasswords, onBrea
   const weakCount = healthData.weak_passwords?.length || 0
   const reusedCount = healthData.reused_passwords?.length || 0
   const breachedCount = healthData.breached_credentials?.length || 0
+  const leakedCanaries = healthData.leaked_canaries ?? []
+  const urlSecrets = healthData.url_secrets ?? []
   const totalIssues = weakCount + reusedCount + breachedCount
   const scoreColor = getScoreColor(healthData.overall_score)
 
@@ -167,7 +179,34 @@ export default function VaultHealth({ onWeakPasswords, onReusedPasswords, onBrea
             </button>
           )}
 
-          {totalIssues === 0 && (
+          {leakedCanaries.map((canary) => (
+            <div key={canary.entry_id} className="flex items-center justify-between gap-4 px-4 py-3 bg-theme-danger border-2 border-theme-accent shadow-theme">
+              <div className="flex items-center gap-3 text-theme-text font-extrabold min-w-0 flex-1">
+                <AlertOctagon size={18} />
+                <span className="truncate">{canary.service} leaked its login data</span>
+              </div>
+              <span className="text-theme-text-secondary text-sm truncate">{canary.email}</span>
+            </div>
+          ))}
+
+          {urlSecrets.map((secret) => (
+            <div key={secret.entry_id} className="flex items-center justify-between gap-4 px-4 py-3 bg-theme-surface-hover border-2 border-theme-accent shadow-theme">
+              <div className="flex flex-col gap-1 min-w-0 flex-1">
+                <div className="flex items-center gap-3 text-theme-accent font-extrabold">
+                  <Link2 size={18} />
+                  <span className="truncate">{secret.title}: secret in URL</span>
+                </div>
+                <span className="text-theme-text-secondary text-xs truncate">
+                  {fixErrors[secret.entry_id] ?? secret.redacted_url}
+                </span>
+              </div>
+              <button onClick={() => fixUrlSecret(secret.entry_id)} className="px-3 py-1.5 bg-theme-bg text-theme-text border-2 border-theme-accent font-extrabold font-theme uppercase tracking-wider text-xs cursor-pointer transition-transform duration-100 hover:bg-theme-surface hover:translate-x-[2px] hover:translate-y-[2px]">
+                Fix
+              </button>
+            </div>
+          ))}
+
+          {totalIssues === 0 && leakedCanaries.length === 0 && urlSecrets.length === 0 && (
             <div className="flex items-center justify-between gap-4 px-4 py-3 bg-theme-success border-2 border-theme-accent shadow-theme">
               <div className="flex items-center gap-3 text-theme-success font-extrabold min-w-0 flex-1">
                 <CheckCircle size={18} />
@@ -1,7 +1,6 @@
 import { useState, useEffect, useCallback } from 'react'
 import { Globe, User, Key } from 'lucide-react'
 import { api } from '../../api/client'
-import { fetchFavicon } from '../../utils/favicon'
 import PaletteInput from '../PaletteInput'
 import { type PaletteMode, type CredentialPreview } from '../../api/types'
 
@@ -23,6 +22,7 @@ function AddCredential({ editEntry, prefillTitle, generatedPassword, onModeChang
   })
   const [error, setError] = useState('')
   const [loadedEdit, setLoadedEdit] = useState(false)
+  const [originalUrl, setOriginalUrl] = useState('')
 
   useEffect(() => {
     if (isEditing && editEntry && !loadedEdit) {
@@ -45,6 +45,7 @@ function AddCredential({ editEntry, prefillTitle, generatedPassword, onModeChang
         password: fullEntry.password,
         url: fullEntry.url || ''
       })
+      setOriginalUrl(fullEntry.url || '')
       setLoadedEdit(true)
     } catch (error) {
       console.error('Failed to load entry for editing:', error)
@@ -60,20 +61,9 @@ function AddCredential({ editEntry, prefillTitle, generatedPassword, onModeChang
     }
 
     try {
-      let iconUrl: string | undefined
       const url = formData.url.trim() || undefined
 
-      if (url) {
-        try {
-          const favicon = await fetchFavicon(url)
-          if (favicon) {
-            iconUrl = favicon
-          }
-        } catch (favError) {
-          console.error('Error fetching favicon:', favError)
-        }
-      }
-
+      // The backend fetches and caches the site icon itself
       if (isEditing && editEntry) {
         await api.updateEntry({
           id: editEntry.id,
@@ -81,15 +71,18 @@ function AddCredential({ editEntry, prefillTitle, generatedPassword, onModeChang
           username: formData.username,
           password: formData.password,
           url,
-          iconUrl,
         })
+        if (url && url !== originalUrl) {
+          await api.refreshIcon(editEntry.id).catch((iconError) => {
+            console.error('Error fetching site icon:', iconError)
+          })
+        }
       } else {
         await api.addEntry({
           title: formData.title,
           username: formData.username,
           password: formData.password,
           url,
-          iconUrl,
         })
       }
 
@@ -100,7 +93,7 @@ function AddCredential({ editEntry, prefillTitle, generatedPassword, onModeChang
       console.error(`Error ${isEditing ? 'updating' : 'adding'} entry:`, err)
       setError(err instanceof Error ? err.message : String(err))
     }
-  }, [formData, editEntry, isEditing, onModeChange, onCredentialsChanged])
+  }, [formData, editEntry, isEditing, originalUrl, onModeChange, onCredentialsChanged])
 
   useEffect(() => {
     function handleKey(e: KeyboardEvent) {
@@ -1,9 +1,10 @@
-import { useState } from 'react'
+import { useEffect, useState } from 'react'
+import { open } from '@tauri-apps/plugin-shell'
 import { useClipboardGuard } from '../../hooks/useClipboardGuard'
 import { useKeyboardNav } from '../../hooks/useKeyboardNav'
 import PaletteList from '../PaletteList'
 import { createEntryActions } from '../PaletteActions'
-import { type PaletteMode, type CredentialPreview } from '../../api/types'
+import { type PaletteMode, type CredentialPreview, type EntryAction } from '../../api/types'
 import { api } from '../../api/client'
 
 interface EntryActionsProps {
@@ -15,12 +16,18 @@ interface EntryActionsProps {
 function EntryActions({ entry, onModeChange, onLock }: EntryActionsProps) {
   const [selectedIndex, setSelectedIndex] = useState(0)
   const [error, setError] = useState('')
+  const [available, setAvailable] = useState<EntryAction[]>([])
   const { copy } = useClipboardGuard()
 
+  useEffect(() => {
+    api.getEntryActions(entry.id)
+      .then(setAvailable)
+      .catch((err) => setError(err instanceof Error ? err.message : String(err)))
+  }, [entry.id])
+
   const handleCopyPassword = async () => {
     try {
-      const value = await api.copyField(entry.id, 'password')
-      await copy(value)
+      await api.copySecretToClipboard(entry.id, 'password')
       onModeChange('search')
     } catch (err) {
       const errMsg = err instanceof Error ? err.message : String(err)
@@ -38,6 +45,15 @@ function EntryActions({ entry, onModeChange, onLock }: EntryActionsProps) {
     }
   }
 
+  const handleOpenUrl = async (url: string) => {
+    try {
+      await open(url)
+      onModeChange('search')
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
   const handleEdit = async () => {
     try {
       const fullEntry = await api.getFullEntry(entry.id)
@@ -52,6 +68,15 @@ function EntryActions({ entry, onModeChange, onLock }: EntryActionsProps) {
     }
   }
 
+  const handleToggleFavorite = async () => {
+    try {
+      await api.setEntryFavorite(entry.id, !entry.favorite)
+      onModeChange('search')
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
   const onBack = () => {
     onModeChange('search')
   }
@@ -63,9 +88,13 @@ function EntryActions({ entry, onModeChange, onLock }: EntryActionsProps) {
   const actions = createEntryActions(
     entry.id,
     entry.title,
+    entry.favorite ?? false,
+    available,
     handleCopyPassword,
     handleCopyUsername,
+    handleOpenUrl,
     handleEdit,
+    handleToggleFavorite,
     onLock,
     onBack,
     handleDelete
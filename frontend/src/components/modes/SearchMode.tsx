@@ -5,8 +5,18 @@ import { useDebounce } from '../../hooks/useDebounce'
 import { useKeyboardNav } from '../../hooks/useKeyboardNav'
 import PaletteInput from '../PaletteInput'
 import PaletteList from '../PaletteList'
+import { iconSrc } from '../../utils/icons'
 import { type PaletteMode, type CredentialPreview } from '../../api/types'
 
+/** The host of a Credential's URL, shown when a search matched on it. */
+function siteOf(url: string): string {
+  try {
+    return new URL(url.includes('://') ? url : `https://${url}`).hostname
+  } catch {
+    return url
+  }
+}
+
 interface SearchModeProps {
   onModeChange: (mode: PaletteMode, entry?: CredentialPreview, prefillTitle?: string) => void
   onLock: () => void
@@ -105,9 +115,13 @@ function SearchMode({ onModeChange, onLock, searchTrigger }: SearchModeProps) {
   const currentItems = searchResults.map((entry) => ({
     id: entry.id,
     title: entry.title,
-    subtitle: entry.username,
+    subtitle:
+      entry.matched === 'url' && entry.url
+        ? `${entry.username} · ${siteOf(entry.url)}`
+        : entry.username,
     icon: Lock,
-    iconUrl: entry.icon_url ?? undefined,
+    iconUrl: iconSrc(entry.icon_url),
+    customIcon: entry.icon,
   }))
 
   const showList = searchResults.length > 0
@@ -38,29 +38,24 @@ describe('api client response parsing', () => {
     invokeMock.mockReset()
   })
 
-  test('addEntry returns the new id without double-parsing the response', async () => {
-    invokeMock.mockResolvedValue(JSON.stringify({ status: 'success', id: 'entry-1' }))
+  test('addEntry returns the new id and password reuse', async () => {
+    invokeMock.mockResolvedValue({ id: 'entry-1', reused_by: 3 })
 
     await expect(
       api.addEntry({ title: 'Example', username: 'user', password: 'secret' })
-    ).resolves.toBe('entry-1')
+    ).resolves.toEqual({ id: 'entry-1', reused_by: 3 })
   })
 
-  test('searchEntries unwraps the success envelope', async () => {
-    invokeMock.mockResolvedValue(
-      JSON.stringify({
-        status: 'success',
-        entries: [
-          {
-            id: 'entry-1',
-            title: 'Example',
-            username: 'user',
-            url: null,
-            icon_url: null,
-          },
-        ],
-      })
-    )
+  test('searchEntries validates the returned previews', async () => {
+    invokeMock.mockResolvedValue([
+      {
+        id: 'entry-1',
+        title: 'Example',
+        username: 'user',
+        url: null,
+        icon_url: null,
+      },
+    ])
 
     await expect(api.searchEntries('exa')).resolves.toEqual([
       {
@@ -73,20 +68,15 @@ describe('api client response parsing', () => {
     ])
   })
 
-  test('getFullEntry unwraps the success envelope', async () => {
-    invokeMock.mockResolvedValue(
-      JSON.stringify({
-        status: 'success',
-        entry: {
-          id: 'entry-1',
-          title: 'Example',
-          username: 'user',
-          password: 'secret',
-          url: null,
-          icon_url: null,
-        },
-      })
-    )
+  test('getFullEntry validates the returned entry', async () => {
+    invokeMock.mockResolvedValue({
+      id: 'entry-1',
+      title: 'Example',
+      username: 'user',
+      password: 'secret',
+      url: null,
+      icon_url: null,
+    })
 
     await expect(api.getFullEntry('entry-1')).resolves.toMatchObject({
       id: 'entry-1',
@@ -96,10 +86,8 @@ describe('api client response parsing', () => {
     })
   })
 
-  test('vaultStatus validates and unwraps the success envelope', async () => {
-    invokeMock.mockResolvedValue(
-      JSON.stringify({ status: 'success', has_vault: true, is_unlocked: false })
-    )
+  test('vaultStatus validates the returned status', async () => {
+    invokeMock.mockResolvedValue({ has_vault: true, is_unlocked: false })
 
     await expect(api.vaultStatus()).resolves.toEqual({
       has_vault: true,
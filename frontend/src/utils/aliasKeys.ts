@@ -0,0 +1,30 @@
+import {
+  setData,
+  getData,
+  hasData,
+  removeData,
+} from './keychain'
+import type { AliasAccount } from '../api/types'
+
+const KEYCHAIN_DOMAIN = 'com.latch.vault'
+const ALIAS_ACCOUNT_NAME = 'email-alias-account'
+
+export async function storeAliasAccount(account: AliasAccount): Promise<void> {
+  await setData({ domain: KEYCHAIN_DOMAIN, name: ALIAS_ACCOUNT_NAME, data: JSON.stringify(account) })
+}
+
+export async function retrieveAliasAccount(): Promise<AliasAccount | null> {
+  if (!(await hasData({ domain: KEYCHAIN_DOMAIN, name: ALIAS_ACCOUNT_NAME }))) {
+    return null
+  }
+  const result = await getData({
+    domain: KEYCHAIN_DOMAIN,
+    name: ALIAS_ACCOUNT_NAME,
+    reason: 'Create email aliases'
+  })
+  return JSON.parse(result.data) as AliasAccount
+}
+
+export async function clearAliasAccount(): Promise<void> {
+  await removeData({ domain: KEYCHAIN_DOMAIN, name: ALIAS_ACCOUNT_NAME })
+}
@@ -3,7 +3,7 @@ import {
   getData,
   hasData,
   removeData,
-} from '@choochmeque/tauri-plugin-biometry-api'
+} from './keychain'
 
 const KEYCHAIN_DOMAIN = 'com.latch.vault'
 const KEYCHAIN_NAME = 'vault-encryption-key'
@@ -0,0 +1,14 @@
+import { convertFileSrc } from '@tauri-apps/api/core'
+
+const ICON_SCHEME = 'latch-icon'
+const ICON_PREFIX = `${ICON_SCHEME}://localhost/`
+
+// Stored icon URLs point at the backend's encrypted icon cache; the webview
+// reaches that protocol under a different URL on each platform
+export function iconSrc(iconUrl: string | null | undefined): string | undefined {
+  if (!iconUrl) return undefined
+  if (iconUrl.startsWith(ICON_PREFIX)) {
+    return convertFileSrc(iconUrl.slice(ICON_PREFIX.length), ICON_SCHEME)
+  }
+  return iconUrl
+}
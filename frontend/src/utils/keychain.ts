@@ -0,0 +1,30 @@
+import * as biometry from '@choochmeque/tauri-plugin-biometry-api'
+import { api } from '../api/client'
+
+// The biometry plugin, and the keychain behind it, registers after the first
+// paint. Everything here waits for it rather than failing on a cold start.
+
+export async function checkStatus(): ReturnType<typeof biometry.checkStatus> {
+  await api.whenReady()
+  return biometry.checkStatus()
+}
+
+export async function setData(...args: Parameters<typeof biometry.setData>): ReturnType<typeof biometry.setData> {
+  await api.whenReady()
+  return biometry.setData(...args)
+}
+
+export async function getData(...args: Parameters<typeof biometry.getData>): ReturnType<typeof biometry.getData> {
+  await api.whenReady()
+  return biometry.getData(...args)
+}
+
+export async function hasData(...args: Parameters<typeof biometry.hasData>): ReturnType<typeof biometry.hasData> {
+  await api.whenReady()
+  return biometry.hasData(...args)
+}
+
+export async function removeData(...args: Parameters<typeof biometry.removeData>): ReturnType<typeof biometry.removeData> {
+  await api.whenReady()
+  return biometry.removeData(...args)
+}
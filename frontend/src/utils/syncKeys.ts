@@ -0,0 +1,30 @@
+import {
+  setData,
+  getData,
+  hasData,
+  removeData,
+} from './keychain'
+import type { S3Config } from '../api/types'
+
+const KEYCHAIN_DOMAIN = 'com.latch.vault'
+const S3_CONFIG_NAME = 's3-sync-config'
+
+export async function storeS3Config(config: S3Config): Promise<void> {
+  await setData({ domain: KEYCHAIN_DOMAIN, name: S3_CONFIG_NAME, data: JSON.stringify(config) })
+}
+
+export async function retrieveS3Config(): Promise<S3Config | null> {
+  if (!(await hasData({ domain: KEYCHAIN_DOMAIN, name: S3_CONFIG_NAME }))) {
+    return null
+  }
+  const result = await getData({
+    domain: KEYCHAIN_DOMAIN,
+    name: S3_CONFIG_NAME,
+    reason: 'Sync your vault'
+  })
+  return JSON.parse(result.data) as S3Config
+}
+
+export async function clearS3Config(): Promise<void> {
+  await removeData({ domain: KEYCHAIN_DOMAIN, name: S3_CONFIG_NAME })
+}